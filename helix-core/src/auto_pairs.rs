@@ -1,7 +1,10 @@
 //! When typing the opening character of one of the possible pairs defined below,
 //! this module provides the functionality to insert the paired closing character.
 
-use crate::{graphemes, movement::Direction, Range, Rope, Selection, Tendril, Transaction};
+use crate::{
+    graphemes, match_brackets, movement::Direction, Range, Rope, Selection, Syntax, Tendril,
+    Transaction,
+};
 use std::collections::HashMap;
 
 use smallvec::SmallVec;
@@ -56,6 +59,51 @@ pub fn prev_is_not_alpha(doc: &Rope, range: &Range) -> bool {
         let prev_char = prev_char(doc, cursor);
         prev_char.map(|c| !c.is_alphanumeric()).unwrap_or(true)
     }
+
+    /// true unless the syntax tree shows that a closer would be a false pair: the range
+    /// is inside a string or a comment, or the next char is this pair's closer and
+    /// has no opener yet
+    pub fn syntax_allows_close(&self, doc: &Rope, range: &Range, syntax: Option<&Syntax>) -> bool {
+        let syntax = match syntax {
+            Some(syntax) => syntax,
+            None => return true,
+        };
+        let cursor = range.cursor(doc.slice(..));
+        if in_string_or_comment(syntax, doc, cursor) {
+            return false;
+        }
+        if !self.same() && doc.get_char(cursor) == Some(self.close) {
+            return match_brackets::find_matching_bracket(syntax, doc, cursor).is_some();
+        }
+        true
+    }
+}
+
+/// Whether `pos` is strictly inside a string or a comment node. Grammars name these nodes
+/// differently (`string`, `string_literal`, `line_comment`, ...), so this goes by the kind.
+fn in_string_or_comment(syntax: &Syntax, doc: &Rope, pos: usize) -> bool {
+    let byte = doc.char_to_byte(pos);
+    let mut node = syntax
+        .tree()
+        .root_node()
+        .descendant_for_byte_range(byte, byte);
+    while let Some(n) = node {
+        let kind = n.kind();
+        let is_comment = kind.contains("comment");
+        let is_literal = kind.contains("string") || matches!(kind, "char_literal" | "character");
+        // the boundaries belong to the surrounding code: `"foo"|` is not in the string. Line
+        // comments run to the end of the line though, so their end is still in them.
+        let end = if is_comment && !kind.contains("block") {
+            byte <= n.end_byte()
+        } else {
+            byte < n.end_byte()
+        };
+        if n.start_byte() < byte && end && (is_comment || is_literal) {
+            return true;
+        }
+        node = n.parent();
+    }
+    false
 }
 
 impl From<&(char, char)> for Pair {
@@ -120,14 +168,20 @@ fn default() -> Self {
 //   middle of triple quotes, and more exotic pairs like Jinja's {% %}
 
 #[must_use]
-pub fn hook(doc: &Rope, selection: &Selection, ch: char, pairs: &AutoPairs) -> Option<Transaction> {
+pub fn hook(
+    doc: &Rope,
+    selection: &Selection,
+    ch: char,
+    pairs: &AutoPairs,
+    syntax: Option<&Syntax>,
+) -> Option<Transaction> {
     log::trace!("autopairs hook selection: {:#?}", selection);
 
     if let Some(pair) = pairs.get(ch) {
         if pair.same() {
-            return Some(handle_same(doc, selection, pair));
+            return Some(handle_same(doc, selection, pair, syntax));
         } else if pair.open == ch {
-            return Some(handle_open(doc, selection, pair));
+            return Some(handle_open(doc, selection, pair, syntax));
         } else if pair.close == ch {
             // && char_at pos == close
             return Some(handle_close(doc, selection, pair));
@@ -262,7 +316,12 @@ fn get_next_range(doc: &Rope, start_range: &Range, offset: usize, len_inserted:
     Range::new(end_anchor, end_head)
 }
 
-fn handle_open(doc: &Rope, selection: &Selection, pair: &Pair) -> Transaction {
+fn handle_open(
+    doc: &Rope,
+    selection: &Selection,
+    pair: &Pair,
+    syntax: Option<&Syntax>,
+) -> Transaction {
     let mut end_ranges = SmallVec::with_capacity(selection.len());
     let mut offs = 0;
 
@@ -275,7 +334,10 @@ fn handle_open(doc: &Rope, selection: &Selection, pair: &Pair) -> Transaction {
         // inserting exactly one or two chars. When arbitrary length pairs are
         // added, these will need to be changed.
         let change = match next_char {
-            Some(_) if !pair.should_close(doc, start_range) => {
+            Some(_)
+                if !pair.should_close(doc, start_range)
+                    || !pair.syntax_allows_close(doc, start_range, syntax) =>
+            {
                 len_inserted = 1;
                 let mut tendril = Tendril::new();
                 tendril.push(pair.open);
@@ -333,7 +395,12 @@ fn handle_close(doc: &Rope, selection: &Selection, pair: &Pair) -> Transaction {
 }
 
 /// handle cases where open and close is the same, or in triples ("""docstring""")
-fn handle_same(doc: &Rope, selection: &Selection, pair: &Pair) -> Transaction {
+fn handle_same(
+    doc: &Rope,
+    selection: &Selection,
+    pair: &Pair,
+    syntax: Option<&Syntax>,
+) -> Transaction {
     let mut end_ranges = SmallVec::with_capacity(selection.len());
 
     let mut offs = 0;
@@ -352,7 +419,9 @@ fn handle_same(doc: &Rope, selection: &Selection, pair: &Pair) -> Transaction {
 
             // for equal pairs, don't insert both open and close if either
             // side has a non-pair char
-            if pair.should_close(doc, start_range) {
+            if pair.should_close(doc, start_range)
+                && pair.syntax_allows_close(doc, start_range, syntax)
+            {
                 pair_str.push(pair.close);
             }
 
@@ -94,6 +94,72 @@ pub fn toggle_line_comments(doc: &Rope, selection: &Selection, token: Option<&st
     Transaction::change(doc, changes.into_iter())
 }
 
+/// Groups the lines of the selection into blocks of consecutive selected lines.
+fn line_blocks(text: RopeSlice, selection: &Selection) -> Vec<(usize, usize)> {
+    let mut blocks: Vec<(usize, usize)> = Vec::with_capacity(selection.len());
+    for range in selection {
+        let (start, end) = range.line_range(text);
+        match blocks.last_mut() {
+            Some((_, last)) if start <= *last + 1 => *last = (*last).max(end),
+            _ => blocks.push((start, end)),
+        }
+    }
+    blocks
+}
+
+/// Wraps the lines of each selection in a block comment, or removes the tokens if the lines
+/// are commented already. For languages without line comments, like HTML and CSS.
+#[must_use]
+pub fn toggle_block_comments(
+    doc: &Rope,
+    selection: &Selection,
+    start_token: &str,
+    end_token: &str,
+) -> Transaction {
+    let text = doc.slice(..);
+    let mut changes: Vec<Change> = Vec::new();
+
+    for (start_line, end_line) in line_blocks(text, selection) {
+        let first = text.line(start_line);
+        let start = match find_first_non_whitespace_char(first) {
+            Some(pos) => text.line_to_char(start_line) + pos,
+            // blocks starting with a blank line are left alone
+            None => continue,
+        };
+        let last = text.line(end_line);
+        let trailing = last.chars().rev().take_while(|c| c.is_whitespace()).count();
+        let end = text.line_to_char(end_line) + last.len_chars() - trailing;
+        if end <= start {
+            continue;
+        }
+
+        let block = Cow::from(text.slice(start..end));
+        if block.starts_with(start_token)
+            && block.ends_with(end_token)
+            && block.chars().count() >= start_token.chars().count() + end_token.chars().count()
+        {
+            let start_len = start_token.chars().count();
+            let end_len = end_token.chars().count();
+            // remove the spaces between the tokens and the commented text too
+            let start_margin = (text.get_char(start + start_len) == Some(' ')) as usize;
+            let end_margin = (end >= start + start_len + end_len + 1
+                && text.get_char(end - end_len - 1) == Some(' '))
+                as usize;
+            changes.push((start, start + start_len + start_margin, None));
+            changes.push((end - end_len - end_margin, end, None));
+        } else {
+            changes.push((
+                start,
+                start,
+                Some(Tendril::from(format!("{} ", start_token))),
+            ));
+            changes.push((end, end, Some(Tendril::from(format!(" {}", end_token)))));
+        }
+    }
+
+    Transaction::change(doc, changes.into_iter())
+}
+
 #[cfg(test)]
 mod test {
     use super::*;
@@ -138,4 +204,19 @@ fn test_find_line_comment() {
 
         // TODO: account for uncommenting with uneven comment indentation
     }
+
+    #[test]
+    fn test_toggle_block_comments() {
+        let mut doc = Rope::from("  <p>\n  </p>\n");
+        let selection = Selection::single(0, 8);
+
+        let transaction = toggle_block_comments(&doc, &selection, "<!--", "-->");
+        transaction.apply(&mut doc);
+        assert_eq!(doc, "  <!-- <p>\n  </p> -->\n");
+
+        let selection = Selection::single(0, doc.len_chars() - 1);
+        let transaction = toggle_block_comments(&doc, &selection, "<!--", "-->");
+        transaction.apply(&mut doc);
+        assert_eq!(doc, "  <p>\n  </p>\n");
+    }
 }
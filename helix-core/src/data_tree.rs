@@ -0,0 +1,299 @@
+//! The key structure of data files (JSON, YAML and TOML) extracted from their tree-sitter parse.
+
+use crate::{tree_sitter::Node, RopeSlice};
+use std::borrow::Cow;
+use std::fmt::Write;
+
+/// Languages whose syntax tree `data_tree` understands.
+pub const LANGUAGES: &[&str] = &["json", "yaml", "toml"];
+
+const MAX_PREVIEW_WIDTH: usize = 60;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PathSegment {
+    Key(String),
+    Index(usize),
+}
+
+/// A key or array item in a data file.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DataNode {
+    /// Index of the enclosing node, `None` for top level nodes.
+    pub parent: Option<usize>,
+    pub path: Vec<PathSegment>,
+    /// The key as written, or the index of array items.
+    pub label: String,
+    /// The value, or a summary of objects and arrays.
+    pub preview: String,
+    /// Char position of the key, or of the value of array items.
+    pub pos: usize,
+}
+
+impl DataNode {
+    /// The path of the node in `jq` syntax, e.g. `.dependencies["serde-json"][0]`.
+    pub fn jq_path(&self) -> String {
+        jq_path(&self.path)
+    }
+}
+
+pub fn jq_path(path: &[PathSegment]) -> String {
+    let mut jq = String::new();
+    for segment in path {
+        match segment {
+            PathSegment::Key(key)
+                if key.starts_with(|c: char| c.is_ascii_alphabetic() || c == '_')
+                    && key.chars().all(|c| c.is_ascii_alphanumeric() || c == '_') =>
+            {
+                write!(jq, ".{}", key).unwrap();
+            }
+            PathSegment::Key(key) => {
+                if jq.is_empty() {
+                    jq.push('.');
+                }
+                write!(jq, "[{:?}]", key).unwrap();
+            }
+            PathSegment::Index(index) => {
+                if jq.is_empty() {
+                    jq.push('.');
+                }
+                write!(jq, "[{}]", index).unwrap();
+            }
+        }
+    }
+    if jq.is_empty() {
+        jq.push('.');
+    }
+    jq
+}
+
+enum Shape {
+    Mapping,
+    Sequence,
+    Scalar,
+}
+
+fn is_toml_entry(kind: &str) -> bool {
+    matches!(kind, "pair" | "table" | "table_array_element")
+}
+
+/// Skips the nodes wrapping values.
+fn unwrap_value(mut node: Node) -> Node {
+    loop {
+        let next = match node.kind() {
+            "block_node" | "flow_node" | "stream" | "block_sequence_item" => {
+                named_children(node).next()
+            }
+            // documents holding entries are TOML tables
+            "document" if !named_children(node).any(|child| is_toml_entry(child.kind())) => {
+                named_children(node).next()
+            }
+            _ => None,
+        };
+        match next {
+            Some(next) => node = next,
+            None => return node,
+        }
+    }
+}
+
+fn shape(node: Node) -> Shape {
+    match node.kind() {
+        "object"
+        | "block_mapping"
+        | "flow_mapping"
+        | "inline_table"
+        | "table"
+        | "table_array_element"
+        | "document" => Shape::Mapping,
+        "array" | "block_sequence" | "flow_sequence" => Shape::Sequence,
+        _ => Shape::Scalar,
+    }
+}
+
+fn named_children<'a>(node: Node<'a>) -> impl Iterator<Item = Node<'a>> {
+    (0..node.named_child_count())
+        .filter_map(move |i| node.named_child(i))
+        .filter(|child| child.kind() != "comment")
+}
+
+fn node_text<'a>(node: Node, text: RopeSlice<'a>) -> Cow<'a, str> {
+    text.byte_slice(node.start_byte()..node.end_byte()).into()
+}
+
+/// The segments of a key, dotted TOML keys have several.
+fn key_segments(node: Node, text: RopeSlice, segments: &mut Vec<PathSegment>) {
+    match node.kind() {
+        "dotted_key" => {
+            for child in named_children(node) {
+                key_segments(child, text, segments);
+            }
+        }
+        _ => {
+            let key = node_text(node, text);
+            let key = key.trim();
+            let unquoted = key
+                .strip_prefix('"')
+                .and_then(|key| key.strip_suffix('"'))
+                .or_else(|| {
+                    key.strip_prefix('\'')
+                        .and_then(|key| key.strip_suffix('\''))
+                })
+                .unwrap_or(key);
+            segments.push(PathSegment::Key(unquoted.to_string()));
+        }
+    }
+}
+
+fn preview(node: Option<Node>, text: RopeSlice) -> String {
+    let node = match node {
+        Some(node) => unwrap_value(node),
+        None => return String::new(),
+    };
+    match shape(node) {
+        Shape::Mapping => format!("{{ {} keys }}", entries(node, text).len()),
+        Shape::Sequence => format!("[ {} items ]", named_children(node).count()),
+        Shape::Scalar => {
+            let value = node_text(node, text);
+            let line = value.lines().next().unwrap_or_default().trim();
+            if line.chars().count() > MAX_PREVIEW_WIDTH || value.trim() != line {
+                let line: String = line.chars().take(MAX_PREVIEW_WIDTH).collect();
+                format!("{}…", line)
+            } else {
+                line.to_string()
+            }
+        }
+    }
+}
+
+struct Entry<'a> {
+    key: Node<'a>,
+    value: Option<Node<'a>>,
+    /// Index of `[[array]]` tables among those with the same key.
+    index: Option<usize>,
+}
+
+fn entries<'a>(node: Node<'a>, text: RopeSlice) -> Vec<Entry<'a>> {
+    let mut entries: Vec<Entry> = Vec::new();
+    // the first child of a table is its header
+    let skip = matches!(node.kind(), "table" | "table_array_element") as usize;
+    for child in named_children(node).skip(skip) {
+        let entry = match child.kind() {
+            "pair" | "block_mapping_pair" | "flow_pair" => {
+                let key = match child
+                    .child_by_field_name("key")
+                    .or_else(|| child.named_child(0))
+                {
+                    Some(key) => key,
+                    None => continue,
+                };
+                let value = child
+                    .child_by_field_name("value")
+                    .or_else(|| named_children(child).last().filter(|value| *value != key));
+                Entry {
+                    key,
+                    value,
+                    index: None,
+                }
+            }
+            "table" | "table_array_element" => {
+                let key = match child.named_child(0) {
+                    Some(key) => key,
+                    None => continue,
+                };
+                let index = (child.kind() == "table_array_element").then(|| {
+                    entries
+                        .iter()
+                        .filter(|entry| {
+                            entry.index.is_some()
+                                && node_text(entry.key, text) == node_text(key, text)
+                        })
+                        .count()
+                });
+                Entry {
+                    key,
+                    value: Some(child),
+                    index,
+                }
+            }
+            _ => continue,
+        };
+        entries.push(entry);
+    }
+    entries
+}
+
+fn visit(
+    node: Node,
+    text: RopeSlice,
+    parent: Option<usize>,
+    path: &[PathSegment],
+    nodes: &mut Vec<DataNode>,
+) {
+    let node = unwrap_value(node);
+    match shape(node) {
+        Shape::Mapping => {
+            for entry in entries(node, text) {
+                let mut path = path.to_vec();
+                key_segments(entry.key, text, &mut path);
+                path.extend(entry.index.map(PathSegment::Index));
+                let label = match entry.index {
+                    Some(index) => format!("{}[{}]", node_text(entry.key, text).trim(), index),
+                    None => node_text(entry.key, text).trim().to_string(),
+                };
+                nodes.push(DataNode {
+                    parent,
+                    path: path.clone(),
+                    label,
+                    preview: preview(entry.value, text),
+                    pos: text.byte_to_char(entry.key.start_byte()),
+                });
+                if let Some(value) = entry.value {
+                    visit(value, text, Some(nodes.len() - 1), &path, nodes);
+                }
+            }
+        }
+        Shape::Sequence => {
+            for (index, item) in named_children(node).enumerate() {
+                let mut path = path.to_vec();
+                path.push(PathSegment::Index(index));
+                nodes.push(DataNode {
+                    parent,
+                    path: path.clone(),
+                    label: format!("[{}]", index),
+                    preview: preview(Some(item), text),
+                    pos: text.byte_to_char(item.start_byte()),
+                });
+                visit(item, text, Some(nodes.len() - 1), &path, nodes);
+            }
+        }
+        Shape::Scalar => (),
+    }
+}
+
+/// Returns the keys and array items below `root` in depth first order.
+pub fn data_tree(root: Node, text: RopeSlice) -> Vec<DataNode> {
+    let mut nodes = Vec::new();
+    visit(root, text, None, &[], &mut nodes);
+    nodes
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn jq_paths() {
+        use PathSegment::*;
+        assert_eq!(jq_path(&[]), ".");
+        assert_eq!(
+            jq_path(&[
+                Key("dependencies".into()),
+                Key("serde-json".into()),
+                Index(0)
+            ]),
+            ".dependencies[\"serde-json\"][0]"
+        );
+        assert_eq!(jq_path(&[Index(2), Key("a_1".into())]), ".[2].a_1");
+        assert_eq!(jq_path(&[Key("1st".into())]), ".[\"1st\"]");
+    }
+}
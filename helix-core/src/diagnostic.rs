@@ -46,4 +46,13 @@ pub struct Diagnostic {
     pub tags: Vec<DiagnosticTag>,
     pub source: Option<String>,
     pub data: Option<serde_json::Value>,
+    /// Id of the language server which reported this diagnostic, if any.
+    pub language_server_id: Option<usize>,
+}
+
+impl Diagnostic {
+    /// The severity, counting diagnostics without one as errors.
+    pub fn effective_severity(&self) -> Severity {
+        self.severity.unwrap_or(Severity::Error)
+    }
 }
@@ -178,6 +178,18 @@ pub fn compare_ropes(before: &Rope, after: &Rope) -> Transaction {
     res
 }
 
+/// Returns the ranges of lines of `before` replaced by ranges of lines of `after`, in order.
+pub fn line_hunks(before: &str, after: &str) -> Vec<(Range<u32>, Range<u32>)> {
+    let input = InternedInput::new(before, after);
+    let mut hunks = Vec::new();
+    imara_diff::diff(
+        Algorithm::Histogram,
+        &input,
+        |before: Range<u32>, after: Range<u32>| hunks.push((before, after)),
+    );
+    hunks
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -189,6 +201,12 @@ fn test_identity(a: &str, b: &str) {
         assert_eq!(old, new);
     }
 
+    #[test]
+    fn hunks() {
+        let hunks = line_hunks("a\nb\nc\nd\n", "a\nB\nc\nd\ne\n");
+        assert_eq!(hunks, vec![(1..2, 1..2), (4..4, 4..5)]);
+    }
+
     quickcheck::quickcheck! {
         fn test_compare_ropes(a: String, b: String) -> bool {
             let mut old = Rope::from(a);
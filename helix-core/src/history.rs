@@ -1,4 +1,4 @@
-use crate::{Assoc, ChangeSet, Range, Rope, Selection, Transaction};
+use crate::{Assoc, ChangeSet, Operation, Range, Rope, Selection, Transaction};
 use once_cell::sync::Lazy;
 use regex::Regex;
 use std::num::NonZeroUsize;
@@ -65,6 +65,20 @@ struct Revision {
     timestamp: Instant,
 }
 
+/// A revision as listed by [History::revisions].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RevisionInfo {
+    pub index: usize,
+    /// The revision this one was committed on, `None` for the root.
+    pub parent: Option<usize>,
+    pub timestamp: Instant,
+    /// Number of characters inserted and deleted by the revision.
+    pub inserted: usize,
+    pub deleted: usize,
+    /// The first line of the first text inserted by the revision.
+    pub summary: String,
+}
+
 impl Default for History {
     fn default() -> Self {
         // Add a dummy root revision with empty transaction
@@ -119,6 +133,47 @@ pub const fn at_root(&self) -> bool {
         self.current == 0
     }
 
+    /// Lists the revisions in the order they were committed, starting with the root. Revisions
+    /// committed after an undo have the same parent as the revision that was undone, which makes
+    /// the history a tree.
+    pub fn revisions(&self) -> Vec<RevisionInfo> {
+        self.revisions
+            .iter()
+            .enumerate()
+            .map(|(index, revision)| {
+                let mut inserted = 0;
+                let mut deleted = 0;
+                let mut summary = None;
+                for operation in revision.transaction.changes().changes() {
+                    match operation {
+                        Operation::Insert(text) => {
+                            inserted += text.chars().count();
+                            summary.get_or_insert_with(|| {
+                                text.trim_start().lines().next().unwrap_or("").to_string()
+                            });
+                        }
+                        Operation::Delete(n) => deleted += n,
+                        Operation::Retain(_) => {}
+                    }
+                }
+                RevisionInfo {
+                    index,
+                    parent: (index != 0).then(|| revision.parent),
+                    timestamp: revision.timestamp,
+                    inserted,
+                    deleted,
+                    summary: summary.unwrap_or_default(),
+                }
+            })
+            .collect()
+    }
+
+    /// Creates the transactions that move the buffer to `revision`, which may be on another
+    /// branch of the history. Returns `None` if there is no such revision.
+    pub fn jump_to_revision(&mut self, revision: usize) -> Option<Vec<Transaction>> {
+        (revision < self.revisions.len()).then(|| self.jump_to(revision))
+    }
+
     /// Returns the changes since the given revision composed into a transaction.
     /// Returns None if there are no changes between the current and given revisions.
     pub fn changes_since(&self, revision: usize) -> Option<Transaction> {
@@ -546,6 +601,50 @@ fn commit_change(
         assert_eq!("a\n", state.doc);
     }
 
+    #[test]
+    fn test_revision_tree() {
+        let mut history = History::default();
+        let mut state = State {
+            doc: Rope::from("a\n"),
+            selection: Selection::point(0),
+        };
+
+        fn commit_change(
+            history: &mut History,
+            state: &mut State,
+            change: crate::transaction::Change,
+        ) {
+            let txn = Transaction::change(&state.doc, vec![change].into_iter());
+            history.commit_revision(&txn, state);
+            txn.apply(&mut state.doc);
+        }
+
+        commit_change(&mut history, &mut state, (1, 1, Some(" b".into())));
+        commit_change(&mut history, &mut state, (0, 1, None));
+        assert_eq!(" b\n", state.doc);
+        // undo the deletion and branch off from the first revision
+        if let Some(txn) = history.undo() {
+            txn.apply(&mut state.doc);
+        }
+        commit_change(&mut history, &mut state, (4, 4, Some("c\nd".into())));
+        assert_eq!("a b\nc\nd", state.doc);
+
+        let revisions = history.revisions();
+        let parents: Vec<_> = revisions.iter().map(|r| r.parent).collect();
+        assert_eq!(parents, vec![None, Some(0), Some(1), Some(1)]);
+        assert_eq!((revisions[1].inserted, revisions[1].deleted), (2, 0));
+        assert_eq!((revisions[2].inserted, revisions[2].deleted), (0, 1));
+        assert_eq!(revisions[3].summary, "c");
+
+        // jump over to the other branch
+        for txn in history.jump_to_revision(2).unwrap() {
+            txn.apply(&mut state.doc);
+        }
+        assert_eq!(" b\n", state.doc);
+        assert_eq!(history.current_revision(), 2);
+        assert!(history.jump_to_revision(4).is_none());
+    }
+
     #[test]
     fn test_parse_undo_kind() {
         use UndoKind::*;
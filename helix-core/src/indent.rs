@@ -58,6 +58,16 @@ pub fn as_str(&self) -> &'static str {
     }
 }
 
+impl std::fmt::Display for IndentStyle {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            IndentStyle::Tabs => f.write_str("tabs"),
+            IndentStyle::Spaces(1) => f.write_str("1 space"),
+            IndentStyle::Spaces(n) => write!(f, "{} spaces", n),
+        }
+    }
+}
+
 /// Attempts to detect the indentation style used in a document.
 ///
 /// Returns the indentation style if the auto-detect confidence is
@@ -726,7 +736,48 @@ pub fn indent_for_newline(
             return indent;
         };
     }
-    let indent_level = indent_level_for_line(text.line(current_line), tab_width);
+    heuristic_indent_for_newline(
+        indent_style,
+        tab_width,
+        text,
+        line_before,
+        line_before_end_pos,
+        current_line,
+    )
+}
+
+/// Guesses the indent of a new line when the language has no indent query. The new line is
+/// indented one level deeper than `line_before` if a bracket is left open on it before
+/// `line_before_end_pos`, and otherwise keeps the indent of `current_line`. A bracket closed
+/// right after the position is not counted as open, since splitting such a pair is handled
+/// by the caller.
+fn heuristic_indent_for_newline(
+    indent_style: &IndentStyle,
+    tab_width: usize,
+    text: RopeSlice,
+    line_before: usize,
+    line_before_end_pos: usize,
+    current_line: usize,
+) -> String {
+    let line_start = text.line_to_char(line_before);
+    let mut open = 0usize;
+    for ch in text
+        .slice(line_start..line_before_end_pos.max(line_start))
+        .chars()
+    {
+        match ch {
+            '(' | '[' | '{' => open += 1,
+            ')' | ']' | '}' => open = open.saturating_sub(1),
+            _ => {}
+        }
+    }
+    let closed_after = matches!(text.get_char(line_before_end_pos), Some(')' | ']' | '}'));
+
+    let indent_level = if open > 0 && !closed_after {
+        indent_level_for_line(text.line(line_before), tab_width) + 1
+    } else {
+        indent_level_for_line(text.line(current_line), tab_width)
+    };
     indent_style.as_str().repeat(indent_level)
 }
 
@@ -771,4 +822,24 @@ fn test_indent_level() {
         let line = Rope::from("\t    \tfn new"); // 1 tab, 4 spaces, tab
         assert_eq!(indent_level_for_line(line.slice(..), tab_width), 3);
     }
+
+    #[test]
+    fn test_heuristic_indent() {
+        let style = IndentStyle::Spaces(2);
+        let indent = |text: &str, line_before: usize, pos: usize, current_line: usize| {
+            let text = Rope::from(text);
+            heuristic_indent_for_newline(&style, 2, text.slice(..), line_before, pos, current_line)
+        };
+        // keeps the indent of the current line
+        assert_eq!(indent("  foo\n", 0, 5, 0), "  ");
+        // indents after an open bracket
+        assert_eq!(indent("  foo {\n", 0, 7, 0), "    ");
+        assert_eq!(indent("  foo(a,\n", 0, 8, 0), "    ");
+        // brackets closed on the line are ignored
+        assert_eq!(indent("  foo() {}\n", 0, 10, 0), "  ");
+        // a pair split by the caller
+        assert_eq!(indent("  foo {}\n", 0, 7, 0), "  ");
+        // opening a line above uses the indent of the open line before it
+        assert_eq!(indent("foo {\n  bar\n", 0, 5, 1), "  ");
+    }
 }
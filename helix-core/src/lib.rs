@@ -4,6 +4,7 @@
 pub mod chars;
 pub mod comment;
 pub mod config;
+pub mod data_tree;
 pub mod diagnostic;
 pub mod diff;
 pub mod graphemes;
@@ -11,18 +12,22 @@
 pub mod increment;
 pub mod indent;
 pub mod line_ending;
+pub mod log_file;
 pub mod macros;
 pub mod match_brackets;
 pub mod movement;
 pub mod object;
+pub mod outline;
 pub mod path;
 mod position;
 pub mod register;
 pub mod search;
 pub mod selection;
 pub mod shellwords;
+pub mod structural;
 pub mod surround;
 pub mod syntax;
+pub mod table;
 pub mod test;
 pub mod textobject;
 mod transaction;
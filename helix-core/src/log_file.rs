@@ -0,0 +1,190 @@
+//! Parsing of log files into entries with a severity level and timestamp.
+
+use crate::RopeSlice;
+use regex::Regex;
+use std::borrow::Cow;
+use std::ops::Range;
+
+/// Matches RFC 3339 and similar timestamps at the start of a line, optionally in brackets.
+pub const DEFAULT_TIMESTAMP_PATTERN: &str =
+    r"^\[?\d{4}-\d{2}-\d{2}[T ]\d{2}:\d{2}:\d{2}(?:[.,]\d+)?(?:Z|[+-]\d{2}:?\d{2})?\]?";
+
+/// Matches the first level name on a line.
+pub const DEFAULT_LEVEL_PATTERN: &str =
+    r"\b(?:TRACE|DEBUG|INFO|WARN|WARNING|ERROR|FATAL|trace|debug|info|warn|warning|error|fatal)\b";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum LogLevel {
+    Trace,
+    Debug,
+    Info,
+    Warn,
+    Error,
+}
+
+impl LogLevel {
+    pub const ALL: [Self; 5] = [
+        Self::Trace,
+        Self::Debug,
+        Self::Info,
+        Self::Warn,
+        Self::Error,
+    ];
+
+    /// Parses common spellings and abbreviations of level names, ignoring case.
+    pub fn parse(name: &str) -> Option<Self> {
+        match name.to_ascii_lowercase().as_str() {
+            "trace" | "trc" | "t" => Some(Self::Trace),
+            "debug" | "dbg" | "d" => Some(Self::Debug),
+            "info" | "inf" | "notice" | "i" => Some(Self::Info),
+            "warn" | "wrn" | "warning" | "w" => Some(Self::Warn),
+            "error" | "err" | "fatal" | "critical" | "crit" | "panic" | "e" => Some(Self::Error),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Trace => "trace",
+            Self::Debug => "debug",
+            Self::Info => "info",
+            Self::Warn => "warn",
+            Self::Error => "error",
+        }
+    }
+}
+
+/// The patterns recognizing the parts of log lines.
+#[derive(Debug, Clone)]
+pub struct LogPatterns {
+    timestamp: Regex,
+    /// The first capture group, or the whole match, is the level name.
+    level: Regex,
+}
+
+impl LogPatterns {
+    pub fn new(timestamp: &str, level: &str) -> Result<Self, regex::Error> {
+        Ok(Self {
+            timestamp: Regex::new(timestamp)?,
+            level: Regex::new(level)?,
+        })
+    }
+
+    fn level(&self, line: &str) -> Option<(LogLevel, Range<usize>)> {
+        self.level.captures_iter(line).find_map(|captures| {
+            let name = captures.get(1).or_else(|| captures.get(0))?;
+            LogLevel::parse(name.as_str()).map(|level| (level, name.range()))
+        })
+    }
+}
+
+impl Default for LogPatterns {
+    fn default() -> Self {
+        Self::new(DEFAULT_TIMESTAMP_PATTERN, DEFAULT_LEVEL_PATTERN).unwrap()
+    }
+}
+
+/// A log line, standing in for any identical lines directly following it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LogEntry {
+    pub line: usize,
+    pub text: String,
+    /// Lines without a level, like the frames of a stack trace, have the one of the line they
+    /// continue.
+    pub level: Option<LogLevel>,
+    /// Byte range of the level name in `text`.
+    pub level_range: Option<Range<usize>>,
+    /// Byte range of the timestamp in `text`.
+    pub timestamp: Option<Range<usize>>,
+    /// Number of consecutive lines folded into this entry, ignoring their timestamps.
+    pub repeats: usize,
+}
+
+impl LogEntry {
+    /// The text without the timestamp.
+    fn message(&self) -> &str {
+        match &self.timestamp {
+            Some(range) => &self.text[range.end..],
+            None => &self.text,
+        }
+    }
+}
+
+pub fn parse_log(text: RopeSlice, patterns: &LogPatterns) -> Vec<LogEntry> {
+    let mut entries: Vec<LogEntry> = Vec::new();
+    for (index, line) in text.lines().enumerate() {
+        let line = Cow::from(line);
+        let line = line.trim_end_matches(|c| c == '\n' || c == '\r');
+        if line.is_empty() && index + 1 == text.len_lines() {
+            break;
+        }
+
+        let timestamp = patterns.timestamp.find(line).map(|m| m.range());
+        let (level, level_range) = match patterns.level(line) {
+            Some((level, range)) => (Some(level), Some(range)),
+            None => (entries.last().and_then(|entry| entry.level), None),
+        };
+        let entry = LogEntry {
+            line: index,
+            text: line.to_string(),
+            level,
+            level_range,
+            timestamp,
+            repeats: 1,
+        };
+        match entries.last_mut() {
+            Some(last) if last.message() == entry.message() => last.repeats += 1,
+            _ => entries.push(entry),
+        }
+    }
+    entries
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::Rope;
+
+    #[test]
+    fn parse_entries() {
+        let doc = Rope::from(
+            "2022-10-01T12:00:00Z INFO started\n\
+             2022-10-01T12:00:01Z WARN retrying\n\
+             2022-10-01T12:00:02Z WARN retrying\n\
+             [2022-10-01 12:00:03.123] error: failed\n\
+             \tat main.rs:3\n\
+             plain\n",
+        );
+        let entries = parse_log(doc.slice(..), &LogPatterns::default());
+        let summary: Vec<_> = entries
+            .iter()
+            .map(|entry| (entry.line, entry.level, entry.repeats))
+            .collect();
+        assert_eq!(
+            summary,
+            vec![
+                (0, Some(LogLevel::Info), 1),
+                (1, Some(LogLevel::Warn), 2),
+                (3, Some(LogLevel::Error), 1),
+                (4, Some(LogLevel::Error), 1),
+                (5, Some(LogLevel::Error), 1),
+            ]
+        );
+        assert_eq!(entries[2].timestamp, Some(0..25));
+        assert_eq!(entries[2].level_range, Some(26..31));
+        assert_eq!(entries[3].timestamp, None);
+    }
+
+    #[test]
+    fn custom_patterns() {
+        let patterns = LogPatterns::new(r"^\d+", r"<(\w+)>").unwrap();
+        let doc = Rope::from("12 <err> boom\n13 <I> fine\n14 <x> unknown\n");
+        let entries = parse_log(doc.slice(..), &patterns);
+        assert_eq!(entries[0].level, Some(LogLevel::Error));
+        assert_eq!(entries[0].level_range, Some(4..7));
+        assert_eq!(entries[1].level, Some(LogLevel::Info));
+        // unknown names continue the previous entry
+        assert_eq!(entries[2].level, Some(LogLevel::Info));
+        assert_eq!(entries[2].level_range, None);
+    }
+}
@@ -90,3 +90,47 @@ fn surrounding_bytes(doc: &Rope, node: &Node) -> Option<(usize, usize)> {
 
     Some((start_byte, end_byte))
 }
+
+/// The bracket tokens which nest for rainbow brackets. Angle brackets are left out as they are
+/// also comparison operators in most grammars.
+const NESTING_BRACKETS: &[&str] = &["(", ")", "[", "]", "{", "}"];
+
+/// Returns the char positions of the bracket tokens within the byte `range` with their
+/// nesting depth, the outermost brackets being at depth 0. The depth of a bracket is the
+/// number of enclosing nodes that are delimited by brackets themselves.
+pub fn bracket_depths(
+    syntax: &Syntax,
+    doc: &Rope,
+    range: std::ops::Range<usize>,
+) -> Vec<(usize, usize)> {
+    fn walk(
+        node: Node,
+        depth: usize,
+        doc: &Rope,
+        range: &std::ops::Range<usize>,
+        brackets: &mut Vec<(usize, usize)>,
+    ) {
+        let mut cursor = node.walk();
+        let children: Vec<_> = node.children(&mut cursor).collect();
+        let delimited = children
+            .iter()
+            .any(|child| NESTING_BRACKETS.contains(&child.kind()));
+        for child in children {
+            if child.end_byte() <= range.start || child.start_byte() >= range.end {
+                continue;
+            }
+            if NESTING_BRACKETS.contains(&child.kind()) {
+                // missing brackets inserted by error recovery are empty
+                if child.start_byte() < child.end_byte() {
+                    brackets.push((doc.byte_to_char(child.start_byte()), depth));
+                }
+            } else {
+                walk(child, depth + delimited as usize, doc, range, brackets);
+            }
+        }
+    }
+
+    let mut brackets = Vec::new();
+    walk(syntax.tree().root_node(), 0, doc, &range, &mut brackets);
+    brackets
+}
@@ -0,0 +1,390 @@
+//! Heading outline of markup documents, used to edit their sections structurally.
+
+use crate::{
+    line_ending::get_line_ending_of_str, Range, Rope, RopeSlice, Selection, Tendril, Transaction,
+};
+use std::borrow::Cow;
+
+/// The heading syntax of a markup language.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OutlineSyntax {
+    /// ATX headings (`## Title`), fenced code blocks are skipped.
+    Markdown,
+    /// Org headlines (`** Title`), `#+begin_` blocks are skipped.
+    Org,
+}
+
+impl OutlineSyntax {
+    /// The outline syntax of the language with the given name in `languages.toml`.
+    pub fn from_language_name(name: &str) -> Option<Self> {
+        match name {
+            "markdown" => Some(Self::Markdown),
+            "org" => Some(Self::Org),
+            _ => None,
+        }
+    }
+
+    fn marker(self) -> char {
+        match self {
+            Self::Markdown => '#',
+            Self::Org => '*',
+        }
+    }
+
+    fn max_level(self) -> usize {
+        match self {
+            Self::Markdown => 6,
+            Self::Org => usize::MAX,
+        }
+    }
+}
+
+/// A heading and the extent of its section.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Heading {
+    /// 1 for top level headings.
+    pub level: usize,
+    pub title: String,
+    /// Line of the heading.
+    pub line: usize,
+    /// Line after the end of the section, including its subsections.
+    pub end: usize,
+    /// Column of the first marker character.
+    marker: usize,
+}
+
+/// Parses the heading on a line, returning its level, marker column and title.
+fn parse_heading(line: &str, syntax: OutlineSyntax) -> Option<(usize, usize, &str)> {
+    let line = line.trim_end_matches(|c| c == '\n' || c == '\r');
+    let marker = match syntax {
+        // up to three spaces of indentation are allowed
+        OutlineSyntax::Markdown => line.len() - line.trim_start_matches(' ').len(),
+        OutlineSyntax::Org => 0,
+    };
+    if marker > 3 {
+        return None;
+    }
+    let rest = &line[marker..];
+    let level = rest.len() - rest.trim_start_matches(syntax.marker()).len();
+    if level == 0 || level > syntax.max_level() {
+        return None;
+    }
+    let title = &rest[level..];
+    if !title.is_empty() && !title.starts_with(|c| c == ' ' || c == '\t') {
+        return None;
+    }
+    let title = match syntax {
+        // closing sequences are optional
+        OutlineSyntax::Markdown => {
+            let trimmed = title.trim_end().trim_end_matches('#');
+            if trimmed.is_empty() || trimmed.ends_with(|c| c == ' ' || c == '\t') {
+                trimmed
+            } else {
+                title
+            }
+        }
+        OutlineSyntax::Org => title,
+    };
+    Some((level, marker, title.trim()))
+}
+
+/// Tracks blocks in which heading markers are literal text.
+#[derive(Default)]
+struct BlockState {
+    /// The fence character and length of the open markdown code block.
+    fence: Option<(char, usize)>,
+    /// Whether an org block is open.
+    org_block: bool,
+}
+
+impl BlockState {
+    /// Returns whether the line is inside of a block, updating the state.
+    fn skip(&mut self, line: &str, syntax: OutlineSyntax) -> bool {
+        let trimmed = line.trim();
+        match syntax {
+            OutlineSyntax::Markdown => {
+                let indent = line.len() - line.trim_start_matches(' ').len();
+                let fence = trimmed.chars().next().filter(|c| *c == '`' || *c == '~');
+                let fence = fence
+                    .filter(|_| indent <= 3)
+                    .map(|c| (c, trimmed.len() - trimmed.trim_start_matches(c).len()))
+                    .filter(|(_, len)| *len >= 3);
+                match (self.fence, fence) {
+                    (None, Some(fence)) => {
+                        self.fence = Some(fence);
+                        true
+                    }
+                    (Some((open, open_len)), Some((c, len)))
+                        if c == open && len >= open_len && trimmed.len() == len =>
+                    {
+                        self.fence = None;
+                        true
+                    }
+                    (fence, _) => fence.is_some(),
+                }
+            }
+            OutlineSyntax::Org => {
+                let lowercase = trimmed.to_lowercase();
+                if self.org_block {
+                    self.org_block = !lowercase.starts_with("#+end_");
+                    true
+                } else {
+                    self.org_block = lowercase.starts_with("#+begin_");
+                    self.org_block
+                }
+            }
+        }
+    }
+}
+
+/// Returns the headings of the document in order.
+pub fn headings(text: RopeSlice, syntax: OutlineSyntax) -> Vec<Heading> {
+    let mut headings: Vec<Heading> = Vec::new();
+    let mut blocks = BlockState::default();
+    for (index, line) in text.lines().enumerate() {
+        let line = Cow::from(line);
+        if blocks.skip(&line, syntax) {
+            continue;
+        }
+        if let Some((level, marker, title)) = parse_heading(&line, syntax) {
+            headings.push(Heading {
+                level,
+                title: title.to_string(),
+                line: index,
+                end: text.len_lines(),
+                marker,
+            });
+        }
+    }
+
+    // a section ends at the next heading of the same or a higher level
+    for i in 0..headings.len() {
+        let level = headings[i].level;
+        if let Some(next) = headings[i + 1..].iter().find(|next| next.level <= level) {
+            headings[i].end = next.line;
+        }
+    }
+    headings
+}
+
+/// Index of the heading of the innermost section containing `line`.
+pub fn section_at(headings: &[Heading], line: usize) -> Option<usize> {
+    headings.iter().rposition(|heading| heading.line <= line)
+}
+
+/// Changes the level of the headings on the selected lines by `delta`. Ranges without a heading
+/// change the heading of the section they are in.
+#[must_use]
+pub fn change_level(
+    doc: &Rope,
+    selection: &Selection,
+    syntax: OutlineSyntax,
+    delta: isize,
+) -> Transaction {
+    let text = doc.slice(..);
+    let headings = headings(text, syntax);
+
+    let mut targets = Vec::new();
+    for range in selection {
+        let (start, end) = range.line_range(text);
+        let len = targets.len();
+        targets.extend(
+            headings
+                .iter()
+                .enumerate()
+                .filter(|(_, heading)| (start..=end).contains(&heading.line))
+                .map(|(index, _)| index),
+        );
+        if targets.len() == len {
+            targets.extend(section_at(&headings, start));
+        }
+    }
+    targets.sort_unstable();
+    targets.dedup();
+
+    let marker = syntax.marker().to_string();
+    let changes = targets.into_iter().filter_map(|index| {
+        let heading = &headings[index];
+        let level = if delta < 0 {
+            heading.level.saturating_sub(delta.unsigned_abs()).max(1)
+        } else {
+            heading
+                .level
+                .saturating_add(delta as usize)
+                .min(syntax.max_level())
+        };
+        let pos = text.line_to_char(heading.line) + heading.marker;
+        if level > heading.level {
+            Some((
+                pos,
+                pos,
+                Some(Tendril::from(marker.repeat(level - heading.level))),
+            ))
+        } else if level < heading.level {
+            Some((pos, pos + heading.level - level, None))
+        } else {
+            None
+        }
+    });
+    Transaction::change(doc, changes)
+}
+
+/// Swaps the section containing the primary cursor, including its subsections, with its
+/// previous or next sibling. Returns `None` if there is no such sibling.
+#[must_use]
+pub fn move_section(
+    doc: &Rope,
+    selection: &Selection,
+    syntax: OutlineSyntax,
+    down: bool,
+) -> Option<Transaction> {
+    let text = doc.slice(..);
+    let headings = headings(text, syntax);
+    let line = text.char_to_line(selection.primary().cursor(text));
+    let current = section_at(&headings, line)?;
+    let heading = &headings[current];
+
+    let sibling = if down {
+        headings[current + 1..]
+            .iter()
+            .find(|sibling| sibling.line == heading.end)
+    } else {
+        headings[..current]
+            .iter()
+            .rev()
+            .find(|sibling| sibling.end == heading.line)
+    };
+    // a heading of a higher level ends the section of the parent
+    let sibling = sibling.filter(|sibling| sibling.level == heading.level)?;
+    let (upper, lower) = if down {
+        (heading, sibling)
+    } else {
+        (sibling, heading)
+    };
+
+    let start = text.line_to_char(upper.line);
+    let mid = text.line_to_char(lower.line);
+    let end = text.line_to_char(lower.end);
+    let mut upper_text = text.slice(start..mid).to_string();
+    let mut lower_text = text.slice(mid..end).to_string();
+    // the last section of the document may lack a line ending, move the one of the other
+    if get_line_ending_of_str(&lower_text).is_none() {
+        if let Some(line_ending) = get_line_ending_of_str(&upper_text) {
+            upper_text.truncate(upper_text.len() - line_ending.as_str().len());
+            lower_text.push_str(line_ending.as_str());
+        }
+    }
+
+    let lower_len = lower_text.chars().count();
+    let map = |pos: usize| {
+        if pos < start || pos >= end {
+            pos
+        } else if pos >= mid {
+            start + pos - mid
+        } else {
+            (start + lower_len + pos - start).min(end.saturating_sub(1))
+        }
+    };
+    let selection = selection
+        .clone()
+        .transform(|range| Range::new(map(range.anchor), map(range.head)));
+
+    lower_text.push_str(&upper_text);
+    let change = (start, end, Some(Tendril::from(lower_text)));
+    Some(Transaction::change(doc, std::iter::once(change)).with_selection(selection))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    const MARKDOWN: &str = "# One\n\
+        text\n\
+        ## One.A\n\
+        ```\n\
+        # not a heading\n\
+        ```\n\
+        ## One.B ##\n\
+        #no heading\n\
+        # Two\n";
+
+    #[test]
+    fn markdown_headings() {
+        let doc = Rope::from(MARKDOWN);
+        let headings = headings(doc.slice(..), OutlineSyntax::Markdown);
+        let outline: Vec<_> = headings
+            .iter()
+            .map(|heading| {
+                (
+                    heading.level,
+                    heading.title.as_str(),
+                    heading.line,
+                    heading.end,
+                )
+            })
+            .collect();
+        assert_eq!(
+            outline,
+            vec![
+                (1, "One", 0, 8),
+                (2, "One.A", 2, 6),
+                (2, "One.B", 6, 8),
+                (1, "Two", 8, 10),
+            ]
+        );
+        assert_eq!(section_at(&headings, 4), Some(1));
+    }
+
+    #[test]
+    fn org_headings() {
+        let doc = Rope::from("* One\n#+BEGIN_SRC\n* code\n#+END_SRC\n** Sub\n*bold*\n");
+        let headings = headings(doc.slice(..), OutlineSyntax::Org);
+        let outline: Vec<_> = headings
+            .iter()
+            .map(|heading| (heading.level, heading.title.as_str(), heading.line))
+            .collect();
+        assert_eq!(outline, vec![(1, "One", 0), (2, "Sub", 4)]);
+    }
+
+    #[test]
+    fn promote_and_demote() {
+        let mut doc = Rope::from(MARKDOWN);
+        // cursor inside of the "One.A" section
+        let selection = Selection::point(doc.line_to_char(3));
+        let transaction = change_level(&doc, &selection, OutlineSyntax::Markdown, 1);
+        transaction.apply(&mut doc);
+        assert_eq!(doc.line(2), "### One.A\n");
+
+        // selection spanning the whole document
+        let selection = Selection::single(0, doc.len_chars());
+        let transaction = change_level(&doc, &selection, OutlineSyntax::Markdown, -1);
+        transaction.apply(&mut doc);
+        assert_eq!(doc.line(0), "# One\n");
+        assert_eq!(doc.line(2), "## One.A\n");
+        assert_eq!(doc.line(4), "# not a heading\n");
+        assert_eq!(doc.line(6), "# One.B ##\n");
+    }
+
+    #[test]
+    fn move_sections() {
+        let mut doc = Rope::from("# One\na\n## Sub\n# Two\nb");
+        let selection = Selection::point(doc.line_to_char(1));
+        assert!(move_section(&doc, &selection, OutlineSyntax::Markdown, false).is_none());
+
+        let transaction = move_section(&doc, &selection, OutlineSyntax::Markdown, true).unwrap();
+        transaction.apply(&mut doc);
+        assert_eq!(doc, "# Two\nb\n# One\na\n## Sub");
+        // the cursor moves along with its section
+        let cursor = transaction
+            .selection()
+            .unwrap()
+            .primary()
+            .cursor(doc.slice(..));
+        assert_eq!(doc.char_to_line(cursor), 3);
+
+        let selection = Selection::point(doc.line_to_char(3));
+        let transaction = move_section(&doc, &selection, OutlineSyntax::Markdown, false).unwrap();
+        transaction.apply(&mut doc);
+        assert_eq!(doc, "# One\na\n## Sub\n# Two\nb");
+    }
+}
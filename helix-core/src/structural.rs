@@ -0,0 +1,312 @@
+//! Structural editing on the syntax tree, in the spirit of paredit: raising a node over its
+//! parent, splicing a list into its parent and moving the closing delimiter of a list to take
+//! in (slurp) or give away (barf) the nodes following it.
+//!
+//! A list is any node delimited by matching brackets, like the lists of lisps or the arguments,
+//! arrays and blocks of other languages.
+
+use tree_sitter::Node;
+
+use crate::{Change, Range, Rope, RopeSlice, Selection, SmallVec, Syntax, Tendril, Transaction};
+
+const DELIMITERS: &[(&str, &str)] = &[("(", ")"), ("[", "]"), ("{", "}")];
+
+/// The edit of one selection range.
+struct Edit {
+    changes: Vec<Change>,
+    /// The range to select, positioned as if the earlier edits didn't change anything.
+    range: Range,
+    /// The change of the document length by the edit.
+    delta: isize,
+}
+
+/// Returns the opening and closing delimiters of `node` if it is a list.
+fn delimiters(node: Node) -> Option<(Node, Node)> {
+    let count = node.child_count();
+    if count < 2 {
+        return None;
+    }
+    let open = node.child(0)?;
+    let close = node.child(count - 1)?;
+    DELIMITERS
+        .iter()
+        .any(|(o, c)| open.kind() == *o && close.kind() == *c)
+        .then(|| (open, close))
+}
+
+/// Returns the `count`th list from the inside whose delimiters enclose the byte range, together
+/// with its delimiters.
+fn enclosing_list<'a>(
+    syntax: &'a Syntax,
+    from: usize,
+    to: usize,
+    count: usize,
+) -> Option<(Node<'a>, Node<'a>, Node<'a>)> {
+    let mut node = syntax
+        .tree()
+        .root_node()
+        .descendant_for_byte_range(from, to);
+    let mut found = 0;
+    while let Some(current) = node {
+        if let Some((open, close)) = delimiters(current) {
+            if open.end_byte() <= from && close.start_byte() >= to {
+                found += 1;
+                if found == count {
+                    return Some((current, open, close));
+                }
+            }
+        }
+        node = current.parent();
+    }
+    None
+}
+
+fn node_text(text: RopeSlice, node: Node) -> Tendril {
+    let from = text.byte_to_char(node.start_byte());
+    let to = text.byte_to_char(node.end_byte());
+    Tendril::from(text.slice(from..to).to_string())
+}
+
+fn char_range(text: RopeSlice, node: Node) -> (usize, usize) {
+    (
+        text.byte_to_char(node.start_byte()),
+        text.byte_to_char(node.end_byte()),
+    )
+}
+
+/// Applies the edits of the selection ranges in one transaction. Edits overlapping earlier
+/// ones are dropped, and ranges without an edit keep their position.
+fn apply<F>(text: &Rope, selection: &Selection, edit_fn: F) -> Option<Transaction>
+where
+    F: Fn(RopeSlice, Range) -> Option<Edit>,
+{
+    let slice = text.slice(..);
+    let mut edits: Vec<(Range, Option<Edit>)> = selection
+        .iter()
+        .map(|range| (*range, edit_fn(slice, *range)))
+        .collect();
+    if edits.iter().all(|(_, edit)| edit.is_none()) {
+        return None;
+    }
+    edits.sort_by_key(|(range, edit)| match edit {
+        Some(edit) => edit.changes[0].0,
+        None => range.from(),
+    });
+
+    let mut changes = Vec::new();
+    let mut ranges = SmallVec::with_capacity(edits.len());
+    let mut end = 0;
+    let mut offset = 0isize;
+    let shift = |range: Range, offset: isize| {
+        Range::new(
+            (range.anchor as isize + offset) as usize,
+            (range.head as isize + offset) as usize,
+        )
+    };
+    for (range, edit) in edits {
+        match edit {
+            Some(edit) if edit.changes[0].0 >= end => {
+                end = edit.changes.last().unwrap().1;
+                ranges.push(shift(edit.range, offset));
+                offset += edit.delta;
+                changes.extend(edit.changes);
+            }
+            _ => ranges.push(shift(range, offset)),
+        }
+    }
+
+    let transaction = Transaction::change(text, changes.into_iter());
+    Some(transaction.with_selection(Selection::new(ranges, 0)))
+}
+
+/// Replaces the `count`th ancestor of the node of each range with the node, ignoring
+/// ancestors spanning the same text as it.
+pub fn raise(
+    syntax: &Syntax,
+    text: &Rope,
+    selection: &Selection,
+    count: usize,
+) -> Option<Transaction> {
+    apply(text, selection, |slice, range| {
+        let from = slice.char_to_byte(range.from());
+        let to = slice.char_to_byte(range.to());
+        let node = syntax
+            .tree()
+            .root_node()
+            .descendant_for_byte_range(from, to)?;
+        let mut ancestor = node;
+        for _ in 0..count {
+            ancestor = ancestor.parent()?;
+            while ancestor.start_byte() == node.start_byte()
+                && ancestor.end_byte() == node.end_byte()
+            {
+                ancestor = ancestor.parent()?;
+            }
+        }
+
+        let raised = node_text(slice, node);
+        let (start, end) = char_range(slice, ancestor);
+        let len = raised.chars().count();
+        Some(Edit {
+            delta: len as isize - (end - start) as isize,
+            range: Range::new(start, start + len),
+            changes: vec![(start, end, Some(raised))],
+        })
+    })
+}
+
+/// Removes the delimiters of the `count`th list enclosing each range.
+pub fn splice(
+    syntax: &Syntax,
+    text: &Rope,
+    selection: &Selection,
+    count: usize,
+) -> Option<Transaction> {
+    apply(text, selection, |slice, range| {
+        let from = slice.char_to_byte(range.from());
+        let to = slice.char_to_byte(range.to());
+        let (_, open, close) = enclosing_list(syntax, from, to, count)?;
+
+        let open = char_range(slice, open);
+        let close = char_range(slice, close);
+        let open_len = (open.1 - open.0) as isize;
+        let close_len = (close.1 - close.0) as isize;
+        let shift = |pos: usize| (pos as isize - open_len) as usize;
+        Some(Edit {
+            range: Range::new(shift(range.anchor), shift(range.head)),
+            delta: -open_len - close_len,
+            changes: vec![(open.0, open.1, None), (close.0, close.1, None)],
+        })
+    })
+}
+
+/// Moves the closing delimiter of the list enclosing each range past the `count` nodes
+/// following the list.
+pub fn slurp(
+    syntax: &Syntax,
+    text: &Rope,
+    selection: &Selection,
+    count: usize,
+) -> Option<Transaction> {
+    apply(text, selection, |slice, range| {
+        let from = slice.char_to_byte(range.from());
+        let to = slice.char_to_byte(range.to());
+        let (list, _, close) = enclosing_list(syntax, from, to, 1)?;
+
+        let mut last = list;
+        for _ in 0..count {
+            last = match last.next_named_sibling() {
+                Some(sibling) => sibling,
+                None if last == list => return None,
+                None => break,
+            };
+        }
+
+        let close_text = node_text(slice, close);
+        let close = char_range(slice, close);
+        let last_end = slice.byte_to_char(last.end_byte());
+        Some(Edit {
+            range,
+            delta: 0,
+            changes: vec![
+                (close.0, close.1, None),
+                (last_end, last_end, Some(close_text)),
+            ],
+        })
+    })
+}
+
+/// Moves the closing delimiter of the list enclosing each range before its last `count` nodes.
+pub fn barf(
+    syntax: &Syntax,
+    text: &Rope,
+    selection: &Selection,
+    count: usize,
+) -> Option<Transaction> {
+    apply(text, selection, |slice, range| {
+        let from = slice.char_to_byte(range.from());
+        let to = slice.char_to_byte(range.to());
+        let (list, open, close) = enclosing_list(syntax, from, to, 1)?;
+
+        let mut cursor = list.walk();
+        let children: Vec<_> = list.named_children(&mut cursor).collect();
+        if children.is_empty() {
+            return None;
+        }
+        // the list keeps the nodes up to the new position of the delimiter
+        let keep = children.len().saturating_sub(count);
+        let new_end = match keep.checked_sub(1) {
+            Some(index) => children[index].end_byte(),
+            None => open.end_byte(),
+        };
+
+        let close_text = node_text(slice, close);
+        let close_len = close_text.chars().count();
+        let close = char_range(slice, close);
+        let new_end = slice.byte_to_char(new_end);
+        let shift = |pos: usize| if pos >= new_end { pos + close_len } else { pos };
+        Some(Edit {
+            range: Range::new(shift(range.anchor), shift(range.head)),
+            delta: 0,
+            changes: vec![
+                (new_end, new_end, Some(close_text)),
+                (close.0, close.1, None),
+            ],
+        })
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::syntax::{Configuration, HighlightConfiguration, Loader};
+    use std::sync::Arc;
+
+    type EditFn = fn(&Syntax, &Rope, &Selection, usize) -> Option<Transaction>;
+
+    /// Applies the edit with the cursor on the first occurrence of `cursor` in `source`.
+    fn edit(edit_fn: EditFn, source: &str, cursor: &str, count: usize) -> (String, Range) {
+        let language = helix_loader::grammar::get_language("rust").unwrap();
+        let config = HighlightConfiguration::new(language, "", "", "").unwrap();
+        let loader = Loader::new(Configuration { language: vec![] });
+        let mut doc = Rope::from(source);
+        let syntax = Syntax::new(&doc, Arc::new(config), Arc::new(loader));
+
+        let pos = source.find(cursor).unwrap();
+        let selection = Selection::single(pos, pos + 1);
+        let transaction = edit_fn(&syntax, &doc, &selection, count).unwrap();
+        transaction.apply(&mut doc);
+        let range = transaction.selection().unwrap().primary();
+        (doc.to_string(), range)
+    }
+
+    #[test]
+    fn test_raise() {
+        let source = "fn f() { let a = (b + x); }";
+        let (text, range) = edit(raise, source, "x", 1);
+        assert_eq!(text, "fn f() { let a = (x); }");
+        assert_eq!(range, Range::new(18, 19));
+        let (text, _) = edit(raise, source, "x", 2);
+        assert_eq!(text, "fn f() { let a = x; }");
+    }
+
+    #[test]
+    fn test_splice() {
+        let source = "fn f() { let a = [1, [2, 3]]; }";
+        let (text, range) = edit(splice, source, "2", 1);
+        assert_eq!(text, "fn f() { let a = [1, 2, 3]; }");
+        assert_eq!(range, Range::new(21, 22));
+        let (text, _) = edit(splice, source, "2", 2);
+        assert_eq!(text, "fn f() { let a = 1, [2, 3]; }");
+    }
+
+    #[test]
+    fn test_slurp_barf() {
+        let (text, _) = edit(slurp, "fn f() { let a = [1, [2], 3]; }", "2", 1);
+        assert_eq!(text, "fn f() { let a = [1, [2, 3]]; }");
+        let (text, _) = edit(slurp, "fn f() { let a = [[1], 2, 3]; }", "1", 2);
+        assert_eq!(text, "fn f() { let a = [[1, 2, 3]]; }");
+        let (text, _) = edit(barf, "fn f() { let a = [1, [2, 3]]; }", "2", 1);
+        assert_eq!(text, "fn f() { let a = [1, [2], 3]; }");
+    }
+}
@@ -20,7 +20,7 @@
     fmt,
     hash::{Hash, Hasher},
     mem::{replace, transmute},
-    path::Path,
+    path::{Path, PathBuf},
     str::FromStr,
     sync::Arc,
 };
@@ -71,6 +71,13 @@ fn default() -> Self {
 }
 
 // largely based on tree-sitter/cli/src/loader.rs
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case", deny_unknown_fields)]
+pub struct BlockCommentTokens {
+    pub start: String,
+    pub end: String,
+}
+
 #[derive(Debug, Serialize, Deserialize)]
 #[serde(rename_all = "kebab-case", deny_unknown_fields)]
 pub struct LanguageConfiguration {
@@ -82,6 +89,8 @@ pub struct LanguageConfiguration {
     pub shebangs: Vec<String>, // interpreter(s) associated with language
     pub roots: Vec<String>,        // these indicate project roots <.git, Cargo.toml>
     pub comment_token: Option<String>,
+    /// Tokens around block comments, used by `toggle_comments` if there is no `comment_token`.
+    pub block_comment_tokens: Option<BlockCommentTokens>,
     pub max_line_length: Option<usize>,
 
     #[serde(default, skip_serializing, deserialize_with = "deserialize_lsp_config")]
@@ -93,6 +102,10 @@ pub struct LanguageConfiguration {
     #[serde(skip_serializing_if = "Option::is_none")]
     pub formatter: Option<FormatterConfiguration>,
 
+    /// Command whose output is turned into diagnostics by `:check`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub checker: Option<CheckerConfiguration>,
+
     #[serde(default)]
     pub diagnostic_severity: Severity,
 
@@ -108,6 +121,13 @@ pub struct LanguageConfiguration {
     // tags_config OnceCell<> https://github.com/tree-sitter/tree-sitter/pull/583
     #[serde(skip_serializing_if = "Option::is_none")]
     pub language_server: Option<LanguageServerConfiguration>,
+    /// Replacements for `language_server` used by documents under specific directories.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub language_server_overrides: Vec<LanguageServerOverride>,
+    /// Servers started alongside `language_server`, e.g. a linter. Requests are routed to the
+    /// first server advertising the needed capability, the primary server taking precedence.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub additional_language_servers: Vec<LanguageServerConfiguration>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub indent: Option<IndentationConfiguration>,
 
@@ -128,7 +148,7 @@ pub struct LanguageConfiguration {
     pub rulers: Option<Vec<u16>>, // if set, override editor's rulers
 }
 
-#[derive(Debug, PartialEq, Eq, Hash)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
 pub enum FileType {
     /// The extension of the file, either the `Path::extension` or the full
     /// filename if the file does not have an extension.
@@ -138,6 +158,20 @@ pub enum FileType {
     Suffix(String),
 }
 
+impl FileType {
+    /// Whether `path` has this file type, like the `Loader` detects the language of files.
+    pub fn matches(&self, path: &Path) -> bool {
+        match self {
+            FileType::Extension(extension) => {
+                let file_name = path.file_name().and_then(|name| name.to_str());
+                let path_extension = path.extension().and_then(|extension| extension.to_str());
+                file_name == Some(extension.as_str()) || path_extension == Some(extension.as_str())
+            }
+            FileType::Suffix(suffix) => path.to_str().map_or(false, |path| path.ends_with(suffix)),
+        }
+    }
+}
+
 impl Serialize for FileType {
     fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
     where
@@ -214,6 +248,16 @@ pub struct LanguageServerConfiguration {
     pub language_id: Option<String>,
 }
 
+/// A language server configuration that applies to documents within `path` (monorepos with
+/// multiple toolchains). Relative paths are made absolute when languages.toml is loaded.
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct LanguageServerOverride {
+    pub path: PathBuf,
+    #[serde(flatten)]
+    pub language_server: LanguageServerConfiguration,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(rename_all = "kebab-case")]
 pub struct FormatterConfiguration {
@@ -223,6 +267,29 @@ pub struct FormatterConfiguration {
     pub args: Vec<String>,
 }
 
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct CheckerConfiguration {
+    pub command: String,
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub args: Vec<String>,
+    pub format: CheckerFormat,
+    /// Regex matching a diagnostic per line, required by [`CheckerFormat::Regex`].
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub pattern: Option<String>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum CheckerFormat {
+    /// The JSON messages of `cargo --message-format=json` or `rustc --error-format=json`.
+    CargoJson,
+    /// Lines matched by `pattern` with the named groups `file`, `line`, `message` and
+    /// optionally `column` and `severity`.
+    Regex,
+}
+
 #[derive(Debug, PartialEq, Eq, Clone, Deserialize, Serialize)]
 #[serde(rename_all = "kebab-case")]
 pub struct AdvancedCompletion {
@@ -493,6 +560,32 @@ fn initialize_highlight(&self, scopes: &[String]) -> Option<Arc<HighlightConfigu
         }
     }
 
+    /// Resolves the language server configuration for a document located at `doc_path`.
+    ///
+    /// The most specific entry of `language_server_overrides` containing the document wins,
+    /// falling back to `language_server`. The directory of the chosen override is returned
+    /// alongside the configuration so callers can tell servers for different subtrees apart.
+    pub fn language_server_for(
+        &self,
+        doc_path: Option<&Path>,
+    ) -> Option<(&LanguageServerConfiguration, Option<PathBuf>)> {
+        let language_server_override = doc_path.and_then(|doc_path| {
+            self.language_server_overrides
+                .iter()
+                .map(|ls_override| {
+                    let dir = crate::path::get_normalized_path(&ls_override.path);
+                    (ls_override, dir)
+                })
+                .filter(|(_, dir)| doc_path.starts_with(dir))
+                .max_by_key(|(_, dir)| dir.components().count())
+        });
+
+        match language_server_override {
+            Some((ls_override, dir)) => Some((&ls_override.language_server, Some(dir))),
+            None => self.language_server.as_ref().map(|config| (config, None)),
+        }
+    }
+
     pub fn reconfigure(&self, scopes: &[String]) {
         if let Some(Some(config)) = self.highlight_config.get() {
             config.configure(scopes);
@@ -547,6 +640,62 @@ fn load_query(&self, kind: &str) -> Option<Query> {
     }
 }
 
+/// Number of lines at the start and the end of a document searched for modelines.
+const MODELINE_LINES: usize = 5;
+
+/// Number of chars at the start of a document the content heuristics look at.
+const CONTENT_HEURISTIC_CHARS: usize = 256;
+
+/// Prefixes of the text, the language they indicate and the reason shown for it.
+const CONTENT_HEURISTICS: &[(&str, &str, &str)] = &[
+    ("<?xml", "xml", "XML prolog"),
+    ("<!doctype html", "html", "HTML doctype"),
+    ("<html", "html", "HTML root element"),
+    ("<svg", "xml", "SVG root element"),
+    ("%YAML", "yaml", "YAML directive"),
+    ("diff --git", "diff", "git diff header"),
+];
+
+/// Why a language was chosen for a document.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum LanguageDetection {
+    /// The modeline naming the language.
+    Modeline(String),
+    FileName,
+    /// The shebang line.
+    Shebang(String),
+    /// The heuristic matching the content.
+    Content(&'static str),
+}
+
+impl fmt::Display for LanguageDetection {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Modeline(modeline) => write!(f, "the modeline `{}`", modeline),
+            Self::FileName => write!(f, "the file name"),
+            Self::Shebang(shebang) => write!(f, "the shebang `{}`", shebang),
+            Self::Content(reason) => write!(f, "the content ({})", reason),
+        }
+    }
+}
+
+/// Returns the language set by a vim modeline like `vim: set ft=rust:` or an emacs modeline
+/// like `-*- mode: rust -*-` in the line.
+fn modeline_language(line: &str) -> Option<&str> {
+    static VIM_MODELINE: Lazy<Regex> = Lazy::new(|| {
+        Regex::new(r"(?:^|\s)(?:vi|vim|ex):.*?\b(?:ft|filetype|syntax)=([\w+-]+)").unwrap()
+    });
+    static EMACS_MODELINE: Lazy<Regex> =
+        Lazy::new(|| Regex::new(r"-\*-\s*(?:.*?\bmode:\s*([\w+-]+).*?|([\w+-]+))\s*-\*-").unwrap());
+    let captures = VIM_MODELINE
+        .captures(line)
+        .or_else(|| EMACS_MODELINE.captures(line))?;
+    captures
+        .get(1)
+        .or_else(|| captures.get(2))
+        .map(|name| name.as_str())
+}
+
 // Expose loader as Lazy<> global since it's always static?
 
 #[derive(Debug)]
@@ -638,6 +787,73 @@ pub fn language_config_for_shebang(&self, source: &Rope) -> Option<Arc<LanguageC
         configuration_id.and_then(|&id| self.language_configs.get(id).cloned())
     }
 
+    /// Finds the language named in a vim or emacs modeline of the first or last lines.
+    pub fn language_config_for_modeline(
+        &self,
+        source: &Rope,
+    ) -> Option<(Arc<LanguageConfiguration>, String)> {
+        let lines = source.len_lines();
+        let head = 0..lines.min(MODELINE_LINES);
+        let tail = lines.saturating_sub(MODELINE_LINES).max(head.end)..lines;
+        head.chain(tail).find_map(|line| {
+            let line = Cow::from(source.line(line));
+            let name = modeline_language(&line)?;
+            let config = self.language_config_for_name(name)?;
+            Some((config, line.trim().to_string()))
+        })
+    }
+
+    /// Guesses the language from the start of the text, like an XML prolog.
+    pub fn language_config_for_content(
+        &self,
+        source: &Rope,
+    ) -> Option<(Arc<LanguageConfiguration>, &'static str)> {
+        let start = source.slice(..source.len_chars().min(CONTENT_HEURISTIC_CHARS));
+        let start = Cow::from(start);
+        let start = start.trim_start();
+        CONTENT_HEURISTICS
+            .iter()
+            .filter(|(prefix, _, _)| {
+                start
+                    .get(..prefix.len())
+                    .map_or(false, |start| start.eq_ignore_ascii_case(prefix))
+            })
+            .find_map(|(_, language_id, reason)| {
+                Some((self.language_config_for_language_id(language_id)?, *reason))
+            })
+    }
+
+    /// Detects the language of a document from its modelines, its file name, its shebang and
+    /// finally its content, in that order, returning why the language was chosen.
+    pub fn detect_language(
+        &self,
+        path: Option<&Path>,
+        source: &Rope,
+    ) -> Option<(Arc<LanguageConfiguration>, LanguageDetection)> {
+        if let Some((config, modeline)) = self.language_config_for_modeline(source) {
+            return Some((config, LanguageDetection::Modeline(modeline)));
+        }
+        if let Some(config) = path.and_then(|path| self.language_config_for_file_name(path)) {
+            return Some((config, LanguageDetection::FileName));
+        }
+        if let Some(config) = self.language_config_for_shebang(source) {
+            let shebang = Cow::from(source.line(0)).trim().to_string();
+            return Some((config, LanguageDetection::Shebang(shebang)));
+        }
+        self.language_config_for_content(source)
+            .map(|(config, reason)| (config, LanguageDetection::Content(reason)))
+    }
+
+    /// Finds a language by its name or, as modelines use the names of other editors, by one of
+    /// its file extensions.
+    fn language_config_for_name(&self, name: &str) -> Option<Arc<LanguageConfiguration>> {
+        self.language_config_for_language_id(name).or_else(|| {
+            self.language_config_ids_by_extension
+                .get(name)
+                .and_then(|&id| self.language_configs.get(id).cloned())
+        })
+    }
+
     pub fn language_config_for_scope(&self, scope: &str) -> Option<Arc<LanguageConfiguration>> {
         self.language_configs
             .iter()
@@ -1043,6 +1259,40 @@ pub fn tree(&self) -> &Tree {
         self.layers[self.root].tree()
     }
 
+    /// The number of language layers, the root layer and one for each injection.
+    pub fn layer_count(&self) -> usize {
+        self.layers.len()
+    }
+
+    /// The languages of the injected layers containing the byte `pos`, like JavaScript in HTML,
+    /// from the innermost to the outermost layer. Empty outside of injections.
+    pub fn injected_language_configs(
+        &self,
+        pos: usize,
+    ) -> impl Iterator<Item = Arc<LanguageConfiguration>> + '_ {
+        let mut layers: Vec<_> = self
+            .layers
+            .values()
+            .filter(|layer| {
+                layer.depth > 0
+                    && layer
+                        .ranges
+                        .iter()
+                        .any(|range| range.start_byte <= pos && pos < range.end_byte)
+            })
+            .collect();
+        layers.sort_by_key(|layer| std::cmp::Reverse(layer.depth));
+        layers.into_iter().filter_map(|layer| {
+            self.loader
+                .language_configs()
+                .find(|config| match config.highlight_config.get() {
+                    Some(Some(highlight_config)) => Arc::ptr_eq(highlight_config, &layer.config),
+                    _ => false,
+                })
+                .cloned()
+        })
+    }
+
     /// Iterate over the highlighted regions for a given slice of source code.
     pub fn highlight_iter<'a>(
         &'a self,
@@ -2197,7 +2447,9 @@ fn next(&mut self) -> Option<Self::Item> {
     }
 }
 
-fn node_is_visible(node: &Node) -> bool {
+/// Whether the node is shown in printed trees: named nodes the grammar doesn't hide and missing
+/// nodes.
+pub fn node_is_visible(node: &Node) -> bool {
     node.is_missing() || (node.is_named() && node.language().node_kind_is_visible(node.kind_id()))
 }
 
@@ -2264,6 +2516,126 @@ mod test {
     use super::*;
     use crate::{Rope, Transaction};
 
+    #[test]
+    fn test_modeline_language() {
+        assert_eq!(modeline_language("// vim: set ft=rust:"), Some("rust"));
+        assert_eq!(modeline_language("# vi: filetype=sh"), Some("sh"));
+        assert_eq!(modeline_language("/* ex: ts=4 syntax=c */"), Some("c"));
+        assert_eq!(
+            modeline_language(";; -*- mode: lisp; coding: utf-8 -*-"),
+            Some("lisp")
+        );
+        assert_eq!(modeline_language("# -*- python -*-"), Some("python"));
+        assert_eq!(modeline_language("# -*- coding: utf-8 -*-"), None);
+        assert_eq!(modeline_language("let regex: ft=rust"), None);
+    }
+
+    #[test]
+    fn test_detect_language() {
+        let language = |name: &str, file_types: &str, shebangs: &str| {
+            toml::from_str::<LanguageConfiguration>(&format!(
+                "name = \"{}\"\nscope = \"source.{}\"\nfile-types = [{}]\nshebangs = [{}]\nroots = []",
+                name, name, file_types, shebangs
+            ))
+            .unwrap()
+        };
+        let loader = Loader::new(Configuration {
+            language: vec![
+                language("bash", "\"sh\"", "\"bash\""),
+                language("python", "\"py\"", "\"python\""),
+                language("xml", "\"xml\"", ""),
+            ],
+        });
+        let detect = |path: Option<&str>, text: &str| {
+            loader
+                .detect_language(path.map(Path::new), &Rope::from(text))
+                .map(|(config, detection)| (config.language_id.clone(), detection))
+        };
+
+        assert_eq!(
+            detect(Some("a.py"), "x = 1\n"),
+            Some(("python".to_string(), LanguageDetection::FileName))
+        );
+        assert_eq!(
+            detect(Some("a.py"), "x = 1\n# vim: ft=sh\n"),
+            Some((
+                "bash".to_string(),
+                LanguageDetection::Modeline("# vim: ft=sh".to_string())
+            ))
+        );
+        assert_eq!(
+            detect(Some("script"), "#!/usr/bin/env python\n"),
+            Some((
+                "python".to_string(),
+                LanguageDetection::Shebang("#!/usr/bin/env python".to_string())
+            ))
+        );
+        assert_eq!(
+            detect(None, "  <?xml version=\"1.0\"?>\n<a/>"),
+            Some(("xml".to_string(), LanguageDetection::Content("XML prolog")))
+        );
+        assert_eq!(detect(Some("notes"), "hello\n"), None);
+    }
+
+    #[test]
+    fn test_language_server_overrides() {
+        let config: LanguageConfiguration = toml::from_str(
+            r#"
+            name = "rust"
+            scope = "source.rust"
+            file-types = ["rs"]
+            roots = []
+            language-server = { command = "rust-analyzer" }
+
+            [[language-server-overrides]]
+            path = "/repo/legacy"
+            command = "rust-analyzer-old"
+
+            [[language-server-overrides]]
+            path = "/repo/legacy/nightly"
+            command = "rust-analyzer-nightly"
+            args = ["--verbose"]
+            "#,
+        )
+        .unwrap();
+
+        let resolve = |path: &str| {
+            config
+                .language_server_for(Some(Path::new(path)))
+                .map(|(ls, dir)| (ls.command.clone(), dir))
+        };
+
+        assert_eq!(
+            resolve("/repo/src/main.rs"),
+            Some(("rust-analyzer".to_string(), None))
+        );
+        assert_eq!(
+            resolve("/repo/legacy/src/lib.rs"),
+            Some((
+                "rust-analyzer-old".to_string(),
+                Some(PathBuf::from("/repo/legacy"))
+            ))
+        );
+        assert_eq!(
+            resolve("/repo/legacy/nightly/src/lib.rs"),
+            Some((
+                "rust-analyzer-nightly".to_string(),
+                Some(PathBuf::from("/repo/legacy/nightly"))
+            ))
+        );
+        // prefix matching is per path component
+        assert_eq!(
+            resolve("/repo/legacy-tools/main.rs"),
+            Some(("rust-analyzer".to_string(), None))
+        );
+        assert_eq!(
+            config
+                .language_server_for(None)
+                .map(|(ls, _)| ls.command.as_str()),
+            Some("rust-analyzer")
+        );
+    }
+
     #[test]
     fn test_textobject_queries() {
         let query_str = r#"
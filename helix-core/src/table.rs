@@ -0,0 +1,561 @@
+//! Plain text tables, either markdown pipe tables or rows of delimiter separated values, and
+//! reflowing them into aligned columns.
+
+use crate::{
+    line_ending::{get_line_ending, line_end_char_index},
+    unicode::width::UnicodeWidthStr,
+    Assoc, Range, Rope, RopeSlice, Selection, Tendril, Transaction, DEFAULT_LINE_ENDING,
+};
+use std::borrow::Cow;
+
+/// The syntax of a table.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TableFormat {
+    /// Markdown pipe tables, the optional delimiter row below the header aligns the columns.
+    Pipe,
+    /// Rows of cells separated by a delimiter as in CSV. Double quoted cells may contain it.
+    Delimited(char),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Alignment {
+    Left,
+    Center,
+    Right,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CellDirection {
+    Next,
+    Previous,
+    Up,
+    Down,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Cell {
+    text: String,
+    /// Char range of the trimmed contents in the document.
+    start: usize,
+    end: usize,
+}
+
+/// A table found around a line of a document.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Table {
+    format: TableFormat,
+    /// First line of the table.
+    pub start: usize,
+    /// Line after the last row.
+    pub end: usize,
+    /// Indentation of the first row, pipe tables may be nested in lists.
+    indent: String,
+    rows: Vec<Vec<Cell>>,
+    /// Alignment of each column, `Some` if the pipe table has a delimiter row.
+    alignments: Option<Vec<Option<Alignment>>>,
+}
+
+fn is_row(line: &str, format: TableFormat) -> bool {
+    match format {
+        TableFormat::Pipe => line.trim_start().starts_with('|'),
+        TableFormat::Delimited(_) => !line.trim().is_empty(),
+    }
+}
+
+/// Splits a row into the char ranges of its cells, relative to the start of the line.
+fn split_row(line: &str, format: TableFormat) -> Vec<(usize, usize)> {
+    let line = line.trim_end_matches(|c| c == '\n' || c == '\r');
+    let chars: Vec<char> = line.chars().collect();
+    let mut bounds = Vec::new();
+    let mut start = 0;
+    let mut quoted = false;
+    let mut escaped = false;
+    for (i, c) in chars.iter().copied().enumerate() {
+        match format {
+            TableFormat::Pipe if escaped => escaped = false,
+            TableFormat::Pipe if c == '\\' => escaped = true,
+            TableFormat::Pipe if c == '|' => {
+                bounds.push((start, i));
+                start = i + 1;
+            }
+            TableFormat::Delimited(_) if c == '"' => quoted = !quoted,
+            TableFormat::Delimited(delimiter) if c == delimiter && !quoted => {
+                bounds.push((start, i));
+                start = i + 1;
+            }
+            _ => (),
+        }
+    }
+    bounds.push((start, chars.len()));
+
+    if format == TableFormat::Pipe {
+        // the text before the leading pipe is indentation
+        bounds.remove(0);
+        // the text after a trailing pipe isn't a cell
+        let trailing = bounds.last().map_or(false, |(start, end)| {
+            chars[*start..*end].iter().all(|c| c.is_whitespace())
+        });
+        if trailing && bounds.len() > 1 {
+            bounds.pop();
+        }
+    }
+
+    bounds
+        .into_iter()
+        .map(|(mut start, mut end)| {
+            while start < end && chars[start].is_whitespace() {
+                start += 1;
+            }
+            while end > start && chars[end - 1].is_whitespace() {
+                end -= 1;
+            }
+            (start, end)
+        })
+        .collect()
+}
+
+fn parse_alignment(cell: &str) -> Option<Option<Alignment>> {
+    let inner = cell.trim_start_matches(':').trim_end_matches(':');
+    if inner.is_empty() || !inner.chars().all(|c| c == '-') || cell.len() - inner.len() > 2 {
+        return None;
+    }
+    Some(match (cell.starts_with(':'), cell.ends_with(':')) {
+        (true, true) => Some(Alignment::Center),
+        (true, false) => Some(Alignment::Left),
+        (false, true) => Some(Alignment::Right),
+        (false, false) => None,
+    })
+}
+
+/// Char offsets of the cells of a reflowed table, relative to its start.
+struct Layout {
+    text: String,
+    cells: Vec<Vec<(usize, usize)>>,
+}
+
+impl Table {
+    /// Finds the table with a row on `line`.
+    pub fn find(text: RopeSlice, line: usize, format: TableFormat) -> Option<Self> {
+        let is_table_row = |line: usize| is_row(&Cow::from(text.line(line)), format);
+        if line >= text.len_lines() || !is_table_row(line) {
+            return None;
+        }
+        let mut start = line;
+        while start > 0 && is_table_row(start - 1) {
+            start -= 1;
+        }
+        let mut end = line + 1;
+        while end < text.len_lines() && is_table_row(end) {
+            end += 1;
+        }
+
+        let first_line = Cow::from(text.line(start));
+        let indent = match format {
+            TableFormat::Pipe => {
+                first_line[..first_line.len() - first_line.trim_start().len()].to_string()
+            }
+            TableFormat::Delimited(_) => String::new(),
+        };
+        let rows: Vec<Vec<Cell>> = (start..end)
+            .map(|line| {
+                let line_start = text.line_to_char(line);
+                let contents = Cow::from(text.line(line));
+                split_row(&contents, format)
+                    .into_iter()
+                    .map(|(start, end)| Cell {
+                        text: text.slice(line_start + start..line_start + end).to_string(),
+                        start: line_start + start,
+                        end: line_start + end,
+                    })
+                    .collect()
+            })
+            .collect();
+
+        let alignments = match format {
+            TableFormat::Pipe => rows.get(1).and_then(|row| {
+                row.iter()
+                    .map(|cell| parse_alignment(&cell.text))
+                    .collect::<Option<Vec<_>>>()
+            }),
+            TableFormat::Delimited(_) => None,
+        };
+
+        Some(Self {
+            format,
+            start,
+            end,
+            indent,
+            rows,
+            alignments,
+        })
+    }
+
+    fn is_delimiter_row(&self, row: usize) -> bool {
+        row == 1 && self.alignments.is_some()
+    }
+
+    fn columns(&self) -> usize {
+        self.rows.iter().map(Vec::len).max().unwrap_or_default()
+    }
+
+    /// The row and column of the cell at `pos`, the closest preceding cell of the row if `pos`
+    /// is between cells.
+    pub fn cell_at(&self, text: RopeSlice, pos: usize) -> Option<(usize, usize)> {
+        let line = text.char_to_line(pos);
+        if !(self.start..self.end).contains(&line) {
+            return None;
+        }
+        let row = line - self.start;
+        let column = self.rows[row]
+            .iter()
+            .rposition(|cell| cell.start <= pos)
+            .unwrap_or_default();
+        Some((row, column))
+    }
+
+    /// Char range of the contents of a cell.
+    pub fn cell_range(&self, row: usize, column: usize) -> Option<(usize, usize)> {
+        let cell = self.rows.get(row)?.get(column)?;
+        Some((cell.start, cell.end))
+    }
+
+    /// The cell next to the cell at `pos`, skipping the delimiter row.
+    pub fn cell_motion(
+        &self,
+        text: RopeSlice,
+        pos: usize,
+        direction: CellDirection,
+    ) -> Option<(usize, usize)> {
+        let (row, column) = self.cell_at(text, pos)?;
+        let rows = (0..self.rows.len()).filter(|row| !self.is_delimiter_row(*row));
+        let cells: Vec<(usize, usize)> = match direction {
+            CellDirection::Next | CellDirection::Previous => rows
+                .flat_map(|row| (0..self.rows[row].len()).map(move |column| (row, column)))
+                .collect(),
+            CellDirection::Up | CellDirection::Down => rows
+                .filter(|row| column < self.rows[*row].len())
+                .map(|row| (row, column))
+                .collect(),
+        };
+        let index = cells.iter().position(|cell| *cell == (row, column));
+        let target = match (direction, index) {
+            (CellDirection::Next | CellDirection::Down, Some(index)) => cells.get(index + 1),
+            (CellDirection::Previous | CellDirection::Up, Some(index)) => {
+                index.checked_sub(1).and_then(|index| cells.get(index))
+            }
+            // on the delimiter row
+            (CellDirection::Next | CellDirection::Down, None) => {
+                cells.iter().find(|(target, _)| *target > row)
+            }
+            (CellDirection::Previous | CellDirection::Up, None) => {
+                cells.iter().rev().find(|(target, _)| *target < row)
+            }
+        }?;
+        self.cell_range(target.0, target.1)
+    }
+
+    /// Alignment of a column, `None` if it isn't specified.
+    pub fn alignment(&self, column: usize) -> Option<Alignment> {
+        self.alignments.as_ref()?.get(column).copied().flatten()
+    }
+
+    /// Sets the alignment of a column, returns `false` if the table has no delimiter row.
+    pub fn set_alignment(&mut self, column: usize, alignment: Option<Alignment>) -> bool {
+        let columns = self.columns();
+        match self.alignments.as_mut() {
+            Some(alignments) => {
+                alignments.resize(columns.max(column + 1), None);
+                alignments[column] = alignment;
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Widths of the columns in display cells.
+    pub fn column_widths(&self) -> Vec<usize> {
+        // delimiter rows need at least three characters
+        let min_width = match self.format {
+            TableFormat::Pipe => 3,
+            TableFormat::Delimited(_) => 1,
+        };
+        let mut widths = vec![min_width; self.columns()];
+        for (row, cells) in self.rows.iter().enumerate() {
+            if self.is_delimiter_row(row) {
+                continue;
+            }
+            for (width, cell) in widths.iter_mut().zip(cells) {
+                *width = (*width).max(cell.text.width());
+            }
+        }
+        widths
+    }
+
+    fn layout(&self, line_ending: &str) -> Layout {
+        let widths = self.column_widths();
+        let mut text = String::new();
+        let mut offset = 0;
+        let mut cells = Vec::with_capacity(self.rows.len());
+        fn push(text: &mut String, offset: &mut usize, s: &str) {
+            text.push_str(s);
+            *offset += s.chars().count();
+        }
+
+        for (row, row_cells) in self.rows.iter().enumerate() {
+            if row > 0 {
+                push(&mut text, &mut offset, line_ending);
+            }
+            let mut positions = Vec::with_capacity(widths.len());
+            push(&mut text, &mut offset, &self.indent);
+            for (column, width) in widths.iter().copied().enumerate() {
+                let contents = if self.is_delimiter_row(row) {
+                    match self.alignment(column) {
+                        Some(Alignment::Left) => format!(":{}", "-".repeat(width - 1)),
+                        Some(Alignment::Center) => format!(":{}:", "-".repeat(width - 2)),
+                        Some(Alignment::Right) => format!("{}:", "-".repeat(width - 1)),
+                        None => "-".repeat(width),
+                    }
+                } else {
+                    row_cells
+                        .get(column)
+                        .map(|cell| cell.text.clone())
+                        .unwrap_or_default()
+                };
+                let padding = width.saturating_sub(contents.width());
+                let (before, after) = match self.alignment(column) {
+                    Some(Alignment::Right) => (padding, 0),
+                    Some(Alignment::Center) => (padding / 2, padding - padding / 2),
+                    _ => (0, padding),
+                };
+
+                match self.format {
+                    TableFormat::Pipe => push(&mut text, &mut offset, "| "),
+                    TableFormat::Delimited(delimiter) if column > 0 => {
+                        push(&mut text, &mut offset, &format!("{} ", delimiter))
+                    }
+                    TableFormat::Delimited(_) => (),
+                }
+                push(&mut text, &mut offset, &" ".repeat(before));
+                let start = offset;
+                push(&mut text, &mut offset, &contents);
+                positions.push((start, offset));
+                // the last cell of delimited rows isn't padded to avoid trailing whitespace
+                let last = column + 1 == widths.len();
+                if !matches!(self.format, TableFormat::Delimited(_) if last) {
+                    push(&mut text, &mut offset, &" ".repeat(after));
+                }
+                if self.format == TableFormat::Pipe {
+                    push(&mut text, &mut offset, " ");
+                }
+            }
+            if self.format == TableFormat::Pipe {
+                push(&mut text, &mut offset, "|");
+            }
+            cells.push(positions);
+        }
+        Layout { text, cells }
+    }
+}
+
+/// Finds the tables containing the cursors of the selection.
+pub fn find_tables(text: RopeSlice, selection: &Selection, format: TableFormat) -> Vec<Table> {
+    let mut tables: Vec<Table> = Vec::new();
+    for range in selection {
+        let line = text.char_to_line(range.cursor(text));
+        if tables
+            .iter()
+            .any(|table| (table.start..table.end).contains(&line))
+        {
+            continue;
+        }
+        tables.extend(Table::find(text, line, format));
+    }
+    tables.sort_unstable_by_key(|table| table.start);
+    tables
+}
+
+/// Reflows the tables into aligned columns, cursors stay in their cells.
+#[must_use]
+pub fn reflow(doc: &Rope, selection: &Selection, tables: &[Table]) -> Transaction {
+    let text = doc.slice(..);
+    let layouts: Vec<(usize, usize, Layout)> = tables
+        .iter()
+        .map(|table| {
+            let line_ending = get_line_ending(&text.line(table.start))
+                .unwrap_or(DEFAULT_LINE_ENDING)
+                .as_str();
+            let start = text.line_to_char(table.start);
+            let end = line_end_char_index(&text, table.end - 1);
+            (start, end, table.layout(line_ending))
+        })
+        .collect();
+
+    let transaction = Transaction::change(
+        doc,
+        layouts
+            .iter()
+            .map(|(start, end, layout)| (*start, *end, Some(Tendril::from(layout.text.as_str())))),
+    );
+
+    let map = |pos: usize| {
+        let mut shift = 0isize;
+        for (table, (start, end, layout)) in tables.iter().zip(&layouts) {
+            if pos >= *start && pos <= *end {
+                let (row, column) = match table.cell_at(text, pos) {
+                    Some(cell) => cell,
+                    None => break,
+                };
+                let cell = table.rows[row].get(column);
+                let offset = cell.map_or(0, |cell| pos.saturating_sub(cell.start));
+                let new_start = (*start as isize + shift) as usize;
+                return match layout.cells[row].get(column) {
+                    Some((cell_start, cell_end)) => {
+                        new_start + (cell_start + offset).min(*cell_end)
+                    }
+                    None => new_start,
+                };
+            }
+            shift += layout.text.chars().count() as isize - (end - start) as isize;
+        }
+        transaction.changes().map_pos(pos, Assoc::After)
+    };
+    let selection = selection
+        .clone()
+        .transform(|range| Range::new(map(range.anchor), map(range.head)));
+    transaction.with_selection(selection)
+}
+
+/// Parses delimiter separated records, unquoting double quoted fields which may span lines.
+/// Returns the line each record starts on along with its fields, blank lines are skipped.
+pub fn parse_records(text: &str, delimiter: char) -> Vec<(usize, Vec<String>)> {
+    let mut records = Vec::new();
+    let mut record = Vec::new();
+    let mut field = String::new();
+    let mut quoted = false;
+    let mut line = 0;
+    let mut record_line = 0;
+    let mut chars = text.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        match c {
+            '"' if quoted && chars.peek() == Some(&'"') => {
+                field.push('"');
+                chars.next();
+            }
+            '"' if quoted => quoted = false,
+            '"' if field.trim().is_empty() => {
+                field.clear();
+                quoted = true;
+            }
+            '\r' if !quoted => (),
+            '\n' if !quoted => {
+                if !record.is_empty() || !field.trim().is_empty() {
+                    record.push(std::mem::take(&mut field));
+                    records.push((record_line, std::mem::take(&mut record)));
+                }
+                field.clear();
+                line += 1;
+                record_line = line;
+            }
+            c if c == delimiter && !quoted => record.push(std::mem::take(&mut field)),
+            c => {
+                if c == '\n' {
+                    line += 1;
+                }
+                field.push(c);
+            }
+        }
+    }
+    if !record.is_empty() || !field.trim().is_empty() {
+        record.push(field);
+        records.push((record_line, record));
+    }
+    records
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn reflow_pipe_table() {
+        let mut doc = Rope::from("text\n|a|b|\n|-|:-:|\n| long cell | c\n\nafter\n");
+        let text = doc.slice(..);
+        let selection = Selection::point(text.line_to_char(1));
+        let tables = find_tables(text, &selection, TableFormat::Pipe);
+        assert_eq!(tables.len(), 1);
+        assert_eq!((tables[0].start, tables[0].end), (1, 4));
+        assert_eq!(tables[0].alignment(1), Some(Alignment::Center));
+
+        let transaction = reflow(&doc, &selection, &tables);
+        transaction.apply(&mut doc);
+        assert_eq!(
+            doc,
+            "text\n\
+            | a         |  b  |\n\
+            | --------- | :-: |\n\
+            | long cell |  c  |\n\
+            \nafter\n"
+        );
+        // the cursor moves to the contents of its cell
+        let cursor = transaction
+            .selection()
+            .unwrap()
+            .primary()
+            .cursor(doc.slice(..));
+        assert_eq!(doc.char(cursor), 'a');
+    }
+
+    #[test]
+    fn reflow_delimited() {
+        let mut doc = Rope::from("name,value\n\"x, y\",1\nz,10");
+        let selection = Selection::point(0);
+        let tables = find_tables(doc.slice(..), &selection, TableFormat::Delimited(','));
+        let transaction = reflow(&doc, &selection, &tables);
+        transaction.apply(&mut doc);
+        assert_eq!(doc, "name  , value\n\"x, y\", 1\nz     , 10");
+    }
+
+    #[test]
+    fn parse_quoted_records() {
+        let records = parse_records("a,b\n\n\"x, \"\"y\"\"\",\"multi\nline\"\r\nlast,", ',');
+        assert_eq!(
+            records,
+            vec![
+                (0, vec!["a".to_string(), "b".to_string()]),
+                (2, vec!["x, \"y\"".to_string(), "multi\nline".to_string()]),
+                (4, vec!["last".to_string(), String::new()]),
+            ]
+        );
+    }
+
+    #[test]
+    fn alignment() {
+        let doc = Rope::from("| a | b |\n|---|---|\n| 1 | 2 |\n");
+        let text = doc.slice(..);
+        let mut table = Table::find(text, 0, TableFormat::Pipe).unwrap();
+        assert_eq!(table.alignment(0), None);
+        assert!(table.set_alignment(0, Some(Alignment::Right)));
+        let layout = table.layout("\n");
+        assert_eq!(layout.text, "|   a | b   |\n| --: | --- |\n|   1 | 2   |");
+
+        let mut table = Table::find(text, 0, TableFormat::Delimited(',')).unwrap();
+        assert!(!table.set_alignment(0, Some(Alignment::Right)));
+    }
+
+    #[test]
+    fn cell_motions() {
+        let doc = Rope::from("| a | b |\n|---|---|\n| c | d |\n");
+        let text = doc.slice(..);
+        let table = Table::find(text, 0, TableFormat::Pipe).unwrap();
+        let a = 2;
+        let b = table.cell_motion(text, a, CellDirection::Next).unwrap();
+        assert_eq!(text.slice(b.0..b.1), "b");
+        let c = table.cell_motion(text, b.0, CellDirection::Next).unwrap();
+        assert_eq!(text.slice(c.0..c.1), "c");
+        let d = table.cell_motion(text, b.0, CellDirection::Down).unwrap();
+        assert_eq!(text.slice(d.0..d.1), "d");
+        assert_eq!(table.cell_motion(text, a, CellDirection::Up), None);
+        assert_eq!(table.cell_motion(text, a, CellDirection::Previous), None);
+    }
+}
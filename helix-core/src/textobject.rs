@@ -8,7 +8,7 @@
 use crate::line_ending::rope_is_line_ending;
 use crate::movement::Direction;
 use crate::surround;
-use crate::syntax::LanguageConfiguration;
+use crate::syntax::{CapturedNode, LanguageConfiguration};
 use crate::Range;
 
 fn find_word_boundary(slice: RopeSlice, mut pos: usize, direction: Direction, long: bool) -> usize {
@@ -276,6 +276,53 @@ pub fn textobject_treesitter(
     get_range().unwrap_or(range)
 }
 
+/// Finds the `inside` object under the cursor of `range` and its neighbour in `dir` among the
+/// objects of the same parent node, e.g. the argument under the cursor and the next argument of
+/// the same call. Returns the char ranges of both, the object under the cursor first.
+pub fn treesitter_object_neighbour(
+    slice: RopeSlice,
+    range: Range,
+    object_name: &str,
+    dir: Direction,
+    slice_tree: Node,
+    lang_config: &LanguageConfiguration,
+) -> Option<(Range, Range)> {
+    let byte_pos = slice.char_to_byte(range.cursor(slice));
+
+    let capture_name = format!("{}.{}", object_name, TextObject::Inside);
+    let mut cursor = QueryCursor::new();
+    let nodes: Vec<_> = lang_config
+        .textobject_query()?
+        .capture_nodes(&capture_name, slice_tree, slice, &mut cursor)?
+        .collect();
+    let parent = |node: &CapturedNode| match node {
+        CapturedNode::Single(node) => node.parent(),
+        CapturedNode::Grouped(nodes) => nodes[0].parent(),
+    };
+
+    let current = nodes
+        .iter()
+        .filter(|node| node.byte_range().contains(&byte_pos))
+        .min_by_key(|node| node.byte_range().len())?;
+    let siblings = nodes.iter().filter(|node| parent(node) == parent(current));
+    let neighbour = match dir {
+        Direction::Forward => siblings
+            .filter(|node| node.start_byte() >= current.end_byte())
+            .min_by_key(|node| node.start_byte())?,
+        Direction::Backward => siblings
+            .filter(|node| node.end_byte() <= current.start_byte())
+            .max_by_key(|node| node.end_byte())?,
+    };
+
+    let to_range = |node: &CapturedNode| {
+        Range::new(
+            slice.byte_to_char(node.start_byte()),
+            slice.byte_to_char(node.end_byte()),
+        )
+    };
+    Some((to_range(current), to_range(neighbour)))
+}
+
 #[cfg(test)]
 mod test {
     use super::TextObject::*;
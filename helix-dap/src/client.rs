@@ -38,6 +38,8 @@ pub struct Client {
     pub thread_id: Option<ThreadId>,
     /// Currently active frame for the current thread.
     pub active_frame: Option<usize>,
+    /// Number of times the debuggee stopped, for views of its state to notice they are stale.
+    pub stops: usize,
     pub quirks: DebuggerQuirks,
 }
 
@@ -83,6 +85,7 @@ pub fn streams(
             thread_states: HashMap::new(),
             thread_id: None,
             active_frame: None,
+            stops: 0,
             quirks: DebuggerQuirks::default(),
         };
 
@@ -207,6 +210,13 @@ pub fn id(&self) -> usize {
         self.id
     }
 
+    /// The active stack frame of the currently selected thread, if the thread is stopped.
+    pub fn current_stack_frame(&self) -> Option<&StackFrame> {
+        self.stack_frames
+            .get(&self.thread_id?)?
+            .get(self.active_frame?)
+    }
+
     fn next_request_id(&self) -> u64 {
         self.request_counter.fetch_add(1, Ordering::Relaxed)
     }
@@ -323,7 +333,7 @@ pub async fn initialize(&mut self, adapter_id: String) -> Result<()> {
             supports_variable_type: Some(true),
             supports_variable_paging: Some(false),
             supports_run_in_terminal_request: Some(true),
-            supports_memory_references: Some(false),
+            supports_memory_references: Some(true),
             supports_progress_reporting: Some(false),
             supports_invalidated_event: Some(false),
         };
@@ -371,6 +381,66 @@ pub async fn set_breakpoints(
         Ok(response.breakpoints)
     }
 
+    pub async fn data_breakpoint_info(
+        &self,
+        name: String,
+        variables_reference: Option<usize>,
+        frame_id: Option<usize>,
+    ) -> Result<requests::DataBreakpointInfoResponse> {
+        let args = requests::DataBreakpointInfoArguments {
+            variables_reference,
+            name,
+            frame_id,
+        };
+
+        self.request::<requests::DataBreakpointInfo>(args).await
+    }
+
+    pub async fn set_data_breakpoints(
+        &self,
+        breakpoints: Vec<DataBreakpoint>,
+    ) -> Result<Vec<Breakpoint>> {
+        let args = requests::SetDataBreakpointsArguments { breakpoints };
+
+        let response = self.request::<requests::SetDataBreakpoints>(args).await?;
+        Ok(response.breakpoints)
+    }
+
+    pub async fn read_memory(
+        &self,
+        memory_reference: String,
+        offset: isize,
+        count: usize,
+    ) -> Result<Option<requests::ReadMemoryResponse>> {
+        let args = requests::ReadMemoryArguments {
+            memory_reference,
+            offset: Some(offset),
+            count,
+        };
+
+        self.request::<requests::ReadMemory>(args).await
+    }
+
+    pub async fn disassemble(
+        &self,
+        memory_reference: String,
+        instruction_offset: isize,
+        instruction_count: usize,
+    ) -> Result<Vec<DisassembledInstruction>> {
+        let args = requests::DisassembleArguments {
+            memory_reference,
+            offset: None,
+            instruction_offset: Some(instruction_offset),
+            instruction_count,
+            resolve_symbols: Some(true),
+        };
+
+        let response = self.request::<requests::Disassemble>(args).await?;
+        Ok(response
+            .map(|response| response.instructions)
+            .unwrap_or_default())
+    }
+
     pub async fn configuration_done(&self) -> Result<()> {
         self.request::<requests::ConfigurationDone>(()).await
     }
@@ -197,6 +197,57 @@ pub struct Breakpoint {
     pub offset: Option<usize>,
 }
 
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum DataBreakpointAccessType {
+    Read,
+    Write,
+    ReadWrite,
+}
+
+impl DataBreakpointAccessType {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Read => "read",
+            Self::Write => "write",
+            Self::ReadWrite => "readWrite",
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Eq, Clone, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DataBreakpoint {
+    pub data_id: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub access_type: Option<DataBreakpointAccessType>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub condition: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub hit_condition: Option<String>,
+}
+
+#[derive(Debug, PartialEq, Eq, Clone, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DisassembledInstruction {
+    pub address: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub instruction_bytes: Option<String>,
+    pub instruction: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub symbol: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub location: Option<Source>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub line: Option<usize>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub column: Option<usize>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub end_line: Option<usize>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub end_column: Option<usize>,
+}
+
 #[derive(Debug, PartialEq, Eq, Clone, Deserialize, Serialize)]
 #[serde(rename_all = "camelCase")]
 pub struct StackFrameFormat {
@@ -682,6 +733,114 @@ impl Request for SetExceptionBreakpoints {
         const COMMAND: &'static str = "setExceptionBreakpoints";
     }
 
+    #[derive(Debug, PartialEq, Eq, Clone, Deserialize, Serialize)]
+    #[serde(rename_all = "camelCase")]
+    pub struct DataBreakpointInfoArguments {
+        #[serde(skip_serializing_if = "Option::is_none")]
+        pub variables_reference: Option<usize>,
+        pub name: String,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        pub frame_id: Option<usize>,
+    }
+
+    #[derive(Debug, PartialEq, Eq, Clone, Deserialize, Serialize)]
+    #[serde(rename_all = "camelCase")]
+    pub struct DataBreakpointInfoResponse {
+        pub data_id: Option<String>,
+        pub description: String,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        pub access_types: Option<Vec<DataBreakpointAccessType>>,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        pub can_persist: Option<bool>,
+    }
+
+    #[derive(Debug)]
+    pub enum DataBreakpointInfo {}
+
+    impl Request for DataBreakpointInfo {
+        type Arguments = DataBreakpointInfoArguments;
+        type Result = DataBreakpointInfoResponse;
+        const COMMAND: &'static str = "dataBreakpointInfo";
+    }
+
+    #[derive(Debug, PartialEq, Eq, Clone, Deserialize, Serialize)]
+    #[serde(rename_all = "camelCase")]
+    pub struct SetDataBreakpointsArguments {
+        pub breakpoints: Vec<DataBreakpoint>,
+    }
+
+    #[derive(Debug, PartialEq, Eq, Clone, Deserialize, Serialize)]
+    #[serde(rename_all = "camelCase")]
+    pub struct SetDataBreakpointsResponse {
+        pub breakpoints: Vec<Breakpoint>,
+    }
+
+    #[derive(Debug)]
+    pub enum SetDataBreakpoints {}
+
+    impl Request for SetDataBreakpoints {
+        type Arguments = SetDataBreakpointsArguments;
+        type Result = SetDataBreakpointsResponse;
+        const COMMAND: &'static str = "setDataBreakpoints";
+    }
+
+    #[derive(Debug, PartialEq, Eq, Clone, Deserialize, Serialize)]
+    #[serde(rename_all = "camelCase")]
+    pub struct ReadMemoryArguments {
+        pub memory_reference: String,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        pub offset: Option<isize>,
+        pub count: usize,
+    }
+
+    #[derive(Debug, PartialEq, Eq, Clone, Deserialize, Serialize)]
+    #[serde(rename_all = "camelCase")]
+    pub struct ReadMemoryResponse {
+        pub address: String,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        pub unreadable_bytes: Option<usize>,
+        /// The bytes read, base64 encoded.
+        #[serde(skip_serializing_if = "Option::is_none")]
+        pub data: Option<String>,
+    }
+
+    #[derive(Debug)]
+    pub enum ReadMemory {}
+
+    impl Request for ReadMemory {
+        type Arguments = ReadMemoryArguments;
+        type Result = Option<ReadMemoryResponse>;
+        const COMMAND: &'static str = "readMemory";
+    }
+
+    #[derive(Debug, PartialEq, Eq, Clone, Deserialize, Serialize)]
+    #[serde(rename_all = "camelCase")]
+    pub struct DisassembleArguments {
+        pub memory_reference: String,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        pub offset: Option<isize>,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        pub instruction_offset: Option<isize>,
+        pub instruction_count: usize,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        pub resolve_symbols: Option<bool>,
+    }
+
+    #[derive(Debug, PartialEq, Eq, Clone, Deserialize, Serialize)]
+    #[serde(rename_all = "camelCase")]
+    pub struct DisassembleResponse {
+        pub instructions: Vec<DisassembledInstruction>,
+    }
+
+    #[derive(Debug)]
+    pub enum Disassemble {}
+
+    impl Request for Disassemble {
+        type Arguments = DisassembleArguments;
+        type Result = Option<DisassembleResponse>;
+        const COMMAND: &'static str = "disassemble";
+    }
+
     // Reverse Requests
 
     #[derive(Debug, Default, PartialEq, Eq, Clone, Deserialize, Serialize)]
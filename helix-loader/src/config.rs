@@ -1,18 +1,55 @@
+use std::path::Path;
+
 /// Default built-in languages.toml.
 pub fn default_lang_config() -> toml::Value {
     toml::from_slice(include_bytes!("../../languages.toml"))
         .expect("Could not parse built-in languages.toml to valid toml")
 }
 
+/// Makes the relative `path` of the language server overrides absolute by joining them to
+/// `root`, so they don't depend on the directory the editor is started in.
+fn resolve_override_paths(config: &mut toml::Value, root: &Path) {
+    let languages = config
+        .get_mut("language")
+        .and_then(toml::Value::as_array_mut)
+        .into_iter()
+        .flatten();
+    for language in languages {
+        let overrides = language
+            .get_mut("language-server-overrides")
+            .and_then(toml::Value::as_array_mut)
+            .into_iter()
+            .flatten();
+        for ls_override in overrides {
+            if let Some(toml::Value::String(path)) = ls_override.get_mut("path") {
+                if Path::new(path.as_str()).is_relative() {
+                    *path = root.join(path.as_str()).to_string_lossy().into_owned();
+                }
+            }
+        }
+    }
+}
+
 /// User configured languages.toml file, merged with the default config.
+///
+/// Relative override paths of a workspace config are resolved against the workspace root, the
+/// ones of the global config against the config directory.
 pub fn user_lang_config() -> Result<toml::Value, toml::de::Error> {
     let config = crate::local_config_dirs()
         .into_iter()
-        .chain([crate::config_dir()].into_iter())
-        .map(|path| path.join("languages.toml"))
-        .filter_map(|file| {
-            std::fs::read(&file)
-                .map(|config| toml::from_slice(&config))
+        .map(|dir| {
+            let root = dir.parent().map_or_else(|| dir.clone(), Path::to_path_buf);
+            (dir, root)
+        })
+        .chain([(crate::config_dir(), crate::config_dir())].into_iter())
+        .filter_map(|(dir, root)| {
+            std::fs::read(dir.join("languages.toml"))
+                .map(|config| {
+                    toml::from_slice(&config).map(|mut config| {
+                        resolve_override_paths(&mut config, &root);
+                        config
+                    })
+                })
                 .ok()
         })
         .collect::<Result<Vec<_>, _>>()?
@@ -40,3 +77,35 @@ pub fn user_lang_config() -> Result<toml::Value, toml::de::Error> {
 
     Ok(config)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn override_paths_are_resolved_against_the_root() {
+        let mut config: toml::Value = toml::from_str(
+            r#"
+            [[language]]
+            name = "rust"
+
+            [[language.language-server-overrides]]
+            path = "tools/legacy"
+            command = "rust-analyzer-old"
+
+            [[language.language-server-overrides]]
+            path = "/opt/nightly"
+            command = "rust-analyzer-nightly"
+            "#,
+        )
+        .unwrap();
+        resolve_override_paths(&mut config, Path::new("/repo"));
+
+        let overrides = config["language"][0]["language-server-overrides"]
+            .as_array()
+            .unwrap();
+        let root = Path::new("/repo").join("tools/legacy");
+        assert_eq!(overrides[0]["path"].as_str(), root.to_str());
+        assert_eq!(overrides[1]["path"].as_str(), Some("/opt/nightly"));
+    }
+}
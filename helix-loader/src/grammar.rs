@@ -186,6 +186,23 @@ pub fn build_grammars(target: Option<String>) -> Result<()> {
     Ok(())
 }
 
+/// Returns the configurations of the grammars with the given ids, or of all selected grammars
+/// if `ids` is empty.
+pub fn grammar_configs(ids: &[String]) -> Result<Vec<GrammarConfiguration>> {
+    let mut grammars = get_grammar_configs()?;
+    if ids.is_empty() {
+        return Ok(grammars);
+    }
+    if let Some(id) = ids
+        .iter()
+        .find(|id| !grammars.iter().any(|grammar| &grammar.grammar_id == *id))
+    {
+        return Err(anyhow!("Unknown grammar {}", id));
+    }
+    grammars.retain(|grammar| ids.contains(&grammar.grammar_id));
+    Ok(grammars)
+}
+
 // Returns the set of grammar configurations the user requests.
 // Grammars are configured in the default and user `languages.toml` and are
 // merged. The `grammar_selection` key of the config is then used to filter
@@ -236,7 +253,7 @@ fn run_parallel<F, Res>(grammars: Vec<GrammarConfiguration>, job: F) -> Vec<Resu
     rx.iter().collect()
 }
 
-enum FetchStatus {
+pub enum FetchStatus {
     GitUpToDate,
     GitUpdated {
         grammar_id: String,
@@ -247,7 +264,7 @@ enum FetchStatus {
     },
 }
 
-fn fetch_grammar(grammar: GrammarConfiguration) -> Result<FetchStatus> {
+pub fn fetch_grammar(grammar: GrammarConfiguration) -> Result<FetchStatus> {
     if let GrammarSource::Git {
         remote, revision, ..
     } = grammar.source
@@ -341,20 +358,78 @@ fn git<I, S>(repository_dir: &Path, args: I) -> Result<String>
     }
 }
 
-enum BuildStatus {
+pub enum BuildStatus {
     AlreadyBuilt,
     Built { grammar_id: String },
 }
 
-fn build_grammar(grammar: GrammarConfiguration, target: Option<&str>) -> Result<BuildStatus> {
-    let grammar_dir = if let GrammarSource::Local { path } = &grammar.source {
+fn grammar_dir(grammar: &GrammarConfiguration) -> PathBuf {
+    if let GrammarSource::Local { path } = &grammar.source {
         PathBuf::from(&path)
     } else {
         crate::runtime_dir()
             .join("grammars")
             .join("sources")
             .join(&grammar.grammar_id)
-    };
+    }
+}
+
+fn src_path(grammar: &GrammarConfiguration, grammar_dir: PathBuf) -> PathBuf {
+    match &grammar.source {
+        GrammarSource::Git {
+            subpath: Some(subpath),
+            ..
+        } => grammar_dir.join(subpath),
+        _ => grammar_dir,
+    }
+    .join("src")
+}
+
+fn library_path(grammar_id: &str) -> PathBuf {
+    let mut library_path = crate::runtime_dir().join("grammars").join(grammar_id);
+    library_path.set_extension(DYLIB_EXTENSION);
+    library_path
+}
+
+fn scanner_path(src_path: &Path) -> Option<PathBuf> {
+    let mut scanner_path = src_path.join("scanner.c");
+    if scanner_path.exists() {
+        return Some(scanner_path);
+    }
+    scanner_path.set_extension("cc");
+    scanner_path.exists().then(|| scanner_path)
+}
+
+/// The state of a grammar on disk.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GrammarState {
+    /// The sources of the grammar are missing.
+    NotFetched,
+    /// The sources were fetched but never built.
+    NotBuilt,
+    /// The sources changed since the library was built.
+    Outdated,
+    Built,
+}
+
+pub fn grammar_state(grammar: &GrammarConfiguration) -> GrammarState {
+    let src_path = src_path(grammar, grammar_dir(grammar));
+    let parser_path = src_path.join("parser.c");
+    if !parser_path.exists() {
+        return GrammarState::NotFetched;
+    }
+    let library_path = library_path(&grammar.grammar_id);
+    if !library_path.exists() {
+        return GrammarState::NotBuilt;
+    }
+    match needs_recompile(&library_path, &parser_path, &scanner_path(&src_path)) {
+        Ok(false) => GrammarState::Built,
+        _ => GrammarState::Outdated,
+    }
+}
+
+pub fn build_grammar(grammar: GrammarConfiguration, target: Option<&str>) -> Result<BuildStatus> {
+    let grammar_dir = grammar_dir(&grammar);
 
     let grammar_dir_entries = grammar_dir.read_dir().with_context(|| {
         format!(
@@ -370,14 +445,7 @@ fn build_grammar(grammar: GrammarConfiguration, target: Option<&str>) -> Result<
         ));
     };
 
-    let path = match &grammar.source {
-        GrammarSource::Git {
-            subpath: Some(subpath),
-            ..
-        } => grammar_dir.join(subpath),
-        _ => grammar_dir,
-    }
-    .join("src");
+    let path = src_path(&grammar, grammar_dir);
 
     build_tree_sitter_library(&path, grammar, target)
 }
@@ -389,21 +457,8 @@ fn build_tree_sitter_library(
 ) -> Result<BuildStatus> {
     let header_path = src_path;
     let parser_path = src_path.join("parser.c");
-    let mut scanner_path = src_path.join("scanner.c");
-
-    let scanner_path = if scanner_path.exists() {
-        Some(scanner_path)
-    } else {
-        scanner_path.set_extension("cc");
-        if scanner_path.exists() {
-            Some(scanner_path)
-        } else {
-            None
-        }
-    };
-    let parser_lib_path = crate::runtime_dir().join("grammars");
-    let mut library_path = parser_lib_path.join(&grammar.grammar_id);
-    library_path.set_extension(DYLIB_EXTENSION);
+    let scanner_path = scanner_path(src_path);
+    let library_path = library_path(&grammar.grammar_id);
 
     let recompile = needs_recompile(&library_path, &parser_path, &scanner_path)
         .context("Failed to compare source and binary timestamps")?;
@@ -25,14 +25,24 @@
     },
 };
 
+/// The `initialize` request with untyped parameters and result, see [`Client::initialize`].
+enum InitializeWithEncodings {}
+
+impl lsp::request::Request for InitializeWithEncodings {
+    type Params = Value;
+    type Result = Value;
+    const METHOD: &'static str = "initialize";
+}
+
 #[derive(Debug)]
 pub struct Client {
     id: usize,
+    name: String,
     _process: Child,
     server_tx: UnboundedSender<Payload>,
     request_counter: AtomicU64,
     pub(crate) capabilities: OnceCell<lsp::ServerCapabilities>,
-    offset_encoding: OffsetEncoding,
+    offset_encoding: OnceCell<OffsetEncoding>,
     config: Option<Value>,
     root_path: std::path::PathBuf,
     root_uri: Option<lsp::Url>,
@@ -53,6 +63,7 @@ pub fn start(
         req_timeout: u64,
         doc_path: Option<&std::path::PathBuf>,
     ) -> Result<(Self, UnboundedReceiver<(usize, Call)>, Arc<Notify>)> {
+        let name = cmd.to_string();
         // Resolve path to the binary
         let cmd = which::which(cmd).map_err(|err| anyhow::anyhow!(err))?;
 
@@ -100,11 +111,12 @@ pub fn start(
 
         let client = Self {
             id,
+            name,
             _process: process,
             server_tx,
             request_counter: AtomicU64::new(0),
             capabilities: OnceCell::new(),
-            offset_encoding: OffsetEncoding::Utf8,
+            offset_encoding: OnceCell::new(),
             config,
             req_timeout,
 
@@ -120,6 +132,11 @@ pub fn id(&self) -> usize {
         self.id
     }
 
+    /// The command the language server was started with.
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
     fn next_request_id(&self) -> jsonrpc::Id {
         let id = self.request_counter.fetch_add(1, Ordering::Relaxed);
         jsonrpc::Id::Num(id)
@@ -146,8 +163,21 @@ pub fn capabilities(&self) -> &lsp::ServerCapabilities {
             .expect("language server not yet initialized!")
     }
 
+    /// The position encoding negotiated on initialization. LSP positions are in UTF-16 code
+    /// units unless the server picked another one.
     pub fn offset_encoding(&self) -> OffsetEncoding {
-        self.offset_encoding
+        self.offset_encoding.get().copied().unwrap_or_default()
+    }
+
+    /// The position encoding the server picked in its `initialize` response.
+    pub(crate) fn negotiated_encoding(result: &Value) -> OffsetEncoding {
+        match result["capabilities"].get("positionEncoding") {
+            Some(encoding) => OffsetEncoding::deserialize(encoding).unwrap_or_else(|_| {
+                log::warn!("unsupported position encoding {}, using utf-16", encoding);
+                OffsetEncoding::default()
+            }),
+            None => OffsetEncoding::default(),
+        }
     }
 
     pub fn config(&self) -> Option<&Value> {
@@ -371,6 +401,9 @@ pub(crate) async fn initialize(&self) -> Result<lsp::InitializeResult> {
                     publish_diagnostics: Some(lsp::PublishDiagnosticsClientCapabilities {
                         ..Default::default()
                     }),
+                    call_hierarchy: Some(lsp::CallHierarchyClientCapabilities {
+                        dynamic_registration: Some(false),
+                    }),
                     ..Default::default()
                 }),
                 window: Some(lsp::WindowClientCapabilities {
@@ -387,7 +420,15 @@ pub(crate) async fn initialize(&self) -> Result<lsp::InitializeResult> {
             locale: None, // TODO
         };
 
-        self.request::<lsp::request::Initialize>(params).await
+        // lsp-types doesn't model the position encodings of LSP 3.17 yet, so they are added to
+        // the serialized capabilities, in order of preference
+        let mut params = serde_json::to_value(params)?;
+        params["capabilities"]["general"]["positionEncodings"] =
+            serde_json::json!(["utf-8", "utf-16"]);
+
+        let result = self.call::<InitializeWithEncodings>(params).await?;
+        let _ = self.offset_encoding.set(Self::negotiated_encoding(&result));
+        Ok(serde_json::from_value(result)?)
     }
 
     pub async fn shutdown(&self) -> Result<()> {
@@ -466,7 +507,11 @@ pub fn changeset_to_changes(
         // Calculation is therefore a bunch trickier.
 
         use helix_core::RopeSlice;
-        fn traverse(pos: lsp::Position, text: RopeSlice) -> lsp::Position {
+        fn traverse(
+            pos: lsp::Position,
+            text: RopeSlice,
+            offset_encoding: OffsetEncoding,
+        ) -> lsp::Position {
             let lsp::Position {
                 mut line,
                 mut character,
@@ -483,7 +528,7 @@ fn traverse(pos: lsp::Position, text: RopeSlice) -> lsp::Position {
                     line += 1;
                     character = 0;
                 } else {
-                    character += ch.len_utf16() as u32;
+                    character += offset_encoding.char_len(ch) as u32;
                 }
             }
             lsp::Position { line, character }
@@ -504,7 +549,7 @@ fn traverse(pos: lsp::Position, text: RopeSlice) -> lsp::Position {
                 }
                 Delete(_) => {
                     let start = pos_to_lsp_pos(new_text, new_pos, offset_encoding);
-                    let end = traverse(start, old_text.slice(old_pos..old_end));
+                    let end = traverse(start, old_text.slice(old_pos..old_end), offset_encoding);
 
                     // deletion
                     changes.push(lsp::TextDocumentContentChangeEvent {
@@ -521,7 +566,8 @@ fn traverse(pos: lsp::Position, text: RopeSlice) -> lsp::Position {
                     // a subsequent delete means a replace, consume it
                     let end = if let Some(Delete(len)) = iter.peek() {
                         old_end = old_pos + len;
-                        let end = traverse(start, old_text.slice(old_pos..old_end));
+                        let end =
+                            traverse(start, old_text.slice(old_pos..old_end), offset_encoding);
 
                         iter.next();
 
@@ -577,7 +623,7 @@ pub fn text_document_did_change(
                 }]
             }
             lsp::TextDocumentSyncKind::INCREMENTAL => {
-                Self::changeset_to_changes(old_text, new_text, changes, self.offset_encoding)
+                Self::changeset_to_changes(old_text, new_text, changes, self.offset_encoding())
             }
             lsp::TextDocumentSyncKind::NONE => return None,
             kind => unimplemented!("{:?}", kind),
@@ -1005,6 +1051,110 @@ pub fn workspace_symbols(&self, query: String) -> Option<impl Future<Output = Re
         Some(self.call::<lsp::request::WorkspaceSymbol>(params))
     }
 
+    fn supports_call_hierarchy(&self) -> bool {
+        let capabilities = self.capabilities.get().unwrap();
+        matches!(
+            capabilities.call_hierarchy_provider,
+            Some(
+                lsp::CallHierarchyServerCapability::Simple(true)
+                    | lsp::CallHierarchyServerCapability::Options(_)
+            )
+        )
+    }
+
+    pub fn prepare_call_hierarchy(
+        &self,
+        text_document: lsp::TextDocumentIdentifier,
+        position: lsp::Position,
+    ) -> Option<impl Future<Output = Result<Value>>> {
+        // Return early if the server does not support call hierarchies.
+        if !self.supports_call_hierarchy() {
+            return None;
+        }
+
+        let params = lsp::CallHierarchyPrepareParams {
+            text_document_position_params: lsp::TextDocumentPositionParams {
+                text_document,
+                position,
+            },
+            work_done_progress_params: lsp::WorkDoneProgressParams::default(),
+        };
+
+        Some(self.call::<lsp::request::CallHierarchyPrepare>(params))
+    }
+
+    pub fn incoming_calls(
+        &self,
+        item: lsp::CallHierarchyItem,
+    ) -> Option<impl Future<Output = Result<Value>>> {
+        if !self.supports_call_hierarchy() {
+            return None;
+        }
+
+        let params = lsp::CallHierarchyIncomingCallsParams {
+            item,
+            work_done_progress_params: lsp::WorkDoneProgressParams::default(),
+            partial_result_params: lsp::PartialResultParams::default(),
+        };
+
+        Some(self.call::<lsp::request::CallHierarchyIncomingCalls>(params))
+    }
+
+    pub fn outgoing_calls(
+        &self,
+        item: lsp::CallHierarchyItem,
+    ) -> Option<impl Future<Output = Result<Value>>> {
+        if !self.supports_call_hierarchy() {
+            return None;
+        }
+
+        let params = lsp::CallHierarchyOutgoingCallsParams {
+            item,
+            work_done_progress_params: lsp::WorkDoneProgressParams::default(),
+            partial_result_params: lsp::PartialResultParams::default(),
+        };
+
+        Some(self.call::<lsp::request::CallHierarchyOutgoingCalls>(params))
+    }
+
+    // lsp-types doesn't know the `typeHierarchyProvider` capability, so servers without type
+    // hierarchies answer these requests with an error.
+    pub fn prepare_type_hierarchy(
+        &self,
+        text_document: lsp::TextDocumentIdentifier,
+        position: lsp::Position,
+    ) -> impl Future<Output = Result<Value>> {
+        let params = lsp::TypeHierarchyPrepareParams {
+            text_document_position_params: lsp::TextDocumentPositionParams {
+                text_document,
+                position,
+            },
+            work_done_progress_params: lsp::WorkDoneProgressParams::default(),
+        };
+
+        self.call::<lsp::request::TypeHierarchyPrepare>(params)
+    }
+
+    pub fn supertypes(&self, item: lsp::TypeHierarchyItem) -> impl Future<Output = Result<Value>> {
+        let params = lsp::TypeHierarchySupertypesParams {
+            item,
+            work_done_progress_params: lsp::WorkDoneProgressParams::default(),
+            partial_result_params: lsp::PartialResultParams::default(),
+        };
+
+        self.call::<lsp::request::TypeHierarchySupertypes>(params)
+    }
+
+    pub fn subtypes(&self, item: lsp::TypeHierarchyItem) -> impl Future<Output = Result<Value>> {
+        let params = lsp::TypeHierarchySubtypesParams {
+            item,
+            work_done_progress_params: lsp::WorkDoneProgressParams::default(),
+            partial_result_params: lsp::PartialResultParams::default(),
+        };
+
+        self.call::<lsp::request::TypeHierarchySubtypes>(params)
+    }
+
     pub fn code_actions(
         &self,
         text_document: lsp::TextDocumentIdentifier,
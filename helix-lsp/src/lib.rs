@@ -26,6 +26,10 @@
 
 pub type Result<T> = core::result::Result<T, Error>;
 type LanguageId = String;
+/// Language servers are keyed by the language scope, the directory of the directory-local
+/// override they were started for and their position in the language's server list
+/// (0 being the primary server).
+type ServerKey = (LanguageId, Option<std::path::PathBuf>, usize);
 
 #[derive(Error, Debug)]
 pub enum Error {
@@ -45,16 +49,32 @@ pub enum Error {
     Other(#[from] anyhow::Error),
 }
 
-#[derive(Clone, Copy, Debug, Serialize, Deserialize)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
 pub enum OffsetEncoding {
     /// UTF-8 code units aka bytes
     #[serde(rename = "utf-8")]
     Utf8,
-    /// UTF-16 code units
+    /// UTF-16 code units, the encoding mandated by the LSP spec unless negotiated otherwise
     #[serde(rename = "utf-16")]
     Utf16,
 }
 
+impl Default for OffsetEncoding {
+    fn default() -> Self {
+        OffsetEncoding::Utf16
+    }
+}
+
+impl OffsetEncoding {
+    /// Returns the number of code units `ch` occupies in this encoding.
+    pub fn char_len(self, ch: char) -> usize {
+        match self {
+            OffsetEncoding::Utf8 => ch.len_utf8(),
+            OffsetEncoding::Utf16 => ch.len_utf16(),
+        }
+    }
+}
+
 pub mod util {
     use super::*;
     use helix_core::{diagnostic::NumberOrString, Range, Rope, Selection, Tendril, Transaction};
@@ -130,13 +150,9 @@ pub fn lsp_pos_to_pos(
 
         match offset_encoding {
             OffsetEncoding::Utf8 => {
-                let line = doc.line_to_char(pos_line);
-                let pos = line.checked_add(pos.character as usize)?;
-                if pos <= doc.len_chars() {
-                    Some(pos)
-                } else {
-                    None
-                }
+                let line_start = doc.line_to_byte(pos_line);
+                let pos = line_start.checked_add(pos.character as usize)?;
+                doc.try_byte_to_char(pos).ok()
             }
             OffsetEncoding::Utf16 => {
                 let line = doc.line_to_char(pos_line);
@@ -158,8 +174,8 @@ pub fn pos_to_lsp_pos(
         match offset_encoding {
             OffsetEncoding::Utf8 => {
                 let line = doc.char_to_line(pos);
-                let line_start = doc.line_to_char(line);
-                let col = pos - line_start;
+                let line_start = doc.line_to_byte(line);
+                let col = doc.char_to_byte(pos) - line_start;
 
                 lsp::Position::new(line as u32, col as u32)
             }
@@ -362,7 +378,7 @@ pub fn parse(method: &str, params: jsonrpc::Params) -> Result<Notification> {
 
 #[derive(Debug)]
 pub struct Registry {
-    inner: HashMap<LanguageId, (usize, Arc<Client>)>,
+    inner: HashMap<ServerKey, (usize, Arc<Client>)>,
 
     counter: AtomicUsize,
     pub incoming: SelectAll<UnboundedReceiverStream<(usize, Call)>>,
@@ -394,63 +410,99 @@ pub fn remove_by_id(&mut self, id: usize) {
         self.inner.retain(|_, (client_id, _)| client_id != &id)
     }
 
+    /// Lists the language servers configured for a document along with their registry keys,
+    /// the primary server first.
+    fn server_configs<'a>(
+        language_config: &'a LanguageConfiguration,
+        doc_path: Option<&std::path::PathBuf>,
+    ) -> Vec<(ServerKey, &'a LanguageServerConfiguration)> {
+        let scope = &language_config.scope;
+        let primary = language_config
+            .language_server_for(doc_path.map(|p| p.as_path()))
+            .map(|(config, override_dir)| ((scope.clone(), override_dir, 0), config));
+        let additional = language_config
+            .additional_language_servers
+            .iter()
+            .enumerate()
+            .map(|(i, config)| ((scope.clone(), None, i + 1), config));
+
+        primary.into_iter().chain(additional).collect()
+    }
+
     pub fn restart(
         &mut self,
         language_config: &LanguageConfiguration,
         doc_path: Option<&std::path::PathBuf>,
-    ) -> Result<Option<Arc<Client>>> {
-        let config = match &language_config.language_server {
-            Some(config) => config,
-            None => return Ok(None),
-        };
-
-        let scope = language_config.scope.clone();
+    ) -> Result<Vec<Arc<Client>>> {
+        let mut clients = Vec::new();
 
-        match self.inner.entry(scope) {
-            Entry::Vacant(_) => Ok(None),
-            Entry::Occupied(mut entry) => {
-                // initialize a new client
-                let id = self.counter.fetch_add(1, Ordering::Relaxed);
+        for (key, config) in Self::server_configs(language_config, doc_path) {
+            match self.inner.entry(key) {
+                Entry::Vacant(_) => (),
+                Entry::Occupied(mut entry) => {
+                    // initialize a new client
+                    let id = self.counter.fetch_add(1, Ordering::Relaxed);
 
-                let NewClientResult(client, incoming) =
-                    start_client(id, language_config, config, doc_path)?;
-                self.incoming.push(UnboundedReceiverStream::new(incoming));
+                    let NewClientResult(client, incoming) =
+                        start_client(id, language_config, config, doc_path)?;
+                    self.incoming.push(UnboundedReceiverStream::new(incoming));
 
-                let (_, old_client) = entry.insert((id, client.clone()));
+                    let (_, old_client) = entry.insert((id, client.clone()));
 
-                tokio::spawn(async move {
-                    let _ = old_client.force_shutdown().await;
-                });
+                    tokio::spawn(async move {
+                        let _ = old_client.force_shutdown().await;
+                    });
 
-                Ok(Some(client))
+                    clients.push(client);
+                }
             }
         }
+
+        Ok(clients)
     }
 
+    /// Returns all language servers for a document, starting those which are not running yet.
+    ///
+    /// A server failing to start doesn't prevent the others from starting; an error is only
+    /// returned if none of the configured servers are available.
     pub fn get(
         &mut self,
         language_config: &LanguageConfiguration,
         doc_path: Option<&std::path::PathBuf>,
-    ) -> Result<Option<Arc<Client>>> {
-        let config = match &language_config.language_server {
-            Some(config) => config,
-            None => return Ok(None),
-        };
-
-        match self.inner.entry(language_config.scope.clone()) {
-            Entry::Occupied(entry) => Ok(Some(entry.get().1.clone())),
-            Entry::Vacant(entry) => {
-                // initialize a new client
-                let id = self.counter.fetch_add(1, Ordering::Relaxed);
-
-                let NewClientResult(client, incoming) =
-                    start_client(id, language_config, config, doc_path)?;
-                self.incoming.push(UnboundedReceiverStream::new(incoming));
-
-                entry.insert((id, client.clone()));
-                Ok(Some(client))
+    ) -> Result<Vec<Arc<Client>>> {
+        let mut clients = Vec::new();
+        let mut error = None;
+
+        for (key, config) in Self::server_configs(language_config, doc_path) {
+            match self.inner.entry(key) {
+                Entry::Occupied(entry) => clients.push(entry.get().1.clone()),
+                Entry::Vacant(entry) => {
+                    // initialize a new client
+                    let id = self.counter.fetch_add(1, Ordering::Relaxed);
+
+                    match start_client(id, language_config, config, doc_path) {
+                        Ok(NewClientResult(client, incoming)) => {
+                            self.incoming.push(UnboundedReceiverStream::new(incoming));
+                            entry.insert((id, client.clone()));
+                            clients.push(client);
+                        }
+                        Err(err) => {
+                            log::error!(
+                                "failed to start language server `{}`: {}",
+                                config.command,
+                                err
+                            );
+                            error = Some(err);
+                        }
+                    }
+                }
             }
         }
+
+        match error {
+            Some(err) if clients.is_empty() => Err(err),
+            _ => Ok(clients),
+        }
     }
 
     pub fn iter_clients(&self) -> impl Iterator<Item = &Arc<Client>> {
@@ -618,4 +670,115 @@ macro_rules! test_case {
         test_case!("test\n\n\n\ncase", (4, 5) => None);
         test_case!("", (u32::MAX, u32::MAX) => None);
     }
+
+    #[test]
+    fn converts_multibyte_lsp_pos_to_pos() {
+        // "ä" is 2 bytes / 1 UTF-16 code unit, "😀" is 4 bytes / 2 UTF-16 code units
+        let doc = Rope::from("ä😀b\n😀c");
+        let utf8 = |line, character| {
+            lsp_pos_to_pos(
+                &doc,
+                lsp::Position::new(line, character),
+                OffsetEncoding::Utf8,
+            )
+        };
+        let utf16 = |line, character| {
+            lsp_pos_to_pos(
+                &doc,
+                lsp::Position::new(line, character),
+                OffsetEncoding::Utf16,
+            )
+        };
+
+        assert_eq!(utf8(0, 0), Some(0));
+        assert_eq!(utf8(0, 2), Some(1));
+        assert_eq!(utf8(0, 6), Some(2));
+        assert_eq!(utf8(1, 4), Some(5));
+        assert_eq!(utf16(0, 1), Some(1));
+        assert_eq!(utf16(0, 3), Some(2));
+        assert_eq!(utf16(1, 2), Some(5));
+        assert_eq!(utf16(1, 3), Some(6));
+    }
+
+    #[test]
+    fn converts_pos_to_lsp_pos_roundtrip() {
+        let doc = Rope::from("ä😀b\n😀c\n");
+        for encoding in [OffsetEncoding::Utf8, OffsetEncoding::Utf16] {
+            for pos in 0..=doc.len_chars() {
+                let lsp_pos = pos_to_lsp_pos(&doc, pos, encoding);
+                assert_eq!(lsp_pos_to_pos(&doc, lsp_pos, encoding), Some(pos));
+            }
+        }
+
+        assert_eq!(
+            pos_to_lsp_pos(&doc, 3, OffsetEncoding::Utf8),
+            lsp::Position::new(0, 7)
+        );
+        assert_eq!(
+            pos_to_lsp_pos(&doc, 3, OffsetEncoding::Utf16),
+            lsp::Position::new(0, 4)
+        );
+        assert_eq!(
+            pos_to_lsp_pos(&doc, 6, OffsetEncoding::Utf16),
+            lsp::Position::new(1, 3)
+        );
+    }
+
+    #[test]
+    fn negotiates_position_encoding() {
+        use serde_json::json;
+
+        let encoding = |capabilities: serde_json::Value| {
+            crate::Client::negotiated_encoding(&json!({ "capabilities": capabilities }))
+        };
+        assert_eq!(encoding(json!({})), OffsetEncoding::Utf16);
+        assert_eq!(
+            encoding(json!({ "positionEncoding": "utf-8" })),
+            OffsetEncoding::Utf8
+        );
+        assert_eq!(
+            encoding(json!({ "positionEncoding": "utf-16" })),
+            OffsetEncoding::Utf16
+        );
+        // not offered, falls back to the default
+        assert_eq!(
+            encoding(json!({ "positionEncoding": "utf-32" })),
+            OffsetEncoding::Utf16
+        );
+    }
+
+    #[test]
+    fn changeset_to_changes_respects_offset_encoding() {
+        use helix_core::Transaction;
+
+        let old = Rope::from("😀ab\n😀c");
+        // replace "b" and delete "c"
+        let transaction =
+            Transaction::change(&old, [(2, 3, Some("x".into())), (5, 6, None)].into_iter());
+        let mut new = old.clone();
+        transaction.apply(&mut new);
+        assert_eq!(new, "😀ax\n😀");
+
+        let changes = |encoding| {
+            crate::Client::changeset_to_changes(&old, &new, transaction.changes(), encoding)
+                .into_iter()
+                .map(|change| change.range.unwrap())
+                .collect::<Vec<_>>()
+        };
+
+        assert_eq!(
+            changes(OffsetEncoding::Utf16),
+            vec![
+                lsp::Range::new(lsp::Position::new(0, 3), lsp::Position::new(0, 4)),
+                lsp::Range::new(lsp::Position::new(1, 2), lsp::Position::new(1, 3)),
+            ]
+        );
+        assert_eq!(
+            changes(OffsetEncoding::Utf8),
+            vec![
+                lsp::Range::new(lsp::Position::new(0, 5), lsp::Position::new(0, 6)),
+                lsp::Range::new(lsp::Position::new(1, 4), lsp::Position::new(1, 5)),
+            ]
+        );
+    }
 }
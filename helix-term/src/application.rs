@@ -13,24 +13,29 @@
     graphics::Rect,
     theme,
     tree::Layout,
-    Align, Editor,
+    Align, DocumentId, Editor,
 };
 use serde_json::json;
 use tui::backend::Backend;
 
 use crate::{
     args::Args,
-    commands::apply_workspace_edit,
+    commands::{self, apply_workspace_edit},
     compositor::{Compositor, Event},
     config::Config,
+    ipc,
     job::Jobs,
     keymap::Keymaps,
     ui::{self, overlay::overlayed},
 };
 
 use log::{debug, error, warn};
+use serde::Deserialize;
 use std::{
+    borrow::Cow,
+    collections::HashSet,
     io::{stdin, stdout, Write},
+    path::PathBuf,
     sync::Arc,
     time::{Duration, Instant},
 };
@@ -83,6 +88,10 @@ pub struct Application {
     jobs: Jobs,
     lsp_progress: LspProgressMap,
     last_render: Instant,
+    /// Binary documents that were already shown in the hex view.
+    hex_viewed: HashSet<DocumentId>,
+    /// The server of `editor.ipc`, if enabled.
+    ipc: Option<ipc::Server>,
 }
 
 #[cfg(feature = "integration")]
@@ -135,10 +144,17 @@ pub fn new(
 
         use helix_view::editor::Action;
 
-        let theme_loader = std::sync::Arc::new(theme::Loader::new(
-            &helix_loader::config_dir(),
-            &helix_loader::runtime_dir(),
-        ));
+        let mut theme_loader =
+            theme::Loader::new(&helix_loader::config_dir(), &helix_loader::runtime_dir());
+        // the terminal is only asked for its colors if they're needed, as it delays the startup
+        #[cfg(not(feature = "integration"))]
+        if config.theme.as_deref() == Some("adaptive") {
+            match crate::terminal_colors::query(crate::terminal_colors::QUERY_TIMEOUT) {
+                Some(colors) => theme_loader = theme_loader.with_terminal_colors(colors),
+                None => log::warn!("the terminal didn't report its colors for the adaptive theme"),
+            }
+        }
+        let theme_loader = std::sync::Arc::new(theme_loader);
 
         let true_color = config.editor.true_color || crate::true_color();
         let theme = config
@@ -176,13 +192,18 @@ pub fn new(
                 &config.editor
             })),
         );
+        editor.tree_keymap = config.load().tree_keys.clone();
 
         let keys = Box::new(Map::new(Arc::clone(&config), |config: &Config| {
             &config.keys
         }));
-        let editor_view = Box::new(ui::EditorView::new(Keymaps::new(keys)));
+        let mut keymaps = Keymaps::new(keys);
+        keymaps.set_language_layers(&config.load().language_keys);
+        let editor_view = Box::new(ui::EditorView::new(keymaps));
         compositor.push(editor_view);
 
+        // the explorer opened with the files given stays unfocused, leaving the cursor in them
+        let focus_panels = args.files.is_empty();
         if args.load_tutor {
             let path = helix_loader::runtime_dir().join("tutor");
             editor.open(&path, Action::VerticalSplit)?;
@@ -246,6 +267,27 @@ pub fn new(
                 .unwrap_or_else(|_| editor.new_file(Action::VerticalSplit));
         }
 
+        if args.explorer || args.outline {
+            let editor_view = compositor
+                .find::<ui::EditorView>()
+                .expect("the editor view was pushed");
+            if args.explorer {
+                match ui::Explorer::new(&editor) {
+                    Ok(mut explorer) => {
+                        // only an embedded explorer is shown without the focus
+                        if !focus_panels && editor.config().explorer.is_embed() {
+                            explorer.unfocus();
+                        }
+                        editor_view.explorer = Some(overlayed(explorer));
+                    }
+                    Err(err) => editor.set_error(format!("Failed to open the explorer: {}", err)),
+                }
+            }
+            if args.outline {
+                editor_view.outline = Some(ui::Outline::new());
+            }
+        }
+
         editor.set_theme(theme);
 
         #[cfg(windows)]
@@ -254,6 +296,24 @@ pub fn new(
         let signals = Signals::new([signal::SIGTSTP, signal::SIGCONT, signal::SIGUSR1])
             .context("build signal handler")?;
 
+        let ipc_config = config.load().editor.ipc.clone();
+        let ipc = if ipc_config.enable {
+            let path = ipc_config.socket.unwrap_or_else(ipc::default_socket_path);
+            match ipc::Server::bind(&path) {
+                Ok(server) => {
+                    // lets tools started from the editor find it
+                    std::env::set_var("HELIX_SOCKET", server.path());
+                    Some(server)
+                }
+                Err(err) => {
+                    editor.set_error(format!("Failed to listen on {}: {}", path.display(), err));
+                    None
+                }
+            }
+        } else {
+            None
+        };
+
         let app = Self {
             compositor,
             terminal,
@@ -268,6 +328,8 @@ pub fn new(
             jobs: Jobs::new(),
             lsp_progress: LspProgressMap::new(),
             last_render: Instant::now(),
+            hex_viewed: HashSet::new(),
+            ipc,
         };
 
         Ok(app)
@@ -282,6 +344,7 @@ async fn render(&mut self) {
             editor: &mut self.editor,
             jobs: &mut self.jobs,
             scroll: None,
+            focus: false,
         };
 
         // Acquire mutable access to the redraw_handle lock
@@ -334,6 +397,11 @@ pub async fn event_loop_until_idle<S>(&mut self, input_stream: &mut S) -> bool
                 return false;
             }
 
+            // the previous event may have opened or focused a binary document
+            if self.open_hex_view() {
+                self.render().await;
+            }
+
             use futures_util::StreamExt;
 
             tokio::select! {
@@ -353,6 +421,11 @@ pub async fn event_loop_until_idle<S>(&mut self, input_stream: &mut S) -> bool
                     self.jobs.handle_callback(&mut self.editor, &mut self.compositor, callback);
                     self.render().await;
                 }
+                Some(request) = ipc::next_request(&mut self.ipc) => {
+                    let result = self.handle_ipc_request(&request.method, request.params.clone());
+                    request.respond(result);
+                    self.render().await;
+                }
                 event = self.editor.wait_event() => {
                     let _idle_handled = self.handle_editor_event(event).await;
 
@@ -374,6 +447,140 @@ pub async fn event_loop_until_idle<S>(&mut self, input_stream: &mut S) -> bool
         }
     }
 
+    /// Opens binary documents in the hex view the first time they are focused, instead of
+    /// showing their bytes as text. Returns whether the hex view was opened.
+    fn open_hex_view(&mut self) -> bool {
+        let doc = doc!(self.editor);
+        if !doc.is_binary() || !self.hex_viewed.insert(doc.id()) {
+            return false;
+        }
+        let path = match doc.path() {
+            Some(path) => path.clone(),
+            None => return false,
+        };
+        match ui::HexView::new(&path) {
+            Ok(hex_view) => self.compositor.push(Box::new(overlayed(hex_view))),
+            Err(e) => self
+                .editor
+                .set_error(format!("Failed to open hex view: {}", e)),
+        }
+        true
+    }
+
+    /// Answers a request of an `editor.ipc` client.
+    fn handle_ipc_request(
+        &mut self,
+        method: &str,
+        params: serde_json::Value,
+    ) -> Result<serde_json::Value, ipc::Error> {
+        use helix_view::editor::Action;
+
+        #[derive(Deserialize)]
+        struct OpenParams {
+            path: PathBuf,
+            /// 1-based, like the positions of the command line.
+            line: Option<usize>,
+            column: Option<usize>,
+        }
+
+        #[derive(Deserialize)]
+        struct CommandParams {
+            name: String,
+            #[serde(default)]
+            args: Vec<String>,
+        }
+
+        match method {
+            "open" => {
+                let params: OpenParams =
+                    serde_json::from_value(params).map_err(ipc::Error::invalid_params)?;
+                let doc_id = self
+                    .editor
+                    .open(&params.path, Action::Replace)
+                    .map_err(ipc::Error::failed)?;
+                if params.line.is_some() {
+                    let pos = helix_core::Position::new(
+                        params.line.unwrap_or(1).saturating_sub(1),
+                        params.column.unwrap_or(1).saturating_sub(1),
+                    );
+                    let (view, doc) = current!(self.editor);
+                    let pos = Selection::point(pos_at_coords(doc.text().slice(..), pos, true));
+                    doc.set_selection(view.id, pos);
+                    align_view(doc, view, Align::Center);
+                }
+                Ok(json!({ "id": doc_id.to_string() }))
+            }
+            "buffers" => {
+                let focused = doc!(self.editor).id();
+                let buffers: Vec<_> = self
+                    .editor
+                    .documents()
+                    .map(|doc| {
+                        json!({
+                            "id": doc.id().to_string(),
+                            "path": doc.path(),
+                            "name": doc.display_name(),
+                            "language": doc.language_name(),
+                            "modified": doc.is_modified(),
+                            "focused": doc.id() == focused,
+                        })
+                    })
+                    .collect();
+                Ok(json!(buffers))
+            }
+            "command" => {
+                let params: CommandParams =
+                    serde_json::from_value(params).map_err(ipc::Error::invalid_params)?;
+                if !self.editor.config().ipc.commands.contains(&params.name) {
+                    return Err(ipc::Error::new(
+                        ipc::NOT_ALLOWED,
+                        format!("`{}` isn't in editor.ipc.commands", params.name),
+                    ));
+                }
+                self.run_ipc_command(&params.name, &params.args)?;
+                Ok(serde_json::Value::Null)
+            }
+            _ => Err(ipc::Error::new(
+                ipc::METHOD_NOT_FOUND,
+                format!("no method named `{}`", method),
+            )),
+        }
+    }
+
+    /// Runs a static command, or a typable command if `name` starts with `:`.
+    fn run_ipc_command(&mut self, name: &str, args: &[String]) -> Result<(), ipc::Error> {
+        let mut cx = crate::compositor::Context {
+            editor: &mut self.editor,
+            jobs: &mut self.jobs,
+            scroll: None,
+            focus: false,
+        };
+        if let Some(name) = name.strip_prefix(':') {
+            let words: Vec<Cow<str>> = std::iter::once(Cow::from(name))
+                .chain(args.iter().map(Cow::from))
+                .collect();
+            return commands::run_typable(&mut cx, &words).map_err(ipc::Error::failed);
+        }
+
+        let command = commands::MappableCommand::STATIC_COMMAND_LIST
+            .iter()
+            .find(|command| command.name() == name)
+            .ok_or_else(|| ipc::Error::invalid_params(format!("no command named `{}`", name)))?;
+        let mut command_cx = commands::Context {
+            register: None,
+            count: None,
+            editor: &mut *cx.editor,
+            callback: None,
+            on_next_key_callback: None,
+            jobs: &mut *cx.jobs,
+        };
+        command.execute(&mut command_cx);
+        if let Some(callback) = command_cx.callback {
+            callback(&mut self.compositor, &mut cx);
+        }
+        Ok(())
+    }
+
     pub fn handle_config_events(&mut self, config_event: ConfigEvent) {
         match config_event {
             ConfigEvent::Refresh => self.refresh_config(),
@@ -418,6 +625,12 @@ fn refresh_config(&mut self) {
         match Config::load_default() {
             Ok(config) => {
                 self.refresh_theme(&config);
+                self.editor.tree_keymap = config.tree_keys.clone();
+                if let Some(editor_view) = self.compositor.find::<ui::EditorView>() {
+                    editor_view
+                        .keymaps
+                        .set_language_layers(&config.language_keys);
+                }
 
                 // Store new config
                 self.config.store(Arc::new(config));
@@ -446,14 +659,33 @@ pub async fn handle_signals(&mut self, signal: i32) {
                     .backend_mut()
                     .show_cursor(CursorKind::Block)
                     .ok();
-                restore_term().unwrap();
+                // hand the shell the terminal as it was before the editor started
+                if let Err(err) = restore_term() {
+                    log::error!("failed to restore the terminal before suspending: {}", err);
+                }
                 low_level::emulate_default_handler(signal::SIGTSTP).unwrap();
             }
             signal::SIGCONT => {
-                self.claim_term().await.unwrap();
-                // redraw the terminal
+                // the shell may have reset raw mode, the alternate screen, bracketed paste, focus
+                // reporting and the mouse capture while the editor was suspended
+                if let Err(err) = self.claim_term().await {
+                    log::error!("failed to reclaim the terminal after resuming: {}", err);
+                }
+                // the terminal may have been resized in the meantime, so all the layers are laid
+                // out and drawn again from scratch
                 let area = self.terminal.size().expect("couldn't get terminal size");
+                self.terminal
+                    .resize(area)
+                    .expect("Unable to resize terminal");
                 self.compositor.resize(area);
+                let mut cx = crate::compositor::Context {
+                    editor: &mut self.editor,
+                    jobs: &mut self.jobs,
+                    scroll: None,
+                    focus: false,
+                };
+                self.compositor
+                    .handle_event(&Event::Resize(area.width, area.height), &mut cx);
                 self.terminal.clear().expect("couldn't clear terminal");
 
                 self.render().await;
@@ -471,6 +703,7 @@ pub async fn handle_idle_timeout(&mut self) {
             editor: &mut self.editor,
             jobs: &mut self.jobs,
             scroll: None,
+            focus: false,
         };
         let should_render = self.compositor.handle_event(&Event::IdleTimeout, &mut cx);
         if should_render || self.editor.needs_redraw {
@@ -531,6 +764,11 @@ pub fn handle_document_write(&mut self, doc_save_event: DocumentSavedEventResult
             let _ = self.editor.refresh_language_server(id);
         }
 
+        // the notes of the file moved with the written edits
+        if let Err(err) = self.editor.persist_notes(doc_save_event.doc_id) {
+            log::error!("failed to save notes: {}", err);
+        }
+
         // TODO: fix being overwritten by lsp
         self.editor.set_status(format!(
             "'{}' written, {}L {}B",
@@ -591,6 +829,7 @@ pub async fn handle_terminal_events(
             editor: &mut self.editor,
             jobs: &mut self.jobs,
             scroll: None,
+            focus: false,
         };
         // Handle key events
         let should_redraw = match event.unwrap() {
@@ -606,6 +845,12 @@ pub async fn handle_terminal_events(
                 self.compositor
                     .handle_event(&Event::Resize(width, height), &mut cx)
             }
+            // input methods on some platforms report the release of the keys committing their
+            // text too, which would insert it twice
+            CrosstermEvent::Key(crossterm::event::KeyEvent {
+                kind: crossterm::event::KeyEventKind::Release,
+                ..
+            }) => false,
             event => self.compositor.handle_event(&event.into(), &mut cx),
         };
 
@@ -652,9 +897,10 @@ pub async fn handle_language_server_message(
                             tokio::spawn(language_server.did_change_configuration(config.clone()));
                         }
 
-                        let docs = self.editor.documents().filter(|doc| {
-                            doc.language_server().map(|server| server.id()) == Some(server_id)
-                        });
+                        let docs = self
+                            .editor
+                            .documents()
+                            .filter(|doc| doc.language_server_ids().contains(&server_id));
 
                         // trigger textDocument/didOpen for docs that are already open
                         for doc in docs {
@@ -674,7 +920,15 @@ pub async fn handle_language_server_message(
                             ));
                         }
                     }
-                    Notification::PublishDiagnostics(mut params) => {
+                    Notification::PublishDiagnostics(params) => {
+                        let offset_encoding =
+                            match self.editor.language_servers.get_by_id(server_id) {
+                                Some(language_server) => language_server.offset_encoding(),
+                                None => {
+                                    warn!("can't find language server with id `{}`", server_id);
+                                    return;
+                                }
+                            };
                         let path = params.uri.to_file_path().unwrap();
                         let doc = self.editor.document_by_path_mut(&path);
 
@@ -689,18 +943,11 @@ pub async fn handle_language_server_message(
                                     use helix_core::diagnostic::{Diagnostic, Range, Severity::*};
                                     use lsp::DiagnosticSeverity;
 
-                                    let language_server = if let Some(language_server) = doc.language_server() {
-                                        language_server
-                                    } else {
-                                        log::warn!("Discarding diagnostic because language server is not initialized: {:?}", diagnostic);
-                                        return None;
-                                    };
-
                                     // TODO: convert inside server
                                     let start = if let Some(start) = lsp_pos_to_pos(
                                         text,
                                         diagnostic.range.start,
-                                        language_server.offset_encoding(),
+                                        offset_encoding,
                                     ) {
                                         start
                                     } else {
@@ -708,11 +955,9 @@ pub async fn handle_language_server_message(
                                         return None;
                                     };
 
-                                    let end = if let Some(end) = lsp_pos_to_pos(
-                                        text,
-                                        diagnostic.range.end,
-                                        language_server.offset_encoding(),
-                                    ) {
+                                    let end = if let Some(end) =
+                                        lsp_pos_to_pos(text, diagnostic.range.end, offset_encoding)
+                                    {
                                         end
                                     } else {
                                         log::warn!("lsp position out of bounds - {:?}", diagnostic);
@@ -752,13 +997,18 @@ pub async fn handle_language_server_message(
                                     };
 
                                     let tags = if let Some(ref tags) = diagnostic.tags {
-                                        let new_tags = tags.iter().filter_map(|tag| {
-                                            match *tag {
-                                                lsp::DiagnosticTag::DEPRECATED => Some(DiagnosticTag::Deprecated),
-                                                lsp::DiagnosticTag::UNNECESSARY => Some(DiagnosticTag::Unnecessary),
-                                                _ => None
-                                            }
-                                        }).collect();
+                                        let new_tags = tags
+                                            .iter()
+                                            .filter_map(|tag| match *tag {
+                                                lsp::DiagnosticTag::DEPRECATED => {
+                                                    Some(DiagnosticTag::Deprecated)
+                                                }
+                                                lsp::DiagnosticTag::UNNECESSARY => {
+                                                    Some(DiagnosticTag::Unnecessary)
+                                                }
+                                                _ => None,
+                                            })
+                                            .collect();
 
                                         new_tags
                                     } else {
@@ -774,25 +1024,26 @@ pub async fn handle_language_server_message(
                                         tags,
                                         source: diagnostic.source.clone(),
                                         data: diagnostic.data.clone(),
+                                        language_server_id: Some(server_id),
                                     })
                                 })
                                 .collect();
 
-                            doc.set_diagnostics(diagnostics);
+                            doc.replace_diagnostics(diagnostics, server_id);
                         }
 
-                        // Sort diagnostics first by severity and then by line numbers.
-                        // Note: The `lsp::DiagnosticSeverity` enum is already defined in decreasing order
-                        params
-                            .diagnostics
-                            .sort_unstable_by_key(|d| (d.severity, d.range.start));
-
                         // Insert the original lsp::Diagnostics here because we may have no open document
                         // for diagnosic message and so we can't calculate the exact position.
                         // When using them later in the diagnostics picker, we calculate them on-demand.
-                        self.editor
-                            .diagnostics
-                            .insert(params.uri, params.diagnostics);
+                        // Diagnostics of other language servers for the same file are kept.
+                        let diagnostics = self.editor.diagnostics.entry(params.uri).or_default();
+                        diagnostics.retain(|(_, id)| *id != Some(server_id));
+                        diagnostics
+                            .extend(params.diagnostics.into_iter().map(|d| (d, Some(server_id))));
+
+                        // Sort diagnostics first by severity and then by line numbers.
+                        // Note: The `lsp::DiagnosticSeverity` enum is already defined in decreasing order
+                        diagnostics.sort_unstable_by_key(|(d, _)| (d.severity, d.range.start));
                     }
                     Notification::ShowMessage(params) => {
                         log::warn!("unhandled window/showMessage: {:?}", params);
@@ -889,24 +1140,15 @@ pub async fn handle_language_server_message(
                     Notification::Exit => {
                         self.editor.set_status("Language server exited");
 
-                        // Clear any diagnostics for documents with this server open.
-                        let urls: Vec<_> = self
-                            .editor
-                            .documents_mut()
-                            .filter_map(|doc| {
-                                if doc.language_server().map(|server| server.id())
-                                    == Some(server_id)
-                                {
-                                    doc.set_diagnostics(Vec::new());
-                                    doc.url()
-                                } else {
-                                    None
-                                }
-                            })
-                            .collect();
+                        // Clear any diagnostics reported by this server.
+                        for doc in self.editor.documents_mut() {
+                            if doc.language_server_ids().contains(&server_id) {
+                                doc.replace_diagnostics(Vec::new(), server_id);
+                            }
+                        }
 
-                        for url in urls {
-                            self.editor.diagnostics.remove(&url);
+                        for diagnostics in self.editor.diagnostics.values_mut() {
+                            diagnostics.retain(|(_, id)| *id != Some(server_id));
                         }
 
                         // Remove the language server from the registry.
@@ -949,11 +1191,13 @@ pub async fn handle_language_server_message(
                         Ok(serde_json::Value::Null)
                     }
                     MethodCall::ApplyWorkspaceEdit(params) => {
-                        apply_workspace_edit(
-                            &mut self.editor,
-                            helix_lsp::OffsetEncoding::Utf8,
-                            &params.edit,
-                        );
+                        let offset_encoding = self
+                            .editor
+                            .language_servers
+                            .get_by_id(server_id)
+                            .map(|language_server| language_server.offset_encoding())
+                            .unwrap_or_default();
+                        apply_workspace_edit(&mut self.editor, offset_encoding, &params.edit);
 
                         Ok(json!(lsp::ApplyWorkspaceEditResponse {
                             applied: true,
@@ -1073,6 +1317,9 @@ pub async fn close(&mut self) -> Vec<anyhow::Error> {
         //        errors along the way
         let mut errs = Vec::new();
 
+        // removes the socket
+        self.ipc.take();
+
         if let Err(err) = self
             .jobs
             .finish(&mut self.editor, Some(&mut self.compositor))
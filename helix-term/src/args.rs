@@ -1,3 +1,4 @@
+use crate::headless::Step;
 use anyhow::Result;
 use helix_core::Position;
 use helix_view::tree::Layout;
@@ -17,6 +18,12 @@ pub struct Args {
     pub log_file: Option<PathBuf>,
     pub config_file: Option<PathBuf>,
     pub files: Vec<(PathBuf, Position)>,
+    pub headless: bool,
+    pub headless_steps: Vec<Step>,
+    /// Whether to open the file explorer at startup.
+    pub explorer: bool,
+    /// Whether to open the outline panel at startup.
+    pub outline: bool,
 }
 
 impl Args {
@@ -26,6 +33,9 @@ pub fn parse_args() -> Result<Args> {
 
         argv.next(); // skip the program, we don't care about that
 
+        // the position given with `+<line>[:<col>]`, for the file after it
+        let mut position = None;
+
         while let Some(arg) = argv.next() {
             match arg.as_str() {
                 "--" => break, // stop parsing at this point treat the remaining as files
@@ -59,6 +69,17 @@ pub fn parse_args() -> Result<Args> {
                     Some(path) => args.log_file = Some(path.into()),
                     None => anyhow::bail!("--log must specify a path to write"),
                 },
+                "--headless" => args.headless = true,
+                "--command" => match argv.next() {
+                    Some(command) => args.headless_steps.push(Step::Command(command)),
+                    None => anyhow::bail!("--command must specify a command to run"),
+                },
+                "--keys" => match argv.next() {
+                    Some(keys) => args.headless_steps.push(Step::Keys(keys)),
+                    None => anyhow::bail!("--keys must specify the keys to type"),
+                },
+                "--explorer" => args.explorer = true,
+                "--outline" => args.outline = true,
                 arg if arg.starts_with("--") => {
                     anyhow::bail!("unexpected double dash argument: {}", arg)
                 }
@@ -73,17 +94,36 @@ pub fn parse_args() -> Result<Args> {
                         }
                     }
                 }
-                arg => args.files.push(parse_file(arg)),
+                arg if arg.starts_with('+') && !Path::new(arg).exists() => {
+                    match parse_position(&arg[1..]) {
+                        Some(pos) => position = Some(pos),
+                        None => anyhow::bail!("expected +<line>[:<col>], found {}", arg),
+                    }
+                }
+                arg => args.push_file(arg, position.take()),
             }
         }
 
         // push the remaining args, if any to the files
         for arg in argv {
-            args.files.push(parse_file(&arg));
+            args.push_file(&arg, position.take());
+        }
+        if position.is_some() {
+            anyhow::bail!("+<line>[:<col>] must be followed by a file");
+        }
+
+        if !args.headless_steps.is_empty() && !args.headless {
+            anyhow::bail!("--command and --keys need --headless");
         }
 
         Ok(args)
     }
+
+    /// Adds a file, at `position` if given instead of a position after its path.
+    fn push_file(&mut self, arg: &str, position: Option<Position>) {
+        let (path, pos) = parse_file(arg);
+        self.files.push((path, position.unwrap_or(pos)));
+    }
 }
 
 /// Parse arg into [`PathBuf`] and position.
@@ -97,6 +137,16 @@ pub(crate) fn parse_file(s: &str) -> (PathBuf, Position) {
         .unwrap_or_else(def)
 }
 
+/// Parses the `10` and `10:2` of `+10` and `+10:2` into a position.
+fn parse_position(s: &str) -> Option<Position> {
+    let (row, col) = match s.split_once(':') {
+        Some((row, col)) => (row, col.parse::<usize>().ok()?),
+        None => (s, 1),
+    };
+    let row: usize = row.parse().ok()?;
+    Some(Position::new(row.saturating_sub(1), col.saturating_sub(1)))
+}
+
 /// Split file.rs:10:2 into [`PathBuf`], row and col.
 ///
 /// Does not validate if file.rs is a file or directory.
@@ -9,19 +9,26 @@
 pub use typed::*;
 
 use helix_core::{
-    comment, coords_at_pos, encoding, find_first_non_whitespace_char, find_root, graphemes,
+    comment, coords_at_pos,
+    diagnostic::Severity,
+    encoding, find_first_non_whitespace_char, find_root, graphemes,
     history::UndoKind,
     increment::date_time::DateTimeIncrementor,
     increment::{number::NumberIncrementor, Increment},
     indent,
     indent::IndentStyle,
     line_ending::{get_line_ending_of_str, line_end_char_index, str_is_line_ending},
+    log_file::LogPatterns,
     match_brackets,
     movement::{self, Direction},
-    object, pos_at_coords, pos_at_visual_coords,
-    regex::{self, Regex, RegexBuilder},
+    object,
+    outline::{self, OutlineSyntax},
+    pos_at_coords, pos_at_visual_coords,
+    regex::{self, Regex},
     search::{self, CharMatcher},
-    selection, shellwords, surround, textobject,
+    selection, shellwords, structural, surround,
+    table::{self, Alignment, CellDirection, Table, TableFormat},
+    textobject,
     tree_sitter::Node,
     unicode::width::UnicodeWidthChar,
     visual_coords_at_pos, LineEnding, Position, Range, Rope, RopeGraphemes, RopeSlice, Selection,
@@ -172,13 +179,32 @@ pub fn execute(&self, cx: &mut Context) {
                         editor: cx.editor,
                         jobs: cx.jobs,
                         scroll: None,
+                        focus: false,
                     };
-                    if let Err(e) = (command.fun)(&mut cx, &args[..], PromptEvent::Validate) {
-                        cx.editor.set_error(format!("{}", e));
+                    match (command.fun)(&mut cx, &args[..], PromptEvent::Validate) {
+                        Ok(()) => {
+                            let line = std::iter::once(name.as_str())
+                                .chain(args.iter().map(|arg| arg.as_ref()))
+                                .collect::<Vec<_>>()
+                                .join(" ");
+                            cx.editor.record_command(format!(":{}", line));
+                        }
+                        Err(e) => cx.editor.set_error(format!("{}", e)),
                     }
                 }
             }
-            Self::Static { fun, .. } => (fun)(cx),
+            Self::Static { fun, name, .. } => {
+                let had_callback = cx.callback.is_some();
+                let jobs = cx.jobs.futures.len();
+                (fun)(cx);
+                // only commands opening a panel, picker or prompt are worth repeating, they open
+                // it with a callback or once a background job completes
+                let opened =
+                    (!had_callback && cx.callback.is_some()) || cx.jobs.futures.len() > jobs;
+                if opened && !UNRECORDED_COMMANDS.contains(name) {
+                    cx.editor.record_command(name.to_string());
+                }
+            }
         }
     }
 
@@ -250,6 +276,10 @@ pub fn doc(&self) -> &str {
         search_prev, "Select previous search match",
         extend_search_next, "Add next search match to selection",
         extend_search_prev, "Add previous search match to selection",
+        toggle_search_smart_case, "Toggle smart case search",
+        toggle_search_whole_word, "Toggle whole word search",
+        toggle_rainbow_brackets, "Toggle rainbow brackets in the current view",
+        search_history_picker, "Open search history picker",
         search_selection, "Use current selection as search pattern",
         make_search_word_bounded, "Modify current search to make it word bounded",
         global_search, "Global search in workspace folder",
@@ -270,10 +300,16 @@ pub fn doc(&self) -> &str {
         command_mode, "Enter command mode",
         file_picker, "Open file picker",
         file_picker_in_current_directory, "Open file picker at current working directory",
+        recent_file_picker, "Open picker of recently opened files",
+        recent_project_picker, "Open picker of recent workspaces",
         code_action, "Perform code action",
         buffer_picker, "Open buffer picker",
         jumplist_picker, "Open jumplist picker",
         symbol_picker, "Open symbol picker",
+        tree_symbol_picker, "Open symbol picker as a tree",
+        tree_workspace_symbol_picker, "Open workspace symbols as a tree grouped by file",
+        outline, "Open or focus the document outline",
+        panel_search, "Search the items of the open panels and the diagnostics",
         select_references_to_symbol_under_cursor, "Select symbol references",
         workspace_symbol_picker, "Open workspace symbol picker",
         diagnostics_picker, "Open diagnostic picker",
@@ -309,6 +345,10 @@ pub fn doc(&self) -> &str {
         goto_last_diag, "Goto last diagnostic",
         goto_next_diag, "Goto next diagnostic",
         goto_prev_diag, "Goto previous diagnostic",
+        goto_next_error, "Goto next error diagnostic",
+        goto_prev_error, "Goto previous error diagnostic",
+        goto_next_warning, "Goto next warning diagnostic",
+        goto_prev_warning, "Goto previous warning diagnostic",
         goto_next_change, "Goto next change",
         goto_prev_change, "Goto previous change",
         goto_first_change, "Goto first change",
@@ -353,6 +393,34 @@ pub fn doc(&self) -> &str {
         paste_primary_clipboard_before, "Paste primary clipboard before selections",
         indent, "Indent selection",
         unindent, "Unindent selection",
+        promote_heading, "Promote heading of markup section",
+        demote_heading, "Demote heading of markup section",
+        move_section_up, "Move markup section above previous sibling",
+        move_section_down, "Move markup section below next sibling",
+        fold_headings_to_level, "Show markup headings up to count levels in the outline",
+        table_reflow, "Align the columns of the table",
+        table_align_column, "Cycle the alignment of the table column",
+        table_next_cell, "Select next table cell",
+        table_prev_cell, "Select previous table cell",
+        table_cell_above, "Select table cell above",
+        table_cell_below, "Select table cell below",
+        table_view, "Open delimited rows in a sortable table view",
+        data_tree, "Open structure of JSON/YAML/TOML data",
+        log_view, "Open buffer in log view",
+        hex_view, "Open file in hex editor",
+        notes_tree, "Open the notes of the project",
+        yank_history, "Open the yank history",
+        pin_file, "Pin the current file to the first free slot",
+        pinned_files, "Open the pinned files",
+        goto_pin_1, "Go to the file pinned to slot 1",
+        goto_pin_2, "Go to the file pinned to slot 2",
+        goto_pin_3, "Go to the file pinned to slot 3",
+        goto_pin_4, "Go to the file pinned to slot 4",
+        goto_pin_5, "Go to the file pinned to slot 5",
+        goto_pin_6, "Go to the file pinned to slot 6",
+        goto_pin_7, "Go to the file pinned to slot 7",
+        goto_pin_8, "Go to the file pinned to slot 8",
+        goto_pin_9, "Go to the file pinned to slot 9",
         format_selections, "Format selection",
         join_selections, "Join lines inside selection",
         join_selections_space, "Join lines inside selection and select spaces",
@@ -372,9 +440,15 @@ pub fn doc(&self) -> &str {
         shrink_selection, "Shrink selection to previously expanded syntax node",
         select_next_sibling, "Select next sibling in syntax tree",
         select_prev_sibling, "Select previous sibling in syntax tree",
+        raise_syntax_node, "Replace parent syntax node with the selected node",
+        splice_syntax_node, "Remove the delimiters of the enclosing list",
+        slurp_syntax_node, "Move the closing delimiter of the enclosing list past the next node",
+        barf_syntax_node, "Move the closing delimiter of the enclosing list before its last node",
         jump_forward, "Jump forward on jumplist",
         jump_backward, "Jump backward on jumplist",
         save_selection, "Save current selection to jumplist",
+        peek_back, "Return to the location before the last peek",
+        peek_confirm, "Add the locations left by peeks to the jumplist",
         jump_view_right, "Jump to right split",
         jump_view_left, "Jump to left split",
         jump_view_up, "Jump to split above",
@@ -384,6 +458,10 @@ pub fn doc(&self) -> &str {
         swap_view_up, "Swap with split above",
         swap_view_down, "Swap with split below",
         transpose_view, "Transpose splits",
+        rotate_splits, "Rotate splits forward",
+        rotate_splits_reverse, "Rotate splits backward",
+        zoom_view, "Zoom current window or restore the layout",
+        zen_mode, "Toggle distraction-free zen mode",
         rotate_view, "Goto next window",
         hsplit, "Horizontal bottom split",
         hsplit_new, "Horizontal bottom split scratch buffer",
@@ -411,6 +489,8 @@ pub fn doc(&self) -> &str {
         goto_prev_class, "Goto previous type definition",
         goto_next_parameter, "Goto next parameter",
         goto_prev_parameter, "Goto previous parameter",
+        swap_next_parameter, "Swap parameter with the next one",
+        swap_prev_parameter, "Swap parameter with the previous one",
         goto_next_comment, "Goto next comment",
         goto_prev_comment, "Goto previous comment",
         goto_next_test, "Goto next test",
@@ -427,9 +507,17 @@ pub fn doc(&self) -> &str {
         dap_variables, "List variables",
         dap_terminate, "End debug session",
         dap_edit_condition, "Edit breakpoint condition on current line",
+        dap_edit_hit_condition, "Edit breakpoint hit condition on current line",
         dap_edit_log, "Edit breakpoint log message on current line",
+        dap_toggle_data_breakpoint, "Toggle data breakpoint on expression",
+        dap_breakpoints, "Manage breakpoints",
+        dap_console, "Open or focus debug console",
+        dap_watches, "Manage watch expressions",
+        dap_memory, "Show memory of expression",
+        dap_disassemble, "Show disassembly of current stack frame",
         dap_switch_thread, "Switch current thread",
         dap_switch_stack_frame, "Switch stack frame",
+        dap_call_stack, "Show threads and call stacks",
         dap_enable_exceptions, "Enable exception breakpoints",
         dap_disable_exceptions, "Disable exception breakpoints",
         shell_pipe, "Pipe selections through shell command",
@@ -439,11 +527,15 @@ pub fn doc(&self) -> &str {
         shell_keep_pipe, "Filter selections with shell predicate",
         suspend, "Suspend and return to shell",
         rename_symbol, "Rename symbol",
+        queue_rename_symbol, "Stage a symbol rename in the refactoring queue",
+        edit_queue, "Open the refactoring queue",
         increment, "Increment item under cursor",
         decrement, "Decrement item under cursor",
         record_macro, "Record macro",
         replay_macro, "Replay macro",
         command_palette, "Open command pallete",
+        command_history, "Open the history of run commands",
+        repeat_last_command, "Repeat the last command of the command history",
         toggle_or_focus_explorer, "toggle or focus explorer",
         open_explorer_recursion, "open explorer recursion",
         close_explorer, "close explorer",
@@ -464,6 +556,15 @@ fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
     }
 }
 
+/// Commands which are not recorded in the command history since they only lead to other
+/// commands.
+const UNRECORDED_COMMANDS: &[&str] = &[
+    "command_mode",
+    "command_palette",
+    "command_history",
+    "repeat_last_command",
+];
+
 impl std::str::FromStr for MappableCommand {
     type Err = anyhow::Error;
 
@@ -1711,7 +1812,7 @@ fn searcher(cx: &mut Context, direction: Direction) {
     let contents = doc.text().slice(..).to_string();
     let completions = search_completions(cx, Some(reg));
 
-    ui::regex_prompt(
+    ui::search_prompt(
         cx,
         "search:".into(),
         Some(reg),
@@ -1736,33 +1837,50 @@ fn searcher(cx: &mut Context, direction: Direction) {
                 wrap_around,
                 false,
             );
+            update_search_matches(editor, &contents, &regex);
         },
     );
 }
 
+/// Records the matches of the search being typed for the prompt and the view to show.
+fn update_search_matches(editor: &mut Editor, contents: &str, regex: &Regex) {
+    let (view, doc) = current_ref!(editor);
+    let text = doc.text().slice(..);
+    let ranges: Vec<_> = regex
+        .find_iter(contents)
+        .filter(|mat| mat.end() > mat.start())
+        .map(|mat| text.byte_to_char(mat.start())..text.byte_to_char(mat.end()))
+        .collect();
+    let primary = doc.selection(view.id).primary();
+    let current = ranges
+        .iter()
+        .position(|range| range.start == primary.from() && range.end == primary.to());
+    editor.search_matches = Some(helix_view::editor::SearchMatches {
+        doc: doc.id(),
+        ranges,
+        current,
+    });
+}
+
 fn search_next_or_prev_impl(cx: &mut Context, movement: Movement, direction: Direction) {
     let count = cx.count();
-    let config = cx.editor.config();
+    search_register(cx.editor, count, movement, direction);
+}
+
+/// Searches for the last query in the search register `count` times.
+fn search_register(editor: &mut Editor, count: usize, movement: Movement, direction: Direction) {
+    let config = editor.config();
     let scrolloff = config.scrolloff;
-    let (_, doc) = current!(cx.editor);
-    let registers = &cx.editor.registers;
+    let options = editor.search_options;
+    let (_, doc) = current!(editor);
+    let registers = &editor.registers;
     if let Some(query) = registers.read('/').and_then(|query| query.last()) {
         let contents = doc.text().slice(..).to_string();
-        let search_config = &config.search;
-        let case_insensitive = if search_config.smart_case {
-            !query.chars().any(char::is_uppercase)
-        } else {
-            false
-        };
-        let wrap_around = search_config.wrap_around;
-        if let Ok(regex) = RegexBuilder::new(query)
-            .case_insensitive(case_insensitive)
-            .multi_line(true)
-            .build()
-        {
+        let wrap_around = config.search.wrap_around;
+        if let Ok(regex) = ui::search_regex(query, options.smart_case, options.whole_word) {
             for _ in 0..count {
                 search_impl(
-                    cx.editor,
+                    editor,
                     &contents,
                     &regex,
                     movement,
@@ -1774,11 +1892,79 @@ fn search_next_or_prev_impl(cx: &mut Context, movement: Movement, direction: Dir
             }
         } else {
             let error = format!("Invalid regex: {}", query);
-            cx.editor.set_error(error);
+            editor.set_error(error);
         }
     }
 }
 
+fn toggle_search_smart_case(cx: &mut Context) {
+    let options = &mut cx.editor.search_options;
+    options.smart_case = !options.smart_case;
+    let status = if options.smart_case {
+        "Smart case enabled"
+    } else {
+        "Smart case disabled"
+    };
+    cx.editor.set_status(status);
+}
+
+fn toggle_search_whole_word(cx: &mut Context) {
+    let options = &mut cx.editor.search_options;
+    options.whole_word = !options.whole_word;
+    let status = if options.whole_word {
+        "Whole word search enabled"
+    } else {
+        "Whole word search disabled"
+    };
+    cx.editor.set_status(status);
+}
+
+fn toggle_rainbow_brackets(cx: &mut Context) {
+    let enable = {
+        let config = cx.editor.config();
+        let view = view!(cx.editor);
+        !view.local_options.apply(&config).rainbow_brackets.enable
+    };
+    view_mut!(cx.editor).local_options.rainbow_brackets = Some(enable);
+    let status = if enable {
+        "Rainbow brackets enabled"
+    } else {
+        "Rainbow brackets disabled"
+    };
+    cx.editor.set_status(status);
+}
+
+fn search_history_picker(cx: &mut Context) {
+    struct SearchQuery(String);
+
+    impl ui::menu::Item for SearchQuery {
+        type Data = ();
+
+        fn label(&self, _data: &Self::Data) -> Spans {
+            self.0.as_str().into()
+        }
+    }
+
+    let reg = cx.register.unwrap_or('/');
+    // most recent first
+    let mut queries: Vec<SearchQuery> = Vec::new();
+    for query in cx.editor.registers.read(reg).unwrap_or(&[]).iter().rev() {
+        if !queries.iter().any(|seen| &seen.0 == query) {
+            queries.push(SearchQuery(query.clone()));
+        }
+    }
+    if queries.is_empty() {
+        cx.editor.set_status("The search history is empty");
+        return;
+    }
+
+    let picker = ui::Picker::new(queries, (), |cx, query: &SearchQuery, _action| {
+        cx.editor.registers.push('/', query.0.clone());
+        search_register(cx.editor, 1, Movement::Move, Direction::Forward);
+    });
+    cx.push_layer(Box::new(overlayed(picker)));
+}
+
 fn search_next(cx: &mut Context) {
     search_next_or_prev_impl(cx, Movement::Move, Direction::Forward);
 }
@@ -1879,13 +2065,12 @@ fn label(&self, current_path: &Self::Data) -> Spans {
 
     let (all_matches_sx, all_matches_rx) = tokio::sync::mpsc::unbounded_channel::<FileResult>();
     let config = cx.editor.config();
-    let smart_case = config.search.smart_case;
     let file_picker_config = config.file_picker.clone();
 
     let reg = cx.register.unwrap_or('/');
 
     let completions = search_completions(cx, Some(reg));
-    ui::regex_prompt(
+    ui::search_prompt(
         cx,
         "global-search:".into(),
         Some(reg),
@@ -1896,13 +2081,14 @@ fn label(&self, current_path: &Self::Data) -> Spans {
                 .map(|comp| (0.., std::borrow::Cow::Owned(comp.clone())))
                 .collect()
         },
-        move |_editor, regex, event| {
+        move |editor, regex, event| {
             if event != PromptEvent::Validate {
                 return;
             }
 
+            // whole words are already matched by the regex
             if let Ok(matcher) = RegexMatcherBuilder::new()
-                .case_smart(smart_case)
+                .case_smart(editor.search_options.smart_case)
                 .build(regex.as_str())
             {
                 let searcher = SearcherBuilder::new()
@@ -2299,13 +2485,83 @@ fn file_picker_in_current_directory(cx: &mut Context) {
     cx.push_layer(Box::new(overlayed(picker)));
 }
 
+fn recent_file_picker(cx: &mut Context) {
+    let cwd = std::env::current_dir().unwrap_or_else(|_| PathBuf::from("./"));
+    let files: Vec<PathBuf> = cx
+        .editor
+        .recent
+        .files()
+        .iter()
+        .filter(|path| path.exists())
+        .cloned()
+        .collect();
+    let picker = FilePicker::new(
+        files,
+        cwd,
+        |cx, path: &PathBuf, action| {
+            if let Err(e) = cx.editor.open(path, action) {
+                cx.editor
+                    .set_error(format!("Failed to open {}: {}", path.display(), e));
+            }
+        },
+        |_editor, path| Some((path.clone().into(), None)),
+    );
+    cx.push_layer(Box::new(overlayed(picker)));
+}
+
+/// Switches the working directory to a recent workspace and opens the files that were open in
+/// it.
+fn recent_project_picker(cx: &mut Context) {
+    impl ui::menu::Item for helix_view::recent::Workspace {
+        type Data = ();
+
+        fn label(&self, _data: &Self::Data) -> Spans {
+            let root = helix_core::path::fold_home_dir(&self.root);
+            format!("{} ({} files)", root.display(), self.files.len()).into()
+        }
+
+        fn filter_text(&self, _data: &Self::Data) -> Cow<str> {
+            self.root.to_string_lossy()
+        }
+    }
+
+    let workspaces: Vec<_> = cx
+        .editor
+        .recent
+        .workspaces()
+        .iter()
+        .filter(|workspace| workspace.root.is_dir())
+        .cloned()
+        .collect();
+    let picker = Picker::new(workspaces, (), |cx, workspace, _action| {
+        if let Err(e) = std::env::set_current_dir(&workspace.root) {
+            cx.editor.set_error(format!(
+                "Couldn't change the current working directory: {}",
+                e
+            ));
+            return;
+        }
+        // open the file opened last at the end so that it is focused
+        for path in workspace.files.iter().rev().filter(|path| path.exists()) {
+            if let Err(e) = cx.editor.open(path, Action::Replace) {
+                log::error!("failed to open {}: {}", path.display(), e);
+            }
+        }
+        cx.editor.set_status(format!(
+            "Current working directory is now {}",
+            workspace.root.display()
+        ));
+    });
+    cx.push_layer(Box::new(overlayed(picker)));
+}
+
 fn toggle_or_focus_explorer(cx: &mut Context) {
     cx.callback = Some(Box::new(
         |compositor: &mut Compositor, cx: &mut compositor::Context| {
             if let Some(editor) = compositor.find::<ui::EditorView>() {
                 match editor.explorer.as_mut() {
                     Some(explore) => explore.content.focus(),
-                    None => match ui::Explorer::new(cx) {
+                    None => match ui::Explorer::new(cx.editor) {
                         Ok(explore) => editor.explorer = Some(overlayed(explore)),
                         Err(err) => cx.editor.set_error(format!("{}", err)),
                     },
@@ -2319,7 +2575,7 @@ fn open_explorer_recursion(cx: &mut Context) {
     cx.callback = Some(Box::new(
         |compositor: &mut Compositor, cx: &mut compositor::Context| {
             if let Some(editor) = compositor.find::<ui::EditorView>() {
-                match ui::Explorer::new_explorer_recursion() {
+                match ui::Explorer::new_explorer_recursion(cx.editor) {
                     Ok(explore) => editor.explorer = Some(overlayed(explore)),
                     Err(err) => cx.editor.set_error(format!("{}", err)),
                 }
@@ -2336,6 +2592,113 @@ fn close_explorer(cx: &mut Context) {
     }));
 }
 
+/// Searches the entries of the open explorer, the symbols of the open outline and the
+/// diagnostics, focusing the panel of the picked item at it. The diagnostics open if they aren't
+/// open yet.
+fn panel_search(cx: &mut Context) {
+    #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+    enum SearchPanel {
+        Explorer,
+        Outline,
+        Diagnostics,
+    }
+
+    struct PanelEntry {
+        panel: SearchPanel,
+        label: String,
+        /// The node id of the item in its panel.
+        id: u64,
+    }
+
+    impl ui::menu::Item for PanelEntry {
+        type Data = ();
+
+        fn label(&self, _data: &Self::Data) -> Spans {
+            let panel = match self.panel {
+                SearchPanel::Explorer => "explorer",
+                SearchPanel::Outline => "outline",
+                SearchPanel::Diagnostics => "diagnostics",
+            };
+            format!("{:<12} {}", panel, self.label).into()
+        }
+    }
+
+    fn focus_entry(editor: &mut Editor, compositor: &mut Compositor, panel: SearchPanel, id: u64) {
+        let revealed = match panel {
+            SearchPanel::Explorer => compositor
+                .find::<ui::EditorView>()
+                .and_then(|editor_view| editor_view.explorer.as_mut())
+                .map(|explorer| {
+                    explorer.content.focus();
+                    explorer.content.reveal(id)
+                }),
+            SearchPanel::Outline => compositor
+                .find::<ui::EditorView>()
+                .and_then(|editor_view| editor_view.outline.as_mut())
+                .map(|outline| {
+                    outline.focus();
+                    outline.reveal(id)
+                }),
+            SearchPanel::Diagnostics => {
+                match compositor.find::<ui::overlay::Overlay<ui::DiagnosticsTree>>() {
+                    Some(diagnostics) => Some(diagnostics.content.reveal(id)),
+                    None => {
+                        let mut diagnostics = ui::DiagnosticsTree::new(editor);
+                        let revealed = diagnostics.reveal(id);
+                        compositor.push(Box::new(overlayed(diagnostics)));
+                        Some(revealed)
+                    }
+                }
+            }
+        };
+        if revealed != Some(true) {
+            editor.set_error("The item is no longer in its panel");
+        }
+    }
+
+    cx.callback = Some(Box::new(
+        |compositor: &mut Compositor, cx: &mut compositor::Context| {
+            let mut entries = Vec::new();
+            let mut push_entries = |panel, panel_entries: Vec<(String, u64)>| {
+                entries.extend(panel_entries.into_iter().map(|(label, id)| PanelEntry {
+                    panel,
+                    label,
+                    id,
+                }))
+            };
+            if let Some(editor_view) = compositor.find::<ui::EditorView>() {
+                if let Some(explorer) = &editor_view.explorer {
+                    push_entries(SearchPanel::Explorer, explorer.content.search_entries());
+                }
+                if let Some(outline) = &editor_view.outline {
+                    push_entries(SearchPanel::Outline, outline.search_entries());
+                }
+            }
+            push_entries(
+                SearchPanel::Diagnostics,
+                ui::DiagnosticsTree::search_entries(cx.editor),
+            );
+            if entries.is_empty() {
+                cx.editor.set_status("No panel items to search");
+                return;
+            }
+
+            let picker = Picker::new(entries, (), |cx, entry, _action| {
+                let (panel, id) = (entry.panel, entry.id);
+                let callback = async move {
+                    let call: job::Callback =
+                        Callback::EditorCompositor(Box::new(move |editor, compositor| {
+                            focus_entry(editor, compositor, panel, id)
+                        }));
+                    Ok(call)
+                };
+                cx.jobs.callback(callback);
+            });
+            compositor.push(Box::new(overlayed(picker)));
+        },
+    ));
+}
+
 fn buffer_picker(cx: &mut Context) {
     let current = view!(cx.editor).doc;
 
@@ -2520,9 +2883,12 @@ fn label(&self, keymap: &Self::Data) -> Spans {
 pub fn command_palette(cx: &mut Context) {
     cx.callback = Some(Box::new(
         move |compositor: &mut Compositor, cx: &mut compositor::Context| {
-            let keymap = compositor.find::<ui::EditorView>().unwrap().keymaps.map()
-                [&cx.editor.mode]
-                .reverse_map();
+            // includes the layers of the focused buffer
+            let keymaps = &mut compositor.find::<ui::EditorView>().unwrap().keymaps;
+            keymaps.set_focus(doc!(cx.editor), |doc_id| {
+                cx.editor.document(doc_id).is_some()
+            });
+            let keymap = keymaps.effective_keymap(cx.editor.mode).reverse_map();
 
             let mut commands: Vec<MappableCommand> = MappableCommand::STATIC_COMMAND_LIST.into();
             commands.extend(typed::TYPABLE_COMMAND_LIST.iter().map(|cmd| {
@@ -2549,6 +2915,58 @@ pub fn command_palette(cx: &mut Context) {
     ));
 }
 
+/// Runs an entry of the command history, the name of a static command or `:` followed by a
+/// typable command line.
+pub(crate) fn run_history_entry(cx: &mut Context, entry: &str) {
+    let line = match entry.strip_prefix(':') {
+        Some(line) => line,
+        None => match entry.parse::<MappableCommand>() {
+            Ok(command) => return command.execute(cx),
+            Err(e) => return cx.editor.set_error(e.to_string()),
+        },
+    };
+    let mut cx = compositor::Context {
+        editor: cx.editor,
+        jobs: cx.jobs,
+        scroll: None,
+        focus: false,
+    };
+    match run_typable_line(&mut cx, line) {
+        Ok(()) => cx.editor.record_command(entry.to_string()),
+        Err(e) => cx.editor.set_error(format!("{}", e)),
+    }
+}
+
+/// Runs a line of command mode such as `open 'a b.rs'`, with its quotes and escapes resolved
+/// the way the prompt resolves them.
+pub(crate) fn run_typable_line(cx: &mut compositor::Context, line: &str) -> anyhow::Result<()> {
+    let shellwords = shellwords::Shellwords::from(line);
+    run_typable(cx, shellwords.words())
+}
+
+/// Runs the typable command named by the first of `words`, passing it the rest.
+pub(crate) fn run_typable(cx: &mut compositor::Context, words: &[Cow<str>]) -> anyhow::Result<()> {
+    let name = words.first().map(|name| name.as_ref()).unwrap_or_default();
+    let command = typed::TYPABLE_COMMAND_MAP
+        .get(name)
+        .ok_or_else(|| anyhow!("no such command: '{}'", name))?;
+    (command.fun)(cx, &words[1..], PromptEvent::Validate)
+}
+
+fn repeat_last_command(cx: &mut Context) {
+    match cx.editor.command_history.last().cloned() {
+        Some(entry) => run_history_entry(cx, &entry),
+        None => cx.editor.set_status("No command to repeat"),
+    }
+}
+
+fn command_history(cx: &mut Context) {
+    match ui::CommandHistory::new(cx.editor) {
+        Some(history) => cx.push_layer(Box::new(overlayed(history))),
+        None => cx.editor.set_status("No commands were run yet"),
+    }
+}
+
 fn last_picker(cx: &mut Context) {
     // TODO: last picker does not seem to work well with buffer_picker
     cx.callback = Some(Box::new(|compositor, cx| {
@@ -2734,6 +3152,10 @@ fn normal_mode(cx: &mut Context) {
         doc.set_selection(view.id, selection);
         doc.restore_cursor = false;
     }
+
+    if let Some(format) = doc.table_mode {
+        reflow_tables(doc, view, format);
+    }
 }
 
 fn try_restore_indent(doc: &mut Document, view: &mut View) {
@@ -2911,29 +3333,32 @@ fn goto_last_diag(cx: &mut Context) {
 }
 
 fn goto_next_diag(cx: &mut Context) {
-    let editor = &mut cx.editor;
-    let (view, doc) = current!(editor);
+    goto_diag_impl(cx, Direction::Forward, None)
+}
 
-    let cursor_pos = doc
-        .selection(view.id)
-        .primary()
-        .cursor(doc.text().slice(..));
+fn goto_prev_diag(cx: &mut Context) {
+    goto_diag_impl(cx, Direction::Backward, None)
+}
 
-    let diag = doc
-        .diagnostics()
-        .iter()
-        .find(|diag| diag.range.start > cursor_pos)
-        .or_else(|| doc.diagnostics().first());
+fn goto_next_error(cx: &mut Context) {
+    goto_diag_impl(cx, Direction::Forward, Some(Severity::Error))
+}
 
-    let pos = match diag {
-        Some(diag) => diag.range.start,
-        None => return,
-    };
+fn goto_prev_error(cx: &mut Context) {
+    goto_diag_impl(cx, Direction::Backward, Some(Severity::Error))
+}
 
-    goto_pos(editor, pos);
+fn goto_next_warning(cx: &mut Context) {
+    goto_diag_impl(cx, Direction::Forward, Some(Severity::Warning))
 }
 
-fn goto_prev_diag(cx: &mut Context) {
+fn goto_prev_warning(cx: &mut Context) {
+    goto_diag_impl(cx, Direction::Backward, Some(Severity::Warning))
+}
+
+/// Moves to the next or previous diagnostic with `severity`, or with any severity if it is
+/// `None`, wrapping around the ends of the document.
+fn goto_diag_impl(cx: &mut Context, direction: Direction, severity: Option<Severity>) {
     let editor = &mut cx.editor;
     let (view, doc) = current!(editor);
 
@@ -2942,19 +3367,48 @@ fn goto_prev_diag(cx: &mut Context) {
         .primary()
         .cursor(doc.text().slice(..));
 
-    let diag = doc
+    let diagnostics: Vec<_> = doc
         .diagnostics()
         .iter()
-        .rev()
-        .find(|diag| diag.range.start < cursor_pos)
-        .or_else(|| doc.diagnostics().last());
+        .filter(|diag| severity.map_or(true, |severity| diag.effective_severity() == severity))
+        .collect();
 
-    let pos = match diag {
-        Some(diag) => diag.range.start,
-        None => return,
+    let diag = match direction {
+        Direction::Forward => diagnostics
+            .iter()
+            .find(|diag| diag.range.start > cursor_pos),
+        Direction::Backward => diagnostics
+            .iter()
+            .rev()
+            .find(|diag| diag.range.start < cursor_pos),
+    };
+    let (pos, wrapped) = match diag {
+        Some(diag) => (diag.range.start, false),
+        None => {
+            let diag = match direction {
+                Direction::Forward => diagnostics.first(),
+                Direction::Backward => diagnostics.last(),
+            };
+            match diag {
+                Some(diag) => (diag.range.start, true),
+                None => return,
+            }
+        }
     };
 
     goto_pos(editor, pos);
+    if wrapped && diagnostics.len() > 1 {
+        let name = match severity {
+            Some(Severity::Error) => "error",
+            Some(Severity::Warning) => "warning",
+            _ => "diagnostic",
+        };
+        let end = match direction {
+            Direction::Forward => "first",
+            Direction::Backward => "last",
+        };
+        editor.set_status(format!("Wrapped around to the {} {}", end, name));
+    }
 }
 
 fn goto_first_change(cx: &mut Context) {
@@ -3097,23 +3551,22 @@ fn language_server_completion(cx: &mut Context, ch: char) {
         use helix_lsp::lsp;
         // if ch matches completion char, trigger completion
         let doc = doc_mut!(cx.editor);
-        let language_server = match doc.language_server() {
-            Some(language_server) => language_server,
-            None => return,
-        };
-
-        let capabilities = language_server.capabilities();
-
-        if let Some(lsp::CompletionOptions {
-            trigger_characters: Some(triggers),
-            ..
-        }) = &capabilities.completion_provider
-        {
-            // TODO: what if trigger is multiple chars long
-            if triggers.iter().any(|trigger| trigger.contains(ch)) {
-                cx.editor.clear_idle_timer();
-                super::completion(cx);
+        let is_trigger = doc.language_servers().any(|language_server| {
+            match &language_server.capabilities().completion_provider {
+                Some(lsp::CompletionOptions {
+                    trigger_characters: Some(triggers),
+                    ..
+                }) => {
+                    // TODO: what if trigger is multiple chars long
+                    triggers.iter().any(|trigger| trigger.contains(ch))
+                }
+                _ => false,
             }
+        });
+
+        if is_trigger {
+            cx.editor.clear_idle_timer();
+            super::completion(cx);
         }
     }
 
@@ -3124,23 +3577,22 @@ fn signature_help(cx: &mut Context, ch: char) {
         // The language_server!() macro is not used here since it will
         // print an "LSP not active for current buffer" message on
         // every keypress.
-        let language_server = match doc.language_server() {
-            Some(language_server) => language_server,
-            None => return,
-        };
-
-        let capabilities = language_server.capabilities();
-
-        if let lsp::ServerCapabilities {
-            signature_help_provider:
-                Some(lsp::SignatureHelpOptions {
-                    trigger_characters: Some(triggers),
-                    // TODO: retrigger_characters
+        let triggers = doc.language_servers().find_map(|language_server| {
+            match language_server.capabilities() {
+                lsp::ServerCapabilities {
+                    signature_help_provider:
+                        Some(lsp::SignatureHelpOptions {
+                            trigger_characters: Some(triggers),
+                            // TODO: retrigger_characters
+                            ..
+                        }),
                     ..
-                }),
-            ..
-        } = capabilities
-        {
+                } => Some(triggers),
+                _ => None,
+            }
+        });
+
+        if let Some(triggers) = triggers {
             // TODO: what if trigger is multiple chars long
             let is_trigger = triggers.iter().any(|trigger| trigger.contains(ch));
             // lsp doesn't tell us when to close the signature help, so we request
@@ -3174,7 +3626,7 @@ pub fn insert_char(cx: &mut Context, c: char) {
 
         let transaction = auto_pairs
             .as_ref()
-            .and_then(|ap| auto_pairs::hook(text, selection, c, ap))
+            .and_then(|ap| auto_pairs::hook(text, selection, c, ap, doc.syntax()))
             .or_else(|| insert(text, selection, c));
 
         let (view, doc) = current!(cx.editor);
@@ -3527,6 +3979,7 @@ fn yank(cx: &mut Context) {
         cx.register.unwrap_or('"')
     );
 
+    cx.editor.record_yank(values.clone());
     cx.editor
         .registers
         .write(cx.register.unwrap_or('"'), values);
@@ -3566,6 +4019,7 @@ fn yank_joined_to_clipboard_impl(
         .clipboard_provider
         .set_contents(joined, clipboard_type)
         .context("Couldn't set system clipboard content")?;
+    editor.record_yank(values);
 
     editor.set_status(msg);
 
@@ -3591,14 +4045,15 @@ fn yank_main_selection_to_clipboard_impl(
         ClipboardType::Selection => "yanked main selection to primary clipboard",
     };
 
-    let value = doc.selection(view.id).primary().fragment(text);
+    let value = doc.selection(view.id).primary().fragment(text).into_owned();
 
     if let Err(e) = editor
         .clipboard_provider
-        .set_contents(value.into_owned(), clipboard_type)
+        .set_contents(value.clone(), clipboard_type)
     {
         bail!("Couldn't set system clipboard content: {}", e);
     }
+    editor.record_yank(vec![value]);
 
     editor.set_status(message_text);
     Ok(())
@@ -3720,14 +4175,23 @@ fn paste_clipboard_impl(
     clipboard_type: ClipboardType,
     count: usize,
 ) -> anyhow::Result<()> {
+    let contents = editor
+        .clipboard_provider
+        .get_contents(clipboard_type)
+        .context("Couldn't get system clipboard contents")?;
+    editor.record_yank(vec![contents.clone()]);
     let (view, doc) = current!(editor);
-    match editor.clipboard_provider.get_contents(clipboard_type) {
-        Ok(contents) => {
-            paste_impl(&[contents], doc, view, action, count, editor.mode);
-            Ok(())
-        }
-        Err(e) => Err(e.context("Couldn't get system clipboard contents")),
-    }
+    paste_impl(&[contents], doc, view, action, count, editor.mode);
+    Ok(())
+}
+
+/// Pastes an entry of the yank history into the current document.
+pub(crate) fn paste_values(editor: &mut Editor, values: &[String], before: bool) {
+    let action = if before { Paste::Before } else { Paste::After };
+    let mode = editor.mode;
+    let (view, doc) = current!(editor);
+    paste_impl(values, doc, view, action, 1, mode);
+    doc.append_changes_to_history(view);
 }
 
 fn paste_clipboard_after(cx: &mut Context) {
@@ -3804,24 +4268,25 @@ fn replace_selections_with_clipboard_impl(
     clipboard_type: ClipboardType,
 ) -> anyhow::Result<()> {
     let count = cx.count();
-    let (view, doc) = current!(cx.editor);
+    let contents = cx
+        .editor
+        .clipboard_provider
+        .get_contents(clipboard_type)
+        .context("Couldn't get system clipboard contents")?;
+    cx.editor.record_yank(vec![contents.clone()]);
 
-    match cx.editor.clipboard_provider.get_contents(clipboard_type) {
-        Ok(contents) => {
-            let selection = doc.selection(view.id);
-            let transaction = Transaction::change_by_selection(doc.text(), selection, |range| {
-                (
-                    range.from(),
-                    range.to(),
-                    Some(contents.repeat(count).as_str().into()),
-                )
-            });
+    let (view, doc) = current!(cx.editor);
+    let selection = doc.selection(view.id);
+    let transaction = Transaction::change_by_selection(doc.text(), selection, |range| {
+        (
+            range.from(),
+            range.to(),
+            Some(contents.repeat(count).as_str().into()),
+        )
+    });
 
-            apply_transaction(&transaction, doc, view);
-            doc.append_changes_to_history(view);
-        }
-        Err(e) => return Err(e.context("Couldn't get system clipboard contents")),
-    }
+    apply_transaction(&transaction, doc, view);
+    doc.append_changes_to_history(view);
 
     exit_select_mode(cx);
     Ok(())
@@ -3931,49 +4396,383 @@ fn unindent(cx: &mut Context) {
     apply_transaction(&transaction, doc, view);
 }
 
-fn format_selections(cx: &mut Context) {
-    use helix_lsp::{lsp, util::range_to_lsp_range};
+fn outline_syntax(editor: &mut Editor) -> Option<OutlineSyntax> {
+    let syntax = doc!(editor)
+        .language_name()
+        .and_then(OutlineSyntax::from_language_name);
+    if syntax.is_none() {
+        editor.set_error("Document has no heading outline");
+    }
+    syntax
+}
 
+/// Updates the outline panel to the edited headings right away rather than on idle.
+fn refresh_outline(cx: &mut Context) {
+    cx.callback = Some(Box::new(
+        |compositor: &mut Compositor, cx: &mut compositor::Context| {
+            if let Some(outline) = compositor
+                .find::<ui::EditorView>()
+                .and_then(|editor_view| editor_view.outline.as_mut())
+            {
+                outline.refresh(cx.editor, cx.jobs);
+            }
+        },
+    ));
+}
+
+fn change_heading_level(cx: &mut Context, delta: isize) {
+    let syntax = match outline_syntax(cx.editor) {
+        Some(syntax) => syntax,
+        None => return,
+    };
     let (view, doc) = current!(cx.editor);
+    let transaction = outline::change_level(doc.text(), doc.selection(view.id), syntax, delta);
+    apply_transaction(&transaction, doc, view);
+    refresh_outline(cx);
+}
 
-    // via lsp if available
-    // TODO: else via tree-sitter indentation calculations
+fn promote_heading(cx: &mut Context) {
+    let count = cx.count() as isize;
+    change_heading_level(cx, -count)
+}
 
-    let language_server = match doc.language_server() {
-        Some(language_server) => language_server,
+fn demote_heading(cx: &mut Context) {
+    let count = cx.count() as isize;
+    change_heading_level(cx, count)
+}
+
+fn move_section(cx: &mut Context, down: bool) {
+    let syntax = match outline_syntax(cx.editor) {
+        Some(syntax) => syntax,
         None => return,
     };
+    let count = cx.count();
+    let (view, doc) = current!(cx.editor);
+    for _ in 0..count {
+        let transaction =
+            match outline::move_section(doc.text(), doc.selection(view.id), syntax, down) {
+                Some(transaction) => transaction,
+                None => break,
+            };
+        apply_transaction(&transaction, doc, view);
+    }
+    refresh_outline(cx);
+}
 
-    let ranges: Vec<lsp::Range> = doc
-        .selection(view.id)
-        .iter()
-        .map(|range| range_to_lsp_range(doc.text(), *range, language_server.offset_encoding()))
-        .collect();
+fn move_section_up(cx: &mut Context) {
+    move_section(cx, false)
+}
+
+fn move_section_down(cx: &mut Context) {
+    move_section(cx, true)
+}
+
+/// Folds the headings in the outline panel to the count of levels, opening the panel if needed.
+/// The document itself stays unfolded, as views can't hide lines.
+fn fold_headings_to_level(cx: &mut Context) {
+    if outline_syntax(cx.editor).is_none() {
+        return;
+    }
+    let depth = cx.count() - 1;
+    cx.callback = Some(Box::new(
+        move |compositor: &mut Compositor, cx: &mut compositor::Context| {
+            if let Some(editor_view) = compositor.find::<ui::EditorView>() {
+                let outline = editor_view.outline.get_or_insert_with(ui::Outline::new);
+                outline.refresh(cx.editor, cx.jobs);
+                outline.fold_to_depth(depth);
+            }
+        },
+    ));
+}
+
+/// Reflows the tables containing cursors, returns `false` if there are none.
+fn reflow_tables(doc: &mut Document, view: &mut View, format: TableFormat) -> bool {
+    let tables = table::find_tables(doc.text().slice(..), doc.selection(view.id), format);
+    if tables.is_empty() {
+        return false;
+    }
+    let transaction = table::reflow(doc.text(), doc.selection(view.id), &tables);
+    apply_transaction(&transaction, doc, view);
+    true
+}
+
+fn table_reflow(cx: &mut Context) {
+    let (view, doc) = current!(cx.editor);
+    let format = doc.table_mode.unwrap_or(TableFormat::Pipe);
+    if !reflow_tables(doc, view, format) {
+        cx.editor.set_error("No table at cursor");
+    }
+}
+
+fn table_align_column(cx: &mut Context) {
+    let (view, doc) = current!(cx.editor);
+    let format = doc.table_mode.unwrap_or(TableFormat::Pipe);
+    let text = doc.text().slice(..);
+    let cursor = doc.selection(view.id).primary().cursor(text);
+    let (mut table, column) = match Table::find(text, text.char_to_line(cursor), format) {
+        Some(table) => {
+            let (_, column) = table.cell_at(text, cursor).unwrap_or_default();
+            (table, column)
+        }
+        None => {
+            cx.editor.set_error("No table at cursor");
+            return;
+        }
+    };
 
-    if ranges.len() != 1 {
+    let alignment = match table.alignment(column) {
+        None => Some(Alignment::Left),
+        Some(Alignment::Left) => Some(Alignment::Center),
+        Some(Alignment::Center) => Some(Alignment::Right),
+        Some(Alignment::Right) => None,
+    };
+    if !table.set_alignment(column, alignment) {
         cx.editor
-            .set_error("format_selections only supports a single selection for now");
+            .set_error("Only pipe tables with a delimiter row can be aligned");
         return;
     }
+    let transaction = table::reflow(doc.text(), doc.selection(view.id), &[table]);
+    apply_transaction(&transaction, doc, view);
+}
 
-    // TODO: handle fails
-    // TODO: concurrent map over all ranges
+fn table_cell_motion(cx: &mut Context, direction: CellDirection) {
+    let count = cx.count();
+    let (view, doc) = current!(cx.editor);
+    let format = doc.table_mode.unwrap_or(TableFormat::Pipe);
+    // tables in table mode are reflowed before moving on, as when leaving insert mode
+    if doc.table_mode.is_some() {
+        reflow_tables(doc, view, format);
+    }
 
-    let range = ranges[0];
+    let text = doc.text().slice(..);
+    let selection = doc.selection(view.id).clone().transform(|range| {
+        let mut pos = range.cursor(text);
+        let table = match Table::find(text, text.char_to_line(pos), format) {
+            Some(table) => table,
+            None => return range,
+        };
+        let mut target = None;
+        for _ in 0..count {
+            match table.cell_motion(text, pos, direction) {
+                Some(cell) => {
+                    pos = cell.0;
+                    target = Some(cell);
+                }
+                None => break,
+            }
+        }
+        match target {
+            Some((start, end)) if start < end => Range::new(start, end),
+            Some((start, _)) => Range::point(start),
+            None => range,
+        }
+    });
+    doc.set_selection(view.id, selection);
+}
 
-    let request = match language_server.text_document_range_formatting(
-        doc.identifier(),
-        range,
-        lsp::FormattingOptions::default(),
-        None,
-    ) {
-        Some(future) => future,
+fn table_next_cell(cx: &mut Context) {
+    table_cell_motion(cx, CellDirection::Next)
+}
+
+fn table_prev_cell(cx: &mut Context) {
+    table_cell_motion(cx, CellDirection::Previous)
+}
+
+fn table_cell_above(cx: &mut Context) {
+    table_cell_motion(cx, CellDirection::Up)
+}
+
+fn table_cell_below(cx: &mut Context) {
+    table_cell_motion(cx, CellDirection::Down)
+}
+
+fn table_view(cx: &mut Context) {
+    let doc = doc!(cx.editor);
+    let delimiter = match doc.table_mode {
+        Some(TableFormat::Delimited(delimiter)) => delimiter,
+        _ => match doc.path().and_then(|path| path.extension()) {
+            Some(extension) if extension == "tsv" || extension == "tab" => '\t',
+            _ => ',',
+        },
+    };
+    match ui::TableView::new(doc, delimiter) {
+        Some(table_view) => cx.push_layer(Box::new(overlayed(table_view))),
+        None => cx.editor.set_error("Buffer has no rows"),
+    }
+}
+
+fn data_tree(cx: &mut Context) {
+    let (view, doc) = current_ref!(cx.editor);
+    let is_data = doc.language_name().map_or(false, |name| {
+        helix_core::data_tree::LANGUAGES.contains(&name)
+    });
+    if !is_data {
+        cx.editor.set_error("Document is not JSON, YAML or TOML");
+        return;
+    }
+    let cursor = doc
+        .selection(view.id)
+        .primary()
+        .cursor(doc.text().slice(..));
+    match ui::DataTree::new(doc, cursor) {
+        Some(data_tree) => cx.push_layer(Box::new(overlayed(data_tree))),
+        None => cx.editor.set_error("Document has no keys"),
+    }
+}
+
+fn log_view(cx: &mut Context) {
+    let config = cx.editor.config().log.clone();
+    let patterns = match LogPatterns::new(&config.timestamp, &config.level) {
+        Ok(patterns) => patterns,
+        Err(e) => {
+            cx.editor.set_error(format!("Invalid log pattern: {}", e));
+            return;
+        }
+    };
+    let log_view = ui::LogView::new(doc!(cx.editor), patterns);
+    cx.push_layer(Box::new(overlayed(log_view)));
+}
+
+fn edit_queue(cx: &mut Context) {
+    match ui::EditQueue::new(cx.editor) {
+        Some(edit_queue) => cx.push_layer(Box::new(overlayed(edit_queue))),
+        None => cx.editor.set_status("No edits are staged"),
+    }
+}
+
+fn notes_tree(cx: &mut Context) {
+    match ui::NotesTree::new(cx.editor) {
+        Some(notes) => cx.push_layer(Box::new(overlayed(notes))),
+        None => cx.editor.set_status("The project has no notes"),
+    }
+}
+
+fn yank_history(cx: &mut Context) {
+    match ui::YankHistoryView::new(cx.editor) {
+        Some(history) => cx.push_layer(Box::new(overlayed(history))),
+        None => cx.editor.set_status("Nothing was yanked yet"),
+    }
+}
+
+fn pin_file(cx: &mut Context) {
+    let path = match doc!(cx.editor).path() {
+        Some(path) => path.clone(),
         None => {
-            cx.editor
-                .set_error("Language server does not support range formatting");
+            cx.editor.set_error("Buffer has no file");
+            return;
+        }
+    };
+    match cx.editor.pins.pin(&path) {
+        Some(slot) => {
+            cx.editor.save_pins();
+            cx.editor.set_status(format!("Pinned to slot {}", slot + 1));
+        }
+        None => cx
+            .editor
+            .set_error(format!("All {} slots are taken", helix_view::pins::SLOTS)),
+    }
+}
+
+fn pinned_files(cx: &mut Context) {
+    match ui::PinsView::new(cx.editor) {
+        Some(pins) => cx.push_layer(Box::new(overlayed(pins))),
+        None => cx.editor.set_status("No file is pinned"),
+    }
+}
+
+/// Opens the file pinned to the zero-based `slot`.
+pub(crate) fn goto_pin(editor: &mut Editor, slot: usize) {
+    let path = match editor.pins.get(slot) {
+        Some(path) => path.to_path_buf(),
+        None => {
+            editor.set_error(format!("No file is pinned to slot {}", slot + 1));
+            return;
+        }
+    };
+    if let Err(e) = editor.open(&path, Action::Replace) {
+        editor.set_error(format!("Failed to open {}: {}", path.display(), e));
+    }
+}
+
+fn goto_pin_1(cx: &mut Context) {
+    goto_pin(cx.editor, 0);
+}
+
+fn goto_pin_2(cx: &mut Context) {
+    goto_pin(cx.editor, 1);
+}
+
+fn goto_pin_3(cx: &mut Context) {
+    goto_pin(cx.editor, 2);
+}
+
+fn goto_pin_4(cx: &mut Context) {
+    goto_pin(cx.editor, 3);
+}
+
+fn goto_pin_5(cx: &mut Context) {
+    goto_pin(cx.editor, 4);
+}
+
+fn goto_pin_6(cx: &mut Context) {
+    goto_pin(cx.editor, 5);
+}
+
+fn goto_pin_7(cx: &mut Context) {
+    goto_pin(cx.editor, 6);
+}
+
+fn goto_pin_8(cx: &mut Context) {
+    goto_pin(cx.editor, 7);
+}
+
+fn goto_pin_9(cx: &mut Context) {
+    goto_pin(cx.editor, 8);
+}
+
+fn hex_view(cx: &mut Context) {
+    let path = match doc!(cx.editor).path() {
+        Some(path) => path.clone(),
+        None => {
+            cx.editor.set_error("Buffer has no file");
             return;
         }
     };
+    match ui::HexView::new(&path) {
+        Ok(hex_view) => cx.push_layer(Box::new(overlayed(hex_view))),
+        Err(e) => cx.editor.set_error(format!("Failed to read file: {}", e)),
+    }
+}
+
+fn format_selections(cx: &mut Context) {
+    use helix_lsp::{lsp, util::range_to_lsp_range};
+
+    let (view, doc) = current!(cx.editor);
+
+    // via lsp if available
+    // TODO: else via tree-sitter indentation calculations
+
+    if doc.selection(view.id).len() != 1 {
+        cx.editor
+            .set_error("format_selections only supports a single selection for now");
+        return;
+    }
+
+    // TODO: handle fails
+    // TODO: concurrent map over all ranges
+
+    let range = doc.selection(view.id).primary();
+
+    let (language_server, request) =
+        crate::language_server_with_feature!(cx.editor, doc, "range formatting", |ls| {
+            let range = range_to_lsp_range(doc.text(), range, ls.offset_encoding());
+            ls.text_document_range_formatting(
+                doc.identifier(),
+                range,
+                lsp::FormattingOptions::default(),
+                None,
+            )
+        });
 
     let edits = tokio::task::block_in_place(|| helix_lsp::block_on(request)).unwrap_or_default();
 
@@ -4108,21 +4907,21 @@ pub fn completion(cx: &mut Context) {
 
     let (view, doc) = current!(cx.editor);
 
-    let language_server = match doc.language_server() {
-        Some(language_server) => language_server,
-        None => return,
-    };
-
-    let offset_encoding = language_server.offset_encoding();
     let text = doc.text().slice(..);
     let cursor = doc.selection(view.id).primary().cursor(text);
 
-    let pos = pos_to_lsp_pos(doc.text(), cursor, offset_encoding);
-
-    let future = match language_server.completion(doc.identifier(), pos, None) {
-        Some(future) => future,
+    // Completion is requested from the first attached server which supports it.
+    let (language_server, future) = match doc.language_servers().find_map(|language_server| {
+        let pos = pos_to_lsp_pos(doc.text(), cursor, language_server.offset_encoding());
+        language_server
+            .completion(doc.identifier(), pos, None)
+            .map(|future| (language_server, future))
+    }) {
+        Some(found) => found,
         None => return,
     };
+    let offset_encoding = language_server.offset_encoding();
+    let language_server_id = language_server.id();
 
     let trigger_offset = cursor;
 
@@ -4163,6 +4962,7 @@ pub fn completion(cx: &mut Context) {
                 editor,
                 items,
                 offset_encoding,
+                language_server_id,
                 start_offset,
                 trigger_offset,
                 size,
@@ -4174,11 +4974,29 @@ pub fn completion(cx: &mut Context) {
 // comments
 fn toggle_comments(cx: &mut Context) {
     let (view, doc) = current!(cx.editor);
-    let token = doc
-        .language_config()
-        .and_then(|lc| lc.comment_token.as_ref())
-        .map(|tc| tc.as_ref());
-    let transaction = comment::toggle_line_comments(doc.text(), doc.selection(view.id), token);
+    // the tokens of an injected language at the cursor, like JavaScript in HTML. Injections
+    // without comments of their own, like comments or inline markdown, use the tokens of the
+    // enclosing language.
+    let text = doc.text();
+    let cursor = doc.selection(view.id).primary().cursor(text.slice(..));
+    let injected = doc.syntax().and_then(|syntax| {
+        syntax
+            .injected_language_configs(text.char_to_byte(cursor))
+            .find(|config| config.comment_token.is_some() || config.block_comment_tokens.is_some())
+    });
+    let language_config = injected.as_deref().or_else(|| doc.language_config());
+    let token = language_config.and_then(|lc| lc.comment_token.as_deref());
+    let block_tokens = language_config.and_then(|lc| lc.block_comment_tokens.as_ref());
+
+    let transaction = match (token, block_tokens) {
+        (None, Some(block_tokens)) => comment::toggle_block_comments(
+            text,
+            doc.selection(view.id),
+            &block_tokens.start,
+            &block_tokens.end,
+        ),
+        (token, _) => comment::toggle_line_comments(text, doc.selection(view.id), token),
+    };
 
     apply_transaction(&transaction, doc, view);
     exit_select_mode(cx);
@@ -4324,6 +5142,41 @@ fn select_prev_sibling(cx: &mut Context) {
     select_sibling_impl(cx, &|node| Node::prev_sibling(&node))
 }
 
+fn structural_edit_impl(
+    cx: &mut Context,
+    edit_fn: fn(&helix_core::Syntax, &Rope, &Selection, usize) -> Option<Transaction>,
+) {
+    let count = cx.count();
+    let (view, doc) = current!(cx.editor);
+    let syntax = match doc.syntax() {
+        Some(syntax) => syntax,
+        None => {
+            cx.editor
+                .set_status("Syntax-tree is not available in current buffer");
+            return;
+        }
+    };
+    if let Some(transaction) = edit_fn(syntax, doc.text(), doc.selection(view.id), count) {
+        apply_transaction(&transaction, doc, view);
+    }
+}
+
+fn raise_syntax_node(cx: &mut Context) {
+    structural_edit_impl(cx, structural::raise)
+}
+
+fn splice_syntax_node(cx: &mut Context) {
+    structural_edit_impl(cx, structural::splice)
+}
+
+fn slurp_syntax_node(cx: &mut Context) {
+    structural_edit_impl(cx, structural::slurp)
+}
+
+fn barf_syntax_node(cx: &mut Context) {
+    structural_edit_impl(cx, structural::barf)
+}
+
 fn match_brackets(cx: &mut Context) {
     let (view, doc) = current!(cx.editor);
 
@@ -4390,6 +5243,19 @@ fn save_selection(cx: &mut Context) {
     cx.editor.set_status("Selection saved to jumplist");
 }
 
+fn peek_back(cx: &mut Context) {
+    if !cx.editor.pop_peek() {
+        cx.editor.set_status("No peek to go back from");
+    }
+}
+
+fn peek_confirm(cx: &mut Context) {
+    let peeks = cx.editor.peeks.len();
+    cx.editor.confirm_peeks();
+    cx.editor
+        .set_status(format!("{} peeks added to jumplist", peeks));
+}
+
 fn rotate_view(cx: &mut Context) {
     cx.editor.focus_next()
 }
@@ -4430,6 +5296,28 @@ fn transpose_view(cx: &mut Context) {
     cx.editor.transpose_view()
 }
 
+fn rotate_splits(cx: &mut Context) {
+    cx.editor.rotate_splits(false)
+}
+
+fn rotate_splits_reverse(cx: &mut Context) {
+    cx.editor.rotate_splits(true)
+}
+
+fn zoom_view(cx: &mut Context) {
+    cx.editor.toggle_zoom()
+}
+
+fn zen_mode(cx: &mut Context) {
+    cx.callback = Some(Box::new(
+        |compositor: &mut Compositor, cx: &mut compositor::Context| {
+            if let Some(editor_view) = compositor.find::<ui::EditorView>() {
+                editor_view.toggle_zen(cx.editor);
+            }
+        },
+    ));
+}
+
 // split helper, clear it later
 fn split(cx: &mut Context, action: Action) {
     let (view, doc) = current!(cx.editor);
@@ -4609,6 +5497,103 @@ fn goto_prev_parameter(cx: &mut Context) {
     goto_ts_object_impl(cx, "parameter", Direction::Backward)
 }
 
+/// Swaps the tree-sitter object under each cursor with its neighbour in `direction`, keeping the
+/// selection on the moved object so that repeating the command moves it further.
+fn swap_ts_object_impl(cx: &mut Context, object: &'static str, direction: Direction) {
+    let (view, doc) = current!(cx.editor);
+    let (lang_config, syntax) = match doc.language_config().zip(doc.syntax()) {
+        Some(config) => config,
+        None => {
+            cx.editor
+                .set_status("Syntax-tree is not available in current buffer");
+            return;
+        }
+    };
+    let text = doc.text().slice(..);
+    let root = syntax.tree().root_node();
+    let selection = doc.selection(view.id).clone();
+
+    let mut swaps: Vec<_> = selection
+        .iter()
+        .enumerate()
+        .filter_map(|(index, range)| {
+            let (current, neighbour) = textobject::treesitter_object_neighbour(
+                text,
+                *range,
+                object,
+                direction,
+                root,
+                lang_config,
+            )?;
+            Some((index, current, neighbour))
+        })
+        .collect();
+    if swaps.is_empty() {
+        cx.editor.set_status(format!("No {} to swap with", object));
+        return;
+    }
+    swaps.sort_by_key(|(_, current, neighbour)| current.from().min(neighbour.from()));
+    // swaps sharing an object would produce overlapping changes, the cursors of the dropped ones
+    // stay where they are
+    let mut end = 0;
+    swaps.retain(|(_, current, neighbour)| {
+        let (first, second) = if current.from() < neighbour.from() {
+            (current, neighbour)
+        } else {
+            (neighbour, current)
+        };
+        let keep = first.from() >= end;
+        if keep {
+            end = second.to();
+        }
+        keep
+    });
+
+    // swapping keeps the length of the text spanning both objects, so the positions of the
+    // later swaps stay valid
+    let mut changes = Vec::with_capacity(swaps.len() * 2);
+    let mut moved = Vec::with_capacity(swaps.len());
+    for (index, current, neighbour) in &swaps {
+        let (first, second) = if current.from() < neighbour.from() {
+            (current, neighbour)
+        } else {
+            (neighbour, current)
+        };
+        let first_text = Tendril::from(first.fragment(text).as_ref());
+        let second_text = Tendril::from(second.fragment(text).as_ref());
+        changes.push((first.from(), first.to(), Some(second_text)));
+        changes.push((second.from(), second.to(), Some(first_text)));
+
+        let range = match direction {
+            // the moved object ends where the neighbour ended
+            Direction::Forward => Range::new(neighbour.to() - current.len(), neighbour.to()),
+            // the moved object starts where the neighbour started
+            Direction::Backward => Range::new(neighbour.from(), neighbour.from() + current.len()),
+        };
+        moved.push((*index, range));
+    }
+
+    let transaction = Transaction::change(doc.text(), changes.into_iter());
+    let mut ranges: SmallVec<[Range; 1]> = selection
+        .iter()
+        .map(|range| range.map(transaction.changes()))
+        .collect();
+    for (index, range) in moved {
+        ranges[index] = range;
+    }
+    let transaction = transaction.with_selection(Selection::new(ranges, selection.primary_index()));
+    apply_transaction(&transaction, doc, view);
+    exit_select_mode(cx);
+}
+
+fn swap_next_parameter(cx: &mut Context) {
+    swap_ts_object_impl(cx, "parameter", Direction::Forward)
+}
+
+fn swap_prev_parameter(cx: &mut Context) {
+    swap_ts_object_impl(cx, "parameter", Direction::Backward)
+}
+
 fn goto_next_comment(cx: &mut Context) {
     goto_ts_object_impl(cx, "comment", Direction::Forward)
 }
@@ -5058,8 +6043,8 @@ fn shell(cx: &mut compositor::Context, cmd: &str, behavior: &ShellBehavior) {
 }
 
 fn shell_prompt(cx: &mut Context, prompt: Cow<'static, str>, behavior: ShellBehavior) {
-    ui::prompt(
-        cx,
+    // shell commands may span several lines, like a script
+    let prompt = Prompt::new(
         prompt,
         Some('|'),
         ui::completers::none,
@@ -5073,7 +6058,9 @@ fn shell_prompt(cx: &mut Context, prompt: Cow<'static, str>, behavior: ShellBeha
 
             shell(cx, input, &behavior);
         },
-    );
+    )
+    .with_multiline();
+    cx.push_layer(Box::new(prompt));
 }
 
 fn suspend(_cx: &mut Context) {
@@ -5288,6 +6275,8 @@ fn replay_macro(cx: &mut Context) {
         for _ in 0..count {
             for &key in keys.iter() {
                 compositor.handle_event(&compositor::Event::Key(key), cx);
+                // open the panels of the commands run so far, so the following keys reach them
+                cx.jobs.handle_ready_callbacks(cx.editor, compositor);
             }
         }
         // The macro under replay is cleared at the end of the callback, not in the
@@ -2,13 +2,13 @@
 use crate::{
     compositor::{self, Compositor},
     job::{Callback, Jobs},
-    ui::{self, overlay::overlayed, FilePicker, Picker, Popup, Prompt, PromptEvent, Text},
+    ui::{self, overlay::overlayed, FilePicker, Picker, Prompt, PromptEvent},
 };
 use dap::{StackFrame, Thread, ThreadStates};
 use helix_core::syntax::{DebugArgumentValue, DebugConfigCompletion, DebugTemplate};
 use helix_dap::{self as dap, Client};
 use helix_lsp::block_on;
-use helix_view::editor::Breakpoint;
+use helix_view::editor::{Breakpoint, DataBreakpoint};
 
 use serde_json::{to_value, Value};
 use tokio_stream::wrappers::UnboundedReceiverStream;
@@ -20,7 +20,9 @@
 
 use anyhow::{anyhow, bail};
 
-use helix_view::handlers::dap::{breakpoints_changed, jump_to_stack_frame, select_thread_id};
+use helix_view::handlers::dap::{
+    breakpoints_changed, data_breakpoints_changed, jump_to_stack_frame, select_thread_id,
+};
 
 impl ui::menu::Item for StackFrame {
     type Data = ();
@@ -354,6 +356,7 @@ fn debug_parameter_prompt(
             }
         },
     )
+    .with_multiline()
 }
 
 pub fn dap_toggle_breakpoint(cx: &mut Context) {
@@ -478,62 +481,13 @@ pub fn dap_variables(cx: &mut Context) {
             .set_status("Cannot access variables while target is running");
         return;
     }
-    let (frame, thread_id) = match (debugger.active_frame, debugger.thread_id) {
-        (Some(frame), Some(thread_id)) => (frame, thread_id),
-        _ => {
-            cx.editor
-                .set_status("Cannot find current stack frame to access variables");
-            return;
-        }
-    };
-
-    let frame_id = debugger.stack_frames[&thread_id][frame].id;
-    let scopes = match block_on(debugger.scopes(frame_id)) {
-        Ok(s) => s,
-        Err(e) => {
-            cx.editor.set_error(format!("Failed to get scopes: {}", e));
-            return;
-        }
-    };
-
-    // TODO: allow expanding variables into sub-fields
-    let mut variables = Vec::new();
-
-    let theme = &cx.editor.theme;
-    let scope_style = theme.get("ui.linenr.selected");
-    let type_style = theme.get("ui.text");
-    let text_style = theme.get("ui.text.focus");
-
-    for scope in scopes.iter() {
-        // use helix_view::graphics::Style;
-        use tui::text::Span;
-        let response = block_on(debugger.variables(scope.variables_reference));
-
-        variables.push(Spans::from(Span::styled(
-            format!("▸ {}", scope.name),
-            scope_style,
-        )));
-
-        if let Ok(vars) = response {
-            variables.reserve(vars.len());
-            for var in vars {
-                let mut spans = Vec::with_capacity(5);
-
-                spans.push(Span::styled(var.name.to_owned(), text_style));
-                if let Some(ty) = var.ty {
-                    spans.push(Span::raw(": "));
-                    spans.push(Span::styled(ty.to_owned(), type_style));
-                }
-                spans.push(Span::raw(" = "));
-                spans.push(Span::styled(var.value.to_owned(), text_style));
-                variables.push(Spans::from(spans));
-            }
-        }
+    if debugger.current_stack_frame().is_none() {
+        cx.editor
+            .set_status("Cannot find current stack frame to access variables");
+        return;
     }
 
-    let contents = Text::from(tui::text::Text::from(variables));
-    let popup = Popup::new("dap-variables", contents);
-    cx.push_layer(Box::new(popup));
+    cx.push_layer(Box::new(overlayed(ui::VariablesTree::new())));
 }
 
 pub fn dap_terminate(cx: &mut Context) {
@@ -579,88 +533,356 @@ pub fn dap_disable_exceptions(cx: &mut Context) {
     )
 }
 
-// TODO: both edit condition and edit log need to be stable: we might get new breakpoints from the debugger which can change offsets
-pub fn dap_edit_condition(cx: &mut Context) {
-    if let Some((pos, breakpoint)) = get_breakpoint_at_current_line(cx.editor) {
-        let path = match doc!(cx.editor).path() {
-            Some(path) => path.clone(),
-            None => return,
-        };
-        let callback = Box::pin(async move {
-            let call: Callback = Callback::EditorCompositor(Box::new(move |editor, compositor| {
-                let mut prompt = Prompt::new(
-                    "condition:".into(),
-                    None,
-                    ui::completers::none,
-                    move |cx, input: &str, event: PromptEvent| {
-                        if event != PromptEvent::Validate {
-                            return;
-                        }
-
-                        let breakpoints = &mut cx.editor.breakpoints.get_mut(&path).unwrap();
-                        breakpoints[pos].condition = match input {
-                            "" => None,
-                            input => Some(input.to_owned()),
-                        };
-
-                        let debugger = debugger!(cx.editor);
-
-                        if let Err(e) = breakpoints_changed(debugger, path.clone(), breakpoints) {
-                            cx.editor
-                                .set_error(format!("Failed to set breakpoints: {}", e));
-                        }
-                    },
-                );
-                if let Some(condition) = breakpoint.condition {
-                    prompt.insert_str(&condition, editor)
+/// A user editable property of a breakpoint.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BreakpointField {
+    Condition,
+    HitCondition,
+    LogMessage,
+}
+
+impl BreakpointField {
+    pub fn prompt(self) -> &'static str {
+        match self {
+            Self::Condition => "condition:",
+            Self::HitCondition => "hit-condition:",
+            Self::LogMessage => "log-message:",
+        }
+    }
+}
+
+/// Identifies a breakpoint stored in the editor: a source breakpoint by its file and index, or
+/// a data breakpoint by its index.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum BreakpointRef {
+    Source(PathBuf, usize),
+    Data(usize),
+}
+
+/// Returns the current value of `field` for the breakpoint referenced by `target`.
+pub fn breakpoint_field(
+    editor: &Editor,
+    target: &BreakpointRef,
+    field: BreakpointField,
+) -> Option<String> {
+    match target {
+        BreakpointRef::Source(path, pos) => {
+            let breakpoint = editor.breakpoints.get(path)?.get(*pos)?;
+            match field {
+                BreakpointField::Condition => breakpoint.condition.clone(),
+                BreakpointField::HitCondition => breakpoint.hit_condition.clone(),
+                BreakpointField::LogMessage => breakpoint.log_message.clone(),
+            }
+        }
+        BreakpointRef::Data(pos) => {
+            let breakpoint = editor.data_breakpoints.get(*pos)?;
+            match field {
+                BreakpointField::Condition => breakpoint.condition.clone(),
+                BreakpointField::HitCondition => breakpoint.hit_condition.clone(),
+                BreakpointField::LogMessage => None,
+            }
+        }
+    }
+}
+
+/// Sets `field` of the breakpoint referenced by `target` (an empty value clears it) and sends
+/// the updated breakpoints to the debugger if one is running.
+pub fn set_breakpoint_field(
+    editor: &mut Editor,
+    target: &BreakpointRef,
+    field: BreakpointField,
+    value: &str,
+) {
+    let value = match value {
+        "" => None,
+        value => Some(value.to_owned()),
+    };
+
+    match target {
+        BreakpointRef::Source(path, pos) => {
+            let breakpoints = match editor.breakpoints.get_mut(path) {
+                Some(breakpoints) if *pos < breakpoints.len() => breakpoints,
+                _ => return,
+            };
+            let breakpoint = &mut breakpoints[*pos];
+            match field {
+                BreakpointField::Condition => breakpoint.condition = value,
+                BreakpointField::HitCondition => breakpoint.hit_condition = value,
+                BreakpointField::LogMessage => breakpoint.log_message = value,
+            }
+
+            let debugger = debugger!(editor);
+            if let Err(e) = breakpoints_changed(debugger, path.clone(), breakpoints) {
+                editor.set_error(format!("Failed to set breakpoints: {}", e));
+            }
+        }
+        BreakpointRef::Data(pos) => {
+            let breakpoint = match editor.data_breakpoints.get_mut(*pos) {
+                Some(breakpoint) => breakpoint,
+                None => return,
+            };
+            match field {
+                BreakpointField::Condition => breakpoint.condition = value,
+                BreakpointField::HitCondition => breakpoint.hit_condition = value,
+                BreakpointField::LogMessage => {
+                    editor.set_error("Data breakpoints can't have log messages");
+                    return;
                 }
-                compositor.push(Box::new(prompt));
-            }));
-            Ok(call)
-        });
-        cx.jobs.callback(callback);
+            }
+
+            let debugger = debugger!(editor);
+            if let Err(e) = data_breakpoints_changed(debugger, &mut editor.data_breakpoints) {
+                editor.set_error(format!("Failed to set data breakpoints: {}", e));
+            }
+        }
+    }
+}
+
+/// Removes the breakpoint referenced by `target` and updates the debugger if one is running.
+pub fn remove_breakpoint(editor: &mut Editor, target: &BreakpointRef) {
+    match target {
+        BreakpointRef::Source(path, pos) => {
+            let breakpoints = match editor.breakpoints.get_mut(path) {
+                Some(breakpoints) if *pos < breakpoints.len() => breakpoints,
+                _ => return,
+            };
+            breakpoints.remove(*pos);
+
+            let debugger = debugger!(editor);
+            if let Err(e) = breakpoints_changed(debugger, path.clone(), breakpoints) {
+                editor.set_error(format!("Failed to set breakpoints: {}", e));
+            }
+        }
+        BreakpointRef::Data(pos) => {
+            if *pos >= editor.data_breakpoints.len() {
+                return;
+            }
+            editor.data_breakpoints.remove(*pos);
+
+            let debugger = debugger!(editor);
+            if let Err(e) = data_breakpoints_changed(debugger, &mut editor.data_breakpoints) {
+                editor.set_error(format!("Failed to set data breakpoints: {}", e));
+            }
+        }
     }
 }
 
+fn dap_edit_breakpoint_field(cx: &mut Context, field: BreakpointField) {
+    // TODO: the prompt needs to be stable: we might get new breakpoints from the debugger which
+    // can change offsets
+    let pos = match get_breakpoint_at_current_line(cx.editor) {
+        Some((pos, _)) => pos,
+        None => return,
+    };
+    let path = match doc!(cx.editor).path() {
+        Some(path) => path.clone(),
+        None => return,
+    };
+    let target = BreakpointRef::Source(path, pos);
+    let value = breakpoint_field(cx.editor, &target, field);
+
+    let mut prompt = Prompt::new(
+        field.prompt().into(),
+        None,
+        ui::completers::none,
+        move |cx, input: &str, event: PromptEvent| {
+            if event != PromptEvent::Validate {
+                return;
+            }
+            set_breakpoint_field(cx.editor, &target, field, input);
+        },
+    );
+    if let Some(value) = value {
+        prompt.insert_str(&value, cx.editor);
+    }
+    cx.push_layer(Box::new(prompt));
+}
+
+pub fn dap_edit_condition(cx: &mut Context) {
+    dap_edit_breakpoint_field(cx, BreakpointField::Condition)
+}
+
+pub fn dap_edit_hit_condition(cx: &mut Context) {
+    dap_edit_breakpoint_field(cx, BreakpointField::HitCondition)
+}
+
 pub fn dap_edit_log(cx: &mut Context) {
-    if let Some((pos, breakpoint)) = get_breakpoint_at_current_line(cx.editor) {
-        let path = match doc!(cx.editor).path() {
-            Some(path) => path.clone(),
-            None => return,
-        };
-        let callback = Box::pin(async move {
-            let call: Callback = Callback::EditorCompositor(Box::new(move |editor, compositor| {
-                let mut prompt = Prompt::new(
-                    "log-message:".into(),
-                    None,
-                    ui::completers::none,
-                    move |cx, input: &str, event: PromptEvent| {
-                        if event != PromptEvent::Validate {
-                            return;
-                        }
-
-                        let breakpoints = &mut cx.editor.breakpoints.get_mut(&path).unwrap();
-                        breakpoints[pos].log_message = match input {
-                            "" => None,
-                            input => Some(input.to_owned()),
-                        };
-
-                        let debugger = debugger!(cx.editor);
-                        if let Err(e) = breakpoints_changed(debugger, path.clone(), breakpoints) {
-                            cx.editor
-                                .set_error(format!("Failed to set breakpoints: {}", e));
-                        }
-                    },
-                );
-                if let Some(log_message) = breakpoint.log_message {
-                    prompt.insert_str(&log_message, editor);
+    dap_edit_breakpoint_field(cx, BreakpointField::LogMessage)
+}
+
+pub fn dap_toggle_data_breakpoint(cx: &mut Context) {
+    let debugger = debugger!(cx.editor);
+
+    if !debugger
+        .capabilities()
+        .supports_data_breakpoints
+        .unwrap_or_default()
+    {
+        cx.editor
+            .set_error("Debugger does not support data breakpoints");
+        return;
+    }
+
+    // expressions are resolved in the scope of the active stack frame
+    let frame_id = debugger.current_stack_frame().map(|frame| frame.id);
+
+    let prompt = Prompt::new(
+        "data-breakpoint:".into(),
+        None,
+        ui::completers::none,
+        move |cx, input: &str, event: PromptEvent| {
+            if event != PromptEvent::Validate || input.is_empty() {
+                return;
+            }
+
+            let debugger = debugger!(cx.editor);
+            let request = debugger.data_breakpoint_info(input.to_owned(), None, frame_id);
+            let info = match block_on(request) {
+                Ok(info) => info,
+                Err(e) => {
+                    cx.editor
+                        .set_error(format!("Failed to get data breakpoint info: {}", e));
+                    return;
                 }
-                compositor.push(Box::new(prompt));
-            }));
-            Ok(call)
-        });
-        cx.jobs.callback(callback);
+            };
+            let data_id = match info.data_id {
+                Some(data_id) => data_id,
+                None => {
+                    cx.editor
+                        .set_error(format!("Can't set data breakpoint: {}", info.description));
+                    return;
+                }
+            };
+
+            let breakpoints = &mut cx.editor.data_breakpoints;
+            if let Some(pos) = breakpoints.iter().position(|b| b.data_id == data_id) {
+                breakpoints.remove(pos);
+            } else {
+                // break on writes if possible, that's what a data breakpoint is usually set for
+                let access_type = info.access_types.and_then(|access_types| {
+                    access_types
+                        .iter()
+                        .copied()
+                        .find(|access_type| *access_type == dap::DataBreakpointAccessType::Write)
+                        .or_else(|| access_types.first().copied())
+                });
+                breakpoints.push(DataBreakpoint {
+                    data_id,
+                    description: info.description,
+                    access_type,
+                    ..Default::default()
+                });
+            }
+
+            let debugger = debugger!(cx.editor);
+            if let Err(e) = data_breakpoints_changed(debugger, &mut cx.editor.data_breakpoints) {
+                cx.editor
+                    .set_error(format!("Failed to set data breakpoints: {}", e));
+            }
+        },
+    );
+    cx.push_layer(Box::new(prompt));
+}
+
+pub fn dap_console(cx: &mut Context) {
+    cx.callback = Some(Box::new(
+        |compositor: &mut Compositor, _cx: &mut compositor::Context| {
+            if let Some(editor) = compositor.find::<ui::EditorView>() {
+                match editor.debug_console.as_mut() {
+                    Some(console) => console.focus(),
+                    None => editor.debug_console = Some(ui::DebugConsole::new()),
+                }
+            }
+        },
+    ));
+}
+
+pub fn dap_breakpoints(cx: &mut Context) {
+    let tree = ui::BreakpointsTree::new(cx.editor);
+    cx.push_layer(Box::new(overlayed(tree)));
+}
+
+pub fn dap_watches(cx: &mut Context) {
+    let tree = ui::WatchesTree::new(cx.editor);
+    cx.push_layer(Box::new(overlayed(tree)));
+}
+
+pub fn dap_call_stack(cx: &mut Context) {
+    if cx.editor.debugger.is_none() {
+        return;
+    }
+    cx.push_layer(Box::new(overlayed(ui::CallStackTree::new())));
+}
+
+pub fn dap_memory(cx: &mut Context) {
+    if !ui::memory::can_read_memory(cx.editor) {
+        return;
     }
+    let debugger = debugger!(cx.editor);
+    let frame_id = debugger.current_stack_frame().map(|frame| frame.id);
+
+    let prompt = Prompt::new(
+        "memory:".into(),
+        None,
+        ui::completers::none,
+        move |cx, input: &str, event: PromptEvent| {
+            if event != PromptEvent::Validate || input.is_empty() {
+                return;
+            }
+
+            let debugger = debugger!(cx.editor);
+            let memory_reference = match block_on(debugger.eval(input.to_owned(), frame_id)) {
+                Ok(response) => match response.memory_reference {
+                    Some(memory_reference) => memory_reference,
+                    None => {
+                        cx.editor
+                            .set_error(format!("{} does not refer to memory", input));
+                        return;
+                    }
+                },
+                Err(e) => {
+                    cx.editor.set_error(format!("Failed to evaluate: {}", e));
+                    return;
+                }
+            };
+
+            let callback = Box::pin(async move {
+                let call: Callback =
+                    Callback::EditorCompositor(Box::new(move |_editor, compositor| {
+                        let view = ui::MemoryView::new(memory_reference);
+                        compositor.push(Box::new(overlayed(view)));
+                    }));
+                Ok(call)
+            });
+            cx.jobs.callback(callback);
+        },
+    );
+    cx.push_layer(Box::new(prompt));
+}
+
+pub fn dap_disassemble(cx: &mut Context) {
+    if !ui::memory::can_disassemble(cx.editor) {
+        return;
+    }
+    let debugger = debugger!(cx.editor);
+
+    let memory_reference = match debugger.current_stack_frame() {
+        Some(frame) => frame.instruction_pointer_reference.clone(),
+        None => {
+            cx.editor.set_error("No stack frame is currently active");
+            return;
+        }
+    };
+    let memory_reference = match memory_reference {
+        Some(memory_reference) => memory_reference,
+        None => {
+            cx.editor
+                .set_error("Stack frame has no instruction pointer");
+            return;
+        }
+    };
+    let view = ui::DisassemblyView::new(memory_reference);
+    cx.push_layer(Box::new(overlayed(view)));
 }
 
 pub fn dap_switch_thread(cx: &mut Context) {
@@ -1,15 +1,25 @@
 use helix_lsp::{
     block_on,
     lsp::{self, CodeAction, CodeActionOrCommand, DiagnosticSeverity, NumberOrString},
-    util::{diagnostic_to_lsp_diagnostic, lsp_pos_to_pos, lsp_range_to_range, range_to_lsp_range},
+    util::{
+        diagnostic_to_lsp_diagnostic, lsp_pos_to_pos, lsp_range_to_range, pos_to_lsp_pos,
+        range_to_lsp_range,
+    },
     OffsetEncoding,
 };
 use tui::text::{Span, Spans};
 
 use super::{align_view, push_jump, Align, Context, Editor, Open};
 
-use helix_core::{path, Selection};
-use helix_view::{apply_transaction, document::Mode, editor::Action, theme::Style};
+use anyhow::{anyhow, bail, Context as _};
+use helix_core::{path, Selection, Transaction};
+use helix_view::{
+    apply_transaction,
+    document::Mode,
+    editor::{Action, QueuedEdit},
+    theme::Style,
+    Document, ViewId,
+};
 
 use crate::{
     compositor::{self, Compositor},
@@ -19,7 +29,12 @@
 };
 
 use std::{
-    borrow::Cow, cmp::Ordering, collections::BTreeMap, fmt::Write, path::PathBuf, sync::Arc,
+    borrow::Cow,
+    cmp::Ordering,
+    collections::{BTreeMap, HashMap},
+    fmt::Write,
+    path::PathBuf,
+    sync::Arc,
 };
 
 /// Gets the language server that is attached to a document, and
@@ -40,6 +55,32 @@ macro_rules! language_server {
     };
 }
 
+/// Finds the first language server attached to a document which supports a request, i.e.
+/// for which `$request` returns a future, and evaluates to `(language_server, future)`.
+/// Servers are tried in configuration order so the primary server takes precedence. If no
+/// server supports the request, an error naming `$feature` is displayed and the enclosing
+/// function returns.
+#[macro_export]
+macro_rules! language_server_with_feature {
+    ($editor:expr, $doc:expr, $feature:literal, |$language_server:ident| $request:expr) => {{
+        let doc = $doc;
+        if doc.language_servers().next().is_none() {
+            $editor.set_status("Language server not active for current buffer");
+            return;
+        }
+        match doc
+            .language_servers()
+            .find_map(|$language_server| $request.map(|future| ($language_server, future)))
+        {
+            Some(found) => found,
+            None => {
+                $editor.set_error(concat!("Language server does not support ", $feature));
+                return;
+            }
+        }
+    }};
+}
+
 impl ui::menu::Item for lsp::Location {
     /// Current working directory.
     type Data = PathBuf;
@@ -74,12 +115,43 @@ fn label(&self, cwdir: &Self::Data) -> Spans {
     }
 }
 
-impl ui::menu::Item for lsp::SymbolInformation {
+/// State used to label the entries of the symbol pickers.
+pub struct SymbolPickerData {
     /// Path to currently focussed document
-    type Data = Option<lsp::Url>;
+    current_path: Option<lsp::Url>,
+    /// Percentage of the lines of each symbol covered by tests, keyed by the symbol's location
+    coverage: HashMap<(lsp::Url, lsp::Position), f64>,
+}
 
-    fn label(&self, current_doc_path: &Self::Data) -> Spans {
-        if current_doc_path.as_ref() == Some(&self.location.uri) {
+/// Percentage of the lines within `range` that are covered according to the loaded coverage
+/// report.
+fn symbol_coverage(editor: &Editor, uri: &lsp::Url, range: lsp::Range) -> Option<f64> {
+    let path = uri.to_file_path().ok()?;
+    editor
+        .coverage
+        .as_ref()?
+        .file(&path)?
+        .range_percent(range.start.line as usize..=range.end.line as usize)
+}
+
+impl ui::menu::Item for lsp::SymbolInformation {
+    type Data = SymbolPickerData;
+
+    fn label(&self, data: &Self::Data) -> Spans {
+        let label = self.filter_text(data);
+        let location = (self.location.uri.clone(), self.location.range.start);
+        match data.coverage.get(&location) {
+            Some(percent) => format!("{} {:.0}%", label, percent).into(),
+            None => label.into_owned().into(),
+        }
+    }
+
+    fn sort_text(&self, data: &Self::Data) -> Cow<str> {
+        self.filter_text(data)
+    }
+
+    fn filter_text(&self, data: &Self::Data) -> Cow<str> {
+        if data.current_path.as_ref() == Some(&self.location.uri) {
             self.name.as_str().into()
         } else {
             match self.location.uri.to_file_path() {
@@ -103,6 +175,7 @@ struct DiagnosticStyles {
 struct PickerDiagnostic {
     url: lsp::Url,
     diag: lsp::Diagnostic,
+    offset_encoding: OffsetEncoding,
 }
 
 impl ui::menu::Item for PickerDiagnostic {
@@ -198,15 +271,34 @@ fn jump_to_location(
     align_view(doc, view, Align::Center);
 }
 
+fn flat_symbols_coverage(
+    editor: &Editor,
+    symbols: &[lsp::SymbolInformation],
+) -> HashMap<(lsp::Url, lsp::Position), f64> {
+    symbols
+        .iter()
+        .filter_map(|symbol| {
+            let location = &symbol.location;
+            let percent = symbol_coverage(editor, &location.uri, location.range)?;
+            Some(((location.uri.clone(), location.range.start), percent))
+        })
+        .collect()
+}
+
 fn sym_picker(
     symbols: Vec<lsp::SymbolInformation>,
     current_path: Option<lsp::Url>,
+    coverage: HashMap<(lsp::Url, lsp::Position), f64>,
     offset_encoding: OffsetEncoding,
 ) -> FilePicker<lsp::SymbolInformation> {
     // TODO: drop current_path comparison and instead use workspace: bool flag?
+    let data = SymbolPickerData {
+        current_path: current_path.clone(),
+        coverage,
+    };
     FilePicker::new(
         symbols,
-        current_path.clone(),
+        data,
         move |cx, symbol, action| {
             let (view, doc) = current!(cx.editor);
             push_jump(view, doc);
@@ -253,10 +345,9 @@ enum DiagnosticsFormat {
 
 fn diag_picker(
     cx: &Context,
-    diagnostics: BTreeMap<lsp::Url, Vec<lsp::Diagnostic>>,
+    diagnostics: BTreeMap<lsp::Url, Vec<(lsp::Diagnostic, Option<usize>)>>,
     current_path: Option<lsp::Url>,
     format: DiagnosticsFormat,
-    offset_encoding: OffsetEncoding,
 ) -> FilePicker<PickerDiagnostic> {
     // TODO: drop current_path comparison and instead use workspace: bool flag?
 
@@ -264,10 +355,18 @@ fn diag_picker(
     let mut flat_diag = Vec::new();
     for (url, diags) in diagnostics {
         flat_diag.reserve(diags.len());
-        for diag in diags {
+        for (diag, server_id) in diags {
+            let offset_encoding = match server_id {
+                Some(server_id) => match cx.editor.language_servers.get_by_id(server_id) {
+                    Some(language_server) => language_server.offset_encoding(),
+                    None => continue,
+                },
+                None => helix_view::checker::OFFSET_ENCODING,
+            };
             flat_diag.push(PickerDiagnostic {
                 url: url.clone(),
                 diag,
+                offset_encoding,
             });
         }
     }
@@ -282,7 +381,13 @@ fn diag_picker(
     FilePicker::new(
         flat_diag,
         (styles, format),
-        move |cx, PickerDiagnostic { url, diag }, action| {
+        move |cx,
+              PickerDiagnostic {
+                  url,
+                  diag,
+                  offset_encoding,
+              },
+              action| {
             if current_path.as_ref() == Some(url) {
                 let (view, doc) = current!(cx.editor);
                 push_jump(view, doc);
@@ -293,14 +398,14 @@ fn diag_picker(
 
             let (view, doc) = current!(cx.editor);
 
-            if let Some(range) = lsp_range_to_range(doc.text(), diag.range, offset_encoding) {
+            if let Some(range) = lsp_range_to_range(doc.text(), diag.range, *offset_encoding) {
                 // we flip the range so that the cursor sits on the start of the symbol
                 // (for example start of the function).
                 doc.set_selection(view.id, Selection::single(range.head, range.anchor));
                 align_view(doc, view, Align::Center);
             }
         },
-        move |_editor, PickerDiagnostic { url, diag }| {
+        move |_editor, PickerDiagnostic { url, diag, .. }| {
             let location = lsp::Location::new(url.clone(), diag.range);
             Some(location_to_file_location(&location))
         },
@@ -310,10 +415,16 @@ fn diag_picker(
 
 pub fn symbol_picker(cx: &mut Context) {
     fn nested_to_flat(
+        editor: &Editor,
         list: &mut Vec<lsp::SymbolInformation>,
+        coverage: &mut HashMap<(lsp::Url, lsp::Position), f64>,
         file: &lsp::TextDocumentIdentifier,
         symbol: lsp::DocumentSymbol,
     ) {
+        // the coverage is computed over the whole symbol rather than the selection range
+        if let Some(percent) = symbol_coverage(editor, &file.uri, symbol.range) {
+            coverage.insert((file.uri.clone(), symbol.selection_range.start), percent);
+        }
         #[allow(deprecated)]
         list.push(lsp::SymbolInformation {
             name: symbol.name,
@@ -324,68 +435,148 @@ fn nested_to_flat(
             container_name: None,
         });
         for child in symbol.children.into_iter().flatten() {
-            nested_to_flat(list, file, child);
+            nested_to_flat(editor, list, coverage, file, child);
         }
     }
     let doc = doc!(cx.editor);
 
-    let language_server = language_server!(cx.editor, doc);
+    let (language_server, future) =
+        language_server_with_feature!(cx.editor, doc, "document symbols", |ls| ls
+            .document_symbols(doc.identifier()));
     let current_url = doc.url();
     let offset_encoding = language_server.offset_encoding();
 
-    let future = match language_server.document_symbols(doc.identifier()) {
-        Some(future) => future,
-        None => {
-            cx.editor
-                .set_error("Language server does not support document symbols");
-            return;
-        }
-    };
-
     cx.callback(
         future,
         move |editor, compositor, response: Option<lsp::DocumentSymbolResponse>| {
             if let Some(symbols) = response {
+                let mut coverage = HashMap::new();
                 // lsp has two ways to represent symbols (flat/nested)
                 // convert the nested variant to flat, so that we have a homogeneous list
                 let symbols = match symbols {
-                    lsp::DocumentSymbolResponse::Flat(symbols) => symbols,
+                    lsp::DocumentSymbolResponse::Flat(symbols) => {
+                        coverage = flat_symbols_coverage(editor, &symbols);
+                        symbols
+                    }
                     lsp::DocumentSymbolResponse::Nested(symbols) => {
                         let doc = doc!(editor);
                         let mut flat_symbols = Vec::new();
                         for symbol in symbols {
-                            nested_to_flat(&mut flat_symbols, &doc.identifier(), symbol)
+                            nested_to_flat(
+                                editor,
+                                &mut flat_symbols,
+                                &mut coverage,
+                                &doc.identifier(),
+                                symbol,
+                            )
                         }
                         flat_symbols
                     }
                 };
 
-                let picker = sym_picker(symbols, current_url, offset_encoding);
+                let picker = sym_picker(symbols, current_url, coverage, offset_encoding);
                 compositor.push(Box::new(overlayed(picker)))
             }
         },
     )
 }
 
+pub fn tree_symbol_picker(cx: &mut Context) {
+    let (view, doc) = current_ref!(cx.editor);
+
+    let (language_server, future) =
+        language_server_with_feature!(cx.editor, doc, "document symbols", |ls| ls
+            .document_symbols(doc.identifier()));
+    let offset_encoding = language_server.offset_encoding();
+    let text = doc.text();
+    let cursor = doc.selection(view.id).primary().cursor(text.slice(..));
+    let cursor = pos_to_lsp_pos(text, cursor, offset_encoding);
+
+    cx.callback(
+        future,
+        move |editor, compositor, response: Option<lsp::DocumentSymbolResponse>| {
+            let symbols = match response {
+                Some(symbols) => symbols,
+                None => return,
+            };
+            let hidden = &editor.hidden_symbol_kinds;
+            let sort = editor.config().symbol_tree.sort;
+            let tree =
+                ui::SymbolTree::new(doc!(editor), symbols, cursor, offset_encoding, hidden, sort);
+            match tree {
+                Some(mut tree) => {
+                    tree.restore_state(editor);
+                    compositor.push(Box::new(overlayed(tree)))
+                }
+                None => editor.set_status("No symbols found"),
+            }
+        },
+    )
+}
+
+/// Opens the outline panel, or focuses it if it's open already.
+pub fn outline(cx: &mut Context) {
+    cx.callback = Some(Box::new(
+        |compositor: &mut Compositor, cx: &mut compositor::Context| {
+            if let Some(editor_view) = compositor.find::<ui::EditorView>() {
+                match editor_view.outline.as_mut() {
+                    Some(outline) => outline.focus(),
+                    None => {
+                        let mut outline = ui::Outline::new();
+                        outline.refresh(cx.editor, cx.jobs);
+                        editor_view.outline = Some(outline);
+                    }
+                }
+            }
+        },
+    ));
+}
+
+pub fn tree_workspace_symbol_picker(cx: &mut Context) {
+    let doc = doc!(cx.editor);
+    let (language_server, future) =
+        language_server_with_feature!(cx.editor, doc, "workspace symbols", |ls| ls
+            .workspace_symbols("".to_string()));
+    let language_server_id = language_server.id();
+
+    cx.callback(
+        future,
+        move |editor, compositor, response: Option<Vec<lsp::SymbolInformation>>| {
+            let symbols = match response {
+                Some(symbols) => symbols,
+                None => return,
+            };
+            // the tree asks the server for the symbols of a file when it's expanded
+            let language_server = match editor
+                .language_servers
+                .iter_clients()
+                .find(|client| client.id() == language_server_id)
+            {
+                Some(language_server) => language_server.clone(),
+                None => return,
+            };
+            match ui::WorkspaceSymbolTree::new(symbols, language_server) {
+                Some(tree) => compositor.push(Box::new(overlayed(tree))),
+                None => editor.set_status("No symbols found"),
+            }
+        },
+    )
+}
+
 pub fn workspace_symbol_picker(cx: &mut Context) {
     let doc = doc!(cx.editor);
     let current_url = doc.url();
-    let language_server = language_server!(cx.editor, doc);
+    let (language_server, future) =
+        language_server_with_feature!(cx.editor, doc, "workspace symbols", |ls| ls
+            .workspace_symbols("".to_string()));
     let offset_encoding = language_server.offset_encoding();
-    let future = match language_server.workspace_symbols("".to_string()) {
-        Some(future) => future,
-        None => {
-            cx.editor
-                .set_error("Language server does not support workspace symbols");
-            return;
-        }
-    };
 
     cx.callback(
         future,
-        move |_editor, compositor, response: Option<Vec<lsp::SymbolInformation>>| {
+        move |editor, compositor, response: Option<Vec<lsp::SymbolInformation>>| {
             if let Some(symbols) = response {
-                let picker = sym_picker(symbols, current_url, offset_encoding);
+                let coverage = flat_symbols_coverage(editor, &symbols);
+                let picker = sym_picker(symbols, current_url, coverage, offset_encoding);
                 compositor.push(Box::new(overlayed(picker)))
             }
         },
@@ -394,9 +585,7 @@ pub fn workspace_symbol_picker(cx: &mut Context) {
 
 pub fn diagnostics_picker(cx: &mut Context) {
     let doc = doc!(cx.editor);
-    let language_server = language_server!(cx.editor, doc);
     if let Some(current_url) = doc.url() {
-        let offset_encoding = language_server.offset_encoding();
         let diagnostics = cx
             .editor
             .diagnostics
@@ -408,7 +597,6 @@ pub fn diagnostics_picker(cx: &mut Context) {
             [(current_url.clone(), diagnostics)].into(),
             Some(current_url),
             DiagnosticsFormat::HideSourcePath,
-            offset_encoding,
         );
         cx.push_layer(Box::new(overlayed(picker)));
     }
@@ -416,16 +604,13 @@ pub fn diagnostics_picker(cx: &mut Context) {
 
 pub fn workspace_diagnostics_picker(cx: &mut Context) {
     let doc = doc!(cx.editor);
-    let language_server = language_server!(cx.editor, doc);
     let current_url = doc.url();
-    let offset_encoding = language_server.offset_encoding();
     let diagnostics = cx.editor.diagnostics.clone();
     let picker = diag_picker(
         cx,
         diagnostics,
         current_url,
         DiagnosticsFormat::ShowSourcePath,
-        offset_encoding,
     );
     cx.push_layer(Box::new(overlayed(picker)));
 }
@@ -500,37 +685,30 @@ fn action_fixes_diagnostics(action: &CodeActionOrCommand) -> bool {
 pub fn code_action(cx: &mut Context) {
     let (view, doc) = current!(cx.editor);
 
-    let language_server = language_server!(cx.editor, doc);
-
     let selection_range = doc.selection(view.id).primary();
-    let offset_encoding = language_server.offset_encoding();
 
-    let range = range_to_lsp_range(doc.text(), selection_range, offset_encoding);
-
-    let future = match language_server.code_actions(
-        doc.identifier(),
-        range,
-        // Filter and convert overlapping diagnostics
-        lsp::CodeActionContext {
-            diagnostics: doc
-                .diagnostics()
-                .iter()
-                .filter(|&diag| {
-                    selection_range
-                        .overlaps(&helix_core::Range::new(diag.range.start, diag.range.end))
-                })
-                .map(|diag| diagnostic_to_lsp_diagnostic(doc.text(), diag, offset_encoding))
-                .collect(),
-            only: None,
-        },
-    ) {
-        Some(future) => future,
-        None => {
-            cx.editor
-                .set_error("Language server does not support code actions");
-            return;
-        }
-    };
+    let (language_server, future) =
+        language_server_with_feature!(cx.editor, doc, "code actions", |ls| {
+            let offset_encoding = ls.offset_encoding();
+            ls.code_actions(
+                doc.identifier(),
+                range_to_lsp_range(doc.text(), selection_range, offset_encoding),
+                // Filter and convert overlapping diagnostics
+                lsp::CodeActionContext {
+                    diagnostics: doc
+                        .diagnostics()
+                        .iter()
+                        .filter(|&diag| {
+                            selection_range
+                                .overlaps(&helix_core::Range::new(diag.range.start, diag.range.end))
+                        })
+                        .map(|diag| diagnostic_to_lsp_diagnostic(doc.text(), diag, offset_encoding))
+                        .collect(),
+                    only: None,
+                },
+            )
+        });
+    let offset_encoding = language_server.offset_encoding();
 
     cx.callback(
         future,
@@ -634,7 +812,25 @@ fn label(&self, _data: &Self::Data) -> Spans {
 
 pub fn execute_lsp_command(editor: &mut Editor, cmd: lsp::Command) {
     let doc = doc!(editor);
-    let language_server = language_server!(editor, doc);
+
+    // prefer the server which advertises the command, there may be several attached
+    let advertises_command = |ls: &helix_lsp::Client| {
+        ls.capabilities()
+            .execute_command_provider
+            .as_ref()
+            .map_or(false, |provider| provider.commands.contains(&cmd.command))
+    };
+    let language_server = match doc
+        .language_servers()
+        .find(|ls| advertises_command(ls))
+        .or_else(|| doc.language_server())
+    {
+        Some(language_server) => language_server,
+        None => {
+            editor.set_status("Language server not active for current buffer");
+            return;
+        }
+    };
 
     // the command is executed on the server and communicated back
     // to the client asynchronously using workspace edits
@@ -712,6 +908,22 @@ pub fn apply_document_resource_op(op: &lsp::ResourceOp) -> std::io::Result<()> {
     }
 }
 
+/// Determines a view for apply/append_changes_to_history of edits to a possibly hidden document.
+fn history_view(doc: &Document, current_view_id: ViewId) -> ViewId {
+    let selections = doc.selections();
+    if selections.contains_key(&current_view_id) {
+        // use current if possible
+        current_view_id
+    } else {
+        // Hack: we take the first available view_id
+        selections
+            .keys()
+            .next()
+            .copied()
+            .expect("No view_id available")
+    }
+}
+
 pub fn apply_workspace_edit(
     editor: &mut Editor,
     offset_encoding: OffsetEncoding,
@@ -740,20 +952,7 @@ pub fn apply_workspace_edit(
         };
 
         let doc = doc_mut!(editor, &doc_id);
-
-        // Need to determine a view for apply/append_changes_to_history
-        let selections = doc.selections();
-        let view_id = if selections.contains_key(&current_view_id) {
-            // use current if possible
-            current_view_id
-        } else {
-            // Hack: we take the first available view_id
-            selections
-                .keys()
-                .next()
-                .copied()
-                .expect("No view_id available")
-        };
+        let view_id = history_view(doc, current_view_id);
 
         let transaction = helix_lsp::util::generate_transaction_from_edits(
             doc.text(),
@@ -831,6 +1030,106 @@ pub fn apply_workspace_edit(
     }
 }
 
+/// The text edits of each file of a workspace edit, `None` if it also creates, renames or
+/// deletes files.
+fn workspace_text_edits(
+    workspace_edit: &lsp::WorkspaceEdit,
+) -> Option<Vec<(lsp::Url, Vec<lsp::TextEdit>)>> {
+    if let Some(ref changes) = workspace_edit.changes {
+        let changes = changes
+            .iter()
+            .map(|(uri, edits)| (uri.clone(), edits.clone()));
+        return Some(changes.collect());
+    }
+
+    let text_document_edit = |document_edit: &lsp::TextDocumentEdit| {
+        let edits = document_edit
+            .edits
+            .iter()
+            .map(|edit| match edit {
+                lsp::OneOf::Left(text_edit) => text_edit,
+                lsp::OneOf::Right(annotated_text_edit) => &annotated_text_edit.text_edit,
+            })
+            .cloned()
+            .collect();
+        (document_edit.text_document.uri.clone(), edits)
+    };
+    match &workspace_edit.document_changes {
+        Some(lsp::DocumentChanges::Edits(document_edits)) => {
+            Some(document_edits.iter().map(text_document_edit).collect())
+        }
+        Some(lsp::DocumentChanges::Operations(operations)) => operations
+            .iter()
+            .map(|operation| match operation {
+                lsp::DocumentChangeOperation::Edit(document_edit) => {
+                    Some(text_document_edit(document_edit))
+                }
+                lsp::DocumentChangeOperation::Op(_) => None,
+            })
+            .collect(),
+        None => Some(Vec::new()),
+    }
+}
+
+/// Applies all edits of the refactoring queue with one undo point per file and empties it.
+/// Nothing is applied if edits overlap or don't fit the files anymore. Returns the number of
+/// changed files.
+pub fn apply_edit_queue(editor: &mut Editor) -> anyhow::Result<usize> {
+    let queue = editor.edit_queue.clone();
+    let mut files: BTreeMap<&lsp::Url, Vec<(&lsp::TextEdit, OffsetEncoding)>> = BTreeMap::new();
+    for queued in &queue {
+        for (uri, edits) in &queued.edits {
+            let file = files.entry(uri).or_default();
+            file.extend(edits.iter().map(|edit| (edit, queued.offset_encoding)));
+        }
+    }
+
+    let mut changes = Vec::with_capacity(files.len());
+    for (uri, edits) in files {
+        let path = uri
+            .to_file_path()
+            .map_err(|_| anyhow!("unable to convert URI to filepath: {}", uri))?;
+        let doc_id = editor.open(&path, Action::Load)?;
+        let text = doc!(editor, &doc_id).text();
+
+        let mut file_changes = Vec::with_capacity(edits.len());
+        for (edit, offset_encoding) in edits {
+            let range = lsp_range_to_range(text, edit.range, offset_encoding)
+                .with_context(|| format!("edits of {} are out of date", path.display()))?;
+            file_changes.push((range.from(), range.to(), edit.new_text.as_str()));
+        }
+        file_changes.sort_unstable();
+        // the same rename may have been staged twice
+        file_changes.dedup();
+        if file_changes
+            .windows(2)
+            .any(|pair| pair[0].1 > pair[1].0 || pair[0].0 == pair[1].0)
+        {
+            bail!("conflicting edits in {}", path.display());
+        }
+        let transaction = Transaction::change(
+            text,
+            file_changes.into_iter().map(|(from, to, new_text)| {
+                // simplify "" into None for cleaner changesets
+                let replacement = (!new_text.is_empty()).then(|| new_text.into());
+                (from, to, replacement)
+            }),
+        );
+        changes.push((doc_id, transaction));
+    }
+
+    let count = changes.len();
+    let current_view_id = view!(editor).id;
+    for (doc_id, transaction) in changes {
+        let doc = doc_mut!(editor, &doc_id);
+        let view = view_mut!(editor, history_view(doc, current_view_id));
+        apply_transaction(&transaction, doc, view);
+        doc.append_changes_to_history(view);
+    }
+    editor.edit_queue.clear();
+    Ok(count)
+}
+
 fn goto_impl(
     editor: &mut Editor,
     compositor: &mut Compositor,
@@ -877,20 +1176,13 @@ fn to_locations(definitions: Option<lsp::GotoDefinitionResponse>) -> Vec<lsp::Lo
 
 pub fn goto_definition(cx: &mut Context) {
     let (view, doc) = current!(cx.editor);
-    let language_server = language_server!(cx.editor, doc);
+    let (language_server, future) =
+        language_server_with_feature!(cx.editor, doc, "goto-definition", |ls| {
+            let pos = doc.position(view.id, ls.offset_encoding());
+            ls.goto_definition(doc.identifier(), pos, None)
+        });
     let offset_encoding = language_server.offset_encoding();
 
-    let pos = doc.position(view.id, offset_encoding);
-
-    let future = match language_server.goto_definition(doc.identifier(), pos, None) {
-        Some(future) => future,
-        None => {
-            cx.editor
-                .set_error("Language server does not support goto-definition");
-            return;
-        }
-    };
-
     cx.callback(
         future,
         move |editor, compositor, response: Option<lsp::GotoDefinitionResponse>| {
@@ -902,20 +1194,13 @@ pub fn goto_definition(cx: &mut Context) {
 
 pub fn goto_type_definition(cx: &mut Context) {
     let (view, doc) = current!(cx.editor);
-    let language_server = language_server!(cx.editor, doc);
+    let (language_server, future) =
+        language_server_with_feature!(cx.editor, doc, "goto-type-definition", |ls| {
+            let pos = doc.position(view.id, ls.offset_encoding());
+            ls.goto_type_definition(doc.identifier(), pos, None)
+        });
     let offset_encoding = language_server.offset_encoding();
 
-    let pos = doc.position(view.id, offset_encoding);
-
-    let future = match language_server.goto_type_definition(doc.identifier(), pos, None) {
-        Some(future) => future,
-        None => {
-            cx.editor
-                .set_error("Language server does not support goto-type-definition");
-            return;
-        }
-    };
-
     cx.callback(
         future,
         move |editor, compositor, response: Option<lsp::GotoDefinitionResponse>| {
@@ -927,20 +1212,13 @@ pub fn goto_type_definition(cx: &mut Context) {
 
 pub fn goto_implementation(cx: &mut Context) {
     let (view, doc) = current!(cx.editor);
-    let language_server = language_server!(cx.editor, doc);
+    let (language_server, future) =
+        language_server_with_feature!(cx.editor, doc, "goto-implementation", |ls| {
+            let pos = doc.position(view.id, ls.offset_encoding());
+            ls.goto_implementation(doc.identifier(), pos, None)
+        });
     let offset_encoding = language_server.offset_encoding();
 
-    let pos = doc.position(view.id, offset_encoding);
-
-    let future = match language_server.goto_implementation(doc.identifier(), pos, None) {
-        Some(future) => future,
-        None => {
-            cx.editor
-                .set_error("Language server does not support goto-implementation");
-            return;
-        }
-    };
-
     cx.callback(
         future,
         move |editor, compositor, response: Option<lsp::GotoDefinitionResponse>| {
@@ -952,20 +1230,13 @@ pub fn goto_implementation(cx: &mut Context) {
 
 pub fn goto_reference(cx: &mut Context) {
     let (view, doc) = current!(cx.editor);
-    let language_server = language_server!(cx.editor, doc);
+    let (language_server, future) =
+        language_server_with_feature!(cx.editor, doc, "goto-reference", |ls| {
+            let pos = doc.position(view.id, ls.offset_encoding());
+            ls.goto_reference(doc.identifier(), pos, None)
+        });
     let offset_encoding = language_server.offset_encoding();
 
-    let pos = doc.position(view.id, offset_encoding);
-
-    let future = match language_server.goto_reference(doc.identifier(), pos, None) {
-        Some(future) => future,
-        None => {
-            cx.editor
-                .set_error("Language server does not support goto-reference");
-            return;
-        }
-    };
-
     cx.callback(
         future,
         move |editor, compositor, response: Option<Vec<lsp::Location>>| {
@@ -989,23 +1260,20 @@ pub fn signature_help_impl(cx: &mut Context, invoked: SignatureHelpInvoked) {
     let (view, doc) = current!(cx.editor);
     let was_manually_invoked = invoked == SignatureHelpInvoked::Manual;
 
-    let language_server = match doc.language_server() {
-        Some(language_server) => language_server,
-        None => {
-            // Do not show the message if signature help was invoked
-            // automatically on backspace, trigger characters, etc.
-            if was_manually_invoked {
-                cx.editor
-                    .set_status("Language server not active for current buffer");
-            }
-            return;
+    if doc.language_servers().next().is_none() {
+        // Do not show the message if signature help was invoked
+        // automatically on backspace, trigger characters, etc.
+        if was_manually_invoked {
+            cx.editor
+                .set_status("Language server not active for current buffer");
         }
-    };
-    let offset_encoding = language_server.offset_encoding();
-
-    let pos = doc.position(view.id, offset_encoding);
+        return;
+    }
 
-    let future = match language_server.text_document_signature_help(doc.identifier(), pos, None) {
+    let future = match doc.language_servers().find_map(|language_server| {
+        let pos = doc.position(view.id, language_server.offset_encoding());
+        language_server.text_document_signature_help(doc.identifier(), pos, None)
+    }) {
         Some(f) => f,
         None => {
             if was_manually_invoked {
@@ -1107,21 +1375,13 @@ pub fn signature_help_impl(cx: &mut Context, invoked: SignatureHelpInvoked) {
 
 pub fn hover(cx: &mut Context) {
     let (view, doc) = current!(cx.editor);
-    let language_server = language_server!(cx.editor, doc);
-    let offset_encoding = language_server.offset_encoding();
 
     // TODO: factor out a doc.position_identifier() that returns lsp::TextDocumentPositionIdentifier
 
-    let pos = doc.position(view.id, offset_encoding);
-
-    let future = match language_server.text_document_hover(doc.identifier(), pos, None) {
-        Some(future) => future,
-        None => {
-            cx.editor
-                .set_error("Language server does not support hover");
-            return;
-        }
-    };
+    let (_, future) = language_server_with_feature!(cx.editor, doc, "hover", |ls| {
+        let pos = doc.position(view.id, ls.offset_encoding());
+        ls.text_document_hover(doc.identifier(), pos, None)
+    });
 
     cx.callback(
         future,
@@ -1163,6 +1423,15 @@ fn marked_string_to_markdown(contents: lsp::MarkedString) -> String {
 }
 
 pub fn rename_symbol(cx: &mut Context) {
+    rename_symbol_impl(cx, false)
+}
+
+pub fn queue_rename_symbol(cx: &mut Context) {
+    rename_symbol_impl(cx, true)
+}
+
+/// Renames the symbol under the cursor, or stages the rename in the refactoring queue.
+fn rename_symbol_impl(cx: &mut Context, queue: bool) {
     let (view, doc) = current_ref!(cx.editor);
     let text = doc.text().slice(..);
     let primary_selection = doc.selection(view.id).primary();
@@ -1173,7 +1442,8 @@ pub fn rename_symbol(cx: &mut Context) {
         textobject_word(text, primary_selection, TextObject::Inside, 1, false)
     }
     .fragment(text)
-    .into();
+    .to_string();
+    let name = prefill.clone();
     ui::prompt_with_input(
         cx,
         "rename-to:".into(),
@@ -1186,23 +1456,34 @@ pub fn rename_symbol(cx: &mut Context) {
             }
 
             let (view, doc) = current!(cx.editor);
-            let language_server = language_server!(cx.editor, doc);
+            let (language_server, future) =
+                language_server_with_feature!(cx.editor, doc, "symbol renaming", |ls| {
+                    let pos = doc.position(view.id, ls.offset_encoding());
+                    ls.rename_symbol(doc.identifier(), pos, input.to_string())
+                });
             let offset_encoding = language_server.offset_encoding();
-
-            let pos = doc.position(view.id, offset_encoding);
-
-            let future =
-                match language_server.rename_symbol(doc.identifier(), pos, input.to_string()) {
-                    Some(future) => future,
-                    None => {
-                        cx.editor
-                            .set_error("Language server does not support symbol renaming");
-                        return;
-                    }
-                };
-            match block_on(future) {
-                Ok(edits) => apply_workspace_edit(cx.editor, offset_encoding, &edits),
-                Err(err) => cx.editor.set_error(err.to_string()),
+            let edit = match block_on(future) {
+                Ok(edit) => edit,
+                Err(err) => return cx.editor.set_error(err.to_string()),
+            };
+            if !queue {
+                return apply_workspace_edit(cx.editor, offset_encoding, &edit);
+            }
+            match workspace_text_edits(&edit) {
+                Some(edits) => {
+                    cx.editor.edit_queue.push(QueuedEdit {
+                        label: format!("Rename {} to {}", name, input),
+                        offset_encoding,
+                        edits,
+                    });
+                    cx.editor.set_status(format!(
+                        "Staged rename, {} queued",
+                        cx.editor.edit_queue.len()
+                    ));
+                }
+                None => cx
+                    .editor
+                    .set_error("Renames which move files can't be queued"),
             }
         },
     );
@@ -1210,21 +1491,13 @@ pub fn rename_symbol(cx: &mut Context) {
 
 pub fn select_references_to_symbol_under_cursor(cx: &mut Context) {
     let (view, doc) = current!(cx.editor);
-    let language_server = language_server!(cx.editor, doc);
+    let (language_server, future) =
+        language_server_with_feature!(cx.editor, doc, "document highlight", |ls| {
+            let pos = doc.position(view.id, ls.offset_encoding());
+            ls.text_document_document_highlight(doc.identifier(), pos, None)
+        });
     let offset_encoding = language_server.offset_encoding();
 
-    let pos = doc.position(view.id, offset_encoding);
-
-    let future = match language_server.text_document_document_highlight(doc.identifier(), pos, None)
-    {
-        Some(future) => future,
-        None => {
-            cx.editor
-                .set_error("Language server does not support document highlight");
-            return;
-        }
-    };
-
     cx.callback(
         future,
         move |editor, _compositor, response: Option<Vec<lsp::DocumentHighlight>>| {
@@ -1233,8 +1506,6 @@ pub fn select_references_to_symbol_under_cursor(cx: &mut Context) {
                 _ => return,
             };
             let (view, doc) = current!(editor);
-            let language_server = language_server!(editor, doc);
-            let offset_encoding = language_server.offset_encoding();
             let text = doc.text();
             let pos = doc.selection(view.id).primary().head;
 
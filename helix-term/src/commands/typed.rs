@@ -6,7 +6,7 @@
 
 use helix_view::{
     apply_transaction,
-    editor::{Action, CloseError, ConfigEvent},
+    editor::{Action, CloseError, ConfigEvent, SplitDirection},
 };
 use ui::completers::{self, Completer};
 
@@ -362,39 +362,31 @@ fn set_indent_style(
         return Ok(());
     }
 
-    use IndentStyle::*;
-
     // If no argument, report current indent style.
     if args.is_empty() {
-        let style = doc!(cx.editor).indent_style;
-        cx.editor.set_status(match style {
-            Tabs => "tabs".to_owned(),
-            Spaces(1) => "1 space".to_owned(),
-            Spaces(n) if (2..=8).contains(&n) => format!("{} spaces", n),
-            _ => unreachable!(), // Shouldn't happen.
-        });
+        let doc = doc!(cx.editor);
+        let status = format!("{} ({})", doc.indent_style, doc.indent_source);
+        cx.editor.set_status(status);
         return Ok(());
     }
 
-    // Attempt to parse argument as an indent style.
-    let style = match args.get(0) {
-        Some(arg) if "tabs".starts_with(&arg.to_lowercase()) => Some(Tabs),
-        Some(Cow::Borrowed("0")) => Some(Tabs),
-        Some(arg) => arg
-            .parse::<u8>()
-            .ok()
-            .filter(|n| (1..=8).contains(n))
-            .map(Spaces),
-        _ => None,
-    };
-
-    let style = style.context("invalid indent style")?;
-    let doc = doc_mut!(cx.editor);
-    doc.indent_style = style;
+    let style = parse_indent_style(&args[0]).context("invalid indent style")?;
+    doc_mut!(cx.editor).set_indent_style(Some(style));
 
     Ok(())
 }
 
+/// Parses 't' or '0' for tabs or 1-8 for the number of spaces.
+fn parse_indent_style(arg: &str) -> Option<IndentStyle> {
+    if arg == "0" || "tabs".starts_with(&arg.to_lowercase()) {
+        return Some(IndentStyle::Tabs);
+    }
+    arg.parse::<u8>()
+        .ok()
+        .filter(|n| (1..=8).contains(n))
+        .map(IndentStyle::Spaces)
+}
+
 /// Sets or reports the current document's line ending setting.
 fn set_line_ending(
     cx: &mut compositor::Context,
@@ -489,6 +481,28 @@ fn earlier(
     Ok(())
 }
 
+fn undo_tree(
+    cx: &mut compositor::Context,
+    _args: &[Cow<str>],
+    event: PromptEvent,
+) -> anyhow::Result<()> {
+    if event != PromptEvent::Validate {
+        return Ok(());
+    }
+
+    // the tree isn't `Send`, so it's built in the callback
+    let callback = async move {
+        let call: job::Callback = Callback::EditorCompositor(Box::new(
+            move |editor: &mut Editor, compositor: &mut Compositor| {
+                compositor.push(Box::new(overlayed(ui::UndoTree::new(editor))))
+            },
+        ));
+        Ok(call)
+    };
+    cx.jobs.callback(callback);
+    Ok(())
+}
+
 fn later(
     cx: &mut compositor::Context,
     args: &[Cow<str>],
@@ -586,6 +600,10 @@ pub fn write_all_impl(
                 }
                 return None;
             }
+            if doc.is_binary() {
+                errors.push("cannot write a binary file as text\n");
+                return None;
+            }
 
             // Look for a view to apply the formatting change to. If the document
             // is in the current view, just use that. Otherwise, since we don't
@@ -1115,26 +1133,31 @@ fn lsp_workspace_command(
 
     let (_, doc) = current!(cx.editor);
 
-    let language_server = match doc.language_server() {
-        Some(language_server) => language_server,
-        None => {
-            cx.editor
-                .set_status("Language server not active for current buffer");
-            return Ok(());
-        }
-    };
+    if doc.language_servers().next().is_none() {
+        cx.editor
+            .set_status("Language server not active for current buffer");
+        return Ok(());
+    }
 
-    let options = match &language_server.capabilities().execute_command_provider {
-        Some(options) => options,
-        None => {
-            cx.editor
-                .set_status("Workspace commands are not supported for this language server");
-            return Ok(());
-        }
-    };
+    // workspace commands of all attached servers, `execute_lsp_command` routes each
+    // command back to the server which advertises it
+    let available_commands: Vec<String> = doc
+        .language_servers()
+        .filter_map(|language_server| {
+            language_server
+                .capabilities()
+                .execute_command_provider
+                .as_ref()
+        })
+        .flat_map(|options| options.commands.iter().cloned())
+        .collect();
+    if available_commands.is_empty() {
+        cx.editor
+            .set_status("Workspace commands are not supported for this language server");
+        return Ok(());
+    }
     if args.is_empty() {
-        let commands = options
-            .commands
+        let commands = available_commands
             .iter()
             .map(|command| helix_lsp::lsp::Command {
                 title: command.clone(),
@@ -1156,7 +1179,7 @@ fn lsp_workspace_command(
         cx.jobs.callback(callback);
     } else {
         let command = args.join(" ");
-        if options.commands.iter().any(|c| c == &command) {
+        if available_commands.contains(&command) {
             execute_lsp_command(
                 cx.editor,
                 helix_lsp::lsp::Command {
@@ -1175,41 +1198,54 @@ fn lsp_workspace_command(
     Ok(())
 }
 
-fn lsp_restart(
+fn call_hierarchy(
     cx: &mut compositor::Context,
-    _args: &[Cow<str>],
-    event: PromptEvent,
+    direction: ui::CallDirection,
 ) -> anyhow::Result<()> {
-    if event != PromptEvent::Validate {
+    let (view, doc) = current_ref!(cx.editor);
+    if doc.language_servers().next().is_none() {
+        cx.editor
+            .set_status("Language server not active for current buffer");
         return Ok(());
     }
-
-    let (_view, doc) = current!(cx.editor);
-    let config = doc
-        .language_config()
-        .context("LSP not defined for the current document")?;
-
-    let scope = config.scope.clone();
-    cx.editor.language_servers.restart(config, doc.path())?;
-
-    // This collect is needed because refresh_language_server would need to re-borrow editor.
-    let document_ids_to_refresh: Vec<DocumentId> = cx
-        .editor
-        .documents()
-        .filter_map(|doc| match doc.language_config() {
-            Some(config) if config.scope.eq(&scope) => Some(doc.id()),
-            _ => None,
+    let (language_server_id, future) = doc
+        .language_servers()
+        .find_map(|language_server| {
+            let pos = doc.position(view.id, language_server.offset_encoding());
+            language_server
+                .prepare_call_hierarchy(doc.identifier(), pos)
+                .map(|future| (language_server.id(), future))
         })
-        .collect();
-
-    for document_id in document_ids_to_refresh {
-        cx.editor.refresh_language_server(document_id);
-    }
+        .ok_or_else(|| anyhow!("Language server does not support call hierarchy"))?;
+    // the tree requests the calls of an item when it's expanded
+    let language_server = cx
+        .editor
+        .language_servers
+        .iter_clients()
+        .find(|client| client.id() == language_server_id)
+        .cloned()
+        .ok_or_else(|| anyhow!("Language server is not running"))?;
 
+    let callback = async move {
+        let items: Option<Vec<helix_lsp::lsp::CallHierarchyItem>> =
+            serde_json::from_value(future.await?)?;
+        let call: job::Callback = Callback::EditorCompositor(Box::new(
+            move |editor: &mut Editor, compositor: &mut Compositor| match ui::CallHierarchy::new(
+                items.unwrap_or_default(),
+                direction,
+                language_server,
+            ) {
+                Some(tree) => compositor.push(Box::new(overlayed(tree))),
+                None => editor.set_status("No call hierarchy for the symbol under the cursor"),
+            },
+        ));
+        Ok(call)
+    };
+    cx.jobs.callback(callback);
     Ok(())
 }
 
-fn tree_sitter_scopes(
+fn call_hierarchy_incoming(
     cx: &mut compositor::Context,
     _args: &[Cow<str>],
     event: PromptEvent,
@@ -1218,77 +1254,124 @@ fn tree_sitter_scopes(
         return Ok(());
     }
 
-    let (view, doc) = current!(cx.editor);
-    let text = doc.text().slice(..);
+    call_hierarchy(cx, ui::CallDirection::Incoming)
+}
 
-    let pos = doc.selection(view.id).primary().cursor(text);
-    let scopes = indent::get_scopes(doc.syntax(), text, pos);
+fn call_hierarchy_outgoing(
+    cx: &mut compositor::Context,
+    _args: &[Cow<str>],
+    event: PromptEvent,
+) -> anyhow::Result<()> {
+    if event != PromptEvent::Validate {
+        return Ok(());
+    }
 
-    let contents = format!("```json\n{:?}\n````", scopes);
+    call_hierarchy(cx, ui::CallDirection::Outgoing)
+}
+
+fn type_hierarchy(
+    cx: &mut compositor::Context,
+    direction: ui::TypeDirection,
+) -> anyhow::Result<()> {
+    let (view, doc) = current_ref!(cx.editor);
+    let language_server = match doc.language_servers().next() {
+        Some(language_server) => language_server,
+        None => {
+            cx.editor
+                .set_status("Language server not active for current buffer");
+            return Ok(());
+        }
+    };
+    let pos = doc.position(view.id, language_server.offset_encoding());
+    let future = language_server.prepare_type_hierarchy(doc.identifier(), pos);
+    // the tree requests the supertypes or subtypes of a type when it's expanded
+    let language_server_id = language_server.id();
+    let language_server = cx
+        .editor
+        .language_servers
+        .iter_clients()
+        .find(|client| client.id() == language_server_id)
+        .cloned()
+        .ok_or_else(|| anyhow!("Language server is not running"))?;
 
     let callback = async move {
+        let items: Option<Vec<helix_lsp::lsp::TypeHierarchyItem>> =
+            serde_json::from_value(future.await?)?;
         let call: job::Callback = Callback::EditorCompositor(Box::new(
-            move |editor: &mut Editor, compositor: &mut Compositor| {
-                let contents = ui::Markdown::new(contents, editor.syn_loader.clone());
-                let popup = Popup::new("hover", contents).auto_close(true);
-                compositor.replace_or_push("hover", popup);
+            move |editor: &mut Editor, compositor: &mut Compositor| match ui::TypeHierarchy::new(
+                items.unwrap_or_default(),
+                direction,
+                language_server,
+            ) {
+                Some(tree) => compositor.push(Box::new(overlayed(tree))),
+                None => editor.set_status("No type hierarchy for the symbol under the cursor"),
             },
         ));
         Ok(call)
     };
-
     cx.jobs.callback(callback);
-
     Ok(())
 }
 
-fn vsplit(
+fn type_hierarchy_supertypes(
     cx: &mut compositor::Context,
-    args: &[Cow<str>],
+    _args: &[Cow<str>],
     event: PromptEvent,
 ) -> anyhow::Result<()> {
     if event != PromptEvent::Validate {
         return Ok(());
     }
 
-    let id = view!(cx.editor).doc;
+    type_hierarchy(cx, ui::TypeDirection::Supertypes)
+}
 
-    if args.is_empty() {
-        cx.editor.switch(id, Action::VerticalSplit);
-    } else {
-        for arg in args {
-            cx.editor
-                .open(&PathBuf::from(arg.as_ref()), Action::VerticalSplit)?;
-        }
+fn type_hierarchy_subtypes(
+    cx: &mut compositor::Context,
+    _args: &[Cow<str>],
+    event: PromptEvent,
+) -> anyhow::Result<()> {
+    if event != PromptEvent::Validate {
+        return Ok(());
     }
 
-    Ok(())
+    type_hierarchy(cx, ui::TypeDirection::Subtypes)
 }
 
-fn hsplit(
+fn lsp_restart(
     cx: &mut compositor::Context,
-    args: &[Cow<str>],
+    _args: &[Cow<str>],
     event: PromptEvent,
 ) -> anyhow::Result<()> {
     if event != PromptEvent::Validate {
         return Ok(());
     }
 
-    let id = view!(cx.editor).doc;
+    let (_view, doc) = current!(cx.editor);
+    let config = doc
+        .language_config()
+        .context("LSP not defined for the current document")?;
 
-    if args.is_empty() {
-        cx.editor.switch(id, Action::HorizontalSplit);
-    } else {
-        for arg in args {
-            cx.editor
-                .open(&PathBuf::from(arg.as_ref()), Action::HorizontalSplit)?;
-        }
+    let scope = config.scope.clone();
+    cx.editor.language_servers.restart(config, doc.path())?;
+
+    // This collect is needed because refresh_language_server would need to re-borrow editor.
+    let document_ids_to_refresh: Vec<DocumentId> = cx
+        .editor
+        .documents()
+        .filter_map(|doc| match doc.language_config() {
+            Some(config) if config.scope.eq(&scope) => Some(doc.id()),
+            _ => None,
+        })
+        .collect();
+
+    for document_id in document_ids_to_refresh {
+        cx.editor.refresh_language_server(document_id);
     }
 
     Ok(())
 }
 
-fn vsplit_new(
+fn buffers_tree(
     cx: &mut compositor::Context,
     _args: &[Cow<str>],
     event: PromptEvent,
@@ -1297,12 +1380,20 @@ fn vsplit_new(
         return Ok(());
     }
 
-    cx.editor.new_file(Action::VerticalSplit);
-
+    let callback = async move {
+        let call: job::Callback = Callback::EditorCompositor(Box::new(
+            move |editor: &mut Editor, compositor: &mut Compositor| {
+                let tree = ui::BuffersTree::new(editor);
+                compositor.push(Box::new(overlayed(tree)));
+            },
+        ));
+        Ok(call)
+    };
+    cx.jobs.callback(callback);
     Ok(())
 }
 
-fn hsplit_new(
+fn jumplist_tree(
     cx: &mut compositor::Context,
     _args: &[Cow<str>],
     event: PromptEvent,
@@ -1311,38 +1402,65 @@ fn hsplit_new(
         return Ok(());
     }
 
-    cx.editor.new_file(Action::HorizontalSplit);
-
+    let callback = async move {
+        let call: job::Callback = Callback::EditorCompositor(Box::new(
+            move |editor: &mut Editor, compositor: &mut Compositor| {
+                let tree = ui::JumplistTree::new(editor);
+                compositor.push(Box::new(overlayed(tree)));
+            },
+        ));
+        Ok(call)
+    };
+    cx.jobs.callback(callback);
     Ok(())
 }
 
-fn debug_eval(
+fn syntax_metrics(
     cx: &mut compositor::Context,
-    args: &[Cow<str>],
+    _args: &[Cow<str>],
     event: PromptEvent,
 ) -> anyhow::Result<()> {
     if event != PromptEvent::Validate {
         return Ok(());
     }
 
-    if let Some(debugger) = cx.editor.debugger.as_mut() {
-        let (frame, thread_id) = match (debugger.active_frame, debugger.thread_id) {
-            (Some(frame), Some(thread_id)) => (frame, thread_id),
-            _ => {
-                bail!("Cannot find current stack frame to access variables")
-            }
-        };
+    let callback = async move {
+        let call: job::Callback = Callback::EditorCompositor(Box::new(
+            move |editor: &mut Editor, compositor: &mut Compositor| {
+                let tree = ui::SyntaxMetricsTree::new(editor);
+                compositor.push(Box::new(overlayed(tree)));
+            },
+        ));
+        Ok(call)
+    };
+    cx.jobs.callback(callback);
+    Ok(())
+}
 
-        // TODO: support no frame_id
+/// Maps the languages to their grammars. Arguments that aren't languages are taken as grammars.
+fn grammar_ids(editor: &Editor, args: &[Cow<str>]) -> Vec<String> {
+    args.iter()
+        .map(|arg| {
+            editor
+                .syn_loader
+                .language_config_for_language_id(arg)
+                .and_then(|config| config.grammar.clone())
+                .unwrap_or_else(|| arg.to_string())
+        })
+        .collect()
+}
 
-        let frame_id = debugger.stack_frames[&thread_id][frame].id;
-        let response = helix_lsp::block_on(debugger.eval(args.join(" "), Some(frame_id)))?;
-        cx.editor.set_status(response.result);
-    }
-    Ok(())
+fn grammar_job(
+    cx: &mut compositor::Context,
+    args: &[Cow<str>],
+    kind: ui::GrammarJob,
+) -> anyhow::Result<()> {
+    let ids = grammar_ids(cx.editor, args);
+    ui::GrammarsTree::spawn_job(kind, &ids, cx)?;
+    grammar_status(cx, &[], PromptEvent::Validate)
 }
 
-fn debug_start(
+fn grammar_fetch(
     cx: &mut compositor::Context,
     args: &[Cow<str>],
     event: PromptEvent,
@@ -1350,16 +1468,10 @@ fn debug_start(
     if event != PromptEvent::Validate {
         return Ok(());
     }
-
-    let mut args = args.to_owned();
-    let name = match args.len() {
-        0 => None,
-        _ => Some(args.remove(0)),
-    };
-    dap_start_impl(cx, name.as_deref(), None, Some(args))
+    grammar_job(cx, args, ui::GrammarJob::Fetch)
 }
 
-fn debug_remote(
+fn grammar_build(
     cx: &mut compositor::Context,
     args: &[Cow<str>],
     event: PromptEvent,
@@ -1367,20 +1479,10 @@ fn debug_remote(
     if event != PromptEvent::Validate {
         return Ok(());
     }
-
-    let mut args = args.to_owned();
-    let address = match args.len() {
-        0 => None,
-        _ => Some(args.remove(0).parse()?),
-    };
-    let name = match args.len() {
-        0 => None,
-        _ => Some(args.remove(0)),
-    };
-    dap_start_impl(cx, name.as_deref(), address, Some(args))
+    grammar_job(cx, args, ui::GrammarJob::Build)
 }
 
-fn tutor(
+fn grammar_status(
     cx: &mut compositor::Context,
     _args: &[Cow<str>],
     event: PromptEvent,
@@ -1389,21 +1491,412 @@ fn tutor(
         return Ok(());
     }
 
-    let path = helix_loader::runtime_dir().join("tutor");
-    cx.editor.open(&path, Action::Replace)?;
-    // Unset path to prevent accidentally saving to the original tutor file.
-    doc_mut!(cx.editor).set_path(None)?;
+    let callback = async move {
+        let call: job::Callback = Callback::EditorCompositor(Box::new(
+            move |_editor: &mut Editor, compositor: &mut Compositor| {
+                compositor.push(Box::new(overlayed(ui::GrammarsTree::new())));
+            },
+        ));
+        Ok(call)
+    };
+    cx.jobs.callback(callback);
     Ok(())
 }
 
-pub(super) fn goto_line_number(
+/// A row of the `:keymap-conflicts` tree.
+enum ConflictRow {
+    /// A mode, or the tree keys, with the number of conflicts in it.
+    Group(String, usize),
+    Conflict(crate::keymap::conflicts::Conflict),
+}
+
+fn keymap_conflicts(
     cx: &mut compositor::Context,
-    args: &[Cow<str>],
+    _args: &[Cow<str>],
     event: PromptEvent,
 ) -> anyhow::Result<()> {
-    match event {
-        PromptEvent::Abort => {
-            if let Some(line_number) = cx.editor.last_line_number {
+    use crate::keymap::conflicts::{find_conflicts, format_keys, ConflictKind};
+    use tui::text::{Span, Spans};
+
+    if event != PromptEvent::Validate {
+        return Ok(());
+    }
+
+    let callback = async move {
+        let call: job::Callback = Callback::EditorCompositor(Box::new(
+            move |editor: &mut Editor, compositor: &mut Compositor| {
+                let conflicts = match compositor.find::<ui::EditorView>() {
+                    Some(editor_view) => find_conflicts(
+                        &editor_view.keymaps.map(),
+                        editor_view.keymaps.layers(),
+                        &editor.tree_keymap,
+                    ),
+                    None => return,
+                };
+                if conflicts.is_empty() {
+                    editor.set_status("No keybinding conflicts");
+                    return;
+                }
+
+                let mut groups: Vec<(String, Vec<ui::TreePickerNode<ConflictRow>>)> = Vec::new();
+                for conflict in conflicts {
+                    let index = match groups
+                        .iter()
+                        .position(|(group, _)| *group == conflict.group)
+                    {
+                        Some(index) => index,
+                        None => {
+                            groups.push((conflict.group.clone(), Vec::new()));
+                            groups.len() - 1
+                        }
+                    };
+                    groups[index]
+                        .1
+                        .push(ui::TreePickerNode::new(ConflictRow::Conflict(conflict)));
+                }
+                let nodes = groups
+                    .into_iter()
+                    .map(|(group, children)| {
+                        ui::TreePickerNode::new(ConflictRow::Group(group, children.len()))
+                            .with_children(children)
+                    })
+                    .collect();
+
+                let picker = ui::TreePicker::new(
+                    nodes,
+                    |row, theme| match row {
+                        ConflictRow::Group(group, count) => Spans::from(vec![
+                            Span::styled(group.as_str(), theme.get("ui.text.focus")),
+                            Span::styled(format!(" ({})", count), theme.get("comment")),
+                        ]),
+                        ConflictRow::Conflict(conflict) => {
+                            let kind = match conflict.kind {
+                                ConflictKind::Shadowed => {
+                                    Span::styled("shadowed", theme.get("warning"))
+                                }
+                                ConflictKind::Unreachable => {
+                                    Span::styled("unreachable", theme.get("error"))
+                                }
+                            };
+                            Spans::from(vec![
+                                Span::styled(format_keys(&conflict.keys), theme.get("keyword")),
+                                Span::raw(" "),
+                                Span::styled(conflict.command.as_str(), theme.get("function")),
+                                Span::raw(" "),
+                                kind,
+                                Span::styled(
+                                    format!(": {}", conflict.reason),
+                                    theme.get("comment"),
+                                ),
+                            ])
+                        }
+                    },
+                    |cx, row, _action| {
+                        if let ConflictRow::Conflict(conflict) = row {
+                            cx.editor.set_status(format!(
+                                "{} ({}): {}",
+                                format_keys(&conflict.keys),
+                                conflict.command,
+                                conflict.reason
+                            ));
+                        }
+                    },
+                )
+                .with_title("Keymap conflicts");
+                compositor.push(Box::new(overlayed(picker)));
+            },
+        ));
+        Ok(call)
+    };
+    cx.jobs.callback(callback);
+    Ok(())
+}
+
+fn diagnostics_tree(
+    cx: &mut compositor::Context,
+    _args: &[Cow<str>],
+    event: PromptEvent,
+) -> anyhow::Result<()> {
+    if event != PromptEvent::Validate {
+        return Ok(());
+    }
+
+    let callback = async move {
+        let call: job::Callback = Callback::EditorCompositor(Box::new(
+            move |editor: &mut Editor, compositor: &mut Compositor| {
+                let tree = ui::DiagnosticsTree::new(editor);
+                compositor.push(Box::new(overlayed(tree)));
+            },
+        ));
+        Ok(call)
+    };
+    cx.jobs.callback(callback);
+    Ok(())
+}
+
+fn lsp_status(
+    cx: &mut compositor::Context,
+    _args: &[Cow<str>],
+    event: PromptEvent,
+) -> anyhow::Result<()> {
+    if event != PromptEvent::Validate {
+        return Ok(());
+    }
+
+    let doc = doc!(cx.editor);
+    let config = doc
+        .language_config()
+        .context("LSP not defined for the current document")?;
+    let (ls_config, override_dir) = config
+        .language_server_for(doc.path().map(|p| p.as_path()))
+        .context("No language server configured for the current document")?;
+
+    let attached: Vec<_> = doc
+        .language_server_ids()
+        .into_iter()
+        .filter_map(|id| cx.editor.language_servers.get_by_id(id))
+        .collect();
+    let describe = |ls_config: &helix_core::syntax::LanguageServerConfiguration| {
+        let status = match attached
+            .iter()
+            .find(|client| client.name() == ls_config.command)
+        {
+            Some(client) if client.is_initialized() => "running",
+            Some(_) => "initializing",
+            None => "not started",
+        };
+        let command = std::iter::once(ls_config.command.as_str())
+            .chain(ls_config.args.iter().map(String::as_str))
+            .collect::<Vec<_>>()
+            .join(" ");
+        format!("`{}` ({})", command, status)
+    };
+    let source = match &override_dir {
+        Some(dir) => format!("override for `{}`", dir.display()),
+        None => "language default".to_string(),
+    };
+
+    let mut contents = format!(
+        "**{}**\n\n- primary: {}\n- source: {}",
+        config.language_id,
+        describe(ls_config),
+        source
+    );
+    for ls_config in &config.additional_language_servers {
+        contents.push_str(&format!("\n- additional: {}", describe(ls_config)));
+    }
+
+    let callback = async move {
+        let call: job::Callback = Callback::EditorCompositor(Box::new(
+            move |editor: &mut Editor, compositor: &mut Compositor| {
+                let contents = ui::Markdown::new(contents, editor.syn_loader.clone());
+                let popup = Popup::new("lsp-status", contents).auto_close(true);
+                compositor.replace_or_push("lsp-status", popup);
+            },
+        ));
+        Ok(call)
+    };
+
+    cx.jobs.callback(callback);
+
+    Ok(())
+}
+
+fn tree_sitter_scopes(
+    cx: &mut compositor::Context,
+    _args: &[Cow<str>],
+    event: PromptEvent,
+) -> anyhow::Result<()> {
+    if event != PromptEvent::Validate {
+        return Ok(());
+    }
+
+    let (view, doc) = current!(cx.editor);
+    let text = doc.text().slice(..);
+
+    let pos = doc.selection(view.id).primary().cursor(text);
+    let scopes = indent::get_scopes(doc.syntax(), text, pos);
+
+    let contents = format!("```json\n{:?}\n````", scopes);
+
+    let callback = async move {
+        let call: job::Callback = Callback::EditorCompositor(Box::new(
+            move |editor: &mut Editor, compositor: &mut Compositor| {
+                let contents = ui::Markdown::new(contents, editor.syn_loader.clone());
+                let popup = Popup::new("hover", contents).auto_close(true);
+                compositor.replace_or_push("hover", popup);
+            },
+        ));
+        Ok(call)
+    };
+
+    cx.jobs.callback(callback);
+
+    Ok(())
+}
+
+fn vsplit(
+    cx: &mut compositor::Context,
+    args: &[Cow<str>],
+    event: PromptEvent,
+) -> anyhow::Result<()> {
+    if event != PromptEvent::Validate {
+        return Ok(());
+    }
+
+    let id = view!(cx.editor).doc;
+
+    if args.is_empty() {
+        cx.editor.switch(id, Action::VerticalSplit);
+    } else {
+        for arg in args {
+            cx.editor
+                .open(&PathBuf::from(arg.as_ref()), Action::VerticalSplit)?;
+        }
+    }
+
+    Ok(())
+}
+
+fn hsplit(
+    cx: &mut compositor::Context,
+    args: &[Cow<str>],
+    event: PromptEvent,
+) -> anyhow::Result<()> {
+    if event != PromptEvent::Validate {
+        return Ok(());
+    }
+
+    let id = view!(cx.editor).doc;
+
+    if args.is_empty() {
+        cx.editor.switch(id, Action::HorizontalSplit);
+    } else {
+        for arg in args {
+            cx.editor
+                .open(&PathBuf::from(arg.as_ref()), Action::HorizontalSplit)?;
+        }
+    }
+
+    Ok(())
+}
+
+fn vsplit_new(
+    cx: &mut compositor::Context,
+    _args: &[Cow<str>],
+    event: PromptEvent,
+) -> anyhow::Result<()> {
+    if event != PromptEvent::Validate {
+        return Ok(());
+    }
+
+    cx.editor.new_file(Action::VerticalSplit);
+
+    Ok(())
+}
+
+fn hsplit_new(
+    cx: &mut compositor::Context,
+    _args: &[Cow<str>],
+    event: PromptEvent,
+) -> anyhow::Result<()> {
+    if event != PromptEvent::Validate {
+        return Ok(());
+    }
+
+    cx.editor.new_file(Action::HorizontalSplit);
+
+    Ok(())
+}
+
+fn debug_eval(
+    cx: &mut compositor::Context,
+    args: &[Cow<str>],
+    event: PromptEvent,
+) -> anyhow::Result<()> {
+    if event != PromptEvent::Validate {
+        return Ok(());
+    }
+
+    if let Some(debugger) = cx.editor.debugger.as_mut() {
+        let (frame, thread_id) = match (debugger.active_frame, debugger.thread_id) {
+            (Some(frame), Some(thread_id)) => (frame, thread_id),
+            _ => {
+                bail!("Cannot find current stack frame to access variables")
+            }
+        };
+
+        // TODO: support no frame_id
+
+        let frame_id = debugger.stack_frames[&thread_id][frame].id;
+        let response = helix_lsp::block_on(debugger.eval(args.join(" "), Some(frame_id)))?;
+        cx.editor.set_status(response.result);
+    }
+    Ok(())
+}
+
+fn debug_start(
+    cx: &mut compositor::Context,
+    args: &[Cow<str>],
+    event: PromptEvent,
+) -> anyhow::Result<()> {
+    if event != PromptEvent::Validate {
+        return Ok(());
+    }
+
+    let mut args = args.to_owned();
+    let name = match args.len() {
+        0 => None,
+        _ => Some(args.remove(0)),
+    };
+    dap_start_impl(cx, name.as_deref(), None, Some(args))
+}
+
+fn debug_remote(
+    cx: &mut compositor::Context,
+    args: &[Cow<str>],
+    event: PromptEvent,
+) -> anyhow::Result<()> {
+    if event != PromptEvent::Validate {
+        return Ok(());
+    }
+
+    let mut args = args.to_owned();
+    let address = match args.len() {
+        0 => None,
+        _ => Some(args.remove(0).parse()?),
+    };
+    let name = match args.len() {
+        0 => None,
+        _ => Some(args.remove(0)),
+    };
+    dap_start_impl(cx, name.as_deref(), address, Some(args))
+}
+
+fn tutor(
+    cx: &mut compositor::Context,
+    _args: &[Cow<str>],
+    event: PromptEvent,
+) -> anyhow::Result<()> {
+    if event != PromptEvent::Validate {
+        return Ok(());
+    }
+
+    let path = helix_loader::runtime_dir().join("tutor");
+    cx.editor.open(&path, Action::Replace)?;
+    // Unset path to prevent accidentally saving to the original tutor file.
+    doc_mut!(cx.editor).set_path(None)?;
+    Ok(())
+}
+
+pub(super) fn goto_line_number(
+    cx: &mut compositor::Context,
+    args: &[Cow<str>],
+    event: PromptEvent,
+) -> anyhow::Result<()> {
+    match event {
+        PromptEvent::Abort => {
+            if let Some(line_number) = cx.editor.last_line_number {
                 goto_line_impl(cx.editor, NonZeroUsize::new(line_number));
                 let (view, doc) = current!(cx.editor);
                 view.ensure_cursor_in_view(doc, line_number);
@@ -1503,6 +1996,53 @@ fn set_option(
     Ok(())
 }
 
+/// Override a config option in the current view only, e.g. `:set-local line-number relative`.
+/// Without a value the option follows the global config again.
+fn set_local_option(
+    cx: &mut compositor::Context,
+    args: &[Cow<str>],
+    event: PromptEvent,
+) -> anyhow::Result<()> {
+    if event != PromptEvent::Validate {
+        return Ok(());
+    }
+
+    if args.is_empty() || args.len() > 2 {
+        anyhow::bail!("Bad arguments. Usage: `:set-local key [field]`");
+    }
+    let key = args[0].to_lowercase();
+    let value = args.get(1).map(|arg| arg.as_ref());
+
+    // the indent belongs to the document rather than the view
+    if key == "indent" {
+        let style = value
+            .map(|value| parse_indent_style(value).context("invalid indent style"))
+            .transpose()?;
+        doc_mut!(cx.editor).set_indent_style(style);
+        return Ok(());
+    }
+
+    view_mut!(cx.editor).local_options.set(&key, value)
+}
+
+/// Bind the scrolling of the current view to the other views with `:scrollbind`, or unbind it.
+fn scrollbind(
+    cx: &mut compositor::Context,
+    _args: &[Cow<str>],
+    event: PromptEvent,
+) -> anyhow::Result<()> {
+    if event != PromptEvent::Validate {
+        return Ok(());
+    }
+
+    if cx.editor.toggle_scroll_bind() {
+        cx.editor.set_status("Scroll binding enabled");
+    } else {
+        cx.editor.set_status("Scroll binding disabled");
+    }
+    Ok(())
+}
+
 /// Change the language of the current buffer at runtime.
 fn language(
     cx: &mut compositor::Context,
@@ -1531,6 +2071,28 @@ fn language(
     Ok(())
 }
 
+fn language_detection(
+    cx: &mut compositor::Context,
+    _args: &[Cow<str>],
+    event: PromptEvent,
+) -> anyhow::Result<()> {
+    if event != PromptEvent::Validate {
+        return Ok(());
+    }
+
+    let doc = doc!(cx.editor);
+    let language = doc.language_config().map(|config| &config.language_id);
+    let status = match (language, &doc.language_detection) {
+        (Some(language), Some(detection)) => {
+            format!("{} was detected by {}", language, detection)
+        }
+        (Some(language), None) => format!("{} was set with :set-language", language),
+        (None, _) => "No language was detected".to_string(),
+    };
+    cx.editor.set_status(status);
+    Ok(())
+}
+
 fn sort(cx: &mut compositor::Context, args: &[Cow<str>], event: PromptEvent) -> anyhow::Result<()> {
     if event != PromptEvent::Validate {
         return Ok(());
@@ -1623,14 +2185,216 @@ fn reflow(
         (range.from(), range.to(), Some(reflowed_text))
     });
 
-    apply_transaction(&transaction, doc, view);
-    doc.append_changes_to_history(view);
-    view.ensure_cursor_in_view(doc, scrolloff);
+    apply_transaction(&transaction, doc, view);
+    doc.append_changes_to_history(view);
+    view.ensure_cursor_in_view(doc, scrolloff);
+
+    Ok(())
+}
+
+fn tree_sitter_tree(
+    cx: &mut compositor::Context,
+    _args: &[Cow<str>],
+    event: PromptEvent,
+) -> anyhow::Result<()> {
+    if event != PromptEvent::Validate {
+        return Ok(());
+    }
+
+    if doc!(cx.editor).syntax().is_none() {
+        bail!("Syntax information is not available");
+    }
+    let callback = async move {
+        let call: job::Callback = Callback::EditorCompositor(Box::new(
+            move |editor: &mut Editor, compositor: &mut Compositor| {
+                let tree = ui::SyntaxTreeView::new(editor);
+                compositor.push(Box::new(overlayed(tree)));
+            },
+        ));
+        Ok(call)
+    };
+    cx.jobs.callback(callback);
+    Ok(())
+}
+
+fn tree_sitter_subtree(
+    cx: &mut compositor::Context,
+    _args: &[Cow<str>],
+    event: PromptEvent,
+) -> anyhow::Result<()> {
+    if event != PromptEvent::Validate {
+        return Ok(());
+    }
+
+    let (view, doc) = current!(cx.editor);
+
+    if let Some(syntax) = doc.syntax() {
+        let primary_selection = doc.selection(view.id).primary();
+        let text = doc.text();
+        let from = text.char_to_byte(primary_selection.from());
+        let to = text.char_to_byte(primary_selection.to());
+        if let Some(selected_node) = syntax
+            .tree()
+            .root_node()
+            .descendant_for_byte_range(from, to)
+        {
+            let mut contents = String::from("```tsq\n");
+            helix_core::syntax::pretty_print_tree(&mut contents, selected_node)?;
+            contents.push_str("\n```");
+
+            let callback = async move {
+                let call: job::Callback = Callback::EditorCompositor(Box::new(
+                    move |editor: &mut Editor, compositor: &mut Compositor| {
+                        let contents = ui::Markdown::new(contents, editor.syn_loader.clone());
+                        let popup = Popup::new("hover", contents).auto_close(true);
+                        compositor.replace_or_push("hover", popup);
+                    },
+                ));
+                Ok(call)
+            };
+
+            cx.jobs.callback(callback);
+        }
+    }
+
+    Ok(())
+}
+
+fn open_config(
+    cx: &mut compositor::Context,
+    _args: &[Cow<str>],
+    event: PromptEvent,
+) -> anyhow::Result<()> {
+    if event != PromptEvent::Validate {
+        return Ok(());
+    }
+
+    cx.editor
+        .open(&helix_loader::config_file(), Action::Replace)?;
+    Ok(())
+}
+
+fn open_log(
+    cx: &mut compositor::Context,
+    _args: &[Cow<str>],
+    event: PromptEvent,
+) -> anyhow::Result<()> {
+    if event != PromptEvent::Validate {
+        return Ok(());
+    }
+
+    cx.editor.open(&helix_loader::log_file(), Action::Replace)?;
+    Ok(())
+}
+
+fn refresh_config(
+    cx: &mut compositor::Context,
+    _args: &[Cow<str>],
+    event: PromptEvent,
+) -> anyhow::Result<()> {
+    if event != PromptEvent::Validate {
+        return Ok(());
+    }
+
+    cx.editor.config_events.0.send(ConfigEvent::Refresh)?;
+    Ok(())
+}
+
+fn append_output(
+    cx: &mut compositor::Context,
+    args: &[Cow<str>],
+    event: PromptEvent,
+) -> anyhow::Result<()> {
+    if event != PromptEvent::Validate {
+        return Ok(());
+    }
+
+    ensure!(!args.is_empty(), "Shell command required");
+    shell(cx, &args.join(" "), &ShellBehavior::Append);
+    Ok(())
+}
+
+fn insert_output(
+    cx: &mut compositor::Context,
+    args: &[Cow<str>],
+    event: PromptEvent,
+) -> anyhow::Result<()> {
+    if event != PromptEvent::Validate {
+        return Ok(());
+    }
+
+    ensure!(!args.is_empty(), "Shell command required");
+    shell(cx, &args.join(" "), &ShellBehavior::Insert);
+    Ok(())
+}
+
+fn pipe(cx: &mut compositor::Context, args: &[Cow<str>], event: PromptEvent) -> anyhow::Result<()> {
+    if event != PromptEvent::Validate {
+        return Ok(());
+    }
+
+    ensure!(!args.is_empty(), "Shell command required");
+    shell(cx, &args.join(" "), &ShellBehavior::Replace);
+    Ok(())
+}
+
+fn coverage_load(
+    cx: &mut compositor::Context,
+    args: &[Cow<str>],
+    event: PromptEvent,
+) -> anyhow::Result<()> {
+    if event != PromptEvent::Validate {
+        return Ok(());
+    }
+
+    let report = helix_core::path::expand_tilde(
+        args.first()
+            .context("coverage report not provided")?
+            .as_ref()
+            .as_ref(),
+    );
+    // reports usually contain paths relative to the project they were generated in
+    let cwd = std::env::current_dir().context("Couldn't get the current working directory")?;
+    let coverage = helix_view::coverage::Coverage::load(&report, &cwd)?;
+    cx.editor
+        .set_status(format!("Loaded coverage of {} files", coverage.len()));
+    cx.editor.coverage = Some(coverage);
+    Ok(())
+}
+
+fn coverage_clear(
+    cx: &mut compositor::Context,
+    _args: &[Cow<str>],
+    event: PromptEvent,
+) -> anyhow::Result<()> {
+    if event != PromptEvent::Validate {
+        return Ok(());
+    }
+
+    cx.editor.coverage = None;
+    Ok(())
+}
+
+/// Path and cursor line of the current document, for attaching notes.
+fn note_anchor(editor: &Editor) -> anyhow::Result<(PathBuf, usize)> {
+    let (view, doc) = current_ref!(editor);
+    let path = doc.path().context("buffer has no file")?.clone();
+    let text = doc.text().slice(..);
+    let line = doc.selection(view.id).primary().cursor_line(text);
+    Ok((path, line))
+}
+
+fn note(cx: &mut compositor::Context, args: &[Cow<str>], event: PromptEvent) -> anyhow::Result<()> {
+    if event != PromptEvent::Validate {
+        return Ok(());
+    }
+    ensure!(!args.is_empty(), ":note takes the text of the note");
 
-    Ok(())
+    let (path, line) = note_anchor(cx.editor)?;
+    cx.editor.set_note(&path, line, Some(args.join(" ")))
 }
 
-fn tree_sitter_subtree(
+fn note_remove(
     cx: &mut compositor::Context,
     _args: &[Cow<str>],
     event: PromptEvent,
@@ -1639,55 +2403,135 @@ fn tree_sitter_subtree(
         return Ok(());
     }
 
-    let (view, doc) = current!(cx.editor);
+    let (path, line) = note_anchor(cx.editor)?;
+    cx.editor.set_note(&path, line, None)
+}
 
-    if let Some(syntax) = doc.syntax() {
-        let primary_selection = doc.selection(view.id).primary();
-        let text = doc.text();
-        let from = text.char_to_byte(primary_selection.from());
-        let to = text.char_to_byte(primary_selection.to());
-        if let Some(selected_node) = syntax
-            .tree()
-            .root_node()
-            .descendant_for_byte_range(from, to)
-        {
-            let mut contents = String::from("```tsq\n");
-            helix_core::syntax::pretty_print_tree(&mut contents, selected_node)?;
-            contents.push_str("\n```");
+fn check(
+    cx: &mut compositor::Context,
+    args: &[Cow<str>],
+    event: PromptEvent,
+) -> anyhow::Result<()> {
+    if event != PromptEvent::Validate {
+        return Ok(());
+    }
+    ensure!(args.is_empty(), ":check takes no arguments");
 
-            let callback = async move {
-                let call: job::Callback = Callback::EditorCompositor(Box::new(
-                    move |editor: &mut Editor, compositor: &mut Compositor| {
-                        let contents = ui::Markdown::new(contents, editor.syn_loader.clone());
-                        let popup = Popup::new("hover", contents).auto_close(true);
-                        compositor.replace_or_push("hover", popup);
-                    },
-                ));
-                Ok(call)
-            };
+    let doc = doc!(cx.editor);
+    let language = doc.language_config().context("no language configured")?;
+    let checker = language
+        .checker
+        .clone()
+        .with_context(|| format!("no checker configured for {}", language.language_id))?;
+    let root = helix_core::find_root(None, &language.roots);
 
-            cx.jobs.callback(callback);
-        }
-    }
+    cx.editor
+        .set_status(format!("Running {}...", checker.command));
+    let callback = async move {
+        let diagnostics = helix_view::checker::run(&checker, &root).await?;
+        let call: job::Callback = Callback::Editor(Box::new(move |editor: &mut Editor| {
+            let count = diagnostics.len();
+            editor.set_checker_diagnostics(&checker.command, diagnostics);
+            editor.set_status(format!(
+                "{} reported {} diagnostics",
+                checker.command, count
+            ));
+        }));
+        Ok(call)
+    };
+    cx.jobs.callback(callback);
 
     Ok(())
 }
 
-fn open_config(
+fn table_mode(
     cx: &mut compositor::Context,
-    _args: &[Cow<str>],
+    args: &[Cow<str>],
     event: PromptEvent,
 ) -> anyhow::Result<()> {
+    use helix_core::table::TableFormat;
+
     if event != PromptEvent::Validate {
         return Ok(());
     }
 
-    cx.editor
-        .open(&helix_loader::config_file(), Action::Replace)?;
+    let doc = doc_mut!(cx.editor);
+    let mode = match args.first().map(|arg| arg.as_ref()) {
+        None if doc.table_mode.is_some() => None,
+        None | Some("pipe") => Some(TableFormat::Pipe),
+        Some("off") => None,
+        Some("csv") => Some(TableFormat::Delimited(',')),
+        Some("tsv") => Some(TableFormat::Delimited('\t')),
+        Some(delimiter) if delimiter.chars().count() == 1 => {
+            Some(TableFormat::Delimited(delimiter.chars().next().unwrap()))
+        }
+        Some(arg) => bail!("invalid table mode: {}", arg),
+    };
+    doc.table_mode = mode;
+    let doc_id = doc.id();
+
+    // the cell navigation keys apply while the document is focused
+    let callback = async move {
+        let call: job::Callback = Callback::EditorCompositor(Box::new(
+            move |_editor: &mut Editor, compositor: &mut Compositor| {
+                use crate::keymap::{default::table_layer, KeymapLayer, LayerScope};
+
+                if let Some(editor_view) = compositor.find::<ui::EditorView>() {
+                    let scope = LayerScope::Document(doc_id);
+                    editor_view.keymaps.pop_layer(&scope, "table");
+                    if mode.is_some() {
+                        editor_view.keymaps.push_layer(KeymapLayer {
+                            name: "table".to_string(),
+                            scope,
+                            keys: table_layer(),
+                        });
+                    }
+                }
+            },
+        ));
+        Ok(call)
+    };
+    cx.jobs.callback(callback);
+
+    let status = match mode {
+        Some(TableFormat::Pipe) => "Table mode: pipe tables".to_string(),
+        Some(TableFormat::Delimited(delimiter)) => {
+            format!("Table mode: cells delimited by {:?}", delimiter)
+        }
+        None => "Table mode off".to_string(),
+    };
+    cx.editor.set_status(status);
     Ok(())
 }
 
-fn open_log(
+fn show_dir_diff(
+    cx: &mut compositor::Context,
+    source: impl FnOnce() -> anyhow::Result<ui::DiffSource> + Send + 'static,
+) {
+    cx.editor.set_status("Comparing files...");
+    let callback = async move {
+        let (source, changes) = tokio::task::spawn_blocking(move || {
+            let source = source()?;
+            let changes = source.changes()?;
+            Ok::<_, anyhow::Error>((source, changes))
+        })
+        .await??;
+        let call: job::Callback = Callback::EditorCompositor(Box::new(
+            move |editor: &mut Editor, compositor: &mut Compositor| {
+                if changes.is_empty() {
+                    editor.set_status("No differences");
+                    return;
+                }
+                editor.clear_status();
+                compositor.push(Box::new(overlayed(ui::DirDiff::new(source, changes))));
+            },
+        ));
+        Ok(call)
+    };
+    cx.jobs.callback(callback);
+}
+
+fn explorer(
     cx: &mut compositor::Context,
     _args: &[Cow<str>],
     event: PromptEvent,
@@ -1696,24 +2540,104 @@ fn open_log(
         return Ok(());
     }
 
-    cx.editor.open(&helix_loader::log_file(), Action::Replace)?;
+    let callback = async move {
+        let call: job::Callback = Callback::EditorCompositor(Box::new(
+            move |editor: &mut Editor, compositor: &mut Compositor| {
+                if let Some(editor_view) = compositor.find::<ui::EditorView>() {
+                    if editor_view.explorer.take().is_none() {
+                        match ui::Explorer::new(editor) {
+                            Ok(explore) => editor_view.explorer = Some(overlayed(explore)),
+                            Err(err) => editor.set_error(format!("{}", err)),
+                        }
+                    }
+                }
+            },
+        ));
+        Ok(call)
+    };
+    cx.jobs.callback(callback);
     Ok(())
 }
 
-fn refresh_config(
+/// Applies a layout preset from `editor.layouts`: the current document is shown in the given
+/// number of windows and the explorer and debug console are opened or closed.
+fn layout(
     cx: &mut compositor::Context,
-    _args: &[Cow<str>],
+    args: &[Cow<str>],
     event: PromptEvent,
 ) -> anyhow::Result<()> {
     if event != PromptEvent::Validate {
         return Ok(());
     }
+    ensure!(args.len() == 1, ":layout takes a layout name");
 
-    cx.editor.config_events.0.send(ConfigEvent::Refresh)?;
+    let name = args[0].as_ref();
+    let preset = cx
+        .editor
+        .config()
+        .layouts
+        .get(name)
+        .cloned()
+        .ok_or_else(|| anyhow!("No layout named `{}`", name))?;
+
+    if cx.editor.tree.is_zoomed() {
+        cx.editor.toggle_zoom();
+    }
+    let focus = cx.editor.tree.focus;
+    let others: Vec<_> = cx
+        .editor
+        .tree
+        .views()
+        .map(|(view, _)| view.id)
+        .filter(|id| *id != focus)
+        .collect();
+    for view_id in others {
+        cx.editor.close(view_id);
+    }
+    let action = match preset.split {
+        SplitDirection::Vertical => Action::VerticalSplit,
+        SplitDirection::Horizontal => Action::HorizontalSplit,
+    };
+    for _ in 1..preset.windows {
+        let (view, doc) = current!(cx.editor);
+        let doc_id = doc.id();
+        let selection = doc.selection(view.id).clone();
+        let offset = view.offset;
+        cx.editor.switch(doc_id, action);
+        let (view, doc) = current!(cx.editor);
+        doc.set_selection(view.id, selection);
+        view.offset = offset;
+    }
+
+    let callback = async move {
+        let call: job::Callback = Callback::EditorCompositor(Box::new(
+            move |editor: &mut Editor, compositor: &mut Compositor| {
+                let editor_view = match compositor.find::<ui::EditorView>() {
+                    Some(editor_view) => editor_view,
+                    None => return,
+                };
+                match (preset.explorer, editor_view.explorer.is_some()) {
+                    (true, false) => match ui::Explorer::new(editor) {
+                        Ok(explore) => editor_view.explorer = Some(overlayed(explore)),
+                        Err(err) => editor.set_error(format!("{}", err)),
+                    },
+                    (false, true) => editor_view.explorer = None,
+                    _ => {}
+                }
+                match (preset.debug_console, editor_view.debug_console.is_some()) {
+                    (true, false) => editor_view.debug_console = Some(ui::DebugConsole::new()),
+                    (false, true) => editor_view.debug_console = None,
+                    _ => {}
+                }
+            },
+        ));
+        Ok(call)
+    };
+    cx.jobs.callback(callback);
     Ok(())
 }
 
-fn append_output(
+fn dir_diff(
     cx: &mut compositor::Context,
     args: &[Cow<str>],
     event: PromptEvent,
@@ -1721,13 +2645,21 @@ fn append_output(
     if event != PromptEvent::Validate {
         return Ok(());
     }
+    ensure!(args.len() == 2, ":dir-diff takes two directories");
 
-    ensure!(!args.is_empty(), "Shell command required");
-    shell(cx, &args.join(" "), &ShellBehavior::Append);
+    let dirs: Vec<_> = args
+        .iter()
+        .map(|arg| helix_core::path::expand_tilde(Path::new(arg.as_ref())))
+        .collect();
+    for dir in &dirs {
+        ensure!(dir.is_dir(), "{} is not a directory", dir.display());
+    }
+    let (old, new) = (dirs[0].clone(), dirs[1].clone());
+    show_dir_diff(cx, move || Ok(ui::DiffSource::Dirs { old, new }));
     Ok(())
 }
 
-fn insert_output(
+fn git_diff(
     cx: &mut compositor::Context,
     args: &[Cow<str>],
     event: PromptEvent,
@@ -1735,19 +2667,44 @@ fn insert_output(
     if event != PromptEvent::Validate {
         return Ok(());
     }
+    ensure!(args.len() <= 1, ":git-diff takes at most one revision");
 
-    ensure!(!args.is_empty(), "Shell command required");
-    shell(cx, &args.join(" "), &ShellBehavior::Insert);
+    let rev = args.first().map_or("HEAD", |arg| arg.as_ref()).to_string();
+    let dir = doc!(cx.editor)
+        .path()
+        .and_then(|path| path.parent())
+        .map(Path::to_path_buf)
+        .unwrap_or_else(|| std::env::current_dir().unwrap_or_default());
+    show_dir_diff(cx, move || ui::DiffSource::git(&dir, rev));
     Ok(())
 }
 
-fn pipe(cx: &mut compositor::Context, args: &[Cow<str>], event: PromptEvent) -> anyhow::Result<()> {
+fn git_status(
+    cx: &mut compositor::Context,
+    args: &[Cow<str>],
+    event: PromptEvent,
+) -> anyhow::Result<()> {
     if event != PromptEvent::Validate {
         return Ok(());
     }
+    ensure!(args.is_empty(), ":git-status takes no arguments");
 
-    ensure!(!args.is_empty(), "Shell command required");
-    shell(cx, &args.join(" "), &ShellBehavior::Replace);
+    let dir = doc!(cx.editor)
+        .path()
+        .and_then(|path| path.parent())
+        .map(Path::to_path_buf)
+        .unwrap_or_else(|| std::env::current_dir().unwrap_or_default());
+    let callback = async move {
+        let git_status = tokio::task::spawn_blocking(move || ui::GitStatus::new(&dir)).await??;
+        let call: job::Callback = Callback::EditorCompositor(Box::new(
+            move |editor: &mut Editor, compositor: &mut Compositor| match git_status {
+                Some(git_status) => compositor.push(Box::new(overlayed(git_status))),
+                None => editor.set_status("No changes"),
+            },
+        ));
+        Ok(call)
+    };
+    cx.jobs.callback(callback);
     Ok(())
 }
 
@@ -1928,6 +2885,13 @@ fn run_shell_command(
             fun: later,
             completer: None,
         },
+        TypableCommand {
+            name: "undo-tree",
+            aliases: &["ut"],
+            doc: "Show the undo history of the current buffer as a tree of its branches. Selecting a revision moves the buffer to it.",
+            fun: undo_tree,
+            completer: None,
+        },
         TypableCommand {
             name: "write-quit",
             aliases: &["wq", "x"],
@@ -2124,6 +3088,34 @@ fn run_shell_command(
             fun: lsp_workspace_command,
             completer: Some(completers::lsp_workspace_command),
         },
+        TypableCommand {
+            name: "call-hierarchy-incoming",
+            aliases: &["chi"],
+            doc: "Show the callers of the symbol under the cursor as a tree.",
+            fun: call_hierarchy_incoming,
+            completer: None,
+        },
+        TypableCommand {
+            name: "call-hierarchy-outgoing",
+            aliases: &["cho"],
+            doc: "Show the calls made by the symbol under the cursor as a tree.",
+            fun: call_hierarchy_outgoing,
+            completer: None,
+        },
+        TypableCommand {
+            name: "type-hierarchy-supertypes",
+            aliases: &["ths"],
+            doc: "Show the supertypes of the type under the cursor as a tree.",
+            fun: type_hierarchy_supertypes,
+            completer: None,
+        },
+        TypableCommand {
+            name: "type-hierarchy-subtypes",
+            aliases: &["thb"],
+            doc: "Show the subtypes of the type under the cursor as a tree.",
+            fun: type_hierarchy_subtypes,
+            completer: None,
+        },
         TypableCommand {
             name: "lsp-restart",
             aliases: &[],
@@ -2131,6 +3123,69 @@ fn run_shell_command(
             fun: lsp_restart,
             completer: None,
         },
+        TypableCommand {
+            name: "buffers-tree",
+            aliases: &[],
+            doc: "Show the open buffers as a tree of their directories.",
+            fun: buffers_tree,
+            completer: None,
+        },
+        TypableCommand {
+            name: "jumplist-tree",
+            aliases: &[],
+            doc: "Show the jumplist of the current view as a tree grouped by document. Selecting a jump restores its selection.",
+            fun: jumplist_tree,
+            completer: None,
+        },
+        TypableCommand {
+            name: "syntax-metrics",
+            aliases: &[],
+            doc: "Show the parse and highlight query times of the documents with a syntax tree.",
+            fun: syntax_metrics,
+            completer: None,
+        },
+        TypableCommand {
+            name: "grammar-fetch",
+            aliases: &[],
+            doc: "Fetch the tree-sitter grammars of the given languages, or of all languages, in the background.",
+            fun: grammar_fetch,
+            completer: Some(completers::language),
+        },
+        TypableCommand {
+            name: "grammar-build",
+            aliases: &[],
+            doc: "Build the tree-sitter grammars of the given languages, or of all languages, in the background.",
+            fun: grammar_build,
+            completer: Some(completers::language),
+        },
+        TypableCommand {
+            name: "grammar-status",
+            aliases: &[],
+            doc: "Show the tree-sitter grammars with their build status and the errors of failed fetches and builds.",
+            fun: grammar_status,
+            completer: None,
+        },
+        TypableCommand {
+            name: "keymap-conflicts",
+            aliases: &[],
+            doc: "Show the keybindings shadowed or made unreachable by the config and keymap layers, grouped by mode.",
+            fun: keymap_conflicts,
+            completer: None,
+        },
+        TypableCommand {
+            name: "diagnostics-tree",
+            aliases: &[],
+            doc: "Show the diagnostics of all files as a tree grouped by file.",
+            fun: diagnostics_tree,
+            completer: None,
+        },
+        TypableCommand {
+            name: "lsp-status",
+            aliases: &[],
+            doc: "Show which language servers handle the current doc and why",
+            fun: lsp_status,
+            completer: None,
+        },
         TypableCommand {
             name: "tree-sitter-scopes",
             aliases: &[],
@@ -2208,6 +3263,13 @@ fn run_shell_command(
             fun: language,
             completer: Some(completers::language),
         },
+        TypableCommand {
+            name: "language-detection",
+            aliases: &[],
+            doc: "Show why the language of the current buffer was chosen.",
+            fun: language_detection,
+            completer: None,
+        },
         TypableCommand {
             name: "set-option",
             aliases: &["set"],
@@ -2215,6 +3277,13 @@ fn run_shell_command(
             fun: set_option,
             completer: Some(completers::setting),
         },
+        TypableCommand {
+            name: "set-local",
+            aliases: &["setlocal"],
+            doc: "Set a config option in the current view only, or reset it to the global value if no value is given.\nFor example to show relative line numbers in this split, use `:set-local line-number relative`.",
+            fun: set_local_option,
+            completer: Some(completers::local_setting),
+        },
         TypableCommand {
             name: "get-option",
             aliases: &["get"],
@@ -2250,6 +3319,13 @@ fn run_shell_command(
             fun: tree_sitter_subtree,
             completer: None,
         },
+        TypableCommand {
+            name: "tree-sitter-tree",
+            aliases: &["ts-tree"],
+            doc: "Show the tree sitter tree of the current document with the node under the cursor highlighted. Selecting a node selects its range.",
+            fun: tree_sitter_tree,
+            completer: None,
+        },
         TypableCommand {
             name: "config-reload",
             aliases: &[],
@@ -2292,6 +3368,90 @@ fn run_shell_command(
             fun: pipe,
             completer: None,
         },
+        TypableCommand {
+            name: "coverage-load",
+            aliases: &[],
+            doc: "Load a test coverage report in lcov or llvm-cov JSON format.",
+            fun: coverage_load,
+            completer: Some(completers::filename),
+        },
+        TypableCommand {
+            name: "coverage-clear",
+            aliases: &[],
+            doc: "Unload the test coverage report.",
+            fun: coverage_clear,
+            completer: None,
+        },
+        TypableCommand {
+            name: "note",
+            aliases: &[],
+            doc: "Attach a note to the line of the cursor, replacing its note.",
+            fun: note,
+            completer: None,
+        },
+        TypableCommand {
+            name: "note-remove",
+            aliases: &[],
+            doc: "Remove the note of the line of the cursor.",
+            fun: note_remove,
+            completer: None,
+        },
+        TypableCommand {
+            name: "check",
+            aliases: &[],
+            doc: "Run the checker configured for the current language and show its diagnostics.",
+            fun: check,
+            completer: None,
+        },
+        TypableCommand {
+            name: "table-mode",
+            aliases: &[],
+            doc: "Toggle reflowing tables when leaving insert mode. Takes `pipe`, `csv`, `tsv`, a delimiter or `off`.",
+            fun: table_mode,
+            completer: None,
+        },
+        TypableCommand {
+            name: "dir-diff",
+            aliases: &[],
+            doc: "Show the files added, removed and modified between two directories.",
+            fun: dir_diff,
+            completer: Some(completers::directory),
+        },
+        TypableCommand {
+            name: "git-diff",
+            aliases: &[],
+            doc: "Show the files changed in the working tree against a git revision, HEAD by default.",
+            fun: git_diff,
+            completer: None,
+        },
+        TypableCommand {
+            name: "git-status",
+            aliases: &[],
+            doc: "Show the staged, unstaged and untracked files of the git repository.",
+            fun: git_status,
+            completer: None,
+        },
+        TypableCommand {
+            name: "explorer",
+            aliases: &[],
+            doc: "Open the file explorer, or close it if it is open.",
+            fun: explorer,
+            completer: None,
+        },
+        TypableCommand {
+            name: "layout",
+            aliases: &[],
+            doc: "Apply a window layout from the `editor.layouts` config, e.g. `:layout ide`.",
+            fun: layout,
+            completer: Some(completers::layout),
+        },
+        TypableCommand {
+            name: "scrollbind",
+            aliases: &["scb"],
+            doc: "Toggle scrolling the current view together with the other views that have scroll binding enabled.",
+            fun: scrollbind,
+            completer: None,
+        },
         TypableCommand {
             name: "run-shell-command",
             aliases: &["sh"],
@@ -2394,8 +3554,12 @@ pub(super) fn command_mode(cx: &mut Context) {
                 let shellwords = Shellwords::from(input);
                 let args = shellwords.words();
 
-                if let Err(e) = (cmd.fun)(cx, &args[1..], event) {
-                    cx.editor.set_error(format!("{}", e));
+                match (cmd.fun)(cx, &args[1..], event) {
+                    Ok(()) if event == PromptEvent::Validate => {
+                        cx.editor.record_command(format!(":{}", input.trim()));
+                    }
+                    Ok(()) => {}
+                    Err(e) => cx.editor.set_error(format!("{}", e)),
                 }
             } else if event == PromptEvent::Validate {
                 cx.editor
@@ -8,6 +8,15 @@
 
 pub type Callback = Box<dyn FnOnce(&mut Compositor, &mut Context)>;
 
+/// The smallest terminal the layers are rendered in. Smaller terminals only show a note, so the
+/// components don't have to lay themselves out in a handful of cells.
+pub const MIN_WIDTH: u16 = 20;
+pub const MIN_HEIGHT: u16 = 4;
+
+fn too_small(area: Rect) -> bool {
+    area.width < MIN_WIDTH || area.height < MIN_HEIGHT
+}
+
 // Cursive-inspired
 pub enum EventResult {
     Ignored(Option<Callback>),
@@ -23,6 +32,8 @@ pub struct Context<'a> {
     pub editor: &'a mut Editor,
     pub scroll: Option<usize>,
     pub jobs: &'a mut Jobs,
+    /// Set while rendering the topmost panel layer, which receives the input.
+    pub focus: bool,
 }
 
 impl<'a> Context<'a> {
@@ -71,6 +82,12 @@ fn type_name(&self) -> &'static str {
     fn id(&self) -> Option<&'static str> {
         None
     }
+
+    /// Panels dim the layers below them while they are the topmost panel, and draw their border
+    /// with `ui.window.focus`.
+    fn is_panel(&self) -> bool {
+        false
+    }
 }
 
 pub struct Compositor {
@@ -128,8 +145,12 @@ pub fn remove(&mut self, id: &'static str) -> Option<Box<dyn Component>> {
 
     pub fn handle_event(&mut self, event: &Event, cx: &mut Context) -> bool {
         // If it is a key event and a macro is being recorded, push the key event to the recording.
+        // The keys of a macro replayed while recording are already recorded as the key replaying
+        // it.
         if let (Event::Key(key), Some((_, keys))) = (event, &mut cx.editor.macro_recording) {
-            keys.push(*key);
+            if cx.editor.macro_replaying.is_empty() {
+                keys.push(*key);
+            }
         }
 
         let mut callbacks = Vec::new();
@@ -163,12 +184,36 @@ pub fn handle_event(&mut self, event: &Event, cx: &mut Context) -> bool {
     }
 
     pub fn render(&mut self, area: Rect, surface: &mut Surface, cx: &mut Context) {
-        for layer in &mut self.layers {
+        if too_small(area) {
+            surface.clear_with(area, cx.editor.theme.get("ui.background"));
+            if area.width > 0 && area.height > 0 {
+                let style = cx.editor.theme.get("ui.text");
+                surface.set_stringn(
+                    area.x,
+                    area.y,
+                    "Terminal too small",
+                    area.width as usize,
+                    style,
+                );
+            }
+            return;
+        }
+        // the layers below the topmost panel don't receive the input
+        let panel = self.layers.iter().rposition(|layer| layer.is_panel());
+        for (index, layer) in self.layers.iter_mut().enumerate() {
+            if Some(index) == panel {
+                crate::ui::dim_inactive(area, surface, &cx.editor.theme);
+            }
+            cx.focus = Some(index) == panel;
             layer.render(area, surface, cx);
         }
+        cx.focus = false;
     }
 
     pub fn cursor(&self, area: Rect, editor: &Editor) -> (Option<Position>, CursorKind) {
+        if too_small(area) {
+            return (None, CursorKind::Hidden);
+        }
         for layer in self.layers.iter().rev() {
             if let (Some(pos), kind) = layer.cursor(area, editor) {
                 return (Some(pos), kind);
@@ -270,3 +315,210 @@ pub fn is<T: Any>(&mut self) -> bool {
         self.as_any().is::<T>()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        config::Config,
+        keymap::Keymaps,
+        ui::{self, TreePicker, TreePickerNode},
+    };
+    use arc_swap::{access::Map, ArcSwap};
+    use helix_core::syntax;
+    use helix_view::{
+        editor::Action,
+        graphics::{Modifier, Style},
+        theme,
+    };
+    use std::{cell::Cell, rc::Rc, sync::Arc};
+
+    fn editor(area: Rect) -> Editor {
+        let config = Arc::new(ArcSwap::from_pointee(Config::default()));
+        let mut editor = Editor::new(
+            area,
+            Arc::new(theme::Loader::new(
+                &helix_loader::config_dir(),
+                &helix_loader::runtime_dir(),
+            )),
+            Arc::new(syntax::Loader::new(
+                helix_core::config::default_syntax_loader(),
+            )),
+            Box::new(Map::new(config, |config: &Config| &config.editor)),
+        );
+        editor.new_file(Action::VerticalSplit);
+        editor
+    }
+
+    struct Panics;
+
+    impl Component for Panics {
+        fn render(&mut self, _area: Rect, _surface: &mut Surface, _cx: &mut Context) {
+            panic!("rendered below the minimum size");
+        }
+    }
+
+    #[tokio::test]
+    async fn layers_are_skipped_below_minimum_size() {
+        let mut editor = editor(Rect::new(0, 0, 80, 24));
+        let mut jobs = Jobs::new();
+        let mut compositor = Compositor::new(Rect::default());
+        compositor.push(Box::new(Panics));
+        for (width, height) in [(0, 0), (0, 10), (10, 0), (1, 1), (MIN_WIDTH - 1, 40)] {
+            let area = Rect::new(0, 0, width, height);
+            let mut surface = Surface::empty(area);
+            let mut cx = Context {
+                editor: &mut editor,
+                jobs: &mut jobs,
+                scroll: None,
+                focus: false,
+            };
+            compositor.resize(area);
+            compositor.render(area, &mut surface, &mut cx);
+            assert_eq!(compositor.cursor(area, &editor), (None, CursorKind::Hidden));
+        }
+    }
+
+    /// Clears its area, remembering whether it had the focus.
+    struct Layer {
+        area: Rect,
+        panel: bool,
+        focus: Rc<Cell<bool>>,
+    }
+
+    impl Component for Layer {
+        fn render(&mut self, _area: Rect, surface: &mut Surface, cx: &mut Context) {
+            surface.clear_with(self.area, Style::default());
+            self.focus.set(cx.focus);
+        }
+
+        fn is_panel(&self) -> bool {
+            self.panel
+        }
+    }
+
+    #[tokio::test]
+    async fn panels_dim_the_layers_below() {
+        let area = Rect::new(0, 0, 40, 10);
+        let mut editor = editor(area);
+        let theme = r#"
+            "ui.selection" = "red"
+            "ui.inactive" = { modifiers = ["dim"] }
+        "#;
+        editor.set_theme(toml::from_str(theme).unwrap());
+        let mut jobs = Jobs::new();
+        let mut compositor = Compositor::new(area);
+        let focus: Vec<_> = (0..3).map(|_| Rc::new(Cell::new(false))).collect();
+        let panel_area = Rect::new(10, 2, 20, 6);
+        for (index, (area, panel)) in [(area, false), (area, true), (panel_area, true)]
+            .into_iter()
+            .enumerate()
+        {
+            compositor.push(Box::new(Layer {
+                area,
+                panel,
+                focus: focus[index].clone(),
+            }));
+        }
+
+        let mut surface = Surface::empty(area);
+        let mut cx = Context {
+            editor: &mut editor,
+            jobs: &mut jobs,
+            scroll: None,
+            focus: false,
+        };
+        compositor.render(area, &mut surface, &mut cx);
+        let focus: Vec<_> = focus.iter().map(|focus| focus.get()).collect();
+        assert_eq!(focus, [false, false, true]);
+        assert!(surface[(0, 0)].modifier.contains(Modifier::DIM));
+        assert!(!surface[(10, 2)].modifier.contains(Modifier::DIM));
+    }
+
+    #[tokio::test]
+    async fn render_pathological_sizes() {
+        let mut editor = editor(Rect::new(0, 0, 80, 24));
+        let mut jobs = Jobs::new();
+        let mut compositor = Compositor::new(Rect::default());
+        compositor.push(Box::new(ui::EditorView::new(Keymaps::default())));
+        let nodes = vec![TreePickerNode::new("parent".to_string())
+            .with_children(vec![TreePickerNode::new("child".to_string())])];
+        let picker = TreePicker::new(
+            nodes,
+            |value: &String, _| value.as_str().into(),
+            |_, _, _| {},
+        );
+        compositor.push(Box::new(ui::overlay::overlayed(picker)));
+        compositor.push(Box::new(ui::Popup::new(
+            "test",
+            ui::Text::new("a popup\nwith text".to_string()),
+        )));
+
+        // render each size after the previous one, like a terminal being resized
+        for width in (0..=MIN_WIDTH + 2).chain((0..=MIN_WIDTH + 2).rev()) {
+            for height in 0..=MIN_HEIGHT + 2 {
+                let area = Rect::new(0, 0, width, height);
+                let mut surface = Surface::empty(area);
+                compositor.resize(area);
+                let mut cx = Context {
+                    editor: &mut editor,
+                    jobs: &mut jobs,
+                    scroll: None,
+                    focus: false,
+                };
+                compositor.handle_event(&Event::Resize(width, height), &mut cx);
+                compositor.render(area, &mut surface, &mut cx);
+                compositor.cursor(area, &editor);
+            }
+        }
+    }
+
+    /// The layers aren't rendered below the minimum size, but the panels are also drawn in parts
+    /// of the screen which can be arbitrarily small.
+    #[tokio::test]
+    async fn render_panels_in_tiny_areas() {
+        let mut editor = editor(Rect::new(0, 0, 80, 24));
+        let mut jobs = Jobs::new();
+        let nodes = vec![TreePickerNode::new("parent".to_string())
+            .with_children(vec![TreePickerNode::new("child".to_string())])];
+        let picker = TreePicker::new(
+            nodes,
+            |value: &String, _| value.as_str().into(),
+            |_, _, _| {},
+        );
+        let mut panels: Vec<Box<dyn Component>> = vec![
+            Box::new(picker),
+            Box::new(ui::Explorer::new(&editor).unwrap()),
+            Box::new(ui::Outline::new()),
+            Box::new(ui::DebugConsole::new()),
+            Box::new(ui::BuffersTree::new(&editor)),
+            Box::new(ui::JumplistTree::new(&editor)),
+            Box::new(ui::UndoTree::new(&editor)),
+            Box::new(ui::DiagnosticsTree::new(&editor)),
+            Box::new(ui::SyntaxMetricsTree::new(&editor)),
+            Box::new(ui::SyntaxTreeView::new(&editor)),
+            Box::new(ui::GrammarsTree::new()),
+            Box::new(ui::BreakpointsTree::new(&editor)),
+            Box::new(ui::WatchesTree::new(&editor)),
+            Box::new(ui::CallStackTree::new()),
+            Box::new(ui::VariablesTree::new()),
+        ];
+
+        for panel in &mut panels {
+            for width in 0..=2 {
+                for height in 0..=2 {
+                    let area = Rect::new(0, 0, width, height);
+                    let mut surface = Surface::empty(area);
+                    let mut cx = Context {
+                        editor: &mut editor,
+                        jobs: &mut jobs,
+                        scroll: None,
+                        focus: true,
+                    };
+                    panel.render(area, &mut surface, &mut cx);
+                    panel.cursor(area, &editor);
+                }
+            }
+        }
+    }
+}
@@ -1,5 +1,8 @@
-use crate::keymap::{default::default, merge_keys, Keymap};
-use helix_view::document::Mode;
+use crate::keymap::{
+    default::{default, language_layers},
+    merge_keys, Keymap,
+};
+use helix_view::{document::Mode, tree_keymap::TreeKeymap};
 use serde::Deserialize;
 use std::collections::HashMap;
 use std::fmt::Display;
@@ -8,20 +11,71 @@
 use toml::de::Error as TomlError;
 
 #[derive(Debug, Clone, PartialEq, Deserialize)]
-#[serde(deny_unknown_fields)]
+#[serde(from = "ConfigRaw")]
 pub struct Config {
     pub theme: Option<String>,
-    #[serde(default = "default")]
     pub keys: HashMap<Mode, Keymap>,
-    #[serde(default)]
+    /// The bindings of the `[keys.tree]` section merged into the default tree bindings.
+    pub tree_keys: TreeKeymap,
+    /// Keymap layers of languages from the `[keys.language.<name>]` sections merged into the
+    /// default layers.
+    pub language_keys: HashMap<String, HashMap<Mode, Keymap>>,
     pub editor: helix_view::editor::Config,
 }
 
+/// The config as written, where `[keys.tree]` and `[keys.language]` sit next to the sections of
+/// the modes.
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct ConfigRaw {
+    theme: Option<String>,
+    keys: Option<KeysRaw>,
+    #[serde(default)]
+    editor: helix_view::editor::Config,
+}
+
+#[derive(Deserialize)]
+struct KeysRaw {
+    tree: Option<TreeKeymap>,
+    language: Option<HashMap<String, HashMap<Mode, Keymap>>>,
+    #[serde(flatten)]
+    modes: HashMap<Mode, Keymap>,
+}
+
+impl From<ConfigRaw> for Config {
+    fn from(raw: ConfigRaw) -> Self {
+        let (keys, tree, language) = match raw.keys {
+            Some(keys) => (keys.modes, keys.tree, keys.language),
+            None => (default(), None, None),
+        };
+        let mut tree_keys = TreeKeymap::default();
+        if let Some(tree) = tree {
+            tree_keys.merge(tree);
+        }
+        let mut language_keys = language_layers();
+        for (language, modes) in language.unwrap_or_default() {
+            let layer = language_keys.entry(language).or_default();
+            for (mode, keymap) in modes {
+                layer.entry(mode).or_default().merge(keymap);
+            }
+        }
+        Config {
+            theme: raw.theme,
+            keys,
+            tree_keys,
+            language_keys,
+            editor: raw.editor,
+        }
+    }
+}
+
 impl Default for Config {
     fn default() -> Config {
         Config {
             theme: None,
             keys: default(),
+            tree_keys: TreeKeymap::default(),
+            language_keys: language_layers(),
             editor: helix_view::editor::Config::default(),
         }
     }
@@ -104,4 +158,34 @@ fn keys_resolve_to_correct_defaults() {
         let default_keys = Config::default().keys;
         assert_eq!(default_keys, default());
     }
+
+    #[test]
+    fn parsing_panel_rules() {
+        use helix_core::syntax::FileType;
+        use helix_view::editor::Panel;
+        use std::path::Path;
+
+        let config = r#"
+            [[editor.panels]]
+            languages = ["rust", "markdown"]
+            open = ["outline"]
+
+            [[editor.panels]]
+            file-types = ["json", { suffix = ".config/helix/config.toml" }]
+            open = ["explorer", "outline"]
+        "#;
+        let rules = toml::from_str::<Config>(config).unwrap().editor.panels;
+        assert_eq!(rules.len(), 2);
+        assert_eq!(rules[0].languages, ["rust", "markdown"]);
+        assert_eq!(rules[0].open, [Panel::Outline]);
+        assert_eq!(rules[1].open, [Panel::Explorer, Panel::Outline]);
+
+        let file_types = &rules[1].file_types;
+        assert_eq!(file_types[0], FileType::Extension("json".to_string()));
+        assert!(file_types[0].matches(Path::new("/tmp/package.json")));
+        assert!(!file_types[0].matches(Path::new("/tmp/package.jsonc")));
+        let config_dir = Path::new("home").join(".config").join("helix");
+        assert!(file_types[1].matches(&config_dir.join("config.toml")));
+        assert!(!file_types[1].matches(Path::new("config.toml")));
+    }
 }
@@ -0,0 +1,220 @@
+//! `hx --headless`: applies commands and keys to files without a terminal and writes the files
+//! they changed, for scripted edits with the same commands as the editor.
+
+use crate::{
+    args::Args,
+    commands::{self, MappableCommand},
+    compositor::{self, Compositor, Event},
+    config::Config,
+    job::Jobs,
+    keymap::Keymaps,
+    ui,
+};
+use anyhow::{anyhow, bail, Context as _, Result};
+use arc_swap::{access::Map, ArcSwap};
+use futures_util::StreamExt;
+use helix_core::{pos_at_coords, syntax, Selection};
+use helix_view::{
+    editor::{Action, Severity},
+    graphics::Rect,
+    input::{parse_macro, KeyEvent},
+    theme, Editor,
+};
+use std::{
+    path::{Path, PathBuf},
+    sync::Arc,
+    time::Duration,
+};
+
+/// How long a step may wait for the background jobs it started, like a formatter.
+const JOB_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// A step applied to each file, in the order given on the command line.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Step {
+    /// A command name, or a typable command with its arguments after a `:`.
+    Command(String),
+    /// Keys written like macros, e.g. `%sfoo<ret>cbar<esc>`.
+    Keys(String),
+}
+
+enum ParsedStep {
+    Command(String),
+    Keys(Vec<KeyEvent>),
+}
+
+struct Headless {
+    editor: Editor,
+    compositor: Compositor,
+    jobs: Jobs,
+}
+
+impl Headless {
+    fn run_step(&mut self, step: &ParsedStep) -> Result<()> {
+        self.editor.status_msg = None;
+        match step {
+            ParsedStep::Keys(keys) => {
+                for key in keys {
+                    let mut cx = compositor::Context {
+                        editor: &mut self.editor,
+                        jobs: &mut self.jobs,
+                        scroll: None,
+                        focus: false,
+                    };
+                    self.compositor.handle_event(&Event::Key(*key), &mut cx);
+                    self.jobs
+                        .handle_ready_callbacks(&mut self.editor, &mut self.compositor);
+                }
+            }
+            ParsedStep::Command(command) => self.run_command(command)?,
+        }
+        match &self.editor.status_msg {
+            Some((message, Severity::Error)) => Err(anyhow!("{}", message)),
+            _ => Ok(()),
+        }
+    }
+
+    fn run_command(&mut self, command: &str) -> Result<()> {
+        let mut cx = compositor::Context {
+            editor: &mut self.editor,
+            jobs: &mut self.jobs,
+            scroll: None,
+            focus: false,
+        };
+        if let Some(line) = command.strip_prefix(':') {
+            return commands::run_typable_line(&mut cx, line);
+        }
+
+        let mappable: MappableCommand = command.parse()?;
+        let mut command_cx = commands::Context {
+            register: None,
+            count: None,
+            editor: &mut *cx.editor,
+            callback: None,
+            on_next_key_callback: None,
+            jobs: &mut *cx.jobs,
+        };
+        mappable.execute(&mut command_cx);
+        if let Some(callback) = command_cx.callback {
+            callback(&mut self.compositor, &mut cx);
+        }
+        Ok(())
+    }
+
+    /// Waits for the jobs started by the last step, e.g. formatting.
+    async fn finish_jobs(&mut self) -> Result<()> {
+        let Self {
+            editor,
+            compositor,
+            jobs,
+        } = self;
+        let finish = async {
+            while let Some(call) = jobs.futures.next().await {
+                jobs.handle_callback(editor, compositor, call);
+            }
+            jobs.finish(editor, Some(compositor)).await
+        };
+        tokio::time::timeout(JOB_TIMEOUT, finish)
+            .await
+            .map_err(|_| anyhow!("timed out waiting for background jobs"))?
+    }
+
+    /// Opens `path`, runs the steps on it and writes it if they changed it.
+    async fn apply(
+        &mut self,
+        path: &Path,
+        pos: helix_core::Position,
+        steps: &[ParsedStep],
+    ) -> Result<()> {
+        let doc_id = self.editor.open(path, Action::Replace)?;
+        let (view, doc) = current!(self.editor);
+        let pos = Selection::point(pos_at_coords(doc.text().slice(..), pos, true));
+        doc.set_selection(view.id, pos);
+
+        for step in steps {
+            self.run_step(step)?;
+            self.finish_jobs().await?;
+        }
+
+        let modified = self
+            .editor
+            .document(doc_id)
+            .map_or(false, |doc| doc.is_modified());
+        if modified {
+            self.editor.save::<PathBuf>(doc_id, None, false)?;
+            self.editor.flush_writes().await?;
+        }
+        Ok(())
+    }
+}
+
+/// Applies the steps of `args` to each of its files, printing the errors. Returns the exit code,
+/// 1 if a step failed for any file.
+pub async fn run(
+    args: Args,
+    config: Config,
+    syn_loader_conf: syntax::Configuration,
+) -> Result<i32> {
+    if args.files.is_empty() {
+        bail!("--headless needs files to edit");
+    }
+    let steps = args
+        .headless_steps
+        .iter()
+        .map(|step| match step {
+            Step::Command(command) => Ok(ParsedStep::Command(command.clone())),
+            Step::Keys(keys) => parse_macro(keys)
+                .map(ParsedStep::Keys)
+                .with_context(|| format!("invalid keys `{}`", keys)),
+        })
+        .collect::<Result<Vec<_>>>()?;
+
+    let theme_loader = Arc::new(theme::Loader::new(
+        &helix_loader::config_dir(),
+        &helix_loader::runtime_dir(),
+    ));
+    let syn_loader = Arc::new(syntax::Loader::new(syn_loader_conf));
+    // the size only matters to commands scrolling the view
+    let area = Rect::new(0, 0, 120, 40);
+    let config = Arc::new(ArcSwap::from_pointee(config));
+    let mut editor = Editor::new(
+        area,
+        theme_loader,
+        syn_loader,
+        Box::new(Map::new(Arc::clone(&config), |config: &Config| {
+            &config.editor
+        })),
+    );
+    editor.tree_keymap = config.load().tree_keys.clone();
+
+    let keys = Box::new(Map::new(Arc::clone(&config), |config: &Config| {
+        &config.keys
+    }));
+    let mut keymaps = Keymaps::new(keys);
+    keymaps.set_language_layers(&config.load().language_keys);
+    let mut compositor = Compositor::new(area);
+    compositor.push(Box::new(ui::EditorView::new(keymaps)));
+    editor.new_file(Action::VerticalSplit);
+
+    let mut headless = Headless {
+        editor,
+        compositor,
+        jobs: Jobs::new(),
+    };
+    let mut exit_code = 0;
+    for (path, pos) in &args.files {
+        if let Err(err) = headless.apply(path, *pos, &steps).await {
+            eprintln!("{}: {}", path.display(), err);
+            exit_code = 1;
+        }
+    }
+
+    if let Err(err) = headless.editor.flush_writes().await {
+        eprintln!("{}", err);
+        exit_code = 1;
+    }
+    if headless.editor.close_language_servers(None).await.is_err() {
+        log::error!("Timed out waiting for language servers to shutdown");
+    }
+    Ok(exit_code)
+}
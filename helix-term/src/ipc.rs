@@ -0,0 +1,286 @@
+//! A JSON-RPC server on a Unix socket letting external tools control the editor, enabled with
+//! `editor.ipc.enable`. Requests and responses are JSON objects, one per line. The requests are
+//! handed to the event loop of the [`crate::application::Application`], which answers them.
+
+use serde::Deserialize;
+use serde_json::{json, Value};
+use std::path::{Path, PathBuf};
+use tokio::sync::{mpsc, oneshot};
+
+pub const PARSE_ERROR: i64 = -32700;
+pub const INVALID_REQUEST: i64 = -32600;
+pub const METHOD_NOT_FOUND: i64 = -32601;
+pub const INVALID_PARAMS: i64 = -32602;
+/// The command isn't in `editor.ipc.commands`.
+pub const NOT_ALLOWED: i64 = -32001;
+/// The request was valid but failed, e.g. a file couldn't be opened.
+pub const FAILED: i64 = -32000;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Error {
+    pub code: i64,
+    pub message: String,
+}
+
+impl Error {
+    pub fn new(code: i64, message: impl Into<String>) -> Self {
+        Self {
+            code,
+            message: message.into(),
+        }
+    }
+
+    pub fn invalid_params(err: impl std::fmt::Display) -> Self {
+        Self::new(INVALID_PARAMS, err.to_string())
+    }
+
+    pub fn failed(err: impl std::fmt::Display) -> Self {
+        Self::new(FAILED, err.to_string())
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct RawRequest {
+    /// Notifications have no id and get no response.
+    id: Option<Value>,
+    method: String,
+    #[serde(default)]
+    params: Value,
+}
+
+/// A request of a client, answered with [`Request::respond`].
+pub struct Request {
+    pub method: String,
+    pub params: Value,
+    response: Option<oneshot::Sender<Value>>,
+    id: Value,
+}
+
+impl Request {
+    pub fn respond(self, result: Result<Value, Error>) {
+        if let Some(response) = self.response {
+            // the client may have disconnected in the meantime
+            let _ = response.send(response_json(self.id, result));
+        }
+    }
+}
+
+fn response_json(id: Value, result: Result<Value, Error>) -> Value {
+    match result {
+        Ok(result) => json!({ "jsonrpc": "2.0", "id": id, "result": result }),
+        Err(err) => json!({
+            "jsonrpc": "2.0",
+            "id": id,
+            "error": { "code": err.code, "message": err.message },
+        }),
+    }
+}
+
+/// Parses a line sent by a client, returning the request to hand to the editor or the response
+/// to send right away.
+fn parse_request(line: &str) -> Result<(Request, Option<oneshot::Receiver<Value>>), Value> {
+    let value: Value = serde_json::from_str(line)
+        .map_err(|err| response_json(Value::Null, Err(Error::new(PARSE_ERROR, err.to_string()))))?;
+    let id = value.get("id").cloned().unwrap_or(Value::Null);
+    let raw: RawRequest = serde_json::from_value(value)
+        .map_err(|err| response_json(id, Err(Error::new(INVALID_REQUEST, err.to_string()))))?;
+    let (response, receiver) = match raw.id {
+        Some(_) => {
+            let (sender, receiver) = oneshot::channel();
+            (Some(sender), Some(receiver))
+        }
+        None => (None, None),
+    };
+    let request = Request {
+        method: raw.method,
+        params: raw.params,
+        response,
+        id: raw.id.unwrap_or(Value::Null),
+    };
+    Ok((request, receiver))
+}
+
+/// The socket path used when `editor.ipc.socket` isn't set, unique to this process.
+pub fn default_socket_path() -> PathBuf {
+    helix_loader::cache_dir().join(format!("helix-{}.sock", std::process::id()))
+}
+
+/// Listens on a socket and forwards the requests of its clients. The socket file is removed when
+/// the server is dropped.
+pub struct Server {
+    path: PathBuf,
+    requests: mpsc::UnboundedReceiver<Request>,
+}
+
+impl Server {
+    #[cfg(unix)]
+    pub fn bind(path: &Path) -> std::io::Result<Self> {
+        use std::io::{self, ErrorKind};
+        use std::os::unix::{fs::FileTypeExt, net::UnixStream};
+        use tokio::net::UnixListener;
+
+        if let Ok(metadata) = std::fs::symlink_metadata(path) {
+            // never remove a file that isn't ours to remove
+            if !metadata.file_type().is_socket() {
+                return Err(io::Error::new(
+                    ErrorKind::AlreadyExists,
+                    format!("{} exists and is not a socket", path.display()),
+                ));
+            }
+            match UnixStream::connect(path) {
+                Ok(_) => {
+                    return Err(io::Error::new(
+                        ErrorKind::AddrInUse,
+                        format!("another editor is listening on {}", path.display()),
+                    ))
+                }
+                // a socket left behind by an editor that didn't exit cleanly
+                Err(err) if err.kind() == ErrorKind::ConnectionRefused => {
+                    std::fs::remove_file(path)?
+                }
+                Err(err) => return Err(err),
+            }
+        }
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        // clients can run any allowed command, so only the user may connect. The socket is
+        // created with these permissions rather than changed after `bind`, which would leave a
+        // window where anyone could connect.
+        let umask = unsafe { libc::umask(0o177) };
+        let listener = UnixListener::bind(path);
+        unsafe { libc::umask(umask) };
+        let listener = listener?;
+        let (sender, requests) = mpsc::unbounded_channel();
+        tokio::spawn(async move {
+            loop {
+                match listener.accept().await {
+                    Ok((stream, _)) => {
+                        tokio::spawn(serve(stream, sender.clone()));
+                    }
+                    Err(err) => {
+                        log::error!("failed to accept an IPC connection: {}", err);
+                        break;
+                    }
+                }
+            }
+        });
+        Ok(Self {
+            path: path.to_path_buf(),
+            requests,
+        })
+    }
+
+    #[cfg(not(unix))]
+    pub fn bind(_path: &Path) -> std::io::Result<Self> {
+        Err(std::io::Error::new(
+            std::io::ErrorKind::Unsupported,
+            "the IPC server needs Unix sockets",
+        ))
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+}
+
+impl Drop for Server {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_file(&self.path);
+    }
+}
+
+/// The next request of a client, pending forever if there is no server.
+pub async fn next_request(server: &mut Option<Server>) -> Option<Request> {
+    match server {
+        Some(server) => server.requests.recv().await,
+        None => std::future::pending().await,
+    }
+}
+
+/// Answers the requests of a client until it disconnects or the editor exits.
+#[cfg(unix)]
+async fn serve(stream: tokio::net::UnixStream, requests: mpsc::UnboundedSender<Request>) {
+    use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
+
+    let (reader, mut writer) = stream.into_split();
+    let mut lines = BufReader::new(reader).lines();
+    while let Ok(Some(line)) = lines.next_line().await {
+        if line.trim().is_empty() {
+            continue;
+        }
+        let response = match parse_request(&line) {
+            Ok((request, receiver)) => {
+                if requests.send(request).is_err() {
+                    break;
+                }
+                match receiver {
+                    Some(receiver) => match receiver.await {
+                        Ok(response) => response,
+                        Err(_) => break,
+                    },
+                    None => continue,
+                }
+            }
+            Err(response) => response,
+        };
+        let mut response = response.to_string();
+        response.push('\n');
+        if writer.write_all(response.as_bytes()).await.is_err() {
+            break;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_requests() {
+        let (request, receiver) =
+            parse_request(r#"{"jsonrpc": "2.0", "id": 1, "method": "buffers"}"#).unwrap();
+        assert_eq!(request.method, "buffers");
+        assert_eq!(request.params, Value::Null);
+        assert!(receiver.is_some());
+
+        let (request, receiver) =
+            parse_request(r#"{"method": "open", "params": {"path": "a.rs"}}"#).unwrap();
+        assert_eq!(request.params, json!({ "path": "a.rs" }));
+        assert!(receiver.is_none());
+
+        let error = parse_request("{").unwrap_err();
+        assert_eq!(error["error"]["code"], PARSE_ERROR);
+        let error = parse_request(r#"{"id": 2}"#).unwrap_err();
+        assert_eq!(error["error"]["code"], INVALID_REQUEST);
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn bind_keeps_live_sockets() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("helix.sock");
+
+        // a stale socket is replaced
+        drop(std::os::unix::net::UnixListener::bind(&path).unwrap());
+        let server = Server::bind(&path).unwrap();
+        let mode = std::fs::metadata(&path).unwrap().permissions().mode();
+        assert_eq!(mode & 0o777, 0o600);
+
+        // a socket another editor listens on is left alone
+        let err = Server::bind(&path).err().unwrap();
+        assert_eq!(err.kind(), std::io::ErrorKind::AddrInUse);
+        assert!(std::os::unix::net::UnixStream::connect(&path).is_ok());
+
+        drop(server);
+        assert!(!path.exists());
+
+        // files which aren't sockets are never removed
+        std::fs::write(&path, "notes").unwrap();
+        let err = Server::bind(&path).err().unwrap();
+        assert_eq!(err.kind(), std::io::ErrorKind::AlreadyExists);
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), "notes");
+    }
+}
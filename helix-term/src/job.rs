@@ -82,6 +82,14 @@ pub fn handle_callback(
         }
     }
 
+    /// Runs the callbacks of the jobs which are already done without waiting for the others,
+    /// e.g. the ones of commands opening panels while a macro is replayed.
+    pub fn handle_ready_callbacks(&mut self, editor: &mut Editor, compositor: &mut Compositor) {
+        while let Some(Some(call)) = self.futures.next().now_or_never() {
+            self.handle_callback(editor, compositor, call);
+        }
+    }
+
     pub async fn next_job(&mut self) -> Option<anyhow::Result<Option<Callback>>> {
         tokio::select! {
             event = self.futures.next() => {  event }
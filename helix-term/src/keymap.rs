@@ -1,3 +1,4 @@
+pub mod conflicts;
 pub mod default;
 pub mod macros;
 
@@ -7,7 +8,7 @@
     access::{DynAccess, DynGuard},
     ArcSwap,
 };
-use helix_view::{document::Mode, info::Info, input::KeyEvent};
+use helix_view::{document::Mode, info::Info, input::KeyEvent, Document, DocumentId};
 use serde::Deserialize;
 use std::{
     borrow::Cow,
@@ -324,6 +325,52 @@ fn default() -> Self {
     }
 }
 
+/// The buffers a keymap layer applies to.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum LayerScope {
+    /// Documents of a language, by its name in `languages.toml`.
+    Language(String),
+    /// A single document, e.g. while a mode of a command is enabled for it.
+    Document(DocumentId),
+}
+
+/// Bindings that take precedence over the keymap while a buffer in their scope is focused.
+#[derive(Debug, Clone)]
+pub struct KeymapLayer {
+    pub name: String,
+    pub scope: LayerScope,
+    pub keys: HashMap<Mode, Keymap>,
+}
+
+/// The document and the language of the focused buffer.
+type Focus = (DocumentId, Option<String>);
+
+impl LayerScope {
+    fn applies_to(&self, (doc_id, language): &Focus) -> bool {
+        match self {
+            Self::Language(name) => language.as_ref() == Some(name),
+            Self::Document(id) => id == doc_id,
+        }
+    }
+}
+
+/// The root of the topmost layer applying to the focused buffer which binds `key` in `mode`.
+fn layer_root<'a>(
+    layers: &'a [KeymapLayer],
+    focus: Option<&Focus>,
+    mode: Mode,
+    key: KeyEvent,
+) -> Option<&'a KeyTrie> {
+    let focus = focus?;
+    layers
+        .iter()
+        .rev()
+        .filter(|layer| layer.scope.applies_to(focus))
+        .filter_map(|layer| layer.keys.get(&mode))
+        .map(Keymap::root)
+        .find(|root| root.search(&[key]).is_some())
+}
+
 pub struct Keymaps {
     pub map: Box<dyn DynAccess<HashMap<Mode, Keymap>>>,
     /// Stores pending keys waiting for the next key. This is relative to a
@@ -331,6 +378,9 @@ pub struct Keymaps {
     state: Vec<KeyEvent>,
     /// Stores the sticky node if one is activated.
     pub sticky: Option<KeyTrieNode>,
+    /// Layers in the order they were pushed, the last one taking precedence.
+    layers: Vec<KeymapLayer>,
+    focus: Option<Focus>,
 }
 
 impl Keymaps {
@@ -339,6 +389,8 @@ pub fn new(map: Box<dyn DynAccess<HashMap<Mode, Keymap>>>) -> Self {
             map,
             state: Vec::new(),
             sticky: None,
+            layers: Vec::new(),
+            focus: None,
         }
     }
 
@@ -346,6 +398,59 @@ pub fn map(&self) -> DynGuard<HashMap<Mode, Keymap>> {
         self.map.load()
     }
 
+    pub fn layers(&self) -> &[KeymapLayer] {
+        &self.layers
+    }
+
+    pub fn push_layer(&mut self, layer: KeymapLayer) {
+        self.layers.push(layer);
+    }
+
+    /// Removes the layers of `scope` named `name`.
+    pub fn pop_layer(&mut self, scope: &LayerScope, name: &str) {
+        self.layers
+            .retain(|layer| !(layer.scope == *scope && layer.name == name));
+    }
+
+    /// Replaces the layers of languages, e.g. after the config was reloaded.
+    pub fn set_language_layers(&mut self, languages: &HashMap<String, HashMap<Mode, Keymap>>) {
+        self.layers
+            .retain(|layer| !matches!(layer.scope, LayerScope::Language(_)));
+        // language layers sit below the layers of documents
+        let layers = languages.iter().map(|(language, keys)| KeymapLayer {
+            name: language.clone(),
+            scope: LayerScope::Language(language.clone()),
+            keys: keys.clone(),
+        });
+        self.layers.splice(0..0, layers);
+    }
+
+    /// Sets the buffer whose layers apply, dropping the layers of documents which were closed.
+    pub fn set_focus(&mut self, doc: &Document, is_open: impl Fn(DocumentId) -> bool) {
+        self.focus = Some((doc.id(), doc.language_name().map(String::from)));
+        self.layers.retain(|layer| match layer.scope {
+            LayerScope::Document(doc_id) => is_open(doc_id),
+            LayerScope::Language(_) => true,
+        });
+    }
+
+    /// The keymap of `mode` with the bindings of the layers applying to the focused buffer.
+    pub fn effective_keymap(&self, mode: Mode) -> Keymap {
+        let mut keymap = self.map()[&mode].clone();
+        if let Some(focus) = &self.focus {
+            for layer in self
+                .layers
+                .iter()
+                .filter(|layer| layer.scope.applies_to(focus))
+            {
+                if let Some(keys) = layer.keys.get(&mode) {
+                    keymap.merge(keys.clone());
+                }
+            }
+        }
+        keymap
+    }
+
     /// Returns list of keys waiting to be disambiguated in current mode.
     pub fn pending(&self) -> &[KeyEvent] {
         &self.state
@@ -374,7 +479,9 @@ pub fn get(&mut self, mode: Mode, key: KeyEvent) -> KeymapResult {
         let first = self.state.get(0).unwrap_or(&key);
         let trie_node = match self.sticky {
             Some(ref trie) => Cow::Owned(KeyTrie::Node(trie.clone())),
-            None => Cow::Borrowed(&keymap.root),
+            None => Cow::Borrowed(
+                layer_root(&self.layers, self.focus.as_ref(), mode, *first).unwrap_or(&keymap.root),
+            ),
         };
 
         let trie = match trie_node.search(&[*first]) {
@@ -441,6 +548,26 @@ fn duplicate_keys_should_panic() {
         });
     }
 
+    #[test]
+    fn layers_apply_in_their_scope() {
+        let layers = [KeymapLayer {
+            name: "markdown".to_string(),
+            scope: LayerScope::Language("markdown".to_string()),
+            keys: hashmap! {
+                Mode::Normal => Keymap::new(keymap!({ "Markdown"
+                    "i" => goto_definition,
+                })),
+            },
+        }];
+        let markdown = (DocumentId::default(), Some("markdown".to_string()));
+        let rust = (DocumentId::default(), Some("rust".to_string()));
+
+        assert!(layer_root(&layers, Some(&markdown), Mode::Normal, key!('i')).is_some());
+        assert!(layer_root(&layers, Some(&markdown), Mode::Normal, key!('j')).is_none());
+        assert!(layer_root(&layers, Some(&markdown), Mode::Insert, key!('i')).is_none());
+        assert!(layer_root(&layers, Some(&rust), Mode::Normal, key!('i')).is_none());
+    }
+
     #[test]
     fn check_duplicate_keys_in_default_keymap() {
         // will panic on duplicate keys, assumes that `Keymaps` uses keymap! macro
@@ -0,0 +1,331 @@
+//! Bindings which can't be used as configured, e.g. because the config bound their prefix to
+//! a command or a layer took their first key.
+
+use super::{default::default, KeyTrie, Keymap, KeymapLayer, MappableCommand, Mode};
+use helix_view::{
+    input::KeyEvent,
+    keyboard::{KeyCode, KeyModifiers},
+    tree_keymap::{TreeKeyTrie, TreeKeymap},
+};
+use std::collections::HashMap;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ConflictKind {
+    /// Another binding replaced it on the same keys.
+    Shadowed,
+    /// Keys handled before it, like its prefix bound to a command, keep it from being typed.
+    Unreachable,
+}
+
+/// A binding which can't be used as configured.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Conflict {
+    /// The mode of the binding, or `tree` for the keys of tree panels.
+    pub group: String,
+    pub keys: Vec<KeyEvent>,
+    /// The command or tree action the keys were bound to.
+    pub command: String,
+    pub kind: ConflictKind,
+    /// What takes the keys instead.
+    pub reason: String,
+}
+
+/// What keys are bound to in the keymap taking precedence.
+enum Lookup {
+    Bound(String),
+    /// The keys start longer bindings.
+    Prefix,
+    Unbound,
+}
+
+/// Writes keys like macros are written, e.g. `g<C-w>`.
+pub fn format_keys(keys: &[KeyEvent]) -> String {
+    keys.iter()
+        .map(|key| {
+            let key = key.to_string();
+            if key.chars().count() == 1 {
+                key
+            } else {
+                format!("<{}>", key)
+            }
+        })
+        .collect()
+}
+
+fn sequence_name(commands: &[MappableCommand]) -> String {
+    let names: Vec<_> = commands.iter().map(MappableCommand::name).collect();
+    format!("[{}]", names.join(", "))
+}
+
+fn lookup_trie(root: &KeyTrie, keys: &[KeyEvent]) -> Lookup {
+    match root.search(keys) {
+        Some(KeyTrie::Leaf(command)) => Lookup::Bound(command.name().to_string()),
+        Some(KeyTrie::Sequence(commands)) => Lookup::Bound(sequence_name(commands)),
+        Some(KeyTrie::Node(_)) => Lookup::Prefix,
+        None => Lookup::Unbound,
+    }
+}
+
+fn lookup_tree(keymap: &TreeKeymap, keys: &[KeyEvent]) -> Lookup {
+    match keymap.search(keys) {
+        Some(TreeKeyTrie::Action(action)) => Lookup::Bound(action.to_string()),
+        Some(TreeKeyTrie::Node(_)) => Lookup::Prefix,
+        None => Lookup::Unbound,
+    }
+}
+
+/// The key sequences of `trie` bound to commands, with the names of the commands.
+fn trie_bindings(trie: &KeyTrie) -> Vec<(Vec<KeyEvent>, String)> {
+    fn collect(
+        trie: &KeyTrie,
+        keys: &mut Vec<KeyEvent>,
+        bindings: &mut Vec<(Vec<KeyEvent>, String)>,
+    ) {
+        match trie {
+            KeyTrie::Leaf(command) => bindings.push((keys.clone(), command.name().to_string())),
+            KeyTrie::Sequence(commands) => bindings.push((keys.clone(), sequence_name(commands))),
+            KeyTrie::Node(node) => {
+                for (key, trie) in node.iter() {
+                    keys.push(*key);
+                    collect(trie, keys, bindings);
+                    keys.pop();
+                }
+            }
+        }
+    }
+    let mut bindings = Vec::new();
+    collect(trie, &mut Vec::new(), &mut bindings);
+    bindings
+}
+
+/// Checks `bindings` against the keymap looked up by `lookup`, which takes precedence over
+/// them. `source` names that keymap, like "in the config".
+fn check(
+    group: &str,
+    bindings: Vec<(Vec<KeyEvent>, String)>,
+    lookup: impl Fn(&[KeyEvent]) -> Lookup,
+    source: &str,
+    conflicts: &mut Vec<Conflict>,
+) {
+    for (keys, command) in bindings {
+        let prefix = (1..keys.len()).find_map(|len| match lookup(&keys[..len]) {
+            Lookup::Bound(other) => Some((len, other)),
+            Lookup::Prefix | Lookup::Unbound => None,
+        });
+        let (kind, reason) = match prefix {
+            Some((len, other)) => (
+                ConflictKind::Unreachable,
+                format!(
+                    "`{}` runs `{}` {}",
+                    format_keys(&keys[..len]),
+                    other,
+                    source
+                ),
+            ),
+            None => match lookup(&keys) {
+                Lookup::Bound(other) if other == command => continue,
+                Lookup::Bound(other) => (
+                    ConflictKind::Shadowed,
+                    format!("runs `{}` {}", other, source),
+                ),
+                Lookup::Prefix => (
+                    ConflictKind::Shadowed,
+                    format!("starts other bindings {}", source),
+                ),
+                Lookup::Unbound => (
+                    ConflictKind::Unreachable,
+                    format!(
+                        "`{}` is bound {} without it",
+                        format_keys(&keys[..1]),
+                        source
+                    ),
+                ),
+            },
+        };
+        conflicts.push(Conflict {
+            group: group.to_string(),
+            keys,
+            command,
+            kind,
+            reason,
+        });
+    }
+}
+
+/// Finds the default bindings replaced by the config, the bindings hidden by keymap layers
+/// such as the ones of languages, and the tree bindings that counts or panels take first.
+/// `keys` and `tree_keys` are the keymaps merged with the config.
+pub fn find_conflicts(
+    keys: &HashMap<Mode, Keymap>,
+    layers: &[KeymapLayer],
+    tree_keys: &TreeKeymap,
+) -> Vec<Conflict> {
+    let mut conflicts = Vec::new();
+    let defaults = default();
+    for mode in [Mode::Normal, Mode::Select, Mode::Insert] {
+        let group = mode.to_string();
+        let keymap = match keys.get(&mode) {
+            Some(keymap) => keymap,
+            None => continue,
+        };
+        if let Some(default) = defaults.get(&mode) {
+            check(
+                &group,
+                trie_bindings(default.root()),
+                |keys| lookup_trie(keymap.root(), keys),
+                "in the config",
+                &mut conflicts,
+            );
+        }
+
+        // a layer binding the first key of a sequence takes the whole sequence
+        for layer in layers {
+            let layer_root = match layer.keys.get(&mode) {
+                Some(layer_keymap) => layer_keymap.root(),
+                None => continue,
+            };
+            let hidden = trie_bindings(keymap.root())
+                .into_iter()
+                .filter(|(keys, _)| layer_root.search(&keys[..1]).is_some())
+                .collect();
+            check(
+                &group,
+                hidden,
+                |keys| lookup_trie(layer_root, keys),
+                &format!("by the `{}` layer", layer.name),
+                &mut conflicts,
+            );
+        }
+    }
+
+    check(
+        "tree",
+        TreeKeymap::default()
+            .bindings()
+            .into_iter()
+            .map(|(keys, action)| (keys, action.to_string()))
+            .collect(),
+        |keys| lookup_tree(tree_keys, keys),
+        "in the config",
+        &mut conflicts,
+    );
+    for (keys, action) in tree_keys.bindings() {
+        let first = keys[0];
+        let reason = match first.code {
+            KeyCode::Char('0'..='9') if first.modifiers == KeyModifiers::NONE => {
+                "digits type a count"
+            }
+            KeyCode::Esc => "closes most panels before their tree gets it",
+            KeyCode::Char('q') if first.modifiers == KeyModifiers::NONE => {
+                "closes most panels before their tree gets it"
+            }
+            KeyCode::Char('c') if first.modifiers == KeyModifiers::CONTROL => {
+                "closes most panels before their tree gets it"
+            }
+            _ => continue,
+        };
+        conflicts.push(Conflict {
+            group: "tree".to_string(),
+            reason: format!("`{}` {}", format_keys(&keys[..1]), reason),
+            keys,
+            command: action.to_string(),
+            kind: ConflictKind::Unreachable,
+        });
+    }
+
+    conflicts.sort_by_cached_key(|conflict| format_keys(&conflict.keys));
+    conflicts
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::keymap::{macros::keymap, LayerScope};
+    use helix_core::hashmap;
+
+    fn key(keys: &str) -> Vec<KeyEvent> {
+        keys.split(' ').map(|key| key.parse().unwrap()).collect()
+    }
+
+    #[test]
+    fn defaults_have_no_conflicts() {
+        let conflicts = find_conflicts(&default(), &[], &TreeKeymap::default());
+        assert_eq!(conflicts, Vec::new());
+    }
+
+    #[test]
+    fn prefix_bound_in_config() {
+        let mut keys = default();
+        keys.get_mut(&Mode::Normal)
+            .unwrap()
+            .merge(Keymap::new(keymap!({ "Normal mode"
+                "g" => goto_file_start,
+                "x" => move_char_left,
+            })));
+        let conflicts = find_conflicts(&keys, &[], &TreeKeymap::default());
+
+        let goto_definition = conflicts
+            .iter()
+            .find(|conflict| conflict.keys == key("g d"))
+            .unwrap();
+        assert_eq!(goto_definition.kind, ConflictKind::Unreachable);
+        assert_eq!(goto_definition.group, "normal");
+
+        let extend_line = conflicts
+            .iter()
+            .find(|conflict| conflict.keys == key("x") && conflict.group == "normal")
+            .unwrap();
+        assert_eq!(extend_line.kind, ConflictKind::Shadowed);
+        assert_eq!(extend_line.reason, "runs `move_char_left` in the config");
+    }
+
+    #[test]
+    fn layer_takes_first_key() {
+        let layers = [KeymapLayer {
+            name: "test".to_string(),
+            scope: LayerScope::Language("test".to_string()),
+            keys: hashmap! {
+                Mode::Normal => Keymap::new(keymap!({ "Test"
+                    "g" => { "Goto"
+                        "x" => goto_definition,
+                    },
+                })),
+            },
+        }];
+        let conflicts = find_conflicts(&default(), &layers, &TreeKeymap::default());
+        let goto_definition = conflicts
+            .iter()
+            .find(|conflict| conflict.keys == key("g d"))
+            .unwrap();
+        assert_eq!(goto_definition.kind, ConflictKind::Unreachable);
+        assert_eq!(
+            goto_definition.reason,
+            "`g` is bound by the `test` layer without it"
+        );
+    }
+
+    #[test]
+    fn tree_keys_taken_by_counts() {
+        let mut tree_keys = TreeKeymap::default();
+        tree_keys.merge(toml::from_str(r#"1 = "page_down""#).unwrap());
+        let conflicts = find_conflicts(&default(), &[], &tree_keys);
+        assert_eq!(conflicts.len(), 1);
+        assert_eq!(conflicts[0].group, "tree");
+        assert_eq!(conflicts[0].command, "page_down");
+        assert_eq!(conflicts[0].kind, ConflictKind::Unreachable);
+    }
+    #[test]
+    fn tree_keys_replacing_panel_keys() {
+        // the panels peek, jump and fold through the tree keymap, so replacing their keys
+        // hides those actions
+        let mut tree_keys = TreeKeymap::default();
+        tree_keys.merge(toml::from_str(r#"p = "goto_parent""#).unwrap());
+        let conflicts = find_conflicts(&default(), &[], &tree_keys);
+        assert_eq!(conflicts.len(), 1);
+        assert_eq!(conflicts[0].group, "tree");
+        assert_eq!(conflicts[0].keys, key("p"));
+        assert_eq!(conflicts[0].command, "peek");
+        assert_eq!(conflicts[0].kind, ConflictKind::Shadowed);
+        assert_eq!(conflicts[0].reason, "runs `goto_parent` in the config");
+    }
+}
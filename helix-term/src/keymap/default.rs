@@ -100,11 +100,14 @@ pub fn default() -> HashMap<Mode, Keymap> {
         "[" => { "Left bracket"
             "d" => goto_prev_diag,
             "D" => goto_first_diag,
+            "e" => goto_prev_error,
+            "w" => goto_prev_warning,
             "g" => goto_prev_change,
             "G" => goto_first_change,
             "f" => goto_prev_function,
             "t" => goto_prev_class,
             "a" => goto_prev_parameter,
+            "A" => swap_prev_parameter,
             "c" => goto_prev_comment,
             "T" => goto_prev_test,
             "p" => goto_prev_paragraph,
@@ -113,11 +116,14 @@ pub fn default() -> HashMap<Mode, Keymap> {
         "]" => { "Right bracket"
             "d" => goto_next_diag,
             "D" => goto_last_diag,
+            "e" => goto_next_error,
+            "w" => goto_next_warning,
             "g" => goto_next_change,
             "G" => goto_last_change,
             "f" => goto_next_function,
             "t" => goto_next_class,
             "a" => goto_next_parameter,
+            "A" => swap_next_parameter,
             "c" => goto_next_comment,
             "T" => goto_next_test,
             "p" => goto_next_paragraph,
@@ -168,6 +174,16 @@ pub fn default() -> HashMap<Mode, Keymap> {
 
         "A-:" => ensure_selections_forward,
 
+        "A-1" => goto_pin_1,
+        "A-2" => goto_pin_2,
+        "A-3" => goto_pin_3,
+        "A-4" => goto_pin_4,
+        "A-5" => goto_pin_5,
+        "A-6" => goto_pin_6,
+        "A-7" => goto_pin_7,
+        "A-8" => goto_pin_8,
+        "A-9" => goto_pin_9,
+
         "esc" => normal_mode,
         "C-b" | "pageup" => page_up,
         "C-f" | "pagedown" => page_down,
@@ -191,6 +207,10 @@ pub fn default() -> HashMap<Mode, Keymap> {
             "K" => swap_view_up,
             "H" => swap_view_left,
             "J" => swap_view_down,
+            "r" => rotate_splits,
+            "R" => rotate_splits_reverse,
+            "z" => zoom_view,
+            "Z" => zen_mode,
             "n" => { "New split scratch buffer"
                 "C-s" | "s" => hsplit_new,
                 "C-v" | "v" => vsplit_new,
@@ -228,7 +248,15 @@ pub fn default() -> HashMap<Mode, Keymap> {
                 "v" => dap_variables,
                 "t" => dap_terminate,
                 "C-c" => dap_edit_condition,
+                "C-h" => dap_edit_hit_condition,
                 "C-l" => dap_edit_log,
+                "d" => dap_toggle_data_breakpoint,
+                "B" => dap_breakpoints,
+                "r" => dap_console,
+                "w" => dap_watches,
+                "S" => dap_call_stack,
+                "m" => dap_memory,
+                "a" => dap_disassemble,
                 "s" => { "Switch"
                     "t" => dap_switch_thread,
                     "f" => dap_switch_stack_frame,
@@ -254,6 +282,10 @@ pub fn default() -> HashMap<Mode, Keymap> {
                 "J" => swap_view_down,
                 "K" => swap_view_up,
                 "L" => swap_view_right,
+                "r" => rotate_splits,
+                "R" => rotate_splits_reverse,
+                "z" => zoom_view,
+                "Z" => zen_mode,
                 "n" => { "New split scratch buffer"
                     "C-s" | "s" => hsplit_new,
                     "C-v" | "v" => vsplit_new,
@@ -269,8 +301,12 @@ pub fn default() -> HashMap<Mode, Keymap> {
             "r" => rename_symbol,
             "h" => select_references_to_symbol_under_cursor,
             "?" => command_palette,
+            ":" => command_history,
+            "." => repeat_last_command,
             "e" => toggle_or_focus_explorer,
             "E" => open_explorer_recursion,
+            "m" => pin_file,
+            "M" => pinned_files,
         },
         "z" => { "View"
             "z" | "c" => align_view_center,
@@ -377,3 +413,30 @@ pub fn default() -> HashMap<Mode, Keymap> {
         Mode::Insert => Keymap::new(insert),
     )
 }
+
+/// Keymap layers of languages, active while a document of the language is focused.
+pub fn language_layers() -> HashMap<String, HashMap<Mode, Keymap>> {
+    let markdown = keymap!({ "Markdown"
+        "A-h" => promote_heading,
+        "A-l" => demote_heading,
+        "A-k" => move_section_up,
+        "A-j" => move_section_down,
+        "A-z" => fold_headings_to_level,
+    });
+    hashmap!(
+        "markdown".to_string() => hashmap!(Mode::Normal => Keymap::new(markdown)),
+    )
+}
+
+/// The layer pushed for a document while `:table-mode` is enabled for it.
+pub fn table_layer() -> HashMap<Mode, Keymap> {
+    let normal = keymap!({ "Table"
+        "tab" => table_next_cell,
+        "S-tab" => table_prev_cell,
+        "A-k" => table_cell_above,
+        "A-j" => table_cell_below,
+        "A-=" => table_reflow,
+        "A-a" => table_align_column,
+    });
+    hashmap!(Mode::Normal => Keymap::new(normal))
+}
@@ -6,9 +6,12 @@
 pub mod commands;
 pub mod compositor;
 pub mod config;
+pub mod headless;
 pub mod health;
+pub mod ipc;
 pub mod job;
 pub mod keymap;
+pub mod terminal_colors;
 pub mod ui;
 pub use keymap::macros::*;
 
@@ -58,6 +58,7 @@ async fn main_impl() -> Result<i32> {
 
 ARGS:
     <files>...    Sets the input file to use, position can also be specified via file[:row[:col]]
+                  or +row[:col] before the file
 
 FLAGS:
     -h, --help                     Prints help information
@@ -73,6 +74,14 @@ async fn main_impl() -> Result<i32> {
     -V, --version                  Prints version information
     --vsplit                       Splits all given files vertically into different windows
     --hsplit                       Splits all given files horizontally into different windows
+    --explorer                     Opens the file explorer
+    --outline                      Opens the outline panel
+    --headless                     Applies the --command and --keys steps to each file in order
+                                   without a terminal UI, and writes the files they changed
+    --command <command>            A command to run in --headless mode, e.g. ':format' or
+                                   'collapse_selection', can be given several times
+    --keys <keys>                  Keys to type in --headless mode, written like macros,
+                                   e.g. '%sfoo<ret>cbar<esc>', can be given several times
 ",
         env!("CARGO_PKG_NAME"),
         VERSION_AND_GIT_HASH,
@@ -149,6 +158,10 @@ async fn main_impl() -> Result<i32> {
         helix_core::config::default_syntax_loader()
     });
 
+    if args.headless {
+        return helix_term::headless::run(args, config, syn_loader_conf).await;
+    }
+
     // TODO: use the thread local executor to spawn the application task separately from the work pool
     let mut app = Application::new(args, config, syn_loader_conf)
         .context("unable to create new application")?;
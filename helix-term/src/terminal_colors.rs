@@ -0,0 +1,175 @@
+//! Asks the terminal for its background and foreground colors with the OSC 10 and 11 queries,
+//! to derive the `adaptive` theme from them.
+
+use helix_view::theme::TerminalColors;
+use std::time::Duration;
+#[cfg(unix)]
+use std::time::Instant;
+
+/// How long to wait for the terminal to answer.
+pub const QUERY_TIMEOUT: Duration = Duration::from_millis(100);
+
+/// Queries the colors of the terminal, returning `None` if it doesn't report them in time.
+/// Must be called before the terminal events are read, as the answers come in through stdin.
+#[cfg(unix)]
+pub fn query(timeout: Duration) -> Option<TerminalColors> {
+    use crossterm::{terminal, tty::IsTty};
+    use std::io::{stdin, stdout, Write};
+    use std::os::unix::io::AsRawFd;
+
+    if !stdin().is_tty() || !stdout().is_tty() {
+        return None;
+    }
+    terminal::enable_raw_mode().ok()?;
+    // every terminal answers the primary device attributes query ending the queries, so
+    // reading stops once the color answers, if any, came in
+    let mut out = stdout();
+    let written = out
+        .write_all(b"\x1b]10;?\x1b\\\x1b]11;?\x1b\\\x1b[c")
+        .and_then(|_| out.flush());
+    if written.is_err() {
+        let _ = terminal::disable_raw_mode();
+        return None;
+    }
+
+    let answers = read_answers(stdin().as_raw_fd(), timeout);
+    let _ = terminal::disable_raw_mode();
+
+    let answers = String::from_utf8_lossy(&answers?).into_owned();
+    Some(TerminalColors {
+        background: parse_color(&answers, 11)?,
+        foreground: parse_color(&answers, 10)?,
+    })
+}
+
+/// Reads the answers to the queries from `fd` up to the end of the device attributes answer.
+/// Only waits for `fd` to become readable until `timeout` passes and reads a byte at a time, so
+/// keystrokes following the answers are left for the terminal event reader.
+#[cfg(unix)]
+fn read_answers(fd: std::os::unix::io::RawFd, timeout: Duration) -> Option<Vec<u8>> {
+    use std::io::{Error, ErrorKind};
+
+    let deadline = Instant::now() + timeout;
+    let mut answers = Vec::new();
+    while !ends_with_device_attributes(&answers) {
+        let remaining = deadline.checked_duration_since(Instant::now())?;
+        // round up so a remaining fraction of a millisecond doesn't turn into a busy loop
+        let millis = ((remaining.as_micros() + 999) / 1000).min(libc::c_int::MAX as u128);
+        let mut pollfd = libc::pollfd {
+            fd,
+            events: libc::POLLIN,
+            revents: 0,
+        };
+        match unsafe { libc::poll(&mut pollfd, 1, millis as libc::c_int) } {
+            0 => continue,
+            ready if ready < 0 => {
+                if Error::last_os_error().kind() == ErrorKind::Interrupted {
+                    continue;
+                }
+                return None;
+            }
+            _ if pollfd.revents & libc::POLLIN == 0 => return None,
+            _ => {}
+        }
+
+        let mut byte = 0u8;
+        match unsafe { libc::read(fd, &mut byte as *mut u8 as *mut libc::c_void, 1) } {
+            1 => answers.push(byte),
+            read if read < 0 && Error::last_os_error().kind() == ErrorKind::Interrupted => {}
+            _ => return None,
+        }
+    }
+    Some(answers)
+}
+
+#[cfg(not(unix))]
+pub fn query(_timeout: Duration) -> Option<TerminalColors> {
+    None
+}
+
+/// Whether `answers` ends with the answer to the primary device attributes query,
+/// `ESC [ ? ... c`.
+fn ends_with_device_attributes(answers: &[u8]) -> bool {
+    if answers.last() != Some(&b'c') {
+        return false;
+    }
+    let start = match answers.windows(3).rposition(|window| window == b"\x1b[?") {
+        Some(start) => start,
+        None => return false,
+    };
+    answers[start + 3..answers.len() - 1]
+        .iter()
+        .all(|&byte| byte.is_ascii_digit() || byte == b';')
+}
+
+/// Parses the answer to the OSC `code` query in `answers`, e.g.
+/// `ESC ] 11 ; rgb:1c1c/1c1c/1c1c ESC \`, with one to four hex digits per channel.
+fn parse_color(answers: &str, code: u8) -> Option<(u8, u8, u8)> {
+    let prefix = format!("\x1b]{};rgb:", code);
+    let start = answers.find(&prefix)? + prefix.len();
+    let rest = &answers[start..];
+    let end = rest.find(|c| c == '\x1b' || c == '\x07')?;
+    let mut channels = rest[..end].split('/').map(|channel| {
+        if channel.is_empty() || channel.len() > 4 {
+            return None;
+        }
+        let value = u32::from_str_radix(channel, 16).ok()?;
+        let max = (1 << (4 * channel.len())) - 1;
+        Some(((value * 255 + max / 2) / max) as u8)
+    });
+    let color = (channels.next()??, channels.next()??, channels.next()??);
+    match channels.next() {
+        Some(_) => None,
+        None => Some(color),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_colors() {
+        let answers = "\x1b]10;rgb:ffff/8080/0000\x1b\\\x1b]11;rgb:1c/1c/1c\x07\x1b[?62;22c";
+        assert_eq!(parse_color(answers, 10), Some((255, 128, 0)));
+        assert_eq!(parse_color(answers, 11), Some((28, 28, 28)));
+        assert_eq!(
+            parse_color("\x1b]11;rgb:f/0/8\x1b\\", 11),
+            Some((255, 0, 136))
+        );
+        assert_eq!(parse_color("\x1b]11;rgb:ff/00\x1b\\", 11), None);
+        assert_eq!(parse_color("\x1b[?62c", 11), None);
+
+        assert!(ends_with_device_attributes(answers.as_bytes()));
+        assert!(!ends_with_device_attributes(b"\x1b]11;rgb:1c/1c/1c\x07"));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn read_answers_timeout() {
+        use std::io::{Read, Write};
+        use std::os::unix::{io::AsRawFd, net::UnixStream};
+
+        let timeout = Duration::from_millis(20);
+        let (mut terminal, mut tty) = UnixStream::pair().unwrap();
+
+        // a terminal that doesn't answer
+        let start = Instant::now();
+        assert_eq!(read_answers(tty.as_raw_fd(), timeout), None);
+        assert!(start.elapsed() >= timeout);
+
+        // an answer missing the device attributes
+        terminal.write_all(b"\x1b]11;rgb:1c/1c/1c\x07").unwrap();
+        assert_eq!(read_answers(tty.as_raw_fd(), timeout), None);
+
+        // nothing is read past the device attributes, e.g. keys pressed afterwards
+        terminal.write_all(b"\x1b[?62cjk").unwrap();
+        assert_eq!(
+            read_answers(tty.as_raw_fd(), timeout).as_deref(),
+            Some(&b"\x1b[?62c"[..])
+        );
+        let mut keys = [0; 2];
+        tty.read_exact(&mut keys).unwrap();
+        assert_eq!(&keys, b"jk");
+    }
+}
@@ -0,0 +1,371 @@
+use super::{tree::selected_style, MouseAction, Prompt, Tree, TreeItem};
+use crate::{
+    commands::dap::{
+        breakpoint_field, remove_breakpoint, set_breakpoint_field, BreakpointField, BreakpointRef,
+    },
+    compositor::{Component, Compositor, Context, EventResult},
+    ctrl, key, shift, ui,
+};
+use helix_core::{Position, Selection};
+use helix_view::{
+    align_view,
+    editor::{Action, Breakpoint, DataBreakpoint},
+    graphics::{CursorKind, Rect},
+    input::{Event, KeyEvent},
+    Align, Editor,
+};
+use std::cmp::Ordering;
+use std::path::{Path, PathBuf};
+use tui::{
+    buffer::Buffer as Surface,
+    text::{Span, Spans},
+    widgets::{Block, Borders, Widget},
+};
+
+#[derive(Debug, Clone)]
+enum BreakpointItem {
+    File(PathBuf),
+    Source {
+        path: PathBuf,
+        index: usize,
+        breakpoint: Breakpoint,
+    },
+    DataGroup,
+    Data {
+        index: usize,
+        breakpoint: DataBreakpoint,
+    },
+}
+
+impl BreakpointItem {
+    /// Files sorted by path with their breakpoints, followed by the data breakpoints.
+    fn sort_key(&self) -> (bool, Option<&Path>, usize) {
+        match self {
+            Self::File(path) => (false, Some(path), 0),
+            Self::Source { path, index, .. } => (false, Some(path), index + 1),
+            Self::DataGroup => (true, None, 0),
+            Self::Data { index, .. } => (true, None, index + 1),
+        }
+    }
+
+    fn target(&self) -> Option<BreakpointRef> {
+        match self {
+            Self::Source { path, index, .. } => Some(BreakpointRef::Source(path.clone(), *index)),
+            Self::Data { index, .. } => Some(BreakpointRef::Data(*index)),
+            Self::File(_) | Self::DataGroup => None,
+        }
+    }
+}
+
+fn conditions_label(
+    condition: &Option<String>,
+    hit_condition: &Option<String>,
+    log_message: &Option<String>,
+) -> String {
+    let mut label = String::new();
+    if let Some(condition) = condition {
+        label.push_str(&format!(" if {}", condition));
+    }
+    if let Some(hit_condition) = hit_condition {
+        label.push_str(&format!(" hits {}", hit_condition));
+    }
+    if let Some(log_message) = log_message {
+        label.push_str(&format!(" log \"{}\"", log_message));
+    }
+    label
+}
+
+impl TreeItem for BreakpointItem {
+    type Params = ();
+
+    fn text(&self, cx: &mut Context, selected: bool, _params: &mut Self::Params) -> Spans {
+        let theme = &cx.editor.theme;
+        let (text, verified) = match self {
+            Self::File(path) => (
+                helix_core::path::get_relative_path(path)
+                    .to_string_lossy()
+                    .into_owned(),
+                true,
+            ),
+            Self::Source { breakpoint, .. } => (
+                format!(
+                    "line {}{}",
+                    breakpoint.line + 1,
+                    conditions_label(
+                        &breakpoint.condition,
+                        &breakpoint.hit_condition,
+                        &breakpoint.log_message
+                    )
+                ),
+                breakpoint.verified,
+            ),
+            Self::DataGroup => ("data breakpoints".to_string(), true),
+            Self::Data { breakpoint, .. } => (
+                format!(
+                    "{}{}{}",
+                    breakpoint.description,
+                    breakpoint
+                        .access_type
+                        .map(|access_type| format!(" ({})", access_type.as_str()))
+                        .unwrap_or_default(),
+                    conditions_label(&breakpoint.condition, &breakpoint.hit_condition, &None)
+                ),
+                breakpoint.verified,
+            ),
+        };
+
+        let mut style = match self {
+            Self::File(_) | Self::DataGroup => theme.get("ui.text.focus"),
+            _ if verified => theme.get("ui.text"),
+            // breakpoints the debugger has not confirmed yet
+            _ => theme.get("comment"),
+        };
+        if selected {
+            style = style.patch(selected_style(cx));
+        }
+        Spans::from(Span::styled(text, style))
+    }
+
+    fn is_child(&self, other: &Self) -> bool {
+        match (self, other) {
+            (Self::Source { path, .. }, Self::File(parent)) => path == parent,
+            (Self::Data { .. }, Self::DataGroup) => true,
+            _ => false,
+        }
+    }
+
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.sort_key().cmp(&other.sort_key())
+    }
+}
+
+/// Lists all breakpoints grouped by file, along with the data breakpoints, and allows editing
+/// their conditions, hit conditions and log messages.
+pub struct BreakpointsTree {
+    tree: Tree<BreakpointItem>,
+    is_empty: bool,
+    prompt: Option<(BreakpointRef, BreakpointField, Prompt)>,
+}
+
+impl BreakpointsTree {
+    pub fn new(editor: &Editor) -> Self {
+        let items = Self::items(editor);
+        Self {
+            is_empty: items.is_empty(),
+            tree: Tree::build_tree(items),
+            prompt: None,
+        }
+    }
+
+    fn items(editor: &Editor) -> Vec<BreakpointItem> {
+        let mut items = Vec::new();
+        for (path, breakpoints) in &editor.breakpoints {
+            if breakpoints.is_empty() {
+                continue;
+            }
+            items.push(BreakpointItem::File(path.clone()));
+            items.extend(breakpoints.iter().enumerate().map(|(index, breakpoint)| {
+                BreakpointItem::Source {
+                    path: path.clone(),
+                    index,
+                    breakpoint: breakpoint.clone(),
+                }
+            }));
+        }
+        if !editor.data_breakpoints.is_empty() {
+            items.push(BreakpointItem::DataGroup);
+            items.extend(
+                editor
+                    .data_breakpoints
+                    .iter()
+                    .enumerate()
+                    .map(|(index, breakpoint)| BreakpointItem::Data {
+                        index,
+                        breakpoint: breakpoint.clone(),
+                    }),
+            );
+        }
+        items
+    }
+
+    /// Rebuilds the tree from the editor's breakpoints, keeping the selected row.
+    fn refresh(&mut self, editor: &Editor) {
+        let selected = self.tree.selected();
+        let items = Self::items(editor);
+        self.is_empty = items.is_empty();
+        self.tree.replace_with_new_items(items);
+        self.tree.move_down(selected);
+    }
+
+    fn current_target(&self) -> Option<BreakpointRef> {
+        if self.is_empty {
+            return None;
+        }
+        self.tree.current_item().target()
+    }
+
+    fn new_prompt(&mut self, cx: &mut Context, field: BreakpointField) {
+        let target = match self.current_target() {
+            Some(target) => target,
+            None => return,
+        };
+        if field == BreakpointField::LogMessage && matches!(target, BreakpointRef::Data(_)) {
+            cx.editor
+                .set_error("Data breakpoints can't have log messages");
+            return;
+        }
+        let mut prompt = Prompt::new(
+            field.prompt().into(),
+            None,
+            ui::completers::none,
+            |_, _, _| {},
+        );
+        if let Some(value) = breakpoint_field(cx.editor, &target, field) {
+            prompt.insert_str(&value, cx.editor);
+        }
+        self.prompt = Some((target, field, prompt));
+    }
+
+    fn handle_prompt_event(&mut self, event: KeyEvent, cx: &mut Context) -> EventResult {
+        let (target, field, mut prompt) = match self.prompt.take() {
+            Some(prompt) => prompt,
+            None => return EventResult::Ignored(None),
+        };
+        match event.into() {
+            key!(Enter) => {
+                set_breakpoint_field(cx.editor, &target, field, prompt.line());
+                self.refresh(cx.editor);
+            }
+            key!(Esc) | ctrl!('c') => {}
+            _ => {
+                prompt.handle_event(&Event::Key(event), cx);
+                self.prompt = Some((target, field, prompt));
+            }
+        }
+        EventResult::Consumed(None)
+    }
+
+    fn jump_to_current(&self, editor: &mut Editor) -> bool {
+        let (path, line) = match self.tree.current_item() {
+            BreakpointItem::Source {
+                path, breakpoint, ..
+            } => (path, breakpoint.line),
+            _ => return false,
+        };
+        if let Err(e) = editor.open(path, Action::Replace) {
+            editor.set_error(format!("Failed to open {}: {}", path.display(), e));
+            return false;
+        }
+        let (view, doc) = current!(editor);
+        let text = doc.text();
+        let line = line.min(text.len_lines().saturating_sub(1));
+        doc.set_selection(view.id, Selection::point(text.line_to_char(line)));
+        align_view(doc, view, Align::Center);
+        true
+    }
+}
+
+impl Component for BreakpointsTree {
+    fn handle_event(&mut self, event: &Event, cx: &mut Context) -> EventResult {
+        let key_event = match event {
+            Event::Key(event) => *event,
+            Event::Mouse(event) if self.prompt.is_none() => {
+                match self.tree.handle_mouse(event, cx, &mut ()) {
+                    MouseAction::Activate => key!(Enter),
+                    MouseAction::Handled => return EventResult::Consumed(None),
+                    MouseAction::Ignored => return EventResult::Ignored(None),
+                }
+            }
+            Event::Resize(..) => return EventResult::Consumed(None),
+            _ => return EventResult::Ignored(None),
+        };
+
+        if self.prompt.is_some() {
+            return self.handle_prompt_event(key_event, cx);
+        }
+
+        let close_fn = EventResult::Consumed(Some(Box::new(|compositor: &mut Compositor, _| {
+            compositor.pop();
+        })));
+
+        match key_event.into() {
+            key!(Esc) | key!('q') | ctrl!('c') => return close_fn,
+            key!(Enter) if !self.is_empty => {
+                if self.jump_to_current(cx.editor) {
+                    return close_fn;
+                }
+                self.tree.handle_event(Event::Key(key_event), cx, &mut ());
+            }
+            key!('c') => self.new_prompt(cx, BreakpointField::Condition),
+            shift!('H') => self.new_prompt(cx, BreakpointField::HitCondition),
+            shift!('L') => self.new_prompt(cx, BreakpointField::LogMessage),
+            key!('d') => {
+                if let Some(target) = self.current_target() {
+                    remove_breakpoint(cx.editor, &target);
+                    self.refresh(cx.editor);
+                }
+            }
+            _ if self.is_empty => {}
+            _ => {
+                self.tree.handle_event(Event::Key(key_event), cx, &mut ());
+            }
+        }
+        EventResult::Consumed(None)
+    }
+
+    fn render(&mut self, area: Rect, surface: &mut Surface, cx: &mut Context) {
+        let background = cx.editor.theme.get("ui.background");
+        surface.clear_with(area, background);
+
+        let block = Block::default()
+            .title(" Breakpoints ")
+            .borders(Borders::ALL)
+            .border_style(super::border_style(&cx.editor.theme, cx.focus));
+        let inner = block.inner(area);
+        block.render(area, surface);
+
+        if self.is_empty {
+            let style = cx.editor.theme.get("comment");
+            surface.set_stringn(
+                inner.x,
+                inner.y,
+                "No breakpoints",
+                inner.width as usize,
+                style,
+            );
+            return;
+        }
+
+        let tree_area = inner.clip_bottom(1);
+        self.tree.render(tree_area, surface, cx, &mut ());
+
+        let footer_area = inner.clip_top(tree_area.height);
+        match &self.prompt {
+            Some((_, _, prompt)) => prompt.render_prompt(footer_area, surface, cx),
+            None => {
+                let style = cx.editor.theme.get("comment");
+                surface.set_stringn(
+                    footer_area.x,
+                    footer_area.y,
+                    "[c]ondition [H]it condition [L]og message [d]elete",
+                    footer_area.width as usize,
+                    style,
+                );
+            }
+        }
+    }
+
+    fn cursor(&self, area: Rect, editor: &Editor) -> (Option<Position>, CursorKind) {
+        match &self.prompt {
+            Some((_, _, prompt)) => {
+                let inner = Block::default().borders(Borders::ALL).inner(area);
+                prompt.cursor(inner.clip_top(inner.height.saturating_sub(1)), editor)
+            }
+            None => (None, CursorKind::Hidden),
+        }
+    }
+
+    fn is_panel(&self) -> bool {
+        true
+    }
+}
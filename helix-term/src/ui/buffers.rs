@@ -0,0 +1,282 @@
+use super::{
+    tree::{patch_selected, selected_style},
+    MouseAction, Tree, TreeItem,
+};
+use crate::{
+    compositor::{Component, Compositor, Context, EventResult},
+    ctrl, key,
+};
+use helix_core::{path::get_relative_path, Position};
+use helix_view::{
+    document::SCRATCH_BUFFER_NAME,
+    editor::{Action, CloseError},
+    graphics::{CursorKind, Rect},
+    input::Event,
+    DocumentId, Editor,
+};
+use std::{
+    cmp::Ordering,
+    collections::{hash_map::DefaultHasher, BTreeSet},
+    hash::{Hash, Hasher},
+    path::{Path, PathBuf},
+};
+use tui::{
+    buffer::Buffer as Surface,
+    text::{Span, Spans},
+    widgets::{Block, Borders, Widget},
+};
+
+/// The id, the path relative to the working directory and whether it is modified of each
+/// open document.
+type Buffers = Vec<(DocumentId, Option<PathBuf>, bool)>;
+
+#[derive(Debug, Clone)]
+enum BufferItem {
+    Dir {
+        path: PathBuf,
+    },
+    Buffer {
+        id: DocumentId,
+        path: Option<PathBuf>,
+        modified: bool,
+    },
+}
+
+impl BufferItem {
+    fn path(&self) -> Option<&Path> {
+        match self {
+            Self::Dir { path } => Some(path),
+            Self::Buffer { path, .. } => path.as_deref(),
+        }
+    }
+
+    /// Scratch buffers go last, everything else in path order so that directories come right
+    /// before their contents.
+    fn sort_key(&self) -> (bool, Option<&Path>, Option<DocumentId>) {
+        match self {
+            Self::Dir { path } => (false, Some(path), None),
+            Self::Buffer { id, path, .. } => (path.is_none(), path.as_deref(), Some(*id)),
+        }
+    }
+}
+
+impl TreeItem for BufferItem {
+    type Params = ();
+
+    fn text(&self, cx: &mut Context, selected: bool, _params: &mut ()) -> Spans {
+        let theme = &cx.editor.theme;
+        let name = self
+            .path()
+            .and_then(Path::file_name)
+            .map(|name| name.to_string_lossy().into_owned());
+        let mut spans = match self {
+            Self::Dir { .. } => vec![Span::styled(
+                format!("{}/", name.unwrap_or_default()),
+                theme.get("ui.text.focus"),
+            )],
+            Self::Buffer { modified, .. } => {
+                let mut spans = vec![Span::styled(
+                    name.unwrap_or_else(|| SCRATCH_BUFFER_NAME.to_string()),
+                    theme.get("ui.text"),
+                )];
+                if *modified {
+                    spans.push(Span::styled(" [+]", theme.get("warning")));
+                }
+                spans
+            }
+        };
+        if selected {
+            patch_selected(cx, &mut spans);
+        }
+        Spans::from(spans)
+    }
+
+    /// Marks the buffer of the current view.
+    fn columns(&self, cx: &mut Context, selected: bool, _params: &mut ()) -> Vec<Spans> {
+        let id = match self {
+            Self::Buffer { id, .. } => *id,
+            Self::Dir { .. } => return Vec::new(),
+        };
+        if view!(cx.editor).doc != id {
+            return Vec::new();
+        }
+        let mut style = cx.editor.theme.get("comment");
+        if selected {
+            style = style.patch(selected_style(cx));
+        }
+        vec![Spans::from(Span::styled("current", style))]
+    }
+
+    fn is_child(&self, other: &Self) -> bool {
+        match (self.path().and_then(Path::parent), other) {
+            (Some(parent), Self::Dir { path }) => parent == path,
+            _ => false,
+        }
+    }
+
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.sort_key().cmp(&other.sort_key())
+    }
+
+    fn node_id(&self) -> Option<u64> {
+        let mut hasher = DefaultHasher::new();
+        self.sort_key().hash(&mut hasher);
+        Some(hasher.finish())
+    }
+}
+
+/// The open buffers in the hierarchy of their directories. The tree follows buffers being
+/// opened, closed and modified while it is open.
+pub struct BuffersTree {
+    tree: Tree<BufferItem>,
+    /// The buffers the tree was built from.
+    buffers: Buffers,
+}
+
+impl BuffersTree {
+    pub fn new(editor: &Editor) -> Self {
+        let buffers = Self::buffers(editor);
+        Self {
+            tree: Tree::build_tree(Self::items(&buffers)),
+            buffers,
+        }
+    }
+
+    fn buffers(editor: &Editor) -> Buffers {
+        editor
+            .documents()
+            .map(|doc| {
+                let path = doc.path().map(|path| get_relative_path(path).into_owned());
+                (doc.id(), path, doc.is_modified())
+            })
+            .collect()
+    }
+
+    fn items(buffers: &Buffers) -> Vec<BufferItem> {
+        let mut dirs = BTreeSet::new();
+        let mut items = Vec::with_capacity(buffers.len());
+        for (id, path, modified) in buffers {
+            if let Some(path) = path {
+                dirs.extend(
+                    path.ancestors()
+                        .skip(1)
+                        .filter(|dir| !dir.as_os_str().is_empty())
+                        .map(Path::to_path_buf),
+                );
+            }
+            items.push(BufferItem::Buffer {
+                id: *id,
+                path: path.clone(),
+                modified: *modified,
+            });
+        }
+        items.extend(dirs.into_iter().map(|path| BufferItem::Dir { path }));
+        items
+    }
+
+    /// Rebuilds the tree if buffers were opened, closed or modified since it was built.
+    fn refresh(&mut self, editor: &Editor) {
+        let buffers = Self::buffers(editor);
+        if self.buffers == buffers {
+            return;
+        }
+        self.buffers = buffers;
+        self.tree.replace_with_new_items(Self::items(&self.buffers));
+    }
+
+    fn current_buffer(&self) -> Option<DocumentId> {
+        if self.tree.is_empty() {
+            return None;
+        }
+        match self.tree.current_item() {
+            BufferItem::Buffer { id, .. } => Some(*id),
+            BufferItem::Dir { .. } => None,
+        }
+    }
+
+    fn close(&mut self, editor: &mut Editor, id: DocumentId) {
+        match editor.close_document(id, false) {
+            Ok(()) => self.refresh(editor),
+            Err(CloseError::BufferModified(name)) => {
+                editor.set_error(format!("{} has unsaved changes", name))
+            }
+            Err(CloseError::SaveError(e)) => editor.set_error(format!("{}", e)),
+            Err(CloseError::DoesNotExist) => {}
+        }
+    }
+}
+
+impl Component for BuffersTree {
+    fn handle_event(&mut self, event: &Event, cx: &mut Context) -> EventResult {
+        let key_event = match event {
+            Event::Key(event) => *event,
+            Event::Mouse(event) => match self.tree.handle_mouse(event, cx, &mut ()) {
+                MouseAction::Activate => key!(Enter),
+                MouseAction::Handled => return EventResult::Consumed(None),
+                MouseAction::Ignored => return EventResult::Ignored(None),
+            },
+            Event::Resize(..) => return EventResult::Consumed(None),
+            _ => return EventResult::Ignored(None),
+        };
+
+        let close_fn = EventResult::Consumed(Some(Box::new(|compositor: &mut Compositor, _| {
+            compositor.pop();
+        })));
+
+        match key_event.into() {
+            key!(Esc) | key!('q') | ctrl!('c') => return close_fn,
+            key!(Enter) => match self.current_buffer() {
+                Some(id) => {
+                    cx.editor.switch(id, Action::Replace);
+                    return close_fn;
+                }
+                None => {
+                    self.tree.handle_event(Event::Key(key_event), cx, &mut ());
+                }
+            },
+            key!('d') => {
+                if let Some(id) = self.current_buffer() {
+                    self.close(cx.editor, id);
+                }
+            }
+            _ => {
+                self.tree.handle_event(Event::Key(key_event), cx, &mut ());
+            }
+        }
+        EventResult::Consumed(None)
+    }
+
+    fn render(&mut self, area: Rect, surface: &mut Surface, cx: &mut Context) {
+        self.refresh(cx.editor);
+
+        let background = cx.editor.theme.get("ui.background");
+        surface.clear_with(area, background);
+
+        let block = Block::default()
+            .title(" Buffers ")
+            .borders(Borders::ALL)
+            .border_style(super::border_style(&cx.editor.theme, cx.focus));
+        let inner = block.inner(area);
+        block.render(area, surface);
+
+        let tree_area = inner.clip_bottom(1);
+        self.tree.render(tree_area, surface, cx, &mut ());
+
+        let footer_area = inner.clip_top(tree_area.height);
+        surface.set_stringn(
+            footer_area.x,
+            footer_area.y,
+            "[enter]switch [d]close",
+            footer_area.width as usize,
+            cx.editor.theme.get("comment"),
+        );
+    }
+
+    fn cursor(&self, _area: Rect, _editor: &Editor) -> (Option<Position>, CursorKind) {
+        (None, CursorKind::Hidden)
+    }
+
+    fn is_panel(&self) -> bool {
+        true
+    }
+}
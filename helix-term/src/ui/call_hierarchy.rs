@@ -0,0 +1,343 @@
+use super::{
+    symbol_tree::{kind_label, peek_back, peek_location},
+    tree::patch_selected,
+    MouseAction, Tree, TreeItem, TreeKey, TreeOp,
+};
+use crate::{
+    compositor::{Component, Compositor, Context, EventResult},
+    ctrl, key,
+};
+use anyhow::Result;
+use futures_util::future::{BoxFuture, FutureExt};
+use helix_core::{path::get_relative_path, Position, Selection};
+use helix_lsp::{lsp, util::lsp_range_to_range, OffsetEncoding};
+use helix_view::{
+    align_view,
+    editor::Action,
+    graphics::{CursorKind, Rect},
+    input::Event,
+    tree_keymap::TreeAction,
+    Align, Editor,
+};
+use std::{cmp::Ordering, sync::Arc};
+use tui::{
+    buffer::Buffer as Surface,
+    text::{Span, Spans},
+    widgets::{Block, Borders, Widget},
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CallDirection {
+    /// The callers of a function.
+    Incoming,
+    /// The functions a function calls.
+    Outgoing,
+}
+
+#[derive(Debug, Clone)]
+struct CallItem {
+    item: lsp::CallHierarchyItem,
+    /// Where the call to or from the parent item happens. `None` for the items the hierarchy
+    /// was opened on.
+    call_site: Option<lsp::Location>,
+    /// How often the parent item calls or is called from this one.
+    calls: usize,
+    direction: CallDirection,
+    language_server: Arc<helix_lsp::Client>,
+    loaded: bool,
+}
+
+impl CallItem {
+    fn root(
+        item: lsp::CallHierarchyItem,
+        direction: CallDirection,
+        language_server: Arc<helix_lsp::Client>,
+    ) -> Self {
+        Self {
+            item,
+            call_site: None,
+            calls: 0,
+            direction,
+            language_server,
+            loaded: false,
+        }
+    }
+
+    fn child(&self, item: lsp::CallHierarchyItem, call_site: lsp::Location, calls: usize) -> Self {
+        Self {
+            item,
+            call_site: Some(call_site),
+            calls,
+            direction: self.direction,
+            language_server: self.language_server.clone(),
+            loaded: false,
+        }
+    }
+
+    /// The call site, or the item itself for the items the hierarchy was opened on.
+    fn location(&self) -> lsp::Location {
+        self.call_site
+            .clone()
+            .unwrap_or_else(|| lsp::Location::new(self.item.uri.clone(), self.item.selection_range))
+    }
+}
+
+impl TreeItem for CallItem {
+    type Params = ();
+
+    fn text(&self, cx: &mut Context, selected: bool, _params: &mut ()) -> Spans {
+        let theme = &cx.editor.theme;
+        let (label, name, fallback) = kind_label(self.item.kind);
+        let kind_style = theme
+            .try_get(&format!("ui.tree.symbol.{}", name))
+            .unwrap_or_else(|| theme.get(fallback));
+        let location = self.location();
+        let path = match location.uri.to_file_path() {
+            Ok(path) => get_relative_path(&path).to_string_lossy().into_owned(),
+            Err(_) => location.uri.to_string(),
+        };
+        let mut spans = vec![
+            Span::styled(format!("{:<7} ", label), kind_style),
+            Span::styled(self.item.name.clone(), theme.get("ui.text")),
+        ];
+        if let Some(detail) = self
+            .item
+            .detail
+            .as_ref()
+            .filter(|detail| !detail.is_empty())
+        {
+            spans.push(Span::styled(format!(" {}", detail), theme.get("comment")));
+        }
+        spans.push(Span::styled(
+            format!(" {}:{}", path, location.range.start.line + 1),
+            theme.get("comment"),
+        ));
+        if self.calls > 1 {
+            spans.push(Span::styled(
+                format!(" ({} calls)", self.calls),
+                theme.get("comment"),
+            ));
+        }
+        if selected {
+            patch_selected(cx, &mut spans);
+        }
+        Spans::from(spans)
+    }
+
+    fn is_child(&self, _other: &Self) -> bool {
+        // children are only ever loaded for a single item
+        false
+    }
+
+    fn cmp(&self, other: &Self) -> Ordering {
+        let (location, other) = (self.location(), other.location());
+        location
+            .uri
+            .as_str()
+            .cmp(other.uri.as_str())
+            .then(location.range.start.line.cmp(&other.range.start.line))
+            .then(
+                location
+                    .range
+                    .start
+                    .character
+                    .cmp(&other.range.start.character),
+            )
+    }
+
+    /// Requests the calls to or from the item from the language server.
+    fn load_childs(&self) -> Option<BoxFuture<'static, Result<Vec<Self>>>> {
+        let parent = self.clone();
+        match self.direction {
+            CallDirection::Incoming => {
+                let future = self.language_server.incoming_calls(self.item.clone())?;
+                Some(
+                    async move {
+                        let calls: Option<Vec<lsp::CallHierarchyIncomingCall>> =
+                            serde_json::from_value(future.await?)?;
+                        Ok(calls
+                            .unwrap_or_default()
+                            .into_iter()
+                            .filter_map(|call| {
+                                // the calls happen in the caller
+                                let range = *call.from_ranges.first()?;
+                                let call_site = lsp::Location::new(call.from.uri.clone(), range);
+                                Some(parent.child(call.from, call_site, call.from_ranges.len()))
+                            })
+                            .collect())
+                    }
+                    .boxed(),
+                )
+            }
+            CallDirection::Outgoing => {
+                let future = self.language_server.outgoing_calls(self.item.clone())?;
+                Some(
+                    async move {
+                        let calls: Option<Vec<lsp::CallHierarchyOutgoingCall>> =
+                            serde_json::from_value(future.await?)?;
+                        Ok(calls
+                            .unwrap_or_default()
+                            .into_iter()
+                            .filter_map(|call| {
+                                // the calls happen in the item that was expanded
+                                let range = *call.from_ranges.first()?;
+                                let call_site = lsp::Location::new(parent.item.uri.clone(), range);
+                                Some(parent.child(call.to, call_site, call.from_ranges.len()))
+                            })
+                            .collect())
+                    }
+                    .boxed(),
+                )
+            }
+        }
+    }
+}
+
+/// The callers or callees of the items under the cursor. Expanding an item requests its own
+/// callers or callees.
+pub struct CallHierarchy {
+    tree: Tree<CallItem>,
+    direction: CallDirection,
+    offset_encoding: OffsetEncoding,
+}
+
+impl CallHierarchy {
+    /// Returns `None` if there are no items.
+    pub fn new(
+        items: Vec<lsp::CallHierarchyItem>,
+        direction: CallDirection,
+        language_server: Arc<helix_lsp::Client>,
+    ) -> Option<Self> {
+        if items.is_empty() {
+            return None;
+        }
+        let offset_encoding = language_server.offset_encoding();
+        let items = items
+            .into_iter()
+            .map(|item| CallItem::root(item, direction, language_server.clone()))
+            .collect();
+        let tree = Tree::build_tree(items).with_enter_fn(|item, _cx, _params| {
+            if item.loaded {
+                return TreeOp::Restore;
+            }
+            item.loaded = true;
+            TreeOp::LoadChildsAndInsert
+        });
+        Some(Self {
+            tree,
+            direction,
+            offset_encoding,
+        })
+    }
+
+    /// Shows the selected call site while the tree stays open.
+    fn peek(&self, editor: &mut Editor) {
+        let location = self.tree.current_item().location();
+        match location.uri.to_file_path() {
+            Ok(path) => peek_location(editor, &path, location.range, self.offset_encoding),
+            Err(_) => editor.set_error(format!(
+                "unable to convert URI to filepath: {}",
+                location.uri
+            )),
+        }
+    }
+
+    /// Closes the tree and moves the cursor to the selected call site.
+    fn jump(&self) -> EventResult {
+        let location = self.tree.current_item().location();
+        let offset_encoding = self.offset_encoding;
+        EventResult::Consumed(Some(Box::new(move |compositor: &mut Compositor, cx| {
+            compositor.pop();
+            cx.editor.confirm_peeks();
+            let path = match location.uri.to_file_path() {
+                Ok(path) => path,
+                Err(_) => {
+                    cx.editor.set_error(format!(
+                        "unable to convert URI to filepath: {}",
+                        location.uri
+                    ));
+                    return;
+                }
+            };
+            let (view, doc) = current!(cx.editor);
+            view.jumps.push((doc.id(), doc.selection(view.id).clone()));
+            if let Err(e) = cx.editor.open(&path, Action::Replace) {
+                cx.editor
+                    .set_error(format!("Failed to open {}: {}", path.display(), e));
+                return;
+            }
+            let (view, doc) = current!(cx.editor);
+            if let Some(range) = lsp_range_to_range(doc.text(), location.range, offset_encoding) {
+                doc.set_selection(view.id, Selection::single(range.head, range.anchor));
+                align_view(doc, view, Align::Center);
+            }
+        })))
+    }
+}
+
+impl Component for CallHierarchy {
+    fn handle_event(&mut self, event: &Event, cx: &mut Context) -> EventResult {
+        let (action, count) = match event {
+            Event::Key(key!(Esc) | key!('q') | ctrl!('c')) => {
+                return EventResult::Consumed(Some(Box::new(|compositor: &mut Compositor, _| {
+                    compositor.pop();
+                })))
+            }
+            Event::Key(key_event) => match self.tree.key_action(*key_event, cx, &mut ()) {
+                TreeKey::Action(action, count) => (action, count),
+                TreeKey::Consumed | TreeKey::Ignored => return EventResult::Consumed(None),
+            },
+            Event::Mouse(event) => match self.tree.handle_mouse(event, cx, &mut ()) {
+                MouseAction::Activate => (TreeAction::Activate, 0),
+                MouseAction::Handled => return EventResult::Consumed(None),
+                MouseAction::Ignored => return EventResult::Ignored(None),
+            },
+            Event::Resize(..) => return EventResult::Consumed(None),
+            _ => return EventResult::Ignored(None),
+        };
+
+        match action {
+            TreeAction::Activate => return self.jump(),
+            TreeAction::Peek => self.peek(cx.editor),
+            TreeAction::PeekBack => peek_back(cx.editor),
+            action => self.tree.run_action(action, count, cx, &mut ()),
+        }
+        EventResult::Consumed(None)
+    }
+
+    fn render(&mut self, area: Rect, surface: &mut Surface, cx: &mut Context) {
+        let background = cx.editor.theme.get("ui.background");
+        surface.clear_with(area, background);
+
+        let title = match self.direction {
+            CallDirection::Incoming => " Incoming calls ",
+            CallDirection::Outgoing => " Outgoing calls ",
+        };
+        let block = Block::default()
+            .title(title)
+            .borders(Borders::ALL)
+            .border_style(super::border_style(&cx.editor.theme, cx.focus));
+        let inner = block.inner(area);
+        block.render(area, surface);
+
+        let rows_area = inner.clip_bottom(1);
+        self.tree.render(rows_area, surface, cx, &mut ());
+
+        let footer_area = inner.clip_top(rows_area.height);
+        surface.set_stringn(
+            footer_area.x,
+            footer_area.y,
+            "[enter]jump [p]peek [bs]back [o]expand",
+            footer_area.width as usize,
+            cx.editor.theme.get("comment"),
+        );
+    }
+
+    fn cursor(&self, _area: Rect, _editor: &Editor) -> (Option<Position>, CursorKind) {
+        (None, CursorKind::Hidden)
+    }
+
+    fn is_panel(&self) -> bool {
+        true
+    }
+}
@@ -0,0 +1,415 @@
+use super::{
+    memory,
+    overlay::overlayed,
+    tree::{patch_selected, selected_style},
+    DisassemblyView, MouseAction, Tree, TreeItem, TreeOp,
+};
+use crate::{
+    compositor::{Component, Compositor, Context, EventResult},
+    ctrl, key,
+};
+use futures_util::FutureExt;
+use helix_dap::{requests, StackFrame, ThreadId};
+use helix_lsp::block_on;
+use helix_view::{
+    graphics::Rect,
+    handlers::dap::{fetch_stack_trace, jump_to_stack_frame},
+    input::{Event, KeyEvent},
+    Editor,
+};
+use std::{
+    cmp::Ordering,
+    sync::{Arc, Mutex},
+};
+use tui::{
+    buffer::Buffer as Surface,
+    text::{Span, Spans},
+    widgets::{Block, Borders, Widget},
+};
+
+#[derive(Debug, Clone)]
+enum StackItem {
+    Thread {
+        index: usize,
+        id: ThreadId,
+        name: String,
+        /// Whether the frames were fetched, so expanding again restores them.
+        loaded: bool,
+    },
+    Frame {
+        thread_index: usize,
+        thread_id: ThreadId,
+        index: usize,
+        frame: StackFrame,
+    },
+}
+
+impl StackItem {
+    /// Orders the frames right after their thread.
+    fn key(&self) -> (usize, usize) {
+        match self {
+            Self::Thread { index, .. } => (*index, 0),
+            Self::Frame {
+                thread_index,
+                index,
+                ..
+            } => (*thread_index, index + 1),
+        }
+    }
+
+    fn frames(
+        thread_index: usize,
+        thread_id: ThreadId,
+        frames: Vec<StackFrame>,
+    ) -> impl Iterator<Item = Self> {
+        frames
+            .into_iter()
+            .enumerate()
+            .map(move |(index, frame)| Self::Frame {
+                thread_index,
+                thread_id,
+                index,
+                frame,
+            })
+    }
+
+    fn is_active(&self, editor: &Editor) -> bool {
+        let debugger = match editor.debugger.as_ref() {
+            Some(debugger) => debugger,
+            None => return false,
+        };
+        match self {
+            Self::Thread { id, .. } => debugger.thread_id == Some(*id),
+            Self::Frame {
+                thread_id, index, ..
+            } => debugger.thread_id == Some(*thread_id) && debugger.active_frame == Some(*index),
+        }
+    }
+}
+
+impl TreeItem for StackItem {
+    type Params = ();
+
+    fn text(&self, cx: &mut Context, selected: bool, _params: &mut ()) -> Spans {
+        let theme = &cx.editor.theme;
+        let marker = if self.is_active(cx.editor) {
+            "● "
+        } else {
+            "  "
+        };
+        let (name, style) = match self {
+            Self::Thread { name, .. } => (name, theme.get("ui.linenr.selected")),
+            Self::Frame { frame, .. } => (&frame.name, theme.get("function")),
+        };
+
+        let mut spans = vec![
+            Span::styled(marker, theme.get("ui.text")),
+            Span::styled(name.clone(), style),
+        ];
+        if selected {
+            patch_selected(cx, &mut spans);
+        }
+        Spans::from(spans)
+    }
+
+    fn columns(&self, cx: &mut Context, selected: bool, _params: &mut ()) -> Vec<Spans> {
+        let text = match self {
+            Self::Thread { id, .. } => cx
+                .editor
+                .debugger
+                .as_ref()
+                .and_then(|debugger| debugger.thread_states.get(id))
+                .cloned()
+                .unwrap_or_default(),
+            Self::Frame { frame, .. } => {
+                let source = frame.source.as_ref().and_then(|source| {
+                    source.name.clone().or_else(|| {
+                        let path = source.path.as_ref()?;
+                        Some(path.file_name()?.to_string_lossy().into_owned())
+                    })
+                });
+                match source {
+                    Some(source) => format!("{}:{}", source, frame.line),
+                    None => "<unknown>".to_string(),
+                }
+            }
+        };
+        let mut style = cx.editor.theme.get("comment");
+        if selected {
+            style = style.patch(selected_style(cx));
+        }
+        vec![Spans::from(Span::styled(text, style))]
+    }
+
+    fn is_child(&self, other: &Self) -> bool {
+        match (self, other) {
+            (Self::Frame { thread_id, .. }, Self::Thread { id, .. }) => thread_id == id,
+            _ => false,
+        }
+    }
+
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.key().cmp(&other.key())
+    }
+}
+
+/// Shows the threads of the debuggee with their stack frames. The threads are fetched again
+/// whenever the debuggee stops, and selecting a frame makes it the active one.
+pub struct CallStackTree {
+    tree: Tree<StackItem>,
+    /// The stop count of the debugger as of the last refresh.
+    stops: Option<usize>,
+    /// Threads fetched in the background, until they replace the tree.
+    loaded: Arc<Mutex<Option<Result<Vec<(ThreadId, String)>, String>>>>,
+    loading: bool,
+    error: Option<String>,
+}
+
+impl CallStackTree {
+    pub fn new() -> Self {
+        Self {
+            tree: Tree::build_tree(Vec::new()).with_enter_fn(Self::expand),
+            stops: None,
+            loaded: Arc::default(),
+            loading: false,
+            error: None,
+        }
+    }
+
+    /// Fetches the threads if the debuggee stopped since the last refresh. The threads whose
+    /// frames the debugger already knows, like the stopped one, are shown expanded.
+    fn refresh(&mut self, cx: &mut Context) {
+        let debugger = match cx.editor.debugger.as_ref() {
+            Some(debugger) => debugger,
+            None => return,
+        };
+
+        if let Some(threads) = self.loaded.lock().unwrap().take() {
+            self.loading = false;
+            match threads {
+                Ok(threads) => {
+                    let mut items = Vec::new();
+                    for (index, (id, name)) in threads.into_iter().enumerate() {
+                        let frames = debugger.stack_frames.get(&id).cloned();
+                        items.push(StackItem::Thread {
+                            index,
+                            id,
+                            name,
+                            loaded: frames.is_some(),
+                        });
+                        if let Some(frames) = frames {
+                            items.extend(StackItem::frames(index, id, frames));
+                        }
+                    }
+                    let selected = self.tree.selected();
+                    self.error = None;
+                    self.tree.replace_with_new_items(items);
+                    self.tree.move_down(selected);
+                }
+                Err(e) => self.error = Some(e),
+            }
+        }
+
+        if self.stops == Some(debugger.stops) {
+            return;
+        }
+        self.stops = Some(debugger.stops);
+
+        let request = debugger.threads();
+        let loaded = self.loaded.clone();
+        self.loading = true;
+        cx.jobs.spawn(async move {
+            let threads = async {
+                let response: requests::ThreadsResponse = serde_json::from_value(request.await?)?;
+                let threads: Vec<_> = response
+                    .threads
+                    .into_iter()
+                    .map(|thread| (thread.id, thread.name))
+                    .collect();
+                Ok::<_, helix_dap::Error>(threads)
+            }
+            .await
+            .map_err(|e| format!("Failed to get threads: {}", e));
+            *loaded.lock().unwrap() = Some(threads);
+            Ok(())
+        });
+    }
+
+    fn expand(item: &mut StackItem, cx: &mut Context, _params: &mut ()) -> TreeOp<StackItem> {
+        let (thread_index, thread_id, loaded) = match item {
+            StackItem::Thread {
+                index, id, loaded, ..
+            } => (*index, *id, loaded),
+            StackItem::Frame { .. } => return TreeOp::Noop,
+        };
+        if *loaded {
+            return TreeOp::Restore;
+        }
+        let debugger = match cx.editor.debugger.as_ref() {
+            Some(debugger) => debugger,
+            None => {
+                cx.editor.set_error("Debugger is not running");
+                return TreeOp::Noop;
+            }
+        };
+        *loaded = true;
+
+        if let Some(frames) = debugger.stack_frames.get(&thread_id) {
+            let frames = StackItem::frames(thread_index, thread_id, frames.clone());
+            return TreeOp::InsertChild(frames.collect());
+        }
+        let request = debugger.call::<requests::StackTrace>(requests::StackTraceArguments {
+            thread_id,
+            start_frame: None,
+            levels: None,
+            format: None,
+        });
+        TreeOp::LoadAndInsert(
+            async move {
+                let response: requests::StackTraceResponse =
+                    serde_json::from_value(request.await?)?;
+                let frames = StackItem::frames(thread_index, thread_id, response.stack_frames);
+                Ok(frames.collect())
+            }
+            .boxed(),
+        )
+    }
+
+    /// Makes the selected frame the active one of its thread and jumps to its location.
+    fn select_frame(&mut self, cx: &mut Context) -> bool {
+        let (thread_id, index, frame) = match self.tree.current_item() {
+            StackItem::Frame {
+                thread_id,
+                index,
+                frame,
+                ..
+            } => (*thread_id, *index, frame.clone()),
+            StackItem::Thread { .. } => return false,
+        };
+        let debugger = match cx.editor.debugger.as_mut() {
+            Some(debugger) => debugger,
+            None => return false,
+        };
+        if debugger.thread_id != Some(thread_id) || !debugger.stack_frames.contains_key(&thread_id)
+        {
+            debugger.thread_id = Some(thread_id);
+            block_on(fetch_stack_trace(debugger, thread_id));
+        }
+        debugger.active_frame = Some(index);
+        jump_to_stack_frame(cx.editor, &frame);
+        true
+    }
+
+    /// Opens the disassembly around the instruction pointer of the selected frame.
+    fn disassemble(&self, cx: &mut Context) -> EventResult {
+        let memory_reference = match self.tree.current_item() {
+            StackItem::Frame { frame, .. } => frame.instruction_pointer_reference.clone(),
+            StackItem::Thread { .. } => return EventResult::Consumed(None),
+        };
+        let memory_reference = match memory_reference {
+            Some(memory_reference) => memory_reference,
+            None => {
+                cx.editor
+                    .set_error("Stack frame has no instruction pointer");
+                return EventResult::Consumed(None);
+            }
+        };
+        if !memory::can_disassemble(cx.editor) {
+            return EventResult::Consumed(None);
+        }
+        EventResult::Consumed(Some(Box::new(|compositor: &mut Compositor, _| {
+            compositor.push(Box::new(overlayed(DisassemblyView::new(memory_reference))));
+        })))
+    }
+
+    fn handle_key(&mut self, event: KeyEvent, cx: &mut Context) -> EventResult {
+        let close_fn = EventResult::Consumed(Some(Box::new(|compositor: &mut Compositor, _| {
+            compositor.pop();
+        })));
+
+        match event.into() {
+            key!(Esc) | key!('q') | ctrl!('c') => return close_fn,
+            key!(Enter) if !self.tree.is_empty() && self.select_frame(cx) => return close_fn,
+            key!('d') if !self.tree.is_empty() => return self.disassemble(cx),
+            _ => {
+                self.tree.handle_event(Event::Key(event), cx, &mut ());
+            }
+        }
+        EventResult::Consumed(None)
+    }
+}
+
+impl Default for CallStackTree {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Component for CallStackTree {
+    fn handle_event(&mut self, event: &Event, cx: &mut Context) -> EventResult {
+        match event {
+            Event::Key(event) => self.handle_key(*event, cx),
+            Event::Mouse(event) => match self.tree.handle_mouse(event, cx, &mut ()) {
+                MouseAction::Activate => self.handle_key(key!(Enter), cx),
+                MouseAction::Handled => EventResult::Consumed(None),
+                MouseAction::Ignored => EventResult::Ignored(None),
+            },
+            Event::Resize(..) => EventResult::Consumed(None),
+            _ => EventResult::Ignored(None),
+        }
+    }
+
+    fn render(&mut self, area: Rect, surface: &mut Surface, cx: &mut Context) {
+        self.refresh(cx);
+
+        let background = cx.editor.theme.get("ui.background");
+        surface.clear_with(area, background);
+
+        let block = Block::default()
+            .title(" Call stack ")
+            .borders(Borders::ALL)
+            .border_style(super::border_style(&cx.editor.theme, cx.focus));
+        let inner = block.inner(area);
+        block.render(area, surface);
+
+        let tree_area = inner.clip_bottom(1);
+        let message = if cx.editor.debugger.is_none() {
+            Some("Debugger is not running")
+        } else if let Some(error) = &self.error {
+            Some(error.as_str())
+        } else if self.loading && self.tree.is_empty() {
+            Some("Loading…")
+        } else if self.tree.is_empty() {
+            Some("No threads")
+        } else {
+            None
+        };
+        match message {
+            Some(message) => {
+                let style = cx.editor.theme.get("comment");
+                surface.set_stringn(
+                    tree_area.x,
+                    tree_area.y,
+                    message,
+                    tree_area.width as usize,
+                    style,
+                );
+            }
+            None => self.tree.render(tree_area, surface, cx, &mut ()),
+        }
+
+        let footer_area = inner.clip_top(tree_area.height);
+        let style = cx.editor.theme.get("comment");
+        surface.set_stringn(
+            footer_area.x,
+            footer_area.y,
+            "[enter]expand thread/select frame [d]isassemble",
+            footer_area.width as usize,
+            style,
+        );
+    }
+
+    fn is_panel(&self) -> bool {
+        true
+    }
+}
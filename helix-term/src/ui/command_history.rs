@@ -0,0 +1,183 @@
+use super::{tree::patch_selected, MouseAction, Tree, TreeItem};
+use crate::{
+    commands::{self, run_history_entry, MappableCommand},
+    compositor::{Component, Compositor, Context, EventResult},
+    ctrl, key,
+};
+use helix_core::Position;
+use helix_view::{
+    graphics::{CursorKind, Rect},
+    input::Event,
+    Editor,
+};
+use std::cmp::Ordering;
+use tui::{
+    buffer::Buffer as Surface,
+    text::{Span, Spans},
+    widgets::{Block, Borders, Widget},
+};
+
+#[derive(Debug, Clone)]
+struct CommandItem {
+    /// Position in the history, counted from the newest command.
+    index: usize,
+    entry: String,
+}
+
+impl TreeItem for CommandItem {
+    type Params = ();
+
+    fn text(&self, cx: &mut Context, selected: bool, _params: &mut ()) -> Spans {
+        let theme = &cx.editor.theme;
+        let mut spans = vec![Span::styled(self.entry.clone(), theme.get("ui.text"))];
+        // static commands are described by their doc
+        if !self.entry.starts_with(':') {
+            if let Ok(command) = self.entry.parse::<MappableCommand>() {
+                spans.push(Span::styled(
+                    format!("  {}", command.doc()),
+                    theme.get("comment"),
+                ));
+            }
+        }
+        if selected {
+            patch_selected(cx, &mut spans);
+        }
+        Spans::from(spans)
+    }
+
+    fn is_child(&self, _other: &Self) -> bool {
+        false
+    }
+
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.index.cmp(&other.index)
+    }
+}
+
+fn command_items(editor: &Editor) -> Vec<CommandItem> {
+    editor
+        .command_history
+        .iter()
+        .rev()
+        .enumerate()
+        .map(|(index, entry)| CommandItem {
+            index,
+            entry: entry.clone(),
+        })
+        .collect()
+}
+
+/// The commands run recently, newest first, to run them again.
+pub struct CommandHistory {
+    tree: Tree<CommandItem>,
+}
+
+impl CommandHistory {
+    /// Returns `None` if no commands were recorded yet.
+    pub fn new(editor: &Editor) -> Option<Self> {
+        let items = command_items(editor);
+        if items.is_empty() {
+            return None;
+        }
+        Some(Self {
+            tree: Tree::build_tree(items),
+        })
+    }
+
+    fn close() -> EventResult {
+        EventResult::Consumed(Some(Box::new(|compositor: &mut Compositor, _| {
+            compositor.pop();
+        })))
+    }
+
+    /// Closes the history and runs the selected command, which moves it to the top of the
+    /// history.
+    fn run(&self) -> EventResult {
+        let entry = self.tree.current_item().entry.clone();
+        EventResult::Consumed(Some(Box::new(move |compositor: &mut Compositor, cx| {
+            compositor.pop();
+            let mut ctx = commands::Context {
+                register: None,
+                count: None,
+                editor: cx.editor,
+                callback: None,
+                on_next_key_callback: None,
+                jobs: cx.jobs,
+            };
+            run_history_entry(&mut ctx, &entry);
+            if let Some(callback) = ctx.callback.take() {
+                callback(compositor, cx);
+            }
+        })))
+    }
+
+    fn remove(&mut self, editor: &mut Editor) -> EventResult {
+        let entry = &self.tree.current_item().entry;
+        editor.command_history.retain(|recorded| recorded != entry);
+        let items = command_items(editor);
+        if items.is_empty() {
+            return Self::close();
+        }
+        let selected = self.tree.selected().min(items.len() - 1);
+        self.tree.replace_with_new_items(items);
+        self.tree.move_down(selected);
+        EventResult::Consumed(None)
+    }
+}
+
+impl Component for CommandHistory {
+    fn handle_event(&mut self, event: &Event, cx: &mut Context) -> EventResult {
+        let key_event = match event {
+            Event::Key(event) => *event,
+            Event::Mouse(event) => match self.tree.handle_mouse(event, cx, &mut ()) {
+                MouseAction::Activate => key!(Enter),
+                MouseAction::Handled => return EventResult::Consumed(None),
+                MouseAction::Ignored => return EventResult::Ignored(None),
+            },
+            Event::Resize(..) => return EventResult::Consumed(None),
+            _ => return EventResult::Ignored(None),
+        };
+
+        match key_event.into() {
+            key!(Esc) | key!('q') | ctrl!('c') => return Self::close(),
+            key!(Enter) => return self.run(),
+            key!('d') => return self.remove(cx.editor),
+            _ => {
+                self.tree.handle_event(Event::Key(key_event), cx, &mut ());
+            }
+        }
+        EventResult::Consumed(None)
+    }
+
+    fn render(&mut self, area: Rect, surface: &mut Surface, cx: &mut Context) {
+        let background = cx.editor.theme.get("ui.background");
+        surface.clear_with(area, background);
+
+        let block = Block::default()
+            .title(" Command history ")
+            .borders(Borders::ALL)
+            .border_style(super::border_style(&cx.editor.theme, cx.focus));
+        let inner = block.inner(area);
+        block.render(area, surface);
+
+        let tree_area = inner.clip_bottom(1);
+        self.tree.render(tree_area, surface, cx, &mut ());
+
+        let footer_area = inner.clip_top(tree_area.height);
+        surface.set_stringn(
+            footer_area.x,
+            footer_area.y,
+            "[enter]run [d]elete",
+            footer_area.width as usize,
+            cx.editor.theme.get("comment"),
+        );
+    }
+
+    fn cursor(&self, _area: Rect, _editor: &Editor) -> (Option<Position>, CursorKind) {
+        (None, CursorKind::Hidden)
+    }
+
+    fn is_panel(&self) -> bool {
+        true
+    }
+}
@@ -87,6 +87,8 @@ pub struct Completion {
     start_offset: usize,
     #[allow(dead_code)]
     trigger_offset: usize,
+    /// Id of the language server which provided the items, used to resolve them.
+    language_server_id: usize,
     // TODO: maintain a completioncontext with trigger kind & trigger char
 }
 
@@ -97,6 +99,7 @@ pub fn new(
         editor: &Editor,
         mut items: Vec<CompletionItem>,
         offset_encoding: helix_lsp::OffsetEncoding,
+        language_server_id: usize,
         start_offset: usize,
         trigger_offset: usize,
     ) -> Self {
@@ -224,7 +227,7 @@ fn completion_changes(transaction: &Transaction, trigger_offset: usize) -> Vec<C
                     {
                         None
                     } else {
-                        Self::resolve_completion_item(doc, item.clone())
+                        Self::resolve_completion_item(doc, language_server_id, item.clone())
                     };
 
                     if let Some(additional_edits) = resolved_item
@@ -248,6 +251,7 @@ fn completion_changes(transaction: &Transaction, trigger_offset: usize) -> Vec<C
         let mut completion = Self {
             popup,
             start_offset,
+            language_server_id,
             trigger_offset,
         };
 
@@ -259,9 +263,12 @@ fn completion_changes(transaction: &Transaction, trigger_offset: usize) -> Vec<C
 
     fn resolve_completion_item(
         doc: &Document,
+        language_server_id: usize,
         completion_item: lsp::CompletionItem,
     ) -> Option<CompletionItem> {
-        let language_server = doc.language_server()?;
+        let language_server = doc
+            .language_servers()
+            .find(|language_server| language_server.id() == language_server_id)?;
 
         let future = language_server.resolve_completion_item(completion_item)?;
         let response = helix_lsp::block_on(future);
@@ -333,7 +340,10 @@ pub fn ensure_item_resolved(&mut self, cx: &mut commands::Context) -> bool {
             _ => return false,
         };
 
-        let language_server = match doc!(cx.editor).language_server() {
+        let language_server = match doc!(cx.editor)
+            .language_servers()
+            .find(|language_server| language_server.id() == self.language_server_id)
+        {
             Some(language_server) => language_server,
             None => return false,
         };
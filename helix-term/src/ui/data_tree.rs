@@ -0,0 +1,192 @@
+use super::{tree::patch_selected, MouseAction, Tree, TreeItem};
+use crate::{
+    compositor::{Component, Compositor, Context, EventResult},
+    ctrl, key,
+};
+use helix_core::{
+    data_tree::{data_tree, DataNode},
+    unicode::width::UnicodeWidthStr,
+    Position, Selection,
+};
+use helix_view::{
+    align_view,
+    graphics::{CursorKind, Rect},
+    input::Event,
+    Align, Document, DocumentId, Editor,
+};
+use std::cmp::Ordering;
+use tui::{
+    buffer::Buffer as Surface,
+    text::{Span, Spans},
+    widgets::{Block, Borders, Widget},
+};
+
+/// Width of the tree guides drawn per nesting level.
+const INDENT_WIDTH: usize = 2;
+
+#[derive(Debug, Clone)]
+struct DataItem {
+    index: usize,
+    depth: usize,
+    node: DataNode,
+}
+
+impl TreeItem for DataItem {
+    /// Width of the key column.
+    type Params = usize;
+
+    fn text(&self, cx: &mut Context, selected: bool, key_width: &mut usize) -> Spans {
+        let theme = &cx.editor.theme;
+        // keep the previews aligned across nesting levels
+        let width = key_width.saturating_sub(self.depth * INDENT_WIDTH);
+        let padding = " ".repeat(width.saturating_sub(self.node.label.width()));
+        let mut spans = vec![
+            Span::styled(
+                format!("{}{}", self.node.label, padding),
+                theme.get("variable"),
+            ),
+            Span::styled(format!(" {}", self.node.preview), theme.get("ui.text")),
+        ];
+        if selected {
+            patch_selected(cx, &mut spans);
+        }
+        Spans::from(spans)
+    }
+
+    fn is_child(&self, other: &Self) -> bool {
+        self.node.parent == Some(other.index)
+    }
+
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.index.cmp(&other.index)
+    }
+}
+
+/// The keys of a JSON, YAML or TOML buffer as a tree, with previews of their values.
+pub struct DataTree {
+    doc_id: DocumentId,
+    title: String,
+    tree: Tree<DataItem>,
+    /// Width of the widest key including its indentation.
+    key_width: usize,
+}
+
+impl DataTree {
+    /// Returns `None` if the buffer isn't parsed or holds no keys. The key containing `cursor`
+    /// is selected.
+    pub fn new(doc: &Document, cursor: usize) -> Option<Self> {
+        let text = doc.text().slice(..);
+        let nodes = data_tree(doc.syntax()?.tree().root_node(), text);
+        if nodes.is_empty() {
+            return None;
+        }
+
+        let mut items: Vec<DataItem> = Vec::with_capacity(nodes.len());
+        for (index, node) in nodes.into_iter().enumerate() {
+            let depth = node.parent.map_or(0, |parent| items[parent].depth + 1);
+            items.push(DataItem { index, depth, node });
+        }
+        let key_width = items
+            .iter()
+            .map(|item| item.depth * INDENT_WIDTH + item.node.label.width())
+            .max()
+            .unwrap_or_default();
+        let selected = items
+            .iter()
+            .rposition(|item| item.node.pos <= cursor)
+            .unwrap_or_default();
+
+        let mut tree = Tree::build_tree(items);
+        tree.move_down(selected);
+        Some(Self {
+            doc_id: doc.id(),
+            title: doc.display_name().into_owned(),
+            tree,
+            key_width,
+        })
+    }
+
+    fn jump(&self) -> EventResult {
+        let pos = self.tree.current_item().node.pos;
+        let doc_id = self.doc_id;
+        EventResult::Consumed(Some(Box::new(move |compositor: &mut Compositor, cx| {
+            compositor.pop();
+            let (view, doc) = current!(cx.editor);
+            if doc.id() != doc_id {
+                return;
+            }
+            doc.set_selection(view.id, Selection::point(pos.min(doc.text().len_chars())));
+            align_view(doc, view, Align::Center);
+        })))
+    }
+
+    fn yank_path(&self, cx: &mut Context) {
+        let path = self.tree.current_item().node.jq_path();
+        cx.editor.set_status(format!("Yanked {}", path));
+        cx.editor.registers.write('"', vec![path]);
+    }
+}
+
+impl Component for DataTree {
+    fn handle_event(&mut self, event: &Event, cx: &mut Context) -> EventResult {
+        let key_event = match event {
+            Event::Key(event) => *event,
+            Event::Mouse(event) => match self.tree.handle_mouse(event, cx, &mut self.key_width) {
+                MouseAction::Activate => key!(Enter),
+                MouseAction::Handled => return EventResult::Consumed(None),
+                MouseAction::Ignored => return EventResult::Ignored(None),
+            },
+            Event::Resize(..) => return EventResult::Consumed(None),
+            _ => return EventResult::Ignored(None),
+        };
+
+        match key_event.into() {
+            key!(Esc) | key!('q') | ctrl!('c') => {
+                return EventResult::Consumed(Some(Box::new(|compositor: &mut Compositor, _| {
+                    compositor.pop();
+                })))
+            }
+            key!(Enter) => return self.jump(),
+            key!('y') => self.yank_path(cx),
+            _ => {
+                self.tree
+                    .handle_event(Event::Key(key_event), cx, &mut self.key_width);
+            }
+        }
+        EventResult::Consumed(None)
+    }
+
+    fn render(&mut self, area: Rect, surface: &mut Surface, cx: &mut Context) {
+        let background = cx.editor.theme.get("ui.background");
+        surface.clear_with(area, background);
+
+        let block = Block::default()
+            .title(format!(" {} ", self.title))
+            .borders(Borders::ALL)
+            .border_style(super::border_style(&cx.editor.theme, cx.focus));
+        let inner = block.inner(area);
+        block.render(area, surface);
+
+        let tree_area = inner.clip_bottom(1);
+        let mut key_width = self.key_width.min(tree_area.width as usize / 2);
+        self.tree.render(tree_area, surface, cx, &mut key_width);
+
+        let footer_area = inner.clip_top(tree_area.height);
+        let style = cx.editor.theme.get("comment");
+        surface.set_stringn(
+            footer_area.x,
+            footer_area.y,
+            "[enter]jump [o]fold [y]ank path",
+            footer_area.width as usize,
+            style,
+        );
+    }
+
+    fn cursor(&self, _area: Rect, _editor: &Editor) -> (Option<Position>, CursorKind) {
+        (None, CursorKind::Hidden)
+    }
+
+    fn is_panel(&self) -> bool {
+        true
+    }
+}
@@ -0,0 +1,359 @@
+use super::{
+    memory, overlay::overlayed, tree::patch_selected, MemoryView, Prompt, Tree, TreeItem, TreeOp,
+};
+use crate::{
+    compositor::{Component, Compositor, Context, EventResult},
+    ctrl, key, ui,
+};
+use helix_core::Position;
+use helix_lsp::block_on;
+use helix_view::{
+    graphics::{CursorKind, Rect},
+    input::Event,
+    Editor,
+};
+use std::cmp::Ordering;
+use tui::{
+    buffer::Buffer as Surface,
+    text::{Span, Spans},
+    widgets::{Block, Borders, Widget},
+};
+
+/// Register holding the history of evaluated expressions.
+const HISTORY_REGISTER: char = '>';
+
+#[derive(Debug, Clone)]
+enum EntryKind {
+    Input(String),
+    Output {
+        category: Option<String>,
+        text: String,
+    },
+    Error(String),
+    /// An evaluation result or one of its fields, expandable if `variables_reference` is set.
+    Value {
+        name: Option<String>,
+        value: String,
+        ty: Option<String>,
+        variables_reference: usize,
+        memory_reference: Option<String>,
+    },
+}
+
+#[derive(Debug, Clone)]
+struct ConsoleEntry {
+    index: usize,
+    kind: EntryKind,
+}
+
+impl TreeItem for ConsoleEntry {
+    type Params = ();
+
+    fn text(&self, cx: &mut Context, selected: bool, _params: &mut Self::Params) -> Spans {
+        let theme = &cx.editor.theme;
+        let text_style = theme.get("ui.text");
+        let mut spans = match &self.kind {
+            EntryKind::Input(expression) => vec![Span::styled(
+                format!("> {}", expression),
+                theme.get("ui.text.focus"),
+            )],
+            EntryKind::Output { category, text } => {
+                let style = match category.as_deref() {
+                    Some("stderr") => theme.get("error"),
+                    Some("console") => theme.get("comment"),
+                    _ => text_style,
+                };
+                vec![Span::styled(text.clone(), style)]
+            }
+            EntryKind::Error(message) => vec![Span::styled(message.clone(), theme.get("error"))],
+            EntryKind::Value {
+                name,
+                value,
+                ty,
+                variables_reference,
+                ..
+            } => {
+                let marker = if *variables_reference > 0 {
+                    "▸ "
+                } else {
+                    "  "
+                };
+                let mut spans = vec![Span::styled(marker, text_style)];
+                if let Some(name) = name {
+                    spans.push(Span::styled(name.clone(), theme.get("variable")));
+                    if let Some(ty) = ty {
+                        spans.push(Span::raw(": "));
+                        spans.push(Span::styled(ty.clone(), theme.get("type")));
+                    }
+                    spans.push(Span::raw(" = "));
+                }
+                spans.push(Span::styled(value.clone(), text_style));
+                spans
+            }
+        };
+
+        if selected {
+            patch_selected(cx, &mut spans);
+        }
+        Spans::from(spans)
+    }
+
+    fn is_child(&self, _other: &Self) -> bool {
+        // children are only ever inserted when expanding a value
+        false
+    }
+
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.index.cmp(&other.index)
+    }
+}
+
+/// A bottom docked console which evaluates expressions in the context of the focused stack
+/// frame and shows the output of the debuggee. Structured results can be expanded into their
+/// fields.
+pub struct DebugConsole {
+    tree: Tree<ConsoleEntry>,
+    prompt: Prompt,
+    focus: bool,
+    /// Whether keys go to the input line rather than the entries.
+    input_focus: bool,
+    /// Number of `Editor::debug_output` entries already shown.
+    output_seen: usize,
+    next_index: usize,
+    prompt_area: Rect,
+}
+
+impl Default for DebugConsole {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl DebugConsole {
+    pub fn new() -> Self {
+        Self {
+            tree: Tree::build_tree(Vec::new()).with_enter_fn(Self::expand),
+            prompt: Self::new_prompt(),
+            focus: true,
+            input_focus: true,
+            output_seen: 0,
+            next_index: 0,
+            prompt_area: Rect::default(),
+        }
+    }
+
+    fn new_prompt() -> Prompt {
+        Prompt::new(
+            "> ".into(),
+            Some(HISTORY_REGISTER),
+            ui::completers::none,
+            |_, _, _| {},
+        )
+    }
+
+    pub fn focus(&mut self) {
+        self.focus = true;
+    }
+
+    pub fn unfocus(&mut self) {
+        self.focus = false;
+    }
+
+    pub fn is_focus(&self) -> bool {
+        self.focus
+    }
+
+    fn push(&mut self, kind: EntryKind) {
+        self.tree.push(ConsoleEntry {
+            index: self.next_index,
+            kind,
+        });
+        self.next_index += 1;
+        // follow the latest entry
+        self.tree.move_down(usize::MAX / 2);
+    }
+
+    /// Adds the debuggee output received since the last call.
+    fn sync_output(&mut self, editor: &Editor) {
+        // the output was cleared, e.g. by a new debug session
+        if self.output_seen > editor.debug_output.len() {
+            self.output_seen = 0;
+        }
+        for (category, output) in &editor.debug_output[self.output_seen..] {
+            for line in output.lines() {
+                self.push(EntryKind::Output {
+                    category: category.clone(),
+                    text: line.to_string(),
+                });
+            }
+        }
+        self.output_seen = editor.debug_output.len();
+    }
+
+    fn evaluate(&mut self, editor: &mut Editor, expression: String) {
+        self.push(EntryKind::Input(expression.clone()));
+
+        let debugger = match editor.debugger.as_mut() {
+            Some(debugger) => debugger,
+            None => {
+                self.push(EntryKind::Error("Debugger is not running".to_string()));
+                return;
+            }
+        };
+        let frame_id = debugger.current_stack_frame().map(|frame| frame.id);
+        match block_on(debugger.eval(expression, frame_id)) {
+            Ok(response) => self.push(EntryKind::Value {
+                name: None,
+                value: response.result,
+                ty: response.ty,
+                variables_reference: response.variables_reference,
+                memory_reference: response.memory_reference,
+            }),
+            Err(e) => self.push(EntryKind::Error(e.to_string())),
+        }
+    }
+
+    fn open_memory(&self, cx: &mut Context) -> EventResult {
+        let memory_reference = match &self.tree.current_item().kind {
+            EntryKind::Value {
+                memory_reference: Some(memory_reference),
+                ..
+            } => memory_reference.clone(),
+            _ => {
+                cx.editor.set_error("Entry does not refer to memory");
+                return EventResult::Consumed(None);
+            }
+        };
+        if !memory::can_read_memory(cx.editor) {
+            return EventResult::Consumed(None);
+        }
+        EventResult::Consumed(Some(Box::new(|compositor: &mut Compositor, _| {
+            compositor.push(Box::new(overlayed(MemoryView::new(memory_reference))));
+        })))
+    }
+
+    fn expand(
+        entry: &mut ConsoleEntry,
+        cx: &mut Context,
+        _params: &mut (),
+    ) -> TreeOp<ConsoleEntry> {
+        let variables_reference = match entry.kind {
+            EntryKind::Value {
+                variables_reference,
+                ..
+            } if variables_reference > 0 => variables_reference,
+            _ => return TreeOp::Noop,
+        };
+        let debugger = match cx.editor.debugger.as_mut() {
+            Some(debugger) => debugger,
+            None => {
+                cx.editor.set_error("Debugger is not running");
+                return TreeOp::Noop;
+            }
+        };
+        match block_on(debugger.variables(variables_reference)) {
+            Ok(variables) => TreeOp::InsertChild(
+                variables
+                    .into_iter()
+                    .enumerate()
+                    .map(|(index, variable)| ConsoleEntry {
+                        index,
+                        kind: EntryKind::Value {
+                            name: Some(variable.name),
+                            value: variable.value,
+                            ty: variable.ty,
+                            variables_reference: variable.variables_reference,
+                            memory_reference: variable.memory_reference,
+                        },
+                    })
+                    .collect(),
+            ),
+            Err(e) => {
+                cx.editor
+                    .set_error(format!("Failed to get variables: {}", e));
+                TreeOp::Noop
+            }
+        }
+    }
+}
+
+impl Component for DebugConsole {
+    fn handle_event(&mut self, event: &Event, cx: &mut Context) -> EventResult {
+        if !self.focus {
+            return EventResult::Ignored(None);
+        }
+        let key_event = match event {
+            Event::Key(event) => *event,
+            Event::Paste(_) if self.input_focus => {
+                self.prompt.handle_event(event, cx);
+                return EventResult::Consumed(None);
+            }
+            Event::Mouse(event) if !self.input_focus => {
+                return self.tree.handle_event(Event::Mouse(*event), cx, &mut ())
+            }
+            Event::Resize(..) => return EventResult::Consumed(None),
+            _ => return EventResult::Ignored(None),
+        };
+
+        let close_fn = EventResult::Consumed(Some(Box::new(|compositor: &mut Compositor, _| {
+            if let Some(editor) = compositor.find::<ui::EditorView>() {
+                editor.debug_console = None;
+            }
+        })));
+
+        match key_event.into() {
+            key!(Esc) => self.unfocus(),
+            ctrl!('c') => return close_fn,
+            key!(Tab) => self.input_focus = !self.input_focus,
+            key!(Enter) if self.input_focus => {
+                let expression = self.prompt.line().clone();
+                if !expression.is_empty() {
+                    cx.editor
+                        .registers
+                        .push(HISTORY_REGISTER, expression.clone());
+                    self.prompt = Self::new_prompt();
+                    self.evaluate(cx.editor, expression);
+                }
+            }
+            _ if self.input_focus => {
+                self.prompt.handle_event(event, cx);
+            }
+            key!('m') if self.next_index > 0 => return self.open_memory(cx),
+            _ => {
+                self.tree.handle_event(Event::Key(key_event), cx, &mut ());
+            }
+        }
+        EventResult::Consumed(None)
+    }
+
+    fn render(&mut self, area: Rect, surface: &mut Surface, cx: &mut Context) {
+        self.sync_output(cx.editor);
+
+        let background = cx.editor.theme.get("ui.background");
+        surface.clear_with(area, background);
+
+        let block = Block::default()
+            .title(" Debug console ")
+            .borders(Borders::TOP)
+            .border_style(super::border_style(&cx.editor.theme, self.focus));
+        let inner = block.inner(area);
+        block.render(area, surface);
+
+        let tree_area = inner.clip_bottom(1);
+        self.tree.render(tree_area, surface, cx, &mut ());
+
+        self.prompt_area = inner.clip_top(tree_area.height);
+        self.prompt.render_prompt(self.prompt_area, surface, cx);
+
+        if !self.focus {
+            super::dim_inactive(area, surface, &cx.editor.theme);
+        }
+    }
+
+    fn cursor(&self, _area: Rect, editor: &Editor) -> (Option<Position>, CursorKind) {
+        if !self.focus || !self.input_focus {
+            return (None, CursorKind::Hidden);
+        }
+        self.prompt.cursor(self.prompt_area, editor)
+    }
+}
@@ -0,0 +1,398 @@
+use super::{
+    symbol_tree::{peek_back, peek_location},
+    tree::{patch_selected, selected_style},
+    MouseAction, Tree, TreeItem, TreeKey,
+};
+use crate::{
+    compositor::{Component, Compositor, Context, EventResult},
+    ctrl, key,
+};
+use helix_core::{path::get_relative_path, Position, Selection};
+use helix_lsp::{
+    lsp::{self, DiagnosticSeverity, NumberOrString},
+    util::lsp_range_to_range,
+    OffsetEncoding,
+};
+use helix_view::{
+    align_view,
+    editor::Action,
+    graphics::{CursorKind, Rect},
+    input::Event,
+    tree_keymap::TreeAction,
+    Align, Editor,
+};
+use std::{
+    cmp::Ordering,
+    collections::{hash_map::DefaultHasher, BTreeMap},
+    hash::{Hash, Hasher},
+    path::{Path, PathBuf},
+};
+use tui::{
+    buffer::Buffer as Surface,
+    text::{Span, Spans},
+    widgets::{Block, Borders, Widget},
+};
+
+type Diagnostics = BTreeMap<lsp::Url, Vec<(lsp::Diagnostic, Option<usize>)>>;
+
+/// The label and the theme scope of a severity, from the most to the least severe.
+const SEVERITIES: [(&str, &str); 4] = [
+    ("error", "error"),
+    ("warning", "warning"),
+    ("info", "info"),
+    ("hint", "hint"),
+];
+
+fn severity_index(severity: Option<DiagnosticSeverity>) -> Option<usize> {
+    match severity? {
+        DiagnosticSeverity::ERROR => Some(0),
+        DiagnosticSeverity::WARNING => Some(1),
+        DiagnosticSeverity::INFORMATION => Some(2),
+        DiagnosticSeverity::HINT => Some(3),
+        _ => None,
+    }
+}
+
+#[derive(Debug, Clone)]
+enum DiagnosticItem {
+    File {
+        path: PathBuf,
+        /// The number of diagnostics of each of the `SEVERITIES`.
+        counts: [usize; 4],
+    },
+    Diagnostic {
+        path: PathBuf,
+        /// The position among the diagnostics of the file, which are sorted by severity and
+        /// line.
+        index: usize,
+        diagnostic: lsp::Diagnostic,
+        offset_encoding: OffsetEncoding,
+    },
+}
+
+impl DiagnosticItem {
+    fn path(&self) -> &Path {
+        match self {
+            Self::File { path, .. } | Self::Diagnostic { path, .. } => path,
+        }
+    }
+
+    fn sort_key(&self) -> (&Path, usize) {
+        match self {
+            Self::File { path, .. } => (path, 0),
+            Self::Diagnostic { path, index, .. } => (path, index + 1),
+        }
+    }
+}
+
+impl TreeItem for DiagnosticItem {
+    type Params = ();
+
+    fn text(&self, cx: &mut Context, selected: bool, _params: &mut ()) -> Spans {
+        let theme = &cx.editor.theme;
+        let mut spans = match self {
+            Self::File { path, counts } => {
+                let mut spans = vec![Span::styled(
+                    get_relative_path(path).to_string_lossy().into_owned(),
+                    theme.get("ui.text.focus"),
+                )];
+                for (count, (label, scope)) in counts.iter().zip(SEVERITIES) {
+                    if *count > 0 {
+                        let plural = if *count == 1 { "" } else { "s" };
+                        spans.push(Span::styled(
+                            format!(" {} {}{}", count, label, plural),
+                            theme.get(scope),
+                        ));
+                    }
+                }
+                spans
+            }
+            Self::Diagnostic { diagnostic, .. } => {
+                let icon_style = severity_index(diagnostic.severity)
+                    .map(|index| theme.get(SEVERITIES[index].1))
+                    .unwrap_or_default();
+                // only the first line of multi-line messages fits
+                let message = diagnostic.message.lines().next().unwrap_or_default();
+                vec![
+                    Span::styled("● ", icon_style),
+                    Span::styled(message.to_string(), theme.get("ui.text")),
+                ]
+            }
+        };
+        if selected {
+            patch_selected(cx, &mut spans);
+        }
+        Spans::from(spans)
+    }
+
+    /// The line and the source and code of a diagnostic.
+    fn columns(&self, cx: &mut Context, selected: bool, _params: &mut ()) -> Vec<Spans> {
+        let diagnostic = match self {
+            Self::Diagnostic { diagnostic, .. } => diagnostic,
+            Self::File { .. } => return Vec::new(),
+        };
+        let mut style = cx.editor.theme.get("comment");
+        if selected {
+            style = style.patch(selected_style(cx));
+        }
+        let code = diagnostic.code.as_ref().map(|code| match code {
+            NumberOrString::Number(n) => n.to_string(),
+            NumberOrString::String(s) => s.clone(),
+        });
+        let origin = match (&diagnostic.source, code) {
+            (Some(source), Some(code)) => format!("{}({})", source, code),
+            (Some(source), None) => source.clone(),
+            (None, Some(code)) => code,
+            (None, None) => String::new(),
+        };
+        vec![
+            Spans::from(Span::styled(
+                format!("line {}", diagnostic.range.start.line + 1),
+                style,
+            )),
+            Spans::from(Span::styled(origin, style)),
+        ]
+    }
+
+    fn is_child(&self, other: &Self) -> bool {
+        match (self, other) {
+            (Self::Diagnostic { path, .. }, Self::File { path: parent, .. }) => path == parent,
+            _ => false,
+        }
+    }
+
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.sort_key().cmp(&other.sort_key())
+    }
+
+    fn node_id(&self) -> Option<u64> {
+        let mut hasher = DefaultHasher::new();
+        self.path().hash(&mut hasher);
+        if let Self::Diagnostic { diagnostic, .. } = self {
+            diagnostic.message.hash(&mut hasher);
+            diagnostic.range.start.line.hash(&mut hasher);
+        }
+        Some(hasher.finish())
+    }
+}
+
+/// The diagnostics of all files grouped by file, the most severe first. The tree follows the
+/// diagnostics published while it is open.
+pub struct DiagnosticsTree {
+    tree: Tree<DiagnosticItem>,
+    /// The diagnostics the tree was built from.
+    diagnostics: Diagnostics,
+}
+
+impl DiagnosticsTree {
+    pub fn new(editor: &Editor) -> Self {
+        Self {
+            tree: Tree::build_tree(Self::items(editor)),
+            diagnostics: editor.diagnostics.clone(),
+        }
+    }
+
+    fn items(editor: &Editor) -> Vec<DiagnosticItem> {
+        let mut items = Vec::new();
+        for (url, diagnostics) in &editor.diagnostics {
+            let path = match url.to_file_path() {
+                Ok(path) if !diagnostics.is_empty() => path,
+                _ => continue,
+            };
+            let mut counts = [0; 4];
+            let start = items.len();
+            for (index, (diagnostic, server_id)) in diagnostics.iter().enumerate() {
+                let offset_encoding = match server_id {
+                    Some(server_id) => match editor.language_servers.get_by_id(*server_id) {
+                        Some(language_server) => language_server.offset_encoding(),
+                        None => continue,
+                    },
+                    None => helix_view::checker::OFFSET_ENCODING,
+                };
+                if let Some(index) = severity_index(diagnostic.severity) {
+                    counts[index] += 1;
+                }
+                items.push(DiagnosticItem::Diagnostic {
+                    path: path.clone(),
+                    index,
+                    diagnostic: diagnostic.clone(),
+                    offset_encoding,
+                });
+            }
+            if items.len() > start {
+                items.push(DiagnosticItem::File { path, counts });
+            }
+        }
+        items
+    }
+
+    /// The files and the first lines of the messages of the diagnostics, with the node ids
+    /// [`DiagnosticsTree::reveal`] takes.
+    pub fn search_entries(editor: &Editor) -> Vec<(String, u64)> {
+        let mut items = Self::items(editor);
+        items.sort_by(TreeItem::cmp);
+        items
+            .into_iter()
+            .map(|item| {
+                let path = get_relative_path(item.path())
+                    .to_string_lossy()
+                    .into_owned();
+                let label = match &item {
+                    DiagnosticItem::File { .. } => path,
+                    DiagnosticItem::Diagnostic { diagnostic, .. } => format!(
+                        "{}:{} {}",
+                        path,
+                        diagnostic.range.start.line + 1,
+                        diagnostic.message.lines().next().unwrap_or_default()
+                    ),
+                };
+                (label, item.node_id().unwrap_or_default())
+            })
+            .collect()
+    }
+
+    /// Selects the file or the diagnostic with the node id `id`, unfolding its file.
+    pub fn reveal(&mut self, id: u64) -> bool {
+        self.tree.reveal(|item| item.node_id() == Some(id))
+    }
+
+    /// Rebuilds the tree if diagnostics were published since it was built.
+    fn refresh(&mut self, editor: &Editor) {
+        if self.diagnostics == editor.diagnostics {
+            return;
+        }
+        self.diagnostics = editor.diagnostics.clone();
+        self.tree.replace_with_new_items(Self::items(editor));
+    }
+
+    fn current_location(&self) -> Option<(&Path, lsp::Range, OffsetEncoding)> {
+        if self.tree.is_empty() {
+            return None;
+        }
+        match self.tree.current_item() {
+            DiagnosticItem::Diagnostic {
+                path,
+                diagnostic,
+                offset_encoding,
+                ..
+            } => Some((path, diagnostic.range, *offset_encoding)),
+            DiagnosticItem::File { .. } => None,
+        }
+    }
+
+    /// Shows the diagnostic in its document while the tree stays open.
+    fn peek(&self, editor: &mut Editor) {
+        if let Some((path, range, offset_encoding)) = self.current_location() {
+            peek_location(editor, path, range, offset_encoding);
+        }
+    }
+
+    /// Selects the range of the diagnostic, returning whether the tree should close.
+    fn jump(&self, editor: &mut Editor) -> bool {
+        let (path, range, offset_encoding) = match self.current_location() {
+            Some(location) => location,
+            None => return false,
+        };
+        editor.confirm_peeks();
+        {
+            let (view, doc) = current!(editor);
+            view.jumps.push((doc.id(), doc.selection(view.id).clone()));
+        }
+        if let Err(e) = editor.open(path, Action::Replace) {
+            editor.set_error(format!("Failed to open {}: {}", path.display(), e));
+            return false;
+        }
+        let (view, doc) = current!(editor);
+        if let Some(range) = lsp_range_to_range(doc.text(), range, offset_encoding) {
+            // put the cursor on the start of the diagnostic
+            doc.set_selection(view.id, Selection::single(range.head, range.anchor));
+            align_view(doc, view, Align::Center);
+        }
+        true
+    }
+}
+
+impl Component for DiagnosticsTree {
+    fn handle_event(&mut self, event: &Event, cx: &mut Context) -> EventResult {
+        let close_fn = || {
+            EventResult::Consumed(Some(Box::new(|compositor: &mut Compositor, _| {
+                compositor.pop();
+            })))
+        };
+
+        let (action, count) = match event {
+            Event::Key(key!(Esc) | key!('q') | ctrl!('c')) => return close_fn(),
+            Event::Key(key_event) => match self.tree.key_action(*key_event, cx, &mut ()) {
+                TreeKey::Action(action, count) => (action, count),
+                TreeKey::Consumed | TreeKey::Ignored => return EventResult::Consumed(None),
+            },
+            Event::Mouse(event) => match self.tree.handle_mouse(event, cx, &mut ()) {
+                MouseAction::Activate => (TreeAction::Activate, 0),
+                MouseAction::Handled => return EventResult::Consumed(None),
+                MouseAction::Ignored => return EventResult::Ignored(None),
+            },
+            Event::Resize(..) => return EventResult::Consumed(None),
+            _ => return EventResult::Ignored(None),
+        };
+
+        match action {
+            _ if self.tree.is_empty() => {}
+            TreeAction::Activate => {
+                if self.jump(cx.editor) {
+                    return close_fn();
+                }
+                self.tree.run_action(action, count, cx, &mut ());
+            }
+            TreeAction::Peek => self.peek(cx.editor),
+            TreeAction::PeekBack => peek_back(cx.editor),
+            action => self.tree.run_action(action, count, cx, &mut ()),
+        }
+        EventResult::Consumed(None)
+    }
+
+    fn render(&mut self, area: Rect, surface: &mut Surface, cx: &mut Context) {
+        self.refresh(cx.editor);
+
+        let background = cx.editor.theme.get("ui.background");
+        surface.clear_with(area, background);
+
+        let block = Block::default()
+            .title(" Diagnostics ")
+            .borders(Borders::ALL)
+            .border_style(super::border_style(&cx.editor.theme, cx.focus));
+        let inner = block.inner(area);
+        block.render(area, surface);
+
+        let comment = cx.editor.theme.get("comment");
+        if self.tree.is_empty() {
+            surface.set_stringn(
+                inner.x,
+                inner.y,
+                "No diagnostics",
+                inner.width as usize,
+                comment,
+            );
+            return;
+        }
+
+        let tree_area = inner.clip_bottom(1);
+        self.tree.render(tree_area, surface, cx, &mut ());
+
+        let footer_area = inner.clip_top(tree_area.height);
+        surface.set_stringn(
+            footer_area.x,
+            footer_area.y,
+            "[enter]jump [p]peek [bs]back [o]fold",
+            footer_area.width as usize,
+            comment,
+        );
+    }
+
+    fn cursor(&self, _area: Rect, _editor: &Editor) -> (Option<Position>, CursorKind) {
+        (None, CursorKind::Hidden)
+    }
+
+    fn is_panel(&self) -> bool {
+        true
+    }
+}
@@ -0,0 +1,376 @@
+use crate::{
+    compositor::{Component, Compositor, Context, EventResult},
+    ctrl, key, shift,
+};
+use helix_core::{diff::line_hunks, Position, Selection};
+use helix_view::{
+    align_view,
+    editor::Action,
+    graphics::{CursorKind, Modifier, Rect, Style},
+    input::Event,
+    Align, Editor,
+};
+use std::path::PathBuf;
+use tui::{
+    buffer::Buffer as Surface,
+    widgets::{Block, Borders, Widget},
+};
+
+/// Unchanged lines shown around each change.
+const CONTEXT: u32 = 3;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum RowKind {
+    Header,
+    Context,
+    Removed,
+    Added,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Row {
+    kind: RowKind,
+    /// Zero-based line numbers in the old and new text.
+    old: Option<u32>,
+    new: Option<u32>,
+    text: String,
+}
+
+/// Builds the rows of a unified diff, merging changes whose context would overlap.
+fn diff_rows(old: &str, new: &str) -> Vec<Row> {
+    let old_lines: Vec<&str> = old.split_inclusive('\n').collect();
+    let new_lines: Vec<&str> = new.split_inclusive('\n').collect();
+    let line = |lines: &[&str], i: u32| {
+        lines[i as usize]
+            .trim_end_matches(&['\r', '\n'][..])
+            .to_string()
+    };
+    let hunks = line_hunks(old, new);
+
+    let mut rows = Vec::new();
+    let mut hunks = hunks.iter().peekable();
+    while let Some(first) = hunks.next() {
+        let mut group = vec![first];
+        while let Some(next) = hunks.peek() {
+            if next.0.start - group.last().unwrap().0.end > 2 * CONTEXT {
+                break;
+            }
+            group.push(hunks.next().unwrap());
+        }
+
+        let start = first.0.start.saturating_sub(CONTEXT);
+        let end = (group.last().unwrap().0.end + CONTEXT).min(old_lines.len() as u32);
+        let new_start = first.1.start - (first.0.start - start);
+        let header = rows.len();
+        rows.push(Row {
+            kind: RowKind::Header,
+            old: None,
+            new: Some(new_start),
+            text: String::new(),
+        });
+
+        let (mut o, mut n) = (start, new_start);
+        let context = |rows: &mut Vec<Row>, o: &mut u32, n: &mut u32, until: u32| {
+            while *o < until {
+                rows.push(Row {
+                    kind: RowKind::Context,
+                    old: Some(*o),
+                    new: Some(*n),
+                    text: line(&old_lines, *o),
+                });
+                *o += 1;
+                *n += 1;
+            }
+        };
+        for (before, after) in group {
+            context(&mut rows, &mut o, &mut n, before.start);
+            rows.extend(before.clone().map(|i| Row {
+                kind: RowKind::Removed,
+                old: Some(i),
+                new: None,
+                text: line(&old_lines, i),
+            }));
+            rows.extend(after.clone().map(|i| Row {
+                kind: RowKind::Added,
+                old: None,
+                new: Some(i),
+                text: line(&new_lines, i),
+            }));
+            o = before.end;
+            n = after.end;
+        }
+        context(&mut rows, &mut o, &mut n, end);
+
+        rows[header].text = format!(
+            "@@ -{},{} +{},{} @@",
+            start + 1,
+            end - start,
+            new_start + 1,
+            n - new_start
+        );
+    }
+    rows
+}
+
+/// A read-only unified diff between two versions of a file.
+pub struct DiffView {
+    title: String,
+    rows: Vec<Row>,
+    /// The new version of the file, opened on enter.
+    path: Option<PathBuf>,
+    selected: usize,
+    /// First shown row.
+    offset: usize,
+    height: usize,
+    /// The component this view was opened from, restored when it is closed.
+    back: Option<Box<dyn Component>>,
+    /// Whether the keys are handled by another component, leaving out the key hints.
+    preview: bool,
+}
+
+impl DiffView {
+    pub fn new(title: String, old: &str, new: &str, path: Option<PathBuf>) -> Self {
+        Self {
+            title,
+            rows: diff_rows(old, new),
+            path,
+            selected: 0,
+            offset: 0,
+            height: 0,
+            back: None,
+            preview: false,
+        }
+    }
+
+    /// Leaves out the key hints, for a diff shown next to the component handling the keys.
+    pub fn preview(mut self) -> Self {
+        self.preview = true;
+        self
+    }
+
+    /// Shows `back` again once the diff is closed.
+    pub fn with_back(mut self, back: Option<Box<dyn Component>>) -> Self {
+        self.back = back;
+        self
+    }
+
+    fn move_by(&mut self, rows: isize) {
+        let last = self.rows.len().saturating_sub(1) as isize;
+        self.selected = (self.selected as isize + rows).clamp(0, last.max(0)) as usize;
+    }
+
+    fn close(&mut self) -> EventResult {
+        let back = self.back.take();
+        EventResult::Consumed(Some(Box::new(|compositor: &mut Compositor, _| {
+            compositor.pop();
+            if let Some(back) = back {
+                compositor.push(back);
+            }
+        })))
+    }
+
+    /// Opens the file at the new line of the selected row, or the next row with one.
+    fn jump(&self) -> EventResult {
+        let path = match &self.path {
+            Some(path) => path.clone(),
+            None => return EventResult::Consumed(None),
+        };
+        let line = self.rows[self.selected..]
+            .iter()
+            .find_map(|row| row.new)
+            .unwrap_or_default() as usize;
+        EventResult::Consumed(Some(Box::new(move |compositor: &mut Compositor, cx| {
+            compositor.pop();
+            if let Err(e) = cx.editor.open(&path, Action::Replace) {
+                cx.editor
+                    .set_error(format!("Failed to open {}: {}", path.display(), e));
+                return;
+            }
+            let (view, doc) = current!(cx.editor);
+            let text = doc.text();
+            let pos = text.line_to_char(line.min(text.len_lines().saturating_sub(1)));
+            doc.set_selection(view.id, Selection::point(pos));
+            align_view(doc, view, Align::Center);
+        })))
+    }
+}
+
+impl Component for DiffView {
+    fn handle_event(&mut self, event: &Event, _cx: &mut Context) -> EventResult {
+        let key_event = match event {
+            Event::Key(event) => *event,
+            Event::Resize(..) => return EventResult::Consumed(None),
+            _ => return EventResult::Ignored(None),
+        };
+
+        let page = self.height.max(1) as isize;
+        match key_event.into() {
+            key!(Esc) | key!('q') | ctrl!('c') => return self.close(),
+            key!(Enter) => return self.jump(),
+            key!('k') | key!(Up) => self.move_by(-1),
+            key!('j') | key!(Down) => self.move_by(1),
+            ctrl!('u') => self.move_by(-page / 2),
+            ctrl!('d') => self.move_by(page / 2),
+            key!(PageUp) => self.move_by(-page),
+            key!(PageDown) => self.move_by(page),
+            key!('g') | key!(Home) => self.move_by(isize::MIN / 2),
+            shift!('G') | key!(End) => self.move_by(isize::MAX / 2),
+            // jump between hunks
+            key!('n') => {
+                if let Some(next) = self
+                    .rows
+                    .iter()
+                    .skip(self.selected + 1)
+                    .position(|row| row.kind == RowKind::Header)
+                {
+                    self.selected += next + 1;
+                }
+            }
+            shift!('N') => {
+                if let Some(prev) = self.rows[..self.selected]
+                    .iter()
+                    .rposition(|row| row.kind == RowKind::Header)
+                {
+                    self.selected = prev;
+                }
+            }
+            _ => {}
+        }
+        EventResult::Consumed(None)
+    }
+
+    fn render(&mut self, area: Rect, surface: &mut Surface, cx: &mut Context) {
+        let theme = &cx.editor.theme;
+        surface.clear_with(area, theme.get("ui.background"));
+
+        let block = Block::default()
+            .title(format!(" {} ", self.title))
+            .borders(Borders::ALL);
+        let inner = block.inner(area);
+        block.render(area, surface);
+
+        let diff_area = inner.clip_bottom(1);
+        self.height = diff_area.height as usize;
+        if self.selected < self.offset {
+            self.offset = self.selected;
+        } else if self.selected >= self.offset + self.height {
+            self.offset = self.selected + 1 - self.height.max(1);
+        }
+
+        let number_style = theme.get("ui.linenr");
+        let header_style = theme.get("comment");
+        let text_style = theme.get("ui.text");
+        let plus_style = theme.get("diff.plus");
+        let minus_style = theme.get("diff.minus");
+        let selected_style = theme
+            .try_get("ui.cursorline.primary")
+            .unwrap_or_else(|| Style::default().add_modifier(Modifier::REVERSED));
+        let width = self
+            .rows
+            .iter()
+            .filter_map(|row| row.old.max(row.new))
+            .max()
+            .map_or(1, |line| (line + 1).to_string().len());
+        let number = |line: Option<u32>| match line {
+            Some(line) => format!("{:>width$}", line + 1, width = width),
+            None => " ".repeat(width),
+        };
+
+        for (y, (index, row)) in
+            (diff_area.y..diff_area.bottom()).zip(self.rows.iter().enumerate().skip(self.offset))
+        {
+            let line_area = Rect::new(diff_area.x, y, diff_area.width, 1);
+            if row.kind == RowKind::Header {
+                surface.set_stringn(
+                    line_area.x,
+                    y,
+                    &row.text,
+                    line_area.width as usize,
+                    header_style,
+                );
+            } else {
+                let gutter = format!("{} {} ", number(row.old), number(row.new));
+                let (x, _) = surface.set_stringn(
+                    line_area.x,
+                    y,
+                    &gutter,
+                    line_area.width as usize,
+                    number_style,
+                );
+                let (sign, style) = match row.kind {
+                    RowKind::Removed => ('-', minus_style),
+                    RowKind::Added => ('+', plus_style),
+                    _ => (' ', text_style),
+                };
+                let text = format!("{}{}", sign, row.text.replace('\t', "    "));
+                surface.set_stringn(
+                    x,
+                    y,
+                    text,
+                    line_area.right().saturating_sub(x) as usize,
+                    style,
+                );
+            }
+            if index == self.selected {
+                surface.set_style(line_area, selected_style);
+            }
+        }
+
+        let footer_area = inner.clip_top(diff_area.height);
+        let help = if self.rows.is_empty() {
+            "No differences"
+        } else if self.preview {
+            ""
+        } else {
+            "[enter]open [n]ext/[N] previous hunk [q]uit"
+        };
+        surface.set_stringn(
+            footer_area.x,
+            footer_area.y,
+            help,
+            footer_area.width as usize,
+            theme.get("comment"),
+        );
+    }
+
+    fn cursor(&self, _area: Rect, _editor: &Editor) -> (Option<Position>, CursorKind) {
+        (None, CursorKind::Hidden)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn merged_hunks() {
+        let old = "1\n2\n3\n4\n5\n6\n7\n8\n9\n10\n11\n12\n13\n14\n15\n16\n";
+        let new = "1\n2\nthree\n4\n5\n6\n7\n8\n9\n10\n11\n12\n13\n14\n16\n";
+        let rows = diff_rows(old, new);
+        let headers: Vec<_> = rows
+            .iter()
+            .filter(|row| row.kind == RowKind::Header)
+            .map(|row| row.text.as_str())
+            .collect();
+        assert_eq!(headers, ["@@ -1,6 +1,6 @@", "@@ -12,5 +12,4 @@"]);
+
+        // nearby changes share a hunk
+        let new = "1\n2\nthree\n4\n5\n6\n7\n8\nnine\n10\n11\n12\n13\n14\n15\n16\n";
+        let rows = diff_rows(old, new);
+        let headers = rows
+            .iter()
+            .filter(|row| row.kind == RowKind::Header)
+            .count();
+        assert_eq!(headers, 1);
+        assert_eq!(rows[0].text, "@@ -1,12 +1,12 @@");
+        assert_eq!(
+            rows[3],
+            Row {
+                kind: RowKind::Removed,
+                old: Some(2),
+                new: None,
+                text: "3".into()
+            }
+        );
+    }
+}
@@ -0,0 +1,399 @@
+use super::{overlay::overlayed, tree::patch_selected, DiffView, MouseAction, Tree, TreeItem};
+use crate::{
+    compositor::{Component, Compositor, Context, EventResult},
+    ctrl, key,
+};
+use anyhow::{bail, Context as _};
+use helix_core::{path::get_relative_path, Position};
+use helix_view::{
+    editor::Action,
+    graphics::{CursorKind, Rect},
+    input::Event,
+    Editor,
+};
+use std::{
+    cmp::Ordering,
+    collections::{BTreeMap, BTreeSet},
+    path::{Path, PathBuf},
+    process::Command,
+};
+use tui::{
+    buffer::Buffer as Surface,
+    text::{Span, Spans},
+    widgets::{Block, Borders, Widget},
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FileStatus {
+    Added,
+    Removed,
+    Modified,
+}
+
+impl FileStatus {
+    fn letter(self) -> &'static str {
+        match self {
+            Self::Added => "A",
+            Self::Removed => "D",
+            Self::Modified => "M",
+        }
+    }
+
+    fn scope(self) -> &'static str {
+        match self {
+            Self::Added => "diff.plus",
+            Self::Removed => "diff.minus",
+            Self::Modified => "diff.delta",
+        }
+    }
+}
+
+/// What a directory diff compares.
+#[derive(Debug, Clone)]
+pub enum DiffSource {
+    Dirs {
+        old: PathBuf,
+        new: PathBuf,
+    },
+    /// The working tree of a git repository against a revision.
+    Git {
+        root: PathBuf,
+        rev: String,
+    },
+}
+
+pub(super) fn git(dir: &Path, args: &[&str]) -> anyhow::Result<Vec<u8>> {
+    let output = Command::new("git")
+        .arg("-C")
+        .arg(dir)
+        .args(args)
+        .output()
+        .context("failed to run git")?;
+    if !output.status.success() {
+        bail!(
+            "git {} failed: {}",
+            args.join(" "),
+            String::from_utf8_lossy(&output.stderr).trim()
+        );
+    }
+    Ok(output.stdout)
+}
+
+/// Splits the output of a git command run with `-z`.
+pub(super) fn nul_separated(output: &[u8]) -> impl Iterator<Item = &str> {
+    output
+        .split(|byte| *byte == 0)
+        .filter(|field| !field.is_empty())
+        .filter_map(|field| std::str::from_utf8(field).ok())
+}
+
+/// The files below `root`, relative to it.
+fn files(root: &Path) -> BTreeSet<PathBuf> {
+    ignore::WalkBuilder::new(root)
+        .hidden(false)
+        .filter_entry(|entry| entry.file_name() != ".git")
+        .build()
+        .filter_map(|entry| entry.ok())
+        .filter(|entry| entry.file_type().map_or(false, |ty| ty.is_file()))
+        .filter_map(|entry| Some(entry.path().strip_prefix(root).ok()?.to_path_buf()))
+        .collect()
+}
+
+impl DiffSource {
+    /// Compares the working tree of the repository containing `dir` against `rev`.
+    pub fn git(dir: &Path, rev: String) -> anyhow::Result<Self> {
+        let root = git(dir, &["rev-parse", "--show-toplevel"])?;
+        let root = PathBuf::from(String::from_utf8_lossy(&root).trim());
+        git(&root, &["rev-parse", "--verify", "--quiet", &rev])
+            .with_context(|| format!("unknown revision {}", rev))?;
+        Ok(Self::Git { root, rev })
+    }
+
+    fn title(&self) -> String {
+        match self {
+            Self::Dirs { old, new } => format!(
+                "{} → {}",
+                get_relative_path(old).display(),
+                get_relative_path(new).display()
+            ),
+            Self::Git { root, rev } => {
+                format!("{} against {}", get_relative_path(root).display(), rev)
+            }
+        }
+    }
+
+    /// The changed files relative to the compared directories, sorted by path.
+    pub fn changes(&self) -> anyhow::Result<Vec<(PathBuf, FileStatus)>> {
+        match self {
+            Self::Dirs { old, new } => {
+                let (old_files, new_files) = (files(old), files(new));
+                let mut changes = Vec::new();
+                for path in old_files.union(&new_files) {
+                    let status = match (old_files.contains(path), new_files.contains(path)) {
+                        (true, false) => FileStatus::Removed,
+                        (false, true) => FileStatus::Added,
+                        _ => {
+                            let old_contents = std::fs::read(old.join(path))?;
+                            if old_contents == std::fs::read(new.join(path))? {
+                                continue;
+                            }
+                            FileStatus::Modified
+                        }
+                    };
+                    changes.push((path.clone(), status));
+                }
+                Ok(changes)
+            }
+            Self::Git { root, rev } => {
+                let diff = git(
+                    root,
+                    &["diff", "--name-status", "-z", "--no-renames", rev, "--"],
+                )?;
+                let mut changes = BTreeMap::new();
+                let mut fields = nul_separated(&diff);
+                while let (Some(status), Some(path)) = (fields.next(), fields.next()) {
+                    let status = match status {
+                        "A" => FileStatus::Added,
+                        "D" => FileStatus::Removed,
+                        _ => FileStatus::Modified,
+                    };
+                    changes.insert(PathBuf::from(path), status);
+                }
+                let untracked = git(root, &["ls-files", "--others", "--exclude-standard", "-z"])?;
+                for path in nul_separated(&untracked) {
+                    changes.insert(PathBuf::from(path), FileStatus::Added);
+                }
+                Ok(changes.into_iter().collect())
+            }
+        }
+    }
+
+    /// The file holding the new version of `path`.
+    fn new_path(&self, path: &Path) -> PathBuf {
+        match self {
+            Self::Dirs { new, .. } => new.join(path),
+            Self::Git { root, .. } => root.join(path),
+        }
+    }
+
+    fn old_contents(&self, path: &Path) -> anyhow::Result<String> {
+        let contents = match self {
+            Self::Dirs { old, .. } => std::fs::read(old.join(path))?,
+            Self::Git { root, rev } => {
+                // git expects forward slashes in revision paths
+                let path = path.to_string_lossy().replace('\\', "/");
+                git(root, &["show", &format!("{}:{}", rev, path)])?
+            }
+        };
+        Ok(String::from_utf8_lossy(&contents).into_owned())
+    }
+}
+
+#[derive(Debug, Clone)]
+struct DiffItem {
+    path: PathBuf,
+    /// `None` for directories.
+    status: Option<FileStatus>,
+}
+
+impl TreeItem for DiffItem {
+    type Params = ();
+
+    fn text(&self, cx: &mut Context, selected: bool, _params: &mut ()) -> Spans {
+        let theme = &cx.editor.theme;
+        let name = self
+            .path
+            .file_name()
+            .map(|name| name.to_string_lossy())
+            .unwrap_or_default();
+        let mut spans = match self.status {
+            Some(status) => vec![
+                Span::styled(format!("{} ", status.letter()), theme.get(status.scope())),
+                Span::styled(name.into_owned(), theme.get("ui.text")),
+            ],
+            None => vec![Span::styled(
+                format!("{}/", name),
+                theme.get("ui.text.directory"),
+            )],
+        };
+        if selected {
+            patch_selected(cx, &mut spans);
+        }
+        Spans::from(spans)
+    }
+
+    fn is_child(&self, other: &Self) -> bool {
+        self.path.parent() == Some(&other.path)
+    }
+
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.path.cmp(&other.path)
+    }
+}
+
+/// The files added, removed or modified between two directories, or between the working tree
+/// and a git revision, as a tree.
+pub struct DirDiff {
+    source: DiffSource,
+    title: String,
+    tree: Tree<DiffItem>,
+    /// Number of added, removed and modified files.
+    counts: [usize; 3],
+}
+
+impl DirDiff {
+    /// `changes` must not be empty.
+    pub fn new(source: DiffSource, changes: Vec<(PathBuf, FileStatus)>) -> Self {
+        let mut counts = [0; 3];
+        let mut dirs = BTreeSet::new();
+        for (path, status) in &changes {
+            counts[*status as usize] += 1;
+            dirs.extend(
+                path.ancestors()
+                    .skip(1)
+                    .filter(|dir| !dir.as_os_str().is_empty())
+                    .map(Path::to_path_buf),
+            );
+        }
+        let items = dirs
+            .into_iter()
+            .map(|path| DiffItem { path, status: None })
+            .chain(changes.into_iter().map(|(path, status)| DiffItem {
+                path,
+                status: Some(status),
+            }))
+            .collect();
+
+        Self {
+            title: source.title(),
+            source,
+            tree: Tree::build_tree(items),
+            counts,
+        }
+    }
+
+    fn close() -> EventResult {
+        EventResult::Consumed(Some(Box::new(|compositor: &mut Compositor, _| {
+            compositor.pop();
+        })))
+    }
+
+    fn open_file(&self, cx: &mut Context) -> EventResult {
+        let item = self.tree.current_item();
+        match item.status {
+            Some(FileStatus::Removed) => {
+                cx.editor
+                    .set_error(format!("{} was removed", item.path.display()));
+                EventResult::Consumed(None)
+            }
+            Some(_) => {
+                let path = self.source.new_path(&item.path);
+                EventResult::Consumed(Some(Box::new(move |compositor: &mut Compositor, cx| {
+                    compositor.pop();
+                    if let Err(e) = cx.editor.open(&path, Action::Replace) {
+                        cx.editor
+                            .set_error(format!("Failed to open {}: {}", path.display(), e));
+                    }
+                })))
+            }
+            None => EventResult::Consumed(None),
+        }
+    }
+
+    /// Replaces the tree with a diff of the selected file, which returns to the tree when closed.
+    fn show_diff(&self, cx: &mut Context) -> EventResult {
+        let item = self.tree.current_item();
+        let old = match self.source.old_contents(&item.path) {
+            Ok(old) => old,
+            Err(e) => {
+                cx.editor.set_error(format!("{}", e));
+                return EventResult::Consumed(None);
+            }
+        };
+        let (new, path) = match item.status {
+            Some(FileStatus::Removed) => (String::new(), None),
+            _ => {
+                let path = self.source.new_path(&item.path);
+                match std::fs::read(&path) {
+                    Ok(new) => (String::from_utf8_lossy(&new).into_owned(), Some(path)),
+                    Err(e) => {
+                        cx.editor
+                            .set_error(format!("Failed to read {}: {}", path.display(), e));
+                        return EventResult::Consumed(None);
+                    }
+                }
+            }
+        };
+        let diff_view = DiffView::new(item.path.display().to_string(), &old, &new, path);
+        EventResult::Consumed(Some(Box::new(move |compositor: &mut Compositor, _| {
+            let back = compositor.pop();
+            compositor.push(Box::new(overlayed(diff_view.with_back(back))));
+        })))
+    }
+}
+
+impl Component for DirDiff {
+    fn handle_event(&mut self, event: &Event, cx: &mut Context) -> EventResult {
+        let key_event = match event {
+            Event::Key(event) => *event,
+            Event::Mouse(event) => match self.tree.handle_mouse(event, cx, &mut ()) {
+                MouseAction::Activate => key!(Enter),
+                MouseAction::Handled => return EventResult::Consumed(None),
+                MouseAction::Ignored => return EventResult::Ignored(None),
+            },
+            Event::Resize(..) => return EventResult::Consumed(None),
+            _ => return EventResult::Ignored(None),
+        };
+
+        match key_event.into() {
+            key!(Esc) | key!('q') | ctrl!('c') => return Self::close(),
+            key!(Enter) => match self.tree.current_item().status {
+                Some(FileStatus::Added) => return self.open_file(cx),
+                Some(_) => return self.show_diff(cx),
+                None => self.tree.on_enter(cx, &mut ()),
+            },
+            key!('o') => return self.open_file(cx),
+            _ => {
+                self.tree.handle_event(Event::Key(key_event), cx, &mut ());
+            }
+        }
+        EventResult::Consumed(None)
+    }
+
+    fn render(&mut self, area: Rect, surface: &mut Surface, cx: &mut Context) {
+        let background = cx.editor.theme.get("ui.background");
+        surface.clear_with(area, background);
+
+        let block = Block::default()
+            .title(format!(" {} ", self.title))
+            .borders(Borders::ALL)
+            .border_style(super::border_style(&cx.editor.theme, cx.focus));
+        let inner = block.inner(area);
+        block.render(area, surface);
+
+        let tree_area = inner.clip_bottom(1);
+        self.tree.render(tree_area, surface, cx, &mut ());
+
+        let footer_area = inner.clip_top(tree_area.height);
+        let [added, removed, modified] = self.counts;
+        let footer = format!(
+            "{} added, {} removed, {} modified [enter]diff [o]pen file",
+            added, removed, modified
+        );
+        surface.set_stringn(
+            footer_area.x,
+            footer_area.y,
+            footer,
+            footer_area.width as usize,
+            cx.editor.theme.get("comment"),
+        );
+    }
+
+    fn cursor(&self, _area: Rect, _editor: &Editor) -> (Option<Position>, CursorKind) {
+        (None, CursorKind::Hidden)
+    }
+
+    fn is_panel(&self) -> bool {
+        true
+    }
+}
@@ -0,0 +1,256 @@
+use super::{tree::patch_selected, MouseAction, Tree, TreeItem};
+use crate::{
+    commands::lsp::apply_edit_queue,
+    compositor::{Component, Compositor, Context, EventResult},
+    ctrl, key,
+};
+use helix_core::{path::get_relative_path, Position, Selection};
+use helix_lsp::{lsp, util::lsp_range_to_range, OffsetEncoding};
+use helix_view::{
+    align_view,
+    editor::{Action, QueuedEdit},
+    graphics::{CursorKind, Rect},
+    input::Event,
+    Align, Editor,
+};
+use std::cmp::Ordering;
+use tui::{
+    buffer::Buffer as Surface,
+    text::{Span, Spans},
+    widgets::{Block, Borders, Widget},
+};
+
+#[derive(Debug, Clone)]
+enum Row {
+    Queued { label: String },
+    File { name: String, edits: usize },
+    Edit { uri: lsp::Url, edit: lsp::TextEdit },
+}
+
+#[derive(Debug, Clone)]
+struct QueueItem {
+    index: usize,
+    parent: Option<usize>,
+    /// Position of the staged edit in the queue.
+    queued: usize,
+    offset_encoding: OffsetEncoding,
+    row: Row,
+}
+
+impl TreeItem for QueueItem {
+    type Params = ();
+
+    fn text(&self, cx: &mut Context, selected: bool, _params: &mut ()) -> Spans {
+        let theme = &cx.editor.theme;
+        let mut spans = match &self.row {
+            Row::Queued { label } => vec![Span::styled(label.clone(), theme.get("ui.text"))],
+            Row::File { name, edits } => vec![
+                Span::styled(name.clone(), theme.get("ui.text.directory")),
+                Span::styled(format!(" ({} edits)", edits), theme.get("comment")),
+            ],
+            Row::Edit { edit, .. } => {
+                let start = edit.range.start;
+                vec![
+                    Span::styled(
+                        format!("{}:{} ", start.line + 1, start.character + 1),
+                        theme.get("ui.linenr"),
+                    ),
+                    Span::styled(edit.new_text.replace('\n', "⏎"), theme.get("diff.plus")),
+                ]
+            }
+        };
+        if selected {
+            patch_selected(cx, &mut spans);
+        }
+        Spans::from(spans)
+    }
+
+    fn is_child(&self, other: &Self) -> bool {
+        self.parent == Some(other.index)
+    }
+
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.index.cmp(&other.index)
+    }
+}
+
+fn queue_items(queue: &[QueuedEdit]) -> Vec<QueueItem> {
+    let mut items = Vec::new();
+    for (queued, queued_edit) in queue.iter().enumerate() {
+        let mut push = |parent, row| {
+            let index = items.len();
+            items.push(QueueItem {
+                index,
+                parent,
+                queued,
+                offset_encoding: queued_edit.offset_encoding,
+                row,
+            });
+            index
+        };
+        let label = queued_edit.label.clone();
+        let root = push(None, Row::Queued { label });
+        for (uri, edits) in &queued_edit.edits {
+            let name = match uri.to_file_path() {
+                Ok(path) => get_relative_path(&path).to_string_lossy().into_owned(),
+                Err(_) => uri.to_string(),
+            };
+            let edits_len = edits.len();
+            let file = push(
+                Some(root),
+                Row::File {
+                    name,
+                    edits: edits_len,
+                },
+            );
+            for edit in edits {
+                let (uri, edit) = (uri.clone(), edit.clone());
+                push(Some(file), Row::Edit { uri, edit });
+            }
+        }
+    }
+    items
+}
+
+/// The refactoring queue: workspace edits staged to be reviewed and applied together, with one
+/// undo point per file.
+pub struct EditQueue {
+    tree: Tree<QueueItem>,
+}
+
+impl EditQueue {
+    /// Returns `None` if no edits are staged.
+    pub fn new(editor: &Editor) -> Option<Self> {
+        if editor.edit_queue.is_empty() {
+            return None;
+        }
+        Some(Self {
+            tree: Tree::build_tree(queue_items(&editor.edit_queue)),
+        })
+    }
+
+    fn close() -> EventResult {
+        EventResult::Consumed(Some(Box::new(|compositor: &mut Compositor, _| {
+            compositor.pop();
+        })))
+    }
+
+    /// Unstages the edit containing the selected row.
+    fn drop_queued(&mut self, editor: &mut Editor) -> EventResult {
+        let queued = self.tree.current_item().queued;
+        let edit = editor.edit_queue.remove(queued);
+        editor.set_status(format!("Dropped {}", edit.label));
+        if editor.edit_queue.is_empty() {
+            return Self::close();
+        }
+        self.tree
+            .replace_with_new_items(queue_items(&editor.edit_queue));
+        EventResult::Consumed(None)
+    }
+
+    fn apply(editor: &mut Editor) -> EventResult {
+        match apply_edit_queue(editor) {
+            Ok(files) => {
+                editor.set_status(format!("Applied staged edits to {} files", files));
+                Self::close()
+            }
+            Err(e) => {
+                editor.set_error(format!("Staged edits not applied: {}", e));
+                EventResult::Consumed(None)
+            }
+        }
+    }
+
+    /// Closes the queue and selects the text replaced by the selected edit.
+    fn jump(&mut self, cx: &mut Context) -> EventResult {
+        let item = self.tree.current_item();
+        let offset_encoding = item.offset_encoding;
+        let (uri, edit) = match &item.row {
+            Row::Edit { uri, edit } => (uri.clone(), edit.clone()),
+            _ => {
+                self.tree.on_enter(cx, &mut ());
+                return EventResult::Consumed(None);
+            }
+        };
+        let path = match uri.to_file_path() {
+            Ok(path) => path,
+            Err(_) => {
+                cx.editor
+                    .set_error(format!("unable to convert URI to filepath: {}", uri));
+                return EventResult::Consumed(None);
+            }
+        };
+        EventResult::Consumed(Some(Box::new(move |compositor: &mut Compositor, cx| {
+            compositor.pop();
+            if let Err(e) = cx.editor.open(&path, Action::Replace) {
+                cx.editor
+                    .set_error(format!("Failed to open {}: {}", path.display(), e));
+                return;
+            }
+            let (view, doc) = current!(cx.editor);
+            if let Some(range) = lsp_range_to_range(doc.text(), edit.range, offset_encoding) {
+                doc.set_selection(view.id, Selection::single(range.anchor, range.head));
+                align_view(doc, view, Align::Center);
+            }
+        })))
+    }
+}
+
+impl Component for EditQueue {
+    fn handle_event(&mut self, event: &Event, cx: &mut Context) -> EventResult {
+        let key_event = match event {
+            Event::Key(event) => *event,
+            Event::Mouse(event) => match self.tree.handle_mouse(event, cx, &mut ()) {
+                MouseAction::Activate => key!(Enter),
+                MouseAction::Handled => return EventResult::Consumed(None),
+                MouseAction::Ignored => return EventResult::Ignored(None),
+            },
+            Event::Resize(..) => return EventResult::Consumed(None),
+            _ => return EventResult::Ignored(None),
+        };
+
+        match key_event.into() {
+            key!(Esc) | key!('q') | ctrl!('c') => return Self::close(),
+            key!(Enter) => return self.jump(cx),
+            key!('d') => return self.drop_queued(cx.editor),
+            key!('a') => return Self::apply(cx.editor),
+            _ => {
+                self.tree.handle_event(Event::Key(key_event), cx, &mut ());
+            }
+        }
+        EventResult::Consumed(None)
+    }
+
+    fn render(&mut self, area: Rect, surface: &mut Surface, cx: &mut Context) {
+        let background = cx.editor.theme.get("ui.background");
+        surface.clear_with(area, background);
+
+        let title = format!(" Refactoring queue ({}) ", cx.editor.edit_queue.len());
+        let block = Block::default()
+            .title(title)
+            .borders(Borders::ALL)
+            .border_style(super::border_style(&cx.editor.theme, cx.focus));
+        let inner = block.inner(area);
+        block.render(area, surface);
+
+        let tree_area = inner.clip_bottom(1);
+        self.tree.render(tree_area, surface, cx, &mut ());
+
+        let footer_area = inner.clip_top(tree_area.height);
+        surface.set_stringn(
+            footer_area.x,
+            footer_area.y,
+            "[enter]jump [o]fold [d]rop [a]pply all",
+            footer_area.width as usize,
+            cx.editor.theme.get("comment"),
+        );
+    }
+
+    fn cursor(&self, _area: Rect, _editor: &Editor) -> (Option<Position>, CursorKind) {
+        (None, CursorKind::Hidden)
+    }
+
+    fn is_panel(&self) -> bool {
+        true
+    }
+}
@@ -4,28 +4,41 @@
     job::{self, Callback},
     key,
     keymap::{KeymapResult, Keymaps},
-    ui::{overlay::Overlay, Completion, Explorer, ProgressSpinners},
+    ui::{
+        overlay::{overlayed, Overlay},
+        Completion, DebugConsole, Explorer, Minimap, Outline, ProgressSpinners,
+    },
 };
 
 use helix_core::{
     graphemes::{
         ensure_grapheme_boundary_next_byte, next_grapheme_boundary, prev_grapheme_boundary,
     },
+    line_ending::line_end_char_index,
+    match_brackets,
     movement::Direction,
     syntax::{self, HighlightEvent},
     unicode::width::UnicodeWidthStr,
-    visual_coords_at_pos, LineEnding, Position, Range, Selection, Transaction,
+    visual_coords_at_pos, LineEnding, Position, Range, RopeSlice, Selection, Transaction,
 };
 use helix_view::{
-    apply_transaction,
+    align_view, apply_transaction,
     document::{Mode, SCRATCH_BUFFER_NAME},
-    editor::{CompleteAction, CursorShapeConfig},
+    editor::{CompleteAction, CursorShapeConfig, Panel},
     graphics::{Color, CursorKind, Modifier, Rect, Style},
     input::{KeyEvent, MouseButton, MouseEvent, MouseEventKind},
     keyboard::{KeyCode, KeyModifiers},
-    Document, Editor, Theme, View,
+    Align, Document, DocumentId, Editor, Theme, View,
+};
+use std::{
+    borrow::Cow,
+    cell::Cell,
+    cmp::min,
+    num::NonZeroUsize,
+    path::PathBuf,
+    rc::Rc,
+    time::{Duration, Instant},
 };
-use std::{borrow::Cow, cmp::min, num::NonZeroUsize, path::PathBuf};
 
 use tui::buffer::Buffer as Surface;
 
@@ -40,6 +53,15 @@ pub struct EditorView {
     pub(crate) completion: Option<Completion>,
     spinners: ProgressSpinners,
     pub(crate) explorer: Option<Overlay<Explorer>>,
+    pub(crate) debug_console: Option<DebugConsole>,
+    pub(crate) outline: Option<Outline>,
+    /// The explorer and debug console hidden by zen mode.
+    zen_panels: Option<(Option<Overlay<Explorer>>, Option<DebugConsole>)>,
+    minimap: Minimap,
+    /// The document the `panels` rules were last applied for.
+    panels_doc: Option<DocumentId>,
+    /// The panels opened by the `panels` rules.
+    rule_panels: Vec<Panel>,
 }
 
 #[derive(Debug, Clone)]
@@ -65,6 +87,77 @@ pub fn new(keymaps: Keymaps) -> Self {
             completion: None,
             spinners: ProgressSpinners::default(),
             explorer: None,
+            debug_console: None,
+            outline: None,
+            zen_panels: None,
+            minimap: Minimap::default(),
+            panels_doc: None,
+            rule_panels: Vec::new(),
+        }
+    }
+
+    /// Enters or leaves zen mode, hiding the panels until zen mode is left.
+    pub fn toggle_zen(&mut self, editor: &mut Editor) {
+        editor.toggle_zen();
+        if editor.is_zen() {
+            self.zen_panels = Some((self.explorer.take(), self.debug_console.take()));
+        } else if let Some((explorer, debug_console)) = self.zen_panels.take() {
+            // keep panels opened in zen mode
+            self.explorer = self.explorer.take().or(explorer);
+            self.debug_console = self.debug_console.take().or(debug_console);
+        }
+    }
+
+    /// Opens the panels of the `panels` rules matching the focused document once it gains the
+    /// focus, and closes the panels opened for an earlier document which no rule matching this
+    /// one opens. Panels opened otherwise stay open.
+    fn apply_panel_rules(&mut self, cx: &mut Context) {
+        let doc = doc!(cx.editor);
+        if cx.editor.is_zen() || self.panels_doc == Some(doc.id()) {
+            return;
+        }
+        self.panels_doc = Some(doc.id());
+        let mut panels = Vec::new();
+        let config = cx.editor.config();
+        for rule in config.panels.iter().filter(|rule| rule.matches(doc)) {
+            for &panel in &rule.open {
+                if !panels.contains(&panel) {
+                    panels.push(panel);
+                }
+            }
+        }
+
+        for panel in std::mem::take(&mut self.rule_panels) {
+            match panel {
+                _ if panels.contains(&panel) => self.rule_panels.push(panel),
+                Panel::Explorer => self.explorer = None,
+                Panel::Outline => self.outline = None,
+            }
+        }
+        for panel in panels {
+            match panel {
+                Panel::Explorer if self.explorer.is_none() => match Explorer::new(cx.editor) {
+                    Ok(mut explorer) => {
+                        // only an embedded explorer is shown without the focus
+                        if config.explorer.is_embed() {
+                            explorer.unfocus();
+                        }
+                        self.explorer = Some(overlayed(explorer));
+                    }
+                    Err(err) => {
+                        cx.editor
+                            .set_error(format!("Failed to open the explorer: {}", err));
+                        continue;
+                    }
+                },
+                Panel::Outline if self.outline.is_none() => {
+                    let mut outline = Outline::new();
+                    outline.refresh(cx.editor, cx.jobs);
+                    self.outline = Some(outline);
+                }
+                _ => continue,
+            }
+            self.rule_panels.push(panel);
         }
     }
 
@@ -84,7 +177,8 @@ pub fn render_view(
         let inner = view.inner_area(doc);
         let area = view.area;
         let theme = &editor.theme;
-        let config = editor.config();
+        let editor_config = editor.config();
+        let config = view.local_options.apply(&editor_config);
 
         // DAP: Highlight current stack frame position
         let stack_frame = editor.debugger.as_ref().and_then(|debugger| {
@@ -127,7 +221,24 @@ pub fn render_view(
             Self::highlight_cursorcolumn(doc, view, surface, theme);
         }
 
-        let mut highlights = Self::doc_syntax_highlights(doc, view.offset, inner.height, theme);
+        // the highlight queries run lazily while the text is rendered
+        let query_time = Rc::new(Cell::new(Duration::ZERO));
+        let mut highlights: Box<dyn Iterator<Item = HighlightEvent>> = Box::new(TimedIter {
+            iter: Self::doc_syntax_highlights(doc, view.offset, inner.height, theme),
+            elapsed: query_time.clone(),
+        });
+        if config.rainbow_brackets.enable {
+            let brackets = Self::doc_rainbow_highlights(
+                doc,
+                view.offset,
+                inner.height,
+                theme,
+                &config.rainbow_brackets.palette,
+            );
+            if !brackets.is_empty() {
+                highlights = Box::new(syntax::merge(highlights, brackets));
+            }
+        }
         for diagnostic in Self::doc_diagnostics_highlights(doc, theme) {
             // Most of the `diagnostic` Vecs are empty most of the time. Skipping
             // a merge for any empty Vec saves a significant amount of work.
@@ -136,6 +247,12 @@ pub fn render_view(
             }
             highlights = Box::new(syntax::merge(highlights, diagnostic));
         }
+        if is_focused {
+            let matches = Self::doc_search_highlights(editor, doc, view, inner.height, theme);
+            if !matches.is_empty() {
+                highlights = Box::new(syntax::merge(highlights, matches));
+            }
+        }
         let highlights: Box<dyn Iterator<Item = HighlightEvent>> = if is_focused {
             Box::new(syntax::merge(
                 highlights,
@@ -152,6 +269,11 @@ pub fn render_view(
         };
 
         Self::render_text_highlights(doc, view.offset, inner, surface, theme, highlights, &config);
+        if doc.syntax().is_some() {
+            doc.syntax_metrics
+                .borrow_mut()
+                .record_query(query_time.get());
+        }
         Self::render_gutter(editor, doc, view, view.area, surface, theme, is_focused);
         Self::render_rulers(editor, doc, view, inner, surface, theme);
 
@@ -173,6 +295,10 @@ pub fn render_view(
 
         Self::render_diagnostics(doc, view, inner, surface, theme);
 
+        if editor.is_zen() {
+            return;
+        }
+
         let statusline_area = view
             .area
             .clip_top(view.area.height.saturating_sub(1))
@@ -197,10 +323,11 @@ pub fn render_rulers(
             .try_get("ui.virtual.ruler")
             .unwrap_or_else(|| Style::default().bg(Color::Red));
 
-        let rulers = doc
-            .language_config()
-            .and_then(|config| config.rulers.as_ref())
-            .unwrap_or(editor_rulers);
+        let rulers = view.local_options.rulers.as_ref().unwrap_or_else(|| {
+            doc.language_config()
+                .and_then(|config| config.rulers.as_ref())
+                .unwrap_or(editor_rulers)
+        });
 
         rulers
             .iter()
@@ -267,6 +394,41 @@ pub fn doc_syntax_highlights<'doc>(
         }
     }
 
+    /// Get highlight spans coloring the visible brackets by their depth with the `palette`
+    /// scopes the theme defines.
+    pub fn doc_rainbow_highlights(
+        doc: &Document,
+        offset: Position,
+        height: u16,
+        theme: &Theme,
+        palette: &[String],
+    ) -> Vec<(usize, std::ops::Range<usize>)> {
+        let syntax = match doc.syntax() {
+            Some(syntax) => syntax,
+            None => return Vec::new(),
+        };
+        let scopes: Vec<_> = palette
+            .iter()
+            .filter_map(|scope| theme.find_scope_index(scope))
+            .collect();
+        if scopes.is_empty() {
+            return Vec::new();
+        }
+
+        let text = doc.text();
+        let last_line = text.len_lines().saturating_sub(1);
+        let last_visible_line = (offset.row + height as usize)
+            .saturating_sub(1)
+            .min(last_line);
+        let start = text.line_to_byte(offset.row.min(last_line));
+        let end = text.line_to_byte(last_visible_line + 1);
+
+        match_brackets::bracket_depths(syntax, text, start..end)
+            .into_iter()
+            .map(|(pos, depth)| (scopes[depth % scopes.len()], pos..pos + 1))
+            .collect()
+    }
+
     /// Get highlight spans for document diagnostics
     pub fn doc_diagnostics_highlights(
         doc: &Document,
@@ -326,6 +488,42 @@ pub fn doc_diagnostics_highlights(
         [default_vec, info_vec, hint_vec, warning_vec, error_vec]
     }
 
+    /// Get highlight spans for the matches of the search being typed which are in view.
+    pub fn doc_search_highlights(
+        editor: &Editor,
+        doc: &Document,
+        view: &View,
+        height: u16,
+        theme: &Theme,
+    ) -> Vec<(usize, std::ops::Range<usize>)> {
+        let matches = match &editor.search_matches {
+            Some(matches) if matches.doc == doc.id() => matches,
+            _ => return Vec::new(),
+        };
+        let scope = match theme
+            .find_scope_index("ui.search.match")
+            .or_else(|| theme.find_scope_index("ui.selection"))
+        {
+            Some(scope) => scope,
+            None => return Vec::new(),
+        };
+
+        let text = doc.text().slice(..);
+        let first_line = view.offset.row.min(text.len_lines() - 1);
+        let start = text.line_to_char(first_line);
+        let end = match first_line + height as usize {
+            line if line < text.len_lines() => text.line_to_char(line),
+            _ => text.len_chars(),
+        };
+        matches
+            .ranges
+            .iter()
+            .skip_while(|range| range.end <= start)
+            .take_while(|range| range.start < end)
+            .map(|range| (scope, range.clone()))
+            .collect()
+    }
+
     /// Get highlight spans for selections in a document view.
     pub fn doc_selection_highlights(
         mode: Mode,
@@ -427,15 +625,18 @@ pub fn render_text_highlights<H: Iterator<Item = HighlightEvent>>(
         let mut visual_x = 0usize;
         let mut line = 0u16;
         let tab_width = doc.tab_width();
+        let visible_tab: String = std::iter::once(characters.tab)
+            .chain(std::iter::repeat(characters.tabpad).take(tab_width - 1))
+            .collect();
+        let blank_tab = " ".repeat(tab_width);
         let tab = if whitespace.render.tab() == WhitespaceRenderValue::All {
-            std::iter::once(characters.tab)
-                .chain(std::iter::repeat(characters.tabpad).take(tab_width - 1))
-                .collect()
+            &visible_tab
         } else {
-            " ".repeat(tab_width)
+            &blank_tab
         };
         let space = characters.space.to_string();
         let nbsp = characters.nbsp.to_string();
+        let render_trailing = whitespace.render.trailing() == WhitespaceRenderValue::All;
         let newline = if whitespace.render.newline() == WhitespaceRenderValue::All {
             characters.newline.to_string()
         } else {
@@ -444,7 +645,16 @@ pub fn render_text_highlights<H: Iterator<Item = HighlightEvent>>(
         let indent_guide_char = config.indent_guides.character.to_string();
 
         let text_style = theme.get("ui.text");
-        let whitespace_style = theme.get("ui.virtual.whitespace");
+        let space_style = theme.get("ui.virtual.whitespace.space");
+        let nbsp_style = theme.get("ui.virtual.whitespace.nbsp");
+        let tab_style = theme.get("ui.virtual.whitespace.tab");
+        let newline_style = theme.get("ui.virtual.whitespace.newline");
+        let trailing_style = theme.get("ui.virtual.whitespace.trailing");
+        let control_style = theme
+            .try_get("ui.virtual.control")
+            .unwrap_or_else(|| theme.get("error"));
+        // where the trailing whitespace of the current line starts
+        let mut trailing_start = None;
 
         let mut is_in_indent_area = true;
         let mut last_line_indent_level = 0;
@@ -456,6 +666,14 @@ pub fn render_text_highlights<H: Iterator<Item = HighlightEvent>>(
                 .unwrap_or_else(|| theme.get("ui.virtual.whitespace")),
         );
 
+        // the guide of each level shares the color of the brackets at that depth
+        let rainbow_guides =
+            if config.rainbow_brackets.enable && config.rainbow_brackets.indent_guides {
+                config.rainbow_brackets.styles(theme)
+            } else {
+                Vec::new()
+            };
+
         let draw_indent_guides = |indent_level, line, surface: &mut Surface| {
             if !config.indent_guides.render {
                 return;
@@ -476,7 +694,11 @@ pub fn render_text_highlights<H: Iterator<Item = HighlightEvent>>(
                 let x = (viewport.x as usize + (i * tab_width) - offset.col) as u16;
                 let y = viewport.y + line;
                 debug_assert!(surface.in_bounds(x, y));
-                surface.set_string(x, y, &indent_guide_char, indent_guide_style);
+                let style = match rainbow_guides.len() {
+                    0 => indent_guide_style,
+                    len => indent_guide_style.patch(rainbow_guides[i % len]),
+                };
+                surface.set_string(x, y, &indent_guide_char, style);
             }
         };
 
@@ -490,6 +712,8 @@ pub fn render_text_highlights<H: Iterator<Item = HighlightEvent>>(
                 }
                 HighlightEvent::Source { start, end } => {
                     let is_trailing_cursor = text.len_chars() < end;
+                    let doc_text = text;
+                    let mut char_pos = start;
 
                     // `unwrap_or_else` part is for off-the-end indices of
                     // the rope, to allow cursor highlighting at the end
@@ -499,7 +723,8 @@ pub fn render_text_highlights<H: Iterator<Item = HighlightEvent>>(
                         .iter()
                         .fold(text_style, |acc, span| acc.patch(theme.highlight(span.0)));
 
-                    let space = if whitespace.render.space() == WhitespaceRenderValue::All
+                    let space_render: &str = if whitespace.render.space()
+                        == WhitespaceRenderValue::All
                         && !is_trailing_cursor
                     {
                         &space
@@ -507,7 +732,8 @@ pub fn render_text_highlights<H: Iterator<Item = HighlightEvent>>(
                         " "
                     };
 
-                    let nbsp = if whitespace.render.nbsp() == WhitespaceRenderValue::All
+                    let nbsp_render: &str = if whitespace.render.nbsp()
+                        == WhitespaceRenderValue::All
                         && text.len_chars() < end
                     {
                         &nbsp
@@ -520,6 +746,8 @@ pub fn render_text_highlights<H: Iterator<Item = HighlightEvent>>(
                     for grapheme in RopeGraphemes::new(text) {
                         let out_of_bounds = offset.col > visual_x
                             || visual_x >= viewport.width as usize + offset.col;
+                        let grapheme_pos = char_pos;
+                        char_pos += grapheme.len_chars();
 
                         if LineEnding::from_rope_slice(&grapheme).is_some() {
                             if !out_of_bounds {
@@ -528,9 +756,10 @@ pub fn render_text_highlights<H: Iterator<Item = HighlightEvent>>(
                                     (viewport.x as usize + visual_x - offset.col) as u16,
                                     viewport.y + line,
                                     &newline,
-                                    style.patch(whitespace_style),
+                                    style.patch(newline_style),
                                 );
                             }
+                            trailing_start = None;
 
                             draw_indent_guides(last_line_indent_level, line, surface);
 
@@ -544,24 +773,54 @@ pub fn render_text_highlights<H: Iterator<Item = HighlightEvent>>(
                             }
                         } else {
                             let grapheme = Cow::from(grapheme);
-                            let is_whitespace;
+                            let is_trailing = render_trailing
+                                && matches!(grapheme.as_ref(), " " | "\t" | "\u{00A0}")
+                                && grapheme_pos < doc_text.len_chars()
+                                && grapheme_pos
+                                    >= *trailing_start.get_or_insert_with(|| {
+                                        trailing_whitespace_start(doc_text, grapheme_pos)
+                                    });
+                            let control_picture;
+                            // the style of whitespace and control characters
+                            let special_style;
 
                             let (display_grapheme, width) = if grapheme == "\t" {
-                                is_whitespace = true;
+                                special_style = Some(if is_trailing {
+                                    trailing_style
+                                } else {
+                                    tab_style
+                                });
+                                let tab = if is_trailing { &visible_tab } else { tab };
                                 // make sure we display tab as appropriate amount of spaces
                                 let visual_tab_width = tab_width - (visual_x % tab_width);
                                 let grapheme_tab_width =
-                                    helix_core::str_utils::char_to_byte_idx(&tab, visual_tab_width);
+                                    helix_core::str_utils::char_to_byte_idx(tab, visual_tab_width);
 
                                 (&tab[..grapheme_tab_width], visual_tab_width)
                             } else if grapheme == " " {
-                                is_whitespace = true;
-                                (space, 1)
+                                if is_trailing {
+                                    special_style = Some(trailing_style);
+                                    (space.as_str(), 1)
+                                } else {
+                                    special_style = Some(space_style);
+                                    (space_render, 1)
+                                }
                             } else if grapheme == "\u{00A0}" {
-                                is_whitespace = true;
-                                (nbsp, 1)
+                                if is_trailing {
+                                    special_style = Some(trailing_style);
+                                    (nbsp.as_str(), 1)
+                                } else {
+                                    special_style = Some(nbsp_style);
+                                    (nbsp_render, 1)
+                                }
+                            } else if config.render_control_characters
+                                && (grapheme == "\u{FFFD}" || is_control(&grapheme))
+                            {
+                                special_style = Some(control_style);
+                                control_picture = control_picture_of(&grapheme);
+                                (control_picture.as_str(), 1)
                             } else {
-                                is_whitespace = false;
+                                special_style = None;
                                 // Cow will prevent allocations if span contained in a single slice
                                 // which should really be the majority case
                                 let width = grapheme_width(&grapheme);
@@ -576,10 +835,9 @@ pub fn render_text_highlights<H: Iterator<Item = HighlightEvent>>(
                                     (viewport.x as usize + visual_x - offset.col) as u16,
                                     viewport.y + line,
                                     display_grapheme,
-                                    if is_whitespace {
-                                        style.patch(whitespace_style)
-                                    } else {
-                                        style
+                                    match special_style {
+                                        Some(special_style) => style.patch(special_style),
+                                        None => style,
                                     },
                                 );
                             } else if cut_off_start != 0 && cut_off_start < width {
@@ -592,10 +850,9 @@ pub fn render_text_highlights<H: Iterator<Item = HighlightEvent>>(
                                 );
                                 surface.set_style(
                                     rect,
-                                    if is_whitespace {
-                                        style.patch(whitespace_style)
-                                    } else {
-                                        style
+                                    match special_style {
+                                        Some(special_style) => style.patch(special_style),
+                                        None => style,
                                     },
                                 );
                             }
@@ -625,7 +882,6 @@ pub fn render_focused_view_elements(
         // Highlight matching braces
         if let Some(syntax) = doc.syntax() {
             let text = doc.text().slice(..);
-            use helix_core::match_brackets;
             let pos = doc.selection(view.id).primary().cursor(text);
 
             let pos = match_brackets::find_matching_bracket(syntax, doc.text(), pos)
@@ -916,6 +1172,9 @@ fn handle_keymap_event(
     ) -> Option<KeymapResult> {
         let mut last_mode = mode;
         self.pseudo_pending.extend(self.keymaps.pending());
+        self.keymaps.set_focus(doc!(cxt.editor), |doc_id| {
+            cxt.editor.document(doc_id).is_some()
+        });
         let key_result = self.keymaps.get(mode, event);
         cxt.editor.autoinfo = self.keymaps.sticky().map(|node| node.infobox());
 
@@ -1062,12 +1321,19 @@ pub fn set_completion(
         editor: &mut Editor,
         items: Vec<helix_lsp::lsp::CompletionItem>,
         offset_encoding: helix_lsp::OffsetEncoding,
+        language_server_id: usize,
         start_offset: usize,
         trigger_offset: usize,
         size: Rect,
     ) {
-        let mut completion =
-            Completion::new(editor, items, offset_encoding, start_offset, trigger_offset);
+        let mut completion = Completion::new(
+            editor,
+            items,
+            offset_encoding,
+            language_server_id,
+            start_offset,
+            trigger_offset,
+        );
 
         if completion.is_empty() {
             // skip if we got no completion results
@@ -1095,6 +1361,13 @@ pub fn clear_completion(&mut self, editor: &mut Editor) {
     }
 
     pub fn handle_idle_timeout(&mut self, cx: &mut commands::Context) -> EventResult {
+        // the minimap is only rebuilt on idle to keep typing fast
+        let minimap_changed = cx.editor.config().minimap.enable && self.minimap.refresh(cx.editor);
+        if let Some(outline) = self.outline.as_mut() {
+            outline.refresh(cx.editor, cx.jobs);
+        }
+        statusline::request_symbols(cx.editor, cx.jobs);
+
         if let Some(completion) = &mut self.completion {
             return if completion.ensure_item_resolved(cx) {
                 EventResult::Consumed(None)
@@ -1104,7 +1377,11 @@ pub fn handle_idle_timeout(&mut self, cx: &mut commands::Context) -> EventResult
         }
 
         if cx.editor.mode != Mode::Insert || !cx.editor.config().auto_completion {
-            return EventResult::Ignored(None);
+            return if minimap_changed {
+                EventResult::Consumed(None)
+            } else {
+                EventResult::Ignored(None)
+            };
         }
 
         crate::commands::insert::idle_completion(cx);
@@ -1143,6 +1420,18 @@ fn handle_mouse_event(
         };
 
         match kind {
+            MouseEventKind::Down(MouseButton::Left) | MouseEventKind::Drag(MouseButton::Left)
+                if self.minimap.line_at(row, column).is_some() =>
+            {
+                let line = self.minimap.line_at(row, column).unwrap();
+                let (view, doc) = current!(cxt.editor);
+                let line = line.min(doc.text().len_lines().saturating_sub(1));
+                let pos = doc.text().line_to_char(line);
+                doc.set_selection(view.id, Selection::point(pos));
+                align_view(doc, view, Align::Center);
+                EventResult::Consumed(None)
+            }
+
             MouseEventKind::Down(MouseButton::Left) => {
                 let editor = &mut cxt.editor;
 
@@ -1303,6 +1592,16 @@ fn handle_event(
                 return EventResult::Consumed(callback);
             }
         }
+        if let Some(console) = self.debug_console.as_mut() {
+            if let EventResult::Consumed(callback) = console.handle_event(event, context) {
+                return EventResult::Consumed(callback);
+            }
+        }
+        if let Some(outline) = self.outline.as_mut() {
+            if let EventResult::Consumed(callback) = outline.handle_event(event, context) {
+                return EventResult::Consumed(callback);
+            }
+        }
         let mut cx = commands::Context {
             editor: context.editor,
             count: None,
@@ -1361,6 +1660,7 @@ fn handle_event(
                                     editor: cx.editor,
                                     jobs: cx.jobs,
                                     scroll: None,
+                                    focus: false,
                                 };
                                 let res = completion.handle_event(event, &mut cx);
 
@@ -1449,11 +1749,14 @@ fn handle_event(
     fn render(&mut self, area: Rect, surface: &mut Surface, cx: &mut Context) {
         // clear with background color
         surface.set_style(area, cx.editor.theme.get("ui.background"));
+        cx.editor.sync_scroll_binds();
+        self.apply_panel_rules(cx);
         let config = cx.editor.config();
 
         // check if bufferline should be rendered
         use helix_view::editor::BufferLine;
         let use_bufferline = match config.bufferline {
+            _ if cx.editor.is_zen() => false,
             BufferLine::Always => true,
             BufferLine::Multiple if cx.editor.documents.len() > 1 => true,
             _ => false,
@@ -1470,6 +1773,37 @@ fn render(&mut self, area: Rect, surface: &mut Surface, cx: &mut Context) {
         if self.explorer.is_some() && (config.explorer.is_embed()) {
             editor_area = editor_area.clip_left(config.explorer.column_width as u16 + 2);
         }
+        let console_area = self.debug_console.as_ref().map(|_| {
+            let height = (editor_area.height / 3).max(5).min(editor_area.height);
+            editor_area.clip_top(editor_area.height - height)
+        });
+        if let Some(console_area) = console_area {
+            editor_area = editor_area.clip_bottom(console_area.height);
+        }
+        let outline_area = match self.outline {
+            Some(_) if !cx.editor.is_zen() => {
+                let width = config.outline.width.min(editor_area.width);
+                let outline_area = editor_area.clip_left(editor_area.width - width);
+                editor_area = editor_area.clip_right(width);
+                Some(outline_area)
+            }
+            _ => None,
+        };
+        let minimap_area = if config.minimap.enable && !cx.editor.is_zen() {
+            let width = config.minimap.width.min(editor_area.width);
+            let minimap_area = editor_area.clip_left(editor_area.width - width);
+            editor_area = editor_area.clip_right(width);
+            Some(minimap_area)
+        } else {
+            None
+        };
+        if cx.editor.is_zen() {
+            // center the text column
+            let width = config.zen.width.min(editor_area.width);
+            editor_area = editor_area
+                .clip_left((editor_area.width - width) / 2)
+                .with_width(width);
+        }
         cx.editor.resize(editor_area); // -1 from bottom for commandline
 
         if let Some(explore) = self.explorer.as_mut() {
@@ -1482,9 +1816,36 @@ fn render(&mut self, area: Rect, surface: &mut Surface, cx: &mut Context) {
             Self::render_bufferline(cx.editor, area.with_height(1), surface);
         }
 
+        // the views don't receive the input while a panel has the focus
+        let explorer_focused =
+            matches!(&self.explorer, Some(explore) if explore.content.is_focus());
+        let outline_focused = matches!(&self.outline, Some(outline) if outline.is_focus());
+        let console_focused = matches!(&self.debug_console, Some(console) if console.is_focus());
+        let panel_focused = explorer_focused || outline_focused || console_focused;
+        let zoomed = cx.editor.tree.is_zoomed();
         for (view, is_focused) in cx.editor.tree.views() {
+            if zoomed && !is_focused {
+                continue;
+            }
             let doc = cx.editor.document(view.doc).unwrap();
             self.render_view(cx.editor, doc, view, area, surface, is_focused);
+            if !is_focused || panel_focused {
+                super::dim_inactive(view.area, surface, &cx.editor.theme);
+            }
+        }
+
+        match minimap_area {
+            Some(minimap_area) => self.minimap.render(minimap_area, surface, cx.editor),
+            // forget the area so that clicks there don't scroll
+            None => self.minimap = Minimap::default(),
+        }
+
+        if let (Some(console), Some(console_area)) = (self.debug_console.as_mut(), console_area) {
+            console.render(console_area, surface, cx);
+        }
+
+        if let (Some(outline), Some(outline_area)) = (self.outline.as_mut(), outline_area) {
+            outline.render(outline_area, surface, cx);
         }
 
         if config.auto_info {
@@ -1580,6 +1941,12 @@ fn cursor(&self, _area: Rect, editor: &Editor) -> (Option<Position>, CursorKind)
                 }
             }
         }
+        if let Some(console) = &self.debug_console {
+            let cursor = console.cursor(_area, editor);
+            if cursor.0.is_some() {
+                return cursor;
+            }
+        }
         match editor.cursor() {
             // All block cursors are drawn manually
             (pos, CursorKind::Block) => (pos, CursorKind::Hidden),
@@ -1597,3 +1964,47 @@ fn canonicalize_key(key: &mut KeyEvent) {
         key.modifiers.remove(KeyModifiers::SHIFT)
     }
 }
+
+/// The char index where the whitespace at the end of the line of `pos` starts.
+fn trailing_whitespace_start(text: RopeSlice, pos: usize) -> usize {
+    let line = text.char_to_line(pos);
+    let start = text.line_to_char(line);
+    let mut end = line_end_char_index(&text, line);
+    while end > start && matches!(text.char(end - 1), ' ' | '\t' | '\u{00A0}') {
+        end -= 1;
+    }
+    end
+}
+
+fn is_control(grapheme: &str) -> bool {
+    let mut chars = grapheme.chars();
+    matches!((chars.next(), chars.next()), (Some(ch), None) if ch.is_ascii_control())
+}
+
+/// Shows ASCII control characters as their control picture, like `␀` for NUL.
+fn control_picture_of(grapheme: &str) -> String {
+    match grapheme.chars().next() {
+        Some('\u{7F}') => '␡'.to_string(),
+        Some(ch) if ch.is_ascii_control() => char::from_u32(0x2400 + ch as u32)
+            .unwrap_or('\u{FFFD}')
+            .to_string(),
+        _ => grapheme.to_string(),
+    }
+}
+
+/// Adds the time spent producing each item to `elapsed`, to measure lazy iterators.
+struct TimedIter<I> {
+    iter: I,
+    elapsed: Rc<Cell<Duration>>,
+}
+
+impl<I: Iterator> Iterator for TimedIter<I> {
+    type Item = I::Item;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let start = Instant::now();
+        let item = self.iter.next();
+        self.elapsed.set(self.elapsed.get() + start.elapsed());
+        item
+    }
+}
@@ -1,18 +1,21 @@
-use super::{Prompt, Tree, TreeItem, TreeOp};
+use super::{media, Prompt, Tree, TreeItem, TreeOp};
 use crate::{
     compositor::{Component, Compositor, Context, EventResult},
     ctrl, key, shift, ui,
 };
 use anyhow::{bail, ensure, Result};
+use futures_util::future::{BoxFuture, FutureExt};
 use helix_core::Position;
 use helix_view::{
-    editor::Action,
+    editor::{Action, ExplorerConfig},
     graphics::{CursorKind, Modifier, Rect},
     input::{Event, KeyEvent},
     Editor,
 };
 use std::borrow::Cow;
 use std::cmp::Ordering;
+use std::collections::hash_map::DefaultHasher;
+use std::hash::{Hash, Hasher};
 use std::path::{Path, PathBuf};
 use tui::{
     buffer::Buffer as Surface,
@@ -30,36 +33,52 @@ macro_rules! get_theme {
 enum FileType {
     File,
     Dir,
-    Exe,
     Placeholder,
     Parent,
     Root,
 }
 
+/// Which entries of a directory are listed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Listing {
+    show_hidden: bool,
+    /// Whether to skip the files ignored by `.gitignore`, `.ignore` and `.git/info/exclude`.
+    git_ignore: bool,
+}
+
+impl Listing {
+    fn new(config: &ExplorerConfig) -> Self {
+        Self {
+            show_hidden: !config.hidden,
+            git_ignore: config.git_ignore,
+        }
+    }
+}
+
 #[derive(Debug, Clone)]
 struct FileInfo {
     file_type: FileType,
     path: PathBuf,
+    /// How the children of a directory are listed.
+    listing: Listing,
 }
 
 impl FileInfo {
-    fn new(path: PathBuf, file_type: FileType) -> Self {
-        Self { path, file_type }
-    }
-
-    fn root(path: PathBuf) -> Self {
+    fn new(path: PathBuf, file_type: FileType, listing: Listing) -> Self {
         Self {
-            file_type: FileType::Root,
             path,
+            file_type,
+            listing,
         }
     }
 
-    fn parent(path: &Path) -> Self {
+    fn root(path: PathBuf, listing: Listing) -> Self {
+        Self::new(path, FileType::Root, listing)
+    }
+
+    fn parent(path: &Path, listing: Listing) -> Self {
         let p = path.parent().unwrap_or_else(|| Path::new(""));
-        Self {
-            file_type: FileType::Parent,
-            path: p.to_path_buf(),
-        }
+        Self::new(p.to_path_buf(), FileType::Parent, listing)
     }
 
     fn get_text(&self) -> Cow<'static, str> {
@@ -67,7 +86,7 @@ fn get_text(&self) -> Cow<'static, str> {
             FileType::Parent => "..".into(),
             FileType::Placeholder => "---".into(),
             FileType::Root => return format!("{}", self.path.display()).into(),
-            FileType::File | FileType::Exe | FileType::Dir => self
+            FileType::File | FileType::Dir => self
                 .path
                 .file_name()
                 .map_or("/".into(), |p| p.to_string_lossy().into_owned().into()),
@@ -83,7 +102,7 @@ fn text(&self, cx: &mut Context, selected: bool, state: &mut State) -> Spans {
 
         let style = match self.file_type {
             FileType::Parent | FileType::Dir | FileType::Root => "ui.explorer.dir",
-            FileType::File | FileType::Exe | FileType::Placeholder => "ui.explorer.file",
+            FileType::File | FileType::Placeholder => "ui.explorer.file",
         };
         let mut style = theme.try_get(style).unwrap_or_else(|| theme.get("ui.text"));
         if selected {
@@ -97,7 +116,23 @@ fn text(&self, cx: &mut Context, selected: bool, state: &mut State) -> Spans {
                 style = style.add_modifier(Modifier::REVERSED);
             }
         }
-        Spans::from(Span::styled(text, style))
+
+        let percent = match self.file_type {
+            FileType::File => cx
+                .editor
+                .coverage
+                .as_ref()
+                .and_then(|coverage| coverage.file(&self.path))
+                .and_then(|file| file.percent()),
+            _ => None,
+        };
+        match percent {
+            Some(percent) => Spans::from(vec![
+                Span::styled(text, style),
+                Span::styled(format!(" {:.0}%", percent), theme.get("comment")),
+            ]),
+            None => Spans::from(Span::styled(text, style)),
+        }
     }
 
     fn is_child(&self, other: &Self) -> bool {
@@ -111,6 +146,18 @@ fn is_child(&self, other: &Self) -> bool {
         }
     }
 
+    fn node_id(&self) -> Option<u64> {
+        match self.file_type {
+            // share their path with a directory
+            FileType::Parent | FileType::Placeholder => None,
+            _ => {
+                let mut hasher = DefaultHasher::new();
+                self.path.hash(&mut hasher);
+                Some(hasher.finish())
+            }
+        }
+    }
+
     fn cmp(&self, other: &Self) -> Ordering {
         use FileType::*;
         match (self.file_type, other.file_type) {
@@ -132,8 +179,8 @@ fn cmp(&self, other: &Self) -> Ordering {
         if let (Some(p1), Some(p2)) = (self.path.parent(), other.path.parent()) {
             if p1 == p2 {
                 match (self.file_type, other.file_type) {
-                    (Dir, File | Exe) => return Ordering::Less,
-                    (File | Exe, Dir) => return Ordering::Greater,
+                    (Dir, File) => return Ordering::Less,
+                    (File, Dir) => return Ordering::Greater,
                     _ => {}
                 };
             }
@@ -146,32 +193,41 @@ fn get_childs(&self) -> Result<Vec<Self>> {
             FileType::Root | FileType::Dir => {}
             _ => return Ok(vec![]),
         };
-        let mut ret: Vec<_> = std::fs::read_dir(&self.path)?
+        // fail like reading the directory would, the walk only reports errors per entry
+        std::fs::read_dir(&self.path)?;
+        let listing = self.listing;
+        let mut ret: Vec<_> = ignore::WalkBuilder::new(&self.path)
+            .max_depth(Some(1))
+            .hidden(!listing.show_hidden)
+            .ignore(listing.git_ignore)
+            .git_ignore(listing.git_ignore)
+            .git_exclude(listing.git_ignore)
+            .filter_entry(|entry| entry.file_name() != ".git")
+            .build()
             .filter_map(|entry| entry.ok())
-            .filter_map(|entry| {
-                entry.metadata().ok().map(|meta| {
-                    let is_exe = false;
-                    let file_type = match (meta.is_dir(), is_exe) {
-                        (true, _) => FileType::Dir,
-                        (_, false) => FileType::File,
-                        (_, true) => FileType::Exe,
-                    };
-                    Self {
-                        file_type,
-                        path: self.path.join(entry.file_name()),
-                    }
-                })
+            .filter(|entry| entry.depth() == 1)
+            .map(|entry| {
+                let file_type = if entry.path().is_dir() {
+                    FileType::Dir
+                } else {
+                    FileType::File
+                };
+                Self::new(entry.into_path(), file_type, listing)
             })
             .collect();
         if ret.is_empty() {
-            ret.push(Self {
-                path: self.path.clone(),
-                file_type: FileType::Placeholder,
-            })
+            ret.push(Self::new(self.path.clone(), FileType::Placeholder, listing))
         }
         Ok(ret)
     }
 
+    /// Large or slow directories are read on a blocking thread so that expanding them doesn't
+    /// freeze the editor.
+    fn load_childs(&self) -> Option<BoxFuture<'static, Result<Vec<Self>>>> {
+        let item = self.clone();
+        Some(async move { tokio::task::spawn_blocking(move || item.get_childs()).await? }.boxed())
+    }
+
     fn filter(&self, _cx: &mut Context, s: &str, _params: &mut Self::Params) -> bool {
         if s.is_empty() {
             false
@@ -228,13 +284,15 @@ enum PromptAction {
 struct State {
     focus: bool,
     current_root: PathBuf,
+    listing: Listing,
 }
 
 impl State {
-    fn new(focus: bool, current_root: PathBuf) -> Self {
+    fn new(focus: bool, current_root: PathBuf, listing: Listing) -> Self {
         Self {
             focus,
             current_root,
+            listing,
         }
     }
 }
@@ -250,28 +308,30 @@ pub struct Explorer {
 }
 
 impl Explorer {
-    pub fn new(cx: &mut Context) -> Result<Self> {
+    pub fn new(editor: &Editor) -> Result<Self> {
         let current_root = std::env::current_dir().unwrap_or_else(|_| "./".into());
-        let items = Self::get_items(current_root.clone(), cx)?;
+        let listing = Listing::new(&editor.config().explorer);
+        let items = Self::get_items(current_root.clone(), listing, editor)?;
         Ok(Self {
             tree: Tree::build_tree(items).with_enter_fn(Self::toggle_current),
-            state: State::new(true, current_root),
+            state: State::new(true, current_root, listing),
             repeat_motion: None,
             prompt: None,
             on_next_key: None,
         })
     }
 
-    pub fn new_explorer_recursion() -> Result<Self> {
+    pub fn new_explorer_recursion(editor: &Editor) -> Result<Self> {
         let current_root = std::env::current_dir().unwrap_or_else(|_| "./".into());
-        let parent = FileInfo::parent(&current_root);
-        let root = FileInfo::root(current_root.clone());
+        let listing = Listing::new(&editor.config().explorer);
+        let parent = FileInfo::parent(&current_root, listing);
+        let root = FileInfo::root(current_root.clone(), listing);
         let mut tree =
             Tree::build_from_root(root, usize::MAX / 2)?.with_enter_fn(Self::toggle_current);
         tree.insert_current_level(parent);
         Ok(Self {
             tree,
-            state: State::new(true, current_root),
+            state: State::new(true, current_root, listing),
             repeat_motion: None,
             prompt: None,
             on_next_key: None,
@@ -300,11 +360,30 @@ pub fn is_focus(&self) -> bool {
         self.state.focus
     }
 
-    fn get_items(p: PathBuf, cx: &mut Context) -> Result<Vec<FileInfo>> {
-        let mut items = vec![FileInfo::parent(p.as_path())];
-        let root = FileInfo::root(p);
+    /// The paths of the listed files and directories, relative to the root, with the node ids
+    /// [`Explorer::reveal`] takes.
+    pub fn search_entries(&self) -> Vec<(String, u64)> {
+        self.tree
+            .all_items()
+            .into_iter()
+            .filter(|item| matches!(item.file_type, FileType::File | FileType::Dir))
+            .filter_map(|item| {
+                let path = item.path.strip_prefix(&self.state.current_root).ok()?;
+                Some((path.to_string_lossy().into_owned(), item.node_id()?))
+            })
+            .collect()
+    }
+
+    /// Selects the entry with the node id `id`, unfolding its directories.
+    pub fn reveal(&mut self, id: u64) -> bool {
+        self.tree.reveal(|item| item.node_id() == Some(id))
+    }
+
+    fn get_items(p: PathBuf, listing: Listing, editor: &Editor) -> Result<Vec<FileInfo>> {
+        let mut items = vec![FileInfo::parent(p.as_path(), listing)];
+        let root = FileInfo::root(p, listing);
         let childs = root.get_childs()?;
-        if cx.editor.config().explorer.is_tree() {
+        if editor.config().explorer.is_tree() {
             items.push(root)
         }
         items.extend(childs);
@@ -462,7 +541,7 @@ fn toggle_current(
 
         if item.path.is_dir() {
             if cx.editor.config().explorer.is_list() || item.file_type == FileType::Parent {
-                match Self::get_items(item.path.clone(), cx) {
+                match Self::get_items(item.path.clone(), state.listing, cx.editor) {
                     Ok(items) => {
                         state.current_root = item.path.clone();
                         return TreeOp::ReplaceTree(items);
@@ -470,13 +549,31 @@ fn toggle_current(
                     Err(e) => cx.editor.set_error(format!("{e}")),
                 }
             } else {
-                return TreeOp::GetChildsAndInsert;
+                return TreeOp::LoadChildsAndInsert;
             }
         }
         cx.editor.set_error("unkonw file type");
         TreeOp::Noop
     }
 
+    /// Shows or hides hidden files, listing the current root again.
+    fn toggle_hidden(&mut self, cx: &mut Context) {
+        let listing = &mut self.state.listing;
+        listing.show_hidden = !listing.show_hidden;
+        let status = if listing.show_hidden {
+            "Showing hidden files"
+        } else {
+            "Hiding hidden files"
+        };
+        match Self::get_items(self.state.current_root.clone(), *listing, cx.editor) {
+            Ok(items) => {
+                self.tree.replace_with_new_items(items);
+                cx.editor.set_status(status);
+            }
+            Err(e) => cx.editor.set_error(format!("{e}")),
+        }
+    }
+
     fn render_float(&mut self, area: Rect, surface: &mut Surface, cx: &mut Context) {
         let background = cx.editor.theme.get("ui.background");
         let column_width = cx.editor.config().explorer.column_width as u16;
@@ -508,8 +605,11 @@ fn render_embed(&mut self, area: Rect, surface: &mut Surface, cx: &mut Context)
         let preview_area = area.clip_left(side_area.width).clip_bottom(2);
         let prompt_area = area.clip_top(side_area.height);
 
-        let list_area =
-            render_block(side_area.clip_left(1), surface, Borders::RIGHT).clip_bottom(1);
+        let block = Block::default()
+            .borders(Borders::RIGHT)
+            .border_style(super::border_style(&cx.editor.theme, self.is_focus()));
+        let list_area = block.inner(side_area.clip_left(1)).clip_bottom(1);
+        block.render(side_area.clip_left(1), surface);
         self.tree.render(list_area, surface, cx, &mut self.state);
 
         {
@@ -531,6 +631,10 @@ fn render_embed(&mut self, area: Rect, surface: &mut Surface, cx: &mut Context)
             // );
         }
 
+        if !self.is_focus() {
+            super::dim_inactive(side_area, surface, &cx.editor.theme);
+        }
+
         if self.is_focus() {
             if preview_area.width < 30 || preview_area.height < 3 {
                 return;
@@ -614,7 +718,10 @@ fn handle_search_event(&mut self, event: KeyEvent, cx: &mut Context) -> EventRes
                 //     .tree
                 //     .handle_event(Event::Key(event), cx, &mut self.state);
             }
-            key!(Esc) | ctrl!('c') => self.tree.restore_view(),
+            key!(Esc) | ctrl!('c') => {
+                self.tree.restore_view();
+                self.tree.set_search(cx, "", &mut self.state);
+            }
             _ => {
                 if let EventResult::Consumed(_) = prompt.handle_event(&Event::Key(event), cx) {
                     if search_next {
@@ -697,11 +804,11 @@ fn new_path(&mut self, file_name: &str, is_dir: bool) -> Result<()> {
 
         let f = if is_dir {
             std::fs::create_dir(&p)?;
-            FileInfo::new(p, FileType::Dir)
+            FileInfo::new(p, FileType::Dir, current.listing)
         } else {
             let mut fd = std::fs::OpenOptions::new();
             fd.create_new(true).write(true).open(&p)?;
-            FileInfo::new(p, FileType::File)
+            FileInfo::new(p, FileType::File, current.listing)
         };
         if current.file_type == FileType::Placeholder {
             self.tree.replace_current(f);
@@ -717,6 +824,11 @@ impl Component for Explorer {
     fn handle_event(&mut self, event: &Event, cx: &mut Context) -> EventResult {
         let key_event = match event {
             Event::Key(event) => event,
+            Event::Mouse(event) if self.is_focus() && self.prompt.is_none() => {
+                return self
+                    .tree
+                    .handle_event(Event::Mouse(*event), cx, &mut self.state)
+            }
             Event::Resize(..) => return EventResult::Consumed(None),
             _ => return EventResult::Ignored(None),
         };
@@ -754,16 +866,18 @@ fn handle_event(&mut self, event: &Event, cx: &mut Context) -> EventResult {
             }
             key!('b') => {
                 if let Some(p) = self.state.current_root.parent() {
-                    match Self::get_items(p.to_path_buf(), cx) {
+                    match Self::get_items(p.to_path_buf(), self.state.listing, cx.editor) {
                         Ok(items) => {
                             self.state.current_root = p.to_path_buf();
-                            self.tree = Tree::build_tree(items).with_enter_fn(Self::toggle_current);
+                            // selects the directory that was the root
+                            self.tree.replace_with_new_items(items);
                         }
                         Err(e) => cx.editor.set_error(format!("{e}")),
                     }
                 }
             }
             key!('f') => self.new_filter_prompt(),
+            key!('.') => self.toggle_hidden(cx),
             key!('/') => self.new_search_prompt(true),
             key!('?') => self.new_search_prompt(false),
             key!('m') => {
@@ -817,12 +931,12 @@ fn cursor(&self, area: Rect, editor: &Editor) -> (Option<Position>, CursorKind)
         let (x, y) = if config.is_overlay() {
             let colw = config.column_width as u16;
             if area.width > colw {
-                (area.x + colw + 2, area.y + area.height - 2)
+                (area.x + colw + 2, area.y + area.height.saturating_sub(2))
             } else {
                 return (None, CursorKind::Hidden);
             }
         } else {
-            (area.x, area.y + area.height - 1)
+            (area.x, area.y + area.height.saturating_sub(1))
         };
         prompt.cursor(Rect::new(x, y, area.width, 1), editor)
     }
@@ -847,7 +961,16 @@ fn get_preview(p: impl AsRef<Path>, max_line: usize) -> Result<Vec<String>> {
 
     ensure!(p.is_file(), "path: {} is not file or dir", p.display());
     use std::fs::OpenOptions;
-    use std::io::BufRead;
+    use std::io::{BufRead, Read};
+
+    // describe binary files instead of dumping their bytes, like the picker preview
+    let mut header = Vec::new();
+    std::fs::File::open(p)?
+        .take(1024)
+        .read_to_end(&mut header)?;
+    if content_inspector::inspect(&header).is_binary() {
+        return Ok(media::describe(p)?);
+    }
     let mut fd = OpenOptions::new();
     fd.read(true);
     let fd = fd.open(p)?;
@@ -0,0 +1,392 @@
+use super::{
+    dir_diff::{git, nul_separated},
+    tree::patch_selected,
+    DiffView, MouseAction, Tree, TreeItem,
+};
+use crate::{
+    compositor::{Component, Compositor, Context, EventResult},
+    ctrl, key,
+};
+use helix_core::{path::get_relative_path, Position};
+use helix_view::{
+    editor::Action,
+    graphics::{CursorKind, Modifier, Rect},
+    input::Event,
+    Editor,
+};
+use std::{
+    cmp::Ordering,
+    path::{Path, PathBuf},
+};
+use tui::{
+    buffer::Buffer as Surface,
+    text::{Span, Spans},
+    widgets::{Block, Borders, Widget},
+};
+
+/// The groups of changed files, in the order they are shown.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+enum Section {
+    Staged,
+    Unstaged,
+    Untracked,
+}
+
+impl Section {
+    fn name(self) -> &'static str {
+        match self {
+            Self::Staged => "Staged",
+            Self::Unstaged => "Unstaged",
+            Self::Untracked => "Untracked",
+        }
+    }
+}
+
+/// A file changed in a section, with its status letter from `git status`.
+type Change = (Section, PathBuf, char);
+
+/// Parses the output of `git status --porcelain -z --no-renames`. A file with staged and
+/// unstaged changes is listed in both sections.
+fn parse_status(output: &[u8]) -> Vec<Change> {
+    let mut changes = Vec::new();
+    for entry in nul_separated(output) {
+        let mut chars = entry.chars();
+        let (index, worktree) = match (chars.next(), chars.next(), chars.next()) {
+            (Some(index), Some(worktree), Some(' ')) => (index, worktree),
+            _ => continue,
+        };
+        let path = PathBuf::from(chars.as_str());
+        match (index, worktree) {
+            ('?', _) => changes.push((Section::Untracked, path, '?')),
+            ('!', _) => {}
+            _ => {
+                if index != ' ' {
+                    changes.push((Section::Staged, path.clone(), index));
+                }
+                if worktree != ' ' {
+                    changes.push((Section::Unstaged, path, worktree));
+                }
+            }
+        }
+    }
+    changes
+}
+
+#[derive(Debug, Clone)]
+enum StatusItem {
+    Section { section: Section, files: usize },
+    File { change: Change },
+}
+
+impl StatusItem {
+    fn section(&self) -> Section {
+        match self {
+            Self::Section { section, .. } => *section,
+            Self::File { change } => change.0,
+        }
+    }
+}
+
+impl TreeItem for StatusItem {
+    type Params = ();
+
+    fn text(&self, cx: &mut Context, selected: bool, _params: &mut ()) -> Spans {
+        let theme = &cx.editor.theme;
+        let mut spans = match self {
+            Self::Section { section, files } => vec![Span::styled(
+                format!("{} ({})", section.name(), files),
+                theme.get("ui.text").add_modifier(Modifier::BOLD),
+            )],
+            Self::File {
+                change: (_, path, status),
+            } => {
+                let scope = match status {
+                    'A' | '?' => "diff.plus",
+                    'D' => "diff.minus",
+                    _ => "diff.delta",
+                };
+                vec![
+                    Span::styled(format!("{} ", status), theme.get(scope)),
+                    Span::styled(path.display().to_string(), theme.get("ui.text")),
+                ]
+            }
+        };
+        if selected {
+            patch_selected(cx, &mut spans);
+        }
+        Spans::from(spans)
+    }
+
+    fn is_child(&self, other: &Self) -> bool {
+        matches!((self, other), (Self::File { .. }, Self::Section { .. }))
+            && self.section() == other.section()
+    }
+
+    fn cmp(&self, other: &Self) -> Ordering {
+        fn path(item: &StatusItem) -> Option<&Path> {
+            match item {
+                StatusItem::Section { .. } => None,
+                StatusItem::File { change } => Some(&change.1),
+            }
+        }
+        (self.section(), path(self)).cmp(&(other.section(), path(other)))
+    }
+}
+
+/// The files of a git repository which are staged, changed but not staged, or untracked, as a
+/// tree with a root per section. `d` shows the diff of the selected file next to the tree.
+pub struct GitStatus {
+    root: PathBuf,
+    tree: Tree<StatusItem>,
+    /// Whether the diff of the selected file is shown.
+    show_diff: bool,
+    /// The diff shown, with the change it's for.
+    diff: Option<(Change, DiffView)>,
+}
+
+impl GitStatus {
+    /// Lists the changes of the repository containing `dir`, or `None` if there are none.
+    pub fn new(dir: &Path) -> anyhow::Result<Option<Self>> {
+        let root = git(dir, &["rev-parse", "--show-toplevel"])?;
+        let root = PathBuf::from(String::from_utf8_lossy(&root).trim());
+        let status = git(
+            &root,
+            &[
+                "status",
+                "--porcelain",
+                "-z",
+                "--no-renames",
+                "--untracked-files=all",
+            ],
+        )?;
+        let changes = parse_status(&status);
+        if changes.is_empty() {
+            return Ok(None);
+        }
+
+        let mut items = Vec::new();
+        for section in [Section::Staged, Section::Unstaged, Section::Untracked] {
+            let files = changes.iter().filter(|change| change.0 == section).count();
+            if files > 0 {
+                items.push(StatusItem::Section { section, files });
+            }
+        }
+        items.extend(
+            changes
+                .into_iter()
+                .map(|change| StatusItem::File { change }),
+        );
+        Ok(Some(Self {
+            root,
+            tree: Tree::build_tree(items),
+            show_diff: false,
+            diff: None,
+        }))
+    }
+
+    fn selected_change(&self) -> Option<&Change> {
+        match self.tree.current_item() {
+            StatusItem::File { change } => Some(change),
+            StatusItem::Section { .. } => None,
+        }
+    }
+
+    /// The contents of `path` in the index, or at `HEAD` with `rev` set to `HEAD`.
+    fn show(&self, rev: &str, path: &Path) -> anyhow::Result<String> {
+        // git expects forward slashes in revision paths
+        let path = path.to_string_lossy().replace('\\', "/");
+        let contents = git(&self.root, &["show", &format!("{}:{}", rev, path)])?;
+        Ok(String::from_utf8_lossy(&contents).into_owned())
+    }
+
+    /// The diff of the staged changes of a file against `HEAD`, or of its changes in the
+    /// working tree against the index.
+    fn diff(&self, change: &Change) -> anyhow::Result<DiffView> {
+        let (section, path, status) = change;
+        let (old, new) = match section {
+            Section::Staged => {
+                let old = match status {
+                    'A' => String::new(),
+                    _ => self.show("HEAD", path)?,
+                };
+                let new = match status {
+                    'D' => String::new(),
+                    _ => self.show("", path)?,
+                };
+                (old, new)
+            }
+            Section::Unstaged | Section::Untracked => {
+                let old = match section {
+                    Section::Untracked => String::new(),
+                    _ => self.show("", path)?,
+                };
+                let new = match status {
+                    'D' => String::new(),
+                    _ => {
+                        String::from_utf8_lossy(&std::fs::read(self.root.join(path))?).into_owned()
+                    }
+                };
+                (old, new)
+            }
+        };
+        let title = format!("{} ({})", path.display(), section.name().to_lowercase());
+        Ok(DiffView::new(title, &old, &new, Some(self.root.join(path))).preview())
+    }
+
+    /// Diffs the selected file unless its diff is shown already.
+    fn update_diff(&mut self, cx: &mut Context) {
+        let change = match self.selected_change() {
+            Some(change) => change.clone(),
+            None => {
+                self.diff = None;
+                return;
+            }
+        };
+        if matches!(&self.diff, Some((shown, _)) if *shown == change) {
+            return;
+        }
+        match self.diff(&change) {
+            Ok(diff) => self.diff = Some((change, diff)),
+            Err(e) => {
+                self.diff = None;
+                cx.editor.set_error(format!("{}", e));
+            }
+        }
+    }
+
+    fn close() -> EventResult {
+        EventResult::Consumed(Some(Box::new(|compositor: &mut Compositor, _| {
+            compositor.pop();
+        })))
+    }
+
+    fn open_file(&self, cx: &mut Context) -> EventResult {
+        let (path, status) = match self.selected_change() {
+            Some((_, path, status)) => (path, *status),
+            None => return EventResult::Consumed(None),
+        };
+        let path = self.root.join(path);
+        if status == 'D' && !path.exists() {
+            cx.editor.set_error(format!(
+                "{} was removed",
+                get_relative_path(&path).display()
+            ));
+            return EventResult::Consumed(None);
+        }
+        EventResult::Consumed(Some(Box::new(move |compositor: &mut Compositor, cx| {
+            compositor.pop();
+            if let Err(e) = cx.editor.open(&path, Action::Replace) {
+                cx.editor
+                    .set_error(format!("Failed to open {}: {}", path.display(), e));
+            }
+        })))
+    }
+}
+
+impl Component for GitStatus {
+    fn handle_event(&mut self, event: &Event, cx: &mut Context) -> EventResult {
+        let key_event = match event {
+            Event::Key(event) => *event,
+            Event::Mouse(event) => match self.tree.handle_mouse(event, cx, &mut ()) {
+                MouseAction::Activate => key!(Enter),
+                MouseAction::Handled => return EventResult::Consumed(None),
+                MouseAction::Ignored => return EventResult::Ignored(None),
+            },
+            Event::Resize(..) => return EventResult::Consumed(None),
+            _ => return EventResult::Ignored(None),
+        };
+
+        match key_event.into() {
+            key!(Esc) | key!('q') | ctrl!('c') => return Self::close(),
+            key!(Enter) if self.selected_change().is_some() => return self.open_file(cx),
+            key!(Enter) => self.tree.on_enter(cx, &mut ()),
+            key!('d') => {
+                self.show_diff = !self.show_diff;
+                if !self.show_diff {
+                    self.diff = None;
+                }
+            }
+            _ => {
+                self.tree.handle_event(Event::Key(key_event), cx, &mut ());
+            }
+        }
+        EventResult::Consumed(None)
+    }
+
+    fn render(&mut self, area: Rect, surface: &mut Surface, cx: &mut Context) {
+        let background = cx.editor.theme.get("ui.background");
+        surface.clear_with(area, background);
+
+        // the diff takes the right half
+        let (area, diff_area) = if self.show_diff {
+            self.update_diff(cx);
+            let width = area.width / 2;
+            (
+                area.clip_right(width),
+                Some(area.clip_left(area.width - width)),
+            )
+        } else {
+            (area, None)
+        };
+
+        let block = Block::default()
+            .title(format!(
+                " Git status: {} ",
+                get_relative_path(&self.root).display()
+            ))
+            .borders(Borders::ALL)
+            .border_style(super::border_style(&cx.editor.theme, cx.focus));
+        let inner = block.inner(area);
+        block.render(area, surface);
+
+        let tree_area = inner.clip_bottom(1);
+        self.tree.render(tree_area, surface, cx, &mut ());
+
+        let footer_area = inner.clip_top(tree_area.height);
+        surface.set_stringn(
+            footer_area.x,
+            footer_area.y,
+            "[enter]open [d]iff [o]fold [q]uit",
+            footer_area.width as usize,
+            cx.editor.theme.get("comment"),
+        );
+
+        match (diff_area, self.diff.as_mut()) {
+            (Some(diff_area), Some((_, diff))) => diff.render(diff_area, surface, cx),
+            (Some(diff_area), None) => {
+                Block::default()
+                    .borders(Borders::ALL)
+                    .render(diff_area, surface);
+            }
+            _ => {}
+        }
+    }
+
+    fn cursor(&self, _area: Rect, _editor: &Editor) -> (Option<Position>, CursorKind) {
+        (None, CursorKind::Hidden)
+    }
+
+    fn is_panel(&self) -> bool {
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_porcelain_status() {
+        let output = b"M  staged.rs\0MM both.rs\0 D removed.rs\0?? new dir/file.rs\0!! target\0";
+        assert_eq!(
+            parse_status(output),
+            [
+                (Section::Staged, PathBuf::from("staged.rs"), 'M'),
+                (Section::Staged, PathBuf::from("both.rs"), 'M'),
+                (Section::Unstaged, PathBuf::from("both.rs"), 'M'),
+                (Section::Unstaged, PathBuf::from("removed.rs"), 'D'),
+                (Section::Untracked, PathBuf::from("new dir/file.rs"), '?'),
+            ]
+        );
+    }
+}
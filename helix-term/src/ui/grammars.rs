@@ -0,0 +1,382 @@
+use super::{tree::selected_style, MouseAction, Tree, TreeItem};
+use crate::{
+    compositor::{Component, Compositor, Context, EventResult},
+    ctrl, job, key,
+};
+use futures_util::{stream, StreamExt};
+use helix_core::Position;
+use helix_loader::grammar::{self, GrammarState};
+use helix_view::{
+    graphics::{CursorKind, Rect},
+    input::Event,
+    Editor,
+};
+use once_cell::sync::Lazy;
+use std::{
+    cmp::Ordering, collections::HashMap, num::NonZeroUsize, sync::Mutex,
+    thread::available_parallelism,
+};
+use tui::{
+    buffer::Buffer as Surface,
+    text::{Span, Spans},
+    widgets::{Block, Borders, Widget},
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GrammarJob {
+    Fetch,
+    Build,
+}
+
+impl GrammarJob {
+    fn verb(self) -> &'static str {
+        match self {
+            Self::Fetch => "fetch",
+            Self::Build => "build",
+        }
+    }
+
+    fn gerund(self) -> &'static str {
+        match self {
+            Self::Fetch => "fetching",
+            Self::Build => "building",
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum JobState {
+    Running(GrammarJob),
+    Failed(GrammarJob, String),
+}
+
+/// The fetches and builds started from the editor, kept while the panel is closed.
+#[derive(Default)]
+struct Jobs {
+    states: HashMap<String, JobState>,
+    /// Incremented on every change, so panels know when to rebuild.
+    generation: usize,
+}
+
+static JOBS: Lazy<Mutex<Jobs>> = Lazy::new(Mutex::default);
+
+#[derive(Debug, Clone)]
+enum GrammarItem {
+    Grammar {
+        id: String,
+        state: GrammarState,
+        job: Option<JobState>,
+    },
+    /// A line of the error of a failed job.
+    Error {
+        id: String,
+        index: usize,
+        line: String,
+    },
+}
+
+impl GrammarItem {
+    fn sort_key(&self) -> (&str, usize) {
+        match self {
+            Self::Grammar { id, .. } => (id, 0),
+            Self::Error { id, index, .. } => (id, index + 1),
+        }
+    }
+}
+
+impl TreeItem for GrammarItem {
+    type Params = ();
+
+    fn text(&self, cx: &mut Context, selected: bool, _params: &mut ()) -> Spans {
+        let theme = &cx.editor.theme;
+        let mut span = match self {
+            Self::Grammar { id, .. } => Span::styled(id.clone(), theme.get("ui.text")),
+            Self::Error { line, .. } => Span::styled(line.clone(), theme.get("comment")),
+        };
+        if selected {
+            span.style = span.style.patch(selected_style(cx));
+        }
+        Spans::from(span)
+    }
+
+    /// The running or failed job of a grammar, or else its state on disk.
+    fn columns(&self, cx: &mut Context, selected: bool, _params: &mut ()) -> Vec<Spans> {
+        let (state, job) = match self {
+            Self::Grammar { state, job, .. } => (state, job),
+            Self::Error { .. } => return Vec::new(),
+        };
+        let (text, scope) = match job {
+            Some(JobState::Running(kind)) => (format!("{}…", kind.gerund()), "info"),
+            Some(JobState::Failed(kind, _)) => (format!("failed to {}", kind.verb()), "error"),
+            None => match state {
+                GrammarState::NotFetched => ("not fetched".to_string(), "error"),
+                GrammarState::NotBuilt => ("not built".to_string(), "warning"),
+                GrammarState::Outdated => ("outdated".to_string(), "warning"),
+                GrammarState::Built => ("built".to_string(), "comment"),
+            },
+        };
+        let mut style = cx.editor.theme.get(scope);
+        if selected {
+            style = style.patch(selected_style(cx));
+        }
+        vec![Spans::from(Span::styled(text, style))]
+    }
+
+    fn is_child(&self, other: &Self) -> bool {
+        match (self, other) {
+            (Self::Error { id, .. }, Self::Grammar { id: parent, .. }) => id == parent,
+            _ => false,
+        }
+    }
+
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.sort_key().cmp(&other.sort_key())
+    }
+}
+
+/// The grammars of `languages.toml` with their state on disk and the fetches and builds
+/// started from the editor, whose errors are listed below the failed grammars.
+pub struct GrammarsTree {
+    tree: Tree<GrammarItem>,
+    /// The generation of the jobs the tree was built from.
+    generation: Option<usize>,
+    error: Option<String>,
+}
+
+impl GrammarsTree {
+    pub fn new() -> Self {
+        let mut panel = Self {
+            tree: Tree::build_tree(Vec::new()),
+            generation: None,
+            error: None,
+        };
+        // build the tree before the first render, e.g. for keys of a replayed macro
+        panel.refresh();
+        panel
+    }
+
+    /// Fetches or builds the grammars with the given ids, or all grammars if `ids` is empty, in
+    /// the background. The editor status reports the result once all of them are done.
+    pub fn spawn_job(kind: GrammarJob, ids: &[String], cx: &mut Context) -> anyhow::Result<()> {
+        let grammars = grammar::grammar_configs(ids)?;
+        let total = grammars.len();
+        {
+            let mut jobs = JOBS.lock().unwrap();
+            for grammar in &grammars {
+                jobs.states
+                    .insert(grammar.grammar_id.clone(), JobState::Running(kind));
+            }
+            jobs.generation += 1;
+        }
+
+        let notify = cx.editor.redraw_handle.0.clone();
+        let tasks = grammars.into_iter().map(move |grammar| {
+            let notify = notify.clone();
+            async move {
+                let id = grammar.grammar_id.clone();
+                let result = tokio::task::spawn_blocking(move || match kind {
+                    GrammarJob::Fetch => grammar::fetch_grammar(grammar).map(|_| ()),
+                    GrammarJob::Build => grammar::build_grammar(grammar, None).map(|_| ()),
+                })
+                .await;
+                let error = match result {
+                    Ok(Ok(())) => None,
+                    Ok(Err(e)) => Some(format!("{:#}", e)),
+                    Err(e) => Some(e.to_string()),
+                };
+                let failed = error.is_some();
+                let mut jobs = JOBS.lock().unwrap();
+                match error {
+                    Some(error) => jobs.states.insert(id, JobState::Failed(kind, error)),
+                    None => jobs.states.remove(&id),
+                };
+                jobs.generation += 1;
+                notify.notify_one();
+                failed
+            }
+        });
+        let parallelism = available_parallelism().map_or(1, NonZeroUsize::get);
+
+        cx.editor
+            .set_status(format!("Started {} {} grammars", kind.gerund(), total));
+        cx.jobs.callback(async move {
+            let failed = stream::iter(tasks)
+                .buffer_unordered(parallelism)
+                .filter(|failed| futures_util::future::ready(*failed))
+                .count()
+                .await;
+            let call: job::Callback =
+                job::Callback::Editor(Box::new(move |editor: &mut Editor| {
+                    if failed == 0 {
+                        editor.set_status(format!(
+                            "Finished {} {} grammars, restart to load them",
+                            kind.gerund(),
+                            total
+                        ));
+                    } else {
+                        editor.set_error(format!(
+                            "Failed to {} {} of {} grammars, see :grammar-status",
+                            kind.verb(),
+                            failed,
+                            total
+                        ));
+                    }
+                }));
+            Ok(call)
+        });
+        Ok(())
+    }
+
+    /// Rebuilds the tree if jobs progressed since it was built.
+    fn refresh(&mut self) {
+        let jobs = JOBS.lock().unwrap();
+        if self.generation == Some(jobs.generation) {
+            return;
+        }
+        self.generation = Some(jobs.generation);
+
+        let grammars = match grammar::grammar_configs(&[]) {
+            Ok(grammars) => grammars,
+            Err(e) => {
+                self.error = Some(format!("{:#}", e));
+                return;
+            }
+        };
+        self.error = None;
+        let mut items = Vec::with_capacity(grammars.len());
+        for grammar in grammars {
+            let id = grammar.grammar_id.clone();
+            let job = jobs.states.get(&id).cloned();
+            if let Some(JobState::Failed(_, error)) = &job {
+                items.extend(
+                    error
+                        .lines()
+                        .filter(|line| !line.trim().is_empty())
+                        .enumerate()
+                        .map(|(index, line)| GrammarItem::Error {
+                            id: id.clone(),
+                            index,
+                            line: line.to_string(),
+                        }),
+                );
+            }
+            items.push(GrammarItem::Grammar {
+                id,
+                state: grammar::grammar_state(&grammar),
+                job,
+            });
+        }
+        let selected = self.tree.selected();
+        self.tree.replace_with_new_items(items);
+        self.tree.move_down(selected);
+    }
+
+    fn running_jobs(&self) -> usize {
+        JOBS.lock()
+            .unwrap()
+            .states
+            .values()
+            .filter(|state| matches!(state, JobState::Running(_)))
+            .count()
+    }
+
+    fn current_grammar(&self) -> Option<String> {
+        if self.tree.is_empty() {
+            return None;
+        }
+        match self.tree.current_item() {
+            GrammarItem::Grammar { id, .. } | GrammarItem::Error { id, .. } => Some(id.clone()),
+        }
+    }
+
+    fn start(&mut self, kind: GrammarJob, cx: &mut Context) {
+        if let Some(id) = self.current_grammar() {
+            if let Err(e) = Self::spawn_job(kind, &[id], cx) {
+                cx.editor.set_error(format!("{}", e));
+            }
+        }
+    }
+}
+
+impl Default for GrammarsTree {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Component for GrammarsTree {
+    fn handle_event(&mut self, event: &Event, cx: &mut Context) -> EventResult {
+        let key_event = match event {
+            Event::Key(event) => *event,
+            Event::Mouse(event) => match self.tree.handle_mouse(event, cx, &mut ()) {
+                MouseAction::Activate => key!(Enter),
+                MouseAction::Handled => return EventResult::Consumed(None),
+                MouseAction::Ignored => return EventResult::Ignored(None),
+            },
+            Event::Resize(..) => return EventResult::Consumed(None),
+            _ => return EventResult::Ignored(None),
+        };
+
+        match key_event.into() {
+            key!(Esc) | key!('q') | ctrl!('c') => {
+                return EventResult::Consumed(Some(Box::new(|compositor: &mut Compositor, _| {
+                    compositor.pop();
+                })));
+            }
+            key!('f') => self.start(GrammarJob::Fetch, cx),
+            key!('b') => self.start(GrammarJob::Build, cx),
+            _ => {
+                self.tree.handle_event(Event::Key(key_event), cx, &mut ());
+            }
+        }
+        EventResult::Consumed(None)
+    }
+
+    fn render(&mut self, area: Rect, surface: &mut Surface, cx: &mut Context) {
+        self.refresh();
+
+        let background = cx.editor.theme.get("ui.background");
+        surface.clear_with(area, background);
+
+        let block = Block::default()
+            .title(" Grammars ")
+            .borders(Borders::ALL)
+            .border_style(super::border_style(&cx.editor.theme, cx.focus));
+        let inner = block.inner(area);
+        block.render(area, surface);
+
+        let comment = cx.editor.theme.get("comment");
+        let tree_area = inner.clip_bottom(1);
+        match &self.error {
+            Some(error) => surface.set_stringn(
+                tree_area.x,
+                tree_area.y,
+                error,
+                tree_area.width as usize,
+                cx.editor.theme.get("error"),
+            ),
+            None => self.tree.render(tree_area, surface, cx, &mut ()),
+        };
+
+        let footer_area = inner.clip_top(tree_area.height);
+        let footer = match self.running_jobs() {
+            0 => "[f]fetch [b]build".to_string(),
+            running => format!("{} grammars left [f]fetch [b]build", running),
+        };
+        surface.set_stringn(
+            footer_area.x,
+            footer_area.y,
+            footer,
+            footer_area.width as usize,
+            comment,
+        );
+    }
+
+    fn cursor(&self, _area: Rect, _editor: &Editor) -> (Option<Position>, CursorKind) {
+        (None, CursorKind::Hidden)
+    }
+
+    fn is_panel(&self) -> bool {
+        true
+    }
+}
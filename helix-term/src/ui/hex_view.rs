@@ -0,0 +1,377 @@
+use super::Prompt;
+use crate::{
+    compositor::{Component, Compositor, Context, EventResult},
+    ctrl, key, shift, ui,
+};
+use helix_core::Position;
+use helix_view::{
+    graphics::{CursorKind, Modifier, Rect, Style},
+    input::{Event, KeyEvent},
+    Editor,
+};
+use std::path::{Path, PathBuf};
+use tui::{
+    buffer::Buffer as Surface,
+    widgets::{Block, Borders, Widget},
+};
+
+const BYTES_PER_ROW: usize = 16;
+
+/// Parses a search pattern, either hex bytes like `ff d8` or a quoted string like `"PNG"`.
+fn parse_pattern(pattern: &str) -> Option<Vec<u8>> {
+    let pattern = pattern.trim();
+    if let Some(text) = pattern.strip_prefix('"') {
+        let text = text.strip_suffix('"').unwrap_or(text);
+        return (!text.is_empty()).then(|| text.as_bytes().to_vec());
+    }
+    let digits: String = pattern.chars().filter(|c| !c.is_whitespace()).collect();
+    if digits.is_empty() || digits.len() % 2 != 0 || !digits.is_ascii() {
+        return None;
+    }
+    (0..digits.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(&digits[i..i + 2], 16).ok())
+        .collect()
+}
+
+/// Finds the next occurrence of `pattern` after `from`, or the previous one before it, wrapping
+/// around the end of the data.
+fn find(data: &[u8], pattern: &[u8], from: usize, forward: bool) -> Option<usize> {
+    let matches = |start: &usize| data[*start..].starts_with(pattern);
+    if forward {
+        (from + 1..data.len()).chain(0..=from).find(matches)
+    } else {
+        (0..from)
+            .rev()
+            .chain((from..data.len()).rev())
+            .find(matches)
+    }
+}
+
+/// An editable hex dump of a file, with offset, hex and ASCII columns. Changes are only written
+/// to the file on request.
+pub struct HexView {
+    path: PathBuf,
+    title: String,
+    data: Vec<u8>,
+    /// The contents as of the last write, to mark and revert changed bytes.
+    saved: Vec<u8>,
+    cursor: usize,
+    /// Whether the low nibble of the byte at the cursor is edited next.
+    low_nibble: bool,
+    /// First shown row.
+    offset: usize,
+    height: usize,
+    pattern: Option<Vec<u8>>,
+    prompt: Option<Prompt>,
+}
+
+impl HexView {
+    pub fn new(path: &Path) -> std::io::Result<Self> {
+        let data = std::fs::read(path)?;
+        Ok(Self {
+            path: path.to_path_buf(),
+            title: helix_core::path::get_relative_path(path)
+                .to_string_lossy()
+                .into_owned(),
+            saved: data.clone(),
+            data,
+            cursor: 0,
+            low_nibble: false,
+            offset: 0,
+            height: 0,
+            pattern: None,
+            prompt: None,
+        })
+    }
+
+    fn is_modified(&self) -> bool {
+        self.data != self.saved
+    }
+
+    fn move_by(&mut self, bytes: isize) {
+        let last = self.data.len().saturating_sub(1) as isize;
+        self.cursor = (self.cursor as isize + bytes).clamp(0, last.max(0)) as usize;
+        self.low_nibble = false;
+    }
+
+    fn write_nibble(&mut self, digit: u8) {
+        let byte = match self.data.get_mut(self.cursor) {
+            Some(byte) => byte,
+            None => return,
+        };
+        if self.low_nibble {
+            *byte = (*byte & 0xf0) | digit;
+            self.move_by(1);
+        } else {
+            *byte = (*byte & 0x0f) | (digit << 4);
+            self.low_nibble = true;
+        }
+    }
+
+    fn revert_byte(&mut self) {
+        if let (Some(byte), Some(saved)) =
+            (self.data.get_mut(self.cursor), self.saved.get(self.cursor))
+        {
+            *byte = *saved;
+        }
+    }
+
+    fn write(&mut self, editor: &mut Editor) {
+        if let Err(e) = std::fs::write(&self.path, &self.data) {
+            editor.set_error(format!("Failed to write {}: {}", self.title, e));
+            return;
+        }
+        self.saved = self.data.clone();
+        editor.set_status(format!(
+            "Written {} bytes to {}",
+            self.data.len(),
+            self.title
+        ));
+
+        // the text buffer of the file can't be written while it's binary, keep it in sync with
+        // the written bytes
+        let doc_id = match editor.document_by_path(&self.path) {
+            Some(doc) => doc.id(),
+            None => return,
+        };
+        let current_view = view!(editor).id;
+        let redraw_handle = editor.redraw_handle.clone();
+        let doc = doc_mut!(editor, &doc_id);
+        let view_id = match doc.selections().keys().next() {
+            Some(view_id) => *view_id,
+            None => {
+                doc.ensure_view_init(current_view);
+                current_view
+            }
+        };
+        let view = view_mut!(editor, view_id);
+        view.sync_changes(doc);
+        if let Err(e) = doc.reload(view, &editor.diff_providers, redraw_handle) {
+            editor.set_error(format!("Failed to reload {}: {}", self.title, e));
+        }
+    }
+
+    fn search(&mut self, forward: bool, editor: &mut Editor) {
+        let pattern = match &self.pattern {
+            Some(pattern) => pattern,
+            None => return,
+        };
+        match find(&self.data, pattern, self.cursor, forward) {
+            Some(pos) => {
+                self.cursor = pos;
+                self.low_nibble = false;
+            }
+            None => editor.set_error("Pattern not found"),
+        }
+    }
+
+    fn handle_prompt_event(&mut self, event: KeyEvent, cx: &mut Context) -> EventResult {
+        let mut prompt = match self.prompt.take() {
+            Some(prompt) => prompt,
+            None => return EventResult::Ignored(None),
+        };
+        match event.into() {
+            key!(Enter) => match parse_pattern(prompt.line()) {
+                Some(pattern) => {
+                    self.pattern = Some(pattern);
+                    self.search(true, cx.editor);
+                }
+                None => cx
+                    .editor
+                    .set_error("Expected hex bytes like `ff d8` or a quoted string"),
+            },
+            key!(Esc) | ctrl!('c') => {}
+            _ => {
+                prompt.handle_event(&Event::Key(event), cx);
+                self.prompt = Some(prompt);
+            }
+        }
+        EventResult::Consumed(None)
+    }
+}
+
+impl Component for HexView {
+    fn handle_event(&mut self, event: &Event, cx: &mut Context) -> EventResult {
+        let key_event = match event {
+            Event::Key(event) => *event,
+            Event::Resize(..) => return EventResult::Consumed(None),
+            _ => return EventResult::Ignored(None),
+        };
+
+        if self.prompt.is_some() {
+            return self.handle_prompt_event(key_event, cx);
+        }
+
+        let close_fn = EventResult::Consumed(Some(Box::new(|compositor: &mut Compositor, _| {
+            compositor.pop();
+        })));
+
+        let row = BYTES_PER_ROW as isize;
+        let page = self.height.max(1) as isize * row;
+        match key_event.into() {
+            key!(Esc) | key!('q') | ctrl!('c') => {
+                if !self.is_modified() {
+                    return close_fn;
+                }
+                cx.editor
+                    .set_error("Unsaved changes, [w]rite them or [Q] to discard them");
+            }
+            shift!('Q') => return close_fn,
+            key!('h') | key!(Left) => self.move_by(-1),
+            key!('l') | key!(Right) => self.move_by(1),
+            key!('k') | key!(Up) => self.move_by(-row),
+            key!('j') | key!(Down) => self.move_by(row),
+            ctrl!('u') => self.move_by(-page / 2),
+            ctrl!('d') => self.move_by(page / 2),
+            key!(PageUp) => self.move_by(-page),
+            key!(PageDown) => self.move_by(page),
+            key!('g') | key!(Home) => self.move_by(isize::MIN / 2),
+            shift!('G') | key!(End) => self.move_by(isize::MAX / 2),
+            key!(c @ '0'..='9') | key!(c @ 'a'..='f') => {
+                self.write_nibble(c.to_digit(16).unwrap() as u8)
+            }
+            key!('u') => self.revert_byte(),
+            key!('w') | ctrl!('s') => self.write(cx.editor),
+            key!('/') => {
+                self.prompt = Some(Prompt::new(
+                    "search bytes:".into(),
+                    None,
+                    ui::completers::none,
+                    |_, _, _| {},
+                ))
+            }
+            key!('n') => self.search(true, cx.editor),
+            shift!('N') => self.search(false, cx.editor),
+            _ => {}
+        }
+        EventResult::Consumed(None)
+    }
+
+    fn render(&mut self, area: Rect, surface: &mut Surface, cx: &mut Context) {
+        let theme = &cx.editor.theme;
+        surface.clear_with(area, theme.get("ui.background"));
+
+        let modified = if self.is_modified() { "[+] " } else { "" };
+        let title = format!(" {} {}({} bytes) ", self.title, modified, self.data.len());
+        let block = Block::default().title(title).borders(Borders::ALL);
+        let inner = block.inner(area);
+        block.render(area, surface);
+
+        let dump_area = inner.clip_bottom(1);
+        self.height = dump_area.height as usize;
+        let cursor_row = self.cursor / BYTES_PER_ROW;
+        if cursor_row < self.offset {
+            self.offset = cursor_row;
+        } else if cursor_row >= self.offset + self.height {
+            self.offset = cursor_row + 1 - self.height.max(1);
+        }
+
+        let offset_style = theme.get("ui.linenr");
+        let text_style = theme.get("ui.text");
+        let changed_style = theme.get("diff.delta");
+        let cursor_style = theme
+            .try_get("ui.cursor.primary")
+            .unwrap_or_else(|| Style::default().add_modifier(Modifier::REVERSED));
+        let rows = (self.data.len() + BYTES_PER_ROW - 1) / BYTES_PER_ROW;
+        for (y, row) in (dump_area.y..dump_area.bottom()).zip(self.offset..rows) {
+            let start = row * BYTES_PER_ROW;
+            let bytes = &self.data[start..(start + BYTES_PER_ROW).min(self.data.len())];
+            let label = format!("{:08x}", start);
+            let (x, _) = surface.set_stringn(
+                dump_area.x,
+                y,
+                &label,
+                dump_area.width as usize,
+                offset_style,
+            );
+            let mut x = x + 2;
+            let ascii_x = x + (BYTES_PER_ROW * 3) as u16 + 2;
+
+            for (column, byte) in bytes.iter().enumerate() {
+                if column == BYTES_PER_ROW / 2 {
+                    x += 1;
+                }
+                let pos = start + column;
+                let style = if self.saved.get(pos) != Some(byte) {
+                    changed_style
+                } else {
+                    text_style
+                };
+                let hex = format!("{:02x}", byte);
+                let ascii = if byte.is_ascii_graphic() || *byte == b' ' {
+                    *byte as char
+                } else {
+                    '.'
+                };
+                let width = dump_area.right().saturating_sub(x) as usize;
+                surface.set_stringn(x, y, &hex, width, style);
+                if pos == self.cursor {
+                    // highlight the nibble edited next
+                    let nibble_x = x + self.low_nibble as u16;
+                    if nibble_x < dump_area.right() {
+                        surface.set_style(Rect::new(nibble_x, y, 1, 1), cursor_style);
+                    }
+                }
+                let ascii_x = ascii_x + column as u16;
+                if ascii_x < dump_area.right() {
+                    let style = if pos == self.cursor {
+                        cursor_style
+                    } else {
+                        style
+                    };
+                    surface.set_stringn(ascii_x, y, ascii.to_string(), 1, style);
+                }
+                x += 3;
+            }
+        }
+
+        let footer_area = inner.clip_top(dump_area.height);
+        match &self.prompt {
+            Some(prompt) => prompt.render_prompt(footer_area, surface, cx),
+            None => {
+                let style = cx.editor.theme.get("comment");
+                surface.set_stringn(
+                    footer_area.x,
+                    footer_area.y,
+                    format!(
+                        "{:08x} [0-f]edit [u]ndo byte [/]search [w]rite",
+                        self.cursor
+                    ),
+                    footer_area.width as usize,
+                    style,
+                );
+            }
+        }
+    }
+
+    fn cursor(&self, area: Rect, editor: &Editor) -> (Option<Position>, CursorKind) {
+        match &self.prompt {
+            Some(prompt) => {
+                let inner = Block::default().borders(Borders::ALL).inner(area);
+                prompt.cursor(inner.clip_top(inner.height.saturating_sub(1)), editor)
+            }
+            None => (None, CursorKind::Hidden),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn search_patterns() {
+        assert_eq!(parse_pattern("ff D8 "), Some(vec![0xff, 0xd8]));
+        assert_eq!(parse_pattern("ffd"), None);
+        assert_eq!(parse_pattern("\"PNG\""), Some(b"PNG".to_vec()));
+        assert_eq!(parse_pattern("é1"), None);
+
+        let data = b"abcabc";
+        assert_eq!(find(data, b"bc", 1, true), Some(4));
+        assert_eq!(find(data, b"bc", 4, true), Some(1));
+        assert_eq!(find(data, b"bc", 4, false), Some(1));
+        assert_eq!(find(data, b"bc", 1, false), Some(4));
+        assert_eq!(find(data, b"x", 0, true), None);
+    }
+}
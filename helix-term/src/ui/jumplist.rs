@@ -0,0 +1,248 @@
+use super::{
+    tree::{patch_selected, selected_style},
+    MouseAction, Tree, TreeItem,
+};
+use crate::{
+    compositor::{Component, Compositor, Context, EventResult},
+    ctrl, key,
+};
+use helix_core::{path::get_relative_path, Position, Range, Selection};
+use helix_view::{
+    document::SCRATCH_BUFFER_NAME,
+    editor::Action,
+    graphics::{CursorKind, Rect},
+    input::Event,
+    DocumentId, Editor,
+};
+use std::cmp::Ordering;
+use tui::{
+    buffer::Buffer as Surface,
+    text::{Span, Spans},
+    widgets::{Block, Borders, Widget},
+};
+
+#[derive(Debug, Clone)]
+enum JumpItem {
+    File {
+        id: DocumentId,
+        name: String,
+        /// The position of the file, the file of the most recent jump first.
+        rank: usize,
+    },
+    Jump {
+        id: DocumentId,
+        file_rank: usize,
+        /// The position in the jumplist, counted from the most recent jump.
+        index: usize,
+        selection: Selection,
+        line: usize,
+        preview: String,
+    },
+}
+
+impl JumpItem {
+    fn sort_key(&self) -> (usize, usize) {
+        match self {
+            Self::File { rank, .. } => (*rank, 0),
+            Self::Jump {
+                file_rank, index, ..
+            } => (*file_rank, index + 1),
+        }
+    }
+}
+
+impl TreeItem for JumpItem {
+    type Params = ();
+
+    fn text(&self, cx: &mut Context, selected: bool, _params: &mut ()) -> Spans {
+        let theme = &cx.editor.theme;
+        let mut spans = match self {
+            Self::File { name, .. } => vec![Span::styled(name.clone(), theme.get("ui.text.focus"))],
+            Self::Jump { preview, .. } => vec![Span::styled(preview.clone(), theme.get("ui.text"))],
+        };
+        if selected {
+            patch_selected(cx, &mut spans);
+        }
+        Spans::from(spans)
+    }
+
+    /// The line of a jump.
+    fn columns(&self, cx: &mut Context, selected: bool, _params: &mut ()) -> Vec<Spans> {
+        let line = match self {
+            Self::Jump { line, .. } => *line,
+            Self::File { .. } => return Vec::new(),
+        };
+        let mut style = cx.editor.theme.get("comment");
+        if selected {
+            style = style.patch(selected_style(cx));
+        }
+        vec![Spans::from(Span::styled(
+            format!("line {}", line + 1),
+            style,
+        ))]
+    }
+
+    fn is_child(&self, other: &Self) -> bool {
+        match (self, other) {
+            (Self::Jump { id, .. }, Self::File { id: parent, .. }) => id == parent,
+            _ => false,
+        }
+    }
+
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.sort_key().cmp(&other.sort_key())
+    }
+}
+
+/// The jumplist of the current view grouped by document, the most recent jumps first.
+pub struct JumplistTree {
+    tree: Tree<JumpItem>,
+}
+
+impl JumplistTree {
+    pub fn new(editor: &Editor) -> Self {
+        Self {
+            tree: Tree::build_tree(Self::items(editor)),
+        }
+    }
+
+    fn items(editor: &Editor) -> Vec<JumpItem> {
+        let view = view!(editor);
+        let mut files: Vec<DocumentId> = Vec::new();
+        let mut items = Vec::new();
+        for (index, (id, selection)) in view.jumps.iter().rev().enumerate() {
+            let doc = match editor.documents.get(id) {
+                Some(doc) => doc,
+                None => continue,
+            };
+            let file_rank = match files.iter().position(|file| file == id) {
+                Some(rank) => rank,
+                None => {
+                    let name = doc
+                        .path()
+                        .map(|path| get_relative_path(path).to_string_lossy().into_owned())
+                        .unwrap_or_else(|| SCRATCH_BUFFER_NAME.to_string());
+                    items.push(JumpItem::File {
+                        id: *id,
+                        name,
+                        rank: files.len(),
+                    });
+                    files.push(*id);
+                    files.len() - 1
+                }
+            };
+
+            let text = doc.text().slice(..);
+            // the selection may be out of date if the document changed since
+            let len = text.len_chars();
+            let selection = selection
+                .clone()
+                .transform(|range| Range::new(range.anchor.min(len), range.head.min(len)))
+                .ensure_invariants(text);
+            let line = selection.primary().cursor_line(text);
+            let preview = text.line(line).to_string().trim().to_string();
+            items.push(JumpItem::Jump {
+                id: *id,
+                file_rank,
+                index,
+                selection,
+                line,
+                preview,
+            });
+        }
+        items
+    }
+
+    /// Restores the selection of the jump, returning whether the tree should close.
+    fn jump(&self, editor: &mut Editor) -> bool {
+        if self.tree.is_empty() {
+            return false;
+        }
+        let (id, selection) = match self.tree.current_item() {
+            JumpItem::Jump { id, selection, .. } => (*id, selection.clone()),
+            JumpItem::File { .. } => return false,
+        };
+        if !editor.documents.contains_key(&id) {
+            editor.set_error("The document of the jump was closed");
+            return false;
+        }
+        editor.switch(id, Action::Replace);
+        let scrolloff = editor.config().scrolloff;
+        let (view, doc) = current!(editor);
+        doc.set_selection(view.id, selection);
+        view.ensure_cursor_in_view_center(doc, scrolloff);
+        true
+    }
+}
+
+impl Component for JumplistTree {
+    fn handle_event(&mut self, event: &Event, cx: &mut Context) -> EventResult {
+        let key_event = match event {
+            Event::Key(event) => *event,
+            Event::Mouse(event) => match self.tree.handle_mouse(event, cx, &mut ()) {
+                MouseAction::Activate => key!(Enter),
+                MouseAction::Handled => return EventResult::Consumed(None),
+                MouseAction::Ignored => return EventResult::Ignored(None),
+            },
+            Event::Resize(..) => return EventResult::Consumed(None),
+            _ => return EventResult::Ignored(None),
+        };
+
+        let close_fn = EventResult::Consumed(Some(Box::new(|compositor: &mut Compositor, _| {
+            compositor.pop();
+        })));
+
+        match key_event.into() {
+            key!(Esc) | key!('q') | ctrl!('c') => return close_fn,
+            _ if self.tree.is_empty() => {}
+            key!(Enter) => {
+                if self.jump(cx.editor) {
+                    return close_fn;
+                }
+                self.tree.handle_event(Event::Key(key_event), cx, &mut ());
+            }
+            _ => {
+                self.tree.handle_event(Event::Key(key_event), cx, &mut ());
+            }
+        }
+        EventResult::Consumed(None)
+    }
+
+    fn render(&mut self, area: Rect, surface: &mut Surface, cx: &mut Context) {
+        let background = cx.editor.theme.get("ui.background");
+        surface.clear_with(area, background);
+
+        let block = Block::default()
+            .title(" Jumplist ")
+            .borders(Borders::ALL)
+            .border_style(super::border_style(&cx.editor.theme, cx.focus));
+        let inner = block.inner(area);
+        block.render(area, surface);
+
+        let comment = cx.editor.theme.get("comment");
+        if self.tree.is_empty() {
+            surface.set_stringn(inner.x, inner.y, "No jumps", inner.width as usize, comment);
+            return;
+        }
+
+        let tree_area = inner.clip_bottom(1);
+        self.tree.render(tree_area, surface, cx, &mut ());
+
+        let footer_area = inner.clip_top(tree_area.height);
+        surface.set_stringn(
+            footer_area.x,
+            footer_area.y,
+            "[enter]jump",
+            footer_area.width as usize,
+            comment,
+        );
+    }
+
+    fn cursor(&self, _area: Rect, _editor: &Editor) -> (Option<Position>, CursorKind) {
+        (None, CursorKind::Hidden)
+    }
+
+    fn is_panel(&self) -> bool {
+        true
+    }
+}
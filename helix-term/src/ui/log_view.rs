@@ -0,0 +1,404 @@
+use super::Prompt;
+use crate::{
+    compositor::{Component, Compositor, Context, EventResult},
+    ctrl, key, shift, ui,
+};
+use helix_core::{
+    log_file::{parse_log, LogEntry, LogLevel, LogPatterns},
+    Position, Selection,
+};
+use helix_view::{
+    align_view,
+    graphics::{CursorKind, Modifier, Rect, Style},
+    input::{Event, KeyEvent},
+    theme::Theme,
+    Align, Document, DocumentId, Editor,
+};
+use std::time::{Duration, SystemTime};
+use tokio::task::JoinHandle;
+use tui::{
+    buffer::Buffer as Surface,
+    text::{Span, Spans},
+    widgets::{Block, Borders, Widget},
+};
+
+/// How often the file is checked for changes while following it.
+const FOLLOW_INTERVAL: Duration = Duration::from_secs(1);
+
+fn level_style(theme: &Theme, level: LogLevel) -> Style {
+    match level {
+        LogLevel::Trace => theme.get("comment"),
+        LogLevel::Debug => theme.get("hint"),
+        LogLevel::Info => theme.get("info"),
+        LogLevel::Warn => theme.get("warning"),
+        LogLevel::Error => theme.get("error"),
+    }
+}
+
+/// Splits the text of an entry into spans colored by its level.
+fn entry_spans<'a>(entry: &'a LogEntry, theme: &Theme) -> Spans<'a> {
+    let text_style = match entry.level {
+        Some(level) if level >= LogLevel::Warn => level_style(theme, level),
+        _ => theme.get("ui.text"),
+    };
+    let mut marks = Vec::new();
+    if let Some(range) = &entry.timestamp {
+        marks.push((range.clone(), theme.get("comment")));
+    }
+    if let (Some(range), Some(level)) = (&entry.level_range, entry.level) {
+        let style = level_style(theme, level).add_modifier(Modifier::BOLD);
+        marks.push((range.clone(), style));
+    }
+    marks.sort_by_key(|(range, _)| range.start);
+
+    let mut spans = Vec::new();
+    let mut pos = 0;
+    for (range, style) in marks {
+        if range.start < pos {
+            continue;
+        }
+        spans.push(Span::styled(&entry.text[pos..range.start], text_style));
+        spans.push(Span::styled(&entry.text[range.clone()], style));
+        pos = range.end;
+    }
+    spans.push(Span::styled(&entry.text[pos..], text_style));
+    if entry.repeats > 1 {
+        spans.push(Span::styled(
+            format!(" (×{})", entry.repeats),
+            theme.get("comment"),
+        ));
+    }
+    for span in spans.iter_mut() {
+        if span.content.contains('\t') {
+            span.content = span.content.replace('\t', "    ").into();
+        }
+    }
+    Spans::from(spans)
+}
+
+/// A read-only pager over the lines of a log buffer. Lines are colored by their level, repeated
+/// lines are folded into one, and lines below a minimum level or not matching a filter are
+/// hidden. While following, the file is reloaded as it grows and the last line stays selected.
+pub struct LogView {
+    doc_id: DocumentId,
+    title: String,
+    patterns: LogPatterns,
+    entries: Vec<LogEntry>,
+    /// Indices of the shown entries.
+    visible: Vec<usize>,
+    /// Version of the document the entries were parsed from.
+    version: i32,
+    selected: usize,
+    /// First shown entry of `visible`.
+    offset: usize,
+    min_level: Option<LogLevel>,
+    filter: String,
+    prompt: Option<Prompt>,
+    /// Requests redraws while following the file.
+    follow: Option<JoinHandle<()>>,
+    /// Length and modification time of the file when it was last reloaded.
+    file_state: Option<(u64, Option<SystemTime>)>,
+    height: usize,
+}
+
+impl LogView {
+    pub fn new(doc: &Document, patterns: LogPatterns) -> Self {
+        let mut view = Self {
+            doc_id: doc.id(),
+            title: doc.display_name().into_owned(),
+            patterns,
+            entries: Vec::new(),
+            visible: Vec::new(),
+            version: doc.version(),
+            selected: 0,
+            offset: 0,
+            min_level: None,
+            filter: String::new(),
+            prompt: None,
+            follow: None,
+            file_state: Self::file_state(doc),
+            height: 0,
+        };
+        view.parse(doc);
+        view
+    }
+
+    fn file_state(doc: &Document) -> Option<(u64, Option<SystemTime>)> {
+        let metadata = std::fs::metadata(doc.path()?).ok()?;
+        Some((metadata.len(), metadata.modified().ok()))
+    }
+
+    fn parse(&mut self, doc: &Document) {
+        self.entries = parse_log(doc.text().slice(..), &self.patterns);
+        self.version = doc.version();
+        self.refresh();
+    }
+
+    /// Recomputes the shown entries, keeping the selected line if it is still shown.
+    fn refresh(&mut self) {
+        let line = self
+            .visible
+            .get(self.selected)
+            .map(|index| self.entries[*index].line);
+
+        let filter = self.filter.to_lowercase();
+        let min_level = self.min_level;
+        self.visible = self
+            .entries
+            .iter()
+            .enumerate()
+            .filter(|(_, entry)| match (min_level, entry.level) {
+                (Some(min_level), Some(level)) => level >= min_level,
+                _ => true,
+            })
+            .filter(|(_, entry)| filter.is_empty() || entry.text.to_lowercase().contains(&filter))
+            .map(|(index, _)| index)
+            .collect();
+
+        self.selected = line
+            .and_then(|line| {
+                self.visible
+                    .iter()
+                    .position(|index| self.entries[*index].line >= line)
+            })
+            .unwrap_or(self.selected)
+            .min(self.visible.len().saturating_sub(1));
+    }
+
+    fn move_by(&mut self, rows: isize) {
+        let last = self.visible.len().saturating_sub(1) as isize;
+        self.selected = (self.selected as isize + rows).clamp(0, last.max(0)) as usize;
+    }
+
+    /// Raises or lowers the minimum shown level by `delta` steps.
+    fn change_min_level(&mut self, delta: isize, editor: &mut Editor) {
+        // 0 shows all levels
+        let index = self.min_level.map_or(0, |level| level as usize + 1) as isize;
+        let index = (index + delta).clamp(0, LogLevel::ALL.len() as isize) as usize;
+        self.min_level = index.checked_sub(1).map(|index| LogLevel::ALL[index]);
+        match self.min_level {
+            Some(level) => editor.set_status(format!("Showing {} and above", level.as_str())),
+            None => editor.set_status("Showing all levels"),
+        }
+        self.refresh();
+    }
+
+    fn toggle_follow(&mut self, editor: &mut Editor) {
+        match self.follow.take() {
+            Some(task) => {
+                task.abort();
+                editor.set_status("Stopped following");
+            }
+            None => {
+                let notify = editor.redraw_handle.0.clone();
+                self.follow = Some(tokio::spawn(async move {
+                    let mut interval = tokio::time::interval(FOLLOW_INTERVAL);
+                    loop {
+                        interval.tick().await;
+                        notify.notify_one();
+                    }
+                }));
+                self.move_by(isize::MAX / 2);
+                editor.set_status("Following");
+            }
+        }
+    }
+
+    /// Reloads the document if its file changed on disk and it has no unsaved changes.
+    fn reload_changed_file(&mut self, editor: &mut Editor) {
+        let redraw_handle = editor.redraw_handle.clone();
+        let (view, doc) = current!(editor);
+        if doc.id() != self.doc_id || doc.is_modified() {
+            return;
+        }
+        let file_state = Self::file_state(doc);
+        if file_state == self.file_state {
+            return;
+        }
+        self.file_state = file_state;
+        if let Err(e) = doc.reload(view, &editor.diff_providers, redraw_handle) {
+            editor.set_error(format!("Failed to reload log: {}", e));
+        }
+    }
+
+    fn handle_prompt_event(&mut self, event: KeyEvent, cx: &mut Context) -> EventResult {
+        let mut prompt = match self.prompt.take() {
+            Some(prompt) => prompt,
+            None => return EventResult::Ignored(None),
+        };
+        match event.into() {
+            key!(Enter) => {}
+            key!(Esc) | ctrl!('c') => {
+                self.filter.clear();
+                self.refresh();
+            }
+            _ => {
+                prompt.handle_event(&Event::Key(event), cx);
+                if *prompt.line() != self.filter {
+                    self.filter = prompt.line().clone();
+                    self.refresh();
+                }
+                self.prompt = Some(prompt);
+            }
+        }
+        EventResult::Consumed(None)
+    }
+
+    fn jump(&self) -> EventResult {
+        let line = match self.visible.get(self.selected) {
+            Some(index) => self.entries[*index].line,
+            None => return EventResult::Consumed(None),
+        };
+        let doc_id = self.doc_id;
+        EventResult::Consumed(Some(Box::new(move |compositor: &mut Compositor, cx| {
+            compositor.pop();
+            let (view, doc) = current!(cx.editor);
+            if doc.id() != doc_id {
+                return;
+            }
+            let pos = doc
+                .text()
+                .line_to_char(line.min(doc.text().len_lines() - 1));
+            doc.set_selection(view.id, Selection::point(pos));
+            align_view(doc, view, Align::Center);
+        })))
+    }
+}
+
+impl Drop for LogView {
+    fn drop(&mut self) {
+        if let Some(task) = self.follow.take() {
+            task.abort();
+        }
+    }
+}
+
+impl Component for LogView {
+    fn handle_event(&mut self, event: &Event, cx: &mut Context) -> EventResult {
+        let key_event = match event {
+            Event::Key(event) => *event,
+            Event::Resize(..) => return EventResult::Consumed(None),
+            _ => return EventResult::Ignored(None),
+        };
+
+        if self.prompt.is_some() {
+            return self.handle_prompt_event(key_event, cx);
+        }
+
+        let page = self.height.max(1) as isize;
+        match key_event.into() {
+            key!(Esc) | key!('q') | ctrl!('c') => {
+                return EventResult::Consumed(Some(Box::new(|compositor: &mut Compositor, _| {
+                    compositor.pop();
+                })))
+            }
+            key!('j') | key!(Down) => self.move_by(1),
+            key!('k') | key!(Up) => self.move_by(-1),
+            ctrl!('d') => self.move_by(page / 2),
+            ctrl!('u') => self.move_by(-page / 2),
+            key!(PageDown) => self.move_by(page),
+            key!(PageUp) => self.move_by(-page),
+            key!('g') | key!(Home) => self.selected = 0,
+            shift!('G') | key!(End) => self.move_by(isize::MAX / 2),
+            key!('+') => self.change_min_level(1, cx.editor),
+            key!('-') => self.change_min_level(-1, cx.editor),
+            key!('f') => self.toggle_follow(cx.editor),
+            key!('/') => {
+                let mut prompt =
+                    Prompt::new("filter:".into(), None, ui::completers::none, |_, _, _| {});
+                prompt.insert_str(&self.filter, cx.editor);
+                self.prompt = Some(prompt);
+            }
+            key!(Enter) => return self.jump(),
+            _ => {}
+        }
+        EventResult::Consumed(None)
+    }
+
+    fn render(&mut self, area: Rect, surface: &mut Surface, cx: &mut Context) {
+        if self.follow.is_some() {
+            self.reload_changed_file(cx.editor);
+        }
+        if let Some(doc) = cx.editor.documents.get(&self.doc_id) {
+            if doc.version() != self.version {
+                self.parse(doc);
+            }
+        }
+        if self.follow.is_some() {
+            self.move_by(isize::MAX / 2);
+        }
+
+        let theme = &cx.editor.theme;
+        let background = theme.get("ui.background");
+        surface.clear_with(area, background);
+
+        let mut title = format!(
+            " {} ({}/{} lines",
+            self.title,
+            self.visible.len(),
+            self.entries.len()
+        );
+        if let Some(level) = self.min_level {
+            title.push_str(&format!(", {}+", level.as_str()));
+        }
+        if self.follow.is_some() {
+            title.push_str(", following");
+        }
+        title.push_str(") ");
+        let block = Block::default().title(title).borders(Borders::ALL);
+        let inner = block.inner(area);
+        block.render(area, surface);
+
+        let lines_area = inner.clip_bottom(1);
+        self.height = lines_area.height as usize;
+        if self.selected < self.offset {
+            self.offset = self.selected;
+        } else if self.selected >= self.offset + self.height {
+            self.offset = self.selected + 1 - self.height.max(1);
+        }
+
+        let selected_style = theme
+            .try_get("ui.menu.selected")
+            .unwrap_or_else(|| Style::default().add_modifier(Modifier::REVERSED));
+        let end = (self.offset + self.height).min(self.visible.len());
+        for (row, index) in self.visible[self.offset..end].iter().enumerate() {
+            let y = lines_area.y + row as u16;
+            let mut spans = entry_spans(&self.entries[*index], theme);
+            if self.offset + row == self.selected {
+                surface.set_style(
+                    Rect::new(lines_area.x, y, lines_area.width, 1),
+                    selected_style,
+                );
+                for span in spans.0.iter_mut() {
+                    span.style = span.style.patch(selected_style);
+                }
+            }
+            surface.set_spans(lines_area.x, y, &spans, lines_area.width);
+        }
+
+        let footer_area = inner.clip_top(lines_area.height);
+        match &self.prompt {
+            Some(prompt) => prompt.render_prompt(footer_area, surface, cx),
+            None => {
+                let style = cx.editor.theme.get("comment");
+                surface.set_stringn(
+                    footer_area.x,
+                    footer_area.y,
+                    "[/]filter [+/-]level [f]ollow [enter]jump to line",
+                    footer_area.width as usize,
+                    style,
+                );
+            }
+        }
+    }
+
+    fn cursor(&self, area: Rect, editor: &Editor) -> (Option<Position>, CursorKind) {
+        match &self.prompt {
+            Some(prompt) => {
+                let inner = Block::default().borders(Borders::ALL).inner(area);
+                prompt.cursor(inner.clip_top(inner.height.saturating_sub(1)), editor)
+            }
+            None => (None, CursorKind::Hidden),
+        }
+    }
+}
@@ -0,0 +1,228 @@
+//! Descriptions of binary files for previews, with the dimensions of images read from their
+//! headers.
+
+use std::{fs::File, io::Read, path::Path};
+
+/// Bytes read to find the dimensions, JPEG metadata can push them back quite a bit.
+const HEADER_LEN: u64 = 64 * 1024;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct MediaInfo {
+    pub kind: &'static str,
+    /// Width and height in pixels.
+    pub dimensions: Option<(u32, u32)>,
+}
+
+fn u16_be(data: &[u8], at: usize) -> Option<u32> {
+    let bytes = data.get(at..at + 2)?;
+    Some(u16::from_be_bytes([bytes[0], bytes[1]]) as u32)
+}
+
+fn u16_le(data: &[u8], at: usize) -> Option<u32> {
+    let bytes = data.get(at..at + 2)?;
+    Some(u16::from_le_bytes([bytes[0], bytes[1]]) as u32)
+}
+
+fn u24_le(data: &[u8], at: usize) -> Option<u32> {
+    let bytes = data.get(at..at + 3)?;
+    Some(u32::from_le_bytes([bytes[0], bytes[1], bytes[2], 0]))
+}
+
+fn u32_be(data: &[u8], at: usize) -> Option<u32> {
+    let bytes = data.get(at..at + 4)?;
+    Some(u32::from_be_bytes(bytes.try_into().ok()?))
+}
+
+fn i32_le(data: &[u8], at: usize) -> Option<i32> {
+    let bytes = data.get(at..at + 4)?;
+    Some(i32::from_le_bytes(bytes.try_into().ok()?))
+}
+
+/// Finds the dimensions in the first start of frame segment.
+fn jpeg_dimensions(data: &[u8]) -> Option<(u32, u32)> {
+    let mut pos = 2;
+    loop {
+        if *data.get(pos)? != 0xff {
+            return None;
+        }
+        let marker = *data.get(pos + 1)?;
+        match marker {
+            // padding
+            0xff => pos += 1,
+            // markers without a length
+            0x01 | 0xd0..=0xd7 => pos += 2,
+            // start of frame, except for huffman and arithmetic coding tables
+            0xc0..=0xcf if !matches!(marker, 0xc4 | 0xc8 | 0xcc) => {
+                let height = u16_be(data, pos + 5)?;
+                let width = u16_be(data, pos + 7)?;
+                return Some((width, height));
+            }
+            _ => pos += 2 + u16_be(data, pos + 2)? as usize,
+        }
+    }
+}
+
+fn webp_dimensions(data: &[u8]) -> Option<(u32, u32)> {
+    match data.get(12..16)? {
+        b"VP8 " => Some((u16_le(data, 26)? & 0x3fff, u16_le(data, 28)? & 0x3fff)),
+        b"VP8L" => {
+            let bits = u32::from_le_bytes(data.get(21..25)?.try_into().ok()?);
+            Some(((bits & 0x3fff) + 1, ((bits >> 14) & 0x3fff) + 1))
+        }
+        b"VP8X" => Some((u24_le(data, 24)? + 1, u24_le(data, 27)? + 1)),
+        _ => None,
+    }
+}
+
+/// Recognizes common image, media and archive formats by their magic bytes.
+pub fn inspect(header: &[u8]) -> Option<MediaInfo> {
+    let image = |kind, dimensions| Some(MediaInfo { kind, dimensions });
+    let other = |kind| {
+        Some(MediaInfo {
+            kind,
+            dimensions: None,
+        })
+    };
+    let riff_type = header.get(8..12).filter(|_| header.starts_with(b"RIFF"));
+
+    if header.starts_with(b"\x89PNG\r\n\x1a\n") {
+        let dimensions = u32_be(header, 16).zip(u32_be(header, 20));
+        image("PNG image", dimensions)
+    } else if header.starts_with(b"GIF87a") || header.starts_with(b"GIF89a") {
+        image("GIF image", u16_le(header, 6).zip(u16_le(header, 8)))
+    } else if header.starts_with(b"\xff\xd8\xff") {
+        image("JPEG image", jpeg_dimensions(header))
+    } else if header.starts_with(b"BM") && header.len() >= 26 {
+        // the height is negative for images stored top-down
+        let dimensions = i32_le(header, 18)
+            .zip(i32_le(header, 22))
+            .map(|(width, height)| (width.unsigned_abs(), height.unsigned_abs()));
+        image("BMP image", dimensions)
+    } else if riff_type == Some(b"WEBP") {
+        image("WebP image", webp_dimensions(header))
+    } else if header.starts_with(b"\0\0\x01\0") && header.len() >= 8 {
+        // the size of the first icon, 0 stands for 256 pixels
+        let size = |byte: u8| if byte == 0 { 256 } else { byte as u32 };
+        image("ICO image", Some((size(header[6]), size(header[7]))))
+    } else if header.starts_with(b"II*\0") || header.starts_with(b"MM\0*") {
+        other("TIFF image")
+    } else if riff_type == Some(b"WAVE") {
+        other("WAV audio")
+    } else if riff_type == Some(b"AVI ") {
+        other("AVI video")
+    } else if header.starts_with(b"ID3") || header.starts_with(b"\xff\xfb") {
+        other("MP3 audio")
+    } else if header.starts_with(b"fLaC") {
+        other("FLAC audio")
+    } else if header.starts_with(b"OggS") {
+        other("Ogg media")
+    } else if header.starts_with(b"\x1a\x45\xdf\xa3") {
+        other("Matroska video")
+    } else if header.get(4..8) == Some(b"ftyp") {
+        match header.get(8..12) {
+            Some(b"qt  ") => other("QuickTime video"),
+            Some(b"M4A ") => other("MPEG-4 audio"),
+            Some(b"avif") => other("AVIF image"),
+            Some(b"heic") | Some(b"heix") => other("HEIF image"),
+            _ => other("MPEG-4 video"),
+        }
+    } else if header.starts_with(b"%PDF") {
+        other("PDF document")
+    } else if header.starts_with(b"PK\x03\x04") {
+        other("ZIP archive")
+    } else if header.starts_with(b"\x1f\x8b") {
+        other("gzip archive")
+    } else if header.starts_with(b"\xfd7zXZ\0") {
+        other("xz archive")
+    } else if header.starts_with(b"(\xb5/\xfd") {
+        other("zstd archive")
+    } else if header.starts_with(b"\x7fELF") {
+        other("ELF executable")
+    } else if header.starts_with(b"MZ") {
+        other("Windows executable")
+    } else if header.starts_with(b"\0asm") {
+        other("WebAssembly module")
+    } else if header.starts_with(b"SQLite format 3\0") {
+        other("SQLite database")
+    } else {
+        None
+    }
+}
+
+fn format_size(bytes: u64) -> String {
+    const UNITS: [&str; 4] = ["KiB", "MiB", "GiB", "TiB"];
+    if bytes < 1024 {
+        return format!("{} bytes", bytes);
+    }
+    let mut size = bytes as f64 / 1024.0;
+    let mut unit = 0;
+    while size >= 1024.0 && unit + 1 < UNITS.len() {
+        size /= 1024.0;
+        unit += 1;
+    }
+    format!("{:.1} {}", size, UNITS[unit])
+}
+
+/// Lines describing a binary file, shown instead of its contents in previews.
+pub fn describe(path: &Path) -> std::io::Result<Vec<String>> {
+    let file = File::open(path)?;
+    let size = file.metadata()?.len();
+    let mut header = Vec::new();
+    file.take(HEADER_LEN).read_to_end(&mut header)?;
+
+    let mut lines = Vec::new();
+    match inspect(&header) {
+        Some(info) => {
+            lines.push(format!("<{}>", info.kind));
+            if let Some((width, height)) = info.dimensions {
+                lines.push(format!("{} × {} pixels", width, height));
+            }
+        }
+        None => lines.push("<Binary file>".to_string()),
+    }
+    lines.push(format_size(size));
+    Ok(lines)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn image_dimensions() {
+        let mut png = b"\x89PNG\r\n\x1a\n\0\0\0\x0dIHDR".to_vec();
+        png.extend([0, 0, 2, 128, 0, 0, 1, 224]);
+        assert_eq!(inspect(&png).unwrap().dimensions, Some((640, 480)));
+
+        let gif = b"GIF89a\x40\x01\xf0\x00";
+        assert_eq!(inspect(gif).unwrap().dimensions, Some((320, 240)));
+
+        // APP0 segment followed by a baseline frame
+        let jpeg = [
+            0xff, 0xd8, 0xff, 0xe0, 0x00, 0x04, 0x00, 0x00, 0xff, 0xc0, 0x00, 0x11, 0x08, 0x00,
+            0x20, 0x00, 0x40,
+        ];
+        assert_eq!(inspect(&jpeg).unwrap().dimensions, Some((64, 32)));
+
+        let mut bmp = b"BM".to_vec();
+        bmp.resize(18, 0);
+        bmp.extend(16i32.to_le_bytes());
+        bmp.extend((-8i32).to_le_bytes());
+        assert_eq!(inspect(&bmp).unwrap().dimensions, Some((16, 8)));
+
+        let mut webp = b"RIFF\0\0\0\0WEBPVP8X".to_vec();
+        webp.resize(24, 0);
+        webp.extend([99, 0, 0, 49, 0, 0]);
+        assert_eq!(inspect(&webp).unwrap().dimensions, Some((100, 50)));
+    }
+
+    #[test]
+    fn media_kinds() {
+        assert_eq!(inspect(b"%PDF-1.7").unwrap().kind, "PDF document");
+        assert_eq!(inspect(b"\0\0\0\x20ftypisom").unwrap().kind, "MPEG-4 video");
+        assert_eq!(inspect(b"plain text"), None);
+        // truncated headers have no dimensions
+        assert_eq!(inspect(b"GIF89a").unwrap().dimensions, None);
+        assert_eq!(format_size(1536), "1.5 KiB");
+    }
+}
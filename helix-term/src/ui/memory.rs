@@ -0,0 +1,347 @@
+use crate::{
+    compositor::{Component, Compositor, Context, EventResult},
+    ctrl, key, shift,
+};
+use helix_dap::DisassembledInstruction;
+use helix_lsp::block_on;
+use helix_view::{
+    base64,
+    graphics::Rect,
+    input::{Event, KeyEvent},
+    Editor,
+};
+use tui::{
+    buffer::Buffer as Surface,
+    widgets::{Block, Borders, Widget},
+};
+
+const BYTES_PER_ROW: usize = 16;
+
+/// Reports an error unless the running debugger supports the `readMemory` request.
+pub fn can_read_memory(editor: &mut Editor) -> bool {
+    let supported = editor.debugger.as_ref().map(|debugger| {
+        debugger
+            .capabilities()
+            .supports_read_memory_request
+            .unwrap_or_default()
+    });
+    match supported {
+        Some(true) => true,
+        Some(false) => {
+            editor.set_error("Debugger does not support reading memory");
+            false
+        }
+        None => {
+            editor.set_error("Debugger is not running");
+            false
+        }
+    }
+}
+
+/// Reports an error unless the running debugger supports the `disassemble` request.
+pub fn can_disassemble(editor: &mut Editor) -> bool {
+    let supported = editor.debugger.as_ref().map(|debugger| {
+        debugger
+            .capabilities()
+            .supports_disassemble_request
+            .unwrap_or_default()
+    });
+    match supported {
+        Some(true) => true,
+        Some(false) => {
+            editor.set_error("Debugger does not support disassembling");
+            false
+        }
+        None => {
+            editor.set_error("Debugger is not running");
+            false
+        }
+    }
+}
+
+/// Closes the view or returns by how many rows the view should be scrolled.
+fn scroll_event(event: &Event, height: usize) -> Result<isize, EventResult> {
+    let key_event: KeyEvent = match event {
+        Event::Key(event) => *event,
+        Event::Resize(..) => return Err(EventResult::Consumed(None)),
+        _ => return Err(EventResult::Ignored(None)),
+    };
+    let page = height.max(1) as isize;
+    let rows = match key_event.into() {
+        key!(Esc) | key!('q') | ctrl!('c') => {
+            return Err(EventResult::Consumed(Some(Box::new(
+                |compositor: &mut Compositor, _| {
+                    compositor.pop();
+                },
+            ))))
+        }
+        key!('j') | key!(Down) => 1,
+        key!('k') | key!(Up) => -1,
+        ctrl!('d') => page / 2,
+        ctrl!('u') => -page / 2,
+        shift!('D') | key!(PageDown) => page,
+        shift!('U') | key!(PageUp) => -page,
+        _ => 0,
+    };
+    Ok(rows)
+}
+
+fn parse_address(address: &str) -> Option<u64> {
+    let address = address
+        .strip_prefix("0x")
+        .or_else(|| address.strip_prefix("0X"))?;
+    u64::from_str_radix(address, 16).ok()
+}
+
+fn render_frame(title: &str, area: Rect, surface: &mut Surface, cx: &mut Context) -> Rect {
+    let background = cx.editor.theme.get("ui.background");
+    surface.clear_with(area, background);
+
+    let block = Block::default().title(title).borders(Borders::ALL);
+    let inner = block.inner(area);
+    block.render(area, surface);
+    inner
+}
+
+/// A read-only hex dump of the debuggee's memory around a memory reference.
+pub struct MemoryView {
+    memory_reference: String,
+    /// Offset from the memory reference of the first shown row.
+    offset: isize,
+    height: usize,
+    /// The offset and byte count of the last read, to only read again when scrolling.
+    loaded: Option<(isize, usize)>,
+    address: Option<u64>,
+    data: Vec<u8>,
+    error: Option<String>,
+}
+
+impl MemoryView {
+    pub fn new(memory_reference: String) -> Self {
+        Self {
+            memory_reference,
+            offset: 0,
+            height: 0,
+            loaded: None,
+            address: None,
+            data: Vec::new(),
+            error: None,
+        }
+    }
+
+    fn load(&mut self, editor: &Editor, count: usize) {
+        if self.loaded == Some((self.offset, count)) {
+            return;
+        }
+        self.loaded = Some((self.offset, count));
+        self.data.clear();
+        self.address = None;
+
+        let debugger = match editor.debugger.as_ref() {
+            Some(debugger) => debugger,
+            None => {
+                self.error = Some("Debugger is not running".to_string());
+                return;
+            }
+        };
+        let request = debugger.read_memory(self.memory_reference.clone(), self.offset, count);
+        self.error = match block_on(request) {
+            Ok(Some(response)) => {
+                self.address = parse_address(&response.address);
+                match response.data.as_deref().map(base64::decode) {
+                    Some(Some(data)) => {
+                        self.data = data;
+                        None
+                    }
+                    Some(None) => Some("Debugger sent malformed memory contents".to_string()),
+                    None => None,
+                }
+            }
+            Ok(None) => None,
+            Err(e) => Some(format!("Failed to read memory: {}", e)),
+        };
+    }
+
+    fn row_label(&self, row: usize) -> String {
+        let offset = row * BYTES_PER_ROW;
+        match self.address {
+            Some(address) => format!("{:016x}", address.wrapping_add(offset as u64)),
+            None => {
+                let offset = self.offset + offset as isize;
+                let sign = if offset < 0 { '-' } else { '+' };
+                format!("{}{:015x}", sign, offset.unsigned_abs())
+            }
+        }
+    }
+}
+
+impl Component for MemoryView {
+    fn handle_event(&mut self, event: &Event, _cx: &mut Context) -> EventResult {
+        match scroll_event(event, self.height) {
+            Ok(rows) => {
+                self.offset += rows * BYTES_PER_ROW as isize;
+                EventResult::Consumed(None)
+            }
+            Err(result) => result,
+        }
+    }
+
+    fn render(&mut self, area: Rect, surface: &mut Surface, cx: &mut Context) {
+        let title = format!(" Memory at {} ", self.memory_reference);
+        let inner = render_frame(&title, area, surface, cx);
+        self.height = inner.height as usize;
+        self.load(cx.editor, self.height * BYTES_PER_ROW);
+
+        let theme = &cx.editor.theme;
+        if let Some(error) = &self.error {
+            let style = theme.get("error");
+            surface.set_stringn(inner.x, inner.y, error, inner.width as usize, style);
+            return;
+        }
+
+        let address_style = theme.get("ui.linenr");
+        let text_style = theme.get("ui.text");
+        let unreadable_style = theme.get("comment");
+        for row in 0..self.height {
+            let y = inner.y + row as u16;
+            let label = self.row_label(row);
+            let (x, _) =
+                surface.set_stringn(inner.x, y, &label, inner.width as usize, address_style);
+            let mut x = x + 2;
+
+            let start = row * BYTES_PER_ROW;
+            let bytes = self.data.get(start..).unwrap_or_default();
+            let bytes = &bytes[..bytes.len().min(BYTES_PER_ROW)];
+
+            let mut ascii = String::with_capacity(BYTES_PER_ROW);
+            for column in 0..BYTES_PER_ROW {
+                if column == BYTES_PER_ROW / 2 {
+                    x += 1;
+                }
+                let (hex, style) = match bytes.get(column) {
+                    Some(byte) => (format!("{:02x}", byte), text_style),
+                    None => ("??".to_string(), unreadable_style),
+                };
+                let width = inner.right().saturating_sub(x) as usize;
+                surface.set_stringn(x, y, hex, width, style);
+                x += 3;
+
+                ascii.push(match bytes.get(column) {
+                    Some(byte) if byte.is_ascii_graphic() || *byte == b' ' => *byte as char,
+                    Some(_) => '.',
+                    None => ' ',
+                });
+            }
+            let width = inner.right().saturating_sub(x + 1) as usize;
+            surface.set_stringn(x + 1, y, ascii, width, text_style);
+        }
+    }
+}
+
+/// A read-only disassembly around an instruction pointer, highlighting the instruction the
+/// reference points at.
+pub struct DisassemblyView {
+    memory_reference: String,
+    /// Offset in instructions from the memory reference of the first shown row.
+    offset: isize,
+    height: usize,
+    /// Whether the current instruction was centered after the first render.
+    centered: bool,
+    loaded: Option<(isize, usize)>,
+    instructions: Vec<DisassembledInstruction>,
+    error: Option<String>,
+}
+
+impl DisassemblyView {
+    pub fn new(memory_reference: String) -> Self {
+        Self {
+            memory_reference,
+            offset: 0,
+            height: 0,
+            centered: false,
+            loaded: None,
+            instructions: Vec::new(),
+            error: None,
+        }
+    }
+
+    fn load(&mut self, editor: &Editor, count: usize) {
+        if self.loaded == Some((self.offset, count)) {
+            return;
+        }
+        self.loaded = Some((self.offset, count));
+
+        let debugger = match editor.debugger.as_ref() {
+            Some(debugger) => debugger,
+            None => {
+                self.instructions.clear();
+                self.error = Some("Debugger is not running".to_string());
+                return;
+            }
+        };
+        let request = debugger.disassemble(self.memory_reference.clone(), self.offset, count);
+        match block_on(request) {
+            Ok(instructions) => {
+                self.instructions = instructions;
+                self.error = None;
+            }
+            Err(e) => {
+                self.instructions.clear();
+                self.error = Some(format!("Failed to disassemble: {}", e));
+            }
+        }
+    }
+}
+
+impl Component for DisassemblyView {
+    fn handle_event(&mut self, event: &Event, _cx: &mut Context) -> EventResult {
+        match scroll_event(event, self.height) {
+            Ok(rows) => {
+                self.offset += rows;
+                EventResult::Consumed(None)
+            }
+            Err(result) => result,
+        }
+    }
+
+    fn render(&mut self, area: Rect, surface: &mut Surface, cx: &mut Context) {
+        let title = format!(" Disassembly at {} ", self.memory_reference);
+        let inner = render_frame(&title, area, surface, cx);
+        self.height = inner.height as usize;
+        if !self.centered {
+            self.offset = -(self.height as isize / 2);
+            self.centered = true;
+        }
+        self.load(cx.editor, self.height);
+
+        let theme = &cx.editor.theme;
+        if let Some(error) = &self.error {
+            let style = theme.get("error");
+            surface.set_stringn(inner.x, inner.y, error, inner.width as usize, style);
+            return;
+        }
+
+        let address_style = theme.get("ui.linenr");
+        let text_style = theme.get("ui.text");
+        let symbol_style = theme.get("comment");
+        let current_style = theme.get("ui.cursorline.primary");
+        let current = parse_address(&self.memory_reference);
+        for (row, instruction) in self.instructions.iter().enumerate() {
+            let y = inner.y + row as u16;
+            let is_current = current.is_some() && parse_address(&instruction.address) == current;
+            if is_current {
+                surface.set_style(Rect::new(inner.x, y, inner.width, 1), current_style);
+            }
+
+            let marker = if is_current { "→ " } else { "  " };
+            let line = format!("{}{}  ", marker, instruction.address);
+            let (x, _) = surface.set_stringn(inner.x, y, line, inner.width as usize, address_style);
+            let width = inner.right().saturating_sub(x) as usize;
+            let (x, _) = surface.set_stringn(x, y, &instruction.instruction, width, text_style);
+            if let Some(symbol) = &instruction.symbol {
+                let width = inner.right().saturating_sub(x) as usize;
+                surface.set_stringn(x, y, format!("  ; {}", symbol), width, symbol_style);
+            }
+        }
+    }
+}
@@ -0,0 +1,148 @@
+use helix_core::RopeSlice;
+use helix_view::{graphics::Rect, Document, DocumentId, Editor, View};
+use tui::buffer::Buffer as Surface;
+
+/// Characters of a line shown by one cell of the minimap.
+const CHARS_PER_CELL: usize = 4;
+
+/// A compressed overview of the focused document, shown on the right of the editor. Each cell
+/// covers two lines and [`CHARS_PER_CELL`] characters and shows which of the two lines have
+/// text there.
+///
+/// The overview is only rebuilt on idle, until then an edited document is shown as it was.
+#[derive(Default)]
+pub struct Minimap {
+    /// The document, its version and the width the rows were built for.
+    source: Option<(DocumentId, i32, u16)>,
+    rows: Vec<String>,
+    /// The area the minimap was last rendered in and its first row.
+    area: Rect,
+    top: usize,
+}
+
+impl Minimap {
+    fn is_stale(&self, doc: &Document, width: u16) -> bool {
+        self.source != Some((doc.id(), doc.version(), width))
+    }
+
+    fn build(&mut self, doc: &Document, width: u16) {
+        self.rows = build_rows(doc.text().slice(..), width as usize);
+        self.source = Some((doc.id(), doc.version(), width));
+    }
+
+    /// Rebuilds the overview of the focused document if it changed since the minimap was last
+    /// rendered. Returns whether it did.
+    pub fn refresh(&mut self, editor: &Editor) -> bool {
+        let view = editor.tree.get(editor.tree.focus);
+        let doc = &editor.documents[&view.doc];
+        let width = self.area.width;
+        if width == 0 || !self.is_stale(doc, width) {
+            return false;
+        }
+        self.build(doc, width);
+        true
+    }
+
+    pub fn render(&mut self, area: Rect, surface: &mut Surface, editor: &Editor) {
+        let view = editor.tree.get(editor.tree.focus);
+        let doc = &editor.documents[&view.doc];
+        // another document is shown right away, edits wait for idle
+        if !matches!(self.source, Some((id, _, width)) if id == doc.id() && width == area.width) {
+            self.build(doc, area.width);
+        }
+        self.area = area;
+
+        let theme = &editor.theme;
+        let style = theme
+            .try_get("ui.minimap")
+            .unwrap_or_else(|| theme.get("comment"));
+        let viewport_style = style.patch(
+            theme
+                .try_get("ui.minimap.viewport")
+                .unwrap_or_else(|| theme.get("ui.selection")),
+        );
+        surface.set_style(area, style);
+
+        let height = area.height as usize;
+        let (first, count) = viewport_rows(view);
+        self.top = if self.rows.len() <= height {
+            0
+        } else {
+            // scroll along with the view, reaching the end together
+            let max_top = self.rows.len() - height;
+            let max_first = self.rows.len().saturating_sub(count).max(1);
+            (first * max_top / max_first).min(max_top)
+        };
+        for (i, row) in self.rows.iter().skip(self.top).take(height).enumerate() {
+            let index = self.top + i;
+            let style = if index >= first && index < first + count {
+                viewport_style
+            } else {
+                style
+            };
+            let row_area = Rect::new(area.x, area.y + i as u16, area.width, 1);
+            surface.set_style(row_area, style);
+            surface.set_stringn(area.x, row_area.y, row, area.width as usize, style);
+        }
+    }
+
+    /// The first line shown by the minimap row at screen row `row`, if the minimap was rendered
+    /// there.
+    pub fn line_at(&self, row: u16, column: u16) -> Option<usize> {
+        let area = self.area;
+        if area.width == 0
+            || row < area.top()
+            || row >= area.bottom()
+            || column < area.left()
+            || column >= area.right()
+        {
+            return None;
+        }
+        let index = self.top + (row - area.y) as usize;
+        (index < self.rows.len()).then(|| index * 2)
+    }
+}
+
+/// The first minimap row of the lines in view and how many rows they cover.
+fn viewport_rows(view: &View) -> (usize, usize) {
+    let first = view.offset.row / 2;
+    let last = (view.offset.row + view.inner_height()).saturating_sub(1) / 2;
+    (first, last + 1 - first)
+}
+
+fn build_rows(text: RopeSlice, width: usize) -> Vec<String> {
+    // which cells of a line contain text
+    let cells = |line: usize| -> Vec<bool> {
+        let mut cells = vec![false; width];
+        if line >= text.len_lines() {
+            return cells;
+        }
+        for (i, ch) in text.line(line).chars().enumerate() {
+            let cell = i / CHARS_PER_CELL;
+            if cell >= width {
+                break;
+            }
+            if !ch.is_whitespace() {
+                cells[cell] = true;
+            }
+        }
+        cells
+    };
+
+    (0..text.len_lines())
+        .step_by(2)
+        .map(|line| {
+            let top = cells(line);
+            let bottom = cells(line + 1);
+            top.into_iter()
+                .zip(bottom)
+                .map(|cell| match cell {
+                    (true, true) => '█',
+                    (true, false) => '▀',
+                    (false, true) => '▄',
+                    (false, false) => ' ',
+                })
+                .collect()
+        })
+        .collect()
+}
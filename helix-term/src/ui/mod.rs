@@ -1,37 +1,104 @@
+mod breakpoints;
+mod buffers;
+mod call_hierarchy;
+mod call_stack;
+mod command_history;
 mod completion;
+mod data_tree;
+mod debug_console;
+mod diagnostics;
+mod diff_view;
+mod dir_diff;
+mod edit_queue;
 pub(crate) mod editor;
 mod explore;
 mod fuzzy_match;
+mod git_status;
+mod grammars;
+mod hex_view;
 mod info;
+mod jumplist;
+mod log_view;
 pub mod lsp;
 mod markdown;
+mod media;
+pub mod memory;
 pub mod menu;
+mod minimap;
+mod notes;
+mod outline;
 pub mod overlay;
 mod picker;
+mod pins;
 pub mod popup;
 mod prompt;
 mod spinner;
 mod statusline;
+mod symbol_tree;
+mod syntax_metrics;
+mod syntax_tree;
+mod table_view;
 mod text;
 mod tree;
+mod tree_picker;
+mod type_hierarchy;
+mod undo_tree;
+mod variables;
+mod watches;
+mod yank_history;
 
 use crate::compositor::{Component, Compositor};
 use crate::job::{self, Callback};
+pub use breakpoints::BreakpointsTree;
+pub use buffers::BuffersTree;
+pub use call_hierarchy::{CallDirection, CallHierarchy};
+pub use call_stack::CallStackTree;
+pub use command_history::CommandHistory;
 pub use completion::Completion;
+pub use data_tree::DataTree;
+pub use debug_console::DebugConsole;
+pub use diagnostics::DiagnosticsTree;
+pub use diff_view::DiffView;
+pub use dir_diff::{DiffSource, DirDiff};
+pub use edit_queue::EditQueue;
 pub use editor::EditorView;
 pub use explore::Explorer;
+pub use git_status::GitStatus;
+pub use grammars::{GrammarJob, GrammarsTree};
+pub use hex_view::HexView;
+pub use jumplist::JumplistTree;
+pub use log_view::LogView;
 pub use markdown::Markdown;
+pub use memory::{DisassemblyView, MemoryView};
 pub use menu::Menu;
-pub use picker::{FileLocation, FilePicker, Picker};
+pub use minimap::Minimap;
+pub use notes::NotesTree;
+pub use outline::Outline;
+pub use picker::{render_document_preview, FileLocation, FilePicker, Picker};
+pub use pins::PinsView;
 pub use popup::Popup;
 pub use prompt::{Prompt, PromptEvent};
 pub use spinner::{ProgressSpinners, Spinner};
+pub use symbol_tree::{SymbolTree, WorkspaceSymbolTree};
+pub use syntax_metrics::SyntaxMetricsTree;
+pub use syntax_tree::SyntaxTreeView;
+pub use table_view::TableView;
 pub use text::Text;
-pub use tree::{Tree, TreeItem, TreeOp};
+pub use tree::{MouseAction, Tree, TreeItem, TreeKey, TreeOp};
+pub use tree_picker::{TreePicker, TreePickerNode};
+pub use type_hierarchy::{TypeDirection, TypeHierarchy};
+pub use undo_tree::UndoTree;
+pub use variables::VariablesTree;
+pub use watches::WatchesTree;
+pub use yank_history::YankHistoryView;
 
 use helix_core::regex::Regex;
 use helix_core::regex::RegexBuilder;
-use helix_view::Editor;
+use helix_view::{
+    graphics::{Rect, Style},
+    Editor, Theme,
+};
+use tui::buffer::Buffer as Surface;
 
 use std::path::PathBuf;
 
@@ -61,12 +128,54 @@ pub fn prompt_with_input(
     cx.push_layer(Box::new(prompt));
 }
 
+/// Builds the regex for a search pattern. With smart case the search ignores case unless the
+/// pattern contains upper case characters.
+pub fn search_regex(
+    pattern: &str,
+    smart_case: bool,
+    whole_word: bool,
+) -> Result<Regex, helix_core::regex::Error> {
+    let case_insensitive = smart_case && !pattern.chars().any(char::is_uppercase);
+    let pattern = if whole_word {
+        std::borrow::Cow::Owned(format!(r"\b(?:{})\b", pattern))
+    } else {
+        std::borrow::Cow::Borrowed(pattern)
+    };
+    RegexBuilder::new(&pattern)
+        .case_insensitive(case_insensitive)
+        .multi_line(true)
+        .build()
+}
+
 pub fn regex_prompt(
     cx: &mut crate::commands::Context,
     prompt: std::borrow::Cow<'static, str>,
     history_register: Option<char>,
     completion_fn: impl FnMut(&Editor, &str) -> Vec<prompt::Completion> + 'static,
     fun: impl Fn(&mut Editor, Regex, PromptEvent) + 'static,
+) {
+    regex_prompt_impl(cx, prompt, history_register, completion_fn, fun, false)
+}
+
+/// Like [`regex_prompt`], but matches whole words when that search option is enabled and lets
+/// the search options be toggled from the prompt.
+pub fn search_prompt(
+    cx: &mut crate::commands::Context,
+    prompt: std::borrow::Cow<'static, str>,
+    history_register: Option<char>,
+    completion_fn: impl FnMut(&Editor, &str) -> Vec<prompt::Completion> + 'static,
+    fun: impl Fn(&mut Editor, Regex, PromptEvent) + 'static,
+) {
+    regex_prompt_impl(cx, prompt, history_register, completion_fn, fun, true)
+}
+
+fn regex_prompt_impl(
+    cx: &mut crate::commands::Context,
+    prompt: std::borrow::Cow<'static, str>,
+    history_register: Option<char>,
+    completion_fn: impl FnMut(&Editor, &str) -> Vec<prompt::Completion> + 'static,
+    fun: impl Fn(&mut Editor, Regex, PromptEvent) + 'static,
+    search: bool,
 ) {
     let (view, doc) = current!(cx.editor);
     let doc_id = view.doc;
@@ -88,20 +197,15 @@ pub fn regex_prompt(
                 PromptEvent::Update | PromptEvent::Validate => {
                     // skip empty input
                     if input.is_empty() {
+                        if search {
+                            cx.editor.search_matches = None;
+                        }
                         return;
                     }
 
-                    let case_insensitive = if config.search.smart_case {
-                        !input.chars().any(char::is_uppercase)
-                    } else {
-                        false
-                    };
-
-                    match RegexBuilder::new(input)
-                        .case_insensitive(case_insensitive)
-                        .multi_line(true)
-                        .build()
-                    {
+                    let options = cx.editor.search_options;
+                    let whole_word = search && options.whole_word;
+                    match search_regex(input, options.smart_case, whole_word) {
                         Ok(regex) => {
                             let (view, doc) = current!(cx.editor);
 
@@ -119,6 +223,9 @@ pub fn regex_prompt(
                             view.ensure_cursor_in_view(doc, config.scrolloff);
                         }
                         Err(err) => {
+                            if search {
+                                cx.editor.search_matches = None;
+                            }
                             let (view, doc) = current!(cx.editor);
                             doc.set_selection(view.id, snapshot.clone());
                             view.offset = offset_snapshot;
@@ -154,12 +261,31 @@ pub fn regex_prompt(
             }
         },
     );
+    if search {
+        prompt = prompt.with_search_options(cx.editor);
+    }
     // Calculate initial completion
     prompt.recalculate_completion(cx.editor);
     // prompt
     cx.push_layer(Box::new(prompt));
 }
 
+/// Patches `area` with the `ui.inactive` style of the theme, for the views and panels which don't
+/// receive the input. Nothing is dimmed unless the theme sets the scope.
+pub fn dim_inactive(area: Rect, surface: &mut Surface, theme: &Theme) {
+    if let Some(style) = theme.try_get_exact("ui.inactive") {
+        surface.set_style(area, style);
+    }
+}
+
+/// The style of the border of a panel, `ui.window.focus` while it receives the input.
+pub fn border_style(theme: &Theme, focused: bool) -> Style {
+    match focused {
+        true => theme.try_get_exact("ui.window.focus").unwrap_or_default(),
+        false => Style::default(),
+    }
+}
+
 pub fn file_picker(root: PathBuf, config: &helix_view::editor::Config) -> FilePicker<PathBuf> {
     use ignore::{types::TypesBuilder, WalkBuilder};
     use std::time::Instant;
@@ -221,6 +347,7 @@ pub fn file_picker(root: PathBuf, config: &helix_view::editor::Config) -> FilePi
 
     log::debug!("file_picker init {:?}", Instant::now().duration_since(now));
 
+    let query_filter = file_query_filter(root.clone());
     FilePicker::new(
         files,
         root,
@@ -236,6 +363,76 @@ pub fn file_picker(root: PathBuf, config: &helix_view::editor::Config) -> FilePi
         },
         |_editor, path| Some((path.clone().into(), None)),
     )
+    .with_query_filter(query_filter)
+}
+
+/// Splits the terms restricting the files of the file picker off its query: `ext:rs,toml` keeps
+/// the files with one of the extensions, `in:src/ui` the files below the directory and
+/// `is:modified` the files git reports as changed or untracked.
+fn file_query_filter(root: PathBuf) -> picker::QueryFilter<PathBuf> {
+    use std::{cell::RefCell, collections::HashSet, rc::Rc};
+
+    // git is only asked for the modified files once they are filtered by
+    let modified: RefCell<Option<Rc<HashSet<PathBuf>>>> = RefCell::default();
+    Box::new(move |query| {
+        let mut extensions = Vec::new();
+        let mut dirs = Vec::new();
+        let mut only_modified = false;
+        let mut rest = Vec::new();
+        for term in query.split_whitespace() {
+            if let Some(list) = term.strip_prefix("ext:") {
+                extensions.extend(
+                    list.split(',')
+                        .map(|extension| extension.trim_start_matches('.'))
+                        .filter(|extension| !extension.is_empty())
+                        .map(String::from),
+                );
+            } else if let Some(dir) = term.strip_prefix("in:") {
+                dirs.push(root.join(dir));
+            } else if term == "is:modified" {
+                only_modified = true;
+            } else {
+                rest.push(term);
+            }
+        }
+        if extensions.is_empty() && dirs.is_empty() && !only_modified {
+            return (query.to_string(), None);
+        }
+
+        let modified = only_modified.then(|| {
+            modified
+                .borrow_mut()
+                .get_or_insert_with(|| Rc::new(modified_files(&root)))
+                .clone()
+        });
+        let predicate = move |path: &PathBuf| {
+            let extension = path.extension().and_then(|extension| extension.to_str());
+            (extensions.is_empty()
+                || extension.map_or(false, |ext| extensions.iter().any(|e| e == ext)))
+                && (dirs.is_empty() || dirs.iter().any(|dir| path.starts_with(dir)))
+                && modified
+                    .as_ref()
+                    .map_or(true, |modified| modified.contains(path))
+        };
+        let predicate: Box<dyn Fn(&PathBuf) -> bool> = Box::new(predicate);
+        (rest.join(" "), Some(predicate))
+    })
+}
+
+/// The files below `root` which git reports as changed against `HEAD` or untracked.
+fn modified_files(root: &std::path::Path) -> std::collections::HashSet<PathBuf> {
+    let changed = dir_diff::git(root, &["diff", "--name-only", "-z", "--relative", "HEAD"]);
+    let untracked = dir_diff::git(root, &["ls-files", "-z", "--others", "--exclude-standard"]);
+    let mut files = std::collections::HashSet::new();
+    for output in [changed, untracked] {
+        match output {
+            Ok(output) => {
+                files.extend(dir_diff::nul_separated(&output).map(|path| root.join(path)))
+            }
+            Err(err) => log::warn!("failed to list modified files: {}", err),
+        }
+    }
+    files
 }
 
 pub mod completers {
@@ -244,7 +441,7 @@ pub mod completers {
     use fuzzy_matcher::FuzzyMatcher;
     use helix_view::document::SCRATCH_BUFFER_NAME;
     use helix_view::theme;
-    use helix_view::{editor::Config, Editor};
+    use helix_view::{editor::Config, view::LocalOptions, Editor};
     use once_cell::sync::Lazy;
     use std::borrow::Cow;
     use std::cmp::Reverse;
@@ -283,13 +480,33 @@ pub fn buffer(editor: &Editor, input: &str) -> Vec<Completion> {
         names
     }
 
-    pub fn theme(_editor: &Editor, input: &str) -> Vec<Completion> {
+    pub fn layout(editor: &Editor, input: &str) -> Vec<Completion> {
+        let matcher = Matcher::default();
+
+        let config = editor.config();
+        let mut matches: Vec<_> = config
+            .layouts
+            .keys()
+            .filter_map(|name| matcher.fuzzy_match(name, input).map(|score| (name, score)))
+            .collect();
+
+        matches.sort_unstable_by_key(|(_file, score)| Reverse(*score));
+        matches
+            .into_iter()
+            .map(|(name, _)| ((0..), Cow::from(name.clone())))
+            .collect()
+    }
+
+    pub fn theme(editor: &Editor, input: &str) -> Vec<Completion> {
         let mut names = theme::Loader::read_names(&helix_loader::runtime_dir().join("themes"));
         names.extend(theme::Loader::read_names(
             &helix_loader::config_dir().join("themes"),
         ));
         names.push("default".into());
         names.push("base16_default".into());
+        if editor.theme_loader.terminal_colors().is_some() {
+            names.push("adaptive".into());
+        }
         names.sort();
         names.dedup();
 
@@ -353,6 +570,23 @@ pub fn setting(_editor: &Editor, input: &str) -> Vec<Completion> {
             .collect()
     }
 
+    pub fn local_setting(_editor: &Editor, input: &str) -> Vec<Completion> {
+        let matcher = Matcher::default();
+
+        // `indent` is set on the document rather than on the view
+        let mut matches: Vec<_> = LocalOptions::KEYS
+            .iter()
+            .chain(&["indent"])
+            .filter_map(|name| matcher.fuzzy_match(name, input).map(|score| (name, score)))
+            .collect();
+
+        matches.sort_unstable_by_key(|(_file, score)| Reverse(*score));
+        matches
+            .into_iter()
+            .map(|(name, _)| ((0..), Cow::from(*name)))
+            .collect()
+    }
+
     pub fn filename(editor: &Editor, input: &str) -> Vec<Completion> {
         filename_impl(editor, input, |entry| {
             let is_dir = entry.file_type().map_or(false, |entry| entry.is_dir());
@@ -399,23 +633,15 @@ pub fn lsp_workspace_command(editor: &Editor, input: &str) -> Vec<Completion> {
 
         let (_, doc) = current_ref!(editor);
 
-        let language_server = match doc.language_server() {
-            Some(language_server) => language_server,
-            None => {
-                return vec![];
-            }
-        };
-
-        let options = match &language_server.capabilities().execute_command_provider {
-            Some(options) => options,
-            None => {
-                return vec![];
-            }
-        };
-
-        let mut matches: Vec<_> = options
-            .commands
-            .iter()
+        let mut matches: Vec<_> = doc
+            .language_servers()
+            .filter_map(|language_server| {
+                language_server
+                    .capabilities()
+                    .execute_command_provider
+                    .as_ref()
+            })
+            .flat_map(|options| options.commands.iter())
             .filter_map(|command| {
                 matcher
                     .fuzzy_match(command, input)
@@ -0,0 +1,197 @@
+use super::{tree::patch_selected, MouseAction, Tree, TreeItem};
+use crate::{
+    compositor::{Component, Compositor, Context, EventResult},
+    ctrl, key,
+};
+use helix_core::{path::get_relative_path, Position, Selection};
+use helix_view::{
+    align_view,
+    editor::Action,
+    graphics::{CursorKind, Rect},
+    input::Event,
+    Align, Editor,
+};
+use std::{cmp::Ordering, path::PathBuf};
+use tui::{
+    buffer::Buffer as Surface,
+    text::{Span, Spans},
+    widgets::{Block, Borders, Widget},
+};
+
+#[derive(Debug, Clone)]
+struct NoteItem {
+    path: PathBuf,
+    /// The line and text of a note, `None` for the file grouping its notes.
+    note: Option<(usize, String)>,
+}
+
+impl TreeItem for NoteItem {
+    type Params = ();
+
+    fn text(&self, cx: &mut Context, selected: bool, _params: &mut ()) -> Spans {
+        let theme = &cx.editor.theme;
+        let mut spans = match &self.note {
+            Some((line, text)) => vec![
+                Span::styled(format!("{}: ", line + 1), theme.get("ui.linenr")),
+                Span::styled(text.clone(), theme.get("ui.text")),
+            ],
+            None => vec![Span::styled(
+                get_relative_path(&self.path).to_string_lossy().into_owned(),
+                theme.get("ui.text.directory"),
+            )],
+        };
+        if selected {
+            patch_selected(cx, &mut spans);
+        }
+        Spans::from(spans)
+    }
+
+    fn is_child(&self, other: &Self) -> bool {
+        self.note.is_some() && other.note.is_none() && self.path == other.path
+    }
+
+    fn cmp(&self, other: &Self) -> Ordering {
+        let line = |item: &Self| item.note.as_ref().map(|(line, _)| *line);
+        (&self.path, line(self)).cmp(&(&other.path, line(other)))
+    }
+}
+
+fn note_items(editor: &Editor) -> Vec<NoteItem> {
+    let mut items = Vec::new();
+    for (path, notes) in editor.project_notes() {
+        items.push(NoteItem {
+            path: path.clone(),
+            note: None,
+        });
+        items.extend(notes.into_iter().map(|note| NoteItem {
+            path: path.clone(),
+            note: Some((note.line, note.text)),
+        }));
+    }
+    items
+}
+
+/// The notes of the project, grouped by file.
+pub struct NotesTree {
+    tree: Tree<NoteItem>,
+}
+
+impl NotesTree {
+    /// Returns `None` if the project has no notes.
+    pub fn new(editor: &Editor) -> Option<Self> {
+        let items = note_items(editor);
+        if items.is_empty() {
+            return None;
+        }
+        Some(Self {
+            tree: Tree::build_tree(items),
+        })
+    }
+
+    fn close() -> EventResult {
+        EventResult::Consumed(Some(Box::new(|compositor: &mut Compositor, _| {
+            compositor.pop();
+        })))
+    }
+
+    fn jump(&mut self, cx: &mut Context) -> EventResult {
+        let item = self.tree.current_item();
+        let line = match &item.note {
+            Some((line, _)) => *line,
+            None => {
+                self.tree.on_enter(cx, &mut ());
+                return EventResult::Consumed(None);
+            }
+        };
+        let path = item.path.clone();
+        EventResult::Consumed(Some(Box::new(move |compositor: &mut Compositor, cx| {
+            compositor.pop();
+            if let Err(e) = cx.editor.open(&path, Action::Replace) {
+                cx.editor
+                    .set_error(format!("Failed to open {}: {}", path.display(), e));
+                return;
+            }
+            let (view, doc) = current!(cx.editor);
+            let text = doc.text();
+            let pos = text.line_to_char(line.min(text.len_lines().saturating_sub(1)));
+            doc.set_selection(view.id, Selection::point(pos));
+            align_view(doc, view, Align::Center);
+        })))
+    }
+
+    fn remove(&mut self, editor: &mut Editor) -> EventResult {
+        let item = self.tree.current_item();
+        let line = match &item.note {
+            Some((line, _)) => *line,
+            None => return EventResult::Consumed(None),
+        };
+        if let Err(e) = editor.set_note(&item.path.clone(), line, None) {
+            editor.set_error(format!("Failed to save notes: {}", e));
+        }
+        let items = note_items(editor);
+        if items.is_empty() {
+            return Self::close();
+        }
+        let selected = self.tree.selected();
+        self.tree.replace_with_new_items(items);
+        self.tree.move_down(selected.saturating_sub(1));
+        EventResult::Consumed(None)
+    }
+}
+
+impl Component for NotesTree {
+    fn handle_event(&mut self, event: &Event, cx: &mut Context) -> EventResult {
+        let key_event = match event {
+            Event::Key(event) => *event,
+            Event::Mouse(event) => match self.tree.handle_mouse(event, cx, &mut ()) {
+                MouseAction::Activate => key!(Enter),
+                MouseAction::Handled => return EventResult::Consumed(None),
+                MouseAction::Ignored => return EventResult::Ignored(None),
+            },
+            Event::Resize(..) => return EventResult::Consumed(None),
+            _ => return EventResult::Ignored(None),
+        };
+
+        match key_event.into() {
+            key!(Esc) | key!('q') | ctrl!('c') => return Self::close(),
+            key!(Enter) => return self.jump(cx),
+            key!('d') => return self.remove(cx.editor),
+            _ => {
+                self.tree.handle_event(Event::Key(key_event), cx, &mut ());
+            }
+        }
+        EventResult::Consumed(None)
+    }
+
+    fn render(&mut self, area: Rect, surface: &mut Surface, cx: &mut Context) {
+        let background = cx.editor.theme.get("ui.background");
+        surface.clear_with(area, background);
+
+        let block = Block::default()
+            .title(" Notes ")
+            .borders(Borders::ALL)
+            .border_style(super::border_style(&cx.editor.theme, cx.focus));
+        let inner = block.inner(area);
+        block.render(area, surface);
+
+        let tree_area = inner.clip_bottom(1);
+        self.tree.render(tree_area, surface, cx, &mut ());
+
+        let footer_area = inner.clip_top(tree_area.height);
+        surface.set_stringn(
+            footer_area.x,
+            footer_area.y,
+            "[enter]jump [o]fold [d]elete",
+            footer_area.width as usize,
+            cx.editor.theme.get("comment"),
+        );
+    }
+
+    fn cursor(&self, _area: Rect, _editor: &Editor) -> (Option<Position>, CursorKind) {
+        (None, CursorKind::Hidden)
+    }
+
+    fn is_panel(&self) -> bool {
+        true
+    }
+}
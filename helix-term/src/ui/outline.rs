@@ -0,0 +1,320 @@
+use super::{
+    symbol_tree::{contains, peek_back, symbol_items, SymbolItem},
+    MouseAction, Tree, TreeItem, TreeKey,
+};
+use crate::{
+    compositor::{Component, Compositor, Context, EventResult},
+    ctrl,
+    job::{Callback, Jobs},
+    key, ui,
+};
+use helix_core::{
+    outline::{self, Heading, OutlineSyntax},
+    Position, Selection,
+};
+use helix_lsp::{
+    lsp,
+    util::{lsp_range_to_range, pos_to_lsp_pos},
+    OffsetEncoding,
+};
+use helix_view::{
+    align_view,
+    graphics::{CursorKind, Rect},
+    input::Event,
+    tree_keymap::TreeAction,
+    Align, DocumentId, Editor,
+};
+use tui::{
+    buffer::Buffer as Surface,
+    widgets::{Block, Borders, Widget},
+};
+
+/// A document version the symbols were requested for. Saving changes whether the document is
+/// modified, so servers which only update their symbols on save are asked again.
+type Source = (DocumentId, i32, bool);
+
+/// The headings of a markup document as nested symbols. The ranges span whole lines, so they are
+/// the same in every offset encoding.
+fn heading_symbols(headings: &[Heading]) -> Vec<lsp::DocumentSymbol> {
+    let position = |line: usize| lsp::Position::new(line as u32, 0);
+    let mut symbols = Vec::new();
+    let mut rest = headings;
+    while let Some((heading, tail)) = rest.split_first() {
+        let subheadings = tail.iter().take_while(|sub| sub.line < heading.end).count();
+        #[allow(deprecated)]
+        symbols.push(lsp::DocumentSymbol {
+            name: heading.title.clone(),
+            detail: None,
+            kind: lsp::SymbolKind::STRING,
+            tags: None,
+            deprecated: None,
+            range: lsp::Range::new(position(heading.line), position(heading.end)),
+            selection_range: lsp::Range::new(position(heading.line), position(heading.line)),
+            children: Some(heading_symbols(&tail[..subheadings])),
+        });
+        rest = &tail[subheadings..];
+    }
+    symbols
+}
+
+/// A panel docked to the right of the editor with the symbols of the focused document. The
+/// symbols are requested again whenever the document changes or is saved, and the symbol
+/// containing the cursor is selected while the panel isn't focused. Markup documents show their
+/// headings instead, which are read from the document itself.
+pub struct Outline {
+    tree: Tree<SymbolItem>,
+    focus: bool,
+    /// The document version the shown symbols are for.
+    source: Option<Source>,
+    /// The document version symbols were last requested for.
+    requested: Option<Source>,
+    offset_encoding: OffsetEncoding,
+}
+
+impl Default for Outline {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Outline {
+    pub fn new() -> Self {
+        Self {
+            tree: Tree::build_tree(Vec::new()),
+            focus: false,
+            source: None,
+            requested: None,
+            // replaced by the encoding of the server which sends symbols
+            offset_encoding: OffsetEncoding::Utf8,
+        }
+    }
+
+    pub fn focus(&mut self) {
+        self.focus = true;
+    }
+
+    pub fn unfocus(&mut self) {
+        self.focus = false;
+    }
+
+    pub fn is_focus(&self) -> bool {
+        self.focus
+    }
+
+    /// The kinds and names of the symbols, with the node ids [`Outline::reveal`] takes.
+    pub fn search_entries(&self) -> Vec<(String, u64)> {
+        self.tree
+            .all_items()
+            .into_iter()
+            .map(|item| (item.label(), item.node_id().unwrap_or_default()))
+            .collect()
+    }
+
+    /// Selects the symbol with the node id `id`, unfolding its parents.
+    pub fn reveal(&mut self, id: u64) -> bool {
+        self.tree.reveal(|item| item.node_id() == Some(id))
+    }
+
+    /// Requests the symbols of the focused document unless they were already requested for its
+    /// current version. The response is handed to the outline of the `EditorView`.
+    pub fn refresh(&mut self, editor: &Editor, jobs: &mut Jobs) {
+        let doc = doc!(editor);
+        let source = (doc.id(), doc.version(), doc.is_modified());
+        if self.requested == Some(source) {
+            return;
+        }
+        self.requested = Some(source);
+
+        if let Some(syntax) = doc
+            .language_name()
+            .and_then(OutlineSyntax::from_language_name)
+        {
+            let headings = outline::headings(doc.text().slice(..), syntax);
+            let symbols = lsp::DocumentSymbolResponse::Nested(heading_symbols(&headings));
+            self.set_symbols(source, Some((symbols, OffsetEncoding::Utf8)));
+            return;
+        }
+
+        let request = doc.language_servers().find_map(|language_server| {
+            language_server
+                .document_symbols(doc.identifier())
+                .map(|future| (language_server.offset_encoding(), future))
+        });
+        let (offset_encoding, future) = match request {
+            Some(request) => request,
+            None => {
+                self.set_symbols(source, None);
+                // asked again on the next render, as the language server may still be starting,
+                // e.g. for an outline opened at startup
+                self.requested = None;
+                return;
+            }
+        };
+        jobs.callback(async move {
+            let response: Option<lsp::DocumentSymbolResponse> =
+                serde_json::from_value(future.await?)?;
+            let symbols = response.map(|symbols| (symbols, offset_encoding));
+            let call = Callback::EditorCompositor(Box::new(
+                move |_editor: &mut Editor, compositor: &mut Compositor| {
+                    if let Some(outline) = compositor
+                        .find::<ui::EditorView>()
+                        .and_then(|editor_view| editor_view.outline.as_mut())
+                    {
+                        outline.set_symbols(source, symbols);
+                    }
+                },
+            ));
+            Ok(call)
+        });
+    }
+
+    fn set_symbols(
+        &mut self,
+        source: Source,
+        symbols: Option<(lsp::DocumentSymbolResponse, OffsetEncoding)>,
+    ) {
+        // a newer version was requested in the meantime
+        if self.requested != Some(source) {
+            return;
+        }
+        let items = match symbols {
+            Some((symbols, offset_encoding)) => {
+                self.offset_encoding = offset_encoding;
+                symbol_items(symbols)
+            }
+            None => Vec::new(),
+        };
+        self.tree.replace_with_new_items(items);
+        self.source = Some(source);
+    }
+
+    /// Folds the symbols so that only `depth` levels below the top level are shown.
+    pub fn fold_to_depth(&mut self, depth: usize) {
+        self.tree.collapse_to_depth(depth);
+    }
+
+    /// Selects the innermost visible symbol containing the cursor.
+    fn follow_cursor(&mut self, editor: &Editor) {
+        let (view, doc) = current_ref!(editor);
+        if self.source.map(|(doc_id, ..)| doc_id) != Some(doc.id()) {
+            return;
+        }
+        let text = doc.text().slice(..);
+        let cursor = doc.selection(view.id).primary().cursor(text);
+        let cursor = pos_to_lsp_pos(doc.text(), cursor, self.offset_encoding);
+        self.tree.select_last(|item| contains(&item.range, cursor));
+    }
+
+    /// Selects the symbol in the document while the panel keeps the focus.
+    fn peek(&self, editor: &mut Editor) {
+        let doc = doc!(editor);
+        let doc_id = doc.id();
+        if self.source.map(|(doc_id, ..)| doc_id) != Some(doc_id) {
+            return;
+        }
+        let range = self.tree.current_item().selection_range;
+        if let Some(range) = lsp_range_to_range(doc.text(), range, self.offset_encoding) {
+            // put the cursor on the start of the symbol
+            editor.peek(doc_id, Selection::single(range.head, range.anchor));
+        }
+    }
+
+    /// Moves the cursor to the selected symbol.
+    fn jump(&mut self, editor: &mut Editor) {
+        editor.confirm_peeks();
+        let (view, doc) = current!(editor);
+        if self.source.map(|(doc_id, ..)| doc_id) != Some(doc.id()) {
+            return;
+        }
+        let range = self.tree.current_item().selection_range;
+        if let Some(range) = lsp_range_to_range(doc.text(), range, self.offset_encoding) {
+            view.jumps.push((doc.id(), doc.selection(view.id).clone()));
+            // put the cursor on the start of the symbol
+            doc.set_selection(view.id, Selection::single(range.head, range.anchor));
+            align_view(doc, view, Align::Center);
+        }
+        self.unfocus();
+    }
+}
+
+impl Component for Outline {
+    fn handle_event(&mut self, event: &Event, cx: &mut Context) -> EventResult {
+        if !self.focus {
+            return EventResult::Ignored(None);
+        }
+        let (action, count) = match event {
+            Event::Key(key!(Esc)) => {
+                self.unfocus();
+                return EventResult::Consumed(None);
+            }
+            Event::Key(key!('q') | ctrl!('c')) => {
+                return EventResult::Consumed(Some(Box::new(|compositor: &mut Compositor, _| {
+                    if let Some(editor_view) = compositor.find::<ui::EditorView>() {
+                        editor_view.outline = None;
+                    }
+                })))
+            }
+            Event::Key(key_event) => match self.tree.key_action(*key_event, cx, &mut ()) {
+                TreeKey::Action(action, count) => (action, count),
+                TreeKey::Consumed | TreeKey::Ignored => return EventResult::Consumed(None),
+            },
+            Event::Mouse(event) => match self.tree.handle_mouse(event, cx, &mut ()) {
+                MouseAction::Activate => (TreeAction::Activate, 0),
+                MouseAction::Handled => return EventResult::Consumed(None),
+                MouseAction::Ignored => return EventResult::Ignored(None),
+            },
+            Event::Resize(..) => return EventResult::Consumed(None),
+            _ => return EventResult::Ignored(None),
+        };
+
+        match action {
+            TreeAction::Activate if !self.tree.is_empty() => self.jump(cx.editor),
+            TreeAction::Peek if !self.tree.is_empty() => self.peek(cx.editor),
+            TreeAction::PeekBack => peek_back(cx.editor),
+            action => self.tree.run_action(action, count, cx, &mut ()),
+        }
+        EventResult::Consumed(None)
+    }
+
+    fn render(&mut self, area: Rect, surface: &mut Surface, cx: &mut Context) {
+        if !self.focus {
+            self.follow_cursor(cx.editor);
+        }
+
+        let background = cx.editor.theme.get("ui.background");
+        surface.clear_with(area, background);
+
+        let block = Block::default()
+            .title(" Outline ")
+            .borders(Borders::LEFT)
+            .border_style(super::border_style(&cx.editor.theme, self.focus));
+        let inner = block.inner(area);
+        block.render(area, surface);
+
+        let doc_id = doc!(cx.editor).id();
+        match self.source {
+            Some((source, ..)) if source == doc_id && !self.tree.is_empty() => {
+                self.tree.render(inner, surface, cx, &mut ())
+            }
+            Some((source, ..)) if source == doc_id => {
+                surface.set_stringn(
+                    inner.x,
+                    inner.y,
+                    "No symbols",
+                    inner.width as usize,
+                    cx.editor.theme.get("comment"),
+                );
+            }
+            // the symbols of another document are still being requested
+            _ => (),
+        }
+
+        if !self.focus {
+            super::dim_inactive(area, surface, &cx.editor.theme);
+        }
+    }
+
+    fn cursor(&self, _area: Rect, _editor: &Editor) -> (Option<Position>, CursorKind) {
+        (None, CursorKind::Hidden)
+    }
+}
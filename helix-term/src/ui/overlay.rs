@@ -69,4 +69,8 @@ fn cursor(&self, area: Rect, ctx: &Editor) -> (Option<Position>, CursorKind) {
         let dimensions = (self.calc_child_size)(area);
         self.content.cursor(dimensions, ctx)
     }
+
+    fn is_panel(&self) -> bool {
+        self.content.is_panel()
+    }
 }
@@ -1,7 +1,7 @@
 use crate::{
     compositor::{Component, Compositor, Context, Event, EventResult},
     ctrl, key, shift,
-    ui::{self, fuzzy_match::FuzzyQuery, EditorView},
+    ui::{self, fuzzy_match::FuzzyQuery, media, EditorView},
 };
 use tui::{
     buffer::Buffer as Surface,
@@ -15,7 +15,7 @@
 use std::{collections::HashMap, io::Read, path::PathBuf};
 
 use crate::ui::{Prompt, PromptEvent};
-use helix_core::{movement::Direction, Position};
+use helix_core::{movement::Direction, unicode::width::UnicodeWidthStr, Position};
 use helix_view::{
     editor::Action,
     graphics::{CursorKind, Margin, Modifier, Rect},
@@ -25,6 +25,10 @@
 use super::menu::Item;
 
 pub const MIN_AREA_WIDTH_FOR_PREVIEW: u16 = 72;
+
+/// Splits filter terms off a picker query. Returns the rest of the query, which is matched
+/// fuzzily, and the predicate options must satisfy if there were filter terms.
+pub type QueryFilter<T> = Box<dyn Fn(&str) -> (String, Option<Box<dyn Fn(&T) -> bool>>)>;
 /// Biggest file size to preview in bytes
 pub const MAX_FILE_SIZE_FOR_PREVIEW: u64 = 10 * 1024 * 1024;
 
@@ -59,6 +63,60 @@ fn from(v: DocumentId) -> Self {
 /// File path and range of lines (used to align and highlight lines)
 pub type FileLocation = (PathOrId, Option<(usize, usize)>);
 
+/// Renders `doc` into `area` with the lines in `range` centered and highlighted.
+pub fn render_document_preview(
+    doc: &Document,
+    range: Option<(usize, usize)>,
+    area: Rect,
+    surface: &mut Surface,
+    editor: &Editor,
+) {
+    // align to middle
+    let first_line = range
+        .map(|(start, end)| {
+            let height = end.saturating_sub(start) + 1;
+            let middle = start + (height.saturating_sub(1) / 2);
+            middle.saturating_sub(area.height as usize / 2).min(start)
+        })
+        .unwrap_or(0);
+
+    let offset = Position::new(first_line, 0);
+
+    let mut highlights = EditorView::doc_syntax_highlights(doc, offset, area.height, &editor.theme);
+    for spans in EditorView::doc_diagnostics_highlights(doc, &editor.theme) {
+        if spans.is_empty() {
+            continue;
+        }
+        highlights = Box::new(helix_core::syntax::merge(highlights, spans));
+    }
+    EditorView::render_text_highlights(
+        doc,
+        offset,
+        area,
+        surface,
+        &editor.theme,
+        highlights,
+        &editor.config(),
+    );
+
+    // highlight the line
+    if let Some((start, end)) = range {
+        let offset = start.saturating_sub(first_line) as u16;
+        surface.set_style(
+            Rect::new(
+                area.x,
+                area.y + offset,
+                area.width,
+                (end.saturating_sub(start) as u16 + 1).min(area.height.saturating_sub(offset)),
+            ),
+            editor
+                .theme
+                .try_get("ui.highlight")
+                .unwrap_or_else(|| editor.theme.get("ui.selection")),
+        );
+    }
+}
+
 pub struct FilePicker<T: Item> {
     picker: Picker<T>,
     pub truncate_start: bool,
@@ -71,7 +129,8 @@ pub struct FilePicker<T: Item> {
 
 pub enum CachedPreview {
     Document(Box<Document>),
-    Binary,
+    /// Lines describing the file, like its media type and dimensions.
+    Binary(Vec<String>),
     LargeFile,
     NotFound,
 }
@@ -93,14 +152,14 @@ fn document(&self) -> Option<&Document> {
     }
 
     /// Alternate text to show for the preview.
-    fn placeholder(&self) -> &str {
+    fn placeholder(&self) -> Vec<&str> {
         match *self {
-            Self::EditorDocument(_) => "<File preview>",
+            Self::EditorDocument(_) => vec!["<File preview>"],
             Self::Cached(preview) => match preview {
-                CachedPreview::Document(_) => "<File preview>",
-                CachedPreview::Binary => "<Binary file>",
-                CachedPreview::LargeFile => "<File too large to preview>",
-                CachedPreview::NotFound => "<File not found>",
+                CachedPreview::Document(_) => vec!["<File preview>"],
+                CachedPreview::Binary(lines) => lines.iter().map(String::as_str).collect(),
+                CachedPreview::LargeFile => vec!["<File too large to preview>"],
+                CachedPreview::NotFound => vec!["<File not found>"],
             },
         }
     }
@@ -132,6 +191,11 @@ pub fn truncate_start(mut self, truncate_start: bool) -> Self {
         self
     }
 
+    pub fn with_query_filter(mut self, query_filter: QueryFilter<T>) -> Self {
+        self.picker = self.picker.with_query_filter(query_filter);
+        self
+    }
+
     fn current_file(&self, editor: &Editor) -> Option<FileLocation> {
         self.picker
             .selection()
@@ -168,7 +232,10 @@ fn get_preview<'picker, 'editor>(
                 let preview = data
                     .map(
                         |(metadata, content_type)| match (metadata.len(), content_type) {
-                            (_, content_inspector::ContentType::BINARY) => CachedPreview::Binary,
+                            (_, content_inspector::ContentType::BINARY) => CachedPreview::Binary(
+                                media::describe(path)
+                                    .unwrap_or_else(|_| vec!["<Binary file>".to_string()]),
+                            ),
                             (size, _) if size > MAX_FILE_SIZE_FOR_PREVIEW => {
                                 CachedPreview::LargeFile
                             }
@@ -261,60 +328,17 @@ fn render(&mut self, area: Rect, surface: &mut Surface, cx: &mut Context) {
             let doc = match preview.document() {
                 Some(doc) => doc,
                 None => {
-                    let alt_text = preview.placeholder();
-                    let x = inner.x + inner.width.saturating_sub(alt_text.len() as u16) / 2;
-                    let y = inner.y + inner.height / 2;
-                    surface.set_stringn(x, y, alt_text, inner.width as usize, text);
+                    let lines = preview.placeholder();
+                    let y = inner.y + inner.height.saturating_sub(lines.len() as u16) / 2;
+                    for (y, line) in (y..inner.bottom()).zip(lines) {
+                        let x = inner.x + inner.width.saturating_sub(line.width() as u16) / 2;
+                        surface.set_stringn(x, y, line, inner.width as usize, text);
+                    }
                     return;
                 }
             };
 
-            // align to middle
-            let first_line = range
-                .map(|(start, end)| {
-                    let height = end.saturating_sub(start) + 1;
-                    let middle = start + (height.saturating_sub(1) / 2);
-                    middle.saturating_sub(inner.height as usize / 2).min(start)
-                })
-                .unwrap_or(0);
-
-            let offset = Position::new(first_line, 0);
-
-            let mut highlights =
-                EditorView::doc_syntax_highlights(doc, offset, area.height, &cx.editor.theme);
-            for spans in EditorView::doc_diagnostics_highlights(doc, &cx.editor.theme) {
-                if spans.is_empty() {
-                    continue;
-                }
-                highlights = Box::new(helix_core::syntax::merge(highlights, spans));
-            }
-            EditorView::render_text_highlights(
-                doc,
-                offset,
-                inner,
-                surface,
-                &cx.editor.theme,
-                highlights,
-                &cx.editor.config(),
-            );
-
-            // highlight the line
-            if let Some((start, end)) = range {
-                let offset = start.saturating_sub(first_line) as u16;
-                surface.set_style(
-                    Rect::new(
-                        inner.x,
-                        inner.y + offset,
-                        inner.width,
-                        (end.saturating_sub(start) as u16 + 1)
-                            .min(inner.height.saturating_sub(offset)),
-                    ),
-                    cx.editor
-                        .theme
-                        .try_get("ui.highlight")
-                        .unwrap_or_else(|| cx.editor.theme.get("ui.selection")),
-                );
-            }
+            render_document_preview(doc, range, inner, surface, cx.editor);
         }
     }
 
@@ -381,6 +405,7 @@ pub struct Picker<T: Item> {
     pub truncate_start: bool,
     /// Whether to show the preview panel (default true)
     show_preview: bool,
+    query_filter: Option<QueryFilter<T>>,
 
     callback_fn: Box<dyn Fn(&mut Context, &T, Action)>,
 }
@@ -408,6 +433,7 @@ pub fn new(
             previous_pattern: String::new(),
             truncate_start: true,
             show_preview: true,
+            query_filter: None,
             callback_fn: Box::new(callback_fn),
             completion_height: 0,
         };
@@ -428,6 +454,12 @@ pub fn new(
         picker
     }
 
+    /// Lets the query contain filter terms besides the fuzzy pattern.
+    pub fn with_query_filter(mut self, query_filter: QueryFilter<T>) -> Self {
+        self.query_filter = Some(query_filter);
+        self
+    }
+
     pub fn score(&mut self) {
         let now = Instant::now();
 
@@ -437,19 +469,34 @@ pub fn score(&mut self) {
             return;
         }
 
-        if pattern.is_empty() {
+        let (fuzzy_pattern, predicate) = match &self.query_filter {
+            Some(query_filter) => query_filter(pattern),
+            None => (pattern.clone(), None),
+        };
+        let is_shown = |option: &T| {
+            predicate
+                .as_ref()
+                .map_or(true, |predicate| predicate(option))
+        };
+
+        if fuzzy_pattern.is_empty() {
             // Fast path for no pattern.
             self.matches.clear();
-            self.matches
-                .extend(self.options.iter().enumerate().map(|(index, option)| {
-                    let text = option.filter_text(&self.editor_data);
-                    PickerMatch {
-                        index,
-                        score: 0,
-                        len: text.chars().count(),
-                    }
-                }));
-        } else if pattern.starts_with(&self.previous_pattern) {
+            self.matches.extend(
+                self.options
+                    .iter()
+                    .enumerate()
+                    .filter(|(_, option)| is_shown(option))
+                    .map(|(index, option)| {
+                        let text = option.filter_text(&self.editor_data);
+                        PickerMatch {
+                            index,
+                            score: 0,
+                            len: text.chars().count(),
+                        }
+                    }),
+            );
+        } else if self.query_filter.is_none() && pattern.starts_with(&self.previous_pattern) {
             let query = FuzzyQuery::new(pattern);
             // optimization: if the pattern is a more specific version of the previous one
             // then we can score the filtered set.
@@ -469,12 +516,14 @@ pub fn score(&mut self) {
 
             self.matches.sort_unstable();
         } else {
-            let query = FuzzyQuery::new(pattern);
+            // filter terms can get less specific while typing, so everything is scored again
+            let query = FuzzyQuery::new(&fuzzy_pattern);
             self.matches.clear();
             self.matches.extend(
                 self.options
                     .iter()
                     .enumerate()
+                    .filter(|(_, option)| is_shown(option))
                     .filter_map(|(index, option)| {
                         let text = option.filter_text(&self.editor_data);
 
@@ -0,0 +1,192 @@
+use super::{tree::patch_selected, MouseAction, Tree, TreeItem};
+use crate::{
+    commands::goto_pin,
+    compositor::{Component, Compositor, Context, EventResult},
+    ctrl, key, shift,
+};
+use helix_core::{path::get_relative_path, Position};
+use helix_view::{
+    graphics::{CursorKind, Rect},
+    input::Event,
+    Editor,
+};
+use std::{cmp::Ordering, path::PathBuf};
+use tui::{
+    buffer::Buffer as Surface,
+    text::{Span, Spans},
+    widgets::{Block, Borders, Widget},
+};
+
+#[derive(Debug, Clone)]
+struct PinItem {
+    slot: usize,
+    path: PathBuf,
+}
+
+impl TreeItem for PinItem {
+    type Params = ();
+
+    fn text(&self, cx: &mut Context, selected: bool, _params: &mut ()) -> Spans {
+        let theme = &cx.editor.theme;
+        let mut spans = vec![
+            Span::styled(format!("{} ", self.slot + 1), theme.get("special")),
+            Span::styled(
+                get_relative_path(&self.path).to_string_lossy().into_owned(),
+                theme.get("ui.text"),
+            ),
+        ];
+        if selected {
+            patch_selected(cx, &mut spans);
+        }
+        Spans::from(spans)
+    }
+
+    fn is_child(&self, _other: &Self) -> bool {
+        false
+    }
+
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.slot.cmp(&other.slot)
+    }
+}
+
+fn pin_items(editor: &Editor) -> Vec<PinItem> {
+    editor
+        .pins
+        .files()
+        .iter()
+        .enumerate()
+        .map(|(slot, path)| PinItem {
+            slot,
+            path: path.clone(),
+        })
+        .collect()
+}
+
+/// The files pinned to slots, in the order of their slots, to open, reorder and unpin them.
+pub struct PinsView {
+    tree: Tree<PinItem>,
+}
+
+impl PinsView {
+    /// Returns `None` if no file is pinned.
+    pub fn new(editor: &Editor) -> Option<Self> {
+        let items = pin_items(editor);
+        if items.is_empty() {
+            return None;
+        }
+        Some(Self {
+            tree: Tree::build_tree(items),
+        })
+    }
+
+    fn close() -> EventResult {
+        EventResult::Consumed(Some(Box::new(|compositor: &mut Compositor, _| {
+            compositor.pop();
+        })))
+    }
+
+    fn open(&self) -> EventResult {
+        let slot = self.tree.current_item().slot;
+        EventResult::Consumed(Some(Box::new(move |compositor: &mut Compositor, cx| {
+            compositor.pop();
+            goto_pin(cx.editor, slot);
+        })))
+    }
+
+    /// Rebuilds the rows after the pins changed and selects `selected`.
+    fn refresh(&mut self, editor: &mut Editor, selected: usize) -> EventResult {
+        editor.save_pins();
+        let items = pin_items(editor);
+        if items.is_empty() {
+            return Self::close();
+        }
+        let selected = selected.min(items.len() - 1);
+        self.tree.replace_with_new_items(items);
+        self.tree.move_down(selected);
+        EventResult::Consumed(None)
+    }
+
+    fn unpin(&mut self, editor: &mut Editor) -> EventResult {
+        let slot = self.tree.current_item().slot;
+        editor.pins.unpin(slot);
+        self.refresh(editor, slot)
+    }
+
+    /// Exchanges the selected file with the file of the previous or the next slot.
+    fn move_pin(&mut self, editor: &mut Editor, down: bool) -> EventResult {
+        let slot = self.tree.current_item().slot;
+        let other = if down {
+            slot + 1
+        } else {
+            match slot.checked_sub(1) {
+                Some(other) => other,
+                None => return EventResult::Consumed(None),
+            }
+        };
+        if other >= editor.pins.files().len() {
+            return EventResult::Consumed(None);
+        }
+        editor.pins.swap(slot, other);
+        self.refresh(editor, other)
+    }
+}
+
+impl Component for PinsView {
+    fn handle_event(&mut self, event: &Event, cx: &mut Context) -> EventResult {
+        let key_event = match event {
+            Event::Key(event) => *event,
+            Event::Mouse(event) => match self.tree.handle_mouse(event, cx, &mut ()) {
+                MouseAction::Activate => key!(Enter),
+                MouseAction::Handled => return EventResult::Consumed(None),
+                MouseAction::Ignored => return EventResult::Ignored(None),
+            },
+            Event::Resize(..) => return EventResult::Consumed(None),
+            _ => return EventResult::Ignored(None),
+        };
+
+        match key_event.into() {
+            key!(Esc) | key!('q') | ctrl!('c') => return Self::close(),
+            key!(Enter) => return self.open(),
+            key!('d') => return self.unpin(cx.editor),
+            shift!('K') => return self.move_pin(cx.editor, false),
+            shift!('J') => return self.move_pin(cx.editor, true),
+            _ => {
+                self.tree.handle_event(Event::Key(key_event), cx, &mut ());
+            }
+        }
+        EventResult::Consumed(None)
+    }
+
+    fn render(&mut self, area: Rect, surface: &mut Surface, cx: &mut Context) {
+        let background = cx.editor.theme.get("ui.background");
+        surface.clear_with(area, background);
+
+        let block = Block::default()
+            .title(" Pinned files ")
+            .borders(Borders::ALL)
+            .border_style(super::border_style(&cx.editor.theme, cx.focus));
+        let inner = block.inner(area);
+        block.render(area, surface);
+
+        let tree_area = inner.clip_bottom(1);
+        self.tree.render(tree_area, surface, cx, &mut ());
+
+        let footer_area = inner.clip_top(tree_area.height);
+        surface.set_stringn(
+            footer_area.x,
+            footer_area.y,
+            "[enter]open [d]unpin [K]move up [J]move down",
+            footer_area.width as usize,
+            cx.editor.theme.get("comment"),
+        );
+    }
+
+    fn cursor(&self, _area: Rect, _editor: &Editor) -> (Option<Position>, CursorKind) {
+        (None, CursorKind::Hidden)
+    }
+
+    fn is_panel(&self) -> bool {
+        true
+    }
+}
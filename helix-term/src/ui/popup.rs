@@ -251,7 +251,7 @@ const fn div_ceil(a: usize, b: usize) -> usize {
                 (a + b - 1) / b
             }
 
-            if !fits {
+            if !fits && inner.width > 0 {
                 let scroll_height = div_ceil(win_height.pow(2), len).min(win_height);
                 let scroll_line = (win_height - scroll_height) * scroll
                     / std::cmp::max(1, len.saturating_sub(win_height));
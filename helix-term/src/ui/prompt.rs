@@ -7,7 +7,9 @@
 use tui::widgets::{Block, Borders, Widget};
 
 use helix_core::{
-    unicode::segmentation::GraphemeCursor, unicode::width::UnicodeWidthStr, Position,
+    unicode::segmentation::GraphemeCursor,
+    unicode::width::{UnicodeWidthChar, UnicodeWidthStr},
+    Position, Range, Selection,
 };
 use helix_view::{
     graphics::{CursorKind, Margin, Rect},
@@ -29,6 +31,10 @@ pub struct Prompt {
     callback_fn: Box<dyn FnMut(&mut Context, &str, PromptEvent)>,
     pub doc_fn: Box<dyn Fn(&str) -> Option<Cow<str>>>,
     next_char_handler: Option<PromptCharHandler>,
+    /// The prompt without the search options, for prompts which can toggle them.
+    search_prompt: Option<String>,
+    /// Whether `Alt-Enter` inserts line breaks.
+    multiline: bool,
 }
 
 #[derive(Clone, Copy, PartialEq, Eq)]
@@ -80,6 +86,34 @@ pub fn new(
             callback_fn: Box::new(callback_fn),
             doc_fn: Box::new(|_| None),
             next_char_handler: None,
+            search_prompt: None,
+            multiline: false,
+        }
+    }
+
+    /// Lets `Alt-Enter` insert line breaks. The prompt grows to show every line of the input
+    /// and `Up` and `Down` move between the lines before going through the history.
+    pub fn with_multiline(mut self) -> Self {
+        self.multiline = true;
+        self
+    }
+
+    /// Lets `Alt-c` and `Alt-w` toggle the smart case and whole word search options and shows
+    /// the enabled options in the prompt.
+    pub fn with_search_options(mut self, editor: &Editor) -> Self {
+        self.search_prompt = Some(self.prompt.trim_end_matches(':').to_string());
+        self.update_search_prompt(editor);
+        self
+    }
+
+    fn update_search_prompt(&mut self, editor: &Editor) {
+        if let Some(prompt) = &self.search_prompt {
+            let label = editor.search_options.label();
+            self.prompt = if label.is_empty() {
+                format!("{}:", prompt).into()
+            } else {
+                format!("{} [{}]:", prompt, label).into()
+            };
         }
     }
 
@@ -95,6 +129,60 @@ pub fn line(&self) -> &String {
         &self.line
     }
 
+    /// The input shown in the prompt, which is the last history entry if nothing was entered.
+    fn input<'a>(&'a self, editor: &'a Editor) -> Cow<'a, str> {
+        if self.line.is_empty() {
+            self.history_register
+                .and_then(|reg| editor.registers.last(reg))
+                .map(|entry| entry.as_str().into())
+                .unwrap_or_else(|| Cow::from(""))
+        } else {
+            self.line.as_str().into()
+        }
+    }
+
+    /// The byte range of the line of the input containing the cursor.
+    fn current_line(&self) -> std::ops::Range<usize> {
+        let start = self.line[..self.cursor].rfind('\n').map_or(0, |i| i + 1);
+        let end = self.line[self.cursor..]
+            .find('\n')
+            .map_or(self.line.len(), |i| self.cursor + i);
+        start..end
+    }
+
+    /// Moves the cursor to the line above or below, keeping its column where possible. Returns
+    /// `false` if there is no line to move to.
+    fn move_line(&mut self, down: bool) -> bool {
+        let current = self.current_line();
+        let target = if down {
+            if current.end == self.line.len() {
+                return false;
+            }
+            let start = current.end + 1;
+            start
+                ..self.line[start..]
+                    .find('\n')
+                    .map_or(self.line.len(), |i| start + i)
+        } else {
+            if current.start == 0 {
+                return false;
+            }
+            let end = current.start - 1;
+            self.line[..end].rfind('\n').map_or(0, |i| i + 1)..end
+        };
+        let column = UnicodeWidthStr::width(&self.line[current.start..self.cursor]);
+        let mut width = 0;
+        self.cursor = target.end;
+        for (i, c) in self.line[target.clone()].char_indices() {
+            if width >= column {
+                self.cursor = target.start + i;
+                break;
+            }
+            width += c.width().unwrap_or(0);
+        }
+        true
+    }
+
     pub fn recalculate_completion(&mut self, editor: &Editor) {
         self.exit_selection();
         self.completion = (self.completion_fn)(editor, &self.line);
@@ -187,16 +275,8 @@ fn eval_movement(&self, movement: Movement) -> usize {
                 }
                 position
             }
-            Movement::StartOfLine => 0,
-            Movement::EndOfLine => {
-                let mut cursor =
-                    GraphemeCursor::new(self.line.len().saturating_sub(1), self.line.len(), false);
-                if let Ok(Some(pos)) = cursor.next_boundary(&self.line, 0) {
-                    pos
-                } else {
-                    self.cursor
-                }
-            }
+            Movement::StartOfLine => self.current_line().start,
+            Movement::EndOfLine => self.current_line().end,
             Movement::None => self.cursor,
         }
     }
@@ -342,6 +422,63 @@ pub fn change_completion_selection(&mut self, direction: CompletionDirection) {
     pub fn exit_selection(&mut self) {
         self.selection = None;
     }
+
+    /// Runs the callback with the input, or the last history entry if there is none.
+    fn validate(&mut self, cx: &mut Context) {
+        let last_item = self
+            .history_register
+            .and_then(|reg| cx.editor.registers.last(reg).cloned())
+            .map(|entry| entry.into())
+            .unwrap_or_else(|| Cow::from(""));
+
+        // handle executing with last command in history if nothing entered
+        let input: Cow<str> = if self.line.is_empty() {
+            last_item
+        } else {
+            if last_item != self.line {
+                // store in history
+                if let Some(register) = self.history_register {
+                    cx.editor.registers.push(register, self.line.clone());
+                };
+            }
+
+            self.line.as_str().into()
+        };
+
+        (self.callback_fn)(cx, &input, PromptEvent::Validate);
+    }
+}
+
+/// Adds the match after the primary selection, or all matches, of the search that was typed to
+/// the selection.
+fn select_search_matches(editor: &mut Editor, all: bool) {
+    let matches = match editor.search_matches.take() {
+        Some(matches) if !matches.ranges.is_empty() => matches,
+        _ => return,
+    };
+    let scrolloff = editor.config().scrolloff;
+    let (view, doc) = current!(editor);
+    if matches.doc != doc.id() {
+        return;
+    }
+    let selection = doc.selection(view.id).clone();
+    let primary = selection.primary();
+    let to_range = |range: &std::ops::Range<usize>| {
+        Range::new(range.start, range.end).with_direction(primary.direction())
+    };
+    let selection = if all {
+        let ranges = matches.ranges.iter().map(to_range).collect();
+        Selection::new(ranges, matches.current.unwrap_or(0))
+    } else {
+        let next = matches
+            .ranges
+            .iter()
+            .position(|range| range.start >= primary.to())
+            .unwrap_or(0);
+        selection.push(to_range(&matches.ranges[next]))
+    };
+    doc.set_selection(view.id, selection);
+    view.ensure_cursor_in_view(doc, scrolloff);
 }
 
 const BASE_WIDTH: u16 = 30;
@@ -369,9 +506,11 @@ pub fn render_prompt(&self, area: Rect, surface: &mut Surface, cx: &mut Context)
             .min(10) // at most 10 rows (or less)
             .min(area.height.saturating_sub(1));
 
+        // the lines of the input fill the bottom rows, as many as fit
+        let rows = (self.input(cx.editor).split('\n').count() as u16).clamp(1, area.height.max(1));
         let completion_area = Rect::new(
             area.x,
-            (area.height - height).saturating_sub(1),
+            (area.height - height).saturating_sub(rows),
             area.width,
             height,
         );
@@ -408,7 +547,7 @@ pub fn render_prompt(&self, area: Rect, surface: &mut Surface, cx: &mut Context)
                     color,
                 );
                 row += 1;
-                if row > area.height - 1 {
+                if row >= area.height {
                     row = 0;
                     col += 1;
                 }
@@ -446,26 +585,46 @@ pub fn render_prompt(&self, area: Rect, surface: &mut Surface, cx: &mut Context)
             text.render(inner, surface, cx);
         }
 
-        let line = area.height - 1;
+        let line = area.height.saturating_sub(rows);
+        let input = self.input(cx.editor);
+        if rows > 1 {
+            let background = cx.editor.theme.get("ui.background");
+            surface.clear_with(
+                Rect::new(area.x, area.y + line, area.width, rows - 1),
+                background,
+            );
+        }
         // render buffer text
         surface.set_string(area.x, area.y + line, &self.prompt, prompt_color);
 
-        let input: Cow<str> = if self.line.is_empty() {
-            // latest value in the register list
-            self.history_register
-                .and_then(|reg| cx.editor.registers.last(reg))
-                .map(|entry| entry.into())
-                .unwrap_or_else(|| Cow::from(""))
-        } else {
-            self.line.as_str().into()
-        };
+        // continued lines are aligned with the first one
+        for (row, text) in input.split('\n').take(rows as usize).enumerate() {
+            surface.set_string(
+                area.x + self.prompt.width() as u16,
+                area.y + line + row as u16,
+                text,
+                prompt_color,
+            );
+        }
 
-        surface.set_string(
-            area.x + self.prompt.len() as u16,
-            area.y + line,
-            &input,
-            prompt_color,
-        );
+        let matches = match &cx.editor.search_matches {
+            Some(matches) if self.search_prompt.is_some() => matches,
+            _ => return,
+        };
+        let count = match matches.current {
+            Some(current) => format!("match {}/{}", current + 1, matches.ranges.len()),
+            None => format!("{} matches", matches.ranges.len()),
+        };
+        let used =
+            self.prompt.width() + UnicodeWidthStr::width(input.lines().next().unwrap_or("")) + 1;
+        if used + count.len() <= area.width as usize {
+            surface.set_string(
+                area.right() - count.len() as u16,
+                area.y + line,
+                &count,
+                cx.editor.theme.get("comment"),
+            );
+        }
     }
 }
 
@@ -490,14 +649,33 @@ fn handle_event(&mut self, event: &Event, cx: &mut Context) -> EventResult {
         match event {
             ctrl!('c') | key!(Esc) => {
                 (self.callback_fn)(cx, &self.line, PromptEvent::Abort);
+                if self.search_prompt.is_some() {
+                    cx.editor.search_matches = None;
+                }
                 return close_fn;
             }
+            alt!('n') | alt!('a') if self.search_prompt.is_some() => {
+                self.validate(cx);
+                select_search_matches(cx.editor, event == alt!('a'));
+                cx.editor.search_matches = None;
+                return close_fn;
+            }
+            alt!('c') | alt!('w') if self.search_prompt.is_some() => {
+                let options = &mut cx.editor.search_options;
+                if event == alt!('c') {
+                    options.smart_case = !options.smart_case;
+                } else {
+                    options.whole_word = !options.whole_word;
+                }
+                self.update_search_prompt(cx.editor);
+                (self.callback_fn)(cx, &self.line, PromptEvent::Update);
+            }
             alt!('b') | ctrl!(Left) => self.move_cursor(Movement::BackwardWord(1)),
             alt!('f') | ctrl!(Right) => self.move_cursor(Movement::ForwardWord(1)),
             ctrl!('b') | key!(Left) => self.move_cursor(Movement::BackwardChar(1)),
             ctrl!('f') | key!(Right) => self.move_cursor(Movement::ForwardChar(1)),
-            ctrl!('e') | key!(End) => self.move_end(),
-            ctrl!('a') | key!(Home) => self.move_start(),
+            ctrl!('e') | key!(End) => self.move_cursor(Movement::EndOfLine),
+            ctrl!('a') | key!(Home) => self.move_cursor(Movement::StartOfLine),
             ctrl!('w') | alt!(Backspace) | ctrl!(Backspace) => {
                 self.delete_word_backwards(cx.editor)
             }
@@ -530,35 +708,23 @@ fn handle_event(&mut self, event: &Event, cx: &mut Context) -> EventResult {
                     (self.callback_fn)(cx, &self.line, PromptEvent::Update);
                 }
             }
+            alt!(Enter) if self.multiline => {
+                self.insert_str("\n", cx.editor);
+                (self.callback_fn)(cx, &self.line, PromptEvent::Update);
+            }
             key!(Enter) => {
                 if self.selection.is_some() && self.line.ends_with(std::path::MAIN_SEPARATOR) {
                     self.recalculate_completion(cx.editor);
                 } else {
-                    let last_item = self
-                        .history_register
-                        .and_then(|reg| cx.editor.registers.last(reg).cloned())
-                        .map(|entry| entry.into())
-                        .unwrap_or_else(|| Cow::from(""));
-
-                    // handle executing with last command in history if nothing entered
-                    let input: Cow<str> = if self.line.is_empty() {
-                        last_item
-                    } else {
-                        if last_item != self.line {
-                            // store in history
-                            if let Some(register) = self.history_register {
-                                cx.editor.registers.push(register, self.line.clone());
-                            };
-                        }
-
-                        self.line.as_str().into()
-                    };
-
-                    (self.callback_fn)(cx, &input, PromptEvent::Validate);
-
+                    self.validate(cx);
+                    if self.search_prompt.is_some() {
+                        cx.editor.search_matches = None;
+                    }
                     return close_fn;
                 }
             }
+            key!(Up) if self.multiline && self.move_line(false) => (),
+            key!(Down) if self.multiline && self.move_line(true) => (),
             ctrl!('p') | key!(Up) => {
                 if let Some(register) = self.history_register {
                     self.change_history(cx, register, CompletionDirection::Backward);
@@ -629,14 +795,21 @@ fn render(&mut self, area: Rect, surface: &mut Surface, cx: &mut Context) {
         self.render_prompt(area, surface, cx)
     }
 
-    fn cursor(&self, area: Rect, _editor: &Editor) -> (Option<Position>, CursorKind) {
-        let line = area.height as usize - 1;
+    fn cursor(&self, area: Rect, editor: &Editor) -> (Option<Position>, CursorKind) {
+        let rows = self
+            .input(editor)
+            .split('\n')
+            .count()
+            .clamp(1, (area.height as usize).max(1));
+        let row = self.line[..self.cursor].matches('\n').count().min(rows - 1);
+        let line_start = self.current_line().start;
+        // the terminal shows the preedit text of input methods at the cursor, so it's placed
+        // by display width and kept on screen
+        let col = self.prompt.width() + UnicodeWidthStr::width(&self.line[line_start..self.cursor]);
         (
             Some(Position::new(
-                area.y as usize + line,
-                area.x as usize
-                    + self.prompt.len()
-                    + UnicodeWidthStr::width(&self.line[..self.cursor]),
+                area.y as usize + area.height as usize - rows + row,
+                area.x as usize + col.min((area.width as usize).saturating_sub(1)),
             )),
             CursorKind::Block,
         )
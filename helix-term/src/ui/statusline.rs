@@ -1,12 +1,16 @@
 use helix_core::{coords_at_pos, encoding, Position};
-use helix_lsp::lsp::DiagnosticSeverity;
+use helix_lsp::{
+    lsp::{self, DiagnosticSeverity},
+    util::lsp_range_to_range,
+};
 use helix_view::{
-    document::{Mode, SCRATCH_BUFFER_NAME},
+    document::{DocumentSymbol, Mode, SCRATCH_BUFFER_NAME},
     graphics::Rect,
     theme::Style,
     Document, Editor, View,
 };
 
+use crate::job::{Callback, Jobs};
 use crate::ui::ProgressSpinners;
 
 use helix_view::editor::StatusLineElement as StatusLineElementID;
@@ -143,8 +147,10 @@ fn get_render_function<F>(element_id: StatusLineElementID) -> impl Fn(&mut Rende
         helix_view::editor::StatusLineElement::FileEncoding => render_file_encoding,
         helix_view::editor::StatusLineElement::FileLineEnding => render_file_line_ending,
         helix_view::editor::StatusLineElement::FileType => render_file_type,
+        helix_view::editor::StatusLineElement::IndentStyle => render_indent_style,
         helix_view::editor::StatusLineElement::Diagnostics => render_diagnostics,
         helix_view::editor::StatusLineElement::WorkspaceDiagnostics => render_workspace_diagnostics,
+        helix_view::editor::StatusLineElement::DiagnosticPosition => render_diagnostic_position,
         helix_view::editor::StatusLineElement::Selections => render_selections,
         helix_view::editor::StatusLineElement::PrimarySelectionLength => {
             render_primary_selection_length
@@ -153,7 +159,9 @@ fn get_render_function<F>(element_id: StatusLineElementID) -> impl Fn(&mut Rende
         helix_view::editor::StatusLineElement::PositionPercentage => render_position_percentage,
         helix_view::editor::StatusLineElement::TotalLineNumbers => render_total_line_numbers,
         helix_view::editor::StatusLineElement::Separator => render_separator,
+        helix_view::editor::StatusLineElement::SearchOptions => render_search_options,
         helix_view::editor::StatusLineElement::Spacer => render_spacer,
+        helix_view::editor::StatusLineElement::SymbolContext => render_symbol_context,
     }
 }
 
@@ -199,8 +207,8 @@ fn render_lsp_spinner<F>(context: &mut RenderContext, write: F)
         context,
         context
             .doc
-            .language_server()
-            .and_then(|srv| {
+            .language_servers()
+            .find_map(|srv| {
                 context
                     .spinners
                     .get(srv.id())
@@ -250,6 +258,58 @@ fn render_diagnostics<F>(context: &mut RenderContext, write: F)
     }
 }
 
+fn render_diagnostic_position<F>(context: &mut RenderContext, write: F)
+where
+    F: Fn(&mut RenderContext, String, Option<Style>) + Copy,
+{
+    use helix_core::diagnostic::Severity;
+
+    let doc = context.doc;
+    let cursor = doc
+        .selection(context.view.id)
+        .primary()
+        .cursor(doc.text().slice(..));
+    let diagnostics = doc.diagnostics();
+    // prefer the diagnostic starting at the cursor, where the diagnostic motions move to
+    let diag = diagnostics
+        .iter()
+        .find(|diag| diag.range.start == cursor)
+        .or_else(|| {
+            diagnostics
+                .iter()
+                .find(|diag| diag.range.start <= cursor && cursor < diag.range.end)
+        });
+    let diag = match diag {
+        Some(diag) => diag,
+        None => return,
+    };
+
+    let severity = diag.effective_severity();
+    let mut position = 0;
+    let mut total = 0;
+    for other in diagnostics {
+        if other.effective_severity() == severity {
+            total += 1;
+            if std::ptr::eq(other, diag) {
+                position = total;
+            }
+        }
+    }
+    let scope = match severity {
+        Severity::Error => "error",
+        Severity::Warning => "warning",
+        Severity::Info => "info",
+        Severity::Hint => "hint",
+    };
+
+    write(
+        context,
+        "●".to_string(),
+        Some(context.editor.theme.get(scope)),
+    );
+    write(context, format!(" {}/{} ", position, total), None);
+}
+
 fn render_workspace_diagnostics<F>(context: &mut RenderContext, write: F)
 where
     F: Fn(&mut RenderContext, String, Option<Style>) + Copy,
@@ -260,7 +320,7 @@ fn render_workspace_diagnostics<F>(context: &mut RenderContext, write: F)
             .diagnostics
             .values()
             .flatten()
-            .fold((0, 0), |mut counts, diag| {
+            .fold((0, 0), |mut counts, (diag, _)| {
                 match diag.severity {
                     Some(DiagnosticSeverity::WARNING) => counts.0 += 1,
                     Some(DiagnosticSeverity::ERROR) | None => counts.1 += 1,
@@ -348,6 +408,16 @@ fn render_total_line_numbers<F>(context: &mut RenderContext, write: F)
     write(context, format!(" {} ", total_line_numbers), None);
 }
 
+fn render_search_options<F>(context: &mut RenderContext, write: F)
+where
+    F: Fn(&mut RenderContext, String, Option<Style>) + Copy,
+{
+    let label = context.editor.search_options.label();
+    if !label.is_empty() {
+        write(context, format!(" [{}] ", label), None);
+    }
+}
+
 fn render_position_percentage<F>(context: &mut RenderContext, write: F)
 where
     F: Fn(&mut RenderContext, String, Option<Style>) + Copy,
@@ -397,6 +467,18 @@ fn render_file_line_ending<F>(context: &mut RenderContext, write: F)
     write(context, format!(" {} ", line_ending), None);
 }
 
+fn render_indent_style<F>(context: &mut RenderContext, write: F)
+where
+    F: Fn(&mut RenderContext, String, Option<Style>) + Copy,
+{
+    let doc = context.doc;
+    write(
+        context,
+        format!(" {} ({}) ", doc.indent_style, doc.indent_source),
+        None,
+    );
+}
+
 fn render_file_type<F>(context: &mut RenderContext, write: F)
 where
     F: Fn(&mut RenderContext, String, Option<Style>) + Copy,
@@ -445,3 +527,98 @@ fn render_spacer<F>(context: &mut RenderContext, write: F)
 {
     write(context, String::from(" "), None);
 }
+
+fn render_symbol_context<F>(context: &mut RenderContext, write: F)
+where
+    F: Fn(&mut RenderContext, String, Option<Style>) + Copy,
+{
+    let symbols = {
+        let separator = &context.editor.config().statusline.symbol_separator;
+        let text = context.doc.text().slice(..);
+        let cursor = context
+            .doc
+            .selection(context.view.id)
+            .primary()
+            .cursor(text);
+        let symbols: Vec<_> = context.doc.symbol_context(cursor).collect();
+        symbols.join(separator.as_str())
+    };
+
+    if !symbols.is_empty() {
+        write(context, format!(" {} ", symbols), None);
+    }
+}
+
+fn flatten_symbols(symbols: Vec<lsp::DocumentSymbol>, flat: &mut Vec<(String, lsp::Range)>) {
+    for symbol in symbols {
+        flat.push((symbol.name, symbol.range));
+        flatten_symbols(symbol.children.unwrap_or_default(), flat);
+    }
+}
+
+/// Requests the symbols of the focused document for the `symbol-context` element, unless the
+/// element isn't shown or they were requested for the current version of the document.
+pub fn request_symbols(editor: &mut Editor, jobs: &mut Jobs) {
+    let shown = {
+        let config = editor.config();
+        let statusline = &config.statusline;
+        [&statusline.left, &statusline.center, &statusline.right]
+            .iter()
+            .any(|elements| elements.contains(&StatusLineElementID::SymbolContext))
+    };
+    if !shown {
+        return;
+    }
+
+    let doc = doc_mut!(editor);
+    let version = doc.version();
+    if doc.symbols_requested == Some(version) {
+        return;
+    }
+    doc.symbols_requested = Some(version);
+
+    let request = doc.language_servers().find_map(|language_server| {
+        language_server
+            .document_symbols(doc.identifier())
+            .map(|future| (language_server.offset_encoding(), future))
+    });
+    let (offset_encoding, future) = match request {
+        Some(request) => request,
+        None => return,
+    };
+    let doc_id = doc.id();
+    jobs.callback(async move {
+        let response: Option<lsp::DocumentSymbolResponse> = serde_json::from_value(future.await?)?;
+        let call = Callback::Editor(Box::new(move |editor: &mut Editor| {
+            let doc = match editor.documents.get_mut(&doc_id) {
+                // the symbols of an older version would be misplaced
+                Some(doc) if doc.version() == version => doc,
+                _ => return,
+            };
+            let mut ranges = Vec::new();
+            match response {
+                Some(lsp::DocumentSymbolResponse::Nested(symbols)) => {
+                    flatten_symbols(symbols, &mut ranges)
+                }
+                Some(lsp::DocumentSymbolResponse::Flat(symbols)) => ranges.extend(
+                    symbols
+                        .into_iter()
+                        .map(|symbol| (symbol.name, symbol.location.range)),
+                ),
+                None => {}
+            }
+            let symbols = ranges
+                .into_iter()
+                .filter_map(|(name, range)| {
+                    let range = lsp_range_to_range(doc.text(), range, offset_encoding)?;
+                    Some(DocumentSymbol {
+                        name,
+                        range: range.from()..range.to(),
+                    })
+                })
+                .collect();
+            doc.set_symbols(symbols);
+        }));
+        Ok(call)
+    });
+}
@@ -0,0 +1,1044 @@
+use super::{
+    picker::MIN_AREA_WIDTH_FOR_PREVIEW,
+    render_document_preview,
+    tree::{patch_selected, selected_style},
+    MouseAction, Tree, TreeItem, TreeKey, TreeOp,
+};
+use crate::{
+    compositor::{Component, Compositor, Context, EventResult},
+    ctrl,
+    job::{Callback, Jobs},
+    key, ui,
+};
+use anyhow::Result;
+use futures_util::future::{BoxFuture, FutureExt};
+use helix_core::{path::get_relative_path, Position, Selection};
+use helix_lsp::{lsp, util::lsp_range_to_range, OffsetEncoding};
+use helix_view::{
+    align_view,
+    editor::{Action, SymbolSort},
+    graphics::{CursorKind, Margin, Rect},
+    input::{Event, KeyEvent},
+    keyboard::KeyCode,
+    tree_keymap::TreeAction,
+    Align, Document, DocumentId, Editor,
+};
+use std::{
+    cmp::Ordering,
+    collections::{hash_map::DefaultHasher, BTreeMap, HashMap},
+    hash::{Hash, Hasher},
+    path::{Path, PathBuf},
+    sync::Arc,
+};
+use tui::{
+    buffer::Buffer as Surface,
+    text::{Span, Spans},
+    widgets::{Block, Borders, Widget},
+};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(super) struct SymbolItem {
+    index: usize,
+    /// The position of the symbol in the sorted tree, depth first.
+    order: usize,
+    /// Derived from the names and kinds of the symbol and its parents, to recognize the symbol
+    /// in the symbols of a newer document version.
+    id: u64,
+    parent: Option<usize>,
+    name: String,
+    detail: Option<String>,
+    kind: lsp::SymbolKind,
+    /// The whole symbol, including its body.
+    pub(super) range: lsp::Range,
+    /// The range to select when jumping to the symbol, usually its name.
+    pub(super) selection_range: lsp::Range,
+}
+
+impl SymbolItem {
+    /// The kind and the name of the symbol, e.g. `fn main`.
+    pub(super) fn label(&self) -> String {
+        format!("{} {}", kind_label(self.kind).0, self.name)
+    }
+}
+
+/// The label shown before symbols of `kind`, the name of its `ui.tree.symbol.*` theme scope and
+/// the syntax scope styling it if the theme doesn't set that scope.
+pub(super) fn kind_label(kind: lsp::SymbolKind) -> (&'static str, &'static str, &'static str) {
+    match kind {
+        lsp::SymbolKind::FILE => ("file", "file", "string.special.path"),
+        lsp::SymbolKind::MODULE => ("mod", "module", "namespace"),
+        lsp::SymbolKind::NAMESPACE => ("ns", "namespace", "namespace"),
+        lsp::SymbolKind::PACKAGE => ("pkg", "package", "namespace"),
+        lsp::SymbolKind::CLASS => ("class", "class", "type"),
+        lsp::SymbolKind::METHOD => ("method", "method", "function.method"),
+        lsp::SymbolKind::PROPERTY => ("prop", "property", "variable.other.member"),
+        lsp::SymbolKind::FIELD => ("field", "field", "variable.other.member"),
+        lsp::SymbolKind::CONSTRUCTOR => ("ctor", "constructor", "constructor"),
+        lsp::SymbolKind::ENUM => ("enum", "enum", "type"),
+        lsp::SymbolKind::INTERFACE => ("iface", "interface", "type"),
+        lsp::SymbolKind::FUNCTION => ("fn", "function", "function"),
+        lsp::SymbolKind::VARIABLE => ("var", "variable", "variable"),
+        lsp::SymbolKind::CONSTANT => ("const", "constant", "constant"),
+        lsp::SymbolKind::STRING => ("str", "string", "string"),
+        lsp::SymbolKind::NUMBER => ("num", "number", "constant.numeric"),
+        lsp::SymbolKind::BOOLEAN => ("bool", "boolean", "constant.builtin.boolean"),
+        lsp::SymbolKind::ARRAY => ("array", "array", "type"),
+        lsp::SymbolKind::OBJECT => ("object", "object", "type"),
+        lsp::SymbolKind::KEY => ("key", "key", "variable.other.member"),
+        lsp::SymbolKind::NULL => ("null", "null", "constant.builtin"),
+        lsp::SymbolKind::ENUM_MEMBER => ("variant", "enum-member", "type.enum.variant"),
+        lsp::SymbolKind::STRUCT => ("struct", "struct", "type"),
+        lsp::SymbolKind::EVENT => ("event", "event", "type"),
+        lsp::SymbolKind::OPERATOR => ("op", "operator", "operator"),
+        lsp::SymbolKind::TYPE_PARAMETER => ("tparam", "type-parameter", "type.parameter"),
+        _ => ("", "unknown", "ui.text"),
+    }
+}
+
+/// The keys of the symbol tree, shown in its footer.
+const FOOTER_HINTS: &str = "[enter]jump [p]peek [bs]back [o]fold [f]ilter [s]ort";
+
+/// The groups of symbol kinds the symbol tree can hide, with the keys toggling them after `f`.
+const KIND_GROUPS: [(char, &str, &[lsp::SymbolKind]); 5] = [
+    (
+        'f',
+        "functions",
+        &[
+            lsp::SymbolKind::FUNCTION,
+            lsp::SymbolKind::METHOD,
+            lsp::SymbolKind::CONSTRUCTOR,
+            lsp::SymbolKind::OPERATOR,
+        ],
+    ),
+    (
+        't',
+        "types",
+        &[
+            lsp::SymbolKind::CLASS,
+            lsp::SymbolKind::STRUCT,
+            lsp::SymbolKind::ENUM,
+            lsp::SymbolKind::INTERFACE,
+            lsp::SymbolKind::TYPE_PARAMETER,
+        ],
+    ),
+    (
+        'v',
+        "variables",
+        &[
+            lsp::SymbolKind::VARIABLE,
+            lsp::SymbolKind::CONSTANT,
+            lsp::SymbolKind::FIELD,
+            lsp::SymbolKind::PROPERTY,
+            lsp::SymbolKind::ENUM_MEMBER,
+        ],
+    ),
+    (
+        'm',
+        "modules",
+        &[
+            lsp::SymbolKind::FILE,
+            lsp::SymbolKind::MODULE,
+            lsp::SymbolKind::NAMESPACE,
+            lsp::SymbolKind::PACKAGE,
+        ],
+    ),
+    (
+        'o',
+        "others",
+        &[
+            lsp::SymbolKind::STRING,
+            lsp::SymbolKind::NUMBER,
+            lsp::SymbolKind::BOOLEAN,
+            lsp::SymbolKind::ARRAY,
+            lsp::SymbolKind::OBJECT,
+            lsp::SymbolKind::KEY,
+            lsp::SymbolKind::NULL,
+            lsp::SymbolKind::EVENT,
+        ],
+    ),
+];
+
+/// The names of the kind groups all of whose kinds are hidden.
+fn hidden_groups(hidden: &[lsp::SymbolKind]) -> Vec<&'static str> {
+    KIND_GROUPS
+        .iter()
+        .filter(|(_, _, kinds)| kinds.iter().all(|kind| hidden.contains(kind)))
+        .map(|(_, name, _)| *name)
+        .collect()
+}
+
+/// The symbols of `items` whose kind isn't hidden. The children of hidden symbols move up to
+/// their closest shown ancestor.
+fn shown_symbols(items: &[SymbolItem], hidden: &[lsp::SymbolKind]) -> Vec<SymbolItem> {
+    let mut shown = Vec::with_capacity(items.len());
+    for item in items.iter().filter(|item| !hidden.contains(&item.kind)) {
+        let mut item = item.clone();
+        // items are numbered by their position, so parents can be looked up by index
+        while let Some(parent) = item
+            .parent
+            .filter(|&parent| hidden.contains(&items[parent].kind))
+        {
+            item.parent = items[parent].parent;
+        }
+        shown.push(item);
+    }
+    shown
+}
+
+/// The rank of the group of `kind` when sorting by kind: modules, types, functions, variables,
+/// then the others.
+fn kind_rank(kind: lsp::SymbolKind) -> usize {
+    const RANKS: [char; 5] = ['m', 't', 'f', 'v', 'o'];
+    KIND_GROUPS
+        .iter()
+        .find(|(_, _, kinds)| kinds.contains(&kind))
+        .and_then(|(key, ..)| RANKS.iter().position(|rank| rank == key))
+        .unwrap_or(RANKS.len())
+}
+
+/// Orders `items` depth first with the children of each symbol sorted by `sort`.
+fn sort_symbols(items: Vec<SymbolItem>, sort: SymbolSort) -> Vec<SymbolItem> {
+    fn visit(
+        parent: Option<usize>,
+        children: &mut HashMap<Option<usize>, Vec<SymbolItem>>,
+        sorted: &mut Vec<SymbolItem>,
+    ) {
+        for mut item in children.remove(&parent).unwrap_or_default() {
+            item.order = sorted.len();
+            let index = item.index;
+            sorted.push(item);
+            visit(Some(index), children, sorted);
+        }
+    }
+
+    let len = items.len();
+    let mut children: HashMap<Option<usize>, Vec<SymbolItem>> = HashMap::new();
+    for item in items {
+        children.entry(item.parent).or_default().push(item);
+    }
+    let position = |item: &SymbolItem| (item.range.start, item.index);
+    for siblings in children.values_mut() {
+        match sort {
+            SymbolSort::Position => siblings.sort_by_key(position),
+            SymbolSort::Name => siblings.sort_by_cached_key(|item| {
+                (item.name.to_lowercase(), item.range.start, item.index)
+            }),
+            SymbolSort::Kind => siblings.sort_by_key(|item| (kind_rank(item.kind), position(item))),
+        }
+    }
+    let mut sorted = Vec::with_capacity(len);
+    visit(None, &mut children, &mut sorted);
+    sorted
+}
+
+impl TreeItem for SymbolItem {
+    type Params = ();
+
+    fn text(&self, cx: &mut Context, selected: bool, _params: &mut ()) -> Spans {
+        let mut style = cx.editor.theme.get("ui.text");
+        if selected {
+            style = style.patch(selected_style(cx));
+        }
+        Spans::from(Span::styled(self.name.clone(), style))
+    }
+
+    /// The kind and the detail of the symbol.
+    fn columns(&self, cx: &mut Context, selected: bool, _params: &mut ()) -> Vec<Spans> {
+        let theme = &cx.editor.theme;
+        let (label, name, fallback) = kind_label(self.kind);
+        let mut kind_style = theme
+            .try_get(&format!("ui.tree.symbol.{}", name))
+            .unwrap_or_else(|| theme.get(fallback));
+        let mut detail_style = theme.get("comment");
+        if selected {
+            let patch = selected_style(cx);
+            kind_style = kind_style.patch(patch);
+            detail_style = detail_style.patch(patch);
+        }
+        vec![
+            Spans::from(Span::styled(label, kind_style)),
+            Spans::from(Span::styled(
+                self.detail.clone().unwrap_or_default(),
+                detail_style,
+            )),
+        ]
+    }
+
+    fn is_child(&self, other: &Self) -> bool {
+        self.parent == Some(other.index)
+    }
+
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.order.cmp(&other.order)
+    }
+
+    fn node_id(&self) -> Option<u64> {
+        Some(self.id)
+    }
+}
+
+fn symbol_id(parent: Option<u64>, name: &str, kind: lsp::SymbolKind) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    // the name of the kind, as `SymbolKind` isn't `Hash`
+    (parent, name, kind_label(kind).1).hash(&mut hasher);
+    hasher.finish()
+}
+
+fn flatten(items: &mut Vec<SymbolItem>, parent: Option<usize>, symbol: lsp::DocumentSymbol) {
+    let index = items.len();
+    let parent_id = parent.map(|parent| items[parent].id);
+    items.push(SymbolItem {
+        index,
+        order: index,
+        id: symbol_id(parent_id, &symbol.name, symbol.kind),
+        parent,
+        name: symbol.name,
+        detail: symbol.detail.filter(|detail| !detail.is_empty()),
+        kind: symbol.kind,
+        range: symbol.range,
+        selection_range: symbol.selection_range,
+    });
+    for child in symbol.children.into_iter().flatten() {
+        flatten(items, Some(index), child);
+    }
+}
+
+fn flat_symbol_item(index: usize, symbol: lsp::SymbolInformation) -> SymbolItem {
+    SymbolItem {
+        index,
+        order: index,
+        id: symbol_id(None, &symbol.name, symbol.kind),
+        parent: None,
+        name: symbol.name,
+        detail: symbol.container_name,
+        kind: symbol.kind,
+        range: symbol.location.range,
+        selection_range: symbol.location.range,
+    }
+}
+
+/// The symbols of a document as tree items, numbered depth first.
+pub(super) fn symbol_items(symbols: lsp::DocumentSymbolResponse) -> Vec<SymbolItem> {
+    let mut items = Vec::new();
+    match symbols {
+        lsp::DocumentSymbolResponse::Nested(symbols) => {
+            for symbol in symbols {
+                flatten(&mut items, None, symbol);
+            }
+        }
+        lsp::DocumentSymbolResponse::Flat(symbols) => nest_flat_symbols(&mut items, symbols),
+    }
+    items
+}
+
+/// Nests the flat symbols sent by servers without hierarchical symbols: a symbol is a child of
+/// the innermost symbol whose range contains its range. Symbols outside of any other, like
+/// those whose range only covers their name, are children of the last symbol before them
+/// named like their container, if there is one.
+fn nest_flat_symbols(items: &mut Vec<SymbolItem>, mut symbols: Vec<lsp::SymbolInformation>) {
+    // enclosing symbols come before the symbols they contain
+    symbols.sort_by(|a, b| {
+        let (a, b) = (a.location.range, b.location.range);
+        a.start.cmp(&b.start).then(b.end.cmp(&a.end))
+    });
+
+    let mut children = vec![Vec::new(); symbols.len()];
+    let mut roots = Vec::new();
+    // the symbols containing the current one, innermost last
+    let mut enclosing: Vec<usize> = Vec::new();
+    for (index, symbol) in symbols.iter().enumerate() {
+        let range = symbol.location.range;
+        while let Some(&last) = enclosing.last() {
+            let outer = symbols[last].location.range;
+            if outer != range && contains(&outer, range.start) && contains(&outer, range.end) {
+                break;
+            }
+            enclosing.pop();
+        }
+        let parent = enclosing.last().copied().or_else(|| {
+            let container = symbol.container_name.as_deref()?;
+            symbols[..index]
+                .iter()
+                .rposition(|other| other.name == container)
+        });
+        match parent {
+            Some(parent) => children[parent].push(index),
+            None => roots.push(index),
+        }
+        enclosing.push(index);
+    }
+
+    let mut symbols: Vec<_> = symbols.into_iter().map(Some).collect();
+    for root in roots {
+        push_nested(items, None, root, &mut symbols, &children);
+    }
+}
+
+/// Adds the flat symbol at `index` and its children to `items`, depth first like [`flatten`].
+fn push_nested(
+    items: &mut Vec<SymbolItem>,
+    parent: Option<usize>,
+    index: usize,
+    symbols: &mut [Option<lsp::SymbolInformation>],
+    children: &[Vec<usize>],
+) {
+    let symbol = match symbols[index].take() {
+        Some(symbol) => symbol,
+        None => return,
+    };
+    let item_index = items.len();
+    let parent_id = parent.map(|parent| items[parent].id);
+    items.push(SymbolItem {
+        index: item_index,
+        order: item_index,
+        id: symbol_id(parent_id, &symbol.name, symbol.kind),
+        parent,
+        // nested symbols show their container as their parent already
+        detail: symbol.container_name.filter(|_| parent.is_none()),
+        name: symbol.name,
+        kind: symbol.kind,
+        range: symbol.location.range,
+        selection_range: symbol.location.range,
+    });
+    for &child in &children[index] {
+        push_nested(items, Some(item_index), child, symbols, children);
+    }
+}
+
+pub(super) fn contains(range: &lsp::Range, pos: lsp::Position) -> bool {
+    range.start <= pos && pos <= range.end
+}
+
+/// Shows `range` of the file at `path` while the panel stays open. The location shown before is
+/// pushed onto the peek stack instead of the jumplist.
+pub(super) fn peek_location(
+    editor: &mut Editor,
+    path: &Path,
+    range: lsp::Range,
+    offset_encoding: OffsetEncoding,
+) {
+    let doc_id = match editor.open(path, Action::Load) {
+        Ok(doc_id) => doc_id,
+        Err(e) => {
+            editor.set_error(format!("Failed to open {}: {}", path.display(), e));
+            return;
+        }
+    };
+    let doc = doc!(editor, &doc_id);
+    if let Some(range) = lsp_range_to_range(doc.text(), range, offset_encoding) {
+        // put the cursor on the start of the symbol
+        editor.peek(doc_id, Selection::single(range.head, range.anchor));
+    }
+}
+
+/// Returns to the location shown before the last peek.
+pub(super) fn peek_back(editor: &mut Editor) {
+    if !editor.pop_peek() {
+        editor.set_status("No peek to go back from");
+    }
+}
+
+/// The document symbols reported by a language server, nested as a tree, next to a preview of
+/// the selected symbol. The symbols are requested again once the document changed and the
+/// edits pause, and replace the symbols in place. Symbols of the kinds hidden with `f` are
+/// left out of the tree.
+pub struct SymbolTree {
+    doc_id: DocumentId,
+    title: String,
+    tree: Tree<SymbolItem>,
+    /// All symbols, including the hidden ones.
+    symbols: Vec<SymbolItem>,
+    /// Whether `f` was typed and the next key picks the kinds to show.
+    filter_pending: bool,
+    sort: SymbolSort,
+    offset_encoding: OffsetEncoding,
+    /// The document version symbols were last requested for.
+    requested: i32,
+    /// The changed document version waiting for the idle timeout before its symbols are
+    /// requested.
+    waiting: Option<i32>,
+}
+
+impl SymbolTree {
+    /// Returns `None` if there are no symbols. The innermost shown symbol containing `cursor`
+    /// is selected.
+    pub fn new(
+        doc: &Document,
+        symbols: lsp::DocumentSymbolResponse,
+        cursor: lsp::Position,
+        offset_encoding: OffsetEncoding,
+        hidden: &[lsp::SymbolKind],
+        sort: SymbolSort,
+    ) -> Option<Self> {
+        let symbols = symbol_items(symbols);
+        if symbols.is_empty() {
+            return None;
+        }
+
+        let items = sort_symbols(shown_symbols(&symbols, hidden), sort);
+        // symbols are flattened depth first, so the last match is the innermost one
+        let selected = items
+            .iter()
+            .rposition(|item| contains(&item.range, cursor))
+            .unwrap_or_default();
+        let mut tree = Tree::build_tree(items).with_footer_hints(FOOTER_HINTS);
+        tree.move_down(selected);
+        Some(Self {
+            doc_id: doc.id(),
+            title: format!("Symbols — {}", doc.display_name()),
+            tree,
+            symbols,
+            filter_pending: false,
+            sort,
+            offset_encoding,
+            requested: doc.version(),
+            waiting: None,
+        })
+    }
+
+    /// Restores the folds and the selected symbol of the last symbol tree of the document.
+    pub fn restore_state(&mut self, editor: &Editor) {
+        if let Some(state) = editor.symbol_tree_states.get(&self.doc_id) {
+            self.tree.restore_state(state);
+        }
+    }
+
+    /// Replaces the symbols with those of a newer document version, keeping the folds, the
+    /// marks and the selected symbol. The tree is left alone if the symbols didn't change.
+    pub fn replace_symbols(
+        &mut self,
+        symbols: lsp::DocumentSymbolResponse,
+        offset_encoding: OffsetEncoding,
+        hidden: &[lsp::SymbolKind],
+    ) {
+        self.offset_encoding = offset_encoding;
+        let symbols = symbol_items(symbols);
+        if symbols == self.symbols {
+            return;
+        }
+        self.symbols = symbols;
+        self.tree.replace_with_new_items(self.shown_symbols(hidden));
+    }
+
+    /// The symbols which aren't `hidden`, sorted.
+    fn shown_symbols(&self, hidden: &[lsp::SymbolKind]) -> Vec<SymbolItem> {
+        sort_symbols(shown_symbols(&self.symbols, hidden), self.sort)
+    }
+
+    /// Sorts the symbols the next way, keeping the selected symbol.
+    fn cycle_sort(&mut self, editor: &mut Editor) {
+        self.sort = self.sort.next();
+        self.tree
+            .replace_with_new_items(self.shown_symbols(&editor.hidden_symbol_kinds));
+        editor.set_status(format!("Sorted by {}", self.sort));
+    }
+
+    /// Shows the symbols of the kinds that aren't `hidden`. If the selected symbol gets hidden,
+    /// the innermost shown symbol containing it is selected.
+    fn set_hidden(&mut self, hidden: &[lsp::SymbolKind]) {
+        let selected = (!self.tree.is_empty()).then(|| self.tree.current_item().range.start);
+        self.tree.replace_with_new_items(self.shown_symbols(hidden));
+        if let Some(pos) = selected {
+            if !self.tree.is_empty() && self.tree.current_item().range.start != pos {
+                self.tree.select_last(|item| contains(&item.range, pos));
+            }
+        }
+    }
+
+    /// Handles the key typed after `f`: a group key toggles its kinds, the same key with shift
+    /// shows only its kinds and `a` shows all kinds.
+    fn filter_kinds(&mut self, key: KeyEvent, editor: &mut Editor) {
+        let hidden = &mut editor.hidden_symbol_kinds;
+        let ch = match key.code {
+            KeyCode::Char(ch) => ch,
+            _ => return,
+        };
+        if ch == 'a' {
+            hidden.clear();
+        } else {
+            let group = KIND_GROUPS
+                .iter()
+                .find(|(group_key, ..)| *group_key == ch.to_ascii_lowercase());
+            let kinds = match group {
+                Some((_, _, kinds)) => *kinds,
+                None => return,
+            };
+            if ch.is_ascii_uppercase() {
+                hidden.clear();
+                for (_, _, other) in KIND_GROUPS.iter().filter(|(_, _, other)| *other != kinds) {
+                    hidden.extend_from_slice(other);
+                }
+            } else if kinds.iter().all(|kind| hidden.contains(kind)) {
+                hidden.retain(|kind| !kinds.contains(kind));
+            } else {
+                hidden.extend_from_slice(kinds);
+            }
+        }
+
+        let groups = hidden_groups(hidden);
+        if groups.is_empty() {
+            editor.set_status("Showing all symbols");
+        } else {
+            editor.set_status(format!("Hiding {}", groups.join(", ")));
+        }
+        self.set_hidden(&editor.hidden_symbol_kinds);
+    }
+
+    /// Restarts the idle timer once the document changed, so that its symbols are requested
+    /// when the edits pause instead of on every change.
+    fn wait_for_idle(&mut self, editor: &mut Editor) {
+        let version = match editor.document(self.doc_id) {
+            Some(doc) => doc.version(),
+            None => return,
+        };
+        if version != self.requested && self.waiting != Some(version) {
+            self.waiting = Some(version);
+            editor.reset_idle_timer();
+        }
+    }
+
+    /// Requests the symbols again if the document changed since they were last requested. The
+    /// response is handed to the open symbol tree.
+    fn refresh(&mut self, editor: &Editor, jobs: &mut Jobs) {
+        let doc = match editor.document(self.doc_id) {
+            Some(doc) => doc,
+            None => return,
+        };
+        if self.requested == doc.version() {
+            return;
+        }
+        self.requested = doc.version();
+
+        let request = doc.language_servers().find_map(|language_server| {
+            language_server
+                .document_symbols(doc.identifier())
+                .map(|future| (language_server.offset_encoding(), future))
+        });
+        let (offset_encoding, future) = match request {
+            Some(request) => request,
+            None => return,
+        };
+        let (doc_id, version) = (self.doc_id, self.requested);
+        jobs.callback(async move {
+            let response: Option<lsp::DocumentSymbolResponse> =
+                serde_json::from_value(future.await?)?;
+            let call = Callback::EditorCompositor(Box::new(
+                move |editor: &mut Editor, compositor: &mut Compositor| {
+                    let tree = compositor
+                        .find::<ui::overlay::Overlay<SymbolTree>>()
+                        .map(|overlay| &mut overlay.content)
+                        // a newer version was requested in the meantime
+                        .filter(|tree| tree.doc_id == doc_id && tree.requested == version);
+                    if let (Some(tree), Some(symbols)) = (tree, response) {
+                        let hidden = &editor.hidden_symbol_kinds;
+                        tree.replace_symbols(symbols, offset_encoding, hidden);
+                    }
+                },
+            ));
+            Ok(call)
+        });
+    }
+
+    /// Closes the tree and selects the symbol in the document.
+    /// Selects the symbol in the document while the tree stays open.
+    fn peek(&self, editor: &mut Editor) {
+        if self.tree.is_empty() {
+            return;
+        }
+        let range = self.tree.current_item().selection_range;
+        let doc = match editor.document(self.doc_id) {
+            Some(doc) => doc,
+            None => return,
+        };
+        if let Some(range) = lsp_range_to_range(doc.text(), range, self.offset_encoding) {
+            // put the cursor on the start of the symbol
+            editor.peek(self.doc_id, Selection::single(range.head, range.anchor));
+        }
+    }
+
+    fn jump(&self) -> EventResult {
+        if self.tree.is_empty() {
+            return EventResult::Consumed(None);
+        }
+        let range = self.tree.current_item().selection_range;
+        let doc_id = self.doc_id;
+        let offset_encoding = self.offset_encoding;
+        EventResult::Consumed(Some(Box::new(move |compositor: &mut Compositor, cx| {
+            compositor.pop();
+            cx.editor.confirm_peeks();
+            let (view, doc) = current!(cx.editor);
+            if doc.id() != doc_id {
+                return;
+            }
+            if let Some(range) = lsp_range_to_range(doc.text(), range, offset_encoding) {
+                view.jumps.push((doc.id(), doc.selection(view.id).clone()));
+                // put the cursor on the start of the symbol
+                doc.set_selection(view.id, Selection::single(range.head, range.anchor));
+                align_view(doc, view, Align::Center);
+            }
+        })))
+    }
+}
+
+impl Component for SymbolTree {
+    fn handle_event(&mut self, event: &Event, cx: &mut Context) -> EventResult {
+        let (action, count) = match event {
+            Event::Key(key_event) if self.filter_pending => {
+                self.filter_pending = false;
+                self.filter_kinds(*key_event, cx.editor);
+                return EventResult::Consumed(None);
+            }
+            Event::Key(key!(Esc) | key!('q') | ctrl!('c')) => {
+                return EventResult::Consumed(Some(Box::new(|compositor: &mut Compositor, _| {
+                    compositor.pop();
+                })))
+            }
+            Event::Key(key_event) => match self.tree.key_action(*key_event, cx, &mut ()) {
+                TreeKey::Action(action, count) => (action, count),
+                TreeKey::Consumed | TreeKey::Ignored => return EventResult::Consumed(None),
+            },
+            Event::Mouse(event) => match self.tree.handle_mouse(event, cx, &mut ()) {
+                MouseAction::Activate => (TreeAction::Activate, 0),
+                MouseAction::Handled => return EventResult::Consumed(None),
+                MouseAction::Ignored => return EventResult::Ignored(None),
+            },
+            Event::Resize(..) => return EventResult::Consumed(None),
+            Event::IdleTimeout => {
+                self.refresh(cx.editor, cx.jobs);
+                // the editor handles the timeout as well
+                return EventResult::Ignored(None);
+            }
+            _ => return EventResult::Ignored(None),
+        };
+
+        match action {
+            TreeAction::Activate => return self.jump(),
+            TreeAction::Peek => self.peek(cx.editor),
+            TreeAction::PeekBack => peek_back(cx.editor),
+            TreeAction::Filter => self.filter_pending = true,
+            TreeAction::Sort => self.cycle_sort(cx.editor),
+            action => self.tree.run_action(action, count, cx, &mut ()),
+        }
+        EventResult::Consumed(None)
+    }
+
+    fn render(&mut self, area: Rect, surface: &mut Surface, cx: &mut Context) {
+        self.wait_for_idle(cx.editor);
+        // remembered for the next symbol tree of the document
+        cx.editor
+            .symbol_tree_states
+            .insert(self.doc_id, self.tree.state());
+
+        let background = cx.editor.theme.get("ui.background");
+        surface.clear_with(area, background);
+
+        let render_preview = area.width > MIN_AREA_WIDTH_FOR_PREVIEW;
+        let tree_width = if render_preview {
+            area.width / 2
+        } else {
+            area.width
+        };
+        let tree_area = area.with_width(tree_width);
+
+        let hidden = hidden_groups(&cx.editor.hidden_symbol_kinds);
+        if hidden.is_empty() {
+            self.tree.set_title(&self.title);
+        } else {
+            self.tree
+                .set_title(format!("{} (hiding {})", self.title, hidden.join(", ")));
+        }
+        self.tree.set_footer_hints(if self.filter_pending {
+            "[f]unctions [t]ypes [v]ariables [m]odules [o]thers [a]ll, shift: only"
+        } else {
+            FOOTER_HINTS
+        });
+        let rows_area = self.tree.render_framed(tree_area, surface, cx, &mut ());
+        if self.tree.is_empty() {
+            surface.set_stringn(
+                rows_area.x,
+                rows_area.y,
+                "All symbols are hidden, [fa] shows them",
+                rows_area.width as usize,
+                cx.editor.theme.get("ui.text"),
+            );
+        }
+
+        if !render_preview || self.tree.is_empty() {
+            return;
+        }
+        let preview_area = area.clip_left(tree_width);
+        let block = Block::default().borders(Borders::ALL);
+        let inner = block.inner(preview_area).inner(&Margin::horizontal(1));
+        block.render(preview_area, surface);
+
+        // the whole symbol is highlighted, centered if it fits
+        let range = self.tree.current_item().range;
+        let lines = (range.start.line as usize, range.end.line as usize);
+        if let Some(doc) = cx.editor.document(self.doc_id) {
+            render_document_preview(doc, Some(lines), inner, surface, cx.editor);
+        }
+    }
+
+    fn cursor(&self, _area: Rect, _editor: &Editor) -> (Option<Position>, CursorKind) {
+        (None, CursorKind::Hidden)
+    }
+
+    fn is_panel(&self) -> bool {
+        true
+    }
+}
+
+#[derive(Debug, Clone)]
+enum WorkspaceItem {
+    File {
+        path: PathBuf,
+        uri: lsp::Url,
+        /// The workspace symbols found in the file, shown if the language server can't list the
+        /// symbols of the file.
+        symbols: Vec<SymbolItem>,
+        language_server: Arc<helix_lsp::Client>,
+        loaded: bool,
+    },
+    Symbol {
+        path: PathBuf,
+        symbol: SymbolItem,
+    },
+}
+
+fn symbols_of_file(path: &Path, symbols: Vec<SymbolItem>) -> Vec<WorkspaceItem> {
+    symbols
+        .into_iter()
+        .map(|symbol| WorkspaceItem::Symbol {
+            path: path.to_path_buf(),
+            symbol,
+        })
+        .collect()
+}
+
+impl TreeItem for WorkspaceItem {
+    type Params = ();
+
+    fn text(&self, cx: &mut Context, selected: bool, params: &mut ()) -> Spans {
+        let (path, symbols) = match self {
+            Self::File { path, symbols, .. } => (path, symbols),
+            Self::Symbol { symbol, .. } => return symbol.text(cx, selected, params),
+        };
+        let theme = &cx.editor.theme;
+        let mut spans = vec![
+            Span::styled(
+                get_relative_path(path).to_string_lossy().into_owned(),
+                theme.get("ui.text.directory"),
+            ),
+            Span::styled(format!(" {}", symbols.len()), theme.get("comment")),
+        ];
+        if selected {
+            patch_selected(cx, &mut spans);
+        }
+        Spans::from(spans)
+    }
+
+    fn columns(&self, cx: &mut Context, selected: bool, params: &mut ()) -> Vec<Spans> {
+        match self {
+            Self::File { .. } => Vec::new(),
+            Self::Symbol { symbol, .. } => symbol.columns(cx, selected, params),
+        }
+    }
+
+    fn is_child(&self, other: &Self) -> bool {
+        match (self, other) {
+            (
+                Self::Symbol { path, symbol },
+                Self::Symbol {
+                    path: other_path,
+                    symbol: other_symbol,
+                },
+            ) => path == other_path && symbol.is_child(other_symbol),
+            _ => false,
+        }
+    }
+
+    fn cmp(&self, other: &Self) -> Ordering {
+        match (self, other) {
+            (Self::File { path, .. }, Self::File { path: other, .. }) => path.cmp(other),
+            (Self::Symbol { symbol, .. }, Self::Symbol { symbol: other, .. }) => {
+                TreeItem::cmp(symbol, other)
+            }
+            (Self::File { .. }, Self::Symbol { .. }) => Ordering::Less,
+            (Self::Symbol { .. }, Self::File { .. }) => Ordering::Greater,
+        }
+    }
+
+    fn get_childs(&self) -> Result<Vec<Self>> {
+        match self {
+            Self::File { path, symbols, .. } => Ok(symbols_of_file(path, symbols.clone())),
+            Self::Symbol { .. } => Ok(vec![]),
+        }
+    }
+
+    /// Requests the symbols of a file from the language server.
+    fn load_childs(&self) -> Option<BoxFuture<'static, Result<Vec<Self>>>> {
+        let (path, uri, symbols, language_server) = match self {
+            Self::File {
+                path,
+                uri,
+                symbols,
+                language_server,
+                ..
+            } => (path.clone(), uri, symbols.clone(), language_server),
+            Self::Symbol { .. } => return None,
+        };
+        let future =
+            language_server.document_symbols(lsp::TextDocumentIdentifier::new(uri.clone()))?;
+        Some(
+            async move {
+                let response: Option<lsp::DocumentSymbolResponse> =
+                    serde_json::from_value(future.await?)?;
+                let items = response.map(symbol_items).unwrap_or_default();
+                if items.is_empty() {
+                    return Ok(symbols_of_file(&path, symbols));
+                }
+                Ok(symbols_of_file(&path, items))
+            }
+            .boxed(),
+        )
+    }
+}
+
+/// The workspace symbols reported by a language server, grouped by file. The symbols of a file
+/// are requested when it is expanded.
+pub struct WorkspaceSymbolTree {
+    tree: Tree<WorkspaceItem>,
+    offset_encoding: OffsetEncoding,
+}
+
+impl WorkspaceSymbolTree {
+    /// Returns `None` if there are no symbols in files.
+    pub fn new(
+        symbols: Vec<lsp::SymbolInformation>,
+        language_server: Arc<helix_lsp::Client>,
+    ) -> Option<Self> {
+        let mut files: BTreeMap<PathBuf, (lsp::Url, Vec<SymbolItem>)> = BTreeMap::new();
+        for symbol in symbols {
+            let uri = symbol.location.uri.clone();
+            let path = match uri.to_file_path() {
+                Ok(path) => path,
+                Err(_) => continue,
+            };
+            let (_, items) = files.entry(path).or_insert_with(|| (uri, Vec::new()));
+            items.push(flat_symbol_item(items.len(), symbol));
+        }
+        if files.is_empty() {
+            return None;
+        }
+
+        let offset_encoding = language_server.offset_encoding();
+        let items = files
+            .into_iter()
+            .map(|(path, (uri, symbols))| WorkspaceItem::File {
+                path,
+                uri,
+                symbols,
+                language_server: language_server.clone(),
+                loaded: false,
+            })
+            .collect();
+        let tree = Tree::build_tree(items)
+            .with_title("Workspace symbols")
+            .with_footer_hints("[enter]jump [p]peek [bs]back [o]fold")
+            .with_enter_fn(|item, _cx, _params| match item {
+                WorkspaceItem::File { loaded: true, .. } => TreeOp::Restore,
+                WorkspaceItem::File { loaded, .. } => {
+                    *loaded = true;
+                    TreeOp::LoadChildsAndInsert
+                }
+                WorkspaceItem::Symbol { .. } => TreeOp::Noop,
+            });
+        Some(Self {
+            tree,
+            offset_encoding,
+        })
+    }
+
+    /// Closes the tree and opens the file of the symbol with the symbol selected.
+    fn jump(&self) -> EventResult {
+        let (path, range) = match self.tree.current_item() {
+            WorkspaceItem::Symbol { path, symbol } => (path.clone(), symbol.selection_range),
+            WorkspaceItem::File { .. } => return EventResult::Consumed(None),
+        };
+        let offset_encoding = self.offset_encoding;
+        EventResult::Consumed(Some(Box::new(move |compositor: &mut Compositor, cx| {
+            compositor.pop();
+            cx.editor.confirm_peeks();
+            let (view, doc) = current!(cx.editor);
+            view.jumps.push((doc.id(), doc.selection(view.id).clone()));
+            if let Err(e) = cx.editor.open(&path, Action::Replace) {
+                cx.editor
+                    .set_error(format!("Failed to open {}: {}", path.display(), e));
+                return;
+            }
+            let (view, doc) = current!(cx.editor);
+            if let Some(range) = lsp_range_to_range(doc.text(), range, offset_encoding) {
+                // put the cursor on the start of the symbol
+                doc.set_selection(view.id, Selection::single(range.head, range.anchor));
+                align_view(doc, view, Align::Center);
+            }
+        })))
+    }
+}
+
+impl Component for WorkspaceSymbolTree {
+    fn handle_event(&mut self, event: &Event, cx: &mut Context) -> EventResult {
+        let (action, count) = match event {
+            Event::Key(key!(Esc) | key!('q') | ctrl!('c')) => {
+                return EventResult::Consumed(Some(Box::new(|compositor: &mut Compositor, _| {
+                    compositor.pop();
+                })))
+            }
+            Event::Key(key_event) => match self.tree.key_action(*key_event, cx, &mut ()) {
+                TreeKey::Action(action, count) => (action, count),
+                TreeKey::Consumed | TreeKey::Ignored => return EventResult::Consumed(None),
+            },
+            Event::Mouse(event) => match self.tree.handle_mouse(event, cx, &mut ()) {
+                MouseAction::Activate => (TreeAction::Activate, 0),
+                MouseAction::Handled => return EventResult::Consumed(None),
+                MouseAction::Ignored => return EventResult::Ignored(None),
+            },
+            Event::Resize(..) => return EventResult::Consumed(None),
+            _ => return EventResult::Ignored(None),
+        };
+
+        match action {
+            TreeAction::Activate => {
+                if let WorkspaceItem::File { .. } = self.tree.current_item() {
+                    self.tree.on_enter(cx, &mut ());
+                } else {
+                    return self.jump();
+                }
+            }
+            TreeAction::Peek => {
+                if let WorkspaceItem::Symbol { path, symbol } = self.tree.current_item() {
+                    let (path, range) = (path.clone(), symbol.selection_range);
+                    peek_location(cx.editor, &path, range, self.offset_encoding);
+                }
+            }
+            TreeAction::PeekBack => peek_back(cx.editor),
+            action => self.tree.run_action(action, count, cx, &mut ()),
+        }
+        EventResult::Consumed(None)
+    }
+
+    fn render(&mut self, area: Rect, surface: &mut Surface, cx: &mut Context) {
+        let background = cx.editor.theme.get("ui.background");
+        surface.clear_with(area, background);
+
+        self.tree.render_framed(area, surface, cx, &mut ());
+    }
+
+    fn cursor(&self, _area: Rect, _editor: &Editor) -> (Option<Position>, CursorKind) {
+        (None, CursorKind::Hidden)
+    }
+
+    fn is_panel(&self) -> bool {
+        true
+    }
+}
@@ -0,0 +1,274 @@
+use super::{tree::selected_style, MouseAction, Tree, TreeItem};
+use crate::{
+    compositor::{Component, Compositor, Context, EventResult},
+    ctrl, key,
+};
+use helix_core::{path::get_relative_path, Position};
+use helix_view::{
+    document::{SyntaxMetrics, SCRATCH_BUFFER_NAME},
+    graphics::{CursorKind, Rect},
+    input::Event,
+    DocumentId, Editor,
+};
+use std::{
+    cmp::Ordering,
+    collections::{hash_map::DefaultHasher, VecDeque},
+    hash::{Hash, Hasher},
+    time::Duration,
+};
+use tui::{
+    buffer::Buffer as Surface,
+    text::{Span, Spans},
+    widgets::{Block, Borders, Widget},
+};
+
+const SPARKS: [char; 8] = ['▁', '▂', '▃', '▄', '▅', '▆', '▇', '█'];
+
+/// Draws the samples as bars scaled to the largest one.
+fn sparkline(samples: &VecDeque<Duration>) -> String {
+    let max = samples.iter().max().copied().unwrap_or_default();
+    samples
+        .iter()
+        .map(|sample| {
+            if max.is_zero() {
+                return SPARKS[0];
+            }
+            let level = sample.as_secs_f64() / max.as_secs_f64() * (SPARKS.len() - 1) as f64;
+            SPARKS[level.round() as usize]
+        })
+        .collect()
+}
+
+fn format_duration(duration: Duration) -> String {
+    format!("{:.2}ms", duration.as_secs_f64() * 1000.0)
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+enum Metric {
+    Parse,
+    Query,
+}
+
+#[derive(Debug, Clone)]
+enum MetricItem {
+    Document {
+        id: DocumentId,
+        name: String,
+        layers: usize,
+    },
+    Metric {
+        id: DocumentId,
+        metric: Metric,
+        samples: VecDeque<Duration>,
+    },
+}
+
+impl MetricItem {
+    fn sort_key(&self) -> (DocumentId, Option<Metric>) {
+        match self {
+            Self::Document { id, .. } => (*id, None),
+            Self::Metric { id, metric, .. } => (*id, Some(*metric)),
+        }
+    }
+}
+
+impl TreeItem for MetricItem {
+    type Params = ();
+
+    fn text(&self, cx: &mut Context, selected: bool, _params: &mut ()) -> Spans {
+        let theme = &cx.editor.theme;
+        let mut span = match self {
+            Self::Document { name, .. } => Span::styled(name.clone(), theme.get("ui.text.focus")),
+            Self::Metric { metric, .. } => {
+                let label = match metric {
+                    Metric::Parse => "parse",
+                    Metric::Query => "highlight queries",
+                };
+                Span::styled(label, theme.get("ui.text"))
+            }
+        };
+        if selected {
+            span.style = span.style.patch(selected_style(cx));
+        }
+        Spans::from(span)
+    }
+
+    /// The injections of a document, and the last and the average duration and a sparkline of
+    /// the samples of a metric.
+    fn columns(&self, cx: &mut Context, selected: bool, _params: &mut ()) -> Vec<Spans> {
+        let theme = &cx.editor.theme;
+        let mut style = theme.get("comment");
+        let mut spark_style = theme.get("constant");
+        if selected {
+            style = style.patch(selected_style(cx));
+            spark_style = spark_style.patch(selected_style(cx));
+        }
+        match self {
+            Self::Document { layers, .. } => {
+                let injections = layers.saturating_sub(1);
+                let plural = if injections == 1 { "" } else { "s" };
+                vec![Spans::from(Span::styled(
+                    format!("{} injection{}", injections, plural),
+                    style,
+                ))]
+            }
+            Self::Metric { samples, .. } => {
+                let last = samples.back().copied().unwrap_or_default();
+                let total: Duration = samples.iter().sum();
+                let average = total / samples.len().max(1) as u32;
+                vec![
+                    Spans::from(Span::styled(
+                        format!("last {}", format_duration(last)),
+                        style,
+                    )),
+                    Spans::from(Span::styled(
+                        format!("avg {}", format_duration(average)),
+                        style,
+                    )),
+                    Spans::from(Span::styled(sparkline(samples), spark_style)),
+                ]
+            }
+        }
+    }
+
+    fn is_child(&self, other: &Self) -> bool {
+        match (self, other) {
+            (Self::Metric { id, .. }, Self::Document { id: parent, .. }) => id == parent,
+            _ => false,
+        }
+    }
+
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.sort_key().cmp(&other.sort_key())
+    }
+
+    fn node_id(&self) -> Option<u64> {
+        let mut hasher = DefaultHasher::new();
+        self.sort_key().hash(&mut hasher);
+        Some(hasher.finish())
+    }
+}
+
+/// The parse and highlight query timings of the documents with a syntax tree, updated while
+/// the panel is open.
+pub struct SyntaxMetricsTree {
+    tree: Tree<MetricItem>,
+    /// The metrics the tree was built from.
+    metrics: Vec<(DocumentId, usize, SyntaxMetrics)>,
+}
+
+impl SyntaxMetricsTree {
+    pub fn new(editor: &Editor) -> Self {
+        let metrics = Self::metrics(editor);
+        Self {
+            tree: Tree::build_tree(Self::items(editor, &metrics)),
+            metrics,
+        }
+    }
+
+    fn metrics(editor: &Editor) -> Vec<(DocumentId, usize, SyntaxMetrics)> {
+        editor
+            .documents()
+            .filter_map(|doc| {
+                let layers = doc.syntax()?.layer_count();
+                Some((doc.id(), layers, doc.syntax_metrics.borrow().clone()))
+            })
+            .collect()
+    }
+
+    fn items(editor: &Editor, metrics: &[(DocumentId, usize, SyntaxMetrics)]) -> Vec<MetricItem> {
+        let mut items = Vec::with_capacity(metrics.len() * 3);
+        for (id, layers, metrics) in metrics {
+            let name = editor
+                .document(*id)
+                .and_then(|doc| doc.path())
+                .map(|path| get_relative_path(path).to_string_lossy().into_owned())
+                .unwrap_or_else(|| SCRATCH_BUFFER_NAME.to_string());
+            items.push(MetricItem::Document {
+                id: *id,
+                name,
+                layers: *layers,
+            });
+            items.push(MetricItem::Metric {
+                id: *id,
+                metric: Metric::Parse,
+                samples: metrics.parses.clone(),
+            });
+            items.push(MetricItem::Metric {
+                id: *id,
+                metric: Metric::Query,
+                samples: metrics.queries.clone(),
+            });
+        }
+        items
+    }
+
+    /// Rebuilds the tree if documents were parsed or rendered since it was built.
+    fn refresh(&mut self, editor: &Editor) {
+        let metrics = Self::metrics(editor);
+        if self.metrics == metrics {
+            return;
+        }
+        self.metrics = metrics;
+        self.tree
+            .replace_with_new_items(Self::items(editor, &self.metrics));
+    }
+}
+
+impl Component for SyntaxMetricsTree {
+    fn handle_event(&mut self, event: &Event, cx: &mut Context) -> EventResult {
+        let key_event = match event {
+            Event::Key(event) => *event,
+            Event::Mouse(event) => match self.tree.handle_mouse(event, cx, &mut ()) {
+                MouseAction::Activate => key!(Enter),
+                MouseAction::Handled => return EventResult::Consumed(None),
+                MouseAction::Ignored => return EventResult::Ignored(None),
+            },
+            Event::Resize(..) => return EventResult::Consumed(None),
+            _ => return EventResult::Ignored(None),
+        };
+
+        match key_event.into() {
+            key!(Esc) | key!('q') | ctrl!('c') => {
+                return EventResult::Consumed(Some(Box::new(|compositor: &mut Compositor, _| {
+                    compositor.pop();
+                })));
+            }
+            _ if self.tree.is_empty() => {}
+            _ => {
+                self.tree.handle_event(Event::Key(key_event), cx, &mut ());
+            }
+        }
+        EventResult::Consumed(None)
+    }
+
+    fn render(&mut self, area: Rect, surface: &mut Surface, cx: &mut Context) {
+        self.refresh(cx.editor);
+
+        let background = cx.editor.theme.get("ui.background");
+        surface.clear_with(area, background);
+
+        let block = Block::default()
+            .title(" Syntax metrics ")
+            .borders(Borders::ALL)
+            .border_style(super::border_style(&cx.editor.theme, cx.focus));
+        let inner = block.inner(area);
+        block.render(area, surface);
+
+        if self.tree.is_empty() {
+            let style = cx.editor.theme.get("comment");
+            let message = "No documents with a syntax tree";
+            surface.set_stringn(inner.x, inner.y, message, inner.width as usize, style);
+            return;
+        }
+        self.tree.render(inner, surface, cx, &mut ());
+    }
+
+    fn cursor(&self, _area: Rect, _editor: &Editor) -> (Option<Position>, CursorKind) {
+        (None, CursorKind::Hidden)
+    }
+
+    fn is_panel(&self) -> bool {
+        true
+    }
+}
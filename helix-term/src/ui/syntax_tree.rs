@@ -0,0 +1,327 @@
+use super::{tree::selected_style, MouseAction, Tree, TreeItem};
+use crate::{
+    compositor::{Component, Compositor, Context, EventResult},
+    ctrl, key,
+};
+use helix_core::{
+    syntax::node_is_visible,
+    tree_sitter::{Node, Point, TreeCursor},
+    Position, Selection,
+};
+use helix_view::{
+    graphics::{CursorKind, Rect},
+    input::Event,
+    Document, DocumentId, Editor,
+};
+use std::{
+    cmp::Ordering,
+    collections::hash_map::DefaultHasher,
+    hash::{Hash, Hasher},
+};
+use tui::{
+    buffer::Buffer as Surface,
+    text::{Span, Spans},
+    widgets::{Block, Borders, Widget},
+};
+
+#[derive(Debug, Clone)]
+struct NodeItem {
+    /// The indices of the node and its ancestors among the visible children of their parents.
+    path: Vec<usize>,
+    /// The id of the node in the syntax tree the item was built from.
+    id: usize,
+    field: Option<&'static str>,
+    kind: &'static str,
+    is_error: bool,
+    is_missing: bool,
+    start_byte: usize,
+    end_byte: usize,
+    start: Point,
+    end: Point,
+}
+
+impl NodeItem {
+    fn new(node: Node, field: Option<&'static str>, path: Vec<usize>) -> Self {
+        Self {
+            path,
+            id: node.id(),
+            field,
+            kind: node.kind(),
+            is_error: node.is_error(),
+            is_missing: node.is_missing(),
+            start_byte: node.start_byte(),
+            end_byte: node.end_byte(),
+            start: node.start_position(),
+            end: node.end_position(),
+        }
+    }
+}
+
+/// Collects the visible nodes below the cursor. Hidden nodes are skipped with their visible
+/// descendants taking their place, as in `pretty_print_tree`.
+fn collect_nodes(
+    cursor: &mut TreeCursor,
+    path: &mut Vec<usize>,
+    next: &mut usize,
+    items: &mut Vec<NodeItem>,
+) {
+    let node = cursor.node();
+    let visible = node_is_visible(&node);
+    let mut child_next = 0;
+    if visible {
+        path.push(*next);
+        *next += 1;
+        items.push(NodeItem::new(node, cursor.field_name(), path.clone()));
+    }
+    if cursor.goto_first_child() {
+        loop {
+            let next = if visible { &mut child_next } else { &mut *next };
+            collect_nodes(cursor, path, next, items);
+            if !cursor.goto_next_sibling() {
+                break;
+            }
+        }
+        cursor.goto_parent();
+    }
+    if visible {
+        path.pop();
+    }
+}
+
+impl TreeItem for NodeItem {
+    /// The id of the node under the cursor.
+    type Params = Option<usize>;
+
+    fn text(&self, cx: &mut Context, selected: bool, params: &mut Option<usize>) -> Spans {
+        let theme = &cx.editor.theme;
+        let mut spans = Vec::with_capacity(2);
+        if let Some(field) = self.field {
+            spans.push(Span::styled(
+                format!("{}: ", field),
+                theme.get("variable.other.member"),
+            ));
+        }
+        let kind = if self.is_missing {
+            Span::styled(format!("MISSING {}", self.kind), theme.get("warning"))
+        } else if self.is_error {
+            Span::styled(self.kind, theme.get("error"))
+        } else {
+            Span::styled(self.kind, theme.get("ui.text"))
+        };
+        spans.push(kind);
+        let mut patch = None;
+        if *params == Some(self.id) {
+            patch = Some(theme.get("ui.cursor.match"));
+        }
+        if selected {
+            patch = Some(selected_style(cx));
+        }
+        if let Some(patch) = patch {
+            for span in &mut spans {
+                span.style = span.style.patch(patch);
+            }
+        }
+        Spans::from(spans)
+    }
+
+    /// The byte range and the start and end points of the node.
+    fn columns(&self, cx: &mut Context, selected: bool, _params: &mut Option<usize>) -> Vec<Spans> {
+        let mut style = cx.editor.theme.get("comment");
+        if selected {
+            style = style.patch(selected_style(cx));
+        }
+        vec![
+            Spans::from(Span::styled(
+                format!("{}..{}", self.start_byte, self.end_byte),
+                style,
+            )),
+            Spans::from(Span::styled(
+                format!(
+                    "[{}, {}] - [{}, {}]",
+                    self.start.row, self.start.column, self.end.row, self.end.column
+                ),
+                style,
+            )),
+        ]
+    }
+
+    fn is_child(&self, other: &Self) -> bool {
+        self.path.len() == other.path.len() + 1 && self.path.starts_with(&other.path)
+    }
+
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.path.cmp(&other.path)
+    }
+
+    fn node_id(&self) -> Option<u64> {
+        let mut hasher = DefaultHasher::new();
+        self.path.hash(&mut hasher);
+        Some(hasher.finish())
+    }
+}
+
+/// The syntax tree of a document, rebuilt as the document changes, with the node under the
+/// cursor highlighted.
+pub struct SyntaxTreeView {
+    tree: Tree<NodeItem>,
+    doc_id: DocumentId,
+    /// The version of the document the tree was built from.
+    version: Option<i32>,
+    cursor_node: Option<usize>,
+}
+
+impl SyntaxTreeView {
+    pub fn new(editor: &Editor) -> Self {
+        let mut panel = Self {
+            tree: Tree::build_tree(Vec::new()),
+            doc_id: view!(editor).doc,
+            version: None,
+            cursor_node: None,
+        };
+        panel.refresh(editor);
+        // start out on the node under the cursor
+        if let Some(id) = panel.cursor_node {
+            panel.tree.select_last(|item| item.id == id);
+        }
+        panel
+    }
+
+    fn document<'a>(&self, editor: &'a Editor) -> Option<&'a Document> {
+        editor
+            .document(self.doc_id)
+            .filter(|doc| doc.syntax().is_some())
+    }
+
+    /// Rebuilds the tree if the document changed since it was built.
+    fn refresh(&mut self, editor: &Editor) {
+        let doc = match self.document(editor) {
+            Some(doc) => doc,
+            None => return,
+        };
+        if self.version == Some(doc.version()) {
+            return;
+        }
+        self.version = Some(doc.version());
+
+        let root = doc.syntax().unwrap().tree().root_node();
+        let mut items = Vec::new();
+        collect_nodes(&mut root.walk(), &mut Vec::new(), &mut 0, &mut items);
+        self.tree.replace_with_new_items(items);
+
+        self.cursor_node = Self::cursor_node(editor, doc, root);
+    }
+
+    /// The id of the innermost visible node containing the primary selection, if the document
+    /// is focused.
+    fn cursor_node(editor: &Editor, doc: &Document, root: Node) -> Option<usize> {
+        let view = view!(editor);
+        if view.doc != doc.id() {
+            return None;
+        }
+        let text = doc.text();
+        let range = doc.selection(view.id).primary();
+        let from = text.char_to_byte(range.from());
+        let to = text.char_to_byte(range.to());
+        let mut node = root.descendant_for_byte_range(from, to)?;
+        while !node_is_visible(&node) {
+            node = node.parent()?;
+        }
+        Some(node.id())
+    }
+
+    /// Selects the range of the selected node in the document, returning whether the view
+    /// should close.
+    fn select_node(&self, editor: &mut Editor) -> bool {
+        if self.tree.is_empty() {
+            return false;
+        }
+        if view!(editor).doc != self.doc_id {
+            editor.set_error("The document of the syntax tree is not focused");
+            return false;
+        }
+        let item = self.tree.current_item();
+        let scrolloff = editor.config().scrolloff;
+        let (view, doc) = current!(editor);
+        let text = doc.text();
+        let anchor = text.byte_to_char(item.start_byte.min(text.len_bytes()));
+        let head = text.byte_to_char(item.end_byte.min(text.len_bytes()));
+        doc.set_selection(view.id, Selection::single(anchor, head));
+        view.ensure_cursor_in_view_center(doc, scrolloff);
+        true
+    }
+}
+
+impl Component for SyntaxTreeView {
+    fn handle_event(&mut self, event: &Event, cx: &mut Context) -> EventResult {
+        let key_event = match event {
+            Event::Key(event) => *event,
+            Event::Mouse(event) => {
+                let mut cursor_node = self.cursor_node;
+                match self.tree.handle_mouse(event, cx, &mut cursor_node) {
+                    MouseAction::Activate => key!(Enter),
+                    MouseAction::Handled => return EventResult::Consumed(None),
+                    MouseAction::Ignored => return EventResult::Ignored(None),
+                }
+            }
+            Event::Resize(..) => return EventResult::Consumed(None),
+            _ => return EventResult::Ignored(None),
+        };
+
+        let close_fn = EventResult::Consumed(Some(Box::new(|compositor: &mut Compositor, _| {
+            compositor.pop();
+        })));
+
+        match key_event.into() {
+            key!(Esc) | key!('q') | ctrl!('c') => return close_fn,
+            key!(Enter) if self.select_node(cx.editor) => return close_fn,
+            _ => {
+                let mut cursor_node = self.cursor_node;
+                self.tree
+                    .handle_event(Event::Key(key_event), cx, &mut cursor_node);
+            }
+        }
+        EventResult::Consumed(None)
+    }
+
+    fn render(&mut self, area: Rect, surface: &mut Surface, cx: &mut Context) {
+        self.refresh(cx.editor);
+
+        let background = cx.editor.theme.get("ui.background");
+        surface.clear_with(area, background);
+
+        let block = Block::default()
+            .title(" Syntax tree ")
+            .borders(Borders::ALL)
+            .border_style(super::border_style(&cx.editor.theme, cx.focus));
+        let inner = block.inner(area);
+        block.render(area, surface);
+
+        let comment = cx.editor.theme.get("comment");
+        if self.document(cx.editor).is_none() {
+            let message = "The document was closed or has no syntax tree";
+            surface.set_stringn(inner.x, inner.y, message, inner.width as usize, comment);
+            return;
+        }
+
+        let tree_area = inner.clip_bottom(1);
+        let mut cursor_node = self.cursor_node;
+        self.tree.render(tree_area, surface, cx, &mut cursor_node);
+
+        let footer_area = inner.clip_top(tree_area.height);
+        surface.set_stringn(
+            footer_area.x,
+            footer_area.y,
+            "[enter]select node",
+            footer_area.width as usize,
+            comment,
+        );
+    }
+
+    fn cursor(&self, _area: Rect, _editor: &Editor) -> (Option<Position>, CursorKind) {
+        (None, CursorKind::Hidden)
+    }
+
+    fn is_panel(&self) -> bool {
+        true
+    }
+}
@@ -0,0 +1,366 @@
+use super::Prompt;
+use crate::{
+    compositor::{Component, Compositor, Context, EventResult},
+    ctrl, key, shift, ui,
+};
+use helix_core::{table::parse_records, unicode::width::UnicodeWidthStr, Position, Selection};
+use helix_view::{
+    align_view,
+    graphics::{CursorKind, Modifier, Rect, Style},
+    input::{Event, KeyEvent},
+    Align, Document, DocumentId, Editor,
+};
+use std::cmp::Ordering;
+use tui::{
+    buffer::Buffer as Surface,
+    layout::Constraint,
+    widgets::{Block, Borders, Cell, Row, Table, Widget},
+};
+
+/// Columns wider than this are truncated.
+const MAX_COLUMN_WIDTH: usize = 40;
+
+/// Compares cells numerically if both are numbers.
+fn compare_cells(a: &str, b: &str) -> Ordering {
+    match (a.trim().parse::<f64>(), b.trim().parse::<f64>()) {
+        (Ok(a), Ok(b)) => a.partial_cmp(&b).unwrap_or(Ordering::Equal),
+        _ => a.cmp(b),
+    }
+}
+
+/// A read-only view of the delimiter separated records of a buffer, with the first record as
+/// header. Rows can be sorted by a column and filtered.
+pub struct TableView {
+    doc_id: DocumentId,
+    title: String,
+    header: Vec<String>,
+    /// Records along with the line they start on.
+    records: Vec<(usize, Vec<String>)>,
+    /// Indices of the shown records, filtered and sorted.
+    visible: Vec<usize>,
+    widths: Vec<usize>,
+    selected: usize,
+    /// First shown record of `visible`.
+    offset: usize,
+    column: usize,
+    column_offset: usize,
+    /// The sorted column and whether it's sorted in descending order.
+    sort: Option<(usize, bool)>,
+    filter: String,
+    prompt: Option<Prompt>,
+    height: usize,
+}
+
+impl TableView {
+    /// Returns `None` if the buffer has no records.
+    pub fn new(doc: &Document, delimiter: char) -> Option<Self> {
+        let mut records = parse_records(&doc.text().to_string(), delimiter);
+        if records.is_empty() {
+            return None;
+        }
+        let (_, header) = records.remove(0);
+
+        let columns = records
+            .iter()
+            .map(|(_, record)| record.len())
+            .chain(std::iter::once(header.len()))
+            .max()
+            .unwrap_or_default();
+        let mut widths = vec![1; columns];
+        for record in records.iter().map(|(_, record)| record).chain([&header]) {
+            for (width, field) in widths.iter_mut().zip(record) {
+                *width = (*width).max(field.width().min(MAX_COLUMN_WIDTH));
+            }
+        }
+        // room for the sort marker
+        for width in widths.iter_mut() {
+            *width += 2;
+        }
+
+        let mut view = Self {
+            doc_id: doc.id(),
+            title: doc.display_name().into_owned(),
+            header,
+            records,
+            visible: Vec::new(),
+            widths,
+            selected: 0,
+            offset: 0,
+            column: 0,
+            column_offset: 0,
+            sort: None,
+            filter: String::new(),
+            prompt: None,
+            height: 0,
+        };
+        view.refresh();
+        Some(view)
+    }
+
+    fn refresh(&mut self) {
+        let filter = self.filter.to_lowercase();
+        let records = &self.records;
+        self.visible = (0..records.len())
+            .filter(|index| {
+                filter.is_empty()
+                    || records[*index]
+                        .1
+                        .iter()
+                        .any(|field| field.to_lowercase().contains(&filter))
+            })
+            .collect();
+
+        if let Some((column, descending)) = self.sort {
+            let field = |index: &usize| {
+                records[*index]
+                    .1
+                    .get(column)
+                    .map(String::as_str)
+                    .unwrap_or_default()
+            };
+            self.visible.sort_by(|a, b| {
+                let ordering = compare_cells(field(a), field(b));
+                if descending {
+                    ordering.reverse()
+                } else {
+                    ordering
+                }
+            });
+        }
+        self.selected = self.selected.min(self.visible.len().saturating_sub(1));
+    }
+
+    fn move_by(&mut self, rows: isize) {
+        let last = self.visible.len().saturating_sub(1) as isize;
+        self.selected = (self.selected as isize + rows).clamp(0, last.max(0)) as usize;
+    }
+
+    fn cycle_sort(&mut self) {
+        self.sort = match self.sort {
+            Some((column, false)) if column == self.column => Some((column, true)),
+            Some((column, true)) if column == self.column => None,
+            _ => Some((self.column, false)),
+        };
+        self.refresh();
+    }
+
+    fn handle_prompt_event(&mut self, event: KeyEvent, cx: &mut Context) -> EventResult {
+        let mut prompt = match self.prompt.take() {
+            Some(prompt) => prompt,
+            None => return EventResult::Ignored(None),
+        };
+        match event.into() {
+            key!(Enter) => {}
+            key!(Esc) | ctrl!('c') => {
+                self.filter.clear();
+                self.refresh();
+            }
+            _ => {
+                prompt.handle_event(&Event::Key(event), cx);
+                if *prompt.line() != self.filter {
+                    self.filter = prompt.line().clone();
+                    self.refresh();
+                }
+                self.prompt = Some(prompt);
+            }
+        }
+        EventResult::Consumed(None)
+    }
+
+    fn jump(&self) -> EventResult {
+        let line = match self.visible.get(self.selected) {
+            Some(index) => self.records[*index].0,
+            None => return EventResult::Consumed(None),
+        };
+        let doc_id = self.doc_id;
+        EventResult::Consumed(Some(Box::new(move |compositor: &mut Compositor, cx| {
+            compositor.pop();
+            let (view, doc) = current!(cx.editor);
+            if doc.id() != doc_id {
+                return;
+            }
+            let pos = doc
+                .text()
+                .line_to_char(line.min(doc.text().len_lines() - 1));
+            doc.set_selection(view.id, Selection::point(pos));
+            align_view(doc, view, Align::Center);
+        })))
+    }
+
+    /// Adjusts the first shown column so that the selected one is visible in `width`.
+    fn scroll_columns(&mut self, width: usize) {
+        self.column_offset = self.column_offset.min(self.column);
+        while self.column_offset < self.column
+            && self.widths[self.column_offset..=self.column]
+                .iter()
+                .map(|width| width + 1)
+                .sum::<usize>()
+                > width
+        {
+            self.column_offset += 1;
+        }
+    }
+}
+
+impl Component for TableView {
+    fn handle_event(&mut self, event: &Event, cx: &mut Context) -> EventResult {
+        let key_event = match event {
+            Event::Key(event) => *event,
+            Event::Resize(..) => return EventResult::Consumed(None),
+            _ => return EventResult::Ignored(None),
+        };
+
+        if self.prompt.is_some() {
+            return self.handle_prompt_event(key_event, cx);
+        }
+
+        let page = self.height.max(1) as isize;
+        match key_event.into() {
+            key!(Esc) | key!('q') | ctrl!('c') => {
+                return EventResult::Consumed(Some(Box::new(|compositor: &mut Compositor, _| {
+                    compositor.pop();
+                })))
+            }
+            key!('j') | key!(Down) => self.move_by(1),
+            key!('k') | key!(Up) => self.move_by(-1),
+            ctrl!('d') => self.move_by(page / 2),
+            ctrl!('u') => self.move_by(-page / 2),
+            key!(PageDown) => self.move_by(page),
+            key!(PageUp) => self.move_by(-page),
+            key!('g') | key!(Home) => self.selected = 0,
+            shift!('G') | key!(End) => self.move_by(isize::MAX / 2),
+            key!('h') | key!(Left) => self.column = self.column.saturating_sub(1),
+            key!('l') | key!(Right) => {
+                self.column = (self.column + 1).min(self.widths.len().saturating_sub(1))
+            }
+            key!('s') => self.cycle_sort(),
+            key!('/') => {
+                let mut prompt =
+                    Prompt::new("filter:".into(), None, ui::completers::none, |_, _, _| {});
+                prompt.insert_str(&self.filter, cx.editor);
+                self.prompt = Some(prompt);
+            }
+            key!(Enter) => return self.jump(),
+            _ => {}
+        }
+        EventResult::Consumed(None)
+    }
+
+    fn render(&mut self, area: Rect, surface: &mut Surface, cx: &mut Context) {
+        let theme = &cx.editor.theme;
+        let background = theme.get("ui.background");
+        surface.clear_with(area, background);
+
+        let title = format!(
+            " {} ({}/{} rows) ",
+            self.title,
+            self.visible.len(),
+            self.records.len()
+        );
+        let block = Block::default().title(title).borders(Borders::ALL);
+        let inner = block.inner(area);
+        block.render(area, surface);
+
+        let table_area = inner.clip_bottom(1);
+        // the header stays on the first line
+        self.height = table_area.height.saturating_sub(1) as usize;
+        if self.selected < self.offset {
+            self.offset = self.selected;
+        } else if self.selected >= self.offset + self.height {
+            self.offset = self.selected + 1 - self.height.max(1);
+        }
+        self.scroll_columns(table_area.width as usize);
+
+        let mut columns = Vec::new();
+        let mut used = 0;
+        for column in self.column_offset..self.widths.len() {
+            if used + self.widths[column] > table_area.width as usize && !columns.is_empty() {
+                break;
+            }
+            used += self.widths[column] + 1;
+            columns.push(column);
+        }
+        let constraints: Vec<Constraint> = columns
+            .iter()
+            .map(|column| Constraint::Length(self.widths[*column] as u16))
+            .collect();
+
+        let header_style = theme.get("ui.text.focus").add_modifier(Modifier::BOLD);
+        let selected_style = theme
+            .try_get("ui.menu.selected")
+            .unwrap_or_else(|| Style::default().add_modifier(Modifier::REVERSED));
+        let current_style = Style::default().add_modifier(Modifier::REVERSED);
+
+        let header = Row::new(columns.iter().map(|column| {
+            let name = self.header.get(*column).cloned().unwrap_or_default();
+            let name = match self.sort {
+                Some((sorted, false)) if sorted == *column => format!("{} ▲", name),
+                Some((sorted, true)) if sorted == *column => format!("{} ▼", name),
+                _ => name,
+            };
+            let cell = Cell::from(name);
+            if *column == self.column {
+                cell.style(current_style)
+            } else {
+                cell
+            }
+        }))
+        .style(header_style);
+
+        let end = (self.offset + self.height).min(self.visible.len());
+        let rows = self.visible[self.offset..end]
+            .iter()
+            .enumerate()
+            .map(|(row, index)| {
+                let record = &self.records[*index].1;
+                let is_selected = self.offset + row == self.selected;
+                let cells = columns.iter().map(|column| {
+                    // fields spanning several lines are shown on one
+                    let field = record.get(*column).map(String::as_str).unwrap_or_default();
+                    let cell = Cell::from(field.replace('\n', "↵"));
+                    if is_selected && *column == self.column {
+                        cell.style(current_style)
+                    } else {
+                        cell
+                    }
+                });
+                let row = Row::new(cells);
+                if is_selected {
+                    row.style(selected_style)
+                } else {
+                    row
+                }
+            });
+
+        Table::new(rows)
+            .header(header)
+            .widths(&constraints)
+            .column_spacing(1)
+            .render(table_area, surface);
+
+        let footer_area = inner.clip_top(table_area.height);
+        match &self.prompt {
+            Some(prompt) => prompt.render_prompt(footer_area, surface, cx),
+            None => {
+                let style = cx.editor.theme.get("comment");
+                surface.set_stringn(
+                    footer_area.x,
+                    footer_area.y,
+                    "[/]filter [s]ort [enter]jump to row",
+                    footer_area.width as usize,
+                    style,
+                );
+            }
+        }
+    }
+
+    fn cursor(&self, area: Rect, editor: &Editor) -> (Option<Position>, CursorKind) {
+        match &self.prompt {
+            Some(prompt) => {
+                let inner = Block::default().borders(Borders::ALL).inner(area);
+                prompt.cursor(inner.clip_top(inner.height.saturating_sub(1)), editor)
+            }
+            None => (None, CursorKind::Hidden),
+        }
+    }
+}
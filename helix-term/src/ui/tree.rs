@@ -1,26 +1,195 @@
 use std::cmp::Ordering;
+use std::collections::{HashMap, HashSet};
 use std::iter::Peekable;
+use std::ops::Range;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
 
 use anyhow::Result;
+use futures_util::future::BoxFuture;
 
 use crate::{
-    compositor::{Context, EventResult},
-    ctrl, key, shift,
+    compositor::{Component, Context, EventResult},
+    ctrl, key,
+    ui::{self, Prompt},
+};
+use helix_core::unicode::{
+    segmentation::UnicodeSegmentation,
+    width::{UnicodeWidthChar, UnicodeWidthStr},
 };
-use helix_core::unicode::width::UnicodeWidthStr;
 use helix_view::{
-    graphics::Rect,
-    input::{Event, KeyEvent},
+    editor::{TreeEllipsis, TreeIndentGuides, TreeState},
+    graphics::{Modifier, Rect, Style},
+    input::{Event, KeyEvent, MouseButton, MouseEvent, MouseEventKind},
+    tree_keymap::{get_in_node, TreeAction, TreeKeyTrie},
+};
+use tui::{
+    buffer::Buffer as Surface,
+    text::{Span, Spans},
+    widgets::{Block, Borders, Widget},
 };
-use tui::{buffer::Buffer as Surface, text::Spans};
 
-pub trait TreeItem: Sized {
+/// Longest time between the clicks of a double click.
+const DOUBLE_CLICK_TIMEOUT: Duration = Duration::from_millis(500);
+
+/// Rows kept visible above and below the selected item when scrolling.
+const SCROLLOFF: usize = 3;
+
+/// Blank cells between the columns of a row.
+const COLUMN_GAP: usize = 2;
+
+/// The number of items in `elems`, folded or not, leaving out the placeholders of loads.
+fn count_items<T>(elems: &[Elem<T>]) -> usize {
+    elems
+        .iter()
+        .filter(|elem| elem.loading.is_none())
+        .map(|elem| 1 + count_items(&elem.folded))
+        .sum()
+}
+
+/// The position among the items at `level` in `range` of `elems` before which `item` is
+/// ordered by [`TreeItem::cmp`], or the end of `range`.
+fn sibling_pos<T: TreeItem>(
+    elems: &[Elem<T>],
+    range: Range<usize>,
+    level: usize,
+    item: &T,
+) -> usize {
+    let end = range.end;
+    range
+        .into_iter()
+        .find(|&pos| elems[pos].level == level && elems[pos].item.cmp(item) == Ordering::Greater)
+        .unwrap_or(end)
+}
+
+/// Maps the levels of `elems` and of their folded descendants with `f`.
+fn set_levels<T>(elems: &mut [Elem<T>], f: impl Fn(usize) -> usize + Copy) {
+    for elem in elems {
+        elem.level = f(elem.level);
+        set_levels(&mut elem.folded, f);
+    }
+}
+
+/// Shortens `spans` to `width` cells, replacing the graphemes cut off at the end or in the
+/// middle with `…`.
+fn elide<'a>(spans: Spans<'a>, width: usize, ellipsis: TreeEllipsis) -> Vec<Span<'a>> {
+    if spans.width() <= width {
+        return spans.0;
+    }
+    if width == 0 {
+        return Vec::new();
+    }
+    let graphemes: Vec<(&str, Style)> = spans
+        .0
+        .iter()
+        .flat_map(|span| {
+            span.content
+                .graphemes(true)
+                .map(move |grapheme| (grapheme, span.style))
+        })
+        .collect();
+    let keep = width - 1;
+    let (head_width, tail_width) = match ellipsis {
+        TreeEllipsis::End => (keep, 0),
+        TreeEllipsis::Middle => (keep - keep / 2, keep / 2),
+    };
+
+    let head = take_graphemes(graphemes.iter(), head_width);
+    let mut tail = take_graphemes(graphemes.iter().rev(), tail_width);
+    tail.reverse();
+    // the ellipsis is styled like the first grapheme it stands in for
+    let ellipsis_style = graphemes
+        .get(head.len())
+        .map_or_else(Style::default, |g| g.1);
+
+    // graphemes of the same style are joined into a span again
+    let mut elided: Vec<Span<'a>> = Vec::new();
+    let graphemes = head
+        .into_iter()
+        .chain(std::iter::once(("…", ellipsis_style)))
+        .chain(tail);
+    for (grapheme, style) in graphemes {
+        match elided.last_mut() {
+            Some(span) if span.style == style => span.content.to_mut().push_str(grapheme),
+            _ => elided.push(Span::styled(grapheme.to_string(), style)),
+        }
+    }
+    elided
+}
+
+/// The graphemes fitting in `width` cells, in the order of `graphemes`.
+fn take_graphemes<'g, 's: 'g>(
+    graphemes: impl Iterator<Item = &'g (&'s str, Style)>,
+    mut width: usize,
+) -> Vec<(&'s str, Style)> {
+    let mut taken = Vec::new();
+    for &(grapheme, style) in graphemes {
+        let grapheme_width = grapheme.width();
+        if grapheme_width > width {
+            break;
+        }
+        width -= grapheme_width;
+        taken.push((grapheme, style));
+    }
+    taken
+}
+
+/// Shortens or pads `spans` to `width` cells, padding with `pad_style`.
+fn fit_cell<'a>(
+    spans: Spans<'a>,
+    width: usize,
+    pad_style: Style,
+    ellipsis: TreeEllipsis,
+) -> Vec<Span<'a>> {
+    let mut cell = elide(spans, width, ellipsis);
+    let remaining = width.saturating_sub(cell.iter().map(|span| span.width()).sum());
+    if remaining > 0 {
+        cell.push(Span::styled(" ".repeat(remaining), pad_style));
+    }
+    cell
+}
+
+/// The style of the selected row, `ui.menu.selected` or reversed colors if the theme has none.
+pub(super) fn selected_style(cx: &Context) -> Style {
+    cx.editor
+        .theme
+        .try_get("ui.menu.selected")
+        .unwrap_or_else(|| Style::default().add_modifier(Modifier::REVERSED))
+}
+
+/// Patches the style of the selected row onto the `spans` of a [`TreeItem::text`].
+pub(super) fn patch_selected(cx: &Context, spans: &mut [Span]) {
+    let patch = selected_style(cx);
+    for span in spans {
+        span.style = span.style.patch(patch);
+    }
+}
+
+pub trait TreeItem: Sized + Clone + Send + 'static {
     type Params;
 
     fn text(&self, cx: &mut Context, selected: bool, params: &mut Self::Params) -> Spans;
+
+    /// Cells shown after `text` in columns, e.g. the kind and detail of a symbol. The columns
+    /// line up across the visible rows which have cells.
+    fn columns(
+        &self,
+        _cx: &mut Context,
+        _selected: bool,
+        _params: &mut Self::Params,
+    ) -> Vec<Spans> {
+        Vec::new()
+    }
+
     fn is_child(&self, other: &Self) -> bool;
     fn cmp(&self, other: &Self) -> Ordering;
 
+    /// Identifies the item across `Tree::replace_with_new_items`, e.g. by its path, so that its
+    /// fold and the selection survive rebuilding the tree.
+    fn node_id(&self) -> Option<u64> {
+        None
+    }
+
     fn filter(&self, cx: &mut Context, s: &str, params: &mut Self::Params) -> bool {
         self.text(cx, false, params)
             .0
@@ -34,6 +203,13 @@ fn filter(&self, cx: &mut Context, s: &str, params: &mut Self::Params) -> bool {
     fn get_childs(&self) -> Result<Vec<Self>> {
         Ok(vec![])
     }
+
+    /// Loads the children in the background for `TreeOp::LoadChildsAndInsert`, showing a
+    /// "loading…" row below the item until they arrive. Without a future the children are
+    /// taken from `get_childs` instead.
+    fn load_childs(&self) -> Option<BoxFuture<'static, Result<Vec<Self>>>> {
+        None
+    }
 }
 
 fn tree_item_cmp<T: TreeItem>(item1: &T, item2: &T) -> Ordering {
@@ -100,6 +276,53 @@ fn get_elems_recursion<T: TreeItem>(t: &mut Elem<T>, depth: usize) -> Result<usi
     Ok(total)
 }
 
+/// Collects the ids of the items in `elems` with folded children, including folded descendants.
+fn folded_ids<T: TreeItem>(elems: &[Elem<T>], ids: &mut HashSet<u64>) {
+    for elem in elems.iter().filter(|elem| !elem.folded.is_empty()) {
+        if let Some(id) = elem.item.node_id() {
+            ids.insert(id);
+        }
+        folded_ids(&elem.folded, ids);
+    }
+}
+
+/// Collects the ids of the marked items in `elems`, including folded descendants.
+fn marked_ids<T: TreeItem>(elems: &[Elem<T>], ids: &mut HashSet<u64>) {
+    for elem in elems {
+        if let Some(id) = elem.item.node_id().filter(|_| elem.marked) {
+            ids.insert(id);
+        }
+        marked_ids(&elem.folded, ids);
+    }
+}
+
+/// Marks the items of `elems` and their folded descendants whose id is in `ids`, and unmarks
+/// the others.
+fn set_marks<T: TreeItem>(elems: &mut [Elem<T>], ids: &HashSet<u64>) {
+    for elem in elems {
+        elem.marked = elem.item.node_id().map_or(false, |id| ids.contains(&id));
+        set_marks(&mut elem.folded, ids);
+    }
+}
+
+/// Finds the placeholder of the load `id` in `elems` or in their folded children, returning the
+/// list it is in and its position there.
+fn find_placeholder<T>(elems: &mut Vec<Elem<T>>, id: usize) -> Option<(&mut Vec<Elem<T>>, usize)> {
+    if let Some(pos) = elems.iter().position(|elem| elem.loading == Some(id)) {
+        return Some((elems, pos));
+    }
+    elems
+        .iter_mut()
+        .find_map(|elem| find_placeholder(&mut elem.folded, id))
+}
+
+/// Whether an item of `elems` or of their folded children matches `f`.
+fn contains_match<T: TreeItem>(elems: &[Elem<T>], f: &mut impl FnMut(&T) -> bool) -> bool {
+    elems
+        .iter()
+        .any(|elem| (elem.loading.is_none() && f(&elem.item)) || contains_match(&elem.folded, f))
+}
+
 fn expand_elems<T: TreeItem>(dist: &mut Vec<Elem<T>>, mut t: Elem<T>) {
     let childs = std::mem::take(&mut t.folded);
     dist.push(t);
@@ -108,11 +331,37 @@ fn expand_elems<T: TreeItem>(dist: &mut Vec<Elem<T>>, mut t: Elem<T>) {
     }
 }
 
+/// The outcome of a mouse event handled by a tree.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MouseAction {
+    /// The event was outside of the tree.
+    Ignored,
+    /// The selection, a fold or the view changed.
+    Handled,
+    /// The selected item was double clicked, which components handle like `Enter`.
+    Activate,
+}
+
+/// What a key typed into a tree stands for.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TreeKey {
+    /// An action of the tree keymap, with the count typed before it.
+    Action(TreeAction, usize),
+    /// A digit of a count, the start of a sequence like `gg` or a key typed into the search.
+    Consumed,
+    /// The key isn't bound.
+    Ignored,
+}
+
 pub enum TreeOp<T> {
     Noop,
     Restore,
     InsertChild(Vec<T>),
     GetChildsAndInsert,
+    /// Inserts the children from `TreeItem::load_childs` once they are loaded.
+    LoadChildsAndInsert,
+    /// Inserts the children the future loads, for items which need the context to load them.
+    LoadAndInsert(BoxFuture<'static, Result<Vec<T>>>),
     ReplaceTree(Vec<T>),
 }
 
@@ -120,6 +369,11 @@ pub struct Elem<T> {
     item: T,
     level: usize,
     folded: Vec<Self>,
+    /// The id of the background load this row stands in for. Placeholder rows hold a copy of
+    /// the item whose children are loading.
+    loading: Option<usize>,
+    /// Whether the item is marked for a bulk action.
+    marked: bool,
 }
 
 impl<T: Clone> Clone for Elem<T> {
@@ -128,6 +382,8 @@ fn clone(&self) -> Self {
             item: self.item.clone(),
             level: self.level,
             folded: self.folded.clone(),
+            loading: self.loading,
+            marked: self.marked,
         }
     }
 }
@@ -138,6 +394,8 @@ pub fn new(item: T, level: usize) -> Self {
             item,
             level,
             folded: vec![],
+            loading: None,
+            marked: false,
         }
     }
 
@@ -155,6 +413,13 @@ pub struct Tree<T: TreeItem> {
     col: usize,
     max_len: usize,
     count: usize,
+    /// The area the tree was last rendered in, to map mouse events to items.
+    area: Rect,
+    /// The item clicked last and when, to recognize double clicks.
+    last_click: Option<(usize, Instant)>,
+    /// Children loaded in the background with the id of their load, until they are inserted.
+    loaded: Arc<Mutex<Vec<(usize, Result<Vec<T>>)>>>,
+    next_load: usize,
     tree_symbol_style: String,
     #[allow(clippy::type_complexity)]
     pre_render: Option<Box<dyn Fn(&mut Self, Rect) + 'static>>,
@@ -164,7 +429,24 @@ pub struct Tree<T: TreeItem> {
     #[allow(clippy::type_complexity)]
     on_folded_fn: Option<Box<dyn FnMut(&mut T, &mut Context, &mut T::Params) + 'static>>,
     #[allow(clippy::type_complexity)]
-    on_next_key: Option<Box<dyn FnMut(&mut Context, &mut Self, KeyEvent)>>,
+    on_marked_fn: Option<Box<dyn FnMut(&[(usize, &T)], &mut Context, &mut T::Params) + 'static>>,
+    /// Whether the last render showed the gutter of the marks.
+    mark_gutter: bool,
+    /// The keys bound after the prefix typed last, like `g` in `gg`.
+    pending_keys: Option<HashMap<KeyEvent, TreeKeyTrie>>,
+    /// Fixed widths of the columns, the first being the indented text. Columns without a
+    /// width are as wide as their widest visible cell.
+    column_widths: Vec<Option<usize>>,
+    /// The pattern of the last search, whose matches are highlighted.
+    search: Option<String>,
+    /// The search being typed, shown in the last row.
+    search_prompt: Option<Prompt>,
+    /// The title of the block drawn by [`Tree::render_framed`], followed by the number of items.
+    title: Option<String>,
+    /// The key hints at the end of the footer drawn by [`Tree::render_framed`].
+    footer_hints: Option<String>,
+    /// Bumped by each edit of the items, see [`Tree::generation`].
+    generation: u64,
 }
 
 impl<T: TreeItem> Tree<T> {
@@ -178,19 +460,96 @@ pub fn new(items: Vec<Elem<T>>) -> Self {
             col: 0,
             max_len: 0,
             count: 0,
+            area: Rect::default(),
+            last_click: None,
+            loaded: Arc::default(),
+            next_load: 0,
             tree_symbol_style: "ui.text".into(),
             pre_render: None,
             on_opened_fn: None,
             on_folded_fn: None,
-            on_next_key: None,
+            on_marked_fn: None,
+            mark_gutter: false,
+            pending_keys: None,
+            column_widths: Vec::new(),
+            search: None,
+            search_prompt: None,
+            title: None,
+            footer_hints: None,
+            generation: 0,
+        }
+    }
+
+    /// The folds and the selection, for the items with a [`TreeItem::node_id`].
+    pub fn state(&self) -> TreeState {
+        let mut folded = HashSet::new();
+        folded_ids(&self.items, &mut folded);
+        let selected = self
+            .items
+            .get(self.selected)
+            .filter(|elem| elem.loading.is_none())
+            .and_then(|elem| elem.item.node_id());
+        TreeState { folded, selected }
+    }
+
+    /// Folds the items of `state` and selects its selected item if there still is one, e.g.
+    /// in a tree built again from scratch.
+    pub fn restore_state(&mut self, state: &TreeState) {
+        self.fold_ids(&state.folded);
+        if let Some(pos) = state.selected.and_then(|id| {
+            self.items
+                .iter()
+                .position(|elem| elem.item.node_id() == Some(id))
+        }) {
+            self.selected = pos;
+            self.winline = pos;
         }
     }
 
+    fn fold_ids(&mut self, folded: &HashSet<u64>) {
+        // fold from the bottom up so that folded items keep their own folds
+        for index in (0..self.items.len()).rev() {
+            let is_folded = self.items[index]
+                .item
+                .node_id()
+                .map_or(false, |id| folded.contains(&id));
+            let pos = self.next_not_descendant_pos(index);
+            if is_folded && pos > index + 1 {
+                self.items[index].folded = self.items.drain(index + 1..pos).collect();
+            }
+        }
+    }
+
+    /// Replaces the items, keeping the folds and the selection of items with a
+    /// [`TreeItem::node_id`].
     pub fn replace_with_new_items(&mut self, items: Vec<T>) {
+        let TreeState { folded, selected } = self.state();
+        let mut marked = HashSet::new();
+        marked_ids(&self.items, &mut marked);
+
         let old = std::mem::replace(self, Self::new(vec_to_tree(items, 0)));
         self.on_opened_fn = old.on_opened_fn;
         self.on_folded_fn = old.on_folded_fn;
+        self.on_marked_fn = old.on_marked_fn;
         self.tree_symbol_style = old.tree_symbol_style;
+        self.column_widths = old.column_widths;
+        self.search = old.search;
+        self.search_prompt = old.search_prompt;
+        self.title = old.title;
+        self.footer_hints = old.footer_hints;
+        self.generation = old.generation + 1;
+
+        self.fold_ids(&folded);
+        set_marks(&mut self.items, &marked);
+        if let Some(pos) = selected.and_then(|id| {
+            self.items
+                .iter()
+                .position(|elem| elem.item.node_id() == Some(id))
+        }) {
+            self.selected = pos;
+            // keep the selected item on the same row
+            self.winline = old.winline.min(pos);
+        }
     }
 
     pub fn build_tree(items: Vec<T>) -> Self {
@@ -221,11 +580,215 @@ pub fn with_folded_fn<F>(mut self, f: F) -> Self
         self
     }
 
+    /// Calls `f` with the index of each marked row and its item when the tree is activated
+    /// while items are marked, instead of activating the selected item. The marks of folded
+    /// items are kept for when they are shown again. The marks are cleared afterwards.
+    pub fn with_marked_fn<F>(mut self, f: F) -> Self
+    where
+        F: FnMut(&[(usize, &T)], &mut Context, &mut T::Params) + 'static,
+    {
+        self.on_marked_fn = Some(Box::new(f));
+        self
+    }
+
+    /// The indices of the marked rows.
+    pub fn marked(&self) -> Vec<usize> {
+        (0..self.items.len())
+            .filter(|&index| self.items[index].marked)
+            .collect()
+    }
+
+    /// Toggles the mark of the selected item and of the `count - 1` items below it, moving
+    /// the selection past them.
+    pub fn toggle_mark(&mut self, count: usize) {
+        if self.items.is_empty() {
+            return;
+        }
+        for _ in 0..count {
+            let elem = &mut self.items[self.selected];
+            if elem.loading.is_none() {
+                elem.marked = !elem.marked;
+            }
+            if self.selected + 1 == self.items.len() {
+                break;
+            }
+            self.move_down(1);
+        }
+    }
+
+    pub fn clear_marks(&mut self) {
+        set_marks(&mut self.items, &HashSet::new());
+    }
+
+    /// Runs the function of [`Tree::with_marked_fn`] on the marked items, returning whether
+    /// there were any.
+    fn activate_marked(&mut self, cx: &mut Context, params: &mut T::Params) -> bool {
+        let marked = self.marked();
+        let mut on_marked_fn = match self.on_marked_fn.take() {
+            Some(f) if !marked.is_empty() => f,
+            f => {
+                self.on_marked_fn = f;
+                return false;
+            }
+        };
+        let items: Vec<_> = marked
+            .into_iter()
+            .map(|index| (index, &self.items[index].item))
+            .collect();
+        on_marked_fn(&items, cx, params);
+        self.on_marked_fn = Some(on_marked_fn);
+        self.clear_marks();
+        true
+    }
+
+    /// Fixes the widths of the columns, the first being the indented text of the items. Cells
+    /// are truncated to the width of their column.
+    pub fn with_column_widths(mut self, widths: Vec<Option<usize>>) -> Self {
+        self.column_widths = widths;
+        self
+    }
+
+    /// Sets the title shown by [`Tree::render_framed`], e.g. `Symbols — main.rs`.
+    pub fn with_title(mut self, title: impl Into<String>) -> Self {
+        self.set_title(title);
+        self
+    }
+
+    pub fn set_title(&mut self, title: impl Into<String>) {
+        self.title = Some(title.into());
+    }
+
+    /// Sets the key hints shown at the end of the footer by [`Tree::render_framed`], e.g.
+    /// `[enter]jump [o]fold`.
+    pub fn with_footer_hints(mut self, hints: impl Into<String>) -> Self {
+        self.set_footer_hints(hints);
+        self
+    }
+
+    pub fn set_footer_hints(&mut self, hints: impl Into<String>) {
+        self.footer_hints = Some(hints.into());
+    }
+
     pub fn tree_symbol_style(mut self, style: String) -> Self {
         self.tree_symbol_style = style;
         self
     }
 
+    /// Whether the item at `index` has children, folded or not.
+    fn has_children(&self, index: usize) -> bool {
+        let elem = &self.items[index];
+        !elem.folded.is_empty()
+            || self
+                .items
+                .get(index + 1)
+                .map_or(false, |next| next.level > elem.level)
+    }
+
+    /// The items shown on each row with their levels, starting with the sticky rows.
+    pub fn visible_rows(&self) -> impl Iterator<Item = (usize, usize)> + '_ {
+        let top = self.selected.saturating_sub(self.winline);
+        let sticky = self.sticky_rows();
+        let first = top + sticky.len();
+        sticky
+            .into_iter()
+            .chain(first..self.items.len())
+            .take(self.area.height as usize)
+            .map(|index| (index, self.items[index].level))
+    }
+
+    /// For the items in `rows` and the ancestors of the first one, whether each of their
+    /// ancestors below the roots is followed by a sibling, outermost first, and whether the item
+    /// itself is. The guides of the ancestors continue down past the item when they are.
+    ///
+    /// Only the ancestors of the first row, the rows and the items below them up to the next
+    /// root are looked at, so that long trees are drawn in time proportional to the rows shown.
+    fn guide_continuations(&self, rows: Range<usize>) -> HashMap<usize, (Vec<bool>, bool)> {
+        // whether an item at each level follows since the last item at a lower level, first for
+        // the items below the rows
+        let mut seen: Vec<bool> = Vec::new();
+        let mut lowest = usize::MAX;
+        for elem in &self.items[rows.end..] {
+            if elem.level < lowest {
+                lowest = elem.level;
+                seen.resize(seen.len().max(lowest + 1), false);
+                seen[lowest] = true;
+            }
+            if lowest == 0 {
+                break;
+            }
+        }
+        let mut has_next = vec![false; rows.len()];
+        for index in rows.clone().rev() {
+            let level = self.items[index].level;
+            has_next[index - rows.start] = seen.get(level).copied().unwrap_or(false);
+            seen.resize(level, false);
+            seen.push(true);
+        }
+
+        // the items in between an ancestor and the first row are its descendants, leaving
+        // whether it's followed by a sibling as seen from the first row
+        let mut chain = Vec::new();
+        let mut index = rows.start;
+        while index < self.items.len() && self.items[index].level > 0 {
+            match self.find_parent(index) {
+                Some(parent) => {
+                    let level = self.items[parent].level;
+                    chain.push((parent, seen.get(level).copied().unwrap_or(false)));
+                    index = parent;
+                }
+                None => break,
+            }
+        }
+        chain.reverse();
+
+        let mut ancestors: Vec<bool> = Vec::new();
+        let mut continues = HashMap::new();
+        let rows = rows.zip(has_next);
+        for (index, has_next) in chain.into_iter().chain(rows) {
+            let level = self.items[index].level;
+            ancestors.truncate(level.saturating_sub(1));
+            continues.insert(index, (ancestors.clone(), has_next));
+            if level > 0 {
+                ancestors.push(has_next);
+            }
+        }
+        continues
+    }
+
+    /// The ancestors of the first row below them, outermost first, pinned to the top while
+    /// their children are scrolled. They cover the rows at the top but never the selected one.
+    fn sticky_rows(&self) -> Vec<usize> {
+        let top = self.selected.saturating_sub(self.winline);
+        if top == 0 {
+            return Vec::new();
+        }
+        let max = self.winline.min(self.area.height as usize / 2);
+        let candidates = top..=(top + max).min(self.items.len().saturating_sub(1));
+        // the first row with as many ancestors as rows they cover, otherwise the last row with
+        // more, keeping its outermost ancestors
+        let first = candidates
+            .clone()
+            .find(|&index| self.items[index].level == index - top)
+            .or_else(|| {
+                candidates
+                    .rev()
+                    .find(|&index| self.items[index].level > index - top)
+            });
+        let first = match first {
+            Some(first) => first,
+            None => return Vec::new(),
+        };
+        let mut sticky = Vec::new();
+        let mut index = first;
+        while let Some(parent) = self.find_parent(index) {
+            sticky.push(parent);
+            index = parent;
+        }
+        sticky.reverse();
+        sticky.truncate(first - top);
+        sticky
+    }
+
     fn next_item(&self) -> Option<&Elem<T>> {
         self.items.get(self.selected + 1)
     }
@@ -257,7 +820,7 @@ fn find<F>(&self, start: usize, rev: bool, f: F) -> Option<usize>
 
 impl<T: TreeItem> Tree<T> {
     pub fn on_enter(&mut self, cx: &mut Context, params: &mut T::Params) {
-        if self.items.is_empty() {
+        if self.items.is_empty() || self.items[self.selected].loading.is_some() {
             return;
         }
         if let Some(next_level) = self.next_item().map(|elem| elem.level) {
@@ -276,7 +839,14 @@ pub fn on_enter(&mut self, cx: &mut Context, params: &mut T::Params) {
         if let Some(mut on_open_fn) = self.on_opened_fn.take() {
             let mut f = || {
                 let current = &mut self.items[self.selected];
-                let items = match on_open_fn(&mut current.item, cx, params) {
+                let op = match on_open_fn(&mut current.item, cx, params) {
+                    TreeOp::LoadChildsAndInsert => match current.item.load_childs() {
+                        Some(future) => TreeOp::LoadAndInsert(future),
+                        None => TreeOp::GetChildsAndInsert,
+                    },
+                    op => op,
+                };
+                let items = match op {
                     TreeOp::Restore => {
                         let inserts = std::mem::take(&mut current.folded);
                         let _: Vec<_> = self
@@ -290,6 +860,23 @@ pub fn on_enter(&mut self, cx: &mut Context, params: &mut T::Params) {
                         Ok(items) => items,
                         Err(e) => return cx.editor.set_error(format!("{e}")),
                     },
+                    TreeOp::LoadAndInsert(future) => {
+                        let id = self.next_load;
+                        self.next_load += 1;
+                        let loaded = self.loaded.clone();
+                        cx.jobs.spawn(async move {
+                            let childs = future.await;
+                            loaded.lock().unwrap().push((id, childs));
+                            Ok(())
+                        });
+                        let mut placeholder = Elem::new(current.item.clone(), current.level + 1);
+                        placeholder.loading = Some(id);
+                        current.folded = vec![];
+                        self.items.insert(self.selected + 1, placeholder);
+                        return;
+                    }
+                    // turned into the variants above
+                    TreeOp::LoadChildsAndInsert => return,
                     TreeOp::ReplaceTree(items) => return self.replace_with_new_items(items),
                     TreeOp::Noop => return,
                 };
@@ -312,6 +899,105 @@ pub fn on_enter(&mut self, cx: &mut Context, params: &mut T::Params) {
         }
     }
 
+    /// Replaces the placeholders of finished background loads with the loaded children, also
+    /// when their parent was folded or filtered out in the meantime.
+    fn insert_loaded(&mut self, cx: &mut Context) {
+        let loaded = std::mem::take(&mut *self.loaded.lock().unwrap());
+        if !loaded.is_empty() {
+            self.generation += 1;
+        }
+        for (id, childs) in loaded {
+            let childs = childs.unwrap_or_else(|e| {
+                cx.editor.set_error(format!("{e}"));
+                vec![]
+            });
+            if let Some((_, recycle)) = &mut self.recycle {
+                if let Some((elems, pos)) = find_placeholder(recycle, id) {
+                    let level = elems[pos].level;
+                    elems.splice(pos..pos + 1, vec_to_tree(childs.clone(), level));
+                }
+            }
+            let visible = self.items.iter().any(|elem| elem.loading == Some(id));
+            if let Some((elems, pos)) = find_placeholder(&mut self.items, id) {
+                let level = elems[pos].level;
+                let inserts = vec_to_tree(childs, level);
+                let len = inserts.len();
+                elems.splice(pos..pos + 1, inserts);
+                if visible && self.selected > pos {
+                    self.selected = self.selected + len - 1;
+                }
+            }
+        }
+        self.selected = self.selected.min(self.items.len().saturating_sub(1));
+    }
+
+    fn is_unfolded(&self, index: usize) -> bool {
+        self.items
+            .get(index + 1)
+            .map_or(false, |next| next.level > self.items[index].level)
+    }
+
+    /// Unfolds the selected item like `on_enter`, or selects its first child if it is unfolded.
+    pub fn expand_or_child(&mut self, cx: &mut Context, params: &mut T::Params) {
+        if self.items.is_empty() {
+            return;
+        }
+        if self.is_unfolded(self.selected) {
+            self.move_down(1);
+        } else {
+            self.on_enter(cx, params);
+        }
+    }
+
+    /// Folds the selected item like `on_enter`, or selects its parent if it is folded.
+    pub fn collapse_or_parent(&mut self, cx: &mut Context, params: &mut T::Params) {
+        if self.items.is_empty() {
+            return;
+        }
+        if self.is_unfolded(self.selected) {
+            self.on_enter(cx, params);
+        } else {
+            self.goto_parent();
+        }
+    }
+
+    pub fn goto_parent(&mut self) {
+        if self.items.is_empty() {
+            return;
+        }
+        if let Some(parent) = self.find_parent(self.selected) {
+            self.move_up(self.selected - parent);
+        }
+    }
+
+    /// Selects the next sibling of the selected item, or after the last one the item following
+    /// its parent, like a paragraph motion over the groups of siblings.
+    pub fn goto_next_sibling(&mut self) {
+        let level = match self.items.get(self.selected) {
+            Some(elem) => elem.level,
+            None => return,
+        };
+        if let Some(next) =
+            (self.selected + 1..self.items.len()).find(|&index| self.items[index].level <= level)
+        {
+            self.move_down(next - self.selected);
+        }
+    }
+
+    /// Selects the previous sibling of the selected item, or its parent if it is the first one.
+    pub fn goto_prev_sibling(&mut self) {
+        let level = match self.items.get(self.selected) {
+            Some(elem) => elem.level,
+            None => return,
+        };
+        if let Some(prev) = (0..self.selected)
+            .rev()
+            .find(|&index| self.items[index].level <= level)
+        {
+            self.move_up(self.selected - prev);
+        }
+    }
+
     pub fn fold_current_level(&mut self) {
         let start = match self.find_parent(self.selected) {
             Some(start) => start,
@@ -331,7 +1017,116 @@ pub fn fold_current_child(&mut self) {
         }
     }
 
+    /// Unfolds every item that has folded children, keeping the selected item.
+    pub fn expand_all(&mut self) {
+        let selected = self.selected;
+        let mut items = Vec::with_capacity(self.items.len());
+        for (index, elem) in std::mem::take(&mut self.items).into_iter().enumerate() {
+            if index == selected {
+                self.selected = items.len();
+            }
+            expand_elems(&mut items, elem);
+        }
+        self.items = items;
+    }
+
+    /// Folds every item, leaving only the top level items.
+    pub fn collapse_all(&mut self) {
+        self.collapse_to_depth(0)
+    }
+
+    /// Unfolds every item, then folds the items at `depth` and deeper so that no item deeper
+    /// than `depth` is shown. A hidden selection moves to its closest shown ancestor.
+    pub fn collapse_to_depth(&mut self, depth: usize) {
+        self.expand_all();
+        // fold from the bottom up so that folded items keep their own folds
+        for index in (0..self.items.len()).rev() {
+            if self.items[index].level < depth {
+                continue;
+            }
+            let pos = self.next_not_descendant_pos(index);
+            if pos == index + 1 {
+                continue;
+            }
+            if self.selected > index {
+                self.selected = if self.selected < pos {
+                    index
+                } else {
+                    self.selected - (pos - index - 1)
+                };
+            }
+            self.items[index].folded = self.items.drain(index + 1..pos).collect();
+        }
+        self.winline = self.winline.min(self.selected);
+    }
+
+    fn is_match(&self, index: usize, s: &str, cx: &mut Context, params: &mut T::Params) -> bool {
+        let elem = &self.items[index];
+        elem.loading.is_none() && elem.item.filter(cx, s, params)
+    }
+
+    /// Highlights the items matching `s` and unfolds the items with matching descendants,
+    /// without hiding anything. An empty `s` ends the search.
+    pub fn set_search(&mut self, cx: &mut Context, s: &str, params: &mut T::Params) {
+        if s.is_empty() {
+            self.search = None;
+            return;
+        }
+        let mut index = 0;
+        while index < self.items.len() {
+            if contains_match(&self.items[index].folded, &mut |item: &T| {
+                item.filter(cx, s, params)
+            }) {
+                // the unfolded children are checked next, unfolding the path to each match
+                let inserts = std::mem::take(&mut self.items[index].folded);
+                let len = inserts.len();
+                let _: Vec<_> = self.items.splice(index + 1..index + 1, inserts).collect();
+                if self.selected > index {
+                    self.selected += len;
+                }
+                if self.save_view.0 > index {
+                    self.save_view.0 += len;
+                }
+            }
+            index += 1;
+        }
+        self.search = Some(s.to_string());
+    }
+
+    /// Selects the next match of the search, or the previous one if not `forward`, wrapping
+    /// around. With `include_current` the selected item is the first candidate.
+    pub fn goto_match(
+        &mut self,
+        cx: &mut Context,
+        forward: bool,
+        include_current: bool,
+        params: &mut T::Params,
+    ) {
+        let search = match self.search.clone() {
+            Some(search) if !self.items.is_empty() => search,
+            _ => return,
+        };
+        let len = self.items.len();
+        let selected = self.selected;
+        let start = if include_current { 0 } else { 1 };
+        let found = (start..=len)
+            .map(|step| {
+                if forward {
+                    (selected + step) % len
+                } else {
+                    (selected + len - step) % len
+                }
+            })
+            .find(|index| self.is_match(*index, &search, cx, params));
+        match found {
+            Some(index) if index >= selected => self.move_down(index - selected),
+            Some(index) => self.move_up(selected - index),
+            None => {}
+        }
+    }
+
     pub fn search_next(&mut self, cx: &mut Context, s: &str, params: &mut T::Params) {
+        self.set_search(cx, s, params);
         let skip = std::cmp::max(2, self.save_view.0 + 1);
         self.selected = self
             .find(skip, false, |e| e.item.filter(cx, s, params))
@@ -341,6 +1136,7 @@ pub fn search_next(&mut self, cx: &mut Context, s: &str, params: &mut T::Params)
     }
 
     pub fn search_pre(&mut self, cx: &mut Context, s: &str, params: &mut T::Params) {
+        self.set_search(cx, s, params);
         let take = self.save_view.0;
         self.selected = self
             .find(take, true, |e| e.item.filter(cx, s, params))
@@ -400,6 +1196,23 @@ pub fn move_up_page(&mut self) {
         }));
     }
 
+    /// Adjusts the row of the selected item so that it stays `SCROLLOFF` rows away from the
+    /// edges of a viewport of `height` rows, without scrolling past the last item.
+    fn scroll_into_view(&mut self, height: usize) {
+        let scrolloff = SCROLLOFF.min(height.saturating_sub(1) / 2);
+        let below = self.items.len().saturating_sub(self.selected + 1);
+        let hidden = self.items.len().saturating_sub(height);
+        let min_winline = scrolloff
+            .min(self.selected)
+            .max(self.selected.saturating_sub(hidden));
+        let max_winline = height.saturating_sub(1 + scrolloff.min(below));
+        self.winline = self
+            .winline
+            .min(max_winline)
+            .max(min_winline)
+            .min(self.selected);
+    }
+
     pub fn save_view(&mut self) {
         self.save_view = (self.selected, self.winline);
     }
@@ -408,6 +1221,60 @@ pub fn restore_view(&mut self) {
         (self.selected, self.winline) = self.save_view;
     }
 
+    pub fn is_empty(&self) -> bool {
+        self.items.is_empty()
+    }
+
+    /// Selects the last visible item matching `f`. Returns whether there was one.
+    pub fn select_last(&mut self, f: impl Fn(&T) -> bool) -> bool {
+        match self.items.iter().rposition(|elem| f(&elem.item)) {
+            Some(pos) if pos >= self.selected => self.move_down(pos - self.selected),
+            Some(pos) => self.move_up(self.selected - pos),
+            None => return false,
+        }
+        true
+    }
+
+    /// The items, the folded ones included, in the order they are listed when unfolded.
+    pub fn all_items(&self) -> Vec<&T> {
+        fn push_items<'a, T>(elems: &'a [Elem<T>], items: &mut Vec<&'a T>) {
+            for elem in elems {
+                items.push(&elem.item);
+                push_items(&elem.folded, items);
+            }
+        }
+        let mut items = Vec::new();
+        push_items(&self.items, &mut items);
+        items
+    }
+
+    /// Selects the first item matching `f`, unfolding its ancestors if it's folded. Returns
+    /// whether there was one.
+    pub fn reveal(&mut self, f: impl Fn(&T) -> bool) -> bool {
+        fn contains<T>(elems: &[Elem<T>], f: &impl Fn(&T) -> bool) -> bool {
+            elems
+                .iter()
+                .any(|elem| f(&elem.item) || contains(&elem.folded, f))
+        }
+        let mut index = 0;
+        while index < self.items.len() {
+            if f(&self.items[index].item) {
+                match index.cmp(&self.selected) {
+                    Ordering::Less => self.move_up(self.selected - index),
+                    _ => self.move_down(index - self.selected),
+                }
+                return true;
+            }
+            // the item is below this one, which is unfolded on the way
+            if contains(&self.items[index].folded, &f) {
+                let folded = std::mem::take(&mut self.items[index].folded);
+                self.items.splice(index + 1..index + 1, folded);
+            }
+            index += 1;
+        }
+        false
+    }
+
     pub fn current(&self) -> &Elem<T> {
         &self.items[self.selected]
     }
@@ -420,17 +1287,131 @@ pub fn row(&self) -> usize {
         self.winline
     }
 
+    pub fn selected(&self) -> usize {
+        self.selected
+    }
+
+    /// Counts the edits of the items, like inserting, removing, moving or updating them, so
+    /// that row indices and layouts kept for an older generation can be recognized as stale.
+    /// Folding and unfolding items doesn't change it.
+    pub fn generation(&self) -> u64 {
+        self.generation
+    }
+
+    /// Appends a top level item after all other items.
+    pub fn push(&mut self, item: T) {
+        self.generation += 1;
+        self.items.push(Elem::new(item, 0));
+    }
+
     pub fn remove_current(&mut self) -> T {
+        self.generation += 1;
         let elem = self.items.remove(self.selected);
         self.selected = self.selected.saturating_sub(1);
         elem.item
     }
 
     pub fn replace_current(&mut self, item: T) {
-        self.items[self.selected].item = item;
+        self.update_row(self.selected, |current| *current = item);
+    }
+
+    /// Edits the item at `index` in place.
+    pub fn update_row(&mut self, index: usize, f: impl FnOnce(&mut T)) {
+        self.generation += 1;
+        f(&mut self.items[index].item);
+    }
+
+    /// Inserts `item` as the child of the item at `parent`, or as a top level item without a
+    /// parent, among its siblings in the order of [`TreeItem::cmp`]. The child of a folded item
+    /// is inserted folded. Returns the index of the inserted item if it's visible.
+    pub fn insert_child(&mut self, parent: Option<usize>, item: T) -> Option<usize> {
+        self.insert_subtree(parent, vec![Elem::new(item, 0)])
+    }
+
+    /// Removes the item at `index` with its descendants, returning the item. The selection
+    /// stays on the same item, or moves to the row taking the place of the removed ones.
+    pub fn remove_subtree(&mut self, index: usize) -> T {
+        self.generation += 1;
+        let end = self.next_not_descendant_pos(index);
+        let removed = self
+            .items
+            .drain(index..end)
+            .next()
+            .expect("the item is removed");
+        if self.selected >= end {
+            self.selected -= end - index;
+        } else if self.selected >= index {
+            self.selected = index;
+        }
+        self.selected = self.selected.min(self.items.len().saturating_sub(1));
+        self.winline = self.winline.min(self.selected);
+        removed.item
+    }
+
+    /// Moves the item at `index` with its descendants under the item at `parent`, or to the top
+    /// level without a parent, like [`Tree::insert_child`] inserts it. A selected item moves
+    /// along, or selects its new parent if that is folded. Returns false without moving
+    /// anything if `parent` is the item or one of its descendants.
+    pub fn move_subtree(&mut self, index: usize, parent: Option<usize>) -> bool {
+        let end = self.next_not_descendant_pos(index);
+        if parent.map_or(false, |parent| (index..end).contains(&parent)) {
+            return false;
+        }
+        let len = end - index;
+        // the position of the selection in the subtree, if it's selected
+        let selected_offset = Some(self.selected.wrapping_sub(index)).filter(|&o| o < len);
+
+        let subtree: Vec<_> = self.items.drain(index..end).collect();
+        if selected_offset.is_none() && self.selected >= end {
+            self.selected -= len;
+        }
+        let parent = parent.map(|parent| if parent >= end { parent - len } else { parent });
+        match (self.insert_subtree(parent, subtree), selected_offset) {
+            (Some(pos), Some(offset)) => self.selected = pos + offset,
+            (None, Some(_)) => self.selected = parent.unwrap_or_default(),
+            _ => {}
+        }
+        self.selected = self.selected.min(self.items.len().saturating_sub(1));
+        self.winline = self.winline.min(self.selected);
+        true
+    }
+
+    /// Inserts the items of `subtree`, the first one being its root, like
+    /// [`Tree::insert_child`]. The selection stays on the same item.
+    fn insert_subtree(
+        &mut self,
+        parent: Option<usize>,
+        mut subtree: Vec<Elem<T>>,
+    ) -> Option<usize> {
+        self.generation += 1;
+        let level = parent.map_or(0, |parent| self.items[parent].level + 1);
+        let root_level = subtree[0].level;
+        set_levels(&mut subtree, |subtree_level| {
+            subtree_level - root_level + level
+        });
+
+        if let Some(parent) = parent.filter(|&parent| !self.items[parent].folded.is_empty()) {
+            let folded = &mut self.items[parent].folded;
+            let pos = sibling_pos(folded, 0..folded.len(), level, &subtree[0].item);
+            folded.splice(pos..pos, subtree);
+            return None;
+        }
+        let range = match parent {
+            Some(parent) => parent + 1..self.next_not_descendant_pos(parent),
+            None => 0..self.items.len(),
+        };
+        let pos = sibling_pos(&self.items, range, level, &subtree[0].item);
+        let len = subtree.len();
+        let was_empty = self.items.is_empty();
+        self.items.splice(pos..pos, subtree);
+        if !was_empty && self.selected >= pos {
+            self.selected += len;
+        }
+        Some(pos)
     }
 
     pub fn insert_current_level(&mut self, item: T) {
+        self.generation += 1;
         let current = self.current();
         let level = current.level;
         let pos = match current.item.cmp(&item) {
@@ -464,48 +1445,191 @@ pub fn render(
         cx: &mut Context,
         params: &mut T::Params,
     ) {
+        self.insert_loaded(cx);
+        let mut area = area;
+        if let Some(prompt) = &self.search_prompt {
+            let prompt_area = area.clip_top(area.height.saturating_sub(1));
+            prompt.render_prompt(prompt_area, surface, cx);
+            area = area.clip_bottom(1);
+        }
         if let Some(pre_render) = self.pre_render.take() {
             pre_render(self, area);
         }
 
-        self.max_len = 0;
-        self.winline = std::cmp::min(self.winline, area.height.saturating_sub(1) as usize);
-        let style = cx.editor.theme.get(&self.tree_symbol_style);
-        let last_item_index = self.items.len().saturating_sub(1);
-        let skip = self.selected.saturating_sub(self.winline);
-        let iter = self
+        self.area = area;
+        self.scroll_into_view(area.height as usize);
+        // fold indicators are only shown if there is something to fold
+        let foldable = self
             .items
             .iter()
-            .skip(skip)
-            .take(area.height as usize)
-            .enumerate();
-        for (index, elem) in iter {
-            let row = index as u16;
-            let mut area = Rect::new(area.x, area.y + row, area.width, 1);
-            let indent = if elem.level > 0 {
-                if index + skip != last_item_index {
-                    format!("{}├─", "│ ".repeat(elem.level - 1))
+            .any(|elem| elem.level > 0 || !elem.folded.is_empty());
+        let style = cx.editor.theme.get(&self.tree_symbol_style);
+        let selected_style = selected_style(cx);
+        let guides = cx.editor.config().tree.indent_guides;
+        let ellipsis = cx.editor.config().tree.ellipsis;
+        let guide_style = cx.editor.theme.try_get("ui.tree.guides").unwrap_or(style);
+        let top = self.selected.saturating_sub(self.winline);
+        let first = (top + self.sticky_rows().len()).min(self.items.len());
+        let end = (first + area.height as usize).min(self.items.len());
+        let continues = self.guide_continuations(first..end);
+        // without a theme style the sticky rows are set apart by underlining the last one
+        let sticky_rows = self.sticky_rows().len();
+        let sticky_style = cx.editor.theme.try_get("ui.tree.sticky");
+        let match_style = cx
+            .editor
+            .theme
+            .try_get("ui.search.match")
+            .unwrap_or_else(|| cx.editor.theme.get("ui.selection"));
+        let search = self.search.clone();
+        // the gutter of the marks is only shown while items are marked
+        self.mark_gutter = self.items.iter().any(|elem| elem.marked);
+
+        let mut rows = Vec::with_capacity(area.height as usize);
+        for (row, (index, level)) in self.visible_rows().enumerate() {
+            let elem = &self.items[index];
+            let mut indent = String::new();
+            if self.mark_gutter {
+                indent.push_str(if elem.marked { "● " } else { "  " });
+            }
+            let guides_start = indent.len();
+            if level > 0 {
+                let (ancestors, has_next) = &continues[&index];
+                for &continued in ancestors {
+                    indent.push_str(match guides {
+                        TreeIndentGuides::Lines if continued => "│ ",
+                        TreeIndentGuides::Bars => "│ ",
+                        _ => "  ",
+                    });
+                }
+                indent.push_str(match guides {
+                    TreeIndentGuides::Lines if *has_next => "├─",
+                    TreeIndentGuides::Lines => "└─",
+                    TreeIndentGuides::Bars => "│ ",
+                    TreeIndentGuides::None => "  ",
+                });
+            }
+            let guides_range = guides_start..indent.len();
+            if foldable {
+                indent.push_str(if !elem.folded.is_empty() {
+                    "⏵ "
+                } else if self.has_children(index) {
+                    "⏷ "
                 } else {
-                    format!("└─{}", "┴─".repeat(elem.level - 1))
+                    "  "
+                });
+            }
+
+            let selected = index == self.selected;
+            let (mut text, cells) = match elem.loading {
+                Some(_) => {
+                    let mut style = cx.editor.theme.get("comment");
+                    if selected {
+                        style = style.patch(selected_style);
+                    }
+                    (Spans::from(Span::styled("loading…", style)), Vec::new())
+                }
+                None => (
+                    elem.item.text(cx, selected, params),
+                    elem.item.columns(cx, selected, params),
+                ),
+            };
+            if row < sticky_rows {
+                let patch = match sticky_style {
+                    Some(style) => style,
+                    None if row + 1 == sticky_rows => {
+                        Style::default().add_modifier(Modifier::UNDERLINED)
+                    }
+                    None => Style::default(),
+                };
+                for span in text.0.iter_mut() {
+                    span.style = span.style.patch(patch);
+                }
+            }
+            if let Some(search) = &search {
+                if !selected && self.is_match(index, search, cx, params) {
+                    for span in text.0.iter_mut() {
+                        span.style = span.style.patch(match_style);
+                    }
+                }
+            }
+            rows.push((indent, guides_range, text, cells, selected));
+        }
+
+        // the widest cell of each column, counting the indent to the text
+        let mut widths: Vec<usize> = Vec::new();
+        for (indent, _, text, cells, _) in rows.iter().filter(|(.., cells, _)| !cells.is_empty()) {
+            let row_widths = std::iter::once(indent.width() + text.width())
+                .chain(cells.iter().map(|cell| cell.width()));
+            for (column, width) in row_widths.enumerate() {
+                match widths.get_mut(column) {
+                    Some(max) => *max = (*max).max(width),
+                    None => widths.push(width),
                 }
+            }
+        }
+        for (column, width) in self.column_widths.iter().enumerate() {
+            if let (Some(width), Some(max)) = (width, widths.get_mut(column)) {
+                *max = *width;
+            }
+        }
+        // the other columns are right aligned against the edge, the text taking the rest
+        if self.column_widths.first().map_or(true, Option::is_none) && widths.len() > 1 {
+            let columns_width: usize = widths[1..].iter().map(|width| width + COLUMN_GAP).sum();
+            widths[0] = (area.width as usize).saturating_sub(columns_width);
+        }
+
+        let mut max_len = 0;
+        for (row, (indent, guides_range, text, cells, selected)) in rows.into_iter().enumerate() {
+            let mut area = Rect::new(area.x, area.y + row as u16, area.width, 1);
+            let pad_style = if selected {
+                selected_style
             } else {
-                "".to_string()
+                Style::default()
+            };
+            let text_len = text.width() + indent.len();
+            let mut text = if cells.is_empty() {
+                // scrolled rows are left whole to scroll through them
+                if self.col == 0 {
+                    let text_width = (area.width as usize).saturating_sub(indent.width());
+                    Spans::from(elide(text, text_width, ellipsis))
+                } else {
+                    text
+                }
+            } else {
+                let text_width = widths[0].saturating_sub(indent.width());
+                let mut spans = fit_cell(text, text_width, pad_style, ellipsis);
+                for (cell, width) in cells.into_iter().zip(&widths[1..]) {
+                    spans.push(Span::styled(" ".repeat(COLUMN_GAP), pad_style));
+                    spans.extend(fit_cell(cell, *width, pad_style, TreeEllipsis::End));
+                }
+                Spans::from(spans)
             };
 
             let indent_len = indent.chars().count();
-            if indent_len > self.col {
-                let indent: String = indent.chars().skip(self.col).collect();
-                if !indent.is_empty() {
-                    surface.set_stringn(area.x, area.y, &indent, area.width as usize, style);
-                    area = area.clip_left(indent.width() as u16);
+            // the marks, the guides and the fold indicator, scrolled by `self.col` together
+            let parts = [
+                (&indent[..guides_range.start], style),
+                (&indent[guides_range.clone()], guide_style),
+                (&indent[guides_range.end..], style),
+            ];
+            let mut skip = self.col;
+            for (part, part_style) in parts {
+                let part_len = part.chars().count();
+                if skip >= part_len {
+                    skip -= part_len;
+                    continue;
                 }
-            };
+                let start = part.char_indices().nth(skip).map_or(part.len(), |(i, _)| i);
+                skip = 0;
+                let visible = &part[start..];
+                surface.set_stringn(area.x, area.y, visible, area.width as usize, part_style);
+                area = area.clip_left(visible.width() as u16);
+            }
             let mut start_index = self.col.saturating_sub(indent_len);
-            let mut text = elem.item.text(cx, skip + index == self.selected, params);
-            self.max_len = self.max_len.max(text.width() + indent.len());
+            max_len = max_len.max(text_len.max(text.width() + indent.len()));
             for span in text.0.iter_mut() {
                 if area.width == 0 {
-                    return;
+                    break;
                 }
                 if start_index == 0 {
                     surface.set_span(area.x, area.y, span, area.width);
@@ -520,7 +1644,8 @@ pub fn render(
                             .chars()
                             .filter(|c| {
                                 if start_index > 0 {
-                                    start_index = start_index.saturating_sub(c.to_string().width());
+                                    start_index =
+                                        start_index.saturating_sub(c.width().unwrap_or(0));
                                     false
                                 } else {
                                     true
@@ -541,6 +1666,154 @@ pub fn render(
                 }
             }
         }
+        self.max_len = max_len;
+    }
+
+    /// Renders the tree in a block titled with the title and the number of items, above a
+    /// footer with the active filter and search, how many of the visible rows match them and
+    /// the key hints. Returns the area of the rows.
+    pub fn render_framed(
+        &mut self,
+        area: Rect,
+        surface: &mut Surface,
+        cx: &mut Context,
+        params: &mut T::Params,
+    ) -> Rect {
+        let total = match &self.recycle {
+            Some((_, unfiltered)) => count_items(unfiltered),
+            None => count_items(&self.items),
+        };
+        let title = match &self.title {
+            Some(title) => format!(" {} ({}) ", title, total),
+            None => format!(" {} ", total),
+        };
+        let block = Block::default()
+            .title(title)
+            .borders(Borders::ALL)
+            .border_style(super::border_style(&cx.editor.theme, cx.focus));
+        let inner = block.inner(area);
+        block.render(area, surface);
+
+        let rows_area = inner.clip_bottom(1);
+        self.render(rows_area, surface, cx, params);
+
+        let footer = self.footer(cx, params);
+        let footer_area = inner.clip_top(rows_area.height);
+        surface.set_stringn(
+            footer_area.x,
+            footer_area.y,
+            footer,
+            footer_area.width as usize,
+            cx.editor.theme.get("comment"),
+        );
+        rows_area
+    }
+
+    /// The footer of [`Tree::render_framed`], e.g. `filter: foo  /bar  3/12  [o]fold`.
+    fn footer(&self, cx: &mut Context, params: &mut T::Params) -> String {
+        let filter = self.recycle.as_ref().map(|(filter, _)| filter.as_str());
+        let mut parts = Vec::new();
+        if let Some(filter) = filter {
+            parts.push(format!("filter: {}", filter));
+        }
+        if let Some(search) = &self.search {
+            parts.push(format!("/{}", search));
+        }
+        let visible = self
+            .items
+            .iter()
+            .filter(|elem| elem.loading.is_none())
+            .count();
+        match self.search.as_deref().or(filter) {
+            Some(pattern) => {
+                let matched = (0..self.items.len())
+                    .filter(|&index| self.is_match(index, pattern, cx, params))
+                    .count();
+                parts.push(format!("{}/{}", matched, visible));
+            }
+            None => parts.push(format!("{} rows", visible)),
+        }
+        parts.extend(self.footer_hints.clone());
+        parts.join("  ")
+    }
+
+    /// Scrolls the view by `rows`, moving the selection only as far as needed to keep it in
+    /// view.
+    fn scroll_view(&mut self, rows: usize, down: bool) {
+        let len = self.items.len();
+        let height = self.area.height as usize;
+        if len == 0 || height == 0 {
+            return;
+        }
+        let scrolloff = SCROLLOFF.min(height.saturating_sub(1) / 2);
+        let max_top = len.saturating_sub(height);
+        let top = self.selected.saturating_sub(self.winline);
+        let top = if down {
+            (top + rows).min(max_top)
+        } else {
+            top.saturating_sub(rows)
+        };
+        let first = if top == 0 { 0 } else { top + scrolloff };
+        let last = if top == max_top {
+            len - 1
+        } else {
+            top + height - 1 - scrolloff
+        };
+        self.selected = self.selected.max(first).min(last.min(len - 1));
+        self.winline = self.selected - top;
+    }
+
+    /// Handles a mouse event over the area the tree was last rendered in: a click selects an
+    /// item, a click on a fold indicator toggles the fold, a double click activates the item and
+    /// the wheel scrolls the view.
+    pub fn handle_mouse(
+        &mut self,
+        event: &MouseEvent,
+        cx: &mut Context,
+        params: &mut T::Params,
+    ) -> MouseAction {
+        let area = self.area;
+        let inside = area.left() <= event.column
+            && event.column < area.right()
+            && area.top() <= event.row
+            && event.row < area.bottom();
+        if !inside {
+            return MouseAction::Ignored;
+        }
+        let scroll_lines = cx.editor.config().scroll_lines.unsigned_abs();
+        match event.kind {
+            MouseEventKind::ScrollDown => self.scroll_view(scroll_lines, true),
+            MouseEventKind::ScrollUp => self.scroll_view(scroll_lines, false),
+            MouseEventKind::Down(MouseButton::Left) => {
+                let row = (event.row - area.y) as usize;
+                let index = match self.visible_rows().nth(row) {
+                    Some((index, _)) => index,
+                    None => return MouseAction::Handled,
+                };
+                let double_click = matches!(
+                    self.last_click,
+                    Some((last, time)) if last == index && time.elapsed() < DOUBLE_CLICK_TIMEOUT
+                );
+                self.selected = index;
+                self.winline = row;
+                if double_click {
+                    self.last_click = None;
+                    return MouseAction::Activate;
+                }
+                self.last_click = Some((index, Instant::now()));
+
+                // the indicator follows the two columns of indentation per level
+                let column = (event.column - area.x) as usize + self.col;
+                let gutter = if self.mark_gutter { 2 } else { 0 };
+                let indicator = gutter + self.items[index].level * 2;
+                if (indicator..indicator + 2).contains(&column) && self.has_children(index) {
+                    self.last_click = None;
+                    self.on_enter(cx, params);
+                }
+            }
+            _ => return MouseAction::Ignored,
+        }
+        MouseAction::Handled
     }
 
     pub fn handle_event(
@@ -551,38 +1824,171 @@ pub fn handle_event(
     ) -> EventResult {
         let key_event = match event {
             Event::Key(event) => event,
+            Event::Mouse(event) => {
+                return match self.handle_mouse(&event, cx, params) {
+                    MouseAction::Ignored => EventResult::Ignored(None),
+                    MouseAction::Handled => EventResult::Consumed(None),
+                    MouseAction::Activate => {
+                        self.on_enter(cx, params);
+                        EventResult::Consumed(None)
+                    }
+                }
+            }
             Event::Resize(..) => return EventResult::Consumed(None),
             _ => return EventResult::Ignored(None),
         };
-        if let Some(mut on_next_key) = self.on_next_key.take() {
-            on_next_key(cx, self, key_event);
-            return EventResult::Consumed(None);
+        match self.key_action(key_event, cx, params) {
+            TreeKey::Action(action, count) => self.run_action(action, count, cx, params),
+            TreeKey::Consumed => {}
+            TreeKey::Ignored => return EventResult::Ignored(None),
+        }
+        EventResult::Consumed(None)
+    }
+
+    /// Looks up `event` in the tree keymap. Panels run the actions they handle themselves, like
+    /// [`TreeAction::Peek`], and pass the others to [`Tree::run_action`].
+    pub fn key_action(
+        &mut self,
+        event: KeyEvent,
+        cx: &mut Context,
+        params: &mut T::Params,
+    ) -> TreeKey {
+        self.insert_loaded(cx);
+        if self.search_prompt.is_some() {
+            self.handle_search_event(event, cx, params);
+            return TreeKey::Consumed;
         }
         let count = std::mem::replace(&mut self.count, 0);
-        match key_event.into() {
-            key!(i @ '0'..='9') => self.count = i.to_digit(10).unwrap() as usize + count * 10,
-            key!('k') | shift!(Tab) | key!(Up) | ctrl!('k') => self.move_up(1.max(count)),
-            key!('j') | key!(Tab) | key!(Down) | ctrl!('j') => self.move_down(1.max(count)),
-            key!('z') => self.fold_current_level(),
-            key!('h') => self.move_left(1.max(count)),
-            key!('l') => self.move_right(1.max(count)),
-            shift!('G') => self.move_down(usize::MAX / 2),
-            key!(Enter) => self.on_enter(cx, params),
-            ctrl!('d') => self.move_down_half_page(),
-            ctrl!('u') => self.move_up_half_page(),
-            shift!('D') => self.move_down_page(),
-            shift!('U') => self.move_up_page(),
-            key!('g') => {
-                self.on_next_key = Some(Box::new(|_, tree, event| match event.into() {
-                    key!('g') => tree.move_up(usize::MAX / 2),
-                    key!('e') => tree.move_down(usize::MAX / 2),
-                    _ => {}
-                }));
+        let trie = match self.pending_keys.take() {
+            // the second key of a sequence is consumed even if it isn't bound
+            Some(node) => match get_in_node(&node, event) {
+                Some(trie) => trie.clone(),
+                None => return TreeKey::Consumed,
+            },
+            None => {
+                if let key!(i @ '0'..='9') = event {
+                    self.count = i.to_digit(10).unwrap() as usize + count * 10;
+                    return TreeKey::Consumed;
+                }
+                match cx.editor.tree_keymap.get(event) {
+                    Some(trie) => trie.clone(),
+                    None => return TreeKey::Ignored,
+                }
             }
-            _ => return EventResult::Ignored(None),
+        };
+        match trie {
+            TreeKeyTrie::Node(node) => {
+                // keep the count for the rest of the sequence, like `5gg`
+                self.count = count;
+                self.pending_keys = Some(node);
+                TreeKey::Consumed
+            }
+            TreeKeyTrie::Action(action) => TreeKey::Action(action, count),
         }
+    }
 
-        EventResult::Consumed(None)
+    /// Searches as the pattern is typed, from the item selected when the search started.
+    fn handle_search_event(&mut self, event: KeyEvent, cx: &mut Context, params: &mut T::Params) {
+        let mut prompt = match self.search_prompt.take() {
+            Some(prompt) => prompt,
+            None => return,
+        };
+        match event {
+            key!(Enter) => {}
+            key!(Esc) | ctrl!('c') => {
+                self.restore_view();
+                self.search = None;
+            }
+            _ => {
+                if let EventResult::Consumed(_) = prompt.handle_event(&Event::Key(event), cx) {
+                    self.restore_view();
+                    self.set_search(cx, prompt.line(), params);
+                    self.goto_match(cx, true, true, params);
+                }
+                self.search_prompt = Some(prompt);
+            }
+        }
+    }
+
+    /// Runs `action` bound in the tree keymap, `count` times for motions.
+    pub fn run_action(
+        &mut self,
+        action: TreeAction,
+        count: usize,
+        cx: &mut Context,
+        params: &mut T::Params,
+    ) {
+        match action {
+            TreeAction::MoveUp => self.move_up(1.max(count)),
+            TreeAction::MoveDown => self.move_down(1.max(count)),
+            TreeAction::Activate => {
+                if !self.activate_marked(cx, params) {
+                    self.on_enter(cx, params)
+                }
+            }
+            TreeAction::ToggleFold => self.on_enter(cx, params),
+            TreeAction::ToggleMark => self.toggle_mark(1.max(count)),
+            TreeAction::ClearMarks => self.clear_marks(),
+            TreeAction::FoldLevel => self.fold_current_level(),
+            TreeAction::Expand => self.expand_or_child(cx, params),
+            TreeAction::Collapse => self.collapse_or_parent(cx, params),
+            TreeAction::GotoParent => {
+                for _ in 0..1.max(count) {
+                    self.goto_parent()
+                }
+            }
+            TreeAction::NextSibling => {
+                for _ in 0..1.max(count) {
+                    self.goto_next_sibling()
+                }
+            }
+            TreeAction::PrevSibling => {
+                for _ in 0..1.max(count) {
+                    self.goto_prev_sibling()
+                }
+            }
+            TreeAction::Search => {
+                self.save_view();
+                self.search_prompt = Some(Prompt::new(
+                    "search: ".into(),
+                    None,
+                    ui::completers::none,
+                    |_, _, _| {},
+                ));
+            }
+            TreeAction::SearchNext => {
+                for _ in 0..1.max(count) {
+                    self.goto_match(cx, true, false, params);
+                }
+            }
+            TreeAction::SearchPrev => {
+                for _ in 0..1.max(count) {
+                    self.goto_match(cx, false, false, params);
+                }
+            }
+            TreeAction::ExpandAll => self.expand_all(),
+            // with a count, show that many levels
+            TreeAction::CollapseAll => self.collapse_to_depth(count.saturating_sub(1)),
+            TreeAction::ScrollLeft => self.move_left(1.max(count)),
+            TreeAction::ScrollRight => self.move_right(1.max(count)),
+            // with a count, go to that row like `gg` and `G` go to a line in the editor
+            TreeAction::GotoFirst | TreeAction::GotoLast if count > 0 => {
+                self.move_up(usize::MAX / 2);
+                self.move_down(count - 1);
+            }
+            TreeAction::GotoFirst => self.move_up(usize::MAX / 2),
+            TreeAction::GotoLast => self.move_down(usize::MAX / 2),
+            TreeAction::HalfPageUp => self.move_up_half_page(),
+            TreeAction::HalfPageDown => self.move_down_half_page(),
+            TreeAction::PageUp => self.move_up_page(),
+            TreeAction::PageDown => self.move_down_page(),
+            // run by the panels which have them
+            TreeAction::Peek
+            | TreeAction::PeekBack
+            | TreeAction::Filter
+            | TreeAction::Sort
+            | TreeAction::NoOp => {}
+        }
     }
 }
 
@@ -642,7 +2048,7 @@ fn filter_recursion<T>(
                 let (sub_items, current_index) = filter_recursion(items, index, s, cx, params);
                 index = current_index;
                 retain.extend(sub_items);
-            } else if elem.item.filter(cx, s, params) {
+            } else if elem.loading.is_none() && elem.item.filter(cx, s, params) {
                 retain.push(elem.clone())
             }
             index += 1;
@@ -680,3 +2086,177 @@ pub fn restore_recycle(&mut self) {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use helix_view::graphics::Color;
+
+    fn elided(text: &str, width: usize, ellipsis: TreeEllipsis) -> String {
+        let spans = Spans::from(vec![
+            Span::raw(&text[..text.len() / 2]),
+            Span::styled(&text[text.len() / 2..], Style::default().fg(Color::Red)),
+        ]);
+        elide(spans, width, ellipsis)
+            .iter()
+            .map(|span| span.content.as_ref())
+            .collect()
+    }
+
+    #[test]
+    fn elide_rows() {
+        assert_eq!(elided("short", 5, TreeEllipsis::End), "short");
+        assert_eq!(elided("very_long_name", 8, TreeEllipsis::End), "very_lo…");
+        assert_eq!(
+            elided("very_long_name", 8, TreeEllipsis::Middle),
+            "very…ame"
+        );
+        assert_eq!(elided("very_long_name", 1, TreeEllipsis::Middle), "…");
+        assert_eq!(elided("very_long_name", 0, TreeEllipsis::End), "");
+        // wide graphemes aren't split
+        assert_eq!(elided("日本語のテキスト", 6, TreeEllipsis::End), "日本…");
+    }
+
+    #[derive(Clone)]
+    struct Name(&'static str);
+
+    impl TreeItem for Name {
+        type Params = ();
+
+        fn text(&self, _cx: &mut Context, _selected: bool, _params: &mut ()) -> Spans {
+            Spans::from(self.0)
+        }
+
+        fn is_child(&self, _other: &Self) -> bool {
+            false
+        }
+
+        fn cmp(&self, other: &Self) -> Ordering {
+            self.0.cmp(other.0)
+        }
+    }
+
+    /// The visible rows as `level:name`.
+    fn rows(tree: &Tree<Name>) -> Vec<String> {
+        tree.items
+            .iter()
+            .map(|elem| format!("{}:{}", elem.level, elem.item.0))
+            .collect()
+    }
+
+    fn tree() -> Tree<Name> {
+        // a
+        //   b
+        //     c
+        // d
+        Tree::new(vec![
+            Elem::new(Name("a"), 0),
+            Elem::new(Name("b"), 1),
+            Elem::new(Name("c"), 2),
+            Elem::new(Name("d"), 0),
+        ])
+    }
+
+    #[test]
+    fn edit_rows() {
+        let mut tree = tree();
+        tree.selected = 3;
+        let generation = tree.generation();
+
+        assert_eq!(tree.insert_child(Some(0), Name("ab")), Some(1));
+        assert_eq!(rows(&tree), ["0:a", "1:ab", "1:b", "2:c", "0:d"]);
+        assert_eq!(tree.current_item().0, "d");
+        assert_eq!(tree.insert_child(None, Name("e")), Some(5));
+        assert!(tree.generation() > generation);
+
+        let generation = tree.generation();
+        tree.update_row(1, |item| item.0 = "bb");
+        assert_eq!(rows(&tree)[1], "1:bb");
+        assert!(tree.generation() > generation);
+
+        assert_eq!(tree.remove_subtree(2).0, "b");
+        assert_eq!(rows(&tree), ["0:a", "1:bb", "0:d", "0:e"]);
+        assert_eq!(tree.current_item().0, "d");
+
+        // the child of a folded item stays folded
+        tree.selected = 0;
+        tree.fold_current_child();
+        assert_eq!(tree.insert_child(Some(0), Name("b")), None);
+        assert_eq!(rows(&tree), ["0:a", "0:d", "0:e"]);
+        let folded: Vec<_> = tree.items[0].folded.iter().map(|e| e.item.0).collect();
+        assert_eq!(folded, ["b", "bb"]);
+    }
+
+    #[test]
+    fn move_rows() {
+        let mut tree = tree();
+        tree.selected = 2;
+
+        // an item can't move below itself
+        assert!(!tree.move_subtree(1, Some(2)));
+        assert!(tree.move_subtree(1, Some(3)));
+        assert_eq!(rows(&tree), ["0:a", "0:d", "1:b", "2:c"]);
+        assert_eq!(tree.current_item().0, "c");
+
+        assert!(tree.move_subtree(2, None));
+        assert_eq!(rows(&tree), ["0:a", "0:b", "1:c", "0:d"]);
+        assert_eq!(tree.current_item().0, "c");
+
+        tree.selected = 3;
+        assert!(tree.move_subtree(0, Some(1)));
+        assert_eq!(rows(&tree), ["0:b", "1:a", "1:c", "0:d"]);
+        assert_eq!(tree.current_item().0, "d");
+    }
+
+    #[test]
+    fn guides_of_rows() {
+        let levels = [0, 1, 2, 2, 3, 1, 2, 0, 1, 1, 2, 3, 3, 2, 0];
+        let tree = Tree::new(
+            levels
+                .iter()
+                .map(|&level| Elem::new(Name("item"), level))
+                .collect(),
+        );
+        let all = tree.guide_continuations(0..levels.len());
+        assert_eq!(all[&1], (vec![], true));
+        assert_eq!(all[&4], (vec![true, false], false));
+        assert_eq!(all[&12], (vec![false, true], false));
+        // the guides of a part of the rows, and of the ancestors of the first one, are the same
+        for start in 0..levels.len() {
+            for end in start..=levels.len() {
+                for (index, guides) in tree.guide_continuations(start..end) {
+                    assert_eq!(guides, all[&index], "rows {}..{}", start, end);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn reveal_folded_items() {
+        let mut tree = tree();
+        tree.fold_current_child();
+        assert_eq!(rows(&tree), ["0:a", "0:d"]);
+        let names: Vec<_> = tree.all_items().iter().map(|item| item.0).collect();
+        assert_eq!(names, ["a", "b", "c", "d"]);
+
+        assert!(tree.reveal(|item| item.0 == "c"));
+        assert_eq!(rows(&tree), ["0:a", "1:b", "2:c", "0:d"]);
+        assert_eq!(tree.current_item().0, "c");
+        assert!(tree.reveal(|item| item.0 == "a"));
+        assert_eq!(tree.selected(), 0);
+        assert!(!tree.reveal(|item| item.0 == "e"));
+    }
+
+    #[test]
+    fn toggle_marks() {
+        let mut tree = tree();
+        tree.selected = 2;
+        tree.toggle_mark(3);
+        assert_eq!(tree.marked(), [2, 3]);
+        assert_eq!(tree.selected(), 3);
+
+        let mut tree = Tree::<Name>::new(vec![]);
+        tree.toggle_mark(1);
+        assert!(tree.marked().is_empty());
+    }
+}
@@ -0,0 +1,225 @@
+use super::{tree::patch_selected, MouseAction, Tree, TreeItem};
+use crate::{
+    compositor::{Component, Compositor, Context, EventResult},
+    ctrl, key,
+};
+use helix_core::Position;
+use helix_view::{
+    editor::Action,
+    graphics::{CursorKind, Rect},
+    input::Event,
+    Editor, Theme,
+};
+use std::{
+    cmp::Ordering,
+    collections::hash_map::DefaultHasher,
+    hash::{Hash, Hasher},
+    sync::Arc,
+};
+use tui::{
+    buffer::Buffer as Surface,
+    text::Spans,
+    widgets::{Block, Borders, Widget},
+};
+
+/// A value shown by a [`TreePicker`] with the values nested below it.
+pub struct TreePickerNode<T> {
+    pub value: T,
+    pub children: Vec<TreePickerNode<T>>,
+}
+
+impl<T> TreePickerNode<T> {
+    pub fn new(value: T) -> Self {
+        Self {
+            value,
+            children: Vec::new(),
+        }
+    }
+
+    pub fn with_children(mut self, children: Vec<TreePickerNode<T>>) -> Self {
+        self.children = children;
+        self
+    }
+}
+
+struct PickerItem<T> {
+    /// The indices of the node and its ancestors among the children of their parents.
+    path: Vec<usize>,
+    value: Arc<T>,
+}
+
+impl<T> Clone for PickerItem<T> {
+    fn clone(&self) -> Self {
+        Self {
+            path: self.path.clone(),
+            value: self.value.clone(),
+        }
+    }
+}
+
+/// Flattens `nodes` into items keeping their order.
+fn collect_items<T>(nodes: Vec<TreePickerNode<T>>, path: &mut Vec<usize>) -> Vec<PickerItem<T>> {
+    let mut items = Vec::with_capacity(nodes.len());
+    for (index, node) in nodes.into_iter().enumerate() {
+        path.push(index);
+        items.push(PickerItem {
+            path: path.clone(),
+            value: Arc::new(node.value),
+        });
+        items.extend(collect_items(node.children, path));
+        path.pop();
+    }
+    items
+}
+
+type FormatFn<T> = Box<dyn for<'a> Fn(&'a T, &Theme) -> Spans<'a>>;
+
+impl<T: Send + Sync + 'static> TreeItem for PickerItem<T> {
+    type Params = FormatFn<T>;
+
+    fn text(&self, cx: &mut Context, selected: bool, format: &mut FormatFn<T>) -> Spans {
+        let mut spans = format(&*self.value, &cx.editor.theme);
+        if selected {
+            patch_selected(cx, &mut spans.0);
+        }
+        spans
+    }
+
+    fn is_child(&self, other: &Self) -> bool {
+        self.path.len() == other.path.len() + 1 && self.path.starts_with(&other.path)
+    }
+
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.path.cmp(&other.path)
+    }
+
+    fn node_id(&self) -> Option<u64> {
+        let mut hasher = DefaultHasher::new();
+        self.path.hash(&mut hasher);
+        Some(hasher.finish())
+    }
+}
+
+/// Picks a value from a tree, like [`super::Picker`] does from a list. Values are shown with
+/// the format function and the ones with children can be folded and unfolded. Enter calls the
+/// select function with the selected value and closes the picker, or opens it in a split with
+/// Ctrl-s and Ctrl-v.
+pub struct TreePicker<T: Send + Sync + 'static> {
+    tree: Tree<PickerItem<T>>,
+    title: Option<String>,
+    format_fn: FormatFn<T>,
+    select_fn: Box<dyn Fn(&mut Context, &T, Action)>,
+}
+
+impl<T: Send + Sync + 'static> TreePicker<T> {
+    pub fn new<F>(
+        nodes: Vec<TreePickerNode<T>>,
+        format_fn: F,
+        select_fn: impl Fn(&mut Context, &T, Action) + 'static,
+    ) -> Self
+    where
+        F: for<'a> Fn(&'a T, &Theme) -> Spans<'a> + 'static,
+    {
+        Self {
+            tree: Tree::build_tree(collect_items(nodes, &mut Vec::new())),
+            title: None,
+            format_fn: Box::new(format_fn),
+            select_fn: Box::new(select_fn),
+        }
+    }
+
+    pub fn with_title(mut self, title: impl Into<String>) -> Self {
+        self.title = Some(title.into());
+        self
+    }
+
+    /// Replaces the values, keeping the folds and the selection of the nodes at the same
+    /// positions.
+    pub fn replace_nodes(&mut self, nodes: Vec<TreePickerNode<T>>) {
+        self.tree
+            .replace_with_new_items(collect_items(nodes, &mut Vec::new()));
+    }
+
+    /// Selects the last value matching `f`, returning whether there was one.
+    pub fn select(&mut self, f: impl Fn(&T) -> bool) -> bool {
+        self.tree.select_last(|item| f(&item.value))
+    }
+
+    pub fn selection(&self) -> Option<&T> {
+        if self.tree.is_empty() {
+            return None;
+        }
+        Some(&self.tree.current_item().value)
+    }
+}
+
+impl<T: Send + Sync + 'static> Component for TreePicker<T> {
+    fn handle_event(&mut self, event: &Event, cx: &mut Context) -> EventResult {
+        let key_event = match event {
+            Event::Key(event) => *event,
+            Event::Mouse(event) => match self.tree.handle_mouse(event, cx, &mut self.format_fn) {
+                MouseAction::Activate => key!(Enter),
+                MouseAction::Handled => return EventResult::Consumed(None),
+                MouseAction::Ignored => return EventResult::Ignored(None),
+            },
+            Event::Resize(..) => return EventResult::Consumed(None),
+            _ => return EventResult::Ignored(None),
+        };
+
+        let close_fn = EventResult::Consumed(Some(Box::new(|compositor: &mut Compositor, _| {
+            compositor.pop();
+        })));
+
+        let action = match key_event.into() {
+            key!(Esc) | ctrl!('c') => return close_fn,
+            key!(Enter) => Action::Replace,
+            ctrl!('s') => Action::HorizontalSplit,
+            ctrl!('v') => Action::VerticalSplit,
+            _ if self.tree.is_empty() => return EventResult::Consumed(None),
+            _ => {
+                self.tree
+                    .handle_event(Event::Key(key_event), cx, &mut self.format_fn);
+                return EventResult::Consumed(None);
+            }
+        };
+        if let Some(value) = self.selection() {
+            (self.select_fn)(cx, value, action);
+        }
+        close_fn
+    }
+
+    fn render(&mut self, area: Rect, surface: &mut Surface, cx: &mut Context) {
+        let background = cx.editor.theme.get("ui.background");
+        surface.clear_with(area, background);
+
+        let mut block = Block::default()
+            .borders(Borders::ALL)
+            .border_style(super::border_style(&cx.editor.theme, cx.focus));
+        if let Some(title) = &self.title {
+            block = block.title(format!(" {} ", title));
+        }
+        let inner = block.inner(area);
+        block.render(area, surface);
+
+        if self.tree.is_empty() {
+            let style = cx.editor.theme.get("comment");
+            surface.set_stringn(
+                inner.x,
+                inner.y,
+                "Nothing to pick",
+                inner.width as usize,
+                style,
+            );
+            return;
+        }
+        self.tree.render(inner, surface, cx, &mut self.format_fn);
+    }
+
+    fn cursor(&self, _area: Rect, _editor: &Editor) -> (Option<Position>, CursorKind) {
+        (None, CursorKind::Hidden)
+    }
+
+    fn is_panel(&self) -> bool {
+        true
+    }
+}
@@ -0,0 +1,264 @@
+use super::{
+    symbol_tree::{kind_label, peek_back, peek_location},
+    tree::patch_selected,
+    MouseAction, Tree, TreeItem, TreeKey, TreeOp,
+};
+use crate::{
+    compositor::{Component, Compositor, Context, EventResult},
+    ctrl, key,
+};
+use anyhow::Result;
+use futures_util::future::{BoxFuture, FutureExt};
+use helix_core::{path::get_relative_path, Position, Selection};
+use helix_lsp::{lsp, util::lsp_range_to_range, OffsetEncoding};
+use helix_view::{
+    align_view,
+    editor::Action,
+    graphics::{CursorKind, Rect},
+    input::Event,
+    tree_keymap::TreeAction,
+    Align, Editor,
+};
+use std::{cmp::Ordering, sync::Arc};
+use tui::{
+    buffer::Buffer as Surface,
+    text::{Span, Spans},
+    widgets::{Block, Borders, Widget},
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TypeDirection {
+    /// The types a type extends or implements.
+    Supertypes,
+    /// The types extending or implementing a type.
+    Subtypes,
+}
+
+#[derive(Debug, Clone)]
+struct TypeItem {
+    item: lsp::TypeHierarchyItem,
+    direction: TypeDirection,
+    language_server: Arc<helix_lsp::Client>,
+    loaded: bool,
+}
+
+impl TreeItem for TypeItem {
+    type Params = ();
+
+    fn text(&self, cx: &mut Context, selected: bool, _params: &mut ()) -> Spans {
+        let theme = &cx.editor.theme;
+        let (label, name, fallback) = kind_label(self.item.kind);
+        let kind_style = theme
+            .try_get(&format!("ui.tree.symbol.{}", name))
+            .unwrap_or_else(|| theme.get(fallback));
+        let path = match self.item.uri.to_file_path() {
+            Ok(path) => get_relative_path(&path).to_string_lossy().into_owned(),
+            Err(_) => self.item.uri.to_string(),
+        };
+        let mut spans = vec![
+            Span::styled(format!("{:<7} ", label), kind_style),
+            Span::styled(self.item.name.clone(), theme.get("ui.text")),
+        ];
+        if let Some(detail) = self
+            .item
+            .detail
+            .as_ref()
+            .filter(|detail| !detail.is_empty())
+        {
+            spans.push(Span::styled(format!(" {}", detail), theme.get("comment")));
+        }
+        spans.push(Span::styled(
+            format!(" {}:{}", path, self.item.selection_range.start.line + 1),
+            theme.get("comment"),
+        ));
+        if selected {
+            patch_selected(cx, &mut spans);
+        }
+        Spans::from(spans)
+    }
+
+    fn is_child(&self, _other: &Self) -> bool {
+        // children are only ever loaded for a single item
+        false
+    }
+
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.item.name.cmp(&other.item.name)
+    }
+
+    /// Requests the supertypes or subtypes of the item from the language server.
+    fn load_childs(&self) -> Option<BoxFuture<'static, Result<Vec<Self>>>> {
+        let parent = self.clone();
+        let future = match self.direction {
+            TypeDirection::Supertypes => self.language_server.supertypes(self.item.clone()).boxed(),
+            TypeDirection::Subtypes => self.language_server.subtypes(self.item.clone()).boxed(),
+        };
+        Some(
+            async move {
+                let items: Option<Vec<lsp::TypeHierarchyItem>> =
+                    serde_json::from_value(future.await?)?;
+                Ok(items
+                    .unwrap_or_default()
+                    .into_iter()
+                    .map(|item| TypeItem {
+                        item,
+                        direction: parent.direction,
+                        language_server: parent.language_server.clone(),
+                        loaded: false,
+                    })
+                    .collect())
+            }
+            .boxed(),
+        )
+    }
+}
+
+/// The supertypes or subtypes of the types under the cursor. Expanding a type requests its own
+/// supertypes or subtypes.
+pub struct TypeHierarchy {
+    tree: Tree<TypeItem>,
+    direction: TypeDirection,
+    offset_encoding: OffsetEncoding,
+}
+
+impl TypeHierarchy {
+    /// Returns `None` if there are no items.
+    pub fn new(
+        items: Vec<lsp::TypeHierarchyItem>,
+        direction: TypeDirection,
+        language_server: Arc<helix_lsp::Client>,
+    ) -> Option<Self> {
+        if items.is_empty() {
+            return None;
+        }
+        let offset_encoding = language_server.offset_encoding();
+        let items = items
+            .into_iter()
+            .map(|item| TypeItem {
+                item,
+                direction,
+                language_server: language_server.clone(),
+                loaded: false,
+            })
+            .collect();
+        let tree = Tree::build_tree(items).with_enter_fn(|item, _cx, _params| {
+            if item.loaded {
+                return TreeOp::Restore;
+            }
+            item.loaded = true;
+            TreeOp::LoadChildsAndInsert
+        });
+        Some(Self {
+            tree,
+            direction,
+            offset_encoding,
+        })
+    }
+
+    /// Shows the definition of the selected type while the tree stays open.
+    fn peek(&self, editor: &mut Editor) {
+        let item = &self.tree.current_item().item;
+        match item.uri.to_file_path() {
+            Ok(path) => peek_location(editor, &path, item.selection_range, self.offset_encoding),
+            Err(_) => editor.set_error(format!("unable to convert URI to filepath: {}", item.uri)),
+        }
+    }
+
+    /// Closes the tree and moves the cursor to the definition of the selected type.
+    fn jump(&self) -> EventResult {
+        let item = &self.tree.current_item().item;
+        let (uri, range) = (item.uri.clone(), item.selection_range);
+        let offset_encoding = self.offset_encoding;
+        EventResult::Consumed(Some(Box::new(move |compositor: &mut Compositor, cx| {
+            compositor.pop();
+            cx.editor.confirm_peeks();
+            let path = match uri.to_file_path() {
+                Ok(path) => path,
+                Err(_) => {
+                    cx.editor
+                        .set_error(format!("unable to convert URI to filepath: {}", uri));
+                    return;
+                }
+            };
+            let (view, doc) = current!(cx.editor);
+            view.jumps.push((doc.id(), doc.selection(view.id).clone()));
+            if let Err(e) = cx.editor.open(&path, Action::Replace) {
+                cx.editor
+                    .set_error(format!("Failed to open {}: {}", path.display(), e));
+                return;
+            }
+            let (view, doc) = current!(cx.editor);
+            if let Some(range) = lsp_range_to_range(doc.text(), range, offset_encoding) {
+                doc.set_selection(view.id, Selection::single(range.head, range.anchor));
+                align_view(doc, view, Align::Center);
+            }
+        })))
+    }
+}
+
+impl Component for TypeHierarchy {
+    fn handle_event(&mut self, event: &Event, cx: &mut Context) -> EventResult {
+        let (action, count) = match event {
+            Event::Key(key!(Esc) | key!('q') | ctrl!('c')) => {
+                return EventResult::Consumed(Some(Box::new(|compositor: &mut Compositor, _| {
+                    compositor.pop();
+                })))
+            }
+            Event::Key(key_event) => match self.tree.key_action(*key_event, cx, &mut ()) {
+                TreeKey::Action(action, count) => (action, count),
+                TreeKey::Consumed | TreeKey::Ignored => return EventResult::Consumed(None),
+            },
+            Event::Mouse(event) => match self.tree.handle_mouse(event, cx, &mut ()) {
+                MouseAction::Activate => (TreeAction::Activate, 0),
+                MouseAction::Handled => return EventResult::Consumed(None),
+                MouseAction::Ignored => return EventResult::Ignored(None),
+            },
+            Event::Resize(..) => return EventResult::Consumed(None),
+            _ => return EventResult::Ignored(None),
+        };
+
+        match action {
+            TreeAction::Activate => return self.jump(),
+            TreeAction::Peek => self.peek(cx.editor),
+            TreeAction::PeekBack => peek_back(cx.editor),
+            action => self.tree.run_action(action, count, cx, &mut ()),
+        }
+        EventResult::Consumed(None)
+    }
+
+    fn render(&mut self, area: Rect, surface: &mut Surface, cx: &mut Context) {
+        let background = cx.editor.theme.get("ui.background");
+        surface.clear_with(area, background);
+
+        let title = match self.direction {
+            TypeDirection::Supertypes => " Supertypes ",
+            TypeDirection::Subtypes => " Subtypes ",
+        };
+        let block = Block::default()
+            .title(title)
+            .borders(Borders::ALL)
+            .border_style(super::border_style(&cx.editor.theme, cx.focus));
+        let inner = block.inner(area);
+        block.render(area, surface);
+
+        let rows_area = inner.clip_bottom(1);
+        self.tree.render(rows_area, surface, cx, &mut ());
+
+        let footer_area = inner.clip_top(rows_area.height);
+        surface.set_stringn(
+            footer_area.x,
+            footer_area.y,
+            "[enter]jump [p]peek [bs]back [o]expand",
+            footer_area.width as usize,
+            cx.editor.theme.get("comment"),
+        );
+    }
+
+    fn cursor(&self, _area: Rect, _editor: &Editor) -> (Option<Position>, CursorKind) {
+        (None, CursorKind::Hidden)
+    }
+
+    fn is_panel(&self) -> bool {
+        true
+    }
+}
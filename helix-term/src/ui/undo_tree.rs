@@ -0,0 +1,232 @@
+use super::{
+    tree::{patch_selected, Elem},
+    MouseAction, Tree, TreeItem,
+};
+use crate::{
+    compositor::{Component, Compositor, Context, EventResult},
+    ctrl, key,
+};
+use helix_core::{history::RevisionInfo, Position};
+use helix_view::{
+    graphics::{CursorKind, Rect},
+    input::Event,
+    DocumentId, Editor, ViewId,
+};
+use std::{cmp::Ordering, time::Instant};
+use tui::{
+    buffer::Buffer as Surface,
+    text::{Span, Spans},
+    widgets::{Block, Borders, Widget},
+};
+
+#[derive(Debug, Clone)]
+struct RevisionItem {
+    info: RevisionInfo,
+    /// The revision this one is nested under: the revision a branch forked from.
+    branch_of: Option<usize>,
+}
+
+/// Formats the time since `timestamp` like `5m ago`.
+fn ago(timestamp: Instant) -> String {
+    let secs = timestamp.elapsed().as_secs();
+    match secs {
+        0..=59 => format!("{}s ago", secs),
+        60..=3599 => format!("{}m ago", secs / 60),
+        3600..=86399 => format!("{}h ago", secs / 3600),
+        _ => format!("{}d ago", secs / 86400),
+    }
+}
+
+impl TreeItem for RevisionItem {
+    /// The current revision of the document.
+    type Params = usize;
+
+    fn text(&self, cx: &mut Context, selected: bool, current: &mut usize) -> Spans {
+        let theme = &cx.editor.theme;
+        let info = &self.info;
+        let marker = if info.index == *current { "● " } else { "  " };
+        let mut spans = if info.index == 0 {
+            vec![
+                Span::styled(marker, theme.get("ui.text.focus")),
+                Span::styled("original", theme.get("ui.text")),
+            ]
+        } else {
+            vec![
+                Span::styled(marker, theme.get("ui.text.focus")),
+                Span::styled(format!("{:>4} ", info.index), theme.get("ui.linenr")),
+                Span::styled(format!("{:>7} ", ago(info.timestamp)), theme.get("comment")),
+                Span::styled(format!("+{}", info.inserted), theme.get("diff.plus")),
+                Span::raw(" "),
+                Span::styled(format!("-{} ", info.deleted), theme.get("diff.minus")),
+                Span::styled(info.summary.clone(), theme.get("ui.text")),
+            ]
+        };
+        if selected {
+            patch_selected(cx, &mut spans);
+        }
+        Spans::from(spans)
+    }
+
+    fn is_child(&self, other: &Self) -> bool {
+        self.branch_of == Some(other.info.index)
+    }
+
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.info.index.cmp(&other.info.index)
+    }
+}
+
+/// Lays out the history as a tree: a revision follows its parent when it is its first child
+/// and starts a branch nested under the parent otherwise.
+fn revision_elems(revisions: Vec<RevisionInfo>) -> Vec<Elem<RevisionItem>> {
+    let mut children = vec![Vec::new(); revisions.len()];
+    for info in &revisions {
+        if let Some(parent) = info.parent {
+            children[parent].push(info.index);
+        }
+    }
+
+    fn push_branch(
+        start: usize,
+        branch_of: Option<usize>,
+        level: usize,
+        revisions: &[RevisionInfo],
+        children: &[Vec<usize>],
+        elems: &mut Vec<Elem<RevisionItem>>,
+    ) {
+        let mut index = start;
+        loop {
+            let item = RevisionItem {
+                info: revisions[index].clone(),
+                branch_of,
+            };
+            elems.push(Elem::new(item, level));
+            for &branch in children[index].iter().skip(1) {
+                push_branch(branch, Some(index), level + 1, revisions, children, elems);
+            }
+            match children[index].first() {
+                Some(&next) => index = next,
+                None => break,
+            }
+        }
+    }
+
+    let mut elems = Vec::with_capacity(revisions.len());
+    push_branch(0, None, 0, &revisions, &children, &mut elems);
+    elems
+}
+
+/// The undo history of a document as a tree of its branches. Selecting a revision moves the
+/// document to it.
+pub struct UndoTree {
+    tree: Tree<RevisionItem>,
+    doc_id: DocumentId,
+    view_id: ViewId,
+    current: usize,
+}
+
+impl UndoTree {
+    /// Shows the history of the current document.
+    pub fn new(editor: &Editor) -> Self {
+        let (view, doc) = current_ref!(editor);
+        let history = doc.history.take();
+        let revisions = history.revisions();
+        let current = history.current_revision();
+        doc.history.set(history);
+
+        let elems = revision_elems(revisions);
+        let position = elems
+            .iter()
+            .position(|elem| elem.item().info.index == current)
+            .unwrap_or(0);
+        let mut tree = Tree::new(elems);
+        tree.move_down(position);
+        Self {
+            tree,
+            doc_id: doc.id(),
+            view_id: view.id,
+            current,
+        }
+    }
+
+    fn close() -> EventResult {
+        EventResult::Consumed(Some(Box::new(|compositor: &mut Compositor, _| {
+            compositor.pop();
+        })))
+    }
+
+    fn jump(&self, cx: &mut Context) -> EventResult {
+        let revision = self.tree.current_item().info.index;
+        if !cx.editor.tree.contains(self.view_id) {
+            cx.editor.set_error("The window of the history was closed");
+            return Self::close();
+        }
+        let view = cx.editor.tree.get_mut(self.view_id);
+        let doc = match cx.editor.documents.get_mut(&self.doc_id) {
+            Some(doc) => doc,
+            None => return Self::close(),
+        };
+        // pending changes would be lost when moving through the history
+        doc.append_changes_to_history(view);
+        doc.jump_to_revision(view, revision);
+        Self::close()
+    }
+}
+
+impl Component for UndoTree {
+    fn handle_event(&mut self, event: &Event, cx: &mut Context) -> EventResult {
+        let key_event = match event {
+            Event::Key(event) => *event,
+            Event::Mouse(event) => match self.tree.handle_mouse(event, cx, &mut self.current) {
+                MouseAction::Activate => key!(Enter),
+                MouseAction::Handled => return EventResult::Consumed(None),
+                MouseAction::Ignored => return EventResult::Ignored(None),
+            },
+            Event::Resize(..) => return EventResult::Consumed(None),
+            _ => return EventResult::Ignored(None),
+        };
+
+        match key_event.into() {
+            key!(Esc) | key!('q') | ctrl!('c') => return Self::close(),
+            key!(Enter) => return self.jump(cx),
+            key!('o') => self.tree.on_enter(cx, &mut self.current),
+            _ => {
+                self.tree
+                    .handle_event(Event::Key(key_event), cx, &mut self.current);
+            }
+        }
+        EventResult::Consumed(None)
+    }
+
+    fn render(&mut self, area: Rect, surface: &mut Surface, cx: &mut Context) {
+        let background = cx.editor.theme.get("ui.background");
+        surface.clear_with(area, background);
+
+        let block = Block::default()
+            .title(" Undo tree ")
+            .borders(Borders::ALL)
+            .border_style(super::border_style(&cx.editor.theme, cx.focus));
+        let inner = block.inner(area);
+        block.render(area, surface);
+
+        let tree_area = inner.clip_bottom(1);
+        self.tree.render(tree_area, surface, cx, &mut self.current);
+
+        let footer_area = inner.clip_top(tree_area.height);
+        surface.set_stringn(
+            footer_area.x,
+            footer_area.y,
+            "[enter]go to revision [o]fold",
+            footer_area.width as usize,
+            cx.editor.theme.get("comment"),
+        );
+    }
+
+    fn cursor(&self, _area: Rect, _editor: &Editor) -> (Option<Position>, CursorKind) {
+        (None, CursorKind::Hidden)
+    }
+
+    fn is_panel(&self) -> bool {
+        true
+    }
+}
@@ -0,0 +1,378 @@
+use super::{
+    memory,
+    overlay::overlayed,
+    tree::{patch_selected, selected_style},
+    MemoryView, MouseAction, Tree, TreeItem, TreeOp,
+};
+use crate::{
+    compositor::{Component, Compositor, Context, EventResult},
+    ctrl, key,
+};
+use futures_util::{future::BoxFuture, FutureExt};
+use helix_dap::{requests, ThreadId};
+use helix_view::{
+    graphics::Rect,
+    input::{Event, KeyEvent},
+};
+use std::{
+    cmp::Ordering,
+    sync::{Arc, Mutex},
+};
+use tui::{
+    buffer::Buffer as Surface,
+    text::{Span, Spans},
+    widgets::{Block, Borders, Widget},
+};
+
+#[derive(Debug, Clone)]
+enum VariableItem {
+    Scope {
+        index: usize,
+        name: String,
+        variables_reference: usize,
+        /// Whether the children were fetched, so expanding again restores them.
+        loaded: bool,
+    },
+    /// A variable of a scope, or a field of another variable.
+    Variable {
+        index: usize,
+        name: String,
+        value: String,
+        ty: Option<String>,
+        variables_reference: usize,
+        memory_reference: Option<String>,
+        /// Whether the children were fetched, so expanding again restores them.
+        loaded: bool,
+    },
+}
+
+impl VariableItem {
+    fn index(&self) -> usize {
+        match self {
+            Self::Scope { index, .. } | Self::Variable { index, .. } => *index,
+        }
+    }
+
+    fn variables_reference(&self) -> usize {
+        match self {
+            Self::Scope {
+                variables_reference,
+                ..
+            }
+            | Self::Variable {
+                variables_reference,
+                ..
+            } => *variables_reference,
+        }
+    }
+
+    fn memory_reference(&self) -> Option<&str> {
+        match self {
+            Self::Scope { .. } => None,
+            Self::Variable {
+                memory_reference, ..
+            } => memory_reference.as_deref(),
+        }
+    }
+
+    fn loaded_mut(&mut self) -> &mut bool {
+        match self {
+            Self::Scope { loaded, .. } | Self::Variable { loaded, .. } => loaded,
+        }
+    }
+}
+
+impl TreeItem for VariableItem {
+    type Params = ();
+
+    fn text(&self, cx: &mut Context, selected: bool, _params: &mut ()) -> Spans {
+        let theme = &cx.editor.theme;
+        let marker = if self.variables_reference() > 0 {
+            "▸ "
+        } else {
+            "  "
+        };
+        let (name, style) = match self {
+            Self::Scope { name, .. } => (name, theme.get("ui.linenr.selected")),
+            Self::Variable { name, .. } => (name, theme.get("variable")),
+        };
+
+        let mut spans = vec![
+            Span::styled(marker, theme.get("ui.text")),
+            Span::styled(name.clone(), style),
+        ];
+        if selected {
+            patch_selected(cx, &mut spans);
+        }
+        Spans::from(spans)
+    }
+
+    fn columns(&self, cx: &mut Context, selected: bool, _params: &mut ()) -> Vec<Spans> {
+        let (value, ty) = match self {
+            Self::Scope { .. } => return Vec::new(),
+            Self::Variable { value, ty, .. } => (value, ty),
+        };
+        let theme = &cx.editor.theme;
+        let mut type_style = theme.get("type");
+        let mut value_style = theme.get("ui.text");
+        if selected {
+            let patch = selected_style(cx);
+            type_style = type_style.patch(patch);
+            value_style = value_style.patch(patch);
+        }
+        vec![
+            Spans::from(Span::styled(ty.clone().unwrap_or_default(), type_style)),
+            Spans::from(Span::styled(value.clone(), value_style)),
+        ]
+    }
+
+    fn is_child(&self, _other: &Self) -> bool {
+        // children are only ever inserted when expanding a scope or variable
+        false
+    }
+
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.index().cmp(&other.index())
+    }
+}
+
+/// The debuggee state the tree shows: the stop count, the thread and the frame.
+type Source = (usize, Option<ThreadId>, Option<usize>);
+
+/// Shows the scopes of the active stack frame with their variables. Structured variables are
+/// expanded lazily, and the scopes are fetched again whenever the debuggee stops.
+pub struct VariablesTree {
+    tree: Tree<VariableItem>,
+    source: Option<Source>,
+    /// Scopes fetched in the background, until they replace the tree.
+    loaded: Arc<Mutex<Option<Result<Vec<VariableItem>, String>>>>,
+    loading: bool,
+    error: Option<String>,
+}
+
+impl VariablesTree {
+    pub fn new() -> Self {
+        Self {
+            tree: Tree::build_tree(Vec::new()).with_enter_fn(Self::expand),
+            source: None,
+            loaded: Arc::default(),
+            loading: false,
+            error: None,
+        }
+    }
+
+    /// Fetches the scopes of the active stack frame if the debuggee stopped since the last
+    /// refresh, or the thread or frame changed.
+    fn refresh(&mut self, cx: &mut Context) {
+        if let Some(items) = self.loaded.lock().unwrap().take() {
+            self.loading = false;
+            match items {
+                Ok(items) => {
+                    let selected = self.tree.selected();
+                    self.error = None;
+                    self.tree.replace_with_new_items(items);
+                    self.tree.move_down(selected);
+                }
+                Err(e) => self.error = Some(e),
+            }
+        }
+
+        let debugger = match cx.editor.debugger.as_ref() {
+            Some(debugger) => debugger,
+            None => return,
+        };
+        let source = (debugger.stops, debugger.thread_id, debugger.active_frame);
+        if self.source == Some(source) {
+            return;
+        }
+        self.source = Some(source);
+
+        let frame_id = match debugger.current_stack_frame() {
+            Some(frame) => frame.id,
+            None => {
+                self.tree.replace_with_new_items(Vec::new());
+                return;
+            }
+        };
+        let request = debugger.call::<requests::Scopes>(requests::ScopesArguments { frame_id });
+        let loaded = self.loaded.clone();
+        self.loading = true;
+        cx.jobs.spawn(async move {
+            let items = async {
+                let response: requests::ScopesResponse = serde_json::from_value(request.await?)?;
+                let items: Vec<_> = response
+                    .scopes
+                    .into_iter()
+                    .enumerate()
+                    .map(|(index, scope)| VariableItem::Scope {
+                        index,
+                        name: scope.name,
+                        variables_reference: scope.variables_reference,
+                        loaded: false,
+                    })
+                    .collect();
+                Ok::<_, helix_dap::Error>(items)
+            }
+            .await
+            .map_err(|e| format!("Failed to get scopes: {}", e));
+            *loaded.lock().unwrap() = Some(items);
+            Ok(())
+        });
+    }
+
+    fn expand(item: &mut VariableItem, cx: &mut Context, _params: &mut ()) -> TreeOp<VariableItem> {
+        let variables_reference = item.variables_reference();
+        if variables_reference == 0 {
+            return TreeOp::Noop;
+        }
+        if *item.loaded_mut() {
+            return TreeOp::Restore;
+        }
+        let debugger = match cx.editor.debugger.as_ref() {
+            Some(debugger) => debugger,
+            None => {
+                cx.editor.set_error("Debugger is not running");
+                return TreeOp::Noop;
+            }
+        };
+        let request = debugger.call::<requests::Variables>(requests::VariablesArguments {
+            variables_reference,
+            filter: None,
+            start: None,
+            count: None,
+            format: None,
+        });
+        let future: BoxFuture<'static, anyhow::Result<Vec<VariableItem>>> = async move {
+            let response: requests::VariablesResponse = serde_json::from_value(request.await?)?;
+            Ok(response
+                .variables
+                .into_iter()
+                .enumerate()
+                .map(|(index, variable)| VariableItem::Variable {
+                    index,
+                    name: variable.name,
+                    value: variable.value,
+                    ty: variable.ty,
+                    variables_reference: variable.variables_reference,
+                    memory_reference: variable.memory_reference,
+                    loaded: false,
+                })
+                .collect())
+        }
+        .boxed();
+        *item.loaded_mut() = true;
+        TreeOp::LoadAndInsert(future)
+    }
+
+    fn open_memory(&self, cx: &mut Context) -> EventResult {
+        let memory_reference = match self.tree.current_item().memory_reference() {
+            Some(memory_reference) => memory_reference.to_string(),
+            None => {
+                cx.editor.set_error("Variable does not refer to memory");
+                return EventResult::Consumed(None);
+            }
+        };
+        if !memory::can_read_memory(cx.editor) {
+            return EventResult::Consumed(None);
+        }
+        EventResult::Consumed(Some(Box::new(|compositor: &mut Compositor, _| {
+            compositor.push(Box::new(overlayed(MemoryView::new(memory_reference))));
+        })))
+    }
+
+    fn handle_key(&mut self, event: KeyEvent, cx: &mut Context) -> EventResult {
+        match event.into() {
+            key!(Esc) | key!('q') | ctrl!('c') => {
+                EventResult::Consumed(Some(Box::new(|compositor: &mut Compositor, _| {
+                    compositor.pop();
+                })))
+            }
+            key!('m') if !self.tree.is_empty() => self.open_memory(cx),
+            _ => {
+                self.tree.handle_event(Event::Key(event), cx, &mut ());
+                EventResult::Consumed(None)
+            }
+        }
+    }
+}
+
+impl Default for VariablesTree {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Component for VariablesTree {
+    fn handle_event(&mut self, event: &Event, cx: &mut Context) -> EventResult {
+        match event {
+            Event::Key(event) => self.handle_key(*event, cx),
+            Event::Mouse(event) => match self.tree.handle_mouse(event, cx, &mut ()) {
+                MouseAction::Activate => self.handle_key(key!(Enter), cx),
+                MouseAction::Handled => EventResult::Consumed(None),
+                MouseAction::Ignored => EventResult::Ignored(None),
+            },
+            Event::Resize(..) => EventResult::Consumed(None),
+            _ => EventResult::Ignored(None),
+        }
+    }
+
+    fn render(&mut self, area: Rect, surface: &mut Surface, cx: &mut Context) {
+        self.refresh(cx);
+
+        let background = cx.editor.theme.get("ui.background");
+        surface.clear_with(area, background);
+
+        let block = Block::default()
+            .title(" Variables ")
+            .borders(Borders::ALL)
+            .border_style(super::border_style(&cx.editor.theme, cx.focus));
+        let inner = block.inner(area);
+        block.render(area, surface);
+
+        let tree_area = inner.clip_bottom(1);
+        let stopped = cx
+            .editor
+            .debugger
+            .as_ref()
+            .and_then(|debugger| debugger.current_stack_frame())
+            .is_some();
+        let message = if !stopped {
+            Some("Not stopped")
+        } else if let Some(error) = &self.error {
+            Some(error.as_str())
+        } else if self.loading && self.tree.is_empty() {
+            Some("Loading…")
+        } else if self.tree.is_empty() {
+            Some("No scopes")
+        } else {
+            None
+        };
+        match message {
+            Some(message) => {
+                let style = cx.editor.theme.get("comment");
+                surface.set_stringn(
+                    tree_area.x,
+                    tree_area.y,
+                    message,
+                    tree_area.width as usize,
+                    style,
+                );
+            }
+            None => self.tree.render(tree_area, surface, cx, &mut ()),
+        }
+
+        let footer_area = inner.clip_top(tree_area.height);
+        let style = cx.editor.theme.get("comment");
+        surface.set_stringn(
+            footer_area.x,
+            footer_area.y,
+            "[enter]expand [m]emory",
+            footer_area.width as usize,
+            style,
+        );
+    }
+
+    fn is_panel(&self) -> bool {
+        true
+    }
+}
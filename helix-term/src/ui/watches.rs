@@ -0,0 +1,425 @@
+use super::{
+    memory, overlay::overlayed, tree::patch_selected, MemoryView, MouseAction, Prompt, Tree,
+    TreeItem, TreeOp,
+};
+use crate::{
+    compositor::{Component, Compositor, Context, EventResult},
+    ctrl, key, ui,
+};
+use helix_core::{unicode::width::UnicodeWidthStr, Position};
+use helix_lsp::block_on;
+use helix_view::{
+    editor::Watch,
+    graphics::{CursorKind, Rect},
+    handlers::dap::{evaluate_watches, save_watches},
+    input::{Event, KeyEvent},
+    Editor,
+};
+use std::cmp::Ordering;
+use tui::{
+    buffer::Buffer as Surface,
+    text::{Span, Spans},
+    widgets::{Block, Borders, Widget},
+};
+
+/// Width of the tree guides drawn per nesting level.
+const INDENT_WIDTH: usize = 2;
+
+#[derive(Debug, Clone)]
+enum WatchItem {
+    Watch {
+        index: usize,
+        watch: Watch,
+    },
+    /// A field of an evaluated watch, or of another field.
+    Variable {
+        index: usize,
+        depth: usize,
+        name: String,
+        value: String,
+        ty: Option<String>,
+        variables_reference: usize,
+        memory_reference: Option<String>,
+    },
+}
+
+impl WatchItem {
+    fn index(&self) -> usize {
+        match self {
+            Self::Watch { index, .. } | Self::Variable { index, .. } => *index,
+        }
+    }
+
+    fn variables_reference(&self) -> usize {
+        match self {
+            Self::Watch { watch, .. } => match &watch.result {
+                Some(Ok(value)) => value.variables_reference,
+                _ => 0,
+            },
+            Self::Variable {
+                variables_reference,
+                ..
+            } => *variables_reference,
+        }
+    }
+
+    fn memory_reference(&self) -> Option<&str> {
+        match self {
+            Self::Watch { watch, .. } => match &watch.result {
+                Some(Ok(value)) => value.memory_reference.as_deref(),
+                _ => None,
+            },
+            Self::Variable {
+                memory_reference, ..
+            } => memory_reference.as_deref(),
+        }
+    }
+}
+
+fn pad(text: &str, width: usize) -> String {
+    format!("{}{}", text, " ".repeat(width.saturating_sub(text.width())))
+}
+
+impl TreeItem for WatchItem {
+    /// Width of the expression column.
+    type Params = usize;
+
+    fn text(&self, cx: &mut Context, selected: bool, name_width: &mut usize) -> Spans {
+        let theme = &cx.editor.theme;
+        let text_style = theme.get("ui.text");
+        let marker = if self.variables_reference() > 0 {
+            "▸ "
+        } else {
+            "  "
+        };
+
+        let mut spans = vec![Span::styled(marker, text_style)];
+        match self {
+            Self::Watch { watch, .. } => {
+                spans.push(Span::styled(
+                    pad(&watch.expression, *name_width),
+                    theme.get("variable"),
+                ));
+                match &watch.result {
+                    Some(Ok(value)) => {
+                        spans.push(Span::styled(format!(" {}", value.value), text_style));
+                        if let Some(ty) = &value.ty {
+                            spans.push(Span::styled(format!("  {}", ty), theme.get("type")));
+                        }
+                    }
+                    Some(Err(message)) => {
+                        spans.push(Span::styled(format!(" {}", message), theme.get("error")));
+                    }
+                    None => spans.push(Span::styled(" <not evaluated>", theme.get("comment"))),
+                }
+            }
+            Self::Variable {
+                depth,
+                name,
+                value,
+                ty,
+                ..
+            } => {
+                // keep the value column aligned with the one of the watches
+                let width = name_width.saturating_sub(depth * INDENT_WIDTH);
+                spans.push(Span::styled(pad(name, width), theme.get("variable")));
+                spans.push(Span::styled(format!(" {}", value), text_style));
+                if let Some(ty) = ty {
+                    spans.push(Span::styled(format!("  {}", ty), theme.get("type")));
+                }
+            }
+        }
+
+        if selected {
+            patch_selected(cx, &mut spans);
+        }
+        Spans::from(spans)
+    }
+
+    fn is_child(&self, _other: &Self) -> bool {
+        // children are only ever inserted when expanding a value
+        false
+    }
+
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.index().cmp(&other.index())
+    }
+}
+
+enum WatchPrompt {
+    Add,
+    Edit(usize),
+}
+
+/// Lists the watch expressions of the current project together with the result of their last
+/// evaluation. Structured results can be expanded into their fields.
+pub struct WatchesTree {
+    tree: Tree<WatchItem>,
+    /// The watches as of the last refresh, used to pick up evaluations done on stop events.
+    watches: Vec<Watch>,
+    prompt: Option<(WatchPrompt, Prompt)>,
+}
+
+impl WatchesTree {
+    pub fn new(editor: &Editor) -> Self {
+        Self {
+            tree: Tree::build_tree(Self::items(&editor.watches)).with_enter_fn(Self::expand),
+            watches: editor.watches.clone(),
+            prompt: None,
+        }
+    }
+
+    fn items(watches: &[Watch]) -> Vec<WatchItem> {
+        watches
+            .iter()
+            .enumerate()
+            .map(|(index, watch)| WatchItem::Watch {
+                index,
+                watch: watch.clone(),
+            })
+            .collect()
+    }
+
+    /// Rebuilds the tree from the editor's watches, keeping the selected row.
+    fn refresh(&mut self, editor: &Editor) {
+        let selected = self.tree.selected();
+        self.watches = editor.watches.clone();
+        self.tree.replace_with_new_items(Self::items(&self.watches));
+        self.tree.move_down(selected);
+    }
+
+    fn current_watch(&self) -> Option<usize> {
+        if self.watches.is_empty() {
+            return None;
+        }
+        match self.tree.current_item() {
+            WatchItem::Watch { index, .. } => Some(*index),
+            WatchItem::Variable { .. } => None,
+        }
+    }
+
+    fn expand(item: &mut WatchItem, cx: &mut Context, _params: &mut usize) -> TreeOp<WatchItem> {
+        let variables_reference = item.variables_reference();
+        if variables_reference == 0 {
+            return TreeOp::Noop;
+        }
+        let depth = match item {
+            WatchItem::Watch { .. } => 1,
+            WatchItem::Variable { depth, .. } => *depth + 1,
+        };
+        let debugger = match cx.editor.debugger.as_mut() {
+            Some(debugger) => debugger,
+            None => {
+                cx.editor.set_error("Debugger is not running");
+                return TreeOp::Noop;
+            }
+        };
+        match block_on(debugger.variables(variables_reference)) {
+            Ok(variables) => TreeOp::InsertChild(
+                variables
+                    .into_iter()
+                    .enumerate()
+                    .map(|(index, variable)| WatchItem::Variable {
+                        index,
+                        depth,
+                        name: variable.name,
+                        value: variable.value,
+                        ty: variable.ty,
+                        variables_reference: variable.variables_reference,
+                        memory_reference: variable.memory_reference,
+                    })
+                    .collect(),
+            ),
+            Err(e) => {
+                cx.editor
+                    .set_error(format!("Failed to get variables: {}", e));
+                TreeOp::Noop
+            }
+        }
+    }
+
+    fn new_prompt(&mut self, cx: &mut Context, target: WatchPrompt) {
+        let mut prompt = Prompt::new("watch:".into(), None, ui::completers::none, |_, _, _| {});
+        if let WatchPrompt::Edit(index) = target {
+            prompt.insert_str(&cx.editor.watches[index].expression, cx.editor);
+        }
+        self.prompt = Some((target, prompt));
+    }
+
+    fn handle_prompt_event(&mut self, event: KeyEvent, cx: &mut Context) -> EventResult {
+        let (target, mut prompt) = match self.prompt.take() {
+            Some(prompt) => prompt,
+            None => return EventResult::Ignored(None),
+        };
+        match event.into() {
+            key!(Enter) => {
+                let expression = prompt.line().trim();
+                if !expression.is_empty() {
+                    let watch = Watch::new(expression.to_string());
+                    let index = match target {
+                        WatchPrompt::Add => {
+                            cx.editor.watches.push(watch);
+                            cx.editor.watches.len() - 1
+                        }
+                        WatchPrompt::Edit(index) => {
+                            cx.editor.watches[index] = watch;
+                            index
+                        }
+                    };
+                    // evaluate right away if the debuggee is stopped
+                    if let Some(debugger) = cx.editor.debugger.as_ref() {
+                        if debugger.current_stack_frame().is_some() {
+                            let watches = &mut cx.editor.watches[index..=index];
+                            block_on(evaluate_watches(debugger, watches));
+                        }
+                    }
+                    self.watches_changed(cx.editor);
+                }
+            }
+            key!(Esc) | ctrl!('c') => {}
+            _ => {
+                prompt.handle_event(&Event::Key(event), cx);
+                self.prompt = Some((target, prompt));
+            }
+        }
+        EventResult::Consumed(None)
+    }
+
+    fn open_memory(&self, cx: &mut Context) -> EventResult {
+        let memory_reference = match self.tree.current_item().memory_reference() {
+            Some(memory_reference) => memory_reference.to_string(),
+            None => {
+                cx.editor.set_error("Value does not refer to memory");
+                return EventResult::Consumed(None);
+            }
+        };
+        if !memory::can_read_memory(cx.editor) {
+            return EventResult::Consumed(None);
+        }
+        EventResult::Consumed(Some(Box::new(|compositor: &mut Compositor, _| {
+            compositor.push(Box::new(overlayed(MemoryView::new(memory_reference))));
+        })))
+    }
+
+    fn watches_changed(&mut self, editor: &mut Editor) {
+        self.refresh(editor);
+        if let Err(e) = save_watches(&editor.watches) {
+            editor.set_error(format!("Failed to save watches: {}", e));
+        }
+    }
+}
+
+impl Component for WatchesTree {
+    fn handle_event(&mut self, event: &Event, cx: &mut Context) -> EventResult {
+        let key_event = match event {
+            Event::Key(event) => *event,
+            Event::Mouse(event) if self.prompt.is_none() => {
+                match self.tree.handle_mouse(event, cx, &mut 0) {
+                    MouseAction::Activate => key!(Enter),
+                    MouseAction::Handled => return EventResult::Consumed(None),
+                    MouseAction::Ignored => return EventResult::Ignored(None),
+                }
+            }
+            Event::Resize(..) => return EventResult::Consumed(None),
+            _ => return EventResult::Ignored(None),
+        };
+
+        if self.prompt.is_some() {
+            return self.handle_prompt_event(key_event, cx);
+        }
+
+        let close_fn = EventResult::Consumed(Some(Box::new(|compositor: &mut Compositor, _| {
+            compositor.pop();
+        })));
+
+        match key_event.into() {
+            key!(Esc) | key!('q') | ctrl!('c') => return close_fn,
+            key!('a') => self.new_prompt(cx, WatchPrompt::Add),
+            key!('e') => {
+                if let Some(index) = self.current_watch() {
+                    self.new_prompt(cx, WatchPrompt::Edit(index));
+                }
+            }
+            key!('d') => {
+                if let Some(index) = self.current_watch() {
+                    cx.editor.watches.remove(index);
+                    self.watches_changed(cx.editor);
+                }
+            }
+            _ if self.watches.is_empty() => {}
+            key!('m') => return self.open_memory(cx),
+            _ => {
+                let mut name_width = 0;
+                self.tree
+                    .handle_event(Event::Key(key_event), cx, &mut name_width);
+            }
+        }
+        EventResult::Consumed(None)
+    }
+
+    fn render(&mut self, area: Rect, surface: &mut Surface, cx: &mut Context) {
+        if self.watches != cx.editor.watches {
+            self.refresh(cx.editor);
+        }
+
+        let background = cx.editor.theme.get("ui.background");
+        surface.clear_with(area, background);
+
+        let block = Block::default()
+            .title(" Watches ")
+            .borders(Borders::ALL)
+            .border_style(super::border_style(&cx.editor.theme, cx.focus));
+        let inner = block.inner(area);
+        block.render(area, surface);
+
+        let tree_area = inner.clip_bottom(1);
+        if self.watches.is_empty() {
+            let style = cx.editor.theme.get("comment");
+            surface.set_stringn(
+                tree_area.x,
+                tree_area.y,
+                "No watch expressions",
+                tree_area.width as usize,
+                style,
+            );
+        } else {
+            let mut name_width = self
+                .watches
+                .iter()
+                .map(|watch| watch.expression.width())
+                .max()
+                .unwrap_or_default()
+                .min(tree_area.width as usize / 3);
+            self.tree.render(tree_area, surface, cx, &mut name_width);
+        }
+
+        let footer_area = inner.clip_top(tree_area.height);
+        match &self.prompt {
+            Some((_, prompt)) => prompt.render_prompt(footer_area, surface, cx),
+            None => {
+                let style = cx.editor.theme.get("comment");
+                surface.set_stringn(
+                    footer_area.x,
+                    footer_area.y,
+                    "[a]dd [e]dit [d]elete [m]emory",
+                    footer_area.width as usize,
+                    style,
+                );
+            }
+        }
+    }
+
+    fn cursor(&self, area: Rect, editor: &Editor) -> (Option<Position>, CursorKind) {
+        match &self.prompt {
+            Some((_, prompt)) => {
+                let inner = Block::default().borders(Borders::ALL).inner(area);
+                prompt.cursor(inner.clip_top(inner.height.saturating_sub(1)), editor)
+            }
+            None => (None, CursorKind::Hidden),
+        }
+    }
+
+    fn is_panel(&self) -> bool {
+        true
+    }
+}
@@ -0,0 +1,224 @@
+use super::{tree::patch_selected, MouseAction, Tree, TreeItem};
+use crate::{
+    commands::paste_values,
+    compositor::{Component, Compositor, Context, EventResult},
+    ctrl, key,
+};
+use helix_core::Position;
+use helix_view::{
+    graphics::{CursorKind, Margin, Rect},
+    input::Event,
+    yank_history::YankEntry,
+    Editor,
+};
+use std::cmp::Ordering;
+use tui::{
+    buffer::Buffer as Surface,
+    text::{Span, Spans},
+    widgets::{Block, Borders, Widget},
+};
+
+#[derive(Debug, Clone)]
+struct YankItem {
+    index: usize,
+    entry: YankEntry,
+}
+
+impl TreeItem for YankItem {
+    type Params = ();
+
+    fn text(&self, cx: &mut Context, selected: bool, _params: &mut ()) -> Spans {
+        let theme = &cx.editor.theme;
+        let marker = if self.entry.pinned { "* " } else { "  " };
+        let first = self.entry.values.first().map_or("", |value| value.as_str());
+        let mut spans = vec![
+            Span::styled(marker, theme.get("special")),
+            Span::styled(first.replace('\n', "⏎"), theme.get("ui.text")),
+        ];
+        if self.entry.values.len() > 1 {
+            spans.push(Span::styled(
+                format!(" (+{})", self.entry.values.len() - 1),
+                theme.get("comment"),
+            ));
+        }
+        if selected {
+            patch_selected(cx, &mut spans);
+        }
+        Spans::from(spans)
+    }
+
+    fn is_child(&self, _other: &Self) -> bool {
+        false
+    }
+
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.index.cmp(&other.index)
+    }
+}
+
+fn yank_items(editor: &Editor) -> Vec<YankItem> {
+    editor
+        .yank_history
+        .entries()
+        .iter()
+        .enumerate()
+        .map(|(index, entry)| YankItem {
+            index,
+            entry: entry.clone(),
+        })
+        .collect()
+}
+
+/// Recently yanked text and clipboard contents, newest first, next to the full text of the
+/// selected entry.
+pub struct YankHistoryView {
+    tree: Tree<YankItem>,
+}
+
+impl YankHistoryView {
+    /// Returns `None` if nothing was yanked yet.
+    pub fn new(editor: &Editor) -> Option<Self> {
+        let items = yank_items(editor);
+        if items.is_empty() {
+            return None;
+        }
+        Some(Self {
+            tree: Tree::build_tree(items),
+        })
+    }
+
+    fn close() -> EventResult {
+        EventResult::Consumed(Some(Box::new(|compositor: &mut Compositor, _| {
+            compositor.pop();
+        })))
+    }
+
+    /// Closes the history and pastes the selected entry after or before the selections.
+    fn paste(&self, before: bool) -> EventResult {
+        let values = self.tree.current_item().entry.values.clone();
+        EventResult::Consumed(Some(Box::new(move |compositor: &mut Compositor, cx| {
+            compositor.pop();
+            paste_values(cx.editor, &values, before);
+        })))
+    }
+
+    fn yank(&self, editor: &mut Editor) -> EventResult {
+        let values = self.tree.current_item().entry.values.clone();
+        editor.set_status(format!(
+            "yanked {} selection(s) to register \"",
+            values.len()
+        ));
+        editor.registers.write('"', values);
+        Self::close()
+    }
+
+    /// Rebuilds the rows after the history changed, keeping the selection near `selected`.
+    fn refresh(&mut self, editor: &mut Editor, selected: usize) -> EventResult {
+        editor.save_yank_history();
+        let items = yank_items(editor);
+        if items.is_empty() {
+            return Self::close();
+        }
+        let selected = selected.min(items.len() - 1);
+        self.tree.replace_with_new_items(items);
+        self.tree.move_down(selected);
+        EventResult::Consumed(None)
+    }
+
+    fn toggle_pin(&mut self, editor: &mut Editor) -> EventResult {
+        let index = self.tree.current_item().index;
+        editor.yank_history.toggle_pin(index);
+        self.refresh(editor, index)
+    }
+
+    fn remove(&mut self, editor: &mut Editor) -> EventResult {
+        let index = self.tree.current_item().index;
+        editor.yank_history.remove(index);
+        self.refresh(editor, index)
+    }
+}
+
+impl Component for YankHistoryView {
+    fn handle_event(&mut self, event: &Event, cx: &mut Context) -> EventResult {
+        let key_event = match event {
+            Event::Key(event) => *event,
+            Event::Mouse(event) => match self.tree.handle_mouse(event, cx, &mut ()) {
+                MouseAction::Activate => key!(Enter),
+                MouseAction::Handled => return EventResult::Consumed(None),
+                MouseAction::Ignored => return EventResult::Ignored(None),
+            },
+            Event::Resize(..) => return EventResult::Consumed(None),
+            _ => return EventResult::Ignored(None),
+        };
+
+        match key_event.into() {
+            key!(Esc) | key!('q') | ctrl!('c') => return Self::close(),
+            key!(Enter) | key!('p') => return self.paste(false),
+            key!('P') => return self.paste(true),
+            key!('y') => return self.yank(cx.editor),
+            key!('t') => return self.toggle_pin(cx.editor),
+            key!('d') => return self.remove(cx.editor),
+            _ => {
+                self.tree.handle_event(Event::Key(key_event), cx, &mut ());
+            }
+        }
+        EventResult::Consumed(None)
+    }
+
+    fn render(&mut self, area: Rect, surface: &mut Surface, cx: &mut Context) {
+        let background = cx.editor.theme.get("ui.background");
+        surface.clear_with(area, background);
+
+        let list_area = area.with_width(area.width / 2);
+        let title = format!(
+            " Yank history ({}) ",
+            cx.editor.yank_history.entries().len()
+        );
+        let block = Block::default()
+            .title(title)
+            .borders(Borders::ALL)
+            .border_style(super::border_style(&cx.editor.theme, cx.focus));
+        let inner = block.inner(list_area);
+        block.render(list_area, surface);
+
+        let tree_area = inner.clip_bottom(1);
+        self.tree.render(tree_area, surface, cx, &mut ());
+
+        let footer_area = inner.clip_top(tree_area.height);
+        surface.set_stringn(
+            footer_area.x,
+            footer_area.y,
+            "[enter]paste [P]aste before [y]ank [t]oggle pin [d]elete",
+            footer_area.width as usize,
+            cx.editor.theme.get("comment"),
+        );
+
+        let preview_area = area.clip_left(list_area.width);
+        let block = Block::default().borders(Borders::ALL);
+        let inner = block.inner(preview_area).inner(&Margin::horizontal(1));
+        block.render(preview_area, surface);
+
+        // selections are separated by a rule
+        let text_style = cx.editor.theme.get("ui.text");
+        let rule_style = cx.editor.theme.get("comment");
+        let rule = "─".repeat(inner.width as usize);
+        let values = &self.tree.current_item().entry.values;
+        let lines = values.iter().enumerate().flat_map(|(i, value)| {
+            let separator = (i > 0).then(|| (rule.as_str(), rule_style));
+            separator
+                .into_iter()
+                .chain(value.lines().map(move |line| (line, text_style)))
+        });
+        for ((line, style), y) in lines.zip(inner.top()..inner.bottom()) {
+            surface.set_stringn(inner.x, y, line, inner.width as usize, style);
+        }
+    }
+
+    fn cursor(&self, _area: Rect, _editor: &Editor) -> (Option<Position>, CursorKind) {
+        (None, CursorKind::Hidden)
+    }
+
+    fn is_panel(&self) -> bool {
+        true
+    }
+}
@@ -545,3 +545,58 @@ async fn append_inside_nested_pair_multi() -> anyhow::Result<()> {
 
     Ok(())
 }
+
+#[tokio::test(flavor = "multi_thread")]
+async fn insert_open_in_comment() -> anyhow::Result<()> {
+    test_with_config(
+        Args {
+            files: vec![(PathBuf::from("foo.rs"), Position::default())],
+            ..Default::default()
+        },
+        Config::default(),
+        helpers::test_syntax_conf(None),
+        (
+            helpers::platform_line("// see#[ |]#below\n").as_ref(),
+            "i(",
+            helpers::platform_line("// see(#[ |]#below\n").as_ref(),
+        ),
+    )
+    .await?;
+
+    test_with_config(
+        Args {
+            files: vec![(PathBuf::from("foo.rs"), Position::default())],
+            ..Default::default()
+        },
+        Config::default(),
+        helpers::test_syntax_conf(None),
+        (
+            helpers::platform_line("// see#[\n|]#").as_ref(),
+            "i(",
+            helpers::platform_line("// see(#[\n|]#").as_ref(),
+        ),
+    )
+    .await?;
+
+    Ok(())
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn insert_open_before_unmatched_close() -> anyhow::Result<()> {
+    test_with_config(
+        Args {
+            files: vec![(PathBuf::from("foo.rs"), Position::default())],
+            ..Default::default()
+        },
+        Config::default(),
+        helpers::test_syntax_conf(None),
+        (
+            helpers::platform_line("fn main() { foo#[)|]#; }\n").as_ref(),
+            "i(",
+            helpers::platform_line("fn main() { foo(#[)|]#; }\n").as_ref(),
+        ),
+    )
+    .await?;
+
+    Ok(())
+}
@@ -311,3 +311,92 @@ async fn test_undo_redo() -> anyhow::Result<()> {
 
     Ok(())
 }
+
+#[tokio::test(flavor = "multi_thread")]
+async fn test_swap_parameters() -> anyhow::Result<()> {
+    test_with_config(
+        Args {
+            files: vec![(PathBuf::from("foo.rs"), Position::default())],
+            ..Default::default()
+        },
+        Config::default(),
+        helpers::test_syntax_conf(None),
+        (
+            helpers::platform_line("fn main() { foo(#[a|]#, bc, d); }\n").as_ref(),
+            "]A",
+            helpers::platform_line("fn main() { foo(bc, #[a|]#, d); }\n").as_ref(),
+        ),
+    )
+    .await?;
+
+    test_with_config(
+        Args {
+            files: vec![(PathBuf::from("foo.rs"), Position::default())],
+            ..Default::default()
+        },
+        Config::default(),
+        helpers::test_syntax_conf(None),
+        (
+            helpers::platform_line("fn main() { foo(a, bc, #[d|]#); }\n").as_ref(),
+            "[A[A",
+            helpers::platform_line("fn main() { foo(#[d|]#, a, bc); }\n").as_ref(),
+        ),
+    )
+    .await?;
+
+    // the cursor of a swap sharing an object with another one stays
+    test_with_config(
+        Args {
+            files: vec![(PathBuf::from("foo.rs"), Position::default())],
+            ..Default::default()
+        },
+        Config::default(),
+        helpers::test_syntax_conf(None),
+        (
+            helpers::platform_line("fn main() { foo(a, #[b|]#, #(c|)#); }\n").as_ref(),
+            "[A",
+            helpers::platform_line("fn main() { foo(#[b|]#, a, #(c|)#); }\n").as_ref(),
+        ),
+    )
+    .await?;
+
+    Ok(())
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn test_toggle_comments_in_injected_comment() -> anyhow::Result<()> {
+    // the `comment` language injected into comments has no tokens, so the tokens of the
+    // document language are used
+    test_with_config(
+        Args {
+            files: vec![(PathBuf::from("foo.py"), Position::default())],
+            ..Default::default()
+        },
+        Config::default(),
+        helpers::test_syntax_conf(None),
+        (
+            helpers::platform_line("# he#[l|]#lo\n").as_ref(),
+            "<C-c>",
+            helpers::platform_line("he#[l|]#lo\n").as_ref(),
+        ),
+    )
+    .await?;
+
+    // as is the block comment of markdown inside of the injected `markdown.inline`
+    test_with_config(
+        Args {
+            files: vec![(PathBuf::from("foo.md"), Position::default())],
+            ..Default::default()
+        },
+        Config::default(),
+        helpers::test_syntax_conf(None),
+        (
+            helpers::platform_line("he#[l|]#lo\n").as_ref(),
+            "<C-c>",
+            helpers::platform_line("<!-- he#[l|]#lo -->\n").as_ref(),
+        ),
+    )
+    .await?;
+
+    Ok(())
+}
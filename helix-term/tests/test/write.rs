@@ -285,3 +285,36 @@ async fn test_write_fail_new_path() -> anyhow::Result<()> {
 
     Ok(())
 }
+
+#[tokio::test(flavor = "multi_thread")]
+async fn test_write_binary_in_hex_view() -> anyhow::Result<()> {
+    let file = helpers::temp_file_with_contents("\0abc")?;
+
+    test_key_sequences(
+        &mut AppBuilder::new().with_file(file.path(), None).build()?,
+        vec![
+            (
+                // binary files open in the hex view, writing the edited bytes reloads the text
+                Some("0fw<esc>"),
+                Some(&|app| {
+                    assert!(!app.editor.is_err());
+                    assert_eq!("\u{f}abc", doc!(app.editor).text().to_string());
+                }),
+            ),
+            (
+                // the text of the binary file can't be written back
+                Some("ix<esc>:w<ret>"),
+                Some(&|app| {
+                    assert!(app.editor.is_err());
+                    assert!(doc!(app.editor).is_modified());
+                }),
+            ),
+        ],
+        false,
+    )
+    .await?;
+
+    assert_eq!(b"\x0fabc", std::fs::read(file.path())?.as_slice());
+
+    Ok(())
+}
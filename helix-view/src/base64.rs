@@ -86,12 +86,81 @@ pub fn encode(input: &[u8]) -> String {
     String::from_utf8(output).expect("Invalid UTF8")
 }
 
+fn decode_byte(byte: u8) -> Option<u32> {
+    ENCODE_TABLE
+        .iter()
+        .position(|&b| b == byte)
+        .map(|index| index as u32)
+}
+
+/// Decodes padded base64, returning `None` if the input is malformed.
+pub fn decode(input: &str) -> Option<Vec<u8>> {
+    let input = input.as_bytes();
+    if input.len() % 4 != 0 {
+        return None;
+    }
+
+    let chunks = input.len() / 4;
+    let mut output = Vec::with_capacity(chunks * 3);
+    for (index, chunk) in input.chunks(4).enumerate() {
+        let padding = chunk.iter().rev().take_while(|&&b| b == PAD_BYTE).count();
+        // padding is only allowed at the very end
+        if padding > 2 || (padding > 0 && index + 1 != chunks) {
+            return None;
+        }
+
+        // collect the 6-bit groups into the low 24 bits
+        let mut chunk_int = 0_u32;
+        for &byte in &chunk[..4 - padding] {
+            chunk_int = chunk_int.shl(6_u8) | decode_byte(byte)?;
+        }
+        chunk_int = chunk_int.shl(6 * padding as u32);
+
+        let bytes = [
+            chunk_int.shr(16_u8) as u8,
+            chunk_int.shr(8_u8) as u8,
+            chunk_int as u8,
+        ];
+        output.extend_from_slice(&bytes[..3 - padding]);
+    }
+    Some(output)
+}
+
 #[cfg(test)]
 mod tests {
     fn compare_encode(expected: &str, target: &[u8]) {
         assert_eq!(expected, super::encode(target));
     }
 
+    fn compare_decode(expected: &[u8], target: &str) {
+        assert_eq!(Some(expected.to_vec()), super::decode(target));
+    }
+
+    #[test]
+    fn decode_rfc4648() {
+        compare_decode(b"", "");
+        compare_decode(b"f", "Zg==");
+        compare_decode(b"fo", "Zm8=");
+        compare_decode(b"foo", "Zm9v");
+        compare_decode(b"foob", "Zm9vYg==");
+        compare_decode(b"fooba", "Zm9vYmE=");
+        compare_decode(b"foobar", "Zm9vYmFy");
+    }
+
+    #[test]
+    fn decode_roundtrip_all_bytes() {
+        let bytes: Vec<u8> = (0..=255).collect();
+        compare_decode(&bytes, &super::encode(&bytes));
+    }
+
+    #[test]
+    fn decode_malformed() {
+        assert_eq!(None, super::decode("Zg="));
+        assert_eq!(None, super::decode("Z==="));
+        assert_eq!(None, super::decode("Zg==Zm8="));
+        assert_eq!(None, super::decode("Zm9*"));
+    }
+
     #[test]
     fn encode_rfc4648_0() {
         compare_encode("", b"");
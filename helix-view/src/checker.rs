@@ -0,0 +1,318 @@
+//! Diagnostics reported by running compilers or linters, configured with the `checker` key of
+//! a language. They are stored next to the diagnostics of language servers, without a language
+//! server id and tagged with the checker's command as source.
+
+use crate::{Document, Editor};
+use anyhow::{Context, Result};
+use helix_core::{
+    diagnostic::{Diagnostic, NumberOrString, Range, Severity},
+    path::get_normalized_path,
+    regex::{Captures, Regex},
+    syntax::{CheckerConfiguration, CheckerFormat},
+};
+use helix_lsp::{lsp, util::lsp_pos_to_pos, OffsetEncoding};
+use serde::Deserialize;
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+
+/// Columns reported by checkers are counted in bytes.
+pub const OFFSET_ENCODING: OffsetEncoding = OffsetEncoding::Utf8;
+
+/// A diagnostic along with the file it was reported for.
+pub type FileDiagnostic = (PathBuf, lsp::Diagnostic);
+
+/// Runs the checker in `root` and parses its output.
+pub async fn run(checker: &CheckerConfiguration, root: &Path) -> Result<Vec<FileDiagnostic>> {
+    let output = tokio::process::Command::new(&checker.command)
+        .args(&checker.args)
+        .current_dir(root)
+        .stdin(std::process::Stdio::null())
+        .output()
+        .await
+        .with_context(|| format!("failed to run {}", checker.command))?;
+
+    // the exit status is ignored as checkers usually fail if they report any errors
+    let mut text = String::from_utf8_lossy(&output.stdout).into_owned();
+    text.push_str(&String::from_utf8_lossy(&output.stderr));
+    parse(checker, &text, root)
+}
+
+/// Parses the output of a checker, relative paths are resolved against `root`.
+pub fn parse(
+    checker: &CheckerConfiguration,
+    output: &str,
+    root: &Path,
+) -> Result<Vec<FileDiagnostic>> {
+    let source = &checker.command;
+    match checker.format {
+        CheckerFormat::CargoJson => Ok(parse_cargo_json(source, output, root)),
+        CheckerFormat::Regex => {
+            let pattern = checker
+                .pattern
+                .as_deref()
+                .context("the regex checker format requires a pattern")?;
+            let regex = Regex::new(pattern).context("invalid checker pattern")?;
+            Ok(parse_regex(source, &regex, output, root))
+        }
+    }
+}
+
+fn parse_severity(severity: &str) -> Option<lsp::DiagnosticSeverity> {
+    let severity = severity.to_lowercase();
+    match severity.as_str() {
+        "error" | "fatal" | "e" => Some(lsp::DiagnosticSeverity::ERROR),
+        "warning" | "warn" | "w" => Some(lsp::DiagnosticSeverity::WARNING),
+        "info" | "information" | "note" | "i" => Some(lsp::DiagnosticSeverity::INFORMATION),
+        "hint" | "help" | "h" => Some(lsp::DiagnosticSeverity::HINT),
+        // e.g. "error: internal compiler error"
+        _ if severity.starts_with("error") => Some(lsp::DiagnosticSeverity::ERROR),
+        _ => None,
+    }
+}
+
+#[derive(Deserialize)]
+struct RustcDiagnostic {
+    message: String,
+    level: String,
+    code: Option<RustcCode>,
+    #[serde(default)]
+    spans: Vec<RustcSpan>,
+    #[serde(default)]
+    children: Vec<RustcDiagnostic>,
+}
+
+#[derive(Deserialize)]
+struct RustcCode {
+    code: String,
+}
+
+#[derive(Deserialize)]
+struct RustcSpan {
+    file_name: PathBuf,
+    line_start: u32,
+    line_end: u32,
+    column_start: u32,
+    column_end: u32,
+    is_primary: bool,
+}
+
+fn parse_cargo_json(source: &str, output: &str, root: &Path) -> Vec<FileDiagnostic> {
+    let mut diagnostics = Vec::new();
+    for line in output.lines() {
+        let message = match serde_json::from_str::<serde_json::Value>(line) {
+            Ok(message) => message,
+            // cargo prints its progress as plain text
+            Err(_) => continue,
+        };
+        // cargo wraps the messages of rustc
+        let message = match message.get("reason") {
+            Some(reason) if reason == "compiler-message" => message["message"].clone(),
+            Some(_) => continue,
+            None => message,
+        };
+        let diagnostic: RustcDiagnostic = match serde_json::from_value(message) {
+            Ok(diagnostic) => diagnostic,
+            Err(_) => continue,
+        };
+        // diagnostics without location like "aborting due to previous error" are skipped
+        let span = match diagnostic.spans.iter().find(|span| span.is_primary) {
+            Some(span) => span,
+            None => continue,
+        };
+
+        let mut message = diagnostic.message.clone();
+        for child in &diagnostic.children {
+            message.push_str(&format!("\n{}: {}", child.level, child.message));
+        }
+
+        let start = lsp::Position::new(span.line_start - 1, span.column_start - 1);
+        let end = lsp::Position::new(span.line_end - 1, span.column_end - 1);
+        diagnostics.push((
+            root.join(&span.file_name),
+            lsp::Diagnostic {
+                range: lsp::Range::new(start, end),
+                severity: parse_severity(&diagnostic.level),
+                code: diagnostic
+                    .code
+                    .as_ref()
+                    .map(|code| lsp::NumberOrString::String(code.code.clone())),
+                source: Some(source.to_string()),
+                message,
+                ..Default::default()
+            },
+        ));
+    }
+    diagnostics
+}
+
+fn parse_regex(source: &str, regex: &Regex, output: &str, root: &Path) -> Vec<FileDiagnostic> {
+    fn number(captures: &Captures, name: &str) -> Option<u32> {
+        captures.name(name)?.as_str().parse().ok()
+    }
+
+    output
+        .lines()
+        .filter_map(|line| {
+            let captures = regex.captures(line)?;
+            let file = captures.name("file")?.as_str();
+            let line = number(&captures, "line")?.saturating_sub(1);
+            let column = number(&captures, "column").unwrap_or(1).saturating_sub(1);
+            let position = lsp::Position::new(line, column);
+            let diagnostic = lsp::Diagnostic {
+                range: lsp::Range::new(position, position),
+                severity: captures
+                    .name("severity")
+                    .and_then(|severity| parse_severity(severity.as_str())),
+                source: Some(source.to_string()),
+                message: captures.name("message")?.as_str().trim().to_string(),
+                ..Default::default()
+            };
+            Some((root.join(file), diagnostic))
+        })
+        .collect()
+}
+
+/// Positions the diagnostics reported by checkers for `doc` within its text. Only those of the
+/// checker `source` are returned if given.
+pub fn document_diagnostics(
+    diagnostics: &BTreeMap<lsp::Url, Vec<(lsp::Diagnostic, Option<usize>)>>,
+    doc: &Document,
+    source: Option<&str>,
+) -> Vec<Diagnostic> {
+    let diagnostics = match doc.url().and_then(|url| diagnostics.get(&url)) {
+        Some(diagnostics) => diagnostics,
+        None => return Vec::new(),
+    };
+    let text = doc.text();
+    let min_severity = doc
+        .language_config()
+        .map(|config| config.diagnostic_severity)
+        .unwrap_or_default();
+
+    diagnostics
+        .iter()
+        .filter(|(diagnostic, server_id)| {
+            server_id.is_none() && (source.is_none() || diagnostic.source.as_deref() == source)
+        })
+        .filter_map(|(diagnostic, _)| {
+            let start = lsp_pos_to_pos(text, diagnostic.range.start, OFFSET_ENCODING)?;
+            let end = lsp_pos_to_pos(text, diagnostic.range.end, OFFSET_ENCODING)?;
+            let severity = diagnostic.severity.and_then(|severity| match severity {
+                lsp::DiagnosticSeverity::ERROR => Some(Severity::Error),
+                lsp::DiagnosticSeverity::WARNING => Some(Severity::Warning),
+                lsp::DiagnosticSeverity::INFORMATION => Some(Severity::Info),
+                lsp::DiagnosticSeverity::HINT => Some(Severity::Hint),
+                _ => None,
+            });
+            if severity.map_or(false, |severity| severity < min_severity) {
+                return None;
+            }
+            let code = diagnostic.code.clone().map(|code| match code {
+                lsp::NumberOrString::Number(code) => NumberOrString::Number(code),
+                lsp::NumberOrString::String(code) => NumberOrString::String(code),
+            });
+
+            Some(Diagnostic {
+                range: Range { start, end },
+                line: diagnostic.range.start.line as usize,
+                message: diagnostic.message.clone(),
+                severity,
+                code,
+                tags: Vec::new(),
+                source: diagnostic.source.clone(),
+                data: None,
+                language_server_id: None,
+            })
+        })
+        .collect()
+}
+
+impl Editor {
+    /// Replaces the diagnostics previously reported by the checker `source`.
+    pub fn set_checker_diagnostics(&mut self, source: &str, diagnostics: Vec<FileDiagnostic>) {
+        for diagnostics in self.diagnostics.values_mut() {
+            diagnostics.retain(|(diagnostic, server_id)| {
+                server_id.is_some() || diagnostic.source.as_deref() != Some(source)
+            });
+        }
+        for (path, diagnostic) in diagnostics {
+            if let Ok(url) = lsp::Url::from_file_path(get_normalized_path(&path)) {
+                self.diagnostics
+                    .entry(url)
+                    .or_default()
+                    .push((diagnostic, None));
+            }
+        }
+        // same order as the diagnostics of language servers
+        for diagnostics in self.diagnostics.values_mut() {
+            diagnostics.sort_unstable_by_key(|(d, _)| (d.severity, d.range.start));
+        }
+
+        for doc in self.documents.values_mut() {
+            let diagnostics = document_diagnostics(&self.diagnostics, doc, Some(source));
+            doc.replace_checker_diagnostics(diagnostics, source);
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn parse_cargo_messages() {
+        let output = r#"   Compiling foo v0.1.0 (/project)
+{"reason":"compiler-artifact","package_id":"foo 0.1.0"}
+{"reason":"compiler-message","message":{"message":"unused variable: `x`","code":{"code":"unused_variables","explanation":null},"level":"warning","spans":[{"file_name":"src/main.rs","byte_start":16,"byte_end":17,"line_start":2,"line_end":2,"column_start":9,"column_end":10,"is_primary":true}],"children":[{"message":"if this is intentional, prefix it with an underscore: `_x`","code":null,"level":"help","spans":[],"children":[]}]}}
+{"reason":"compiler-message","message":{"message":"aborting due to previous error","code":null,"level":"error","spans":[],"children":[]}}
+{"message":"mismatched types","code":{"code":"E0308"},"level":"error","spans":[{"file_name":"/abs/lib.rs","line_start":1,"line_end":1,"column_start":1,"column_end":4,"is_primary":true}],"children":[]}
+"#;
+        let diagnostics = parse_cargo_json("cargo", output, Path::new("/project"));
+        assert_eq!(diagnostics.len(), 2);
+
+        let (path, diagnostic) = &diagnostics[0];
+        assert_eq!(path, Path::new("/project/src/main.rs"));
+        assert_eq!(
+            diagnostic.range,
+            lsp::Range::new(lsp::Position::new(1, 8), lsp::Position::new(1, 9))
+        );
+        assert_eq!(diagnostic.severity, Some(lsp::DiagnosticSeverity::WARNING));
+        assert_eq!(
+            diagnostic.message,
+            "unused variable: `x`\nhelp: if this is intentional, prefix it with an underscore: `_x`"
+        );
+        assert_eq!(diagnostic.source.as_deref(), Some("cargo"));
+
+        let (path, diagnostic) = &diagnostics[1];
+        assert_eq!(path, Path::new("/abs/lib.rs"));
+        assert_eq!(diagnostic.severity, Some(lsp::DiagnosticSeverity::ERROR));
+        assert_eq!(
+            diagnostic.code,
+            Some(lsp::NumberOrString::String("E0308".to_string()))
+        );
+    }
+
+    #[test]
+    fn parse_regex_lines() {
+        let regex = Regex::new(
+            r"^(?P<file>[^:]+):(?P<line>\d+):(?:(?P<column>\d+):)? (?P<severity>\w+): (?P<message>.*)$",
+        )
+        .unwrap();
+        let output = "src/a.c:3:5: warning: unused variable\n\
+            some unrelated line\n\
+            src/b.c:10: error: expected ';'\n";
+        let diagnostics = parse_regex("cc", &regex, output, Path::new("/project"));
+        assert_eq!(diagnostics.len(), 2);
+
+        let (path, diagnostic) = &diagnostics[0];
+        assert_eq!(path, Path::new("/project/src/a.c"));
+        assert_eq!(diagnostic.range.start, lsp::Position::new(2, 4));
+        assert_eq!(diagnostic.severity, Some(lsp::DiagnosticSeverity::WARNING));
+        assert_eq!(diagnostic.message, "unused variable");
+
+        let (path, diagnostic) = &diagnostics[1];
+        assert_eq!(path, Path::new("/project/src/b.c"));
+        assert_eq!(diagnostic.range.start, lsp::Position::new(9, 0));
+        assert_eq!(diagnostic.severity, Some(lsp::DiagnosticSeverity::ERROR));
+    }
+}
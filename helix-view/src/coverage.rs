@@ -0,0 +1,245 @@
+//! Line coverage reports produced by test runners, either as lcov tracefiles or as the JSON
+//! export of `llvm-cov`.
+
+use anyhow::{anyhow, bail, Context, Result};
+use helix_core::path::get_normalized_path;
+use serde::Deserialize;
+use std::collections::{BTreeMap, HashMap};
+use std::ops::RangeInclusive;
+use std::path::{Path, PathBuf};
+
+/// Execution counts of the instrumented lines of a file.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct FileCoverage {
+    /// 0-indexed line to the number of times it was executed.
+    lines: BTreeMap<usize, u64>,
+}
+
+impl FileCoverage {
+    /// How often `line` was executed, `None` if the line isn't instrumented.
+    pub fn hits(&self, line: usize) -> Option<u64> {
+        self.lines.get(&line).copied()
+    }
+
+    /// Percentage of the instrumented lines that were executed.
+    pub fn percent(&self) -> Option<f64> {
+        Self::percent_of(self.lines.values())
+    }
+
+    /// Percentage of the instrumented lines within `lines` that were executed.
+    pub fn range_percent(&self, lines: RangeInclusive<usize>) -> Option<f64> {
+        Self::percent_of(self.lines.range(lines).map(|(_, hits)| hits))
+    }
+
+    fn percent_of<'a>(hits: impl Iterator<Item = &'a u64>) -> Option<f64> {
+        let (covered, total) = hits.fold((0, 0), |(covered, total), &hits| {
+            (covered + (hits > 0) as usize, total + 1)
+        });
+        (total > 0).then(|| covered as f64 * 100.0 / total as f64)
+    }
+
+    fn add(&mut self, line: usize, hits: u64) {
+        let entry = self.lines.entry(line).or_default();
+        *entry = (*entry).max(hits);
+    }
+}
+
+/// Coverage of all files of a report, keyed by their normalized absolute path.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct Coverage {
+    files: HashMap<PathBuf, FileCoverage>,
+}
+
+#[derive(Deserialize)]
+struct LlvmCovExport {
+    data: Vec<LlvmCovData>,
+}
+
+#[derive(Deserialize)]
+struct LlvmCovData {
+    files: Vec<LlvmCovFile>,
+}
+
+#[derive(Deserialize)]
+struct LlvmCovFile {
+    filename: PathBuf,
+    segments: Vec<LlvmCovSegment>,
+}
+
+/// `[line, column, count, has_count, is_region_entry, is_gap_region]`, segments are sorted by
+/// their position. Older exports lack the gap flag.
+#[derive(Deserialize)]
+struct LlvmCovSegment(usize, usize, u64, bool, bool, #[serde(default)] bool);
+
+impl Coverage {
+    /// Loads a report, relative paths inside it are resolved against `base`.
+    pub fn load(report: &Path, base: &Path) -> Result<Self> {
+        let contents = std::fs::read_to_string(report)
+            .with_context(|| format!("failed to read {}", report.display()))?;
+        if contents.trim_start().starts_with('{') {
+            Self::from_llvm_cov_json(&contents, base)
+        } else {
+            Self::from_lcov(&contents, base)
+        }
+    }
+
+    /// Parses an lcov tracefile, only the line records (`DA`) are taken into account.
+    pub fn from_lcov(contents: &str, base: &Path) -> Result<Self> {
+        let mut coverage = Self::default();
+        let mut current: Option<(PathBuf, FileCoverage)> = None;
+
+        for (index, line) in contents.lines().enumerate() {
+            let line = line.trim();
+            let invalid = || anyhow!("invalid lcov record on line {}", index + 1);
+            if let Some(path) = line.strip_prefix("SF:") {
+                current = Some((base.join(path), FileCoverage::default()));
+            } else if let Some(record) = line.strip_prefix("DA:") {
+                let (_, file) = current.as_mut().ok_or_else(invalid)?;
+                let mut fields = record.split(',');
+                let line: usize = fields
+                    .next()
+                    .and_then(|line| line.parse().ok())
+                    .ok_or_else(invalid)?;
+                // counts may be reported as floats by some tools
+                let hits = fields
+                    .next()
+                    .and_then(|hits| hits.parse::<f64>().ok())
+                    .ok_or_else(invalid)?;
+                file.add(line.saturating_sub(1), hits as u64);
+            } else if line == "end_of_record" {
+                let (path, file) = current.take().ok_or_else(invalid)?;
+                coverage.insert(&path, file);
+            }
+        }
+        if current.is_some() {
+            bail!("lcov tracefile ends without end_of_record");
+        }
+        Ok(coverage)
+    }
+
+    /// Parses the output of `llvm-cov export -format=text`.
+    pub fn from_llvm_cov_json(contents: &str, base: &Path) -> Result<Self> {
+        let export: LlvmCovExport =
+            serde_json::from_str(contents).context("invalid llvm-cov export")?;
+
+        let mut coverage = Self::default();
+        for file in export.data.into_iter().flat_map(|data| data.files) {
+            let mut lines = FileCoverage::default();
+            // count of the region spanning the lines between two segments
+            let mut active = None;
+            let mut last_line = 0;
+            for LlvmCovSegment(line, _column, count, has_count, _is_entry, is_gap) in file.segments
+            {
+                // the region also covers the line on which the next one starts
+                if let Some(count) = active {
+                    for line in last_line + 1..=line {
+                        lines.add(line - 1, count);
+                    }
+                }
+                active = (has_count && !is_gap).then(|| count);
+                if let Some(count) = active {
+                    lines.add(line.saturating_sub(1), count);
+                }
+                last_line = line;
+            }
+            coverage.insert(&base.join(file.filename), lines);
+        }
+        Ok(coverage)
+    }
+
+    fn insert(&mut self, path: &Path, file: FileCoverage) {
+        let path = get_normalized_path(path);
+        match self.files.get_mut(&path) {
+            // a file can be reported several times, e.g. once per test binary
+            Some(existing) => {
+                for (line, hits) in file.lines {
+                    existing.add(line, hits);
+                }
+            }
+            None => {
+                self.files.insert(path, file);
+            }
+        }
+    }
+
+    pub fn file(&self, path: &Path) -> Option<&FileCoverage> {
+        self.files.get(path)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.files.is_empty()
+    }
+
+    pub fn len(&self) -> usize {
+        self.files.len()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn parse_lcov() {
+        let report = "TN:\n\
+            SF:src/lib.rs\n\
+            FN:1,main\n\
+            DA:1,1\n\
+            DA:2,0\n\
+            DA:4,3\n\
+            end_of_record\n\
+            SF:/abs/other.rs\n\
+            DA:1,0\n\
+            end_of_record\n";
+        let coverage = Coverage::from_lcov(report, Path::new("/project")).unwrap();
+        assert_eq!(coverage.len(), 2);
+
+        let file = coverage.file(Path::new("/project/src/lib.rs")).unwrap();
+        assert_eq!(file.hits(0), Some(1));
+        assert_eq!(file.hits(1), Some(0));
+        assert_eq!(file.hits(2), None);
+        assert_eq!(file.hits(3), Some(3));
+        assert_eq!(file.percent().map(f64::round), Some(67.0));
+        assert_eq!(file.range_percent(3..=10), Some(100.0));
+        assert_eq!(file.range_percent(10..=20), None);
+
+        let file = coverage.file(Path::new("/abs/other.rs")).unwrap();
+        assert_eq!(file.percent(), Some(0.0));
+    }
+
+    #[test]
+    fn parse_lcov_invalid() {
+        assert!(Coverage::from_lcov("DA:1,1\n", Path::new("/")).is_err());
+        assert!(Coverage::from_lcov("SF:a.rs\nDA:x,1\n", Path::new("/")).is_err());
+        assert!(Coverage::from_lcov("SF:a.rs\nDA:1,1\n", Path::new("/")).is_err());
+    }
+
+    #[test]
+    fn parse_llvm_cov_json() {
+        let report = r#"{
+            "type": "llvm.coverage.json.export",
+            "version": "2.0.1",
+            "data": [{
+                "files": [{
+                    "filename": "/project/src/main.rs",
+                    "segments": [
+                        [1, 11, 1, true, true, false],
+                        [3, 8, 0, true, true, false],
+                        [5, 6, 1, true, false, false],
+                        [6, 2, 0, false, false, false]
+                    ]
+                }]
+            }]
+        }"#;
+        let coverage = Coverage::from_llvm_cov_json(report, Path::new("/")).unwrap();
+        let file = coverage.file(Path::new("/project/src/main.rs")).unwrap();
+        assert_eq!(file.hits(0), Some(1));
+        assert_eq!(file.hits(1), Some(1));
+        // the line on which the uncovered region starts was executed
+        assert_eq!(file.hits(2), Some(1));
+        assert_eq!(file.hits(3), Some(0));
+        assert_eq!(file.hits(4), Some(1));
+        assert_eq!(file.hits(5), Some(1));
+        assert_eq!(file.hits(6), None);
+    }
+}
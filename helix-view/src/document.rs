@@ -8,25 +8,28 @@
 use serde::de::{self, Deserialize, Deserializer};
 use serde::Serialize;
 use std::borrow::Cow;
-use std::cell::Cell;
-use std::collections::HashMap;
+use std::cell::{Cell, RefCell};
+use std::collections::{HashMap, VecDeque};
 use std::fmt::Display;
 use std::future::Future;
 use std::path::{Path, PathBuf};
 use std::str::FromStr;
 use std::sync::Arc;
+use std::time::{Duration, Instant};
 
 use helix_core::{
     encoding,
     history::{History, State, UndoKind},
     indent::{auto_detect_indent_style, IndentStyle},
     line_ending::auto_detect_line_ending,
-    syntax::{self, LanguageConfiguration},
+    syntax::{self, LanguageConfiguration, LanguageDetection},
+    table::TableFormat,
     ChangeSet, Diagnostic, LineEnding, Rope, RopeBuilder, Selection, Syntax, Transaction,
     DEFAULT_LINE_ENDING,
 };
 
 use crate::editor::RedrawHandle;
+use crate::notes::{DocumentNote, Note};
 use crate::{apply_transaction, DocumentId, Editor, View, ViewId};
 
 /// 8kB of buffer space for encoding and decoding `Rope`s.
@@ -98,6 +101,67 @@ pub struct DocumentSavedEvent {
 pub type DocumentSavedEventResult = Result<DocumentSavedEvent, anyhow::Error>;
 pub type DocumentSavedEventFuture = BoxFuture<'static, DocumentSavedEventResult>;
 
+/// A symbol of the document reported by its language server, with the char range it spans.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DocumentSymbol {
+    pub name: String,
+    pub range: std::ops::Range<usize>,
+}
+
+/// Number of samples kept by [`SyntaxMetrics`].
+const SYNTAX_METRICS_SAMPLES: usize = 30;
+
+/// Timings of the syntax tree of a document, for diagnosing slow grammars.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct SyntaxMetrics {
+    /// The durations of the last parses, the oldest first.
+    pub parses: VecDeque<Duration>,
+    /// The time the highlight queries took in the last renders, the oldest first.
+    pub queries: VecDeque<Duration>,
+}
+
+impl SyntaxMetrics {
+    fn push(samples: &mut VecDeque<Duration>, duration: Duration) {
+        if samples.len() == SYNTAX_METRICS_SAMPLES {
+            samples.pop_front();
+        }
+        samples.push_back(duration);
+    }
+
+    pub fn record_parse(&mut self, duration: Duration) {
+        Self::push(&mut self.parses, duration);
+    }
+
+    pub fn record_query(&mut self, duration: Duration) {
+        Self::push(&mut self.queries, duration);
+    }
+}
+
+/// Where the indent style of a document comes from.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum IndentSource {
+    /// Detected from the contents of the document.
+    Detected,
+    /// The `indent` of the language in `languages.toml`.
+    Language,
+    /// No indent could be detected and the language sets none.
+    Default,
+    /// Set with `:indent-style` or `:set-local indent`. Kept when the document is reloaded
+    /// or formatted.
+    Manual,
+}
+
+impl Display for IndentSource {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Detected => f.write_str("detected"),
+            Self::Language => f.write_str("language"),
+            Self::Default => f.write_str("default"),
+            Self::Manual => f.write_str("manual"),
+        }
+    }
+}
+
 pub struct Document {
     pub(crate) id: DocumentId,
     text: Rope,
@@ -108,15 +172,26 @@ pub struct Document {
 
     pub restore_cursor: bool,
 
+    /// Format of the tables reflowed when leaving insert mode, `None` if table mode is off.
+    pub table_mode: Option<TableFormat>,
+
+    /// Whether the file contained NUL bytes when it was opened.
+    binary: bool,
+
     /// Current indent style.
     pub indent_style: IndentStyle,
+    pub indent_source: IndentSource,
 
     /// The document's default line ending.
     pub line_ending: LineEnding,
 
     syntax: Option<Syntax>,
+    /// Recorded while parsing and rendering, so rendering records through a shared reference.
+    pub syntax_metrics: RefCell<SyntaxMetrics>,
     /// Corresponding language scope name. Usually `source.<lang>`.
     pub(crate) language: Option<Arc<LanguageConfiguration>>,
+    /// Why the language was chosen, if it was detected rather than set.
+    pub language_detection: Option<LanguageDetection>,
 
     /// Pending changes since last history commit.
     changes: ChangeSet,
@@ -135,7 +210,14 @@ pub struct Document {
     pub(crate) modified_since_accessed: bool,
 
     diagnostics: Vec<Diagnostic>,
-    language_server: Option<Arc<helix_lsp::Client>>,
+    notes: Vec<DocumentNote>,
+    /// The symbols of the language server, sorted so that a symbol comes before the symbols
+    /// it contains.
+    symbols: Vec<DocumentSymbol>,
+    /// The version symbols were last requested for.
+    pub symbols_requested: Option<i32>,
+    /// Attached language servers, the primary server first.
+    language_servers: Vec<Arc<helix_lsp::Client>>,
 
     diff_handle: Option<DiffHandle>,
 }
@@ -363,19 +445,27 @@ pub fn from(text: Rope, encoding: Option<&'static encoding::Encoding>) -> Self {
             text,
             selections: HashMap::default(),
             indent_style: DEFAULT_INDENT,
+            indent_source: IndentSource::Default,
             line_ending: DEFAULT_LINE_ENDING,
             restore_cursor: false,
+            table_mode: None,
+            binary: false,
             syntax: None,
+            syntax_metrics: RefCell::default(),
             language: None,
+            language_detection: None,
             changes,
             old_state,
             diagnostics: Vec::new(),
+            notes: Vec::new(),
+            symbols: Vec::new(),
+            symbols_requested: None,
             version: 0,
             history: Cell::new(History::default()),
             savepoint: None,
             last_saved_revision: 0,
             modified_since_accessed: false,
-            language_server: None,
+            language_servers: Vec::new(),
             diff_handle: None,
         }
     }
@@ -398,7 +488,13 @@ pub fn open(
             (Rope::from(DEFAULT_LINE_ENDING.as_str()), encoding)
         };
 
+        // like binary detection in global search, only look at the first buffer
+        let binary = rope
+            .slice(..rope.len_chars().min(BUF_SIZE))
+            .chars()
+            .any(|c| c == '\0');
         let mut doc = Self::from(rope, Some(encoding));
+        doc.binary = binary;
 
         // set the path and try detecting the language
         doc.set_path(Some(path))?;
@@ -482,19 +578,19 @@ pub fn format(&self) -> Option<BoxFuture<'static, Result<Transaction, FormatterE
             return Some(formatting_future.boxed());
         };
 
-        let language_server = self.language_server()?;
         let text = self.text.clone();
-        let offset_encoding = language_server.offset_encoding();
-
-        let request = language_server.text_document_formatting(
-            self.identifier(),
-            lsp::FormattingOptions {
-                tab_size: self.tab_width() as u32,
-                insert_spaces: matches!(self.indent_style, IndentStyle::Spaces(_)),
-                ..Default::default()
-            },
-            None,
-        )?;
+        let (request, offset_encoding) = self.language_servers().find_map(|language_server| {
+            let request = language_server.text_document_formatting(
+                self.identifier(),
+                lsp::FormattingOptions {
+                    tab_size: self.tab_width() as u32,
+                    insert_spaces: matches!(self.indent_style, IndentStyle::Spaces(_)),
+                    ..Default::default()
+                },
+                None,
+            )?;
+            Some((request, language_server.offset_encoding()))
+        })?;
 
         let fut = async move {
             let edits = request.await.unwrap_or_else(|e| {
@@ -554,8 +650,13 @@ impl Future<Output = Result<DocumentSavedEvent, anyhow::Error>> + 'static + Send
             }
         };
 
+        // the text of a binary file is decoded lossily, writing it back would corrupt the file
+        if self.binary && self.path.as_ref() == Some(&path) {
+            bail!("can't save a binary file as text, edit it in the hex view");
+        }
+
         let identifier = self.path().map(|_| self.identifier());
-        let language_server = self.language_server.clone();
+        let language_servers = self.language_servers.clone();
 
         // mark changes up to now as saved
         let current_rev = self.get_current_revision();
@@ -587,14 +688,14 @@ impl Future<Output = Result<DocumentSavedEvent, anyhow::Error>> + 'static + Send
                 text: text.clone(),
             };
 
-            if let Some(language_server) = language_server {
-                if !language_server.is_initialized() {
-                    return Ok(event);
-                }
+            if let Some(identifier) = identifier {
+                for language_server in language_servers {
+                    if !language_server.is_initialized() {
+                        continue;
+                    }
 
-                if let Some(identifier) = identifier {
                     if let Some(notification) =
-                        language_server.text_document_did_save(identifier, &text)
+                        language_server.text_document_did_save(identifier.clone(), &text)
                     {
                         notification.await?;
                     }
@@ -607,13 +708,17 @@ impl Future<Output = Result<DocumentSavedEvent, anyhow::Error>> + 'static + Send
         Ok(future)
     }
 
-    /// Detect the programming language based on the file type.
+    /// Detect the programming language based on modelines, the file type, the shebang or the
+    /// content, remembering how it was detected.
     pub fn detect_language(&mut self, config_loader: Arc<syntax::Loader>) {
-        if let Some(path) = &self.path {
-            let language_config = config_loader
-                .language_config_for_file_name(path)
-                .or_else(|| config_loader.language_config_for_shebang(self.text()));
-            self.set_language(language_config, Some(config_loader));
+        if self.path.is_some() {
+            match config_loader.detect_language(self.path.as_deref(), self.text()) {
+                Some((language_config, detection)) => {
+                    self.set_language(Some(language_config), Some(config_loader));
+                    self.language_detection = Some(detection);
+                }
+                None => self.set_language(None, Some(config_loader)),
+            }
         }
     }
 
@@ -621,14 +726,44 @@ pub fn detect_language(&mut self, config_loader: Arc<syntax::Loader>) {
     /// configured in `languages.toml`, with a fallback to tabs if it isn't specified. Line ending
     /// is likewise auto-detected, and will fallback to the default OS line ending.
     pub fn detect_indent_and_line_ending(&mut self) {
-        self.indent_style = auto_detect_indent_style(&self.text).unwrap_or_else(|| {
-            self.language_config()
-                .and_then(|config| config.indent.as_ref())
-                .map_or(DEFAULT_INDENT, |config| IndentStyle::from_str(&config.unit))
-        });
+        self.detect_indent();
         self.line_ending = auto_detect_line_ending(&self.text).unwrap_or(DEFAULT_LINE_ENDING);
     }
 
+    /// Detects the indent style as in [`Document::detect_indent_and_line_ending`], unless it
+    /// was set manually.
+    pub fn detect_indent(&mut self) {
+        if self.indent_source == IndentSource::Manual {
+            return;
+        }
+        let language_indent = self
+            .language_config()
+            .and_then(|config| config.indent.as_ref())
+            .map(|config| IndentStyle::from_str(&config.unit));
+        (self.indent_style, self.indent_source) = match auto_detect_indent_style(&self.text) {
+            Some(style) => (style, IndentSource::Detected),
+            None => match language_indent {
+                Some(style) => (style, IndentSource::Language),
+                None => (DEFAULT_INDENT, IndentSource::Default),
+            },
+        };
+    }
+
+    /// Sets the indent style, keeping it when the document is reloaded or formatted. `None`
+    /// detects it again.
+    pub fn set_indent_style(&mut self, style: Option<IndentStyle>) {
+        match style {
+            Some(style) => {
+                self.indent_style = style;
+                self.indent_source = IndentSource::Manual;
+            }
+            None => {
+                self.indent_source = IndentSource::Default;
+                self.detect_indent();
+            }
+        }
+    }
+
     /// Reload the document from its path.
     pub fn reload(
         &mut self,
@@ -671,6 +806,11 @@ pub fn set_encoding(&mut self, label: &str) -> Result<(), Error> {
         Ok(())
     }
 
+    /// Whether the file looked like a binary file when it was opened.
+    pub fn is_binary(&self) -> bool {
+        self.binary
+    }
+
     /// Returns the [`Document`]'s current encoding.
     pub fn encoding(&self) -> &'static encoding::Encoding {
         self.encoding
@@ -695,9 +835,12 @@ pub fn set_language(
         language_config: Option<Arc<helix_core::syntax::LanguageConfiguration>>,
         loader: Option<Arc<helix_core::syntax::Loader>>,
     ) {
+        self.language_detection = None;
         if let (Some(language_config), Some(loader)) = (language_config, loader) {
             if let Some(highlight_config) = language_config.highlight_config(&loader.scopes()) {
+                let start = Instant::now();
                 let syntax = Syntax::new(&self.text, highlight_config, loader);
+                self.syntax_metrics.get_mut().record_parse(start.elapsed());
                 self.syntax = Some(syntax);
             }
 
@@ -730,9 +873,9 @@ pub fn set_language_by_language_id(
         Ok(())
     }
 
-    /// Set the LSP.
-    pub fn set_language_server(&mut self, language_server: Option<Arc<helix_lsp::Client>>) {
-        self.language_server = language_server;
+    /// Set the language servers, the primary server first.
+    pub fn set_language_servers(&mut self, language_servers: Vec<Arc<helix_lsp::Client>>) {
+        self.language_servers = language_servers;
     }
 
     /// Select text within the [`Document`].
@@ -817,10 +960,12 @@ fn apply_impl(&mut self, transaction: &Transaction, view_id: ViewId) -> bool {
 
             // update tree-sitter syntax tree
             if let Some(syntax) = &mut self.syntax {
+                let start = Instant::now();
                 // TODO: no unwrap
                 syntax
                     .update(&old_doc, &self.text, transaction.changes())
                     .unwrap();
+                self.syntax_metrics.get_mut().record_parse(start.elapsed());
             }
 
             // map state.diagnostics over changes::map_pos too
@@ -834,8 +979,15 @@ fn apply_impl(&mut self, transaction: &Transaction, view_id: ViewId) -> bool {
             self.diagnostics
                 .sort_unstable_by_key(|diagnostic| diagnostic.range);
 
+            // notes stay with the text of their line
+            for note in &mut self.notes {
+                note.pos = transaction
+                    .changes()
+                    .map_pos(note.pos, helix_core::Assoc::After);
+            }
+
             // emit lsp notification
-            if let Some(language_server) = self.language_server() {
+            for language_server in self.language_servers() {
                 let notify = language_server.text_document_did_change(
                     self.versioned_identifier(),
                     &old_doc,
@@ -921,6 +1073,11 @@ fn earlier_later_impl(&mut self, view: &mut View, uk: UndoKind, earlier: bool) -
         } else {
             self.history.get_mut().later(uk)
         };
+        self.apply_history_jump(view, txns)
+    }
+
+    /// Applies the transactions of a jump through the history.
+    fn apply_history_jump(&mut self, view: &mut View, txns: Vec<Transaction>) -> bool {
         let mut success = false;
         for txn in txns {
             if self.apply_impl(&txn, view.id) {
@@ -946,6 +1103,15 @@ pub fn later(&mut self, view: &mut View, uk: UndoKind) -> bool {
         self.earlier_later_impl(view, uk, false)
     }
 
+    /// Moves the [`Document`] to the revision `revision` of its history, which may be on another
+    /// branch. Returns whether the document changed.
+    pub fn jump_to_revision(&mut self, view: &mut View, revision: usize) -> bool {
+        match self.history.get_mut().jump_to_revision(revision) {
+            Some(txns) => self.apply_history_jump(view, txns),
+            None => false,
+        }
+    }
+
     /// Commit pending changes to history
     pub fn append_changes_to_history(&mut self, view: &mut View) {
         if self.changes.is_empty() {
@@ -1042,8 +1208,8 @@ pub fn language_id(&self) -> Option<&str> {
         let language_config = self.language.as_deref()?;
 
         language_config
-            .language_server
-            .as_ref()?
+            .language_server_for(self.path().map(|path| path.as_path()))?
+            .0
             .language_id
             .as_deref()
             .or(Some(language_config.language_id.as_str()))
@@ -1059,10 +1225,26 @@ pub fn version(&self) -> i32 {
         self.version
     }
 
-    /// Language server if it has been initialized.
+    /// Primary language server if it has been initialized.
     pub fn language_server(&self) -> Option<&helix_lsp::Client> {
-        let server = self.language_server.as_deref()?;
-        server.is_initialized().then(|| server)
+        let server = self.language_servers.first()?;
+        server.is_initialized().then(|| server.as_ref())
+    }
+
+    /// All initialized language servers, the primary server first.
+    pub fn language_servers(&self) -> impl Iterator<Item = &helix_lsp::Client> {
+        self.language_servers
+            .iter()
+            .map(|server| server.as_ref())
+            .filter(|server| server.is_initialized())
+    }
+
+    /// Ids of all attached language servers, including ones which are still initializing.
+    pub fn language_server_ids(&self) -> Vec<usize> {
+        self.language_servers
+            .iter()
+            .map(|server| server.id())
+            .collect()
     }
 
     pub fn diff_handle(&self) -> Option<&DiffHandle> {
@@ -1174,6 +1356,72 @@ pub fn set_diagnostics(&mut self, diagnostics: Vec<Diagnostic>) {
             .sort_unstable_by_key(|diagnostic| diagnostic.range);
     }
 
+    #[inline]
+    pub fn notes(&self) -> &[DocumentNote] {
+        &self.notes
+    }
+
+    pub fn set_symbols(&mut self, mut symbols: Vec<DocumentSymbol>) {
+        symbols.sort_by_key(|symbol| (symbol.range.start, std::cmp::Reverse(symbol.range.end)));
+        self.symbols = symbols;
+    }
+
+    /// The names of the symbols containing `pos`, the outermost first.
+    pub fn symbol_context(&self, pos: usize) -> impl Iterator<Item = &str> {
+        self.symbols
+            .iter()
+            .take_while(move |symbol| symbol.range.start <= pos)
+            .filter(move |symbol| symbol.range.contains(&pos))
+            .map(|symbol| symbol.name.as_str())
+    }
+
+    /// Anchors the notes to the start of their lines.
+    pub fn set_notes(&mut self, notes: &[Note]) {
+        let last_line = self.text.len_lines().saturating_sub(1);
+        self.notes = notes
+            .iter()
+            .map(|note| DocumentNote {
+                pos: self.text.line_to_char(note.line.min(last_line)),
+                text: note.text.clone(),
+            })
+            .collect();
+    }
+
+    /// The notes with the lines they moved to, sorted by line.
+    pub fn note_lines(&self) -> Vec<Note> {
+        let mut notes: Vec<_> = self
+            .notes
+            .iter()
+            .map(|note| Note {
+                line: self.text.char_to_line(note.pos),
+                text: note.text.clone(),
+            })
+            .collect();
+        notes.sort_by_key(|note| note.line);
+        notes
+    }
+
+    /// Replaces the diagnostics reported by the language server with `language_server_id`,
+    /// keeping diagnostics from other servers and sources.
+    pub fn replace_diagnostics(&mut self, diagnostics: Vec<Diagnostic>, language_server_id: usize) {
+        self.diagnostics
+            .retain(|diagnostic| diagnostic.language_server_id != Some(language_server_id));
+        self.diagnostics.extend(diagnostics);
+        self.diagnostics
+            .sort_unstable_by_key(|diagnostic| diagnostic.range);
+    }
+
+    /// Replaces the diagnostics reported by the checker `source`, keeping diagnostics from
+    /// language servers and other checkers.
+    pub fn replace_checker_diagnostics(&mut self, diagnostics: Vec<Diagnostic>, source: &str) {
+        self.diagnostics.retain(|diagnostic| {
+            diagnostic.language_server_id.is_some() || diagnostic.source.as_deref() != Some(source)
+        });
+        self.diagnostics.extend(diagnostics);
+        self.diagnostics
+            .sort_unstable_by_key(|diagnostic| diagnostic.range);
+    }
+
     /// Get the document's auto pairs. If the document has a recognized
     /// language config with auto pairs configured, returns that;
     /// otherwise, falls back to the global auto pairs config. If the global
@@ -1241,6 +1489,25 @@ fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
 mod test {
     use super::*;
 
+    #[test]
+    fn symbol_context() {
+        let mut doc = Document::from(Rope::from("mod a { fn b() {} }\nfn c() {}\n"), None);
+        let symbol = |name: &str, range| DocumentSymbol {
+            name: name.to_string(),
+            range,
+        };
+        doc.set_symbols(vec![
+            symbol("b", 8..17),
+            symbol("c", 20..29),
+            symbol("a", 0..19),
+        ]);
+
+        assert_eq!(doc.symbol_context(10).collect::<Vec<_>>(), ["a", "b"]);
+        assert_eq!(doc.symbol_context(18).collect::<Vec<_>>(), ["a"]);
+        assert_eq!(doc.symbol_context(22).collect::<Vec<_>>(), ["c"]);
+        assert_eq!(doc.symbol_context(19).count(), 0);
+    }
+
     #[test]
     fn changeset_to_changes_ignore_line_endings() {
         use helix_lsp::{lsp, Client, OffsetEncoding};
@@ -1,12 +1,18 @@
 use crate::{
     align_view,
     clipboard::{get_clipboard_provider, ClipboardProvider},
+    coverage::Coverage,
     document::{DocumentSavedEventFuture, DocumentSavedEventResult, Mode},
-    graphics::{CursorKind, Rect},
+    graphics::{CursorKind, Rect, Style},
     info::Info,
     input::KeyEvent,
+    notes::Notes,
+    pins::Pins,
+    recent::Recent,
     theme::{self, Theme},
     tree::{self, Tree},
+    tree_keymap::TreeKeymap,
+    yank_history::YankHistory,
     Align, Document, DocumentId, View, ViewId,
 };
 use helix_vcs::DiffProviderRegistry;
@@ -18,7 +24,7 @@
 
 use std::{
     borrow::Cow,
-    collections::{BTreeMap, HashMap},
+    collections::{BTreeMap, HashMap, HashSet},
     io::stdin,
     num::NonZeroUsize,
     path::{Path, PathBuf},
@@ -41,8 +47,9 @@
 use helix_core::Position;
 use helix_core::{
     auto_pairs::AutoPairs,
-    syntax::{self, AutoPairConfig},
-    Change,
+    log_file,
+    syntax::{self, AutoPairConfig, FileType},
+    Change, Selection,
 };
 use helix_dap as dap;
 use helix_lsp::lsp;
@@ -136,6 +143,11 @@ pub struct ExplorerConfig {
     pub position: ExplorerPosition,
     /// explorer column width
     pub column_width: usize,
+    /// Whether to hide hidden files, they can be toggled in the explorer. Defaults to true.
+    pub hidden: bool,
+    /// Whether to hide files ignored by `.gitignore`, `.ignore` and `.git/info/exclude`.
+    /// Defaults to true.
+    pub git_ignore: bool,
 }
 
 impl ExplorerConfig {
@@ -174,10 +186,299 @@ fn default() -> Self {
             style: ExplorerStyle::Tree,
             position: ExplorerPosition::Overlay,
             column_width: 30,
+            hidden: true,
+            git_ignore: true,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case", default, deny_unknown_fields)]
+pub struct LogConfig {
+    /// Regex matching the timestamp of log lines.
+    pub timestamp: String,
+    /// Regex matching the level of log lines. The first capture group is used as the level name
+    /// if there is one.
+    pub level: String,
+}
+
+impl Default for LogConfig {
+    fn default() -> Self {
+        Self {
+            timestamp: log_file::DEFAULT_TIMESTAMP_PATTERN.to_string(),
+            level: log_file::DEFAULT_LEVEL_PATTERN.to_string(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case", default, deny_unknown_fields)]
+pub struct YankHistoryConfig {
+    /// Number of unpinned entries to keep.
+    pub size: usize,
+    /// Whether to keep the history across sessions.
+    pub persist: bool,
+}
+
+impl Default for YankHistoryConfig {
+    fn default() -> Self {
+        Self {
+            size: 50,
+            persist: false,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case", default, deny_unknown_fields)]
+pub struct MinimapConfig {
+    /// Whether to show the minimap. Defaults to false.
+    pub enable: bool,
+    /// Width of the minimap in columns.
+    pub width: u16,
+}
+
+impl Default for MinimapConfig {
+    fn default() -> Self {
+        Self {
+            enable: false,
+            width: 12,
         }
     }
 }
 
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case", default, deny_unknown_fields)]
+pub struct OutlineConfig {
+    /// Width of the outline panel in columns.
+    pub width: u16,
+}
+
+impl Default for OutlineConfig {
+    fn default() -> Self {
+        Self { width: 32 }
+    }
+}
+
+/// Panels opened when a document matching `languages` or `file-types` gains the focus.
+#[derive(Debug, Default, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case", default, deny_unknown_fields)]
+pub struct PanelRule {
+    /// Names of languages, e.g. `rust`.
+    pub languages: Vec<String>,
+    /// Extensions, file names or `{ suffix = "..." }` path suffixes, like the `file-types` of
+    /// languages.
+    pub file_types: Vec<FileType>,
+    /// The panels to open.
+    pub open: Vec<Panel>,
+}
+
+impl PanelRule {
+    pub fn matches(&self, doc: &Document) -> bool {
+        let language = doc.language_name();
+        self.languages
+            .iter()
+            .any(|name| language == Some(name.as_str()))
+            || doc.path().map_or(false, |path| {
+                self.file_types
+                    .iter()
+                    .any(|file_type| file_type.matches(path))
+            })
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Panel {
+    /// The file explorer.
+    Explorer,
+    /// The symbols of the focused document, see `outline`.
+    Outline,
+}
+
+#[derive(Debug, Default, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case", default, deny_unknown_fields)]
+pub struct SymbolTreeConfig {
+    /// How the symbols of each parent are ordered when the symbol tree opens.
+    pub sort: SymbolSort,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum SymbolSort {
+    /// By their position in the document.
+    Position,
+    /// By name, ignoring case.
+    Name,
+    /// By kind, e.g. types before functions, then by position.
+    Kind,
+}
+
+impl SymbolSort {
+    /// The next sort mode when cycling through them.
+    pub fn next(self) -> Self {
+        match self {
+            Self::Position => Self::Name,
+            Self::Name => Self::Kind,
+            Self::Kind => Self::Position,
+        }
+    }
+}
+
+impl Default for SymbolSort {
+    fn default() -> Self {
+        Self::Position
+    }
+}
+
+impl std::fmt::Display for SymbolSort {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            Self::Position => "position",
+            Self::Name => "name",
+            Self::Kind => "kind",
+        })
+    }
+}
+
+#[derive(Debug, Default, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case", default, deny_unknown_fields)]
+pub struct TreeConfig {
+    /// How the levels of the items of tree panels are drawn.
+    pub indent_guides: TreeIndentGuides,
+    /// Where the text of items too wide for the panel is cut off.
+    pub ellipsis: TreeEllipsis,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum TreeEllipsis {
+    /// Keeps the start of the text, e.g. `very_long_na…`.
+    End,
+    /// Keeps the start and the end of the text, e.g. `very_lo…name`.
+    Middle,
+}
+
+impl Default for TreeEllipsis {
+    fn default() -> Self {
+        Self::End
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum TreeIndentGuides {
+    /// Lines connecting the items to their parents, like `├─` and `└─`.
+    Lines,
+    /// A bar per level.
+    Bars,
+    /// Spaces only.
+    None,
+}
+
+impl Default for TreeIndentGuides {
+    fn default() -> Self {
+        Self::Lines
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case", default, deny_unknown_fields)]
+pub struct IpcConfig {
+    /// Whether to listen for requests on a Unix socket at startup. Defaults to false.
+    pub enable: bool,
+    /// The path of the socket, defaults to `helix-<pid>.sock` in the cache directory.
+    pub socket: Option<PathBuf>,
+    /// The commands clients may run, with a `:` before typable commands.
+    pub commands: Vec<String>,
+}
+
+impl Default for IpcConfig {
+    fn default() -> Self {
+        Self {
+            enable: false,
+            socket: None,
+            commands: [
+                "toggle_or_focus_explorer",
+                "open_explorer_recursion",
+                "close_explorer",
+                "file_picker",
+                "symbol_picker",
+                ":write",
+                ":reload",
+                ":buffer-close",
+            ]
+            .into_iter()
+            .map(String::from)
+            .collect(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case", default, deny_unknown_fields)]
+pub struct ZenConfig {
+    /// Width of the centered text column.
+    pub width: u16,
+}
+
+impl Default for ZenConfig {
+    fn default() -> Self {
+        Self { width: 80 }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum SplitDirection {
+    /// Side by side.
+    Vertical,
+    /// Stacked.
+    Horizontal,
+}
+
+/// A window layout applied with `:layout`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case", default, deny_unknown_fields)]
+pub struct LayoutPreset {
+    /// Whether the file explorer is shown.
+    pub explorer: bool,
+    /// Whether the debug console is shown.
+    pub debug_console: bool,
+    /// Number of windows showing the current document, other windows are closed.
+    pub windows: usize,
+    /// How the windows are split.
+    pub split: SplitDirection,
+}
+
+impl Default for LayoutPreset {
+    fn default() -> Self {
+        Self {
+            explorer: false,
+            debug_console: false,
+            windows: 1,
+            split: SplitDirection::Vertical,
+        }
+    }
+}
+
+fn default_layouts() -> HashMap<String, LayoutPreset> {
+    let ide = LayoutPreset {
+        explorer: true,
+        debug_console: true,
+        ..LayoutPreset::default()
+    };
+    let split = LayoutPreset {
+        windows: 2,
+        ..LayoutPreset::default()
+    };
+    HashMap::from([
+        ("ide".to_string(), ide),
+        ("split".to_string(), split),
+        ("single".to_string(), LayoutPreset::default()),
+    ])
+}
+
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 #[serde(rename_all = "kebab-case", default, deny_unknown_fields)]
 pub struct Config {
@@ -235,14 +536,39 @@ pub struct Config {
     pub rulers: Vec<u16>,
     #[serde(default)]
     pub whitespace: WhitespaceConfig,
+    /// Show control characters as control pictures like `␀` and highlight replacement
+    /// characters of invalid UTF-8. Defaults to true.
+    pub render_control_characters: bool,
     /// Persistently display open buffers along the top
     pub bufferline: BufferLine,
     /// Vertical indent width guides.
     pub indent_guides: IndentGuidesConfig,
+    /// Coloring of brackets by their nesting depth.
+    pub rainbow_brackets: RainbowBracketsConfig,
     /// Whether to color modes with different colors. Defaults to `false`.
     pub color_modes: bool,
     /// explore config
     pub explorer: ExplorerConfig,
+    /// Patterns of the log view.
+    pub log: LogConfig,
+    /// Size and persistence of the yank history.
+    pub yank_history: YankHistoryConfig,
+    /// Window layouts by name, applied with `:layout`.
+    pub layouts: HashMap<String, LayoutPreset>,
+    /// Distraction-free mode toggled with `zen_mode`.
+    pub zen: ZenConfig,
+    /// Overview of the focused document on the right of the editor.
+    pub minimap: MinimapConfig,
+    /// Symbols of the focused document docked on the right of the editor.
+    pub outline: OutlineConfig,
+    /// Panels opened for the documents of certain languages or files.
+    pub panels: Vec<PanelRule>,
+    /// The symbol tree opened with `tree_symbol_picker`.
+    pub symbol_tree: SymbolTreeConfig,
+    /// Look of the tree panels.
+    pub tree: TreeConfig,
+    /// The socket external tools control the editor through.
+    pub ipc: IpcConfig,
 }
 
 #[derive(Debug, Default, Clone, PartialEq, Eq, Serialize, Deserialize)]
@@ -326,6 +652,49 @@ pub struct SearchConfig {
     pub smart_case: bool,
     /// Whether the search should wrap after depleting the matches. Default to true.
     pub wrap_around: bool,
+    /// Only match whole words. Defaults to false.
+    pub whole_word: bool,
+}
+
+/// The search options in effect, which start out as configured and can be toggled while
+/// editing.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SearchOptions {
+    pub smart_case: bool,
+    pub whole_word: bool,
+}
+
+impl From<&SearchConfig> for SearchOptions {
+    fn from(config: &SearchConfig) -> Self {
+        Self {
+            smart_case: config.smart_case,
+            whole_word: config.whole_word,
+        }
+    }
+}
+
+/// The matches of the search being typed, shown until the search prompt closes.
+#[derive(Debug, Clone)]
+pub struct SearchMatches {
+    pub doc: DocumentId,
+    /// The char ranges of the matches, in document order.
+    pub ranges: Vec<std::ops::Range<usize>>,
+    /// The match the primary selection is on.
+    pub current: Option<usize>,
+}
+
+impl SearchOptions {
+    /// The enabled options, like `smart-case whole-word`.
+    pub fn label(&self) -> String {
+        let mut options = Vec::new();
+        if self.smart_case {
+            options.push("smart-case");
+        }
+        if self.whole_word {
+            options.push("whole-word");
+        }
+        options.join(" ")
+    }
 }
 
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
@@ -335,6 +704,8 @@ pub struct StatusLineConfig {
     pub center: Vec<StatusLineElement>,
     pub right: Vec<StatusLineElement>,
     pub separator: String,
+    /// The string between the symbols of the `symbol-context` element.
+    pub symbol_separator: String,
     pub mode: ModeConfig,
 }
 
@@ -347,6 +718,7 @@ fn default() -> Self {
             center: vec![],
             right: vec![E::Diagnostics, E::Selections, E::Position, E::FileEncoding],
             separator: String::from("│"),
+            symbol_separator: String::from(" > "),
             mode: ModeConfig::default(),
         }
     }
@@ -391,12 +763,18 @@ pub enum StatusLineElement {
     /// The file type (language ID or "text")
     FileType,
 
+    /// The indent style and where it comes from, like `4 spaces (detected)`
+    IndentStyle,
+
     /// A summary of the number of errors and warnings
     Diagnostics,
 
     /// A summary of the number of errors and warnings on file and workspace
     WorkspaceDiagnostics,
 
+    /// The position of the diagnostic at the cursor among the diagnostics of its severity
+    DiagnosticPosition,
+
     /// The number of selections (cursors)
     Selections,
 
@@ -409,6 +787,9 @@ pub enum StatusLineElement {
     /// The separator string
     Separator,
 
+    /// The search options which are enabled
+    SearchOptions,
+
     /// The cursor position as a percent of the total file
     PositionPercentage,
 
@@ -417,6 +798,9 @@ pub enum StatusLineElement {
 
     /// A single space
     Spacer,
+
+    /// The symbols containing the cursor, like `module > Struct > method`
+    SymbolContext,
 }
 
 // Cursor shape is read and used on every rendered frame and so needs
@@ -525,6 +909,8 @@ pub enum GutterType {
     Spacer,
     /// Highlight local changes
     Diff,
+    /// Show which lines were executed according to the loaded coverage report
+    Coverage,
 }
 
 impl std::str::FromStr for GutterType {
@@ -536,6 +922,7 @@ fn from_str(s: &str) -> Result<Self, Self::Err> {
             "spacer" => Ok(Self::Spacer),
             "line-numbers" => Ok(Self::LineNumbers),
             "diff" => Ok(Self::Diff),
+            "coverage" => Ok(Self::Coverage),
             _ => anyhow::bail!("Gutter type can only be `diagnostics` or `line-numbers`."),
         }
     }
@@ -567,6 +954,8 @@ pub enum WhitespaceRender {
         nbsp: Option<WhitespaceRenderValue>,
         tab: Option<WhitespaceRenderValue>,
         newline: Option<WhitespaceRenderValue>,
+        /// Whitespace at the end of lines, also rendered when its kind isn't.
+        trailing: Option<WhitespaceRenderValue>,
     },
 }
 
@@ -612,6 +1001,14 @@ pub fn newline(&self) -> WhitespaceRenderValue {
             } => newline.or(default).unwrap_or(WhitespaceRenderValue::None),
         }
     }
+    pub fn trailing(&self) -> WhitespaceRenderValue {
+        match *self {
+            Self::Basic(val) => val,
+            Self::Specific {
+                default, trailing, ..
+            } => trailing.or(default).unwrap_or(WhitespaceRenderValue::None),
+        }
+    }
 }
 
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
@@ -644,6 +1041,39 @@ pub struct IndentGuidesConfig {
     pub skip_levels: u8,
 }
 
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default, rename_all = "kebab-case", deny_unknown_fields)]
+pub struct RainbowBracketsConfig {
+    pub enable: bool,
+    /// Theme scopes of the brackets by depth, starting over for deeper brackets.
+    pub palette: Vec<String>,
+    /// Whether indent guides take the color of their level as well.
+    pub indent_guides: bool,
+}
+
+impl Default for RainbowBracketsConfig {
+    fn default() -> Self {
+        Self {
+            enable: false,
+            palette: ["keyword", "function", "type", "constant", "string", "label"]
+                .into_iter()
+                .map(String::from)
+                .collect(),
+            indent_guides: false,
+        }
+    }
+}
+
+impl RainbowBracketsConfig {
+    /// The palette scopes defined by `theme`, for the depth modulo their number.
+    pub fn styles(&self, theme: &Theme) -> Vec<Style> {
+        self.palette
+            .iter()
+            .filter_map(|scope| theme.try_get(scope))
+            .collect()
+    }
+}
+
 impl Default for IndentGuidesConfig {
     fn default() -> Self {
         Self {
@@ -692,10 +1122,22 @@ fn default() -> Self {
             terminal: get_terminal_provider(),
             rulers: Vec::new(),
             whitespace: WhitespaceConfig::default(),
+            render_control_characters: true,
             bufferline: BufferLine::default(),
             indent_guides: IndentGuidesConfig::default(),
+            rainbow_brackets: RainbowBracketsConfig::default(),
             color_modes: false,
             explorer: ExplorerConfig::default(),
+            log: LogConfig::default(),
+            yank_history: YankHistoryConfig::default(),
+            layouts: default_layouts(),
+            zen: ZenConfig::default(),
+            minimap: MinimapConfig::default(),
+            outline: OutlineConfig::default(),
+            panels: Vec::new(),
+            symbol_tree: SymbolTreeConfig::default(),
+            tree: TreeConfig::default(),
+            ipc: IpcConfig::default(),
         }
     }
 }
@@ -705,6 +1147,7 @@ fn default() -> Self {
         Self {
             wrap_around: true,
             smart_case: true,
+            whole_word: false,
         }
     }
 }
@@ -734,6 +1177,73 @@ pub struct Breakpoint {
     pub log_message: Option<String>,
 }
 
+/// A breakpoint which triggers when the value behind `data_id` is accessed.
+#[derive(Debug, Clone, Default)]
+pub struct DataBreakpoint {
+    pub id: Option<usize>,
+    pub verified: bool,
+    pub message: Option<String>,
+
+    /// Identifier of the watched data, as returned by the debug adapter.
+    pub data_id: String,
+    /// Human readable description of the watched data.
+    pub description: String,
+    pub access_type: Option<dap::DataBreakpointAccessType>,
+    pub condition: Option<String>,
+    pub hit_condition: Option<String>,
+}
+
+/// An expression which is re-evaluated every time the debuggee stops.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Watch {
+    pub expression: String,
+    /// Outcome of the last evaluation, `None` if it wasn't evaluated yet.
+    pub result: Option<Result<WatchValue, String>>,
+}
+
+impl Watch {
+    pub fn new(expression: String) -> Self {
+        Self {
+            expression,
+            result: None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct WatchValue {
+    pub value: String,
+    pub ty: Option<String>,
+    pub variables_reference: usize,
+    pub memory_reference: Option<String>,
+}
+
+/// Workspace edits, e.g. of a rename, staged in the refactoring queue to be reviewed and
+/// applied together with the other staged edits.
+#[derive(Debug, Clone)]
+pub struct QueuedEdit {
+    pub label: String,
+    pub offset_encoding: helix_lsp::OffsetEncoding,
+    /// The text edits of each file.
+    pub edits: Vec<(lsp::Url, Vec<lsp::TextEdit>)>,
+}
+
+/// A location shown before previewing a symbol, reference or call site from a panel.
+#[derive(Debug, Clone)]
+pub struct Peek {
+    pub view_id: ViewId,
+    pub doc_id: DocumentId,
+    pub selection: Selection,
+}
+
+/// The folded items and the selected item of a tree panel, identified by their node ids, to
+/// restore them when the panel is opened again.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct TreeState {
+    pub folded: HashSet<u64>,
+    pub selected: Option<u64>,
+}
+
 use futures_util::stream::{Flatten, Once};
 
 pub struct Editor {
@@ -755,12 +1265,45 @@ pub struct Editor {
     pub macro_recording: Option<(char, Vec<KeyEvent>)>,
     pub macro_replaying: Vec<char>,
     pub language_servers: helix_lsp::Registry,
-    pub diagnostics: BTreeMap<lsp::Url, Vec<lsp::Diagnostic>>,
+    /// Diagnostics of all files, paired with the id of the reporting language server or `None`
+    /// for diagnostics of checkers (see [`crate::checker`]).
+    pub diagnostics: BTreeMap<lsp::Url, Vec<(lsp::Diagnostic, Option<usize>)>>,
     pub diff_providers: DiffProviderRegistry,
 
     pub debugger: Option<dap::Client>,
     pub debugger_events: SelectAll<UnboundedReceiverStream<dap::Payload>>,
     pub breakpoints: HashMap<PathBuf, Vec<Breakpoint>>,
+    pub data_breakpoints: Vec<DataBreakpoint>,
+    /// Output of the debuggee as reported by the debug adapter: the category and the text.
+    pub debug_output: Vec<(Option<String>, String)>,
+    /// Watch expressions of the current project, see [`crate::handlers::dap::load_watches`].
+    pub watches: Vec<Watch>,
+    /// The test coverage report loaded with `:coverage-load`.
+    pub coverage: Option<Coverage>,
+    /// Notes attached to lines of the files of the project.
+    pub notes: Notes,
+    /// Recently yanked text and clipboard contents.
+    pub yank_history: YankHistory,
+    /// Recently opened files and workspaces.
+    pub recent: Recent,
+    /// Files of the project pinned to numbered slots.
+    pub pins: Pins,
+    /// Edits staged in the refactoring queue, in the order they were staged.
+    pub edit_queue: Vec<QueuedEdit>,
+    /// The locations left by previews from panels, oldest first. They only reach the jumplist
+    /// once the previews are confirmed.
+    pub peeks: Vec<Peek>,
+    /// Recently run commands, oldest first. Static commands are recorded by name and typable
+    /// commands as `:` followed by their command line.
+    pub command_history: Vec<String>,
+    /// Key bindings of tree panels, from the `[keys.tree]` section of the config.
+    pub tree_keymap: TreeKeymap,
+    /// The state of the symbol tree of each document as it was last closed.
+    pub symbol_tree_states: HashMap<DocumentId, TreeState>,
+    /// The symbol kinds hidden in the symbol trees for the rest of the session.
+    pub hidden_symbol_kinds: Vec<lsp::SymbolKind>,
+    /// What zen mode changed, to restore it when leaving zen mode.
+    zen: Option<ZenState>,
 
     pub clipboard_provider: Box<dyn ClipboardProvider>,
 
@@ -778,6 +1321,8 @@ pub struct Editor {
 
     pub config: Box<dyn DynAccess<Config>>,
     pub auto_pairs: Option<AutoPairs>,
+    pub search_options: SearchOptions,
+    pub search_matches: Option<SearchMatches>,
 
     pub idle_timer: Pin<Box<Sleep>>,
     pub last_motion: Option<Motion>,
@@ -796,6 +1341,15 @@ pub struct Editor {
 
 pub type RedrawHandle = (Arc<Notify>, Arc<RwLock<()>>);
 
+#[derive(Debug)]
+struct ZenState {
+    /// The view shown in zen mode and its gutters.
+    view: ViewId,
+    gutters: Vec<GutterType>,
+    /// Whether the view was zoomed before.
+    zoomed: bool,
+}
+
 #[derive(Debug)]
 pub enum EditorEvent {
     DocumentSaved(DocumentSavedEventResult),
@@ -849,6 +1403,7 @@ pub fn new(
     ) -> Self {
         let conf = config.load();
         let auto_pairs = (&conf.auto_pairs).into();
+        let search_options = (&conf.search).into();
 
         // HAXX: offset the render area height by 1 to account for prompt/commandline
         area.height -= 1;
@@ -872,6 +1427,25 @@ pub fn new(
             debugger: None,
             debugger_events: SelectAll::new(),
             breakpoints: HashMap::new(),
+            data_breakpoints: Vec::new(),
+            debug_output: Vec::new(),
+            watches: crate::handlers::dap::load_watches(),
+            coverage: None,
+            notes: Notes::load(),
+            yank_history: if conf.yank_history.persist {
+                YankHistory::load()
+            } else {
+                YankHistory::default()
+            },
+            recent: Recent::load(),
+            pins: Pins::load(),
+            edit_queue: Vec::new(),
+            peeks: Vec::new(),
+            command_history: Vec::new(),
+            tree_keymap: TreeKeymap::default(),
+            symbol_tree_states: HashMap::new(),
+            hidden_symbol_kinds: Vec::new(),
+            zen: None,
             syn_loader,
             theme_loader,
             last_theme: None,
@@ -885,6 +1459,8 @@ pub fn new(
             last_completion: None,
             config,
             auto_pairs,
+            search_options,
+            search_matches: None,
             exit_code: 0,
             config_events: unbounded_channel(),
             redraw_handle: Default::default(),
@@ -906,6 +1482,7 @@ pub fn config(&self) -> DynGuard<Config> {
     pub fn refresh_config(&mut self) {
         let config = self.config();
         self.auto_pairs = (&config.auto_pairs).into();
+        self.search_options = (&config.search).into();
         self.reset_idle_timer();
     }
 
@@ -927,6 +1504,17 @@ pub fn clear_status(&mut self) {
         self.status_msg = None;
     }
 
+    /// Records a command in the command history, moving it to the end if it was run before.
+    pub fn record_command(&mut self, command: String) {
+        const MAX_COMMAND_HISTORY: usize = 100;
+
+        self.command_history.retain(|recorded| *recorded != command);
+        self.command_history.push(command);
+        if self.command_history.len() > MAX_COMMAND_HISTORY {
+            self.command_history.remove(0);
+        }
+    }
+
     #[inline]
     pub fn set_status<T: Into<Cow<'static, str>>>(&mut self, status: T) {
         let status = status.into();
@@ -998,7 +1586,36 @@ fn set_theme_impl(&mut self, theme: Theme, preview: ThemeAction) {
     /// Refreshes the language server for a given document
     pub fn refresh_language_server(&mut self, doc_id: DocumentId) -> Option<()> {
         let doc = self.documents.get_mut(&doc_id)?;
-        Self::launch_language_server(&mut self.language_servers, doc)
+        let old_ids = doc.language_server_ids();
+        let result = Self::launch_language_server(&mut self.language_servers, doc);
+        self.stop_unused_language_servers(&old_ids);
+        result
+    }
+
+    /// Shuts down the language servers of `ids` which no document uses any more, e.g. after
+    /// the language of their last document changed.
+    fn stop_unused_language_servers(&mut self, ids: &[usize]) {
+        for &id in ids {
+            let used = self
+                .documents
+                .values()
+                .any(|doc| doc.language_server_ids().contains(&id));
+            if used {
+                continue;
+            }
+            let language_server = self
+                .language_servers
+                .iter_clients()
+                .find(|client| client.id() == id)
+                .cloned();
+            if let Some(language_server) = language_server {
+                self.language_servers.remove_by_id(id);
+                for diagnostics in self.diagnostics.values_mut() {
+                    diagnostics.retain(|(_, server_id)| *server_id != Some(id));
+                }
+                tokio::spawn(async move { language_server.force_shutdown().await });
+            }
+        }
     }
 
     /// Launch a language server for a given document
@@ -1006,8 +1623,8 @@ fn launch_language_server(ls: &mut helix_lsp::Registry, doc: &mut Document) -> O
         // if doc doesn't have a URL it's a scratch buffer, ignore it
         let doc_url = doc.url()?;
 
-        // try to find a language server based on the language name
-        let language_server = doc.language.as_ref().and_then(|language| {
+        // try to find language servers based on the language name
+        let language_servers = doc.language.as_ref().map_or_else(Vec::new, |language| {
             ls.get(language, doc.path())
                 .map_err(|e| {
                     log::error!(
@@ -1016,29 +1633,41 @@ fn launch_language_server(ls: &mut helix_lsp::Registry, doc: &mut Document) -> O
                         e
                     )
                 })
-                .ok()
-                .flatten()
+                .unwrap_or_default()
         });
-        if let Some(language_server) = language_server {
-            // only spawn a new lang server if the servers aren't the same
-            if Some(language_server.id()) != doc.language_server().map(|server| server.id()) {
-                if let Some(language_server) = doc.language_server() {
-                    tokio::spawn(language_server.text_document_did_close(doc.identifier()));
-                }
 
-                let language_id = doc.language_id().map(ToOwned::to_owned).unwrap_or_default();
+        let ids: Vec<_> = language_servers.iter().map(|server| server.id()).collect();
+        let old_ids = doc.language_server_ids();
+        // only notify the servers if they aren't the same
+        if ids == old_ids {
+            return Some(());
+        }
 
-                // TODO: this now races with on_init code if the init happens too quickly
-                tokio::spawn(language_server.text_document_did_open(
-                    doc_url,
-                    doc.version(),
-                    doc.text(),
-                    language_id,
-                ));
+        // the servers of the previous language, all of them if the new one has none
+        for language_server in doc.language_servers() {
+            if !ids.contains(&language_server.id()) {
+                tokio::spawn(language_server.text_document_did_close(doc.identifier()));
+            }
+        }
+        for id in old_ids.iter().filter(|id| !ids.contains(id)) {
+            doc.replace_diagnostics(Vec::new(), *id);
+        }
 
-                doc.set_language_server(Some(language_server));
+        let language_id = doc.language_id().map(ToOwned::to_owned).unwrap_or_default();
+        for language_server in &language_servers {
+            if old_ids.contains(&language_server.id()) {
+                continue;
             }
+            // TODO: this now races with on_init code if the init happens too quickly
+            tokio::spawn(language_server.text_document_did_open(
+                doc_url.clone(),
+                doc.version(),
+                doc.text(),
+                language_id.clone(),
+            ));
         }
+
+        doc.set_language_servers(language_servers);
         Some(())
     }
 
@@ -1128,12 +1757,18 @@ pub fn switch(&mut self, id: DocumentId, action: Action) {
             }
             Action::HorizontalSplit | Action::VerticalSplit => {
                 // copy the current view, unless there is no view yet
-                let view = self
-                    .tree
-                    .try_get(self.tree.focus)
+                let focused = self.tree.try_get(self.tree.focus);
+                let view = focused
                     .filter(|v| id == v.doc) // Different Document
                     .cloned()
-                    .unwrap_or_else(|| View::new(id, self.config().gutters.clone()));
+                    .unwrap_or_else(|| {
+                        let mut view = View::new(id, self.config().gutters.clone());
+                        // splits showing another document still inherit the local options
+                        if let Some(focused) = focused {
+                            view.local_options = focused.local_options.clone();
+                        }
+                        view
+                    });
                 let view_id = self.tree.split(
                     view,
                     match action {
@@ -1198,10 +1833,17 @@ pub fn open(&mut self, path: &Path, action: Action) -> Result<DocumentId, Error>
             if let Some(diff_base) = self.diff_providers.get_diff_base(&path) {
                 doc.set_diff_base(diff_base, self.redraw_handle.clone());
             }
+            let diagnostics = crate::checker::document_diagnostics(&self.diagnostics, &doc, None);
+            doc.set_diagnostics(diagnostics);
+            doc.set_notes(self.notes.file(&path));
             self.new_document(doc)
         };
 
         self.switch(id, action);
+        // documents loaded without being shown are only previewed
+        if !matches!(action, Action::Load) {
+            self.record_recent_file(&path);
+        }
         Ok(id)
     }
 
@@ -1226,7 +1868,7 @@ pub fn close_document(&mut self, doc_id: DocumentId, force: bool) -> Result<(),
         // This will also disallow any follow-up writes
         self.saves.remove(&doc_id);
 
-        if let Some(language_server) = doc.language_server() {
+        for language_server in doc.language_servers() {
             // TODO: track error
             tokio::spawn(language_server.text_document_did_close(doc.identifier()));
         }
@@ -1268,6 +1910,7 @@ enum Action {
         }
 
         self.documents.remove(&doc_id);
+        self.symbol_tree_states.remove(&doc_id);
 
         // If the document we removed was visible in all views, we will have no more views. We don't
         // want to close the editor just for a simple buffer close, so we need to create a new view
@@ -1286,6 +1929,7 @@ enum Action {
         }
 
         self._refresh();
+        self.record_recent_workspace(None);
 
         Ok(())
     }
@@ -1322,6 +1966,60 @@ pub fn resize(&mut self, area: Rect) {
         };
     }
 
+    /// Shows `selection` of a document in the focused view without touching the jumplist. The
+    /// location shown before is pushed onto the peek stack.
+    pub fn peek(&mut self, doc_id: DocumentId, selection: Selection) {
+        if !self.documents.contains_key(&doc_id) {
+            return;
+        }
+        let (view, doc) = current_ref!(self);
+        self.peeks.push(Peek {
+            view_id: view.id,
+            doc_id: doc.id,
+            selection: doc.selection(view.id).clone(),
+        });
+        let view_id = view.id;
+        if doc.id != doc_id {
+            self.replace_document_in_view(view_id, doc_id);
+        }
+        let view = self.tree.get_mut(view_id);
+        let doc = doc_mut!(self, &doc_id);
+        doc.set_selection(view_id, selection);
+        align_view(doc, view, Align::Center);
+    }
+
+    /// Returns to the location shown before the last peek. Returns `false` if there was nothing
+    /// to return to.
+    pub fn pop_peek(&mut self) -> bool {
+        while let Some(peek) = self.peeks.pop() {
+            // the view or document may have been closed since
+            if !self.tree.contains(peek.view_id) || !self.documents.contains_key(&peek.doc_id) {
+                continue;
+            }
+            self.focus(peek.view_id);
+            if view!(self).doc != peek.doc_id {
+                self.replace_document_in_view(peek.view_id, peek.doc_id);
+            }
+            let view = self.tree.get_mut(peek.view_id);
+            let doc = doc_mut!(self, &peek.doc_id);
+            doc.set_selection(peek.view_id, peek.selection);
+            align_view(doc, view, Align::Center);
+            return true;
+        }
+        false
+    }
+
+    /// Pushes the locations left by peeks onto the jumplists of their views, oldest first, and
+    /// clears the peek stack.
+    pub fn confirm_peeks(&mut self) {
+        for peek in std::mem::take(&mut self.peeks) {
+            if self.tree.contains(peek.view_id) && self.documents.contains_key(&peek.doc_id) {
+                let view = self.tree.get_mut(peek.view_id);
+                view.jumps.push((peek.doc_id, peek.selection));
+            }
+        }
+    }
+
     pub fn focus(&mut self, view_id: ViewId) {
         let prev_id = std::mem::replace(&mut self.tree.focus, view_id);
 
@@ -1329,6 +2027,9 @@ pub fn focus(&mut self, view_id: ViewId) {
         // within view
         if prev_id != view_id {
             self.mode = Mode::Normal;
+            if self.tree.is_zoomed() {
+                self.tree.recalculate();
+            }
             self.ensure_cursor_in_view(view_id);
 
             // Update jumplist selections with new document changes.
@@ -1352,12 +2053,89 @@ pub fn focus_direction(&mut self, direction: tree::Direction) {
 
     pub fn swap_split_in_direction(&mut self, direction: tree::Direction) {
         self.tree.swap_split_in_direction(direction);
+        if self.tree.is_zoomed() {
+            // the swap exchanged the areas of the views, give the zoomed view the whole area again
+            self.tree.recalculate();
+        }
     }
 
     pub fn transpose_view(&mut self) {
         self.tree.transpose();
     }
 
+    pub fn rotate_splits(&mut self, reverse: bool) {
+        self.tree.rotate(reverse);
+    }
+
+    pub fn toggle_zoom(&mut self) {
+        self.tree.toggle_zoom();
+        self.ensure_cursor_in_view(self.tree.focus);
+    }
+
+    /// Toggles zen mode: the focused view is zoomed and shown without gutters. Leaving zen mode
+    /// restores the gutters and the zoom of before.
+    pub fn toggle_zen(&mut self) {
+        match self.zen.take() {
+            Some(state) => {
+                if self.tree.contains(state.view) {
+                    self.tree.get_mut(state.view).set_gutters(state.gutters);
+                }
+                if !state.zoomed && self.tree.is_zoomed() {
+                    self.tree.toggle_zoom();
+                }
+            }
+            None => {
+                let zoomed = self.tree.is_zoomed();
+                if !zoomed {
+                    self.tree.toggle_zoom();
+                }
+                let view = self.tree.get_mut(self.tree.focus);
+                let gutters = view.set_gutters(Vec::new());
+                self.zen = Some(ZenState {
+                    view: view.id,
+                    gutters,
+                    zoomed,
+                });
+            }
+        }
+        self.ensure_cursor_in_view(self.tree.focus);
+    }
+
+    pub fn is_zen(&self) -> bool {
+        self.zen.is_some()
+    }
+
+    /// Toggles scroll binding of the focused view, keeping its current distance to other bound
+    /// views. Returns whether the view is bound now.
+    pub fn toggle_scroll_bind(&mut self) -> bool {
+        let view = self.tree.get_mut(self.tree.focus);
+        view.scroll_bind = match view.scroll_bind {
+            Some(_) => None,
+            None => Some(view.offset.row),
+        };
+        view.scroll_bind.is_some()
+    }
+
+    /// Scrolls the views bound with `:scrollbind` along with the focused view.
+    pub fn sync_scroll_binds(&mut self) {
+        let focused = self.tree.get(self.tree.focus);
+        let (focus, row, anchor) = match focused.scroll_bind {
+            Some(anchor) => (focused.id, focused.offset.row, anchor),
+            None => return,
+        };
+        for (view, _) in self.tree.views_mut() {
+            let other_anchor = match view.scroll_bind {
+                Some(other_anchor) if view.id != focus => other_anchor,
+                _ => continue,
+            };
+            let last_line = self.documents[&view.doc]
+                .text()
+                .len_lines()
+                .saturating_sub(1);
+            view.offset.row = (row + other_anchor).saturating_sub(anchor).min(last_line);
+        }
+    }
+
     pub fn should_close(&self) -> bool {
         self.tree.is_empty()
     }
@@ -32,6 +32,7 @@ pub fn style<'doc>(
             GutterType::LineNumbers => line_numbers(editor, doc, view, theme, is_focused),
             GutterType::Spacer => padding(editor, doc, view, theme, is_focused),
             GutterType::Diff => diff(editor, doc, view, theme, is_focused),
+            GutterType::Coverage => coverage(editor, doc, view, theme, is_focused),
         }
     }
 
@@ -41,6 +42,7 @@ pub fn width(self, _view: &View, doc: &Document) -> usize {
             GutterType::LineNumbers => line_numbers_width(_view, doc),
             GutterType::Spacer => 1,
             GutterType::Diff => 1,
+            GutterType::Coverage => 1,
         }
     }
 }
@@ -132,6 +134,38 @@ pub fn diff<'doc>(
     }
 }
 
+pub fn coverage<'doc>(
+    editor: &'doc Editor,
+    doc: &'doc Document,
+    _view: &View,
+    theme: &Theme,
+    _is_focused: bool,
+) -> GutterFn<'doc> {
+    let covered = theme
+        .try_get("coverage.covered")
+        .unwrap_or_else(|| theme.get("diff.plus"));
+    let uncovered = theme
+        .try_get("coverage.uncovered")
+        .unwrap_or_else(|| theme.get("diff.minus"));
+
+    let file = editor
+        .coverage
+        .as_ref()
+        .zip(doc.path())
+        .and_then(|(coverage, path)| coverage.file(path));
+    match file {
+        Some(file) => Box::new(move |line: usize, _selected: bool, out: &mut String| {
+            let style = match file.hits(line)? {
+                0 => uncovered,
+                _ => covered,
+            };
+            write!(out, "▎").unwrap();
+            Some(style)
+        }),
+        None => Box::new(move |_, _, _| None),
+    }
+}
+
 pub fn line_numbers<'doc>(
     editor: &'doc Editor,
     doc: &'doc Document,
@@ -154,7 +188,10 @@ pub fn line_numbers<'doc>(
         .text()
         .char_to_line(doc.selection(view.id).primary().cursor(text));
 
-    let line_number = editor.config().line_number;
+    let line_number = view
+        .local_options
+        .line_number
+        .unwrap_or(editor.config().line_number);
     let mode = editor.mode;
 
     Box::new(move |line: usize, selected: bool, out: &mut String| {
@@ -268,6 +305,32 @@ pub fn breakpoints<'doc>(
     })
 }
 
+pub fn notes<'doc>(
+    _editor: &'doc Editor,
+    doc: &'doc Document,
+    _view: &View,
+    theme: &Theme,
+    _is_focused: bool,
+) -> GutterFn<'doc> {
+    let style = theme
+        .try_get("ui.gutter.note")
+        .unwrap_or_else(|| theme.get("hint"));
+    let text = doc.text();
+    let lines: Vec<_> = doc
+        .notes()
+        .iter()
+        .map(|note| text.char_to_line(note.pos))
+        .collect();
+
+    Box::new(move |line: usize, _selected: bool, out: &mut String| {
+        if !lines.contains(&line) {
+            return None;
+        }
+        write!(out, "✎").unwrap();
+        Some(style)
+    })
+}
+
 pub fn diagnostics_or_breakpoints<'doc>(
     editor: &'doc Editor,
     doc: &'doc Document,
@@ -277,8 +340,11 @@ pub fn diagnostics_or_breakpoints<'doc>(
 ) -> GutterFn<'doc> {
     let mut diagnostics = diagnostic(editor, doc, view, theme, is_focused);
     let mut breakpoints = breakpoints(editor, doc, view, theme, is_focused);
+    let mut notes = notes(editor, doc, view, theme, is_focused);
 
     Box::new(move |line, selected, out| {
-        breakpoints(line, selected, out).or_else(|| diagnostics(line, selected, out))
+        breakpoints(line, selected, out)
+            .or_else(|| diagnostics(line, selected, out))
+            .or_else(|| notes(line, selected, out))
     })
 }
@@ -1,9 +1,10 @@
-use crate::editor::{Action, Breakpoint};
+use crate::editor::{Action, Breakpoint, DataBreakpoint, Watch, WatchValue};
 use crate::{align_view, Align, Editor};
 use helix_core::Selection;
 use helix_dap::{self as dap, Client, Payload, Request, ThreadId};
 use helix_lsp::block_on;
 use log::warn;
+use std::collections::BTreeMap;
 use std::fmt::Write;
 use std::path::PathBuf;
 
@@ -86,44 +87,63 @@ pub fn breakpoints_changed(
     path: PathBuf,
     breakpoints: &mut [Breakpoint],
 ) -> Result<(), anyhow::Error> {
-    // TODO: handle capabilities correctly again, by filtering breakpoints when emitting
-    // if breakpoint.condition.is_some()
-    //     && !debugger
-    //         .caps
-    //         .as_ref()
-    //         .unwrap()
-    //         .supports_conditional_breakpoints
-    //         .unwrap_or_default()
-    // {
-    //     bail!(
-    //         "Can't edit breakpoint: debugger does not support conditional breakpoints"
-    //     )
-    // }
-    // if breakpoint.log_message.is_some()
-    //     && !debugger
-    //         .caps
-    //         .as_ref()
-    //         .unwrap()
-    //         .supports_log_points
-    //         .unwrap_or_default()
-    // {
-    //     bail!("Can't edit breakpoint: debugger does not support logpoints")
-    // }
+    // Fields the debugger doesn't support are left out of the request instead of failing it, so
+    // the rest of the file's breakpoints are still set.
+    let caps = debugger.capabilities();
+    let supports_conditions = caps.supports_conditional_breakpoints.unwrap_or_default();
+    let supports_hit_conditions = caps
+        .supports_hit_conditional_breakpoints
+        .unwrap_or_default();
+    let supports_log_points = caps.supports_log_points.unwrap_or_default();
+
+    let mut unsupported = Vec::new();
+    let mut ignored = vec![false; breakpoints.len()];
     let source_breakpoints = breakpoints
         .iter()
-        .map(|breakpoint| helix_dap::SourceBreakpoint {
-            line: breakpoint.line + 1, // convert from 0-indexing to 1-indexing (TODO: could set debugger to 0-indexing on init)
-            ..Default::default()
+        .zip(&mut ignored)
+        .map(|(breakpoint, ignored)| {
+            let mut field = |supported: bool, value: &Option<String>, name: &'static str| {
+                if supported || value.is_none() {
+                    return value.clone();
+                }
+                *ignored = true;
+                if !unsupported.contains(&name) {
+                    unsupported.push(name);
+                }
+                None
+            };
+            helix_dap::SourceBreakpoint {
+                line: breakpoint.line + 1, // convert from 0-indexing to 1-indexing (TODO: could set debugger to 0-indexing on init)
+                column: None,
+                condition: field(supports_conditions, &breakpoint.condition, "conditions"),
+                hit_condition: field(
+                    supports_hit_conditions,
+                    &breakpoint.hit_condition,
+                    "hit conditions",
+                ),
+                log_message: field(supports_log_points, &breakpoint.log_message, "log messages"),
+            }
         })
         .collect::<Vec<_>>();
+    if !unsupported.is_empty() {
+        warn!(
+            "Debugger does not support breakpoint {}, ignoring them in {}",
+            unsupported.join(", "),
+            path.display()
+        );
+    }
 
     let request = debugger.set_breakpoints(path, source_breakpoints);
     match block_on(request) {
         Ok(Some(dap_breakpoints)) => {
-            for (breakpoint, dap_breakpoint) in breakpoints.iter_mut().zip(dap_breakpoints) {
+            for ((breakpoint, dap_breakpoint), ignored) in
+                breakpoints.iter_mut().zip(dap_breakpoints).zip(ignored)
+            {
                 breakpoint.id = dap_breakpoint.id;
                 breakpoint.verified = dap_breakpoint.verified;
-                breakpoint.message = dap_breakpoint.message;
+                breakpoint.message = dap_breakpoint
+                    .message
+                    .or_else(|| ignored.then(|| "Debugger ignores unsupported fields".to_string()));
                 // TODO: handle breakpoint.message
                 // TODO: verify source matches
                 breakpoint.line = dap_breakpoint.line.unwrap_or(0).saturating_sub(1); // convert to 0-indexing
@@ -138,6 +158,107 @@ pub fn breakpoints_changed(
     Ok(())
 }
 
+pub fn data_breakpoints_changed(
+    debugger: &mut dap::Client,
+    breakpoints: &mut [DataBreakpoint],
+) -> Result<(), anyhow::Error> {
+    if !debugger
+        .capabilities()
+        .supports_data_breakpoints
+        .unwrap_or_default()
+    {
+        anyhow::bail!("Can't set data breakpoints: debugger does not support data breakpoints")
+    }
+
+    let data_breakpoints = breakpoints
+        .iter()
+        .map(|breakpoint| helix_dap::DataBreakpoint {
+            data_id: breakpoint.data_id.clone(),
+            access_type: breakpoint.access_type,
+            condition: breakpoint.condition.clone(),
+            hit_condition: breakpoint.hit_condition.clone(),
+        })
+        .collect::<Vec<_>>();
+
+    let request = debugger.set_data_breakpoints(data_breakpoints);
+    match block_on(request) {
+        Ok(dap_breakpoints) => {
+            for (breakpoint, dap_breakpoint) in breakpoints.iter_mut().zip(dap_breakpoints) {
+                breakpoint.id = dap_breakpoint.id;
+                breakpoint.verified = dap_breakpoint.verified;
+                breakpoint.message = dap_breakpoint.message;
+            }
+        }
+        Err(e) => anyhow::bail!("Failed to set data breakpoints: {}", e),
+    };
+    Ok(())
+}
+
+/// Evaluates all watch expressions in the context of the current stack frame.
+pub async fn evaluate_watches(debugger: &Client, watches: &mut [Watch]) {
+    let frame_id = debugger.current_stack_frame().map(|frame| frame.id);
+    for watch in watches {
+        let result = debugger.eval(watch.expression.clone(), frame_id).await;
+        watch.result = Some(
+            result
+                .map(|response| WatchValue {
+                    value: response.result,
+                    ty: response.ty,
+                    variables_reference: response.variables_reference,
+                    memory_reference: response.memory_reference,
+                })
+                .map_err(|e| e.to_string()),
+        );
+    }
+}
+
+fn watches_file() -> PathBuf {
+    helix_loader::cache_dir().join("watches.toml")
+}
+
+/// Key of the current project in the watches file.
+fn watches_project() -> String {
+    helix_core::find_root(None, &[])
+        .to_string_lossy()
+        .into_owned()
+}
+
+fn read_watches_file() -> BTreeMap<String, Vec<String>> {
+    std::fs::read_to_string(watches_file())
+        .ok()
+        .and_then(|contents| toml::from_str(&contents).ok())
+        .unwrap_or_default()
+}
+
+/// Loads the watch expressions persisted for the current project.
+pub fn load_watches() -> Vec<Watch> {
+    read_watches_file()
+        .remove(&watches_project())
+        .unwrap_or_default()
+        .into_iter()
+        .map(Watch::new)
+        .collect()
+}
+
+/// Persists the watch expressions of the current project, keeping those of other projects.
+pub fn save_watches(watches: &[Watch]) -> Result<(), anyhow::Error> {
+    let mut projects = read_watches_file();
+    let project = watches_project();
+    if watches.is_empty() {
+        projects.remove(&project);
+    } else {
+        let expressions = watches.iter().map(|watch| watch.expression.clone());
+        projects.insert(project, expressions.collect());
+    }
+
+    let path = watches_file();
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+    std::fs::write(path, toml::to_string(&projects)?)?;
+    Ok(())
+}
+
 impl Editor {
     pub async fn handle_debugger_message(&mut self, payload: helix_dap::Payload) -> bool {
         use dap::requests::RunInTerminal;
@@ -158,6 +279,7 @@ pub async fn handle_debugger_message(&mut self, payload: helix_dap::Payload) ->
                     ..
                 }) => {
                     let all_threads_stopped = all_threads_stopped.unwrap_or_default();
+                    debugger.stops += 1;
 
                     if all_threads_stopped {
                         if let Ok(response) = debugger.request::<dap::requests::Threads>(()).await {
@@ -173,6 +295,10 @@ pub async fn handle_debugger_message(&mut self, payload: helix_dap::Payload) ->
                         select_thread_id(self, thread_id, false).await;
                     }
 
+                    if let Some(debugger) = self.debugger.as_ref() {
+                        evaluate_watches(debugger, &mut self.watches).await;
+                    }
+
                     let scope = match thread_id {
                         Some(id) => format!("Thread {}", id),
                         None => "Target".to_owned(),
@@ -231,6 +357,14 @@ pub async fn handle_debugger_message(&mut self, payload: helix_dap::Payload) ->
                                     breakpoints[i].column = breakpoint.column;
                                 }
                             }
+                            if let Some(data_breakpoint) = self
+                                .data_breakpoints
+                                .iter_mut()
+                                .find(|b| b.id.is_some() && b.id == breakpoint.id)
+                            {
+                                data_breakpoint.verified = breakpoint.verified;
+                                data_breakpoint.message = breakpoint.message.clone();
+                            }
                         }
                         "removed" => {
                             for breakpoints in self.breakpoints.values_mut() {
@@ -240,6 +374,8 @@ pub async fn handle_debugger_message(&mut self, payload: helix_dap::Payload) ->
                                     breakpoints.remove(i);
                                 }
                             }
+                            self.data_breakpoints
+                                .retain(|b| b.id.is_none() || b.id != breakpoint.id);
                         }
                         reason => {
                             warn!("Unknown breakpoint event: {}", reason);
@@ -249,9 +385,9 @@ pub async fn handle_debugger_message(&mut self, payload: helix_dap::Payload) ->
                 Event::Output(events::Output {
                     category, output, ..
                 }) => {
-                    let prefix = match category {
+                    let prefix = match &category {
                         Some(category) => {
-                            if &category == "telemetry" {
+                            if category == "telemetry" {
                                 return false;
                             }
                             format!("Debug ({}):", category)
@@ -261,12 +397,18 @@ pub async fn handle_debugger_message(&mut self, payload: helix_dap::Payload) ->
 
                     log::info!("{}", output);
                     self.set_status(format!("{} {}", prefix, output));
+                    self.debug_output.push((category, output));
                 }
                 Event::Initialized(_) => {
                     // send existing breakpoints
                     for (path, breakpoints) in &mut self.breakpoints {
                         // TODO: call futures in parallel, await all
-                        let _ = breakpoints_changed(debugger, path.clone(), breakpoints);
+                        if let Err(e) = breakpoints_changed(debugger, path.clone(), breakpoints) {
+                            warn!("{}", e);
+                        }
+                    }
+                    if !self.data_breakpoints.is_empty() {
+                        let _ = data_breakpoints_changed(debugger, &mut self.data_breakpoints);
                     }
                     // TODO: fetch breakpoints (in case we're attaching)
 
@@ -1,7 +1,9 @@
 #[macro_use]
 pub mod macros;
 
+pub mod checker;
 pub mod clipboard;
+pub mod coverage;
 pub mod document;
 pub mod editor;
 pub mod env;
@@ -15,9 +17,14 @@ pub mod handlers {
 pub mod info;
 pub mod input;
 pub mod keyboard;
+pub mod notes;
+pub mod pins;
+pub mod recent;
 pub mod theme;
 pub mod tree;
+pub mod tree_keymap;
 pub mod view;
+pub mod yank_history;
 
 use std::num::NonZeroUsize;
 
@@ -0,0 +1,168 @@
+//! Free-text notes attached to lines of files. The notes of a project are persisted in
+//! `.helix/notes/notes.toml` below its root, while the notes of open documents move with edits
+//! and are persisted when the document is written.
+
+use crate::Editor;
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::BTreeMap,
+    path::{Path, PathBuf},
+};
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Note {
+    /// Zero-based line of the note.
+    pub line: usize,
+    pub text: String,
+}
+
+/// A note of an open document, anchored to the start of its line when it was attached.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DocumentNote {
+    pub pos: usize,
+    pub text: String,
+}
+
+/// The notes of the files of a project.
+#[derive(Debug, Default)]
+pub struct Notes {
+    root: PathBuf,
+    /// Notes by path relative to the root, sorted by line.
+    files: BTreeMap<PathBuf, Vec<Note>>,
+}
+
+impl Notes {
+    fn file_path(root: &Path) -> PathBuf {
+        root.join(".helix").join("notes").join("notes.toml")
+    }
+
+    /// Loads the notes of the current project.
+    pub fn load() -> Self {
+        let root = helix_core::find_root(None, &[]);
+        let files: BTreeMap<String, Vec<Note>> = std::fs::read_to_string(Self::file_path(&root))
+            .ok()
+            .and_then(|contents| toml::from_str(&contents).ok())
+            .unwrap_or_default();
+        Self {
+            files: files
+                .into_iter()
+                .map(|(path, notes)| (PathBuf::from(path), notes))
+                .collect(),
+            root,
+        }
+    }
+
+    /// Writes the notes of all files, removing the notes file once there are none.
+    pub fn save(&self) -> anyhow::Result<()> {
+        let path = Self::file_path(&self.root);
+        if self.files.is_empty() {
+            if path.exists() {
+                std::fs::remove_file(path)?;
+            }
+            return Ok(());
+        }
+        let files: BTreeMap<_, _> = self
+            .files
+            .iter()
+            .map(|(path, notes)| (path.to_string_lossy(), notes))
+            .collect();
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        std::fs::write(path, toml::to_string(&files)?)?;
+        Ok(())
+    }
+
+    fn relative<'a>(&self, path: &'a Path) -> &'a Path {
+        path.strip_prefix(&self.root).unwrap_or(path)
+    }
+
+    pub fn file(&self, path: &Path) -> &[Note] {
+        self.files
+            .get(self.relative(path))
+            .map_or(&[], |notes| notes.as_slice())
+    }
+
+    pub fn set_file(&mut self, path: &Path, mut notes: Vec<Note>) {
+        let path = self.relative(path).to_path_buf();
+        if notes.is_empty() {
+            self.files.remove(&path);
+        } else {
+            notes.sort_by_key(|note| note.line);
+            self.files.insert(path, notes);
+        }
+    }
+
+    /// The notes of each file by absolute path.
+    pub fn iter(&self) -> impl Iterator<Item = (PathBuf, &[Note])> {
+        self.files
+            .iter()
+            .map(|(path, notes)| (self.root.join(path), notes.as_slice()))
+    }
+}
+
+impl Editor {
+    /// Persists the notes of an open document with their positions after its edits.
+    pub fn persist_notes(&mut self, doc_id: crate::DocumentId) -> anyhow::Result<()> {
+        let (path, notes) = match self
+            .document(doc_id)
+            .and_then(|doc| Some((doc.path()?.clone(), doc.note_lines())))
+        {
+            Some(found) => found,
+            None => return Ok(()),
+        };
+        if notes.as_slice() == self.notes.file(&path) {
+            return Ok(());
+        }
+        self.notes.set_file(&path, notes);
+        self.notes.save()
+    }
+
+    /// Attaches a note to a line of a file, replacing its note, or removes the note with
+    /// `None`, and persists the notes.
+    pub fn set_note(
+        &mut self,
+        path: &Path,
+        line: usize,
+        text: Option<String>,
+    ) -> anyhow::Result<()> {
+        let doc = self
+            .documents
+            .values_mut()
+            .find(|doc| doc.path().map(|p| p.as_path()) == Some(path));
+        let mut notes = match &doc {
+            Some(doc) => doc.note_lines(),
+            None => self.notes.file(path).to_vec(),
+        };
+        notes.retain(|note| note.line != line);
+        if let Some(text) = text {
+            notes.push(Note { line, text });
+        }
+        if let Some(doc) = doc {
+            doc.set_notes(&notes);
+        }
+        self.notes.set_file(path, notes);
+        self.notes.save()
+    }
+
+    /// The notes of all files of the project, including those of open documents which moved
+    /// with unsaved edits.
+    pub fn project_notes(&self) -> BTreeMap<PathBuf, Vec<Note>> {
+        let mut files: BTreeMap<_, _> = self
+            .notes
+            .iter()
+            .map(|(path, notes)| (path, notes.to_vec()))
+            .collect();
+        for doc in self.documents() {
+            if let Some(path) = doc.path() {
+                let notes = doc.note_lines();
+                if notes.is_empty() {
+                    files.remove(path);
+                } else {
+                    files.insert(path.clone(), notes);
+                }
+            }
+        }
+        files
+    }
+}
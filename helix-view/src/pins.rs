@@ -0,0 +1,131 @@
+//! Files pinned to numbered slots, to switch between them with a single key. The pins of each
+//! project are persisted in `pins.toml` in the cache directory.
+
+use crate::Editor;
+use std::{
+    collections::BTreeMap,
+    path::{Path, PathBuf},
+};
+
+/// The number of slots, reachable with `Alt-1` to `Alt-9`.
+pub const SLOTS: usize = 9;
+
+/// The pinned files of the current project, the file of the first slot first.
+#[derive(Debug, Default)]
+pub struct Pins {
+    files: Vec<PathBuf>,
+}
+
+impl Pins {
+    fn file() -> PathBuf {
+        helix_loader::cache_dir().join("pins.toml")
+    }
+
+    /// Key of the current project in the pins file.
+    fn project() -> String {
+        helix_core::find_root(None, &[])
+            .to_string_lossy()
+            .into_owned()
+    }
+
+    fn read_file() -> BTreeMap<String, Vec<PathBuf>> {
+        std::fs::read_to_string(Self::file())
+            .ok()
+            .and_then(|contents| toml::from_str(&contents).ok())
+            .unwrap_or_default()
+    }
+
+    /// Loads the pins of the current project.
+    pub fn load() -> Self {
+        Self {
+            files: Self::read_file()
+                .remove(&Self::project())
+                .unwrap_or_default(),
+        }
+    }
+
+    /// Persists the pins of the current project, keeping those of other projects.
+    pub fn save(&self) -> anyhow::Result<()> {
+        let mut projects = Self::read_file();
+        let project = Self::project();
+        if self.files.is_empty() {
+            projects.remove(&project);
+        } else {
+            projects.insert(project, self.files.clone());
+        }
+        let path = Self::file();
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        std::fs::write(path, toml::to_string(&projects)?)?;
+        Ok(())
+    }
+
+    pub fn files(&self) -> &[PathBuf] {
+        &self.files
+    }
+
+    /// The file pinned to the zero-based `slot`.
+    pub fn get(&self, slot: usize) -> Option<&Path> {
+        self.files.get(slot).map(PathBuf::as_path)
+    }
+
+    /// Pins `path` to the first free slot and returns the slot, or the slot it is pinned to
+    /// already. Returns `None` if all slots are taken.
+    pub fn pin(&mut self, path: &Path) -> Option<usize> {
+        if let Some(slot) = self.files.iter().position(|file| file == path) {
+            return Some(slot);
+        }
+        if self.files.len() >= SLOTS {
+            return None;
+        }
+        self.files.push(path.to_path_buf());
+        Some(self.files.len() - 1)
+    }
+
+    /// Removes the pin of `slot`, moving the files of the following slots up.
+    pub fn unpin(&mut self, slot: usize) {
+        if slot < self.files.len() {
+            self.files.remove(slot);
+        }
+    }
+
+    /// Exchanges the files of two slots.
+    pub fn swap(&mut self, a: usize, b: usize) {
+        if a < self.files.len() && b < self.files.len() {
+            self.files.swap(a, b);
+        }
+    }
+}
+
+impl Editor {
+    pub fn save_pins(&self) {
+        if let Err(err) = self.pins.save() {
+            log::error!("failed to save the pinned files: {}", err);
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn pin_slots() {
+        let mut pins = Pins::default();
+        assert_eq!(pins.pin(Path::new("a")), Some(0));
+        assert_eq!(pins.pin(Path::new("b")), Some(1));
+        assert_eq!(pins.pin(Path::new("a")), Some(0));
+
+        pins.swap(0, 1);
+        assert_eq!(pins.get(0), Some(Path::new("b")));
+        pins.unpin(0);
+        assert_eq!(pins.files(), [PathBuf::from("a")]);
+
+        for i in 0..SLOTS {
+            pins.pin(Path::new(&i.to_string()));
+        }
+        assert_eq!(pins.files().len(), SLOTS);
+        assert_eq!(pins.pin(Path::new("c")), None);
+    }
+}
@@ -0,0 +1,126 @@
+//! Recently opened files and workspaces, newest first, kept across sessions.
+
+use crate::Editor;
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::VecDeque,
+    path::{Path, PathBuf},
+};
+
+/// How many files and workspaces are remembered.
+const LIMIT: usize = 100;
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Workspace {
+    pub root: PathBuf,
+    /// The files of the workspace that were open when it was last used, the one opened last
+    /// first.
+    #[serde(default)]
+    pub files: Vec<PathBuf>,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct Recent {
+    #[serde(default)]
+    files: VecDeque<PathBuf>,
+    #[serde(default)]
+    workspaces: VecDeque<Workspace>,
+}
+
+impl Recent {
+    fn file() -> PathBuf {
+        helix_loader::cache_dir().join("recent.toml")
+    }
+
+    /// Loads the files and workspaces persisted by earlier sessions.
+    pub fn load() -> Self {
+        std::fs::read_to_string(Self::file())
+            .ok()
+            .and_then(|contents| toml::from_str(&contents).ok())
+            .unwrap_or_default()
+    }
+
+    pub fn save(&self) -> anyhow::Result<()> {
+        let path = Self::file();
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        std::fs::write(path, toml::to_string(self)?)?;
+        Ok(())
+    }
+
+    pub fn files(&self) -> &VecDeque<PathBuf> {
+        &self.files
+    }
+
+    pub fn workspaces(&self) -> &VecDeque<Workspace> {
+        &self.workspaces
+    }
+
+    /// Moves `path` to the front, evicting the oldest files beyond the limit.
+    pub fn push_file(&mut self, path: &Path) {
+        self.files.retain(|file| file != path);
+        self.files.push_front(path.to_path_buf());
+        self.files.truncate(LIMIT);
+    }
+
+    /// Moves the workspace at `root` to the front with `files` as the files open in it,
+    /// evicting the oldest workspaces beyond the limit.
+    pub fn push_workspace(&mut self, root: &Path, files: Vec<PathBuf>) {
+        self.workspaces.retain(|workspace| workspace.root != root);
+        self.workspaces.push_front(Workspace {
+            root: root.to_path_buf(),
+            files,
+        });
+        self.workspaces.truncate(LIMIT);
+    }
+}
+
+impl Editor {
+    /// Records an opened file and the files open in the current workspace.
+    pub fn record_recent_file(&mut self, path: &Path) {
+        self.recent.push_file(path);
+        self.record_recent_workspace(Some(path));
+    }
+
+    /// Records the files open in the current workspace, starting with `first`.
+    pub fn record_recent_workspace(&mut self, first: Option<&Path>) {
+        let root = helix_core::find_root(None, &[]);
+        let mut files: Vec<PathBuf> = first.map(Path::to_path_buf).into_iter().collect();
+        files.extend(
+            self.documents()
+                .filter_map(|doc| doc.path())
+                .filter(|path| path.starts_with(&root) && Some(path.as_path()) != first)
+                .cloned(),
+        );
+        self.recent.push_workspace(&root, files);
+        if let Err(err) = self.recent.save() {
+            log::error!("failed to save the recent files: {}", err);
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn push_moves_to_front() {
+        let mut recent = Recent::default();
+        for file in ["a", "b", "a"] {
+            recent.push_file(Path::new(file));
+        }
+        let files: Vec<_> = recent
+            .files()
+            .iter()
+            .map(|path| path.to_str().unwrap())
+            .collect();
+        assert_eq!(files, ["a", "b"]);
+
+        recent.push_workspace(Path::new("/x"), vec![]);
+        recent.push_workspace(Path::new("/y"), vec![]);
+        recent.push_workspace(Path::new("/x"), vec![PathBuf::from("/x/a")]);
+        assert_eq!(recent.workspaces().len(), 2);
+        assert_eq!(recent.workspaces()[0].files, [PathBuf::from("/x/a")]);
+    }
+}
@@ -25,10 +25,96 @@
         .expect("Failed to parse base 16 default theme")
 });
 
+/// The scopes of the `adaptive` theme, styled with a palette derived from the background and
+/// foreground colors of the terminal. `mix-N` is the background mixed with N percent of the
+/// foreground, and the accents are the ANSI colors of the terminal.
+const ADAPTIVE_THEME: &str = r#"
+"ui.background" = { bg = "bg" }
+"ui.background.separator" = "mix-25"
+"ui.text" = "fg"
+"ui.text.focus" = { fg = "fg", modifiers = ["bold"] }
+"ui.text.info" = "mix-60"
+"ui.text.directory" = "blue"
+"ui.cursor" = { fg = "bg", bg = "mix-60" }
+"ui.cursor.primary" = { fg = "bg", bg = "fg" }
+"ui.cursor.match" = { bg = "mix-25", modifiers = ["bold"] }
+"ui.selection" = { bg = "mix-15" }
+"ui.selection.primary" = { bg = "mix-25" }
+"ui.highlight" = { bg = "mix-15" }
+"ui.cursorline.primary" = { bg = "mix-5" }
+"ui.cursorcolumn.primary" = { bg = "mix-5" }
+"ui.linenr" = "mix-35"
+"ui.linenr.selected" = "fg"
+"ui.statusline" = { fg = "fg", bg = "mix-15" }
+"ui.statusline.inactive" = { fg = "mix-60", bg = "mix-5" }
+"ui.statusline.normal" = { fg = "bg", bg = "blue" }
+"ui.statusline.insert" = { fg = "bg", bg = "green" }
+"ui.statusline.select" = { fg = "bg", bg = "magenta" }
+"ui.bufferline" = { fg = "mix-60", bg = "mix-5" }
+"ui.bufferline.active" = { fg = "fg", bg = "mix-15" }
+"ui.bufferline.background" = { bg = "mix-5" }
+"ui.popup" = { bg = "mix-5" }
+"ui.window" = "mix-25"
+"ui.help" = { fg = "fg", bg = "mix-5" }
+"ui.menu" = { fg = "fg", bg = "mix-5" }
+"ui.menu.selected" = { bg = "mix-25", modifiers = ["bold"] }
+"ui.menu.scroll" = { fg = "mix-35", bg = "mix-10" }
+"ui.search.match" = { bg = "mix-25", modifiers = ["underlined"] }
+"ui.virtual.whitespace" = "mix-25"
+"ui.virtual.indent-guide" = "mix-15"
+"ui.virtual.ruler" = { bg = "mix-5" }
+"ui.tree.guides" = "mix-25"
+"ui.tree.sticky" = { bg = "mix-10" }
+"ui.explorer.dir" = "blue"
+"ui.explorer.file" = "fg"
+"ui.explorer.focus" = { bg = "mix-25" }
+"ui.explorer.unfocus" = { bg = "mix-10" }
+"ui.minimap" = "mix-35"
+"ui.minimap.viewport" = { bg = "mix-15" }
+
+"comment" = { fg = "mix-50", modifiers = ["italic"] }
+"keyword" = "magenta"
+"function" = "blue"
+"type" = "yellow"
+"constructor" = "yellow"
+"namespace" = "yellow"
+"constant" = "cyan"
+"string" = "green"
+"attribute" = "yellow"
+"label" = "magenta"
+"tag" = "blue"
+"special" = "cyan"
+"variable" = "fg"
+"punctuation" = "mix-60"
+"markup.heading" = { fg = "blue", modifiers = ["bold"] }
+"markup.bold" = { modifiers = ["bold"] }
+"markup.italic" = { modifiers = ["italic"] }
+"markup.link.url" = { fg = "cyan", modifiers = ["underlined"] }
+"markup.raw" = "green"
+"diff.plus" = "green"
+"diff.minus" = "red"
+"diff.delta" = "yellow"
+
+"error" = "red"
+"warning" = "yellow"
+"info" = "blue"
+"hint" = "cyan"
+"diagnostic" = { modifiers = ["underlined"] }
+"#;
+
+/// The background and foreground colors reported by the terminal, from which the `adaptive`
+/// theme is derived.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TerminalColors {
+    pub background: (u8, u8, u8),
+    pub foreground: (u8, u8, u8),
+}
+
 #[derive(Clone, Debug)]
 pub struct Loader {
     user_dir: PathBuf,
     default_dir: PathBuf,
+    terminal_colors: Option<TerminalColors>,
 }
 impl Loader {
     /// Creates a new loader that can load themes from two directories.
@@ -36,9 +122,21 @@ pub fn new<P: AsRef<Path>>(user_dir: P, default_dir: P) -> Self {
         Self {
             user_dir: user_dir.as_ref().join("themes"),
             default_dir: default_dir.as_ref().join("themes"),
+            terminal_colors: None,
         }
     }
 
+    /// Sets the colors of the terminal, making the `adaptive` theme available.
+    pub fn with_terminal_colors(mut self, colors: TerminalColors) -> Self {
+        self.terminal_colors = Some(colors);
+        self
+    }
+
+    /// The colors of the terminal, if it reported them.
+    pub fn terminal_colors(&self) -> Option<TerminalColors> {
+        self.terminal_colors
+    }
+
     /// Loads a theme first looking in the `user_dir` then in `default_dir`
     pub fn load(&self, name: &str) -> Result<Theme> {
         if name == "default" {
@@ -47,6 +145,12 @@ pub fn load(&self, name: &str) -> Result<Theme> {
         if name == "base16_default" {
             return Ok(self.base16_default());
         }
+        if name == "adaptive" {
+            return self
+                .terminal_colors
+                .map(adaptive_theme)
+                .ok_or_else(|| anyhow!("the terminal didn't report its colors"));
+        }
 
         let theme = self.load_theme(name, name, false).map(Theme::from)?;
 
@@ -180,6 +284,36 @@ pub fn base16_default(&self) -> Theme {
     }
 }
 
+/// Derives the `adaptive` theme from the colors of the terminal.
+pub fn adaptive_theme(colors: TerminalColors) -> Theme {
+    let hex = |(r, g, b): (u8, u8, u8)| Value::String(format!("#{:02x}{:02x}{:02x}", r, g, b));
+    let mix = |percent: u16| {
+        let channel =
+            |bg: u8, fg: u8| ((bg as u16 * (100 - percent) + fg as u16 * percent + 50) / 100) as u8;
+        let (bg, fg) = (colors.background, colors.foreground);
+        hex((
+            channel(bg.0, fg.0),
+            channel(bg.1, fg.1),
+            channel(bg.2, fg.2),
+        ))
+    };
+
+    let mut palette = Map::new();
+    palette.insert("bg".into(), hex(colors.background));
+    palette.insert("fg".into(), hex(colors.foreground));
+    for percent in [5, 10, 15, 25, 35, 50, 60] {
+        palette.insert(format!("mix-{}", percent), mix(percent));
+    }
+    let mut theme: Value = toml::from_str(ADAPTIVE_THEME).expect("Failed to parse adaptive theme");
+    if let Value::Table(scopes) = &mut theme {
+        scopes.insert("palette".into(), Value::Table(palette));
+    }
+    Theme {
+        name: "adaptive".into(),
+        ..Theme::from(theme)
+    }
+}
+
 #[derive(Clone, Debug, Default)]
 pub struct Theme {
     name: String,
@@ -465,6 +599,19 @@ fn try_from(value: Value) -> Result<Self, Self::Error> {
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_adaptive_theme() {
+        let theme = adaptive_theme(TerminalColors {
+            background: (0, 0, 0),
+            foreground: (200, 100, 50),
+        });
+        assert_eq!(theme.name(), "adaptive");
+        assert_eq!(theme.get("ui.background").bg, Some(Color::Rgb(0, 0, 0)));
+        assert_eq!(theme.get("ui.text").fg, Some(Color::Rgb(200, 100, 50)));
+        assert_eq!(theme.get("comment").fg, Some(Color::Rgb(100, 50, 25)));
+        assert_eq!(theme.get("keyword").fg, Some(Color::Magenta));
+    }
+
     #[test]
     fn test_parse_style_string() {
         let fg = Value::String("#ffffff".to_string());
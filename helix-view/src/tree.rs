@@ -8,7 +8,8 @@ pub struct Tree {
     root: ViewId,
     // (container, index inside the container)
     pub focus: ViewId,
-    // fullscreen: bool,
+    /// Whether the focused view is zoomed to the whole area, hiding the other views.
+    zoomed: bool,
     area: Rect,
 
     nodes: HopSlotMap<ViewId, Node>,
@@ -96,7 +97,7 @@ pub fn new(area: Rect) -> Self {
         Self {
             root,
             focus: root,
-            // fullscreen: false,
+            zoomed: false,
             area,
             nodes,
             stack: Vec::new(),
@@ -134,6 +135,7 @@ pub fn insert(&mut self, view: View) -> ViewId {
         container.children.insert(pos, node);
         // focus the new node
         self.focus = node;
+        self.zoomed = false;
 
         // recalculate all the sizes
         self.recalculate();
@@ -207,6 +209,7 @@ pub fn split(&mut self, view: View, layout: Layout) -> ViewId {
 
         // focus the new node
         self.focus = node;
+        self.zoomed = false;
 
         // recalculate all the sizes
         self.recalculate();
@@ -243,6 +246,7 @@ pub fn remove(&mut self, index: ViewId) {
             self.nodes.remove(index);
         }
 
+        self.zoomed = false;
         self.recalculate()
     }
 
@@ -415,6 +419,18 @@ pub fn recalculate(&mut self) {
                 }
             }
         }
+
+        if self.zoomed {
+            // hidden views keep an empty area so that they are neither rendered nor clicked
+            let area = self.area;
+            for (view, focused) in self.views_mut() {
+                view.area = if focused {
+                    area
+                } else {
+                    Rect::new(area.x, area.y, 0, 0)
+                };
+            }
+        }
     }
 
     pub fn traverse(&self) -> Traverse {
@@ -572,6 +588,31 @@ pub fn transpose(&mut self) {
         }
     }
 
+    /// Rotates the splits in the container of the focused view, so that each takes the place of
+    /// the next one and the last one becomes the first, or the other way around if `reverse`.
+    pub fn rotate(&mut self, reverse: bool) {
+        let parent = self.nodes[self.focus].parent;
+        if let Content::Container(container) = &mut self.nodes[parent].content {
+            if reverse {
+                container.children.rotate_left(1);
+            } else {
+                container.children.rotate_right(1);
+            }
+            self.recalculate();
+        }
+    }
+
+    /// Zooms the focused view to the whole area, or restores the layout if it is zoomed. The zoom
+    /// follows the focus and ends when a split is added or closed.
+    pub fn toggle_zoom(&mut self) {
+        self.zoomed = !self.zoomed;
+        self.recalculate();
+    }
+
+    pub fn is_zoomed(&self) -> bool {
+        self.zoomed
+    }
+
     pub fn swap_split_in_direction(&mut self, direction: Direction) -> Option<()> {
         let focus = self.focus;
         let target = self.find_split_in_direction(focus, direction)?;
@@ -771,6 +812,36 @@ fn find_split_in_direction() {
         assert_eq!(None, tree.find_split_in_direction(r0, Direction::Up));
     }
 
+    #[test]
+    fn rotate_and_zoom() {
+        let mut tree = Tree::new(Rect::new(0, 0, 180, 80));
+        let view = View::new(DocumentId::default(), vec![]);
+        let a = tree.insert(view);
+        let b = tree.split(View::new(DocumentId::default(), vec![]), Layout::Vertical);
+        let c = tree.split(View::new(DocumentId::default(), vec![]), Layout::Vertical);
+        let order = |tree: &Tree| tree.traverse().map(|(id, _)| id).collect::<Vec<_>>();
+        assert_eq!(order(&tree), vec![a, b, c]);
+
+        tree.rotate(false);
+        assert_eq!(order(&tree), vec![c, a, b]);
+        assert_eq!(tree.get(c).area.x, 0);
+        tree.rotate(true);
+        assert_eq!(order(&tree), vec![a, b, c]);
+
+        tree.focus = b;
+        tree.toggle_zoom();
+        assert!(tree.is_zoomed());
+        assert_eq!(tree.get(b).area, tree.area());
+        assert_eq!(tree.get(a).area.area(), 0);
+        tree.toggle_zoom();
+        assert_eq!(tree.get(b).area.width, 60);
+
+        // splitting ends the zoom
+        tree.toggle_zoom();
+        tree.split(View::new(DocumentId::default(), vec![]), Layout::Horizontal);
+        assert!(!tree.is_zoomed());
+    }
+
     #[test]
     fn swap_split_in_direction() {
         let mut tree = Tree::new(Rect {
@@ -0,0 +1,284 @@
+//! Key bindings of tree panels like the file explorer, set in the `[keys.tree]` section of the
+//! config.
+
+use crate::{
+    input::KeyEvent,
+    keyboard::{KeyCode, KeyModifiers},
+};
+use serde::Deserialize;
+use std::{
+    collections::HashMap,
+    fmt::{self, Write},
+};
+
+/// What a key does in a tree.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TreeAction {
+    MoveUp,
+    MoveDown,
+    /// Opens or folds the selected item, or runs the bulk action of the panel on the marked
+    /// items. Panels with locations, like the symbol tree, jump to the selected item.
+    Activate,
+    /// Folds or unfolds the selected item.
+    ToggleFold,
+    /// Marks or unmarks the selected item for a bulk action and moves down.
+    ToggleMark,
+    /// Unmarks all items.
+    ClearMarks,
+    /// Folds the parent of the selected item.
+    FoldLevel,
+    /// Unfolds the selected item, or moves to its first child if it is unfolded.
+    Expand,
+    /// Folds the selected item, or moves to its parent if it is folded.
+    Collapse,
+    /// Selects the parent of the selected item.
+    GotoParent,
+    /// Selects the next sibling of the selected item, or the item after its parent after the
+    /// last one.
+    NextSibling,
+    /// Selects the previous sibling of the selected item, or its parent before the first one.
+    PrevSibling,
+    /// Prompts for a pattern to highlight the matching items, unfolding their ancestors.
+    Search,
+    SearchNext,
+    SearchPrev,
+    ExpandAll,
+    /// Folds every item, with a count shows that many levels.
+    CollapseAll,
+    ScrollLeft,
+    ScrollRight,
+    GotoFirst,
+    GotoLast,
+    HalfPageUp,
+    HalfPageDown,
+    PageUp,
+    PageDown,
+    /// Previews the location of the selected item, in panels with locations.
+    Peek,
+    /// Returns to the location before the last peek.
+    PeekBack,
+    /// Waits for a key hiding or showing the symbols of a kind, in the symbol tree.
+    Filter,
+    /// Sorts the items by the next order, in the symbol tree.
+    Sort,
+    /// Disables a key.
+    NoOp,
+}
+
+impl fmt::Display for TreeAction {
+    /// Writes the name used in the config, like `move_up`.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (i, c) in format!("{:?}", self).chars().enumerate() {
+            if c.is_ascii_uppercase() && i > 0 {
+                f.write_char('_')?;
+            }
+            f.write_char(c.to_ascii_lowercase())?;
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(untagged)]
+pub enum TreeKeyTrie {
+    Action(TreeAction),
+    /// Keys waiting for another key, like `g` in `gg`.
+    Node(HashMap<KeyEvent, TreeKeyTrie>),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(transparent)]
+pub struct TreeKeymap(HashMap<KeyEvent, TreeKeyTrie>);
+
+impl TreeKeymap {
+    /// Looks up `key`, matching uppercase letters typed with shift.
+    pub fn get(&self, key: KeyEvent) -> Option<&TreeKeyTrie> {
+        self.0.get(&canonicalize(key))
+    }
+
+    /// Looks up a sequence of keys, e.g. the node of `g` or the action of `gg`.
+    pub fn search(&self, keys: &[KeyEvent]) -> Option<&TreeKeyTrie> {
+        let (first, rest) = keys.split_first()?;
+        let mut trie = self.get(*first)?;
+        for key in rest {
+            trie = match trie {
+                TreeKeyTrie::Node(node) => get_in_node(node, *key)?,
+                TreeKeyTrie::Action(_) => return None,
+            };
+        }
+        Some(trie)
+    }
+
+    /// The key sequences bound to actions.
+    pub fn bindings(&self) -> Vec<(Vec<KeyEvent>, TreeAction)> {
+        fn collect(
+            map: &HashMap<KeyEvent, TreeKeyTrie>,
+            keys: &mut Vec<KeyEvent>,
+            bindings: &mut Vec<(Vec<KeyEvent>, TreeAction)>,
+        ) {
+            for (key, trie) in map {
+                keys.push(*key);
+                match trie {
+                    TreeKeyTrie::Action(action) => bindings.push((keys.clone(), *action)),
+                    TreeKeyTrie::Node(node) => collect(node, keys, bindings),
+                }
+                keys.pop();
+            }
+        }
+        let mut bindings = Vec::new();
+        collect(&self.0, &mut Vec::new(), &mut bindings);
+        bindings
+    }
+
+    /// Adds the bindings of `other`, replacing the bindings of the same keys.
+    pub fn merge(&mut self, other: Self) {
+        fn merge_map(
+            map: &mut HashMap<KeyEvent, TreeKeyTrie>,
+            other: HashMap<KeyEvent, TreeKeyTrie>,
+        ) {
+            for (key, trie) in other {
+                match (map.get_mut(&key), trie) {
+                    (Some(TreeKeyTrie::Node(node)), TreeKeyTrie::Node(other_node)) => {
+                        merge_map(node, other_node)
+                    }
+                    (_, trie) => {
+                        map.insert(key, trie);
+                    }
+                }
+            }
+        }
+        merge_map(&mut self.0, other.0);
+    }
+}
+
+/// Looks up `key` in a node of a [`TreeKeymap`].
+pub fn get_in_node(node: &HashMap<KeyEvent, TreeKeyTrie>, key: KeyEvent) -> Option<&TreeKeyTrie> {
+    node.get(&canonicalize(key))
+}
+
+fn canonicalize(mut key: KeyEvent) -> KeyEvent {
+    if let KeyCode::Char(_) = key.code {
+        key.modifiers.remove(KeyModifiers::SHIFT)
+    }
+    key
+}
+
+impl Default for TreeKeymap {
+    fn default() -> Self {
+        use TreeAction::*;
+        let bindings = [
+            ("k", MoveUp),
+            ("up", MoveUp),
+            ("C-k", MoveUp),
+            ("j", MoveDown),
+            ("down", MoveDown),
+            ("C-j", MoveDown),
+            ("ret", Activate),
+            ("o", ToggleFold),
+            ("tab", ToggleFold),
+            ("space", ToggleMark),
+            ("A-u", ClearMarks),
+            ("z", FoldLevel),
+            ("E", ExpandAll),
+            ("C", CollapseAll),
+            ("l", Expand),
+            ("right", Expand),
+            ("h", Collapse),
+            ("left", Collapse),
+            ("}", NextSibling),
+            ("{", PrevSibling),
+            ("/", Search),
+            ("n", SearchNext),
+            ("N", SearchPrev),
+            ("H", ScrollLeft),
+            ("L", ScrollRight),
+            ("G", GotoLast),
+            ("C-u", HalfPageUp),
+            ("C-d", HalfPageDown),
+            ("U", PageUp),
+            ("pageup", PageUp),
+            ("D", PageDown),
+            ("pagedown", PageDown),
+            ("p", Peek),
+            ("backspace", PeekBack),
+            ("f", Filter),
+            ("s", Sort),
+        ];
+        let mut map: HashMap<_, _> = bindings
+            .into_iter()
+            .map(|(key, action)| (key.parse().unwrap(), TreeKeyTrie::Action(action)))
+            .collect();
+        let goto = HashMap::from([
+            ("g".parse().unwrap(), TreeKeyTrie::Action(GotoFirst)),
+            ("e".parse().unwrap(), TreeKeyTrie::Action(GotoLast)),
+            ("p".parse().unwrap(), TreeKeyTrie::Action(GotoParent)),
+        ]);
+        map.insert("g".parse().unwrap(), TreeKeyTrie::Node(goto));
+        Self(map)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn merge_user_bindings() {
+        let user: TreeKeymap = toml::from_str(
+            r#"
+            o = "activate"
+            j = "move_up"
+            g = { l = "goto_last" }
+            "#,
+        )
+        .unwrap();
+        let mut keymap = TreeKeymap::default();
+        keymap.merge(user);
+
+        let key = |s: &str| s.parse::<KeyEvent>().unwrap();
+        let action = |trie: Option<&TreeKeyTrie>| match trie {
+            Some(TreeKeyTrie::Action(action)) => Some(*action),
+            _ => None,
+        };
+        assert_eq!(action(keymap.get(key("o"))), Some(TreeAction::Activate));
+        assert_eq!(action(keymap.get(key("j"))), Some(TreeAction::MoveUp));
+        assert_eq!(action(keymap.get(key("S-G"))), Some(TreeAction::GotoLast));
+        assert_eq!(action(keymap.get(key("left"))), Some(TreeAction::Collapse));
+        assert_eq!(action(keymap.get(key("l"))), Some(TreeAction::Expand));
+        match keymap.get(key("g")) {
+            Some(TreeKeyTrie::Node(node)) => {
+                assert_eq!(
+                    action(get_in_node(node, key("l"))),
+                    Some(TreeAction::GotoLast)
+                );
+                assert_eq!(
+                    action(get_in_node(node, key("g"))),
+                    Some(TreeAction::GotoFirst)
+                );
+            }
+            _ => panic!("`g` should be a prefix"),
+        }
+    }
+
+    #[test]
+    fn panel_keys_are_bound_once() {
+        let keymap = TreeKeymap::default();
+        let keys_of = |action| {
+            keymap
+                .bindings()
+                .into_iter()
+                .filter(|(_, bound)| *bound == action)
+                .map(|(keys, _)| keys)
+                .collect::<Vec<_>>()
+        };
+        let key = |s: &str| s.parse::<KeyEvent>().unwrap();
+        assert_eq!(keys_of(TreeAction::Peek), vec![vec![key("p")]]);
+        assert_eq!(
+            keys_of(TreeAction::GotoParent),
+            vec![vec![key("g"), key("p")]]
+        );
+        assert!(keys_of(TreeAction::ToggleFold).contains(&vec![key("tab")]));
+        assert!(!keys_of(TreeAction::MoveDown).contains(&vec![key("tab")]));
+    }
+}
@@ -1,9 +1,16 @@
-use crate::{align_view, editor::GutterType, graphics::Rect, Align, Document, DocumentId, ViewId};
+use crate::{
+    align_view,
+    editor::{Config, GutterType, LineNumber, WhitespaceRender},
+    graphics::Rect,
+    Align, Document, DocumentId, ViewId,
+};
 use helix_core::{
     pos_at_visual_coords, visual_coords_at_pos, Position, RopeSlice, Selection, Transaction,
 };
 
+use serde::de::DeserializeOwned;
 use std::{
+    borrow::Cow,
     collections::{HashMap, VecDeque},
     fmt,
 };
@@ -66,7 +73,7 @@ pub fn remove(&mut self, doc_id: &DocumentId) {
         self.jumps.retain(|(other_id, _)| other_id != doc_id);
     }
 
-    pub fn iter(&self) -> impl Iterator<Item = &Jump> {
+    pub fn iter(&self) -> impl DoubleEndedIterator<Item = &Jump> {
         self.jumps.iter()
     }
 
@@ -87,6 +94,88 @@ fn apply(&mut self, transaction: &Transaction, doc: &Document) {
     }
 }
 
+/// Options set with `:set-local` that override the editor config in a single view.
+/// Unset options follow the global config.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct LocalOptions {
+    pub line_number: Option<LineNumber>,
+    pub cursorline: Option<bool>,
+    pub cursorcolumn: Option<bool>,
+    pub whitespace_render: Option<WhitespaceRender>,
+    pub indent_guides: Option<bool>,
+    pub rainbow_brackets: Option<bool>,
+    pub rulers: Option<Vec<u16>>,
+}
+
+impl LocalOptions {
+    /// The config keys that can be set per view.
+    pub const KEYS: &'static [&'static str] = &[
+        "line-number",
+        "cursorline",
+        "cursorcolumn",
+        "whitespace.render",
+        "indent-guides.render",
+        "rainbow-brackets.enable",
+        "rulers",
+    ];
+
+    /// Sets `key` to `value`, or resets it to the global config if `value` is `None`.
+    pub fn set(&mut self, key: &str, value: Option<&str>) -> anyhow::Result<()> {
+        fn parse<T: DeserializeOwned>(value: Option<&str>) -> anyhow::Result<Option<T>> {
+            value
+                .map(|value| {
+                    // JSON strings require quotes, so bare words are tried as strings too
+                    serde_json::from_str(value)
+                        .or_else(|_| serde_json::from_value(value.into()))
+                        .map_err(|_| anyhow::anyhow!("Could not parse field `{}`", value))
+                })
+                .transpose()
+        }
+
+        match key {
+            "line-number" => self.line_number = parse(value)?,
+            "cursorline" => self.cursorline = parse(value)?,
+            "cursorcolumn" => self.cursorcolumn = parse(value)?,
+            "whitespace.render" => self.whitespace_render = parse(value)?,
+            "indent-guides.render" => self.indent_guides = parse(value)?,
+            "rainbow-brackets.enable" => self.rainbow_brackets = parse(value)?,
+            "rulers" => self.rulers = parse(value)?,
+            _ => anyhow::bail!("Unknown local key `{}`", key),
+        }
+        Ok(())
+    }
+
+    /// Returns `config` with the options set in this view applied.
+    pub fn apply<'a>(&self, config: &'a Config) -> Cow<'a, Config> {
+        if *self == Self::default() {
+            return Cow::Borrowed(config);
+        }
+        let mut config = config.clone();
+        if let Some(line_number) = self.line_number {
+            config.line_number = line_number;
+        }
+        if let Some(cursorline) = self.cursorline {
+            config.cursorline = cursorline;
+        }
+        if let Some(cursorcolumn) = self.cursorcolumn {
+            config.cursorcolumn = cursorcolumn;
+        }
+        if let Some(render) = self.whitespace_render {
+            config.whitespace.render = render;
+        }
+        if let Some(render) = self.indent_guides {
+            config.indent_guides.render = render;
+        }
+        if let Some(enable) = self.rainbow_brackets {
+            config.rainbow_brackets.enable = enable;
+        }
+        if let Some(rulers) = &self.rulers {
+            config.rulers = rulers.clone();
+        }
+        Cow::Owned(config)
+    }
+}
+
 #[derive(Clone)]
 pub struct View {
     pub id: ViewId,
@@ -110,6 +199,11 @@ pub struct View {
     /// mapping keeps track of the last applied history revision so that only new changes
     /// are applied.
     doc_revisions: HashMap<DocumentId, usize>,
+    /// Options overriding the editor config in this view. Copied to splits of this view.
+    pub local_options: LocalOptions,
+    /// The scroll row when `:scrollbind` was enabled. Bound views keep the distance between
+    /// their rows when one of them scrolls.
+    pub scroll_bind: Option<usize>,
 }
 
 impl fmt::Debug for View {
@@ -135,6 +229,8 @@ pub fn new(doc: DocumentId, gutter_types: Vec<crate::editor::GutterType>) -> Sel
             object_selections: Vec::new(),
             gutters: gutter_types,
             doc_revisions: HashMap::new(),
+            local_options: LocalOptions::default(),
+            scroll_bind: None,
         }
     }
 
@@ -157,6 +253,11 @@ pub fn gutters(&self) -> &[GutterType] {
         &self.gutters
     }
 
+    /// Replaces the gutters of the view, returning the previous ones.
+    pub fn set_gutters(&mut self, gutters: Vec<GutterType>) -> Vec<GutterType> {
+        std::mem::replace(&mut self.gutters, gutters)
+    }
+
     pub fn gutter_offset(&self, doc: &Document) -> u16 {
         self.gutters
             .iter()
@@ -567,4 +668,26 @@ fn test_text_pos_at_screen_coords_graphemes() {
             Some(7)
         );
     }
+
+    #[test]
+    fn test_local_options() {
+        let config = Config::default();
+        let mut options = LocalOptions::default();
+        assert!(matches!(options.apply(&config), Cow::Borrowed(_)));
+
+        options.set("line-number", Some("relative")).unwrap();
+        options.set("cursorline", Some("true")).unwrap();
+        options.set("rulers", Some("[80, 100]")).unwrap();
+        assert!(options.set("line-number", Some("sideways")).is_err());
+        assert!(options.set("mouse", Some("false")).is_err());
+
+        let applied = options.apply(&config);
+        assert_eq!(applied.line_number, LineNumber::Relative);
+        assert!(applied.cursorline);
+        assert_eq!(applied.rulers, vec![80, 100]);
+        assert_eq!(applied.cursorcolumn, config.cursorcolumn);
+
+        options.set("line-number", None).unwrap();
+        assert_eq!(options.apply(&config).line_number, config.line_number);
+    }
 }
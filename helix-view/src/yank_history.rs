@@ -0,0 +1,135 @@
+//! A bounded history of yanked text and clipboard contents, newest first. Pinned entries are
+//! never evicted.
+
+use crate::Editor;
+use serde::{Deserialize, Serialize};
+use std::{collections::VecDeque, path::PathBuf};
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct YankEntry {
+    /// The yanked text of each selection.
+    pub values: Vec<String>,
+    #[serde(default)]
+    pub pinned: bool,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct YankHistory {
+    entries: VecDeque<YankEntry>,
+}
+
+impl YankHistory {
+    fn file() -> PathBuf {
+        helix_loader::cache_dir().join("yank-history.toml")
+    }
+
+    /// Loads the history persisted by earlier sessions.
+    pub fn load() -> Self {
+        std::fs::read_to_string(Self::file())
+            .ok()
+            .and_then(|contents| toml::from_str(&contents).ok())
+            .unwrap_or_default()
+    }
+
+    pub fn save(&self) -> anyhow::Result<()> {
+        let path = Self::file();
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        std::fs::write(path, toml::to_string(self)?)?;
+        Ok(())
+    }
+
+    pub fn entries(&self) -> &VecDeque<YankEntry> {
+        &self.entries
+    }
+
+    /// Records yanked text, moving an equal entry to the front instead of repeating it, and
+    /// evicts the oldest unpinned entries beyond `limit`.
+    pub fn push(&mut self, values: Vec<String>, limit: usize) {
+        if values.iter().all(|value| value.is_empty()) {
+            return;
+        }
+        let pinned = match self.entries.iter().position(|entry| entry.values == values) {
+            Some(index) => self.entries.remove(index).unwrap().pinned,
+            None => false,
+        };
+        self.entries.push_front(YankEntry { values, pinned });
+
+        let mut unpinned = self.entries.iter().filter(|entry| !entry.pinned).count();
+        while unpinned > limit {
+            let oldest = self
+                .entries
+                .iter()
+                .rposition(|entry| !entry.pinned)
+                .unwrap();
+            self.entries.remove(oldest);
+            unpinned -= 1;
+        }
+    }
+
+    pub fn toggle_pin(&mut self, index: usize) {
+        if let Some(entry) = self.entries.get_mut(index) {
+            entry.pinned = !entry.pinned;
+        }
+    }
+
+    pub fn remove(&mut self, index: usize) {
+        self.entries.remove(index);
+    }
+}
+
+impl Editor {
+    /// Records yanked text or clipboard contents in the yank history.
+    pub fn record_yank(&mut self, values: Vec<String>) {
+        let limit = self.config().yank_history.size;
+        self.yank_history.push(values, limit);
+        self.save_yank_history();
+    }
+
+    /// Persists the yank history if it is enabled in the config.
+    pub fn save_yank_history(&self) {
+        if !self.config().yank_history.persist {
+            return;
+        }
+        if let Err(err) = self.yank_history.save() {
+            log::error!("failed to save the yank history: {}", err);
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn texts(history: &YankHistory) -> Vec<&str> {
+        history
+            .entries()
+            .iter()
+            .map(|entry| entry.values[0].as_str())
+            .collect()
+    }
+
+    #[test]
+    fn eviction() {
+        let mut history = YankHistory::default();
+        for text in ["a", "b", "c"] {
+            history.push(vec![text.to_string()], 2);
+        }
+        assert_eq!(texts(&history), ["c", "b"]);
+
+        // repeated yanks move to the front
+        history.push(vec!["b".to_string()], 2);
+        assert_eq!(texts(&history), ["b", "c"]);
+
+        // pinned entries are kept and don't count towards the limit
+        history.toggle_pin(1);
+        history.push(vec!["d".to_string()], 2);
+        history.push(vec!["e".to_string()], 2);
+        assert_eq!(texts(&history), ["e", "d", "c"]);
+        assert!(history.entries()[2].pinned);
+
+        history.push(vec![String::new()], 2);
+        assert_eq!(history.entries().len(), 3);
+    }
+}